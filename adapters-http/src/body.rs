@@ -0,0 +1,157 @@
+// =============================================================================
+// Request Bodies - JSON In, DTOs Out
+// =============================================================================
+//
+// `PlaceOrderRequest` is the application's DTO, in plain types but with no
+// serde: the application doesn't know about JSON, and shouldn't. These
+// structs are its JSON shape, and convert to it:
+//
+//     {
+//       "recipient_name": "Ferris",
+//       "recipient_email": "ferris@rustacean.net",
+//       "items": [{ "name": "Book", "price": 4999, "quantity": 2 }]
+//     }
+//
+// Prices are in cents, as everywhere else. The quantity defaults to 1.
+//
+// VALIDATION:
+// -----------
+// The domain's errors say WHAT is wrong (InvalidOrder), not WHERE. An API
+// client wants to know which field to fix, and all of them at once, so the
+// body is checked here first and every problem listed with its field:
+//
+//     { "field": "items[1].quantity", "problem": "must be between 1 and 1000" }
+//
+// Numbers are read as i64 so that a negative price reaches this check,
+// instead of failing deserialization with a less helpful message.
+
+use application::{MAX_QUANTITY, PlaceOrderRequest};
+use domain::Contact;
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /orders`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlaceOrderBody {
+    pub recipient_name: String,
+    pub recipient_email: String,
+    pub items: Vec<ItemBody>,
+}
+
+/// One line of a [`PlaceOrderBody`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemBody {
+    pub name: String,
+    /// Unit price, in cents.
+    pub price: i64,
+    #[serde(default = "one")]
+    pub quantity: i64,
+}
+
+const fn one() -> i64 {
+    1
+}
+
+/// Body of `DELETE /orders/{id}`: who to tell about the cancellation.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecipientBody {
+    pub recipient_name: String,
+    pub recipient_email: String,
+}
+
+/// One problem with one field of a request body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Where the problem is, e.g. `"items[1].quantity"`.
+    pub field: String,
+    pub problem: String,
+}
+
+impl FieldError {
+    fn new(field: impl Into<String>, problem: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            problem: problem.into(),
+        }
+    }
+}
+
+impl PlaceOrderBody {
+    /// Checks every field, then converts to the application's DTO.
+    ///
+    /// # Errors
+    ///
+    /// Returns every problem found, one [`FieldError`] each.
+    pub fn to_request(&self) -> Result<PlaceOrderRequest, Vec<FieldError>> {
+        let mut errors = recipient_errors(&self.recipient_name, &self.recipient_email);
+        if self.items.is_empty() {
+            errors.push(FieldError::new("items", "must not be empty"));
+        }
+        let mut items = Vec::new();
+        for (index, item) in self.items.iter().enumerate() {
+            if item.name.trim().is_empty() {
+                errors.push(FieldError::new(
+                    format!("items[{index}].name"),
+                    "must not be blank",
+                ));
+            }
+            if u32::try_from(item.price).is_err() {
+                errors.push(FieldError::new(
+                    format!("items[{index}].price"),
+                    format!("must be between 0 and {} cents", u32::MAX),
+                ));
+            }
+            match u32::try_from(item.quantity) {
+                Ok(quantity) if (1..=MAX_QUANTITY).contains(&quantity) => {
+                    items.push((item.name.clone(), item.price, quantity));
+                }
+                _ => errors.push(FieldError::new(
+                    format!("items[{index}].quantity"),
+                    format!("must be between 1 and {MAX_QUANTITY}"),
+                )),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(PlaceOrderRequest {
+                recipient_name: self.recipient_name.clone(),
+                recipient_email: self.recipient_email.clone(),
+                items,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl RecipientBody {
+    /// Checks both fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns every problem found, one [`FieldError`] each.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let errors = recipient_errors(&self.recipient_name, &self.recipient_email);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn recipient_errors(name: &str, email: &str) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("recipient_name", "must not be blank"));
+    }
+    if !Contact::Email(email.to_string()).is_valid() {
+        errors.push(FieldError::new(
+            "recipient_email",
+            "must be an email address",
+        ));
+    }
+    errors
+}
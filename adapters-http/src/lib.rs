@@ -0,0 +1,37 @@
+// =============================================================================
+// ADAPTERS-HTTP CRATE - A REST API in Front of the Service
+// =============================================================================
+//
+// Every other adapter crate is on the DRIVEN side of the hexagon: the
+// application calls them, through ports it defines (OrderRepository,
+// PaymentGateway, Sender). This one is on the DRIVING side: it calls the
+// application.
+//
+//           driving                              driven
+//     HTTP ──► adapters-http ──► OrderService ──► OrderRepository ──► SQLite
+//     CLI  ──► app/cli.rs    ──►              ──► PaymentGateway  ──► Stripe
+//
+// A driving adapter needs no port trait: the application's public API (the
+// use cases and their DTOs) is its port. So this crate depends on
+// `application`, which no driven adapter does.
+//
+// ROUTES:
+// -------
+//     POST   /orders        place an order (a PlaceOrderBody, as JSON)  201
+//     GET    /orders        every order                                 200
+//     GET    /orders/{id}   one order                                   200
+//     DELETE /orders/{id}   cancel it (a RecipientBody, as JSON)        200
+//
+// The Router is plain Rust: a request in, a response out, no socket. Tests
+// drive it directly. `serve`, behind the `server` feature, puts it on a real
+// socket with tiny_http.
+
+mod body;
+mod router;
+#[cfg(feature = "server")]
+mod server;
+
+pub use body::{FieldError, ItemBody, PlaceOrderBody, RecipientBody};
+pub use router::{HttpRequest, HttpResponse, Router, status_of};
+#[cfg(feature = "server")]
+pub use server::serve;
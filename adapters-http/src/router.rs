@@ -0,0 +1,289 @@
+// =============================================================================
+// Router - HTTP Requests to Use Cases
+// =============================================================================
+//
+// The router holds the application's shared state: the OrderService for the
+// commands, the OrderQueries for the reads. Both take `&self` everywhere, so
+// one router serves every request, from as many threads as the server likes.
+//
+// Each request goes through the same three steps:
+//
+//     1. route:   method and path to a handler (or 404 / 405)
+//     2. decode:  the JSON body to a DTO, checked field by field (or 400)
+//     3. call:    the use case, its OrderDto back to JSON (or its error)
+//
+// ERROR TRANSLATION:
+// ------------------
+// OrderError -> an HTTP status, by category (see `status_of`), and a body
+// with the error's stable code and its message:
+//
+//     404 { "error": { "code": "order_not_found", "message": "OrderNotFound(OrderId(7))" } }
+//
+// Clients match on the code, never on the message. Problems found before the
+// use case is even called (an unknown route, a body that isn't JSON, fields
+// that don't validate) get codes of their own, and validation errors list
+// every field in `details`.
+
+use crate::body::{FieldError, PlaceOrderBody, RecipientBody};
+use application::{OrderDto, OrderQueries, OrderService};
+use domain::{Contact, OrderError, OrderId, OrderRepository, PaymentGateway, Recipient, Sender};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::fmt;
+
+/// An HTTP request, as much of it as the routes look at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: String,
+    /// The path, possibly followed by a query string (ignored).
+    pub path: String,
+    pub body: String,
+}
+
+impl HttpRequest {
+    /// Creates a request.
+    #[must_use]
+    pub fn new(method: &str, path: &str, body: impl Into<String>) -> Self {
+        Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.into(),
+        }
+    }
+}
+
+/// An HTTP response: always JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn json(status: u16, body: &impl Serialize) -> Self {
+        Self {
+            status,
+            // Our own structs and `json!` values always serialize
+            body: serde_json::to_string(body).unwrap_or_default(),
+        }
+    }
+
+    fn error(status: u16, code: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            &json!({ "error": { "code": code, "message": message } }),
+        )
+    }
+
+    fn invalid_fields(details: &[FieldError]) -> Self {
+        Self::json(
+            400,
+            &json!({ "error": {
+                "code": "validation_failed",
+                "message": "some fields are invalid",
+                "details": details,
+            } }),
+        )
+    }
+
+    /// The body, parsed back into JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns the parse error if the body isn't JSON.
+    pub fn json_body(&self) -> serde_json::Result<Value> {
+        serde_json::from_str(&self.body)
+    }
+}
+
+impl From<OrderError> for HttpResponse {
+    fn from(error: OrderError) -> Self {
+        Self::error(status_of(&error), error.code(), &error.to_string())
+    }
+}
+
+/// The HTTP status for `error`:
+///
+/// - 400: the request breaks a business rule (no items, a bad recipient...)
+/// - 402: the payment failed
+/// - 404: something it refers to doesn't exist
+/// - 409: it clashes with the order's state, or with another request
+/// - 500: the infrastructure failed (storage, notification)
+///
+/// Every variant is listed: a new one has to be given a status.
+#[must_use]
+pub const fn status_of(error: &OrderError) -> u16 {
+    match error {
+        OrderError::InvalidOrder
+        | OrderError::InvalidRecipient
+        | OrderError::InvalidLineItem
+        | OrderError::InvalidRefund
+        | OrderError::InvalidCustomer
+        | OrderError::UnknownProduct(_)
+        | OrderError::NoExchangeRate { .. } => 400,
+        OrderError::PaymentFailed(_) => 402,
+        OrderError::OrderNotFound(_)
+        | OrderError::CustomerNotFound(_)
+        | OrderError::GiftCardNotFound(_)
+        | OrderError::SubscriptionNotFound(_) => 404,
+        OrderError::InvalidStatus(_)
+        | OrderError::DuplicateSubmission { .. }
+        | OrderError::Conflict { .. } => 409,
+        OrderError::StorageFailed(_)
+        | OrderError::NotificationFailed(_)
+        | OrderError::DuplicateOrder(_)
+        | OrderError::StorageFailedAfterCharge { .. }
+        | OrderError::NotificationFailedAfterCharge { .. } => 500,
+    }
+}
+
+/// The JSON shape of an [`OrderDto`].
+#[derive(Serialize)]
+struct OrderBody<'a> {
+    id: u32,
+    items: &'a [String],
+    total: &'a str,
+    status: &'a str,
+    warnings: &'a [String],
+}
+
+impl<'a> From<&'a OrderDto> for OrderBody<'a> {
+    fn from(order: &'a OrderDto) -> Self {
+        Self {
+            id: order.id,
+            items: &order.items,
+            total: &order.total,
+            status: &order.status,
+            warnings: &order.warnings,
+        }
+    }
+}
+
+/// Routes HTTP requests to the order use cases.
+pub struct Router<'a, R, P, N>
+where
+    R: OrderRepository,
+    P: PaymentGateway,
+    N: Sender,
+{
+    service: &'a OrderService<'a, R, P, N>,
+    queries: &'a OrderQueries<'a, R>,
+}
+
+// Manual impl: the service and the queries are only Debug when their adapters are.
+impl<R, P, N> fmt::Debug for Router<'_, R, P, N>
+where
+    R: OrderRepository,
+    P: PaymentGateway,
+    N: Sender,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router").finish_non_exhaustive()
+    }
+}
+
+impl<'a, R, P, N> Router<'a, R, P, N>
+where
+    R: OrderRepository,
+    P: PaymentGateway,
+    N: Sender,
+{
+    /// Creates a router sending commands to `service` and reads to `queries`.
+    #[must_use]
+    pub const fn new(
+        service: &'a OrderService<'a, R, P, N>,
+        queries: &'a OrderQueries<'a, R>,
+    ) -> Self {
+        Self { service, queries }
+    }
+
+    /// Handles one request. Never fails: every problem is a response.
+    #[must_use]
+    pub fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        let path = request.path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        match (request.method.as_str(), segments.as_slice()) {
+            ("POST", ["orders"]) => self.place(&request.body),
+            ("GET", ["orders"]) => self.list(),
+            ("GET", ["orders", id]) => with_id(id, |id| self.get(id)),
+            ("DELETE", ["orders", id]) => with_id(id, |id| self.cancel(id, &request.body)),
+            (method, ["orders"] | ["orders", _]) => HttpResponse::error(
+                405,
+                "method_not_allowed",
+                &format!("{method} isn't allowed on {path}"),
+            ),
+            _ => HttpResponse::error(404, "route_not_found", &format!("no route for {path}")),
+        }
+    }
+
+    fn place(&self, body: &str) -> HttpResponse {
+        let body: PlaceOrderBody = match decode(body) {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+        let request = match body.to_request() {
+            Ok(request) => request,
+            Err(details) => return HttpResponse::invalid_fields(&details),
+        };
+        match self.service.place_order_dto(&request) {
+            Ok(order) => HttpResponse::json(201, &OrderBody::from(&order)),
+            Err(e) => e.into(),
+        }
+    }
+
+    fn list(&self) -> HttpResponse {
+        match self.queries.list_orders() {
+            Ok(orders) => {
+                let orders: Vec<OrderDto> = orders.iter().map(OrderDto::from).collect();
+                let orders: Vec<OrderBody<'_>> = orders.iter().map(OrderBody::from).collect();
+                HttpResponse::json(200, &json!({ "orders": orders }))
+            }
+            Err(e) => e.into(),
+        }
+    }
+
+    fn get(&self, id: OrderId) -> HttpResponse {
+        match self.service.get_order_dto(id.0) {
+            Ok(Some(order)) => HttpResponse::json(200, &OrderBody::from(&order)),
+            Ok(None) => OrderError::OrderNotFound(id).into(),
+            Err(e) => e.into(),
+        }
+    }
+
+    fn cancel(&self, id: OrderId, body: &str) -> HttpResponse {
+        let body: RecipientBody = match decode(body) {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+        if let Err(details) = body.validate() {
+            return HttpResponse::invalid_fields(&details);
+        }
+        let cancelled = Recipient::new(
+            body.recipient_name.as_str(),
+            Contact::Email(body.recipient_email),
+        )
+        .and_then(|recipient| self.service.cancel_order(id, &recipient));
+        match cancelled {
+            Ok(order) => HttpResponse::json(200, &OrderBody::from(&OrderDto::from(&order))),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+/// Calls `handle` with the ID in the path, or answers 400 if it isn't one.
+fn with_id(id: &str, handle: impl FnOnce(OrderId) -> HttpResponse) -> HttpResponse {
+    match id.parse() {
+        Ok(id) => handle(OrderId(id)),
+        Err(_) => HttpResponse::invalid_fields(&[FieldError {
+            field: "id".to_string(),
+            problem: format!("{id:?} isn't an order ID"),
+        }]),
+    }
+}
+
+/// The body as a `T`, or the 400 response saying why it isn't one.
+fn decode<T: DeserializeOwned>(body: &str) -> Result<T, HttpResponse> {
+    serde_json::from_str(body).map_err(|e| HttpResponse::error(400, "invalid_body", &e.to_string()))
+}
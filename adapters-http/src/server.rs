@@ -0,0 +1,64 @@
+// =============================================================================
+// Server - The Router on a Real Socket
+// =============================================================================
+//
+// Behind the `server` feature, so the default build has no HTTP server to
+// compile. tiny_http is blocking and tiny: it hands us each request, we hand
+// it to the Router, and write back what comes out.
+//
+// Requests are handled one at a time, on the calling thread. The Router is
+// `Sync` whenever the adapters are, so a busier server would hand requests to
+// a pool of scoped threads instead; nothing in the Router would change.
+
+use crate::router::{HttpRequest, HttpResponse, Router};
+use domain::{OrderRepository, PaymentGateway, Sender};
+use std::io;
+use tiny_http::{Header, Response, Server};
+
+/// Listens on `addr` (e.g. `"127.0.0.1:8080"`) and answers every request
+/// with `router`, until the process stops.
+///
+/// # Errors
+///
+/// Returns the I/O error if `addr` can't be listened on.
+pub fn serve<R, P, N>(router: &Router<'_, R, P, N>, addr: &str) -> io::Result<()>
+where
+    R: OrderRepository,
+    P: PaymentGateway,
+    N: Sender,
+{
+    let server = Server::http(addr).map_err(io::Error::other)?;
+    println!("  [HTTP] Listening on {addr}");
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let response = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => router.handle(&HttpRequest::new(
+                request.method().as_str(),
+                request.url(),
+                body,
+            )),
+            Err(e) => HttpResponse {
+                status: 400,
+                body: serde_json::json!({
+                    "error": { "code": "invalid_body", "message": e.to_string() }
+                })
+                .to_string(),
+            },
+        };
+        println!(
+            "  [HTTP] {} {} -> {}",
+            request.method(),
+            request.url(),
+            response.status
+        );
+        let content_type = Header::from_bytes("Content-Type", "application/json")
+            .map_err(|()| io::Error::other("invalid header"))?;
+        let reply = Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(reply) {
+            println!("  [HTTP] Couldn't answer: {e}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+// =============================================================================
+// Route Tests - The REST API, End to End, In-Process
+// =============================================================================
+//
+// The router is wired to the in-memory adapters the way a server would wire
+// it to real ones, then driven with plain HttpRequests: no socket, no port
+// to pick, nothing left running after the test.
+
+use adapters_http::{HttpRequest, HttpResponse, Router};
+use adapters_notification::RecordingSender;
+use adapters_payment::{FailingPaymentGateway, MockPaymentGateway};
+use adapters_repository::InMemoryOrderRepository;
+use application::{OrderQueries, OrderService};
+use domain::{OrderRepository, PaymentGateway, Sender};
+use serde_json::{Value, json};
+
+const BOOK_AND_PENS: &str = r#"{
+    "recipient_name": "Ferris",
+    "recipient_email": "ferris@rustacean.net",
+    "items": [
+        { "name": "Book", "price": 4999 },
+        { "name": "Pen", "price": 199, "quantity": 2 }
+    ]
+}"#;
+
+const FERRIS: &str = r#"{ "recipient_name": "Ferris", "recipient_email": "ferris@rustacean.net" }"#;
+
+fn send(
+    router: &Router<'_, impl OrderRepository, impl PaymentGateway, impl Sender>,
+    method: &str,
+    path: &str,
+    body: &str,
+) -> (u16, Value) {
+    let HttpResponse { status, body } = router.handle(&HttpRequest::new(method, path, body));
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+#[test]
+fn place_get_list_and_cancel() {
+    let repo = InMemoryOrderRepository::new();
+    let payment = MockPaymentGateway::new();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, &payment, &sender);
+    let queries = OrderQueries::new(&repo);
+    let router = Router::new(&service, &queries);
+
+    let (placed_status, placed) = send(&router, "POST", "/orders", BOOK_AND_PENS);
+    let (got_status, got) = send(&router, "GET", "/orders/1", "");
+    let (cancelled_status, cancelled) = send(&router, "DELETE", "/orders/1", FERRIS);
+    let (listed_status, listed) = send(&router, "GET", "/orders?page=1", "");
+
+    assert_eq!(placed_status, 201);
+    assert_eq!(
+        placed,
+        json!({
+            "id": 1,
+            "items": ["1 x Book @ $49.99", "2 x Pen @ $1.99"],
+            "total": "$53.97",
+            "status": "Paid",
+            "warnings": [],
+        })
+    );
+    assert_eq!((got_status, &got), (200, &placed));
+    assert_eq!(cancelled_status, 200);
+    assert_eq!(cancelled["status"], "Cancelled");
+    assert_eq!(listed_status, 200);
+    assert_eq!(listed["orders"].as_array().unwrap().len(), 1);
+    assert_eq!(listed["orders"][0]["status"], "Cancelled");
+    assert_eq!(sender.sent().len(), 2);
+}
+
+#[test]
+fn unknown_orders_and_routes_are_404() {
+    let repo = InMemoryOrderRepository::new();
+    let payment = MockPaymentGateway::new();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, &payment, &sender);
+    let queries = OrderQueries::new(&repo);
+    let router = Router::new(&service, &queries);
+
+    let (status, body) = send(&router, "GET", "/orders/7", "");
+    let (cancel_status, cancel_body) = send(&router, "DELETE", "/orders/7", FERRIS);
+    let (route_status, route_body) = send(&router, "GET", "/customers", "");
+    let (method_status, method_body) = send(&router, "PUT", "/orders/7", "");
+
+    assert_eq!(status, 404);
+    assert_eq!(
+        body,
+        json!({ "error": { "code": "order_not_found", "message": "OrderNotFound(OrderId(7))" } })
+    );
+    assert_eq!(cancel_status, 404);
+    assert_eq!(cancel_body["error"]["code"], "order_not_found");
+    assert_eq!(route_status, 404);
+    assert_eq!(route_body["error"]["code"], "route_not_found");
+    assert_eq!(method_status, 405);
+    assert_eq!(method_body["error"]["code"], "method_not_allowed");
+}
+
+#[test]
+fn invalid_bodies_are_400_with_every_field() {
+    let repo = InMemoryOrderRepository::new();
+    let payment = MockPaymentGateway::new();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, &payment, &sender);
+    let queries = OrderQueries::new(&repo);
+    let router = Router::new(&service, &queries);
+
+    let (json_status, json_body) = send(&router, "POST", "/orders", "{ not json");
+    let (unknown_status, unknown_body) = send(
+        &router,
+        "POST",
+        "/orders",
+        r#"{ "recipient_name": "Ferris", "recipient_email": "f@r.net", "items": [], "coupon": "X" }"#,
+    );
+    let (fields_status, fields_body) = send(
+        &router,
+        "POST",
+        "/orders",
+        r#"{
+            "recipient_name": " ",
+            "recipient_email": "not-an-email",
+            "items": [
+                { "name": "Book", "price": -1 },
+                { "name": "", "price": 100, "quantity": 0 }
+            ]
+        }"#,
+    );
+    let (id_status, id_body) = send(&router, "GET", "/orders/seven", "");
+    let (cancel_status, cancel_body) = send(&router, "DELETE", "/orders/1", "");
+
+    assert_eq!(json_status, 400);
+    assert_eq!(json_body["error"]["code"], "invalid_body");
+    assert_eq!(unknown_status, 400);
+    assert_eq!(unknown_body["error"]["code"], "invalid_body");
+    assert_eq!(fields_status, 400);
+    assert_eq!(fields_body["error"]["code"], "validation_failed");
+    let fields: Vec<&str> = fields_body["error"]["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|detail| detail["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "recipient_name",
+            "recipient_email",
+            "items[0].price",
+            "items[1].name",
+            "items[1].quantity",
+        ]
+    );
+    assert_eq!(id_status, 400);
+    assert_eq!(id_body["error"]["details"][0]["field"], "id");
+    assert_eq!(cancel_status, 400);
+    assert_eq!(cancel_body["error"]["code"], "invalid_body");
+    assert!(repo.is_empty());
+}
+
+#[test]
+fn a_declined_payment_is_402() {
+    let repo = InMemoryOrderRepository::new();
+    let payment = FailingPaymentGateway::default();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, &payment, &sender);
+    let queries = OrderQueries::new(&repo);
+    let router = Router::new(&service, &queries);
+
+    let (status, body) = send(&router, "POST", "/orders", BOOK_AND_PENS);
+
+    assert_eq!(status, 402);
+    assert_eq!(body["error"]["code"], "payment_failed");
+    assert!(repo.is_empty());
+}
+
+#[test]
+fn cancelling_twice_is_a_conflict() {
+    let repo = InMemoryOrderRepository::new();
+    let payment = MockPaymentGateway::new();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, &payment, &sender);
+    let queries = OrderQueries::new(&repo);
+    let router = Router::new(&service, &queries);
+
+    send(&router, "POST", "/orders", BOOK_AND_PENS);
+    send(&router, "DELETE", "/orders/1", FERRIS);
+    let (status, body) = send(&router, "DELETE", "/orders/1", FERRIS);
+
+    assert_eq!(status, 409);
+    assert_eq!(body["error"]["code"], "invalid_status");
+}
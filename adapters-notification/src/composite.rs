@@ -0,0 +1,214 @@
+// =============================================================================
+// Composite Sender - One Event, Many Channels
+// =============================================================================
+//
+// OrderService takes exactly ONE sender. But "email the customer AND post to
+// the ops Slack channel" is two senders.
+//
+// Instead of teaching OrderService about lists, we use the Composite pattern:
+// a Sender that holds other senders and forwards every event to each of them.
+// OrderService still sees one sender. Nothing in the core changes.
+//
+// The interesting question is what a partial failure means. The email went
+// out but Slack is down: did the notification fail? That's a business call,
+// so it's a policy (FanoutPolicy) chosen when wiring the app.
+
+use domain::{
+    HealthCheck, HealthStatus, NotificationEvent, OrderError, Recipient, RequestContext, Sender,
+};
+use std::fmt;
+
+/// What a [`CompositeSender`] does when some of its senders fail.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FanoutPolicy {
+    /// Stop at the first failure: later senders are not called.
+    FailFast,
+    /// Call every sender, fail if at least one failed.
+    #[default]
+    FailIfAnyFails,
+    /// Call every sender, fail only if all of them failed.
+    FailIfAllFail,
+}
+
+/// Sender that forwards each event to several senders, in order.
+pub struct CompositeSender {
+    senders: Vec<Box<dyn Sender>>,
+    policy: FanoutPolicy,
+}
+
+impl CompositeSender {
+    /// Creates a composite over `senders`, which are called in order.
+    #[must_use]
+    pub fn new(senders: Vec<Box<dyn Sender>>, policy: FanoutPolicy) -> Self {
+        Self { senders, policy }
+    }
+}
+
+// Manual impl: the boxed senders have no Debug of their own.
+impl fmt::Debug for CompositeSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompositeSender")
+            .field("senders", &self.senders.len())
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl Sender for CompositeSender {
+    /// Notifies every inner sender, then applies the [`FanoutPolicy`].
+    ///
+    /// With no inner sender at all there is nothing to fail: that's `Ok`.
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        self.notify_with_ctx(&RequestContext::default(), recipient, event)
+    }
+
+    /// Same as `notify`, passing the context on to every inner sender.
+    fn notify_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<(), OrderError> {
+        let mut failures = 0;
+        for sender in &self.senders {
+            if let Err(e) = sender.notify_with_ctx(ctx, recipient, event) {
+                println!("  [Composite] One sender failed: {e}");
+                if self.policy == FanoutPolicy::FailFast {
+                    return Err(OrderError::NotificationFailed(None));
+                }
+                failures += 1;
+            }
+        }
+
+        let failed = match self.policy {
+            FanoutPolicy::FailFast | FanoutPolicy::FailIfAnyFails => failures > 0,
+            FanoutPolicy::FailIfAllFail => failures > 0 && failures == self.senders.len(),
+        };
+        if failed {
+            Err(OrderError::NotificationFailed(None))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl HealthCheck for CompositeSender {
+    /// The senders are trait objects, whose health can't be asked: only an
+    /// empty composite, where every notification goes nowhere, is reported.
+    fn health(&self) -> HealthStatus {
+        if self.senders.is_empty() {
+            HealthStatus::Unhealthy("no senders".to_string())
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, OrderId};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // Records its name in a log shared with the test, optionally failing
+    struct RecordingSender {
+        name: &'static str,
+        fail: bool,
+        log: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl Sender for RecordingSender {
+        fn notify(
+            &self,
+            _recipient: &Recipient,
+            _event: &NotificationEvent,
+        ) -> Result<(), OrderError> {
+            self.log.borrow_mut().push(self.name);
+            if self.fail {
+                Err(OrderError::NotificationFailed(None))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    // Three senders, the second one failing
+    fn composite(policy: FanoutPolicy) -> (CompositeSender, Rc<RefCell<Vec<&'static str>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let senders: Vec<Box<dyn Sender>> = ["email", "slack", "sms"]
+            .into_iter()
+            .map(|name| {
+                Box::new(RecordingSender {
+                    name,
+                    fail: name == "slack",
+                    log: Rc::clone(&log),
+                }) as Box<dyn Sender>
+            })
+            .collect();
+        (CompositeSender::new(senders, policy), log)
+    }
+
+    fn notify(sender: &CompositeSender) -> Result<(), OrderError> {
+        let recipient =
+            Recipient::new("Test", Contact::Email("test@example.com".to_string())).unwrap();
+        sender.notify(
+            &recipient,
+            &NotificationEvent::OrderShipped { id: OrderId(1) },
+        )
+    }
+
+    #[test]
+    fn fail_fast_stops_at_the_first_failure() {
+        let (sender, log) = composite(FanoutPolicy::FailFast);
+
+        assert!(matches!(
+            notify(&sender),
+            Err(OrderError::NotificationFailed(_))
+        ));
+        assert_eq!(*log.borrow(), vec!["email", "slack"]);
+    }
+
+    #[test]
+    fn fail_if_any_fails_calls_everyone_then_fails() {
+        let (sender, log) = composite(FanoutPolicy::FailIfAnyFails);
+
+        assert!(matches!(
+            notify(&sender),
+            Err(OrderError::NotificationFailed(_))
+        ));
+        assert_eq!(*log.borrow(), vec!["email", "slack", "sms"]);
+    }
+
+    #[test]
+    fn fail_if_all_fail_tolerates_one_failure() {
+        let (sender, log) = composite(FanoutPolicy::FailIfAllFail);
+
+        assert!(notify(&sender).is_ok());
+        assert_eq!(*log.borrow(), vec!["email", "slack", "sms"]);
+    }
+
+    #[test]
+    fn fail_if_all_fail_fails_when_everyone_fails() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let senders: Vec<Box<dyn Sender>> = vec![
+            Box::new(RecordingSender {
+                name: "email",
+                fail: true,
+                log: Rc::clone(&log),
+            }),
+            Box::new(RecordingSender {
+                name: "slack",
+                fail: true,
+                log: Rc::clone(&log),
+            }),
+        ];
+        let sender = CompositeSender::new(senders, FanoutPolicy::FailIfAllFail);
+
+        assert!(matches!(
+            notify(&sender),
+            Err(OrderError::NotificationFailed(_))
+        ));
+        assert_eq!(log.borrow().len(), 2);
+    }
+}
@@ -0,0 +1,37 @@
+// =============================================================================
+// Configuration Errors - Caught at Startup, Not at the First Order
+// =============================================================================
+//
+// A sender built from an empty API key or a misspelled from-address fails on
+// every notification, and nobody notices until a customer asks where their
+// confirmation is. Constructors that take configuration check it, and refuse
+// with a `ConfigError` the deployment sees when it starts.
+//
+// Unlike `OrderError`, this never reaches the application: it's an error about
+// wiring the adapters, returned to whoever wires them (main, or a test).
+
+use std::fmt;
+
+/// Why a sender can't be built from its configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A required setting, or the environment variable holding it, is
+    /// missing or empty.
+    Missing(&'static str),
+    /// A setting has a value that can't be right.
+    Invalid {
+        setting: &'static str,
+        value: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(setting) => write!(f, "{setting} is missing"),
+            Self::Invalid { setting, value } => write!(f, "{setting}={value:?} is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -5,52 +5,456 @@
 // This adapter "sends" notifications by printing to stdout.
 // Perfect for testing and local development - no email server needed!
 //
+// "Stdout" is only the default: `with_writer` hands it any `io::Write`. A test
+// gives it a buffer and reads back exactly what was "sent", without
+// `--nocapture` and without the lines of every other test in between.
+//
 // Remember the Email struct from dip_02? This is its spiritual successor.
 // Same concept: implement the Sender trait with a simple implementation.
+//
+// VERBOSITY AND COLOR:
+// --------------------
+// How much it prints is a `Verbosity`:
+// - Quiet:   nothing, and the notification still counts as sent
+// - Normal:  one line per event (the default)
+// - Verbose: the line, and under a confirmation, its whole receipt
+//
+// In a terminal, the order ID stands out, in bold. Piped into a file or
+// `grep`, escape codes are noise: color is only used when the output is a
+// terminal. Which it is, the sender asks a "tty check", a plain function a
+// test replaces to see the colors a buffer never gets.
+
+use crate::receipt::ReceiptRenderer;
+use crate::template::{MessageTemplate, wording};
+use domain::{
+    HealthCheck, HealthStatus, NotificationEvent, OrderError, OrderId, Recipient, Sender,
+};
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Mutex, PoisonError};
 
-use domain::{Order, OrderError, Sender};
+/// Turns bold on, in a terminal.
+const BOLD: &str = "\x1b[1m";
+/// Turns every attribute off again.
+const RESET: &str = "\x1b[0m";
+
+/// How much the console sender says: what `--quiet` and `--verbose` ask
+/// for.
+///
+/// A presentation setting, for output meant for a person reading along: the
+/// domain never sees it. A sender set to `Quiet` still "sends": it just
+/// doesn't show it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Nothing.
+    Quiet,
+    /// One line per event.
+    #[default]
+    Normal,
+    /// Everything there is to show, a confirmation's whole receipt included.
+    Verbose,
+}
 
 /// Console-based notification sender for testing.
 ///
 /// "Sends" notifications by printing to the console.
-/// No network calls, no external services - just println!
-#[derive(Debug, Default, Clone, Copy)]
-pub struct ConsoleSender;
+/// No network calls, no external services - just a line on stdout
+/// (or on the writer it was given).
+pub struct ConsoleSender {
+    template: Option<MessageTemplate>,
+    verbosity: Verbosity,
+    color: bool,
+    /// Whether the writer is a terminal, asked at each notification.
+    is_tty: fn() -> bool,
+    // The port takes &self, and writing needs &mut
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+// Manual impl: the writer is a trait object with no Debug of its own.
+impl fmt::Debug for ConsoleSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsoleSender")
+            .field("template", &self.template)
+            .field("verbosity", &self.verbosity)
+            .field("color", &self.color)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ConsoleSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleSender {
+    /// Creates a sender printing to stdout, with the default wording, in
+    /// color when stdout is a terminal.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout()).with_tty_check(|| io::stdout().is_terminal())
+    }
+
+    /// Creates a sender writing its lines to `writer` instead of stdout.
+    ///
+    /// Without [`ConsoleSender::with_tty_check`], `writer` isn't taken for a
+    /// terminal: no color.
+    #[must_use]
+    pub fn with_writer(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            template: None,
+            verbosity: Verbosity::Normal,
+            color: true,
+            is_tty: || false,
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Prints as much as `verbosity` asks for.
+    #[must_use]
+    pub const fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Turns color off (or back on): on by default, for terminals only.
+    #[must_use]
+    pub const fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Asks `is_tty` whether the writer is a terminal, before using color.
+    ///
+    /// `|| io::stderr().is_terminal()` for a sender writing to stderr; a test
+    /// passes `|| true`.
+    #[must_use]
+    pub const fn with_tty_check(mut self, is_tty: fn() -> bool) -> Self {
+        self.is_tty = is_tty;
+        self
+    }
+
+    /// Words confirmations with `template` instead.
+    #[must_use]
+    pub fn with_template(mut self, template: MessageTemplate) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// The event in the template's words, or the default ones, with the
+    /// order ID in bold when printing in color.
+    ///
+    /// A template's words are printed as written.
+    fn text(&self, event: &NotificationEvent) -> Result<String, OrderError> {
+        if self.color && (self.is_tty)() {
+            wording(self.template.as_ref(), event, |event| {
+                message_with(event, |id| format!("{BOLD}{id}{RESET}"))
+            })
+        } else {
+            wording(self.template.as_ref(), event, message)
+        }
+    }
+
+    /// Everything `notify` prints for `event`, at the sender's verbosity.
+    fn output(
+        &self,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<String, OrderError> {
+        let mut output = match self.verbosity {
+            Verbosity::Quiet => return Ok(String::new()),
+            Verbosity::Normal | Verbosity::Verbose => {
+                format!("  [Console] To {recipient}: {}\n", self.text(event)?)
+            }
+        };
+        if let (Verbosity::Verbose, NotificationEvent::OrderConfirmed(order)) =
+            (self.verbosity, event)
+        {
+            for line in ReceiptRenderer::new().render_text(order).lines() {
+                if !line.is_empty() {
+                    output.push_str("    ");
+                    output.push_str(line);
+                }
+                output.push('\n');
+            }
+        }
+        Ok(output)
+    }
+}
 
 impl Sender for ConsoleSender {
-    /// "Sends" a notification by printing to stdout.
+    /// "Sends" a notification by printing to stdout (or the writer).
     ///
     /// In production, this might call SendGrid, queue a message in RabbitMQ,
     /// or send an SMS via Twilio. Here, it just prints. And that's enough
     /// for testing!
-    fn send(&self, order: &Order) -> Result<(), OrderError> {
-        println!(
-            "  [Console] Order #{} confirmed! Total: {}",
-            order.id, order.total
-        );
-        Ok(())
+    ///
+    /// Any kind of contact works: we just print it. A writer that fails is
+    /// a `NotificationFailed`; a quiet sender never touches it.
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let output = self.output(recipient, event)?;
+        if output.is_empty() {
+            return Ok(());
+        }
+        // A poisoned lock only means another thread panicked; the writer is still fine
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        writer.write_all(output.as_bytes()).map_err(|e| {
+            println!("  [Console] Can't write the notification: {e}");
+            OrderError::NotificationFailed(None)
+        })
+    }
+}
+
+/// Words each kind of event for the console.
+pub(crate) fn message(event: &NotificationEvent) -> String {
+    message_with(event, |id| id.to_string())
+}
+
+/// [`message`], with the order ID written by `show_id`.
+fn message_with(event: &NotificationEvent, show_id: impl Fn(OrderId) -> String) -> String {
+    let id = show_id(event.order_id());
+    match event {
+        NotificationEvent::OrderConfirmed(order) => {
+            format!("Order #{id} confirmed! Total: {}", order.total)
+        }
+        NotificationEvent::OrderCancelled { .. } => format!("Order #{id} has been cancelled."),
+        NotificationEvent::RefundIssued { amount, .. } => {
+            format!("Refund of {amount} issued for order #{id}.")
+        }
+        NotificationEvent::OrderShipped { .. } => format!("Order #{id} is on its way!"),
+    }
+}
+
+impl HealthCheck for ConsoleSender {
+    // A write to our own output: nothing to reach
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use domain::{LineItem, Money, OrderId};
+    use domain::{Contact, LineItem, Money, Order, OrderId};
+    use std::sync::Arc;
+
+    /// A `Vec<u8>` the test keeps a handle on after giving it to the sender.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Buffer {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
 
     #[test]
-    fn console_sender_succeeds() {
-        let sender = ConsoleSender;
+    fn console_sender_writes_one_line_per_notification() {
+        let buffer = Buffer::default();
+        let sender = ConsoleSender::with_writer(buffer.clone());
         let order = Order::new(
             OrderId(1),
             vec![LineItem {
                 name: "Test".to_string(),
                 price: Money(100),
+                weight_grams: None,
             }],
         )
         .unwrap();
 
-        let result = sender.send(&order);
+        let recipient = Recipient::new("Test", Contact::Phone("+15550001111".to_string())).unwrap();
+
+        sender.send(&recipient, &order).unwrap();
+        sender
+            .notify(
+                &recipient,
+                &NotificationEvent::OrderShipped { id: OrderId(1) },
+            )
+            .unwrap();
 
-        assert!(result.is_ok());
+        assert_eq!(
+            buffer.text(),
+            format!(
+                "  [Console] To {recipient}: Order #OrderId(1) confirmed! Total: $1.00\n  \
+                 [Console] To {recipient}: Order #OrderId(1) is on its way!\n"
+            )
+        );
+    }
+
+    fn mug_order() -> Order {
+        let mug = LineItem {
+            name: "Mug".to_string(),
+            price: Money(999),
+            weight_grams: None,
+        };
+        Order::new(OrderId(3), vec![mug.clone(), mug]).unwrap()
+    }
+
+    /// What `sender` writes for a confirmation of [`mug_order`] and a
+    /// shipping notice.
+    fn written(sender: impl FnOnce(Buffer) -> ConsoleSender) -> String {
+        let buffer = Buffer::default();
+        let sender = sender(buffer.clone());
+        let recipient =
+            Recipient::new("Test", Contact::Email("t@example.com".to_string())).unwrap();
+
+        sender.send(&recipient, &mug_order()).unwrap();
+        sender
+            .notify(
+                &recipient,
+                &NotificationEvent::OrderShipped { id: OrderId(3) },
+            )
+            .unwrap();
+        buffer.text()
+    }
+
+    #[test]
+    fn each_verbosity_prints_its_own_amount() {
+        let at = |verbosity| {
+            written(|buffer| ConsoleSender::with_writer(buffer).with_verbosity(verbosity))
+        };
+
+        assert_eq!(at(Verbosity::Quiet), "");
+        assert_eq!(
+            at(Verbosity::Normal),
+            "  [Console] To Test <t@example.com>: Order #OrderId(3) confirmed! Total: $19.98\n  \
+             [Console] To Test <t@example.com>: Order #OrderId(3) is on its way!\n"
+        );
+        assert_eq!(
+            at(Verbosity::Verbose),
+            "  [Console] To Test <t@example.com>: Order #OrderId(3) confirmed! Total: $19.98\n    \
+             Order #3\n\
+             \n      \
+             Qty  Item                               Amount\n        \
+               2  Mug                                $19.98\n\
+             \n           \
+                  Total                              $19.98\n  \
+             [Console] To Test <t@example.com>: Order #OrderId(3) is on its way!\n"
+        );
+    }
+
+    #[test]
+    fn a_quiet_sender_never_touches_its_writer() {
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _bytes: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let recipient = Recipient::new("Test", Contact::Phone("+15550001111".to_string())).unwrap();
+
+        let sender = ConsoleSender::with_writer(Broken).with_verbosity(Verbosity::Quiet);
+
+        assert!(sender.send(&recipient, &mug_order()).is_ok());
+    }
+
+    #[test]
+    fn the_order_id_is_bold_in_a_terminal_only() {
+        let shipped = |sender: fn(Buffer) -> ConsoleSender| {
+            written(sender).lines().last().unwrap().to_string()
+        };
+
+        assert_eq!(
+            shipped(|buffer| ConsoleSender::with_writer(buffer).with_tty_check(|| true)),
+            "  [Console] To Test <t@example.com>: Order #\x1b[1mOrderId(3)\x1b[0m is on its way!"
+        );
+        // Not a terminal, or color turned off: plain text
+        assert_eq!(
+            shipped(ConsoleSender::with_writer),
+            "  [Console] To Test <t@example.com>: Order #OrderId(3) is on its way!"
+        );
+        assert_eq!(
+            shipped(|buffer| {
+                ConsoleSender::with_writer(buffer)
+                    .with_tty_check(|| true)
+                    .with_color(false)
+            }),
+            "  [Console] To Test <t@example.com>: Order #OrderId(3) is on its way!"
+        );
+    }
+
+    #[test]
+    fn a_failing_writer_is_a_failed_notification() {
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _bytes: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let recipient = Recipient::new("Test", Contact::Phone("+15550001111".to_string())).unwrap();
+
+        let result = ConsoleSender::with_writer(Broken).notify(
+            &recipient,
+            &NotificationEvent::OrderShipped { id: OrderId(1) },
+        );
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+    }
+
+    #[test]
+    fn console_words_each_event_differently() {
+        let id = OrderId(7);
+
+        assert_eq!(
+            message(&NotificationEvent::OrderCancelled { id }),
+            "Order #OrderId(7) has been cancelled."
+        );
+        assert_eq!(
+            message(&NotificationEvent::RefundIssued {
+                id,
+                amount: Money(250)
+            }),
+            "Refund of $2.50 issued for order #OrderId(7)."
+        );
+        assert_eq!(
+            message(&NotificationEvent::OrderShipped { id }),
+            "Order #OrderId(7) is on its way!"
+        );
+    }
+
+    #[test]
+    fn a_template_rewords_the_confirmation_only() {
+        let sender = ConsoleSender::new()
+            .with_template(MessageTemplate::parse("Thanks for order {order_id}!").unwrap());
+        let order = Order::new(
+            OrderId(7),
+            vec![LineItem {
+                name: "Test".to_string(),
+                price: Money(100),
+                weight_grams: None,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(
+            sender
+                .text(&NotificationEvent::OrderConfirmed(order))
+                .unwrap(),
+            "Thanks for order 7!"
+        );
+        assert_eq!(
+            sender
+                .text(&NotificationEvent::OrderShipped { id: OrderId(7) })
+                .unwrap(),
+            "Order #OrderId(7) is on its way!"
+        );
     }
 }
@@ -0,0 +1,370 @@
+// =============================================================================
+// Dead-Letter Sender - Where Failed Notifications Wait for a Human
+// =============================================================================
+//
+// RetryingSender tries a few times, then gives up, and the notification is
+// gone: nobody knows the customer never got their confirmation. This decorator
+// keeps what failed instead, as a dead letter:
+//
+//     OrderService -> DeadLetterSender -> RetryingSender -> SmtpSender
+//                           |
+//                           +--(failed)--> DeadLetterStore
+//
+// A dead letter holds the notification itself (recipient and event, so it can
+// be sent again), the message as the customer would have read it, why it
+// failed, how many times it was tried, and when it was set aside.
+//
+// The caller still gets the error: setting the notification aside doesn't
+// make it delivered.
+//
+// REDRIVE:
+// --------
+// Once the mail server is back (or the address fixed), someone calls
+// `redrive` with a working sender. Every dead letter is tried again: the ones
+// that get through leave the store, the others stay, one attempt older.
+//
+// "Attempts" are this decorator's: the first send, then one per redrive. A
+// RetryingSender underneath makes more calls than that; it logs them.
+//
+// Two stores implement the `DeadLetterStore` trait:
+// - InMemoryDeadLetterStore: a Vec, for tests and demos
+// - FileDeadLetterStore: a file, one line per dead letter (dead_letter_file.rs)
+
+use crate::console::message;
+use domain::{
+    Clock, HealthCheck, HealthStatus, NotificationEvent, OrderError, OrderId, Recipient,
+    RequestContext, Sender,
+};
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+/// A notification that couldn't be sent, kept to be sent again later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetter {
+    pub recipient: Recipient,
+    pub event: NotificationEvent,
+    /// The message, worded as the console words it.
+    pub payload: String,
+    /// Why the last attempt failed.
+    pub error: String,
+    /// How many times it was tried: the first send, plus one per redrive.
+    pub attempts: u32,
+    /// When the first send failed.
+    pub failed_at: SystemTime,
+}
+
+impl DeadLetter {
+    /// The order the notification is about.
+    #[must_use]
+    pub fn order_id(&self) -> OrderId {
+        self.event.order_id()
+    }
+}
+
+/// Where dead letters are kept until they're redriven.
+pub trait DeadLetterStore {
+    /// Keeps `letter`, after the ones already there.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the letter can't be stored.
+    fn push(&self, letter: DeadLetter) -> io::Result<()>;
+
+    /// Every dead letter, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the letters can't be read back.
+    fn letters(&self) -> io::Result<Vec<DeadLetter>>;
+
+    /// Replaces every dead letter with `letters`: what's left after a redrive.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the letters can't be stored.
+    fn replace(&self, letters: Vec<DeadLetter>) -> io::Result<()>;
+}
+
+/// Dead-letter store keeping the letters in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryDeadLetterStore {
+    letters: Mutex<Vec<DeadLetter>>,
+}
+
+impl InMemoryDeadLetterStore {
+    /// Creates an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many dead letters are waiting.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.guard().len()
+    }
+
+    /// True when no dead letter is waiting.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn guard(&self) -> MutexGuard<'_, Vec<DeadLetter>> {
+        // A poisoned lock only means another thread panicked; the letters are still fine
+        self.letters.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl DeadLetterStore for InMemoryDeadLetterStore {
+    fn push(&self, letter: DeadLetter) -> io::Result<()> {
+        self.guard().push(letter);
+        Ok(())
+    }
+
+    fn letters(&self) -> io::Result<Vec<DeadLetter>> {
+        Ok(self.guard().clone())
+    }
+
+    fn replace(&self, letters: Vec<DeadLetter>) -> io::Result<()> {
+        *self.guard() = letters;
+        Ok(())
+    }
+}
+
+/// Outcome of [`DeadLetterSender::redrive`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RedriveReport {
+    /// Dead letters sent, and removed from the store.
+    pub sent: usize,
+    /// Dead letters that failed again, and stay in the store.
+    pub failed: usize,
+}
+
+/// Sender decorator keeping the notifications its inner sender failed to send.
+pub struct DeadLetterSender<S> {
+    inner: S,
+    store: Arc<dyn DeadLetterStore + Send + Sync>,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+// Manual impl: the store and the clock are trait objects with no Debug of their own.
+impl<S: fmt::Debug> fmt::Debug for DeadLetterSender<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadLetterSender")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Sender> DeadLetterSender<S> {
+    /// Wraps `inner`, keeping its failures in `store`, stamped by `clock`.
+    #[must_use]
+    pub fn new(
+        inner: S,
+        store: Arc<dyn DeadLetterStore + Send + Sync>,
+        clock: Arc<dyn Clock + Send + Sync>,
+    ) -> Self {
+        Self {
+            inner,
+            store,
+            clock,
+        }
+    }
+
+    /// The wrapped sender.
+    #[must_use]
+    pub const fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Tries every dead letter again through `sender`, oldest first. The ones
+    /// sent leave the store; the others stay, in order, one attempt older.
+    ///
+    /// A store that can't be read or written is logged, and nothing is
+    /// counted as sent.
+    pub fn redrive(&mut self, sender: &S) -> RedriveReport {
+        let letters = match self.store.letters() {
+            Ok(letters) => letters,
+            Err(e) => {
+                println!("  [DeadLetter] Can't read the dead letters: {e}");
+                return RedriveReport::default();
+            }
+        };
+        let mut report = RedriveReport::default();
+        let mut remaining = Vec::new();
+        for mut letter in letters {
+            match sender.notify(&letter.recipient, &letter.event) {
+                Ok(()) => report.sent += 1,
+                Err(e) => {
+                    report.failed += 1;
+                    letter.attempts += 1;
+                    letter.error = e.to_string();
+                    remaining.push(letter);
+                }
+            }
+        }
+        if let Err(e) = self.store.replace(remaining) {
+            println!("  [DeadLetter] Can't store what's left after the redrive: {e}");
+            return RedriveReport {
+                sent: 0,
+                failed: report.sent + report.failed,
+            };
+        }
+        report
+    }
+}
+
+impl<S: Sender> Sender for DeadLetterSender<S> {
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        self.notify_with_ctx(&RequestContext::default(), recipient, event)
+    }
+
+    /// Sends through the inner sender. If that fails, the notification is
+    /// kept as a dead letter, and the error returned all the same.
+    fn notify_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<(), OrderError> {
+        let result = self.inner.notify_with_ctx(ctx, recipient, event);
+        if let Err(e) = &result {
+            let letter = DeadLetter {
+                recipient: recipient.clone(),
+                event: event.clone(),
+                payload: message(event),
+                error: e.to_string(),
+                attempts: 1,
+                failed_at: self.clock.now(),
+            };
+            match self.store.push(letter) {
+                Ok(()) => println!(
+                    "  [DeadLetter] Notification for order #{} set aside: {e}",
+                    event.order_id()
+                ),
+                Err(store_error) => println!(
+                    "  [DeadLetter] Notification for order #{} is lost, the store failed: {store_error}",
+                    event.order_id()
+                ),
+            }
+        }
+        result
+    }
+}
+
+impl<S: HealthCheck> HealthCheck for DeadLetterSender<S> {
+    /// An unusable inner sender only degrades it: nothing is lost, the
+    /// notifications wait in the store for a redrive.
+    fn health(&self) -> HealthStatus {
+        match self.inner.health() {
+            HealthStatus::Unhealthy(reason) => {
+                HealthStatus::Degraded(format!("{reason}, notifications kept for a redrive"))
+            }
+            usable => usable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntermittentSender;
+    use adapters_repository::FixedClock;
+    use domain::{Contact, Money};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn ann() -> Recipient {
+        Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap()
+    }
+
+    fn dead_letter_sender(
+        inner: IntermittentSender,
+    ) -> (
+        DeadLetterSender<IntermittentSender>,
+        Arc<InMemoryDeadLetterStore>,
+    ) {
+        let store = Arc::new(InMemoryDeadLetterStore::new());
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH + Duration::from_secs(60)));
+        (DeadLetterSender::new(inner, store.clone(), clock), store)
+    }
+
+    #[test]
+    fn failures_become_dead_letters() {
+        let (sender, store) = dead_letter_sender(IntermittentSender::scripted(vec![true, false]));
+        let refund = NotificationEvent::RefundIssued {
+            id: OrderId(2),
+            amount: Money(250),
+        };
+
+        sender
+            .notify(&ann(), &NotificationEvent::OrderShipped { id: OrderId(1) })
+            .unwrap();
+        let result = sender.notify(&ann(), &refund);
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+        assert_eq!(
+            store.letters().unwrap(),
+            vec![DeadLetter {
+                recipient: ann(),
+                event: refund,
+                payload: "Refund of $2.50 issued for order #OrderId(2).".to_string(),
+                error: OrderError::NotificationFailed(None).to_string(),
+                attempts: 1,
+                failed_at: UNIX_EPOCH + Duration::from_secs(60),
+            }]
+        );
+    }
+
+    #[test]
+    fn redrive_empties_the_store_once_the_sender_works() {
+        let (mut sender, store) = dead_letter_sender(IntermittentSender::failing_first(2));
+        for id in [1, 2] {
+            let shipped = NotificationEvent::OrderShipped { id: OrderId(id) };
+            assert!(sender.notify(&ann(), &shipped).is_err());
+        }
+        assert_eq!(store.len(), 2);
+
+        let working = IntermittentSender::failing_first(0);
+        let report = sender.redrive(&working);
+
+        assert_eq!(report, RedriveReport { sent: 2, failed: 0 });
+        assert!(store.is_empty());
+        assert_eq!(working.delivered(), vec![OrderId(1), OrderId(2)]);
+    }
+
+    #[test]
+    fn redrive_keeps_what_fails_again() {
+        let (mut sender, store) = dead_letter_sender(IntermittentSender::failing_first(2));
+        for id in [1, 2] {
+            let shipped = NotificationEvent::OrderShipped { id: OrderId(id) };
+            assert!(sender.notify(&ann(), &shipped).is_err());
+        }
+
+        let report = sender.redrive(&IntermittentSender::scripted(vec![false, true]));
+
+        assert_eq!(report, RedriveReport { sent: 1, failed: 1 });
+        let letters = store.letters().unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].order_id(), OrderId(1));
+        assert_eq!(letters[0].attempts, 2);
+    }
+
+    #[test]
+    fn a_dead_inner_sender_only_degrades_it() {
+        let sender = DeadLetterSender::new(
+            crate::FailingSender::new(),
+            Arc::new(InMemoryDeadLetterStore::new()),
+            Arc::new(FixedClock::new(UNIX_EPOCH)),
+        );
+
+        assert_eq!(
+            sender.health(),
+            HealthStatus::Degraded(
+                "every send fails, notifications kept for a redrive".to_string()
+            )
+        );
+    }
+}
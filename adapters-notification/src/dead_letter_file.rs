@@ -0,0 +1,354 @@
+// =============================================================================
+// File Dead-Letter Store - Dead Letters That Survive a Restart
+// =============================================================================
+//
+// InMemoryDeadLetterStore forgets everything when the process stops, and a
+// redrive usually happens after a restart. This store keeps the dead letters
+// in a file, one line each, fields separated by tabs:
+//
+//     <failed at, unix seconds> <attempts> <name> <contact kind> <contact>
+//     <error> <payload> <event kind> <order id> <tenant> <refund amount, or ->
+//     [<item name> <item price>]...
+//
+// Tabs, newlines and backslashes inside a field are written `\t`, `\n` and
+// `\\`, so every dead letter stays on one line. Amounts are in cents.
+//
+// A confirmation is stored with its order's ID, tenant and items: enough to
+// word it again. What else the order held (payment, gift card, status) isn't
+// part of a notification's wording, and comes back as `Order::new` sets it.
+//
+// `push` appends a line. `replace` writes the whole file again, which is fine
+// for a dead-letter queue: it's short, or someone has a bigger problem.
+
+use crate::dead_letter::{DeadLetter, DeadLetterStore};
+use domain::{Contact, LineItem, Money, NotificationEvent, Order, OrderId, Recipient, TenantId};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Dead-letter store keeping one line per dead letter in a file.
+#[derive(Debug)]
+pub struct FileDeadLetterStore {
+    path: PathBuf,
+    // Reads and writes of the file, one at a time
+    lock: Mutex<()>,
+}
+
+impl FileDeadLetterStore {
+    /// Uses the file at `path`, creating it if it doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file can't be created.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// The file the dead letters are kept in.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl DeadLetterStore for FileDeadLetterStore {
+    fn push(&self, letter: DeadLetter) -> io::Result<()> {
+        // A poisoned lock only means another thread panicked; the file is still fine
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        file.write_all(encode(&letter).as_bytes())
+    }
+
+    fn letters(&self) -> io::Result<Vec<DeadLetter>> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        fs::read_to_string(&self.path)?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(decode)
+            .collect()
+    }
+
+    fn replace(&self, letters: Vec<DeadLetter>) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let content: String = letters.iter().map(encode).collect();
+        fs::write(&self.path, content)
+    }
+}
+
+/// One dead letter, as a line of the file.
+fn encode(letter: &DeadLetter) -> String {
+    let seconds = letter
+        .failed_at
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (contact_kind, contact) = match letter.recipient.contact() {
+        Contact::Email(address) => ("email", address),
+        Contact::Phone(number) => ("phone", number),
+        Contact::Webhook(url) => ("webhook", url),
+    };
+    let (event_kind, tenant, amount, items) = match &letter.event {
+        NotificationEvent::OrderConfirmed(order) => {
+            ("confirmed", order.tenant, None, order.items.as_slice())
+        }
+        NotificationEvent::OrderCancelled { .. } => {
+            ("cancelled", TenantId::default(), None, &[][..])
+        }
+        NotificationEvent::RefundIssued { amount, .. } => {
+            ("refund", TenantId::default(), Some(*amount), &[][..])
+        }
+        NotificationEvent::OrderShipped { .. } => ("shipped", TenantId::default(), None, &[][..]),
+    };
+
+    let mut fields = vec![
+        seconds.to_string(),
+        letter.attempts.to_string(),
+        letter.recipient.name().to_string(),
+        contact_kind.to_string(),
+        contact.clone(),
+        letter.error.clone(),
+        letter.payload.clone(),
+        event_kind.to_string(),
+        letter.order_id().0.to_string(),
+        tenant.0.to_string(),
+        amount.map_or_else(|| "-".to_string(), |amount| amount.0.to_string()),
+    ];
+    for item in items {
+        fields.push(item.name.clone());
+        fields.push(item.price.0.to_string());
+    }
+    let escaped: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+    format!("{}\n", escaped.join("\t"))
+}
+
+/// A line of the file, back to a dead letter.
+fn decode(line: &str) -> io::Result<DeadLetter> {
+    let fields: Vec<String> = line.split('\t').map(unescape).collect();
+    let [
+        seconds,
+        attempts,
+        name,
+        contact_kind,
+        contact,
+        error,
+        payload,
+        event_kind,
+        order_id,
+        tenant,
+        amount,
+        items @ ..,
+    ] = fields.as_slice()
+    else {
+        return Err(invalid(&format!(
+            "{} fields, expected 11 or more",
+            fields.len()
+        )));
+    };
+
+    let contact = match contact_kind.as_str() {
+        "email" => Contact::Email(contact.clone()),
+        "phone" => Contact::Phone(contact.clone()),
+        "webhook" => Contact::Webhook(contact.clone()),
+        other => return Err(invalid(&format!("unknown contact kind {other:?}"))),
+    };
+    let recipient =
+        Recipient::new(name.as_str(), contact).map_err(|e| invalid(&format!("recipient: {e}")))?;
+    let id = OrderId(number(order_id)?);
+    let event = match event_kind.as_str() {
+        "confirmed" => {
+            let items = items
+                .chunks(2)
+                .map(|pair| match pair {
+                    [name, price] => Ok(LineItem {
+                        name: name.clone(),
+                        price: Money(number(price)?),
+                        weight_grams: None,
+                    }),
+                    _ => Err(invalid("an item name without a price")),
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            let mut order = Order::new(id, items).map_err(|e| invalid(&format!("order: {e}")))?;
+            order.tenant = TenantId(number(tenant)?);
+            NotificationEvent::OrderConfirmed(order)
+        }
+        "cancelled" => NotificationEvent::OrderCancelled { id },
+        "refund" => NotificationEvent::RefundIssued {
+            id,
+            amount: Money(number(amount)?),
+        },
+        "shipped" => NotificationEvent::OrderShipped { id },
+        other => return Err(invalid(&format!("unknown event kind {other:?}"))),
+    };
+
+    Ok(DeadLetter {
+        recipient,
+        event,
+        payload: payload.clone(),
+        error: error.clone(),
+        attempts: number(attempts)?,
+        failed_at: UNIX_EPOCH + Duration::from_secs(number(seconds)?),
+    })
+}
+
+fn number<T: FromStr>(field: &str) -> io::Result<T> {
+    field
+        .parse()
+        .map_err(|_| invalid(&format!("{field:?} isn't a number")))
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("bad dead letter: {what}"),
+    )
+}
+
+/// `field` with no tab or newline left in it.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The reverse of [`escape`].
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dead_letter::DeadLetterSender;
+    use crate::{FailingSender, RecordingSender};
+    use adapters_repository::FixedClock;
+    use domain::{OrderError, Sender};
+    use std::sync::Arc;
+    use std::time::SystemTime;
+
+    /// A fresh, empty directory under the system's temp directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("{name}_{}_{nanos}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn letter(event: NotificationEvent) -> DeadLetter {
+        DeadLetter {
+            recipient: Recipient::new("Ann\tB.", Contact::Phone("+15550001111".to_string()))
+                .unwrap(),
+            event,
+            payload: "Line one\nline two \\ done".to_string(),
+            error: OrderError::NotificationFailed(None).to_string(),
+            attempts: 3,
+            failed_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        }
+    }
+
+    #[test]
+    fn every_kind_of_event_round_trips() {
+        let mut order = Order::new(
+            OrderId(7),
+            vec![
+                LineItem {
+                    name: "Mug\twith a tab".to_string(),
+                    price: Money(999),
+                    weight_grams: None,
+                },
+                LineItem {
+                    name: "Keyboard".to_string(),
+                    price: Money(12999),
+                    weight_grams: None,
+                },
+            ],
+        )
+        .unwrap();
+        order.tenant = TenantId(3);
+        let letters = vec![
+            letter(NotificationEvent::OrderConfirmed(order)),
+            letter(NotificationEvent::OrderCancelled { id: OrderId(8) }),
+            letter(NotificationEvent::RefundIssued {
+                id: OrderId(9),
+                amount: Money(250),
+            }),
+            letter(NotificationEvent::OrderShipped { id: OrderId(10) }),
+        ];
+
+        for letter in letters {
+            let line = encode(&letter);
+            assert_eq!(line.matches('\n').count(), 1);
+            assert_eq!(decode(line.trim_end_matches('\n')).unwrap(), letter);
+        }
+    }
+
+    #[test]
+    fn a_broken_line_is_invalid_data() {
+        let error = decode("1700000000\t1\tAnn").unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn dead_letters_survive_a_reopen_and_a_redrive() {
+        let dir = temp_dir("dead_letters");
+        let path = dir.join("dead_letters.log");
+        let recipient =
+            Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH));
+
+        {
+            let store = Arc::new(FileDeadLetterStore::open(&path).unwrap());
+            let sender = DeadLetterSender::new(FailingSender::new(), store, clock.clone());
+            for id in [1, 2] {
+                let shipped = NotificationEvent::OrderShipped { id: OrderId(id) };
+                assert!(sender.notify(&recipient, &shipped).is_err());
+            }
+        }
+        // Reopened, as after a restart
+        let store = Arc::new(FileDeadLetterStore::open(&path).unwrap());
+        let waiting = store.letters().unwrap().len();
+        let recorder = RecordingSender::new();
+        let mut sender = DeadLetterSender::new(recorder.clone(), store.clone(), clock);
+        let report = sender.redrive(&recorder);
+
+        let left = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(waiting, 2);
+        assert_eq!(report.sent, 2);
+        assert_eq!(recorder.sent().len(), 2);
+        assert!(left.is_empty());
+    }
+}
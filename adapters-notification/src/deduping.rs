@@ -0,0 +1,208 @@
+// =============================================================================
+// Deduping Sender - The Same Confirmation, Once
+// =============================================================================
+//
+// A retried `place_order` or an outbox flushed twice sends the same
+// confirmation twice, and customers report the second one as spam. This
+// decorator remembers what it already sent, and lets the repeat go no further:
+//
+//     OrderService -> DedupingSender -> SmtpSender
+//
+// A notification is known by its order and its kind of event: the order #42
+// confirmation, the order #42 shipping notice. The same key again within the
+// window is suppressed: the inner sender isn't called, and the caller gets
+// `Ok`, because the customer HAS been told. `suppressed()` counts them.
+//
+// Only a send that went through marks its key. A failed one doesn't, so the
+// retry that follows is sent, not suppressed as a duplicate of a send that
+// never happened.
+//
+// After the window, the key is forgotten and the same notification goes out
+// again: a refund issued today and another one next month are both news.
+// Time comes from the `Clock` port, so tests move past the window with a
+// FixedClock instead of waiting.
+//
+// The keys live in memory: two instances of the service, or a restart, each
+// start with nothing sent.
+
+use domain::{
+    Clock, HealthCheck, HealthStatus, NotificationEvent, OrderError, OrderId, Recipient,
+    RequestContext, Sender,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::{self, Discriminant};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// What a notification is known by: its order, and its kind of event.
+type Key = (OrderId, Discriminant<NotificationEvent>);
+
+/// Sender decorator suppressing notifications already sent within a window.
+pub struct DedupingSender<S> {
+    inner: S,
+    clock: Arc<dyn Clock + Send + Sync>,
+    window: Duration,
+    // When each key was last sent
+    sent: Mutex<HashMap<Key, SystemTime>>,
+    suppressed: AtomicUsize,
+}
+
+// Manual impl: the clock is a trait object with no Debug of its own.
+impl<S: fmt::Debug> fmt::Debug for DedupingSender<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupingSender")
+            .field("inner", &self.inner)
+            .field("window", &self.window)
+            .field("sent", &self.sent)
+            .field("suppressed", &self.suppressed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Sender> DedupingSender<S> {
+    /// Wraps `inner`, suppressing a notification sent again within `window`.
+    #[must_use]
+    pub fn new(inner: S, clock: Arc<dyn Clock + Send + Sync>, window: Duration) -> Self {
+        Self {
+            inner,
+            clock,
+            window,
+            sent: Mutex::new(HashMap::new()),
+            suppressed: AtomicUsize::new(0),
+        }
+    }
+
+    /// The wrapped sender.
+    #[must_use]
+    pub const fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// How many duplicates were suppressed so far.
+    #[must_use]
+    pub fn suppressed(&self) -> usize {
+        self.suppressed.load(Ordering::SeqCst)
+    }
+}
+
+impl<S: Sender> Sender for DedupingSender<S> {
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        self.notify_with_ctx(&RequestContext::default(), recipient, event)
+    }
+
+    /// Sends through the inner sender, unless the same notification was sent
+    /// within the window: then it's suppressed, and `Ok`.
+    fn notify_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<(), OrderError> {
+        let now = self.clock.now();
+        let key = (event.order_id(), mem::discriminant(event));
+        // Held during the send, so two threads can't both send the same key.
+        // A poisoned lock only means another thread panicked; the keys are still fine
+        let mut sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+        // Forget the keys that slid out of the window
+        sent.retain(|_, at| {
+            now.duration_since(*at)
+                .map_or(true, |age| age < self.window)
+        });
+        if sent.contains_key(&key) {
+            self.suppressed.fetch_add(1, Ordering::SeqCst);
+            println!(
+                "  [Dedup] Order #{} was already notified of this: suppressed",
+                key.0
+            );
+            return Ok(());
+        }
+        self.inner.notify_with_ctx(ctx, recipient, event)?;
+        sent.insert(key, now);
+        Ok(())
+    }
+}
+
+impl<S: HealthCheck> HealthCheck for DedupingSender<S> {
+    // Only as healthy as the sender underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntermittentSender;
+    use adapters_repository::FixedClock;
+    use domain::{Contact, Money};
+    use std::time::UNIX_EPOCH;
+
+    fn ann() -> Recipient {
+        Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap()
+    }
+
+    fn shipped(id: u32) -> NotificationEvent {
+        NotificationEvent::OrderShipped { id: OrderId(id) }
+    }
+
+    fn sender(
+        inner: IntermittentSender,
+        clock: &Arc<FixedClock>,
+    ) -> DedupingSender<IntermittentSender> {
+        DedupingSender::new(inner, clock.clone(), Duration::from_secs(60))
+    }
+
+    #[test]
+    fn the_same_notification_is_sent_once() {
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH));
+        let sender = sender(IntermittentSender::failing_first(0), &clock);
+        let refund = NotificationEvent::RefundIssued {
+            id: OrderId(1),
+            amount: Money(250),
+        };
+
+        for event in [shipped(1), shipped(1), refund, shipped(2), shipped(1)] {
+            sender.notify(&ann(), &event).unwrap();
+        }
+
+        // Another kind of event, or another order, isn't a duplicate
+        assert_eq!(sender.suppressed(), 2);
+        assert_eq!(
+            sender.inner().delivered(),
+            vec![OrderId(1), OrderId(1), OrderId(2)]
+        );
+    }
+
+    #[test]
+    fn the_window_expires() {
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH));
+        let sender = sender(IntermittentSender::failing_first(0), &clock);
+
+        sender.notify(&ann(), &shipped(1)).unwrap();
+        clock.advance(Duration::from_secs(59));
+        sender.notify(&ann(), &shipped(1)).unwrap();
+        clock.advance(Duration::from_secs(1));
+        sender.notify(&ann(), &shipped(1)).unwrap();
+
+        assert_eq!(sender.suppressed(), 1);
+        assert_eq!(sender.inner().attempts(), 2);
+    }
+
+    #[test]
+    fn a_failed_send_can_be_retried() {
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH));
+        let sender = sender(IntermittentSender::failing_first(1), &clock);
+
+        let first = sender.notify(&ann(), &shipped(1));
+        let retry = sender.notify(&ann(), &shipped(1));
+        let duplicate = sender.notify(&ann(), &shipped(1));
+
+        assert!(matches!(first, Err(OrderError::NotificationFailed(_))));
+        assert!(retry.is_ok());
+        assert!(duplicate.is_ok());
+        assert_eq!(sender.suppressed(), 1);
+        assert_eq!(sender.inner().delivered(), vec![OrderId(1)]);
+    }
+}
@@ -0,0 +1,70 @@
+// =============================================================================
+// Failing Sender - The Mail Server Is Down
+// =============================================================================
+//
+// Every send fails with `NotificationFailed`. Use it to check what the
+// application does when a customer can't be told: does it keep the order and
+// warn, or refund it?
+//
+// It counts the attempts, so a test can also check that the application tried,
+// and how many times.
+
+use domain::{HealthCheck, HealthStatus, NotificationEvent, OrderError, Recipient, Sender};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Sender failing every notification, for tests.
+#[derive(Debug, Default)]
+pub struct FailingSender {
+    attempts: AtomicU32,
+}
+
+impl FailingSender {
+    /// Creates a sender with no attempt yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many notifications were attempted so far.
+    #[must_use]
+    pub fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::SeqCst)
+    }
+}
+
+impl Sender for FailingSender {
+    /// Always fails with `NotificationFailed`.
+    fn notify(&self, _recipient: &Recipient, _event: &NotificationEvent) -> Result<(), OrderError> {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        Err(OrderError::NotificationFailed(None))
+    }
+}
+
+impl HealthCheck for FailingSender {
+    // Fails every send, by design
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Unhealthy("every send fails".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, OrderId};
+
+    #[test]
+    fn every_attempt_fails_and_is_counted() {
+        let sender = FailingSender::new();
+        let recipient =
+            Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        let event = NotificationEvent::OrderShipped { id: OrderId(1) };
+
+        assert!(matches!(
+            sender.notify(&recipient, &event),
+            Err(OrderError::NotificationFailed(_))
+        ));
+        assert!(sender.notify(&recipient, &event).is_err());
+
+        assert_eq!(sender.attempts(), 2);
+    }
+}
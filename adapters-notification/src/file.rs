@@ -0,0 +1,309 @@
+// =============================================================================
+// File Sender - Notifications for Somebody Else to Deliver
+// =============================================================================
+//
+// Some deployments can't reach any network: no mail server, no Twilio. There,
+// notifications are appended to a local file, and another process picks them
+// up (or an auditor reads them).
+//
+// One line per notification, fields separated by a space, message last:
+//
+//     <unix seconds> <order id> <total, or - for events without one> <message>
+//     1700000000 42 $179.98 Order #OrderId(42)\n\n  Keyboard ...
+//
+// A confirmation's message is the whole receipt (`Order::render_receipt`), so
+// it has newlines: they are written as `\n` (and backslashes as `\\`), so every
+// notification stays on one line, and the reader can split on newlines.
+//
+// ROTATION:
+// ---------
+// With `rotate_at(bytes)`, a file never grows past `bytes` (unless a single
+// line is longer): the line that would cross the limit starts the next file,
+// numbered after the first one:
+//
+//     notifications.log  ->  notifications.log.1  ->  notifications.log.2 ...
+//
+// Reopening the sender carries on in the last numbered file.
+
+use crate::console::message;
+use crate::template::{MessageTemplate, wording};
+use domain::{HealthCheck, HealthStatus, NotificationEvent, OrderError, Recipient, Sender};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Notification sender appending one line per notification to a file.
+#[derive(Debug)]
+pub struct FileSender {
+    path: PathBuf,
+    rotate_at: Option<u64>,
+    template: Option<MessageTemplate>,
+    current: Mutex<CurrentFile>,
+}
+
+/// The file being appended to.
+#[derive(Debug)]
+struct CurrentFile {
+    file: File,
+    index: u32,
+    size: u64,
+}
+
+impl FileSender {
+    /// Opens (or creates) the file at `path` for appending, or the last
+    /// numbered file after it if it was rotated before.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file can't be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut index = 0;
+        while numbered(&path, index + 1).exists() {
+            index += 1;
+        }
+        let current = open_numbered(&path, index)?;
+        Ok(Self {
+            path,
+            rotate_at: None,
+            template: None,
+            current: Mutex::new(current),
+        })
+    }
+
+    /// Starts the next numbered file instead of growing past `bytes`.
+    #[must_use]
+    pub const fn rotate_at(mut self, bytes: u64) -> Self {
+        self.rotate_at = Some(bytes);
+        self
+    }
+
+    /// Words confirmations with `template` instead of the receipt.
+    #[must_use]
+    pub fn with_template(mut self, template: MessageTemplate) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// The file the next notification goes to.
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        // A poisoned lock only means another thread panicked; the file is still fine
+        let current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        numbered(&self.path, current.index)
+    }
+
+    /// Appends `line`, first moving to the next file if it would cross the
+    /// size limit.
+    fn append(&self, line: &str) -> io::Result<()> {
+        // A poisoned lock only means another thread panicked; the file is still fine
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        let len = line.len() as u64;
+        if let Some(limit) = self.rotate_at
+            && current.size > 0
+            && current.size + len > limit
+        {
+            *current = open_numbered(&self.path, current.index + 1)?;
+        }
+        current.file.write_all(line.as_bytes())?;
+        current.size += len;
+        Ok(())
+    }
+}
+
+/// `path` itself for 0, `path.1`, `path.2`... after that.
+fn numbered(path: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let mut numbered = path.as_os_str().to_owned();
+    numbered.push(format!(".{index}"));
+    PathBuf::from(numbered)
+}
+
+fn open_numbered(path: &Path, index: u32) -> io::Result<CurrentFile> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(numbered(path, index))?;
+    let size = file.metadata()?.len();
+    Ok(CurrentFile { file, index, size })
+}
+
+/// One notification, as a line of the file: the message in `template`'s
+/// words, if there's one.
+fn line(
+    timestamp: SystemTime,
+    event: &NotificationEvent,
+    template: Option<&MessageTemplate>,
+) -> Result<String, OrderError> {
+    let seconds = timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let total = match event {
+        NotificationEvent::OrderConfirmed(order) => order.total.to_string(),
+        _ => "-".to_string(),
+    };
+    let text = wording(template, event, |event| match event {
+        NotificationEvent::OrderConfirmed(order) => order.render_receipt(),
+        other => message(other),
+    })?;
+    Ok(format!(
+        "{seconds} {} {total} {}\n",
+        event.order_id().0,
+        escape(&text)
+    ))
+}
+
+/// `text` on one line: newlines as `\n`, backslashes as `\\`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Sender for FileSender {
+    /// Appends one line. I/O errors become `NotificationFailed`.
+    fn notify(&self, _recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let line = line(SystemTime::now(), event, self.template.as_ref())?;
+        self.append(&line).map_err(|e| {
+            println!("  [File] Can't append to {}: {e}", self.path().display());
+            OrderError::NotificationFailed(None)
+        })
+    }
+}
+
+impl HealthCheck for FileSender {
+    /// Checks the directory is still there, and writable.
+    fn health(&self) -> HealthStatus {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        match std::fs::metadata(dir) {
+            Ok(metadata) if metadata.permissions().readonly() => {
+                HealthStatus::Unhealthy(format!("{} is read-only", dir.display()))
+            }
+            Ok(_) => HealthStatus::Healthy,
+            Err(e) => HealthStatus::Unhealthy(format!("{}: {e}", dir.display())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, LineItem, Money, Order, OrderId};
+    use std::fs;
+
+    /// A fresh, empty directory under the system's temp directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("{name}_{}_{nanos}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn ann() -> Recipient {
+        Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap()
+    }
+
+    fn confirmed(item: &str) -> NotificationEvent {
+        NotificationEvent::OrderConfirmed(
+            Order::new(
+                OrderId(42),
+                vec![LineItem {
+                    name: item.to_string(),
+                    price: Money(1999),
+                    weight_grams: None,
+                }],
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn each_notification_is_one_line() {
+        let dir = temp_dir("file_sender_lines");
+        let path = dir.join("notifications.log");
+
+        {
+            let sender = FileSender::open(&path).unwrap();
+            sender.notify(&ann(), &confirmed("Mug")).unwrap();
+        }
+        // Reopening appends instead of truncating
+        let sender = FileSender::open(&path).unwrap();
+        sender
+            .notify(&ann(), &NotificationEvent::OrderShipped { id: OrderId(42) })
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let (seconds, rest) = lines[0].split_once(' ').unwrap();
+        assert!(seconds.parse::<u64>().unwrap() > 1_700_000_000);
+        assert!(rest.starts_with("42 $19.99 Order #OrderId(42)\\n\\n  Mug "));
+        assert!(lines[1].ends_with(" 42 - Order #OrderId(42) is on its way!"));
+    }
+
+    #[test]
+    fn newlines_in_item_names_are_escaped() {
+        let line = line(UNIX_EPOCH, &confirmed("Mug\nBlue \\ large"), None).unwrap();
+
+        assert_eq!(line.matches('\n').count(), 1);
+        assert!(line.contains("  Mug\\nBlue \\\\ large "));
+    }
+
+    #[test]
+    fn a_full_file_rotates_to_the_next_number() {
+        let dir = temp_dir("file_sender_rotation");
+        let path = dir.join("notifications.log");
+        let shipped = NotificationEvent::OrderShipped { id: OrderId(42) };
+        let one_line = line(SystemTime::now(), &shipped, None).unwrap().len() as u64;
+
+        let sender = FileSender::open(&path).unwrap().rotate_at(2 * one_line);
+        for _ in 0..5 {
+            sender.notify(&ann(), &shipped).unwrap();
+        }
+        let last = sender.path();
+        drop(sender);
+        // Reopened, it carries on in the last file
+        let reopened = FileSender::open(&path).unwrap();
+
+        let lines_in = |index| {
+            fs::read_to_string(numbered(&path, index))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        let counts = [lines_in(0), lines_in(1), lines_in(2)];
+        let third_exists = numbered(&path, 3).exists();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(counts, [2, 2, 1]);
+        assert!(!third_exists);
+        assert_eq!(last, dir.join("notifications.log.2"));
+        assert_eq!(reopened.path(), last);
+    }
+
+    #[test]
+    fn a_template_replaces_the_receipt() {
+        let template = MessageTemplate::parse("{item_count} item(s):\n{items}").unwrap();
+
+        let line = line(UNIX_EPOCH, &confirmed("Mug"), Some(&template)).unwrap();
+
+        assert_eq!(line, "0 42 $19.99 1 item(s):\\n- Mug ($19.99)\n");
+    }
+}
@@ -0,0 +1,198 @@
+// =============================================================================
+// Intermittent Sender - The Mail Server Has Bad Minutes
+// =============================================================================
+//
+// Some sends fail, the others get through. Which ones is up to the test:
+//
+//     IntermittentSender::failing_first(2)                 // fail, fail, then deliver
+//     IntermittentSender::scripted(vec![true, false, true]) // deliver, fail, deliver
+//
+// Once the script runs out, every send gets through: the server is back.
+//
+// It counts attempts and failures, and keeps the ID of the order behind each
+// delivered notification, so a test can check a retry delivered each one
+// exactly once.
+
+use domain::{
+    HealthCheck, HealthStatus, NotificationEvent, OrderError, OrderId, Recipient, Sender,
+};
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Sender failing the sends its script says to fail, for tests.
+#[derive(Debug)]
+pub struct IntermittentSender {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // `true` delivers, `false` fails; empty delivers
+    script: VecDeque<bool>,
+    attempts: u32,
+    failures: u32,
+    delivered: Vec<OrderId>,
+}
+
+impl IntermittentSender {
+    /// Fails the first `failures` sends, then delivers every one.
+    #[must_use]
+    pub fn failing_first(failures: usize) -> Self {
+        Self::scripted(vec![false; failures])
+    }
+
+    /// Delivers or fails each send in turn, as `script` says: `true`
+    /// delivers, `false` fails. Delivers every send after the last.
+    #[must_use]
+    pub fn scripted(script: Vec<bool>) -> Self {
+        Self {
+            state: Mutex::new(State {
+                script: script.into(),
+                ..State::default()
+            }),
+        }
+    }
+
+    /// How many notifications were attempted so far.
+    #[must_use]
+    pub fn attempts(&self) -> u32 {
+        self.state().attempts
+    }
+
+    /// How many of them failed.
+    #[must_use]
+    pub fn failures(&self) -> u32 {
+        self.state().failures
+    }
+
+    /// The order behind each delivered notification, oldest first.
+    #[must_use]
+    pub fn delivered(&self) -> Vec<OrderId> {
+        self.state().delivered.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A poisoned lock only means another thread panicked; the counters are still fine
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Sender for IntermittentSender {
+    /// Fails with `NotificationFailed` or delivers, as the script says.
+    fn notify(&self, _recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let mut state = self.state();
+        state.attempts += 1;
+        if state.script.pop_front().unwrap_or(true) {
+            state.delivered.push(event.order_id());
+            Ok(())
+        } else {
+            state.failures += 1;
+            Err(OrderError::NotificationFailed(None))
+        }
+    }
+}
+
+impl HealthCheck for IntermittentSender {
+    /// Degraded while failures are left in the script.
+    fn health(&self) -> HealthStatus {
+        let failures = self
+            .state()
+            .script
+            .iter()
+            .filter(|delivers| !**delivers)
+            .count();
+        if failures == 0 {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Degraded(format!("failures left in the script: {failures}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, LineItem, Money, Order};
+
+    fn send(sender: &IntermittentSender, id: u32) -> Result<(), OrderError> {
+        let recipient =
+            Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        sender.notify(
+            &recipient,
+            &NotificationEvent::OrderShipped { id: OrderId(id) },
+        )
+    }
+
+    #[test]
+    fn failing_first_fails_then_delivers() {
+        let sender = IntermittentSender::failing_first(2);
+
+        assert!(send(&sender, 1).is_err());
+        assert!(send(&sender, 1).is_err());
+        assert!(send(&sender, 1).is_ok());
+        assert!(send(&sender, 2).is_ok());
+
+        assert_eq!(sender.attempts(), 4);
+        assert_eq!(sender.failures(), 2);
+        assert_eq!(sender.delivered(), vec![OrderId(1), OrderId(2)]);
+    }
+
+    #[test]
+    fn scripted_follows_the_script() {
+        let sender = IntermittentSender::scripted(vec![true, false, true]);
+
+        assert!(send(&sender, 1).is_ok());
+        assert!(matches!(
+            send(&sender, 2),
+            Err(OrderError::NotificationFailed(_))
+        ));
+        assert!(send(&sender, 3).is_ok());
+
+        assert_eq!(sender.failures(), 1);
+        assert_eq!(sender.delivered(), vec![OrderId(1), OrderId(3)]);
+    }
+
+    #[test]
+    fn send_all_carries_on_past_failures() {
+        let sender = IntermittentSender::scripted(vec![true, false, false, true]);
+        let recipient =
+            Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        let batch: Vec<(Recipient, Order)> = (1..=5)
+            .map(|id| {
+                let item = LineItem {
+                    name: "Book".to_string(),
+                    price: Money(4999),
+                    weight_grams: None,
+                };
+                (
+                    recipient.clone(),
+                    Order::new(OrderId(id), vec![item]).unwrap(),
+                )
+            })
+            .collect();
+
+        let report = sender.send_all(&batch);
+
+        assert_eq!(report.succeeded, 3);
+        assert_eq!(report.failed(), 2);
+        assert_eq!(report.failed_indices(), [1, 2]);
+        assert!(!report.all_sent());
+        assert_eq!(sender.attempts(), 5);
+        assert_eq!(sender.delivered(), [OrderId(1), OrderId(4), OrderId(5)]);
+    }
+
+    #[test]
+    fn health_is_degraded_until_the_scripted_failures_are_spent() {
+        let sender = IntermittentSender::scripted(vec![false, true, false]);
+        assert_eq!(
+            sender.health(),
+            HealthStatus::Degraded("failures left in the script: 2".to_string())
+        );
+
+        for id in 1..=3 {
+            let _ = send(&sender, id);
+        }
+
+        assert_eq!(sender.health(), HealthStatus::Healthy);
+    }
+}
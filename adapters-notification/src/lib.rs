@@ -5,6 +5,20 @@
 // This crate provides concrete implementations of the `Sender` port.
 // Remember dip_02? The Email struct was our first notification adapter.
 // Here we have two: ConsoleSender (for testing) and SendGridSender (for production).
+// SmtpSender, behind the `smtp` feature, emails through any mail server.
+// WebhookSender, behind the `http` feature, POSTs signed JSON to an endpoint.
+// SmsSender texts a one-line summary through Twilio. SlackSender tells ops
+// about big orders. FileSender appends to a file, for networks with no way out.
+// QueueingSender holds notifications back until a background flush, and
+// RetryingSender tries failed ones again. DeadLetterSender keeps the ones that
+// still failed, in memory or in a file, to redrive later. DedupingSender keeps the same
+// confirmation from going out twice. A MessageTemplate rewords the
+// confirmation of ConsoleSender, SendGridSender and FileSender. ReceiptRenderer
+// turns a confirmation into the HTML and text bodies of an email.
+// CompositeSender combines several of them, to notify on more than one channel,
+// and RoutingSender picks the channels by the kind of event.
+// RecordingSender keeps what was sent, for tests to check. FailingSender and
+// IntermittentSender fail on purpose, for tests of what happens then.
 //
 // WHY A SEPARATE CRATE?
 // ---------------------
@@ -16,7 +30,7 @@
 //
 // Benefits:
 // 1. INDEPENDENT COMPILATION: Change notification code? Payment doesn't recompile.
-// 2. SELECTIVE DEPENDENCIES: Only this crate needs sendgrid-rs (in production).
+// 2. SELECTIVE DEPENDENCIES: Only this crate needs sendgrid-rs or lettre (in production).
 // 3. TEAM OWNERSHIP: The "notifications team" owns this crate.
 // 4. CLEANER DEPENDENCY GRAPH: Each adapter category is isolated.
 //
@@ -29,10 +43,52 @@
 //
 // This is DIP at the crate level.
 
+mod composite;
+mod config;
 mod console;
+mod dead_letter;
+mod dead_letter_file;
+mod deduping;
+mod failing;
+mod file;
+mod intermittent;
+mod queueing;
+mod receipt;
+mod recording;
+mod retrying;
+mod routing;
 mod sendgrid;
+mod slack;
+mod sms;
+#[cfg(feature = "smtp")]
+mod smtp;
+mod template;
+#[cfg(feature = "http")]
+mod webhook;
 
 // Re-export the public adapters.
 // Users of this crate just write: `use adapters_notification::ConsoleSender;`
-pub use console::ConsoleSender;
+pub use composite::{CompositeSender, FanoutPolicy};
+pub use config::ConfigError;
+pub use console::{ConsoleSender, Verbosity};
+pub use dead_letter::{
+    DeadLetter, DeadLetterSender, DeadLetterStore, InMemoryDeadLetterStore, RedriveReport,
+};
+pub use dead_letter_file::FileDeadLetterStore;
+pub use deduping::DedupingSender;
+pub use failing::FailingSender;
+pub use file::FileSender;
+pub use intermittent::IntermittentSender;
+pub use queueing::{OverflowPolicy, QueueingSender};
+pub use receipt::ReceiptRenderer;
+pub use recording::{RecordingSender, SentNotification};
+pub use retrying::RetryingSender;
+pub use routing::{EventKind, RoutingSender};
 pub use sendgrid::SendGridSender;
+pub use slack::SlackSender;
+pub use sms::{MAX_SMS_CHARS, SmsSender};
+#[cfg(feature = "smtp")]
+pub use smtp::{SmtpConfig, SmtpSender, TlsMode};
+pub use template::{MessageTemplate, TemplateError};
+#[cfg(feature = "http")]
+pub use webhook::{SIGNATURE_HEADER, WebhookSender};
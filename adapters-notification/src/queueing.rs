@@ -0,0 +1,226 @@
+// =============================================================================
+// Queueing Sender - Send Later, Not During Checkout
+// =============================================================================
+//
+// Talking to a mail server takes time, and checkout waits for it. This
+// decorator doesn't talk to anybody: it puts the notification in a queue and
+// answers at once. A background tick calls `flush`, which sends the queue
+// through the real sender:
+//
+//     OrderService -> QueueingSender -> (flush, later) -> SmtpSender
+//
+// A failed send stays at the front of the queue, in order, for the next
+// flush. Nothing is lost, unless the process stops: the queue lives in memory.
+// When losing a notification is not an option, use the application's outbox
+// instead, stored next to the orders.
+//
+// BOUNDED:
+// --------
+// If the mail server is down for long, the queue would grow without end. It
+// has a capacity instead, and an `OverflowPolicy` for what happens at it:
+// - DropOldest: make room by dropping the oldest notification
+// - RejectNew:  keep the queue, and fail the new notification
+// Either way, `dropped` counts what never made it.
+
+use domain::{
+    FlushReport, HealthCheck, HealthStatus, NotificationEvent, OrderError, Recipient, Sender,
+};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// What [`QueueingSender`] does with a notification when its queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued notification to make room.
+    DropOldest,
+    /// Fail the new notification with `NotificationFailed`.
+    RejectNew,
+}
+
+/// Sender decorator queueing notifications in memory until [`flush`].
+///
+/// [`flush`]: QueueingSender::flush
+#[derive(Debug)]
+pub struct QueueingSender<S> {
+    inner: S,
+    capacity: usize,
+    policy: OverflowPolicy,
+    queue: Mutex<VecDeque<(Recipient, NotificationEvent)>>,
+    dropped: AtomicUsize,
+}
+
+impl<S: Sender> QueueingSender<S> {
+    /// Wraps `inner`, queueing up to `capacity` notifications.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0: nothing could ever be queued.
+    #[must_use]
+    pub fn new(inner: S, capacity: usize, policy: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "QueueingSender: capacity must be at least 1");
+        Self {
+            inner,
+            capacity,
+            policy,
+            queue: Mutex::new(VecDeque::with_capacity(capacity)),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// The wrapped sender.
+    #[must_use]
+    pub const fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// How many notifications wait for the next flush.
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.queue().len()
+    }
+
+    /// How many notifications were dropped or rejected because the queue was
+    /// full.
+    #[must_use]
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
+    /// Sends every queued notification through the inner sender, oldest
+    /// first. Failed ones stay queued, in order, for the next flush.
+    pub fn flush(&mut self) -> FlushReport {
+        let queue = self.queue.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let mut report = FlushReport::default();
+        let mut failed = VecDeque::new();
+        for (recipient, event) in queue.drain(..) {
+            if self.inner.notify(&recipient, &event).is_ok() {
+                report.sent += 1;
+            } else {
+                report.failed += 1;
+                failed.push_back((recipient, event));
+            }
+        }
+        *queue = failed;
+        report
+    }
+
+    fn queue(&self) -> MutexGuard<'_, VecDeque<(Recipient, NotificationEvent)>> {
+        // A poisoned lock only means another thread panicked; the queue is still fine
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<S: Sender> Sender for QueueingSender<S> {
+    /// Queues the notification, and returns at once.
+    ///
+    /// Fails with `NotificationFailed` only if the queue is full under
+    /// [`OverflowPolicy::RejectNew`].
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let mut queue = self.queue();
+        if queue.len() >= self.capacity {
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    queue.pop_front();
+                }
+                OverflowPolicy::RejectNew => return Err(OrderError::NotificationFailed(None)),
+            }
+        }
+        queue.push_back((recipient.clone(), event.clone()));
+        Ok(())
+    }
+}
+
+impl<S: HealthCheck> HealthCheck for QueueingSender<S> {
+    // Only as healthy as the sender underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntermittentSender;
+    use domain::{Contact, OrderId};
+
+    fn queue(sender: &QueueingSender<IntermittentSender>, ids: &[u32]) -> Vec<bool> {
+        let recipient =
+            Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        ids.iter()
+            .map(|&id| {
+                sender
+                    .notify(
+                        &recipient,
+                        &NotificationEvent::OrderShipped { id: OrderId(id) },
+                    )
+                    .is_ok()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nothing_is_sent_before_the_flush() {
+        let sender = QueueingSender::new(
+            IntermittentSender::failing_first(0),
+            10,
+            OverflowPolicy::RejectNew,
+        );
+
+        assert_eq!(queue(&sender, &[1, 2]), [true, true]);
+
+        assert_eq!(sender.pending(), 2);
+        assert_eq!(sender.inner().attempts(), 0);
+    }
+
+    #[test]
+    fn failed_sends_stay_in_front_for_the_next_flush() {
+        let mut sender = QueueingSender::new(
+            IntermittentSender::scripted(vec![true, false, false, true]),
+            10,
+            OverflowPolicy::RejectNew,
+        );
+        queue(&sender, &[1, 2, 3, 4]);
+
+        let first = sender.flush();
+        queue(&sender, &[5]);
+        let second = sender.flush();
+
+        assert_eq!(first, FlushReport { sent: 2, failed: 2 });
+        assert_eq!(second, FlushReport { sent: 3, failed: 0 });
+        assert_eq!(sender.pending(), 0);
+        let delivered: Vec<u32> = sender.inner().delivered().iter().map(|id| id.0).collect();
+        assert_eq!(delivered, [1, 4, 2, 3, 5]);
+    }
+
+    #[test]
+    fn drop_oldest_makes_room() {
+        let mut sender = QueueingSender::new(
+            IntermittentSender::failing_first(0),
+            2,
+            OverflowPolicy::DropOldest,
+        );
+
+        assert_eq!(queue(&sender, &[1, 2, 3]), [true, true, true]);
+        sender.flush();
+
+        assert_eq!(sender.dropped(), 1);
+        assert_eq!(sender.inner().delivered(), vec![OrderId(2), OrderId(3)]);
+    }
+
+    #[test]
+    fn reject_new_keeps_the_queue() {
+        let mut sender = QueueingSender::new(
+            IntermittentSender::failing_first(0),
+            2,
+            OverflowPolicy::RejectNew,
+        );
+
+        assert_eq!(queue(&sender, &[1, 2, 3]), [true, true, false]);
+        sender.flush();
+
+        assert_eq!(sender.dropped(), 1);
+        assert_eq!(sender.inner().delivered(), vec![OrderId(1), OrderId(2)]);
+    }
+}
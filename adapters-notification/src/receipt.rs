@@ -0,0 +1,260 @@
+// =============================================================================
+// Receipt Renderer - The Confirmation, as an Email Body
+// =============================================================================
+//
+// `Order::render_receipt` is the receipt as a console shows it. A real email
+// carries two bodies: an HTML one, with a table of the items, and a text/plain
+// alternative for the mail clients (and the customers) that won't show HTML.
+// Email senders render both here:
+//
+//     Qty  Item                               Amount
+//       2  Mug                                $19.98
+//       1  Keyboard                          $129.99
+//
+//          Total                             $149.97
+//
+// An order has no quantities: buying two mugs is two identical line items.
+// The receipt groups identical items (same name, same price) into one row,
+// in the order they first appear, with the amount for the whole row.
+// Shipping isn't an item but gets a row too, after them, unless it's free.
+//
+// Item names come from the customer's cart, so they're escaped in the HTML:
+// a product called `<script>` is shown, never run.
+
+use domain::{Money, Order, SHIPPING_LINE_NAME};
+use std::fmt::Write as _;
+
+/// Renders an order's receipt as the HTML and plain-text bodies of an email.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReceiptRenderer;
+
+/// One row of the receipt: identical items, grouped.
+struct Row<'a> {
+    quantity: u32,
+    name: &'a str,
+    price: Money,
+}
+
+impl Row<'_> {
+    /// What the whole row costs.
+    const fn amount(&self) -> Money {
+        Money(self.price.0 * self.quantity)
+    }
+}
+
+impl ReceiptRenderer {
+    /// Creates a renderer.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// The receipt as an HTML fragment: a heading, then a table with one row
+    /// per item and the total in its footer.
+    #[must_use]
+    pub fn render_html(&self, order: &Order) -> String {
+        let mut html = format!("<h1>Order #{}</h1>\n<table>\n", order.id.0);
+        html.push_str("  <thead><tr><th>Qty</th><th>Item</th><th>Amount</th></tr></thead>\n");
+        html.push_str("  <tbody>\n");
+        for row in rows(order) {
+            let _ = writeln!(
+                html,
+                "    <tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                row.quantity,
+                escape_html(row.name),
+                row.amount()
+            );
+        }
+        html.push_str("  </tbody>\n  <tfoot>\n");
+        let _ = writeln!(
+            html,
+            "    <tr><th colspan=\"2\">Total</th><th><strong>{}</strong></th></tr>",
+            order.total
+        );
+        for (label, amount) in payments(order) {
+            let _ = writeln!(
+                html,
+                "    <tr><td colspan=\"2\">{label}</td><td>{amount}</td></tr>"
+            );
+        }
+        html.push_str("  </tfoot>\n</table>\n");
+        html
+    }
+
+    /// The receipt as plain text, in columns.
+    #[must_use]
+    pub fn render_text(&self, order: &Order) -> String {
+        let mut text = format!("Order #{}\n\n", order.id.0);
+        let _ = writeln!(text, "  {:>3}  {:<30} {:>10}", "Qty", "Item", "Amount");
+        for row in rows(order) {
+            let _ = writeln!(
+                text,
+                "  {:>3}  {:<30} {:>10}",
+                row.quantity,
+                row.name,
+                row.amount().to_string()
+            );
+        }
+        let _ = writeln!(
+            text,
+            "\n  {:>3}  {:<30} {:>10}",
+            "",
+            "Total",
+            order.total.to_string()
+        );
+        for (label, amount) in payments(order) {
+            let _ = writeln!(
+                text,
+                "  {:>3}  {:<30} {:>10}",
+                "",
+                label,
+                amount.to_string()
+            );
+        }
+        text
+    }
+}
+
+/// Identical items grouped into rows, in the order they first appear, then
+/// the shipping.
+fn rows(order: &Order) -> Vec<Row<'_>> {
+    let mut rows: Vec<Row<'_>> = Vec::new();
+    for item in &order.items {
+        match rows
+            .iter_mut()
+            .find(|row| row.name == item.name && row.price == item.price)
+        {
+            Some(row) => row.quantity += 1,
+            None => rows.push(Row {
+                quantity: 1,
+                name: &item.name,
+                price: item.price,
+            }),
+        }
+    }
+    if order.shipping_cost() != Money(0) {
+        rows.push(Row {
+            quantity: 1,
+            name: SHIPPING_LINE_NAME,
+            price: order.shipping_cost(),
+        });
+    }
+    rows
+}
+
+/// How the total was paid, when a gift card covered part of it.
+fn payments(order: &Order) -> Vec<(&'static str, Money)> {
+    match order.gift_card {
+        Some(gift) => vec![("Gift card", gift.amount), ("Card", order.card_amount())],
+        None => Vec::new(),
+    }
+}
+
+/// `text` with HTML's special characters escaped.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Address, LineItem, OrderId, Shipping};
+
+    fn item(name: &str, price: u32) -> LineItem {
+        LineItem {
+            name: name.to_string(),
+            price: Money(price),
+            weight_grams: None,
+        }
+    }
+
+    // Two mugs and a keyboard: two rows
+    fn order() -> Order {
+        Order::new(
+            OrderId(42),
+            vec![item("Mug", 999), item("Keyboard", 12999), item("Mug", 999)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn the_text_receipt_groups_identical_items() {
+        assert_eq!(
+            ReceiptRenderer::new().render_text(&order()),
+            "Order #42\n\
+             \n  \
+             Qty  Item                               Amount\n    \
+               2  Mug                                $19.98\n    \
+               1  Keyboard                          $129.99\n\
+             \n       \
+                  Total                             $149.97\n"
+        );
+    }
+
+    #[test]
+    fn the_html_receipt_is_a_table() {
+        assert_eq!(
+            ReceiptRenderer::new().render_html(&order()),
+            "<h1>Order #42</h1>\n\
+             <table>\n  \
+             <thead><tr><th>Qty</th><th>Item</th><th>Amount</th></tr></thead>\n  \
+             <tbody>\n    \
+             <tr><td>2</td><td>Mug</td><td>$19.98</td></tr>\n    \
+             <tr><td>1</td><td>Keyboard</td><td>$129.99</td></tr>\n  \
+             </tbody>\n  \
+             <tfoot>\n    \
+             <tr><th colspan=\"2\">Total</th><th><strong>$149.97</strong></th></tr>\n  \
+             </tfoot>\n\
+             </table>\n"
+        );
+    }
+
+    #[test]
+    fn shipping_comes_after_the_items() {
+        let mut order = order();
+        order
+            .set_shipping(Some(Shipping {
+                destination: Address::new("1 Main St", "Springfield", "12345", "US").unwrap(),
+                cost: Money(499),
+            }))
+            .unwrap();
+
+        let text = ReceiptRenderer::new().render_text(&order);
+
+        assert!(text.contains(
+            "  1  Keyboard                          $129.99\n    \
+               1  Shipping                            $4.99\n"
+        ));
+        assert!(text.ends_with("Total                             $154.96\n"));
+    }
+
+    #[test]
+    fn item_names_are_escaped_in_html() {
+        let order = Order::new(
+            OrderId(1),
+            vec![item("<script>alert('Tom & Jerry')</script>", 100)],
+        )
+        .unwrap();
+
+        let html = ReceiptRenderer::new().render_html(&order);
+        let text = ReceiptRenderer::new().render_text(&order);
+
+        assert!(
+            html.contains("<td>&lt;script&gt;alert(&#39;Tom &amp; Jerry&#39;)&lt;/script&gt;</td>")
+        );
+        assert!(!html.contains("<script>"));
+        // Plain text is shown as is
+        assert!(text.contains("<script>alert('Tom & Jerry')</script>"));
+    }
+}
@@ -0,0 +1,116 @@
+// =============================================================================
+// Recording Sender - What Was Sent, and to Whom
+// =============================================================================
+//
+// ConsoleSender proves a notification went out by printing it. A test can't
+// read stdout. This sender keeps every notification instead, so the test can
+// check it was the right one, for the right order, to the right person.
+//
+// Each notification is stored with its message, worded exactly as
+// ConsoleSender words it: a test can check that the total shows as "$10.00".
+//
+// The records live in an `Arc<Mutex<Vec<SentNotification>>>`, and clones of
+// the sender share it: hand one clone to the service, keep the other for the
+// assertions.
+
+use crate::console::message;
+use domain::{
+    HealthCheck, HealthStatus, NotificationEvent, OrderError, OrderId, Recipient, Sender,
+};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// One notification, as seen by [`RecordingSender`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentNotification {
+    pub recipient: Recipient,
+    /// The order the event is about.
+    pub order_id: OrderId,
+    /// The event, worded for the customer.
+    pub message: String,
+}
+
+/// Sender recording every notification, for test assertions.
+///
+/// Clones share the same records.
+#[derive(Debug, Default, Clone)]
+pub struct RecordingSender {
+    sent: Arc<Mutex<Vec<SentNotification>>>,
+}
+
+impl RecordingSender {
+    /// Creates a sender with nothing sent yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every notification sent so far, oldest first.
+    #[must_use]
+    pub fn sent(&self) -> Vec<SentNotification> {
+        self.records().clone()
+    }
+
+    /// Forgets every notification sent so far.
+    pub fn clear(&self) {
+        self.records().clear();
+    }
+
+    fn records(&self) -> MutexGuard<'_, Vec<SentNotification>> {
+        // A poisoned lock only means another thread panicked; the records are still fine
+        self.sent.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Sender for RecordingSender {
+    /// Records the notification. Never fails.
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        self.records().push(SentNotification {
+            recipient: recipient.clone(),
+            order_id: event.order_id(),
+            message: message(event),
+        });
+        Ok(())
+    }
+}
+
+impl HealthCheck for RecordingSender {
+    // Keeps the notifications in memory: always there
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, Money};
+
+    #[test]
+    fn clones_share_the_records() {
+        let sender = RecordingSender::new();
+        let handed_out = sender.clone();
+        let recipient =
+            Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+
+        handed_out
+            .notify(
+                &recipient,
+                &NotificationEvent::RefundIssued {
+                    id: OrderId(3),
+                    amount: Money(250),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            sender.sent(),
+            vec![SentNotification {
+                recipient,
+                order_id: OrderId(3),
+                message: "Refund of $2.50 issued for order #OrderId(3).".to_string(),
+            }]
+        );
+        sender.clear();
+        assert!(handed_out.sent().is_empty());
+    }
+}
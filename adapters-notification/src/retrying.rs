@@ -0,0 +1,192 @@
+// =============================================================================
+// Retrying Sender - A Decorator
+// =============================================================================
+//
+// Mail servers have bad minutes too: a refused connection, a greylisting
+// "try again later". Giving up on the customer's confirmation for that is a
+// poor experience.
+//
+// This adapter WRAPS another Sender and tries again when it fails with
+// `NotificationFailed`, up to a maximum number of attempts. Any other error
+// isn't the sender's to retry, and is returned at once:
+//
+//     OrderService -> RetryingSender -> SmtpSender
+//
+// When every attempt failed, the last error is returned. NotificationFailed
+// carries no data, so the number of attempts goes to the log instead, and to
+// `last_attempts()` for tests.
+//
+// Like RetryingPaymentGateway, it waits between attempts through a sleeper:
+// `std::thread::sleep` in production, a closure that only records the delay
+// in tests.
+//
+// Unlike a payment, a notification has no idempotency key: if a "failed"
+// send actually went through, the retry sends it twice. For a notification,
+// twice beats never.
+
+use domain::{
+    HealthCheck, HealthStatus, NotificationEvent, OrderError, Recipient, RequestContext, Sender,
+};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+/// Sender decorator retrying failed notifications.
+///
+/// `Z` is the sleeper called between attempts. Production code uses
+/// [`std::thread::sleep`]; tests inject a closure that only records the delay.
+#[derive(Debug)]
+pub struct RetryingSender<S, Z = fn(Duration)> {
+    inner: S,
+    max_attempts: u32,
+    delay: Duration,
+    sleeper: Z,
+    last_attempts: AtomicU32,
+}
+
+impl<S: Sender> RetryingSender<S> {
+    /// Wraps `inner`, trying each notification up to `max_attempts` times
+    /// (zero counts as one), 200ms apart.
+    #[must_use]
+    pub fn new(inner: S, max_attempts: u32) -> Self {
+        Self::with_sleeper(inner, max_attempts, std::thread::sleep)
+    }
+}
+
+impl<S: Sender, Z: Fn(Duration)> RetryingSender<S, Z> {
+    /// Wraps `inner` with a custom sleeper.
+    #[must_use]
+    pub const fn with_sleeper(inner: S, max_attempts: u32, sleeper: Z) -> Self {
+        Self {
+            inner,
+            max_attempts,
+            delay: Duration::from_millis(200),
+            sleeper,
+            last_attempts: AtomicU32::new(0),
+        }
+    }
+
+    /// Waits `delay` between two attempts instead of 200ms.
+    #[must_use]
+    pub const fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// The wrapped sender.
+    #[must_use]
+    pub const fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// How many attempts the last notification took.
+    #[must_use]
+    pub fn last_attempts(&self) -> u32 {
+        self.last_attempts.load(Ordering::SeqCst)
+    }
+}
+
+impl<S: Sender, Z: Fn(Duration)> Sender for RetryingSender<S, Z> {
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        self.notify_with_ctx(&RequestContext::default(), recipient, event)
+    }
+
+    /// Sends through the inner sender until it succeeds, fails with anything
+    /// but `NotificationFailed`, or runs out of attempts.
+    ///
+    /// The error of the last attempt is the one returned.
+    fn notify_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<(), OrderError> {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let result = self.inner.notify_with_ctx(ctx, recipient, event);
+            match result {
+                Err(OrderError::NotificationFailed(_)) if attempt < max_attempts => {
+                    println!(
+                        "  [Retry] Notification attempt {attempt} failed, retrying in {:?}",
+                        self.delay
+                    );
+                    (self.sleeper)(self.delay);
+                    attempt += 1;
+                }
+                result => {
+                    self.last_attempts.store(attempt, Ordering::SeqCst);
+                    if let Err(e) = &result {
+                        println!(
+                            "  [Retry] Giving up on the notification after {attempt} attempt(s): {e}"
+                        );
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+}
+
+impl<S: HealthCheck, Z> HealthCheck for RetryingSender<S, Z> {
+    // Only as healthy as the sender underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FailingSender, IntermittentSender};
+    use domain::{Contact, OrderId};
+    use std::cell::RefCell;
+
+    fn shipped() -> NotificationEvent {
+        NotificationEvent::OrderShipped { id: OrderId(1) }
+    }
+
+    fn ann() -> Recipient {
+        Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap()
+    }
+
+    #[test]
+    fn failing_twice_then_succeeding_takes_three_calls() {
+        let delays = RefCell::new(Vec::new());
+        let sender = RetryingSender::with_sleeper(IntermittentSender::failing_first(2), 3, |d| {
+            delays.borrow_mut().push(d);
+        });
+
+        let result = sender.notify(&ann(), &shipped());
+
+        assert!(result.is_ok());
+        assert_eq!(sender.inner().attempts(), 3);
+        assert_eq!(sender.last_attempts(), 3);
+        assert_eq!(*delays.borrow(), vec![Duration::from_millis(200); 2]);
+    }
+
+    #[test]
+    fn exhausted_retries_surface_the_last_error() {
+        let sender = RetryingSender::with_sleeper(FailingSender::new(), 4, |_| {})
+            .with_delay(Duration::ZERO);
+
+        let result = sender.notify(&ann(), &shipped());
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+        assert_eq!(sender.inner().attempts(), 4);
+        assert_eq!(sender.last_attempts(), 4);
+    }
+
+    #[test]
+    fn each_send_counts_its_own_attempts() {
+        let sender = RetryingSender::with_sleeper(
+            IntermittentSender::scripted(vec![false, true, true]),
+            3,
+            |_| {},
+        );
+
+        sender.notify(&ann(), &shipped()).unwrap();
+        assert_eq!(sender.last_attempts(), 2);
+        sender.notify(&ann(), &shipped()).unwrap();
+        assert_eq!(sender.last_attempts(), 1);
+    }
+}
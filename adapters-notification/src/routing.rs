@@ -0,0 +1,258 @@
+// =============================================================================
+// Routing Sender - Each Kind of Event to Its Own Channels
+// =============================================================================
+//
+// CompositeSender sends EVERY event to every channel. Usually that's too much:
+// a confirmation goes by email, a refund by email and to the ops Slack channel,
+// a shipping notice by SMS. This sender holds a routing table, from the kind of
+// event to the senders it goes to:
+//
+//     RoutingSender::new()
+//         .with_route(EventKind::Confirmed, vec![email.clone()])
+//         .with_route(EventKind::Refund, vec![email, slack])
+//
+// Every sender of the route is called, in order, and every one of them is
+// required: if any fails, the notification fails (after the others were
+// tried), as with `FanoutPolicy::FailIfAnyFails`. NotificationFailed carries
+// one source at most, not a list, so which ones failed goes to the log.
+//
+// A kind of event with no route goes to the default route, if there's one.
+// Otherwise it's dropped: `Ok`, since nobody asked for it, but counted in
+// `unrouted()` so a forgotten route shows up somewhere.
+//
+// The same sender often serves several routes, so they're shared: an `Arc`.
+
+use domain::{
+    HealthCheck, HealthStatus, NotificationEvent, OrderError, Recipient, RequestContext, Sender,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The kind of a [`NotificationEvent`], without its data: what routes are
+/// chosen by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// [`NotificationEvent::OrderConfirmed`]
+    Confirmed,
+    /// [`NotificationEvent::OrderCancelled`]
+    Cancelled,
+    /// [`NotificationEvent::RefundIssued`]
+    Refund,
+    /// [`NotificationEvent::OrderShipped`]
+    Shipped,
+}
+
+impl EventKind {
+    /// The kind of `event`.
+    #[must_use]
+    pub const fn of(event: &NotificationEvent) -> Self {
+        match event {
+            NotificationEvent::OrderConfirmed(_) => Self::Confirmed,
+            NotificationEvent::OrderCancelled { .. } => Self::Cancelled,
+            NotificationEvent::RefundIssued { .. } => Self::Refund,
+            NotificationEvent::OrderShipped { .. } => Self::Shipped,
+        }
+    }
+}
+
+/// Sender forwarding each event to the senders of its kind's route.
+#[derive(Default)]
+pub struct RoutingSender {
+    routes: HashMap<EventKind, Vec<Arc<dyn Sender>>>,
+    default_route: Option<Vec<Arc<dyn Sender>>>,
+    unrouted: AtomicUsize,
+}
+
+// Manual impl: the senders have no Debug of their own.
+impl fmt::Debug for RoutingSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routes: HashMap<_, _> = self
+            .routes
+            .iter()
+            .map(|(kind, senders)| (kind, senders.len()))
+            .collect();
+        f.debug_struct("RoutingSender")
+            .field("routes", &routes)
+            .field("default_route", &self.default_route.as_ref().map(Vec::len))
+            .field("unrouted", &self.unrouted)
+            .finish()
+    }
+}
+
+impl RoutingSender {
+    /// Creates a sender with no route: every event is dropped until routes
+    /// are added.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends events of `kind` to `senders`, replacing any earlier route for
+    /// that kind.
+    #[must_use]
+    pub fn with_route(mut self, kind: EventKind, senders: Vec<Arc<dyn Sender>>) -> Self {
+        self.routes.insert(kind, senders);
+        self
+    }
+
+    /// Sends events of a kind with no route of its own to `senders`, instead
+    /// of dropping them.
+    #[must_use]
+    pub fn with_default_route(mut self, senders: Vec<Arc<dyn Sender>>) -> Self {
+        self.default_route = Some(senders);
+        self
+    }
+
+    /// How many events were dropped because no route, not even a default
+    /// one, took them.
+    #[must_use]
+    pub fn unrouted(&self) -> usize {
+        self.unrouted.load(Ordering::SeqCst)
+    }
+}
+
+impl Sender for RoutingSender {
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        self.notify_with_ctx(&RequestContext::default(), recipient, event)
+    }
+
+    /// Notifies every sender of the event's route, and fails if any of them
+    /// failed. An event with no route is dropped, successfully.
+    fn notify_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<(), OrderError> {
+        let kind = EventKind::of(event);
+        let Some(senders) = self.routes.get(&kind).or(self.default_route.as_ref()) else {
+            self.unrouted.fetch_add(1, Ordering::SeqCst);
+            println!("  [Routing] No route for {kind:?}: dropped");
+            return Ok(());
+        };
+
+        let mut failures = 0;
+        for (index, sender) in senders.iter().enumerate() {
+            if let Err(e) = sender.notify_with_ctx(ctx, recipient, event) {
+                println!("  [Routing] Sender {index} of the {kind:?} route failed: {e}");
+                failures += 1;
+            }
+        }
+        if failures > 0 {
+            Err(OrderError::NotificationFailed(None))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl HealthCheck for RoutingSender {
+    /// Like `CompositeSender`, the senders are trait objects: only a router
+    /// with nowhere to send anything is reported.
+    fn health(&self) -> HealthStatus {
+        if self.routes.is_empty() && self.default_route.is_none() {
+            HealthStatus::Unhealthy("no routes".to_string())
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FailingSender, RecordingSender};
+    use domain::{Contact, LineItem, Money, Order, OrderId};
+
+    fn ann() -> Recipient {
+        Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap()
+    }
+
+    fn confirmed(id: u32) -> NotificationEvent {
+        NotificationEvent::OrderConfirmed(
+            Order::new(
+                OrderId(id),
+                vec![LineItem {
+                    name: "Mug".to_string(),
+                    price: Money(999),
+                    weight_grams: None,
+                }],
+            )
+            .unwrap(),
+        )
+    }
+
+    fn refund(id: u32) -> NotificationEvent {
+        NotificationEvent::RefundIssued {
+            id: OrderId(id),
+            amount: Money(250),
+        }
+    }
+
+    fn order_ids(sender: &RecordingSender) -> Vec<u32> {
+        sender.sent().iter().map(|sent| sent.order_id.0).collect()
+    }
+
+    #[test]
+    fn each_kind_goes_to_its_own_route() {
+        let email = RecordingSender::new();
+        let slack = RecordingSender::new();
+        let sms = RecordingSender::new();
+        let sender = RoutingSender::new()
+            .with_route(EventKind::Confirmed, vec![Arc::new(email.clone())])
+            .with_route(
+                EventKind::Refund,
+                vec![Arc::new(email.clone()), Arc::new(slack.clone())],
+            )
+            .with_route(EventKind::Shipped, vec![Arc::new(sms.clone())]);
+
+        sender.notify(&ann(), &confirmed(1)).unwrap();
+        sender.notify(&ann(), &refund(2)).unwrap();
+        sender
+            .notify(&ann(), &NotificationEvent::OrderShipped { id: OrderId(3) })
+            .unwrap();
+
+        assert_eq!(order_ids(&email), [1, 2]);
+        assert_eq!(order_ids(&slack), [2]);
+        assert_eq!(order_ids(&sms), [3]);
+        assert_eq!(sender.unrouted(), 0);
+    }
+
+    #[test]
+    fn unrouted_kinds_take_the_default_route_or_are_dropped() {
+        let email = RecordingSender::new();
+        let fallback = RecordingSender::new();
+        let cancelled = NotificationEvent::OrderCancelled { id: OrderId(4) };
+        let without_default =
+            RoutingSender::new().with_route(EventKind::Confirmed, vec![Arc::new(email.clone())]);
+        let with_default = RoutingSender::new()
+            .with_route(EventKind::Confirmed, vec![Arc::new(email.clone())])
+            .with_default_route(vec![Arc::new(fallback.clone())]);
+
+        assert!(without_default.notify(&ann(), &cancelled).is_ok());
+        assert!(with_default.notify(&ann(), &cancelled).is_ok());
+
+        assert_eq!(without_default.unrouted(), 1);
+        assert_eq!(with_default.unrouted(), 0);
+        assert_eq!(order_ids(&fallback), [4]);
+        assert!(email.sent().is_empty());
+    }
+
+    #[test]
+    fn one_failed_sender_fails_the_route_after_trying_all() {
+        let email = RecordingSender::new();
+        let slack = Arc::new(FailingSender::new());
+        let sender = RoutingSender::new().with_route(
+            EventKind::Refund,
+            vec![slack.clone(), Arc::new(email.clone())],
+        );
+
+        let result = sender.notify(&ann(), &refund(5));
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+        assert_eq!(slack.attempts(), 1);
+        assert_eq!(order_ids(&email), [5]);
+    }
+}
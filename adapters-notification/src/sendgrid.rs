@@ -8,8 +8,42 @@
 //
 // The key point: the APPLICATION layer doesn't know this is SendGrid.
 // It just knows it has something that implements `Sender`.
+//
+// CONFIGURATION:
+// --------------
+// SendGrid needs an API key and a verified from-address. `new` checks both
+// (a key that isn't empty, an address that looks like one) and `from_env`
+// reads them from SENDGRID_API_KEY and SENDGRID_FROM. A bad configuration is
+// a `ConfigError` at startup, not a failed email per order.
+//
+// The demo has no SendGrid account: `simulated()` builds a sender with no key,
+// which only logs, to stdout or to a `Logger` when one is plugged in.
+//
+// A confirmation is the receipt (see receipt.rs), as HTML with a text/plain
+// alternative, unless a body template rewords it: then it's the template's
+// text only.
+//
+// The API key is a secret: Debug leaves it out, so it never ends up in a log.
+//
+// BATCHES:
+// --------
+// SendGrid's `/v3/mail/send` takes up to 1,000 "personalizations" (one per
+// recipient, each with its own subject and body) in one call. `send_all`
+// uses that: one API call per 1,000 confirmations instead of one each.
+
+use crate::config::ConfigError;
+use crate::console::message;
+use crate::receipt::ReceiptRenderer;
+use crate::template::{MessageTemplate, wording};
+use domain::{
+    Contact, HealthCheck, HealthStatus, Logger, NotificationEvent, Order, OrderError, Recipient,
+    RequestContext, SendAllReport, Sender,
+};
+use std::fmt;
+use std::sync::Arc;
 
-use domain::{Order, OrderError, Sender};
+/// The most recipients one SendGrid API call takes.
+const PERSONALIZATIONS_PER_CALL: usize = 1_000;
 
 /// Simulated SendGrid notification sender.
 ///
@@ -19,36 +53,199 @@ use domain::{Order, OrderError, Sender};
 /// 3. Call the SendGrid API
 /// 4. Handle rate limits and retries
 ///
-/// Here we simulate it with println!
-#[derive(Debug, Default, Clone, Copy)]
-pub struct SendGridSender;
+/// Here we simulate it: the email is logged instead.
+#[derive(Clone)]
+pub struct SendGridSender {
+    // Empty for the simulated sender
+    api_key: String,
+    from_email: String,
+    subject_template: Option<MessageTemplate>,
+    body_template: Option<MessageTemplate>,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
 
-// In a real implementation, we'd have:
-//
-// pub struct SendGridSender {
-//     api_key: String,
-//     from_email: String,
-// }
-//
-// impl SendGridSender {
-//     pub fn new(api_key: &str, from_email: &str) -> Self {
-//         Self {
-//             api_key: api_key.to_string(),
-//             from_email: from_email.to_string(),
-//         }
-//     }
-// }
+// Manual impl: the API key must never end up in a log.
+impl fmt::Debug for SendGridSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendGridSender")
+            .field("from_email", &self.from_email)
+            .field("simulated", &self.api_key.is_empty())
+            .field("subject_template", &self.subject_template)
+            .field("body_template", &self.body_template)
+            .field("logger", &self.logger.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SendGridSender {
+    /// Creates a sender emailing from `from_email` with the account of
+    /// `api_key`, with the default wording.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Missing`] if `api_key` is empty, and
+    /// [`ConfigError::Invalid`] if `from_email` isn't an email address.
+    pub fn new(api_key: impl Into<String>, from_email: &str) -> Result<Self, ConfigError> {
+        let api_key = api_key.into();
+        if api_key.trim().is_empty() {
+            return Err(ConfigError::Missing("SendGrid API key"));
+        }
+        if !Contact::Email(from_email.to_string()).is_valid() {
+            return Err(ConfigError::Invalid {
+                setting: "SendGrid from-address",
+                value: from_email.to_string(),
+            });
+        }
+        Ok(Self {
+            api_key,
+            from_email: from_email.to_string(),
+            subject_template: None,
+            body_template: None,
+            logger: None,
+        })
+    }
+
+    /// Creates a sender configured by `SENDGRID_API_KEY` and `SENDGRID_FROM`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Missing`] if a variable isn't set, and the
+    /// errors of [`SendGridSender::new`] for its value.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// A sender with no account, for demos: it only logs.
+    #[must_use]
+    pub fn simulated() -> Self {
+        Self {
+            api_key: String::new(),
+            from_email: "orders@example.com".to_string(),
+            subject_template: None,
+            body_template: None,
+            logger: None,
+        }
+    }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    /// Words the subject of confirmations with `template` instead.
+    #[must_use]
+    pub fn with_subject_template(mut self, template: MessageTemplate) -> Self {
+        self.subject_template = Some(template);
+        self
+    }
+
+    /// Words the body of confirmations with `template` instead.
+    #[must_use]
+    pub fn with_body_template(mut self, template: MessageTemplate) -> Self {
+        self.body_template = Some(template);
+        self
+    }
+
+    /// The address emails are sent from.
+    #[must_use]
+    pub fn from_email(&self) -> &str {
+        &self.from_email
+    }
+
+    /// Reads the configuration from `var`, one `SENDGRID_*` variable at a time.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let required = |name: &'static str| {
+            var(name)
+                .filter(|value| !value.trim().is_empty())
+                .ok_or(ConfigError::Missing(name))
+        };
+        Self::new(required("SENDGRID_API_KEY")?, &required("SENDGRID_FROM")?)
+    }
+
+    /// The emails of a batch of confirmations, by their index in the batch,
+    /// and the confirmations that can't be sent (not to an email address).
+    fn batch_emails<'b>(
+        &self,
+        batch: &'b [(Recipient, Order)],
+    ) -> (Vec<(&'b str, Email)>, SendAllReport) {
+        let mut emails = Vec::new();
+        let mut report = SendAllReport::default();
+        for (index, (recipient, order)) in batch.iter().enumerate() {
+            let email = match recipient.contact() {
+                Contact::Email(address) => self
+                    .email(&NotificationEvent::OrderConfirmed(order.clone()))
+                    .map(|email| (address.as_str(), email)),
+                _ => Err(OrderError::NotificationFailed(None)),
+            };
+            match email {
+                Ok(email) => emails.push(email),
+                Err(e) => report.failures.push((index, e)),
+            }
+        }
+        (emails, report)
+    }
+
+    /// The email about `event`.
+    fn email(&self, event: &NotificationEvent) -> Result<Email, OrderError> {
+        let receipt = ReceiptRenderer::new();
+        let html = match (event, &self.body_template) {
+            (NotificationEvent::OrderConfirmed(order), None) => Some(receipt.render_html(order)),
+            _ => None,
+        };
+        Ok(Email {
+            subject: wording(self.subject_template.as_ref(), event, subject)?,
+            text: wording(self.body_template.as_ref(), event, |event| match event {
+                NotificationEvent::OrderConfirmed(order) => receipt.render_text(order),
+                other => message(other),
+            })?,
+            html,
+        })
+    }
+}
+
+/// What SendGrid is asked to send.
+#[derive(Debug)]
+struct Email {
+    subject: String,
+    /// The text/plain body.
+    text: String,
+    /// The text/html body, when there's one.
+    html: Option<String>,
+}
 
 impl Sender for SendGridSender {
-    /// Sends an order confirmation email via SendGrid.
+    /// Sends a notification email via SendGrid.
     ///
     /// Real implementation would look like:
     /// ```ignore
-    /// async fn send(&self, order: &Order) -> Result<(), OrderError> {
-    ///     let message = Message::new()
+    /// async fn notify(
+    ///     &self,
+    ///     recipient: &Recipient,
+    ///     event: &NotificationEvent,
+    /// ) -> Result<(), OrderError> {
+    ///     let Contact::Email(address) = recipient.contact() else {
+    ///         return Err(OrderError::NotificationFailed);
+    ///     };
+    ///
+    ///     let email = self.email(event)?;
+    ///     let mut message = Message::new()
     ///         .set_from(self.from_email.clone())
-    ///         .set_subject(format!("Order #{} Confirmed", order.id))
-    ///         .add_content(/* HTML template */);
+    ///         .add_to(address)
+    ///         .set_subject(&email.subject)
+    ///         .add_content(Content::new().set_content_type("text/plain").set_value(&email.text));
+    ///     if let Some(html) = &email.html {
+    ///         message = message
+    ///             .add_content(Content::new().set_content_type("text/html").set_value(html));
+    ///     }
     ///
     ///     sendgrid::send(&self.api_key, &message)
     ///         .await
@@ -60,34 +257,260 @@ impl Sender for SendGridSender {
     ///
     /// Note how SendGrid errors become `OrderError::NotificationFailed`.
     /// The application layer never sees sendgrid::Error!
-    fn send(&self, order: &Order) -> Result<(), OrderError> {
-        println!(
-            "  [SendGrid API] Sending email: 'Order #{} Confirmed'",
-            order.id
-        );
+    ///
+    /// SendGrid only does email. A recipient reachable by phone or webhook
+    /// is a `NotificationFailed` too.
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let Contact::Email(address) = recipient.contact() else {
+            return Err(OrderError::NotificationFailed(None));
+        };
+        let email = self.email(event)?;
+        self.log(&format!(
+            "[SendGrid API] Sending email from {} to {address}: '{}'",
+            self.from_email, email.subject
+        ));
+        self.log(&format!("  {}", email.text));
+        if let Some(html) = &email.html {
+            self.log(&format!(
+                "  (+ a text/html alternative, {} bytes)",
+                html.len()
+            ));
+        }
         Ok(())
     }
+
+    /// Sends the confirmations through SendGrid's batch endpoint: one API
+    /// call per [`PERSONALIZATIONS_PER_CALL`] recipients.
+    ///
+    /// Real implementation: the same `Message`, with one `Personalization`
+    /// per recipient (its address, subject and body) instead of `add_to`.
+    /// A recipient that can't get an email fails alone; the call is made
+    /// for the others.
+    fn send_all(&self, batch: &[(Recipient, Order)]) -> SendAllReport {
+        let (emails, mut report) = self.batch_emails(batch);
+        for call in emails.chunks(PERSONALIZATIONS_PER_CALL) {
+            self.log(&format!(
+                "[SendGrid API] Sending {} emails from {} in one call",
+                call.len(),
+                self.from_email
+            ));
+            for (address, email) in call {
+                self.log(&format!("  to {address}: '{}'", email.subject));
+            }
+            report.succeeded += call.len();
+        }
+        report
+    }
+}
+
+/// Email subject line for each kind of event.
+pub(crate) fn subject(event: &NotificationEvent) -> String {
+    match event {
+        NotificationEvent::OrderConfirmed(order) => format!("Order #{} Confirmed", order.id),
+        NotificationEvent::OrderCancelled { id } => format!("Order #{id} Cancelled"),
+        NotificationEvent::RefundIssued { id, amount } => {
+            format!("Refund of {amount} Issued for Order #{id}")
+        }
+        NotificationEvent::OrderShipped { id } => format!("Order #{id} Shipped"),
+    }
+}
+
+impl HealthCheck for SendGridSender {
+    // Simulated: there is no SendGrid call to make
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use domain::{LineItem, Money, OrderId};
+    use adapters_repository::CapturingLogger;
+    use domain::{LineItem, Money, Order, OrderId};
+    use std::collections::HashMap;
 
-    #[test]
-    fn sendgrid_sender_succeeds() {
-        let sender = SendGridSender;
-        let order = Order::new(
+    fn test_order() -> Order {
+        Order::new(
             OrderId(1),
             vec![LineItem {
                 name: "Test".to_string(),
                 price: Money(100),
+                weight_grams: None,
             }],
         )
-        .unwrap();
+        .unwrap()
+    }
+
+    #[test]
+    fn sendgrid_sender_succeeds() {
+        let sender = SendGridSender::simulated();
+        let recipient =
+            Recipient::new("Test", Contact::Email("test@example.com".to_string())).unwrap();
 
-        let result = sender.send(&order);
+        let result = sender.send(&recipient, &test_order());
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn log_lines_go_to_the_logger() {
+        let logger = Arc::new(CapturingLogger::new());
+        let sender = SendGridSender::simulated().with_logger(logger.clone());
+        let recipient =
+            Recipient::new("Test", Contact::Email("test@example.com".to_string())).unwrap();
+
+        sender
+            .notify(
+                &recipient,
+                &NotificationEvent::OrderShipped { id: OrderId(1) },
+            )
+            .unwrap();
+
+        let messages: Vec<_> = logger.lines().into_iter().map(|l| l.message).collect();
+        assert_eq!(
+            messages[0],
+            format!(
+                "[SendGrid API] Sending email from orders@example.com to test@example.com: '{}'",
+                subject(&NotificationEvent::OrderShipped { id: OrderId(1) })
+            )
+        );
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn sendgrid_rejects_non_email_recipient() {
+        let sender = SendGridSender::simulated();
+        let recipient = Recipient::new("Test", Contact::Phone("+15550001111".to_string())).unwrap();
+
+        let result = sender.send(&recipient, &test_order());
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+    }
+
+    #[test]
+    fn sendgrid_send_all_fails_only_the_recipients_without_an_email() {
+        let sender = SendGridSender::simulated();
+        let email = Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        let phone = Recipient::new("Bob", Contact::Phone("+15550001111".to_string())).unwrap();
+        let batch = [
+            (email.clone(), test_order()),
+            (phone, test_order()),
+            (email, test_order()),
+        ];
+
+        let report = sender.send_all(&batch);
+
+        assert_eq!(report.succeeded, 2);
+        assert_eq!(report.failed_indices(), [1]);
+        assert!(matches!(
+            report.failures[0].1,
+            OrderError::NotificationFailed(_)
+        ));
+        let (emails, _) = sender.batch_emails(&batch);
+        let addresses: Vec<&str> = emails.iter().map(|(address, _)| *address).collect();
+        assert_eq!(addresses, ["ann@example.com", "ann@example.com"]);
+    }
+
+    #[test]
+    fn sendgrid_subject_per_event() {
+        let id = OrderId(1);
+
+        assert_eq!(
+            subject(&NotificationEvent::OrderConfirmed(test_order())),
+            "Order #OrderId(1) Confirmed"
+        );
+        assert_eq!(
+            subject(&NotificationEvent::OrderCancelled { id }),
+            "Order #OrderId(1) Cancelled"
+        );
+        assert_eq!(
+            subject(&NotificationEvent::RefundIssued {
+                id,
+                amount: Money(100)
+            }),
+            "Refund of $1.00 Issued for Order #OrderId(1)"
+        );
+        assert_eq!(
+            subject(&NotificationEvent::OrderShipped { id }),
+            "Order #OrderId(1) Shipped"
+        );
+    }
+
+    #[test]
+    fn sendgrid_templates_replace_subject_and_body() {
+        let sender = SendGridSender::simulated()
+            .with_subject_template(MessageTemplate::parse("Your order {order_id}").unwrap())
+            .with_body_template(MessageTemplate::parse("{items}\nTotal: {total}").unwrap());
+
+        let email = sender
+            .email(&NotificationEvent::OrderConfirmed(test_order()))
+            .unwrap();
+
+        assert_eq!(email.subject, "Your order 1");
+        assert_eq!(email.text, "- Test ($1.00)\nTotal: $1.00");
+        assert!(email.html.is_none());
+    }
+
+    #[test]
+    fn sendgrid_confirmations_carry_the_receipt_twice() {
+        let order = test_order();
+        let receipt = ReceiptRenderer::new();
+
+        let confirmation = SendGridSender::simulated()
+            .email(&NotificationEvent::OrderConfirmed(order.clone()))
+            .unwrap();
+        let shipping = SendGridSender::simulated()
+            .email(&NotificationEvent::OrderShipped { id: order.id })
+            .unwrap();
+
+        assert_eq!(confirmation.text, receipt.render_text(&order));
+        assert_eq!(confirmation.html, Some(receipt.render_html(&order)));
+        assert_eq!(shipping.text, "Order #OrderId(1) is on its way!");
+        assert!(shipping.html.is_none());
+    }
+
+    #[test]
+    fn new_checks_the_key_and_the_from_address() {
+        assert!(SendGridSender::new("SG.key", "orders@shop.example").is_ok());
+        assert_eq!(
+            SendGridSender::new("  ", "orders@shop.example").unwrap_err(),
+            ConfigError::Missing("SendGrid API key")
+        );
+        assert_eq!(
+            SendGridSender::new("SG.key", "orders at shop").unwrap_err(),
+            ConfigError::Invalid {
+                setting: "SendGrid from-address",
+                value: "orders at shop".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn from_vars_needs_both_variables() {
+        let vars = HashMap::from([
+            ("SENDGRID_API_KEY", "SG.key"),
+            ("SENDGRID_FROM", "orders@shop.example"),
+        ]);
+        let var = |name: &str| vars.get(name).map(ToString::to_string);
+
+        let sender = SendGridSender::from_vars(var).unwrap();
+        let without_from =
+            SendGridSender::from_vars(|name| var(name).filter(|_| name != "SENDGRID_FROM"));
+
+        assert_eq!(sender.from_email(), "orders@shop.example");
+        assert_eq!(
+            without_from.unwrap_err(),
+            ConfigError::Missing("SENDGRID_FROM")
+        );
+    }
+
+    #[test]
+    fn debug_never_shows_the_api_key() {
+        let sender = SendGridSender::new("SG.very-secret-key", "orders@shop.example").unwrap();
+
+        let debug = format!("{sender:?}");
+
+        assert!(!debug.contains("very-secret"));
+        assert!(debug.contains("orders@shop.example"));
+    }
 }
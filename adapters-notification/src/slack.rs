@@ -0,0 +1,189 @@
+// =============================================================================
+// Slack Sender - Big Orders, Announced to Ops
+// =============================================================================
+//
+// This one doesn't tell the customer: it tells us. Each confirmed order goes
+// to a Slack channel, through one of Slack's incoming webhooks:
+//
+//     POST https://hooks.slack.com/services/T000/B000/XXXX
+//     {"text":"*Order #42* confirmed: 2 items, total *$179.98*"}
+//
+// Ops only cares about the big ones, so the sender can be given a minimum
+// total: smaller orders are skipped, and that's a success, not a failure.
+// Other events (cancellations, refunds, shipping) carry no total, and are
+// skipped too.
+//
+// Like SendGridSender, it only simulates the call by default: it prints. With
+// the `http` feature, it makes the real one. A non-2xx answer or no answer at
+// all is a NotificationFailed, after logging the original.
+//
+// Put it in a CompositeSender next to the customer's channel: the recipient is
+// ignored here, the channel is the webhook's.
+
+use domain::{HealthCheck, HealthStatus, Money, NotificationEvent, OrderError, Recipient, Sender};
+
+/// Notification sender announcing confirmed orders in a Slack channel.
+#[derive(Debug, Clone)]
+pub struct SlackSender {
+    webhook_url: String,
+    min_total: Option<Money>,
+}
+
+impl SlackSender {
+    /// Creates a sender announcing every confirmed order through the incoming
+    /// webhook at `webhook_url`.
+    #[must_use]
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self {
+            webhook_url: webhook_url.into(),
+            min_total: None,
+        }
+    }
+
+    /// Only announces orders totalling `min_total` or more.
+    #[must_use]
+    pub const fn with_min_total(mut self, min_total: Money) -> Self {
+        self.min_total = Some(min_total);
+        self
+    }
+
+    /// The text announcing `event`, or `None` if it isn't worth announcing.
+    fn message(&self, event: &NotificationEvent) -> Option<String> {
+        let NotificationEvent::OrderConfirmed(order) = event else {
+            return None;
+        };
+        if self.min_total.is_some_and(|min| order.total.0 < min.0) {
+            return None;
+        }
+        let items = match order.items.len() {
+            1 => "1 item".to_string(),
+            n => format!("{n} items"),
+        };
+        Some(format!(
+            "*Order #{}* confirmed: {items}, total *{}*",
+            order.id.0, order.total
+        ))
+    }
+
+    /// Posts `text` to the channel.
+    #[cfg(feature = "http")]
+    fn post(&self, text: &str) -> Result<(), OrderError> {
+        let response = reqwest::blocking::Client::new()
+            .post(&self.webhook_url)
+            .header("Content-Type", "application/json")
+            .body(payload(text))
+            .send()
+            .map_err(|e| {
+                println!("  [Slack] POST to the webhook failed: {e}");
+                OrderError::NotificationFailed(None)
+            })?;
+        let status = response.status();
+        if !status.is_success() {
+            println!("  [Slack] POST to the webhook -> {status}");
+            return Err(OrderError::NotificationFailed(None));
+        }
+        Ok(())
+    }
+
+    /// Pretends to post `text` to the channel.
+    #[cfg(not(feature = "http"))]
+    fn post(&self, text: &str) -> Result<(), OrderError> {
+        if self.webhook_url.is_empty() {
+            println!("  [Slack] No webhook URL: can't post '{text}'");
+            return Err(OrderError::NotificationFailed(None));
+        }
+        println!("  [Slack] Posting to the ops channel: '{text}'");
+        Ok(())
+    }
+}
+
+/// The JSON body Slack's incoming webhooks expect.
+#[cfg(feature = "http")]
+fn payload(text: &str) -> String {
+    serde_json::json!({ "text": text }).to_string()
+}
+
+impl Sender for SlackSender {
+    /// Announces a confirmed order at or above the minimum total. Anything
+    /// else is skipped, successfully.
+    fn notify(&self, _recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        match self.message(event) {
+            Some(text) => self.post(&text),
+            None => Ok(()),
+        }
+    }
+}
+
+impl HealthCheck for SlackSender {
+    /// Only the URL is checked: a test post would show up in the channel.
+    fn health(&self) -> HealthStatus {
+        if self.webhook_url.is_empty() {
+            HealthStatus::Unhealthy("no webhook URL".to_string())
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, LineItem, Order, OrderId};
+
+    fn confirmed(prices: &[u32]) -> NotificationEvent {
+        let items = prices
+            .iter()
+            .map(|&price| LineItem {
+                name: "Item".to_string(),
+                price: Money(price),
+                weight_grams: None,
+            })
+            .collect();
+        NotificationEvent::OrderConfirmed(Order::new(OrderId(42), items).unwrap())
+    }
+
+    #[test]
+    fn the_message_has_id_item_count_and_total() {
+        let sender = SlackSender::new("https://hooks.slack.com/services/T/B/X");
+
+        assert_eq!(
+            sender.message(&confirmed(&[4999, 12999])).unwrap(),
+            "*Order #42* confirmed: 2 items, total *$179.98*"
+        );
+        assert_eq!(
+            sender.message(&confirmed(&[500])).unwrap(),
+            "*Order #42* confirmed: 1 item, total *$5.00*"
+        );
+    }
+
+    #[test]
+    fn orders_below_the_minimum_are_skipped() {
+        let sender =
+            SlackSender::new("https://hooks.slack.com/services/T/B/X").with_min_total(Money(10000));
+
+        assert!(sender.message(&confirmed(&[9999])).is_none());
+        assert!(sender.message(&confirmed(&[10000])).is_some());
+        assert!(sender.message(&confirmed(&[10001])).is_some());
+    }
+
+    #[test]
+    fn events_without_a_total_are_skipped() {
+        let sender = SlackSender::new("https://hooks.slack.com/services/T/B/X");
+        let recipient =
+            Recipient::new("Ops", Contact::Email("ops@example.com".to_string())).unwrap();
+        let shipped = NotificationEvent::OrderShipped { id: OrderId(42) };
+
+        assert!(sender.message(&shipped).is_none());
+        // Skipped, so nothing is posted: not even to an empty URL
+        assert!(SlackSender::new("").notify(&recipient, &shipped).is_ok());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn the_payload_is_slack_json() {
+        assert_eq!(
+            payload("*Order #1* \"big\""),
+            r#"{"text":"*Order #1* \"big\""}"#
+        );
+    }
+}
@@ -0,0 +1,334 @@
+// =============================================================================
+// SMS Sender - Order Updates by Text Message, via Twilio
+// =============================================================================
+//
+// Some customers would rather get a text than an email. This adapter sends a
+// one-line SMS through Twilio's Messages API.
+//
+// Like SendGridSender, it only simulates the call by default: it logs the
+// text, to stdout or to a `Logger` when one is plugged in. With the `twilio`
+// feature, it makes the real one:
+//
+//     POST https://api.twilio.com/2010-04-01/Accounts/{sid}/Messages.json
+//          To=+15550001111&From=+15550009999&Body=Order #42 confirmed, total $179.98
+//
+// ONE SEGMENT:
+// ------------
+// A text longer than 160 characters is split and billed as several. Ours are
+// one line ("Order #42 confirmed, total $179.98"), and anything longer is cut
+// at 160 with an ellipsis: one message, one charge.
+//
+// E.164 ONLY:
+// -----------
+// The domain accepts a phone number with or without its `+`. Twilio doesn't:
+// it wants E.164, a `+`, the country code, then the number, 15 digits at most
+// and no leading zero. An email or a number without its country code is a
+// NotificationFailed, after saying why.
+
+use domain::{
+    Contact, HealthCheck, HealthStatus, Logger, NotificationEvent, OrderError, Recipient,
+    RequestContext, Sender,
+};
+use std::fmt;
+use std::sync::Arc;
+
+/// Longest text sent as a single SMS.
+pub const MAX_SMS_CHARS: usize = 160;
+
+/// Notification sender texting customers through Twilio.
+pub struct SmsSender {
+    account_sid: String,
+    auth_token: String,
+    from_number: String,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
+
+// Manual impl: the auth token must never end up in a log.
+impl fmt::Debug for SmsSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmsSender")
+            .field("account_sid", &self.account_sid)
+            .field("from_number", &self.from_number)
+            .field("logger", &self.logger.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SmsSender {
+    /// Creates a sender texting from `from_number` on the Twilio account
+    /// `account_sid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from_number` isn't an E.164 number: Twilio would refuse
+    /// every message.
+    #[must_use]
+    pub fn new(account_sid: &str, auth_token: &str, from_number: &str) -> Self {
+        assert!(
+            is_e164(from_number),
+            "SmsSender: {from_number:?} isn't an E.164 number"
+        );
+        Self {
+            account_sid: account_sid.to_string(),
+            auth_token: auth_token.to_string(),
+            from_number: from_number.to_string(),
+            logger: None,
+        }
+    }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    /// Sends `text` to `to` through Twilio's API.
+    #[cfg(feature = "twilio")]
+    fn send_sms(&self, to: &str, text: &str) -> Result<(), OrderError> {
+        let url = format!(
+            "https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json",
+            self.account_sid
+        );
+        let response = reqwest::blocking::Client::new()
+            .post(&url)
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&[("To", to), ("From", &self.from_number), ("Body", text)])
+            .send()
+            .map_err(|e| {
+                self.log(&format!("[Twilio API] Sending to {to} failed: {e}"));
+                OrderError::NotificationFailed(None)
+            })?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            self.log(&format!("[Twilio API] Sending to {to} -> {status} {body}"));
+            return Err(OrderError::NotificationFailed(None));
+        }
+        Ok(())
+    }
+
+    /// Pretends to send `text` to `to`. Like Twilio, refuses to without a
+    /// token.
+    #[cfg(not(feature = "twilio"))]
+    fn send_sms(&self, to: &str, text: &str) -> Result<(), OrderError> {
+        if self.auth_token.is_empty() {
+            self.log(&format!("[Twilio API] Sending to {to} -> 401 Unauthorized"));
+            return Err(OrderError::NotificationFailed(None));
+        }
+        self.log(&format!(
+            "[Twilio API] SMS from {} to {to}: '{text}'",
+            self.from_number
+        ));
+        Ok(())
+    }
+}
+
+impl Sender for SmsSender {
+    /// Texts the recipient a one-line summary of the event.
+    ///
+    /// Only an E.164 phone number will do: a recipient reachable by email or
+    /// webhook is a `NotificationFailed`.
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let Contact::Phone(number) = recipient.contact() else {
+            self.log(&format!(
+                "[Twilio API] {recipient} has no phone number: can't text them"
+            ));
+            return Err(OrderError::NotificationFailed(None));
+        };
+        if !is_e164(number) {
+            self.log(&format!(
+                "[Twilio API] {number} isn't E.164 (+ and country code): can't text it"
+            ));
+            return Err(OrderError::NotificationFailed(None));
+        }
+        self.send_sms(number, &truncate(&text(event), MAX_SMS_CHARS))
+    }
+}
+
+/// One line per kind of event, short enough for a single SMS.
+fn text(event: &NotificationEvent) -> String {
+    match event {
+        NotificationEvent::OrderConfirmed(order) => {
+            format!("Order #{} confirmed, total {}", order.id.0, order.total)
+        }
+        NotificationEvent::OrderCancelled { id } => format!("Order #{} cancelled", id.0),
+        NotificationEvent::RefundIssued { id, amount } => {
+            format!("Refund of {amount} issued for order #{}", id.0)
+        }
+        NotificationEvent::OrderShipped { id } => format!("Order #{} shipped", id.0),
+    }
+}
+
+/// `text`, cut to `max` characters, the last one an ellipsis, if it's longer.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// `+`, then 2 to 15 digits, the first not a zero.
+fn is_e164(number: &str) -> bool {
+    let Some(digits) = number.strip_prefix('+') else {
+        return false;
+    };
+    (2..=15).contains(&digits.len())
+        && !digits.starts_with('0')
+        && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+impl HealthCheck for SmsSender {
+    /// Fetches the Twilio account: the cheapest call proving that Twilio
+    /// answers and takes our credentials.
+    #[cfg(feature = "twilio")]
+    fn health(&self) -> HealthStatus {
+        let url = format!(
+            "https://api.twilio.com/2010-04-01/Accounts/{}.json",
+            self.account_sid
+        );
+        let response = reqwest::blocking::Client::new()
+            .get(&url)
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .send();
+        match response {
+            Err(e) => HealthStatus::Unhealthy(format!("Twilio unreachable: {e}")),
+            Ok(response) if response.status().is_success() => HealthStatus::Healthy,
+            Ok(response) if response.status().is_server_error() => {
+                HealthStatus::Degraded(format!("Twilio answered {}", response.status()))
+            }
+            Ok(response) => HealthStatus::Unhealthy(format!(
+                "Twilio refused the credentials ({})",
+                response.status()
+            )),
+        }
+    }
+
+    /// Like the simulated sends: unhealthy without a token.
+    #[cfg(not(feature = "twilio"))]
+    fn health(&self) -> HealthStatus {
+        if self.auth_token.is_empty() {
+            HealthStatus::Unhealthy("no Twilio auth token".to_string())
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_repository::CapturingLogger;
+    use domain::{LineItem, Money, Order, OrderId};
+    use std::panic;
+
+    fn sender() -> SmsSender {
+        SmsSender::new("AC123", "token", "+15550009999")
+    }
+
+    fn confirmed() -> NotificationEvent {
+        NotificationEvent::OrderConfirmed(
+            Order::new(
+                OrderId(42),
+                vec![LineItem {
+                    name: "Keyboard".to_string(),
+                    price: Money(17998),
+                    weight_grams: None,
+                }],
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn a_confirmation_is_one_short_line() {
+        assert_eq!(text(&confirmed()), "Order #42 confirmed, total $179.98");
+    }
+
+    // With the `twilio` feature, this one would text for real
+    #[cfg(not(feature = "twilio"))]
+    #[test]
+    fn sms_texts_a_phone_number() {
+        let recipient = Recipient::new("Ann", Contact::Phone("+15550001111".to_string())).unwrap();
+
+        assert!(sender().notify(&recipient, &confirmed()).is_ok());
+    }
+
+    #[test]
+    fn log_lines_go_to_the_logger() {
+        let logger = Arc::new(CapturingLogger::new());
+        let sender = sender().with_logger(logger.clone());
+        let local = Recipient::new("Ann", Contact::Phone("5550001111".to_string())).unwrap();
+
+        let _ = sender.notify(&local, &confirmed());
+
+        let messages: Vec<_> = logger.lines().into_iter().map(|l| l.message).collect();
+        assert_eq!(
+            messages,
+            ["[Twilio API] 5550001111 isn't E.164 (+ and country code): can't text it"]
+        );
+    }
+
+    #[test]
+    fn sms_rejects_email_and_local_numbers() {
+        let email = Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        let local = Recipient::new("Ann", Contact::Phone("5550001111".to_string())).unwrap();
+
+        assert!(matches!(
+            sender().notify(&email, &confirmed()),
+            Err(OrderError::NotificationFailed(_))
+        ));
+        assert!(matches!(
+            sender().notify(&local, &confirmed()),
+            Err(OrderError::NotificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn e164_wants_a_plus_a_country_code_and_15_digits_at_most() {
+        assert!(is_e164("+15550001111"));
+        assert!(is_e164("+441632960961"));
+        assert!(is_e164("+123456789012345"));
+
+        assert!(!is_e164("15550001111"));
+        assert!(!is_e164("+05550001111"));
+        assert!(!is_e164("+1234567890123456"));
+        assert!(!is_e164("+1 555 000 1111"));
+        assert!(!is_e164("+1"));
+        assert!(!is_e164("+"));
+    }
+
+    #[test]
+    fn text_is_cut_at_160_characters() {
+        let fits = "x".repeat(MAX_SMS_CHARS);
+        let too_long = "é".repeat(MAX_SMS_CHARS + 1);
+
+        assert_eq!(truncate(&fits, MAX_SMS_CHARS), fits);
+        let cut = truncate(&too_long, MAX_SMS_CHARS);
+        assert_eq!(cut.chars().count(), MAX_SMS_CHARS);
+        assert!(cut.ends_with("é…"));
+    }
+
+    #[test]
+    fn a_from_number_without_country_code_panics() {
+        let result = panic::catch_unwind(|| SmsSender::new("AC123", "token", "5550009999"));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn debug_hides_the_auth_token() {
+        let sender = SmsSender::new("AC123", "s3cret-token", "+15550009999");
+
+        assert!(!format!("{sender:?}").contains("s3cret-token"));
+    }
+}
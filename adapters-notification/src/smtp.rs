@@ -0,0 +1,488 @@
+// =============================================================================
+// SMTP Sender - Plain Email, for On-Prem Customers
+// =============================================================================
+//
+// SendGridSender needs SendGrid. Customers hosting the shop themselves have a
+// mail server instead, and SMTP is what every mail server speaks. This adapter
+// talks to it with `lettre`, behind the `smtp` feature, so the default build
+// pulls in no mail client:
+//
+//     MAILHOG_HOST=localhost cargo test -p adapters-notification --features smtp
+//
+// Without MAILHOG_HOST, the test that sends a real email returns early. MailHog
+// (https://github.com/mailhog/MailHog) catches everything sent to its port 1025
+// and shows it on http://localhost:8025.
+//
+// THE EMAIL:
+// ----------
+// One email per event, to the recipient's address:
+// - subject: the same as SendGrid's ("Order #OrderId(1) Confirmed"...)
+// - body: for a confirmation, the receipt twice (see receipt.rs), as HTML and
+//   as its text/plain alternative; for everything else, the console's wording
+//   as text/plain
+//
+// CONFIGURATION:
+// --------------
+// `SmtpConfig` in code, or `SmtpSender::from_env()` in production:
+//
+//     SMTP_HOST      mail.example.com          (required)
+//     SMTP_FROM      Shop <shop@example.com>   (required)
+//     SMTP_TLS       none | starttls | tls     (default: starttls)
+//     SMTP_PORT      587                       (default: 25, 587 or 465, per SMTP_TLS)
+//     SMTP_USERNAME  shop                      (optional, with SMTP_PASSWORD)
+//     SMTP_PASSWORD  ...
+//
+// ERROR TRANSLATION:
+// ------------------
+// lettre's errors (no connection, authentication refused, address rejected)
+// -> OrderError::NotificationFailed, with the lettre error attached as its
+// source, like the sqlx one in SqlxOrderRepository. A recipient without an
+// email address is a NotificationFailed too, with nothing attached.
+//
+// A failed send is also logged, to stdout or to a `Logger` when one is
+// plugged in. A bad configuration isn't: the error `new` returns has it all.
+//
+// SYNC PORT, BLOCKING TRANSPORT:
+// ------------------------------
+// Our ports are not async, so the adapter uses lettre's blocking transport,
+// one connection per email.
+
+use crate::console::message;
+use crate::receipt::ReceiptRenderer;
+use crate::sendgrid::subject;
+use domain::{
+    Contact, HealthCheck, HealthStatus, Logger, NotificationEvent, OrderError, Recipient,
+    RequestContext, Sender,
+};
+use lettre::message::header::ContentType;
+use lettre::message::{Mailbox, Message, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Address, SmtpTransport, Transport};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How the connection to the mail server is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsMode {
+    /// No TLS at all. Only for a server on the same machine (or MailHog).
+    None,
+    /// Plain connection upgraded with STARTTLS, usually on port 587.
+    StartTls,
+    /// TLS from the first byte, usually on port 465.
+    Implicit,
+}
+
+impl TlsMode {
+    /// The port mail servers usually listen on in this mode.
+    #[must_use]
+    pub const fn default_port(self) -> u16 {
+        match self {
+            Self::None => 25,
+            Self::StartTls => 587,
+            Self::Implicit => 465,
+        }
+    }
+}
+
+/// Where and how [`SmtpSender`] sends email.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    /// Username and password, when the server wants them.
+    pub credentials: Option<(String, String)>,
+    /// Who the email is from: `shop@example.com` or `Shop <shop@example.com>`.
+    pub from: String,
+    pub tls: TlsMode,
+}
+
+// Manual impl: the password must never end up in a log.
+impl fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field(
+                "username",
+                &self.credentials.as_ref().map(|(username, _)| username),
+            )
+            .field("from", &self.from)
+            .field("tls", &self.tls)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SmtpConfig {
+    /// Sends from `from` through `host`, on the usual port for `tls`, without
+    /// credentials.
+    #[must_use]
+    pub fn new(host: impl Into<String>, from: impl Into<String>, tls: TlsMode) -> Self {
+        Self {
+            host: host.into(),
+            port: tls.default_port(),
+            credentials: None,
+            from: from.into(),
+            tls,
+        }
+    }
+
+    /// Connects on `port` instead of the usual one.
+    #[must_use]
+    pub const fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Logs in as `username` before sending.
+    #[must_use]
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Reads the configuration from `var`, one `SMTP_*` variable at a time.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let required = |name: &str| var(name).ok_or_else(|| format!("{name} isn't set"));
+
+        let tls = match var("SMTP_TLS").as_deref() {
+            None | Some("starttls") => TlsMode::StartTls,
+            Some("tls") => TlsMode::Implicit,
+            Some("none") => TlsMode::None,
+            Some(other) => return Err(format!("SMTP_TLS={other}: expected none, starttls or tls")),
+        };
+        let mut config = Self::new(required("SMTP_HOST")?, required("SMTP_FROM")?, tls);
+        if let Some(port) = var("SMTP_PORT") {
+            config.port = port
+                .parse()
+                .map_err(|_| format!("SMTP_PORT={port}: not a port number"))?;
+        }
+        match (var("SMTP_USERNAME"), var("SMTP_PASSWORD")) {
+            (Some(username), Some(password)) => Ok(config.with_credentials(username, password)),
+            (None, None) => Ok(config),
+            _ => Err("SMTP_USERNAME and SMTP_PASSWORD go together".to_string()),
+        }
+    }
+}
+
+/// Notification sender emailing customers through an SMTP server.
+pub struct SmtpSender {
+    transport: SmtpTransport,
+    from: Mailbox,
+    host: String,
+    port: u16,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
+
+// Manual impl: show where the email goes, not the transport's internals.
+impl fmt::Debug for SmtpSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpSender")
+            .field("from", &self.from.to_string())
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("logger", &self.logger.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SmtpSender {
+    /// Creates a sender for `config`. Doesn't connect yet: each email opens
+    /// its own connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::NotificationFailed`] if the from-address is
+    /// malformed, or if TLS can't be set up for the host.
+    pub fn new(config: SmtpConfig) -> Result<Self, OrderError> {
+        let from: Mailbox = config
+            .from
+            .parse()
+            .map_err(OrderError::notification_failed)?;
+        let builder = match config.tls {
+            TlsMode::None => Ok(SmtpTransport::builder_dangerous(&config.host)),
+            TlsMode::StartTls => SmtpTransport::starttls_relay(&config.host),
+            TlsMode::Implicit => SmtpTransport::relay(&config.host),
+        }
+        .map_err(OrderError::notification_failed)?;
+        let mut builder = builder
+            .port(config.port)
+            .timeout(Some(Duration::from_secs(30)));
+        if let Some((username, password)) = config.credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            host: config.host,
+            port: config.port,
+            logger: None,
+        })
+    }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Creates a sender configured by the `SMTP_*` environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::NotificationFailed`] if a required variable is
+    /// missing or malformed, or for the same reasons as [`SmtpSender::new`].
+    pub fn from_env() -> Result<Self, OrderError> {
+        let config = SmtpConfig::from_vars(|name| std::env::var(name).ok())
+            .map_err(OrderError::notification_failed)?;
+        Self::new(config)
+    }
+
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    /// Builds the email telling `recipient` about `event`.
+    fn email(
+        &self,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<Message, OrderError> {
+        let Contact::Email(address) = recipient.contact() else {
+            return Err(OrderError::NotificationFailed(None));
+        };
+        let address: Address = address.parse().map_err(|e| {
+            self.log(&format!(
+                "[SMTP] Invalid recipient address {address:?}: {e}"
+            ));
+            OrderError::notification_failed(e)
+        })?;
+        let builder = Message::builder()
+            .from(self.from.clone())
+            .to(Mailbox::new(Some(recipient.name().to_string()), address))
+            .subject(subject(event));
+        let email = match event {
+            NotificationEvent::OrderConfirmed(order) => {
+                let receipt = ReceiptRenderer::new();
+                builder.multipart(MultiPart::alternative_plain_html(
+                    receipt.render_text(order),
+                    receipt.render_html(order),
+                ))
+            }
+            other => builder.header(ContentType::TEXT_PLAIN).body(message(other)),
+        };
+        email.map_err(|e| {
+            self.log(&format!("[SMTP] Can't build the email: {e}"));
+            OrderError::notification_failed(e)
+        })
+    }
+}
+
+impl Sender for SmtpSender {
+    /// Emails the recipient about the event.
+    ///
+    /// Only an email address will do: a recipient reachable by phone or
+    /// webhook is a `NotificationFailed`.
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let email = self.email(recipient, event)?;
+        self.transport.send(&email).map(|_| ()).map_err(|e| {
+            self.log(&format!(
+                "[SMTP] Sending to {recipient} through {}:{} failed: {e}",
+                self.host, self.port
+            ));
+            OrderError::notification_failed(e)
+        })
+    }
+}
+
+impl HealthCheck for SmtpSender {
+    /// Opens a connection to the server, and says NOOP.
+    fn health(&self) -> HealthStatus {
+        match self.transport.test_connection() {
+            Ok(true) => HealthStatus::Healthy,
+            Ok(false) => {
+                HealthStatus::Unhealthy(format!("{}:{} didn't answer NOOP", self.host, self.port))
+            }
+            Err(e) => HealthStatus::Unhealthy(format!("{}:{}: {e}", self.host, self.port)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_repository::CapturingLogger;
+    use domain::{LineItem, Money, Order, OrderId};
+    use std::collections::HashMap;
+
+    fn sender() -> SmtpSender {
+        SmtpSender::new(SmtpConfig::new(
+            "localhost",
+            "Shop <shop@example.com>",
+            TlsMode::None,
+        ))
+        .unwrap()
+    }
+
+    fn ann() -> Recipient {
+        Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap()
+    }
+
+    fn confirmed() -> NotificationEvent {
+        NotificationEvent::OrderConfirmed(
+            Order::new(
+                OrderId(7),
+                vec![LineItem {
+                    name: "Keyboard".to_string(),
+                    price: Money(12999),
+                    weight_grams: None,
+                }],
+            )
+            .unwrap(),
+        )
+    }
+
+    fn config_from(vars: &[(&str, &str)]) -> Result<SmtpConfig, String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect();
+        SmtpConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn a_confirmation_is_an_html_receipt_with_a_text_alternative() {
+        let email = sender().email(&ann(), &confirmed()).unwrap();
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("From: Shop <shop@example.com>\r\n"));
+        assert!(formatted.contains("To: Ann <ann@example.com>\r\n"));
+        assert!(formatted.contains("Subject: Order #OrderId(7) Confirmed\r\n"));
+        assert!(formatted.contains("Content-Type: multipart/alternative;"));
+        assert!(formatted.contains("Content-Type: text/plain; charset=utf-8\r\n"));
+        assert!(formatted.contains("Content-Type: text/html; charset=utf-8\r\n"));
+        assert!(formatted.contains("<td>Keyboard</td>"));
+        assert!(formatted.contains("$129.99"));
+    }
+
+    #[test]
+    fn other_events_use_the_console_wording() {
+        let email = sender()
+            .email(&ann(), &NotificationEvent::OrderShipped { id: OrderId(7) })
+            .unwrap();
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Subject: Order #OrderId(7) Shipped\r\n"));
+        assert!(formatted.contains("Order #OrderId(7) is on its way!"));
+    }
+
+    #[test]
+    fn smtp_rejects_non_email_recipient() {
+        let recipient = Recipient::new("Bob", Contact::Phone("+15550001111".to_string())).unwrap();
+
+        let result = sender().notify(&recipient, &confirmed());
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+    }
+
+    #[test]
+    fn a_failed_send_goes_to_the_logger() {
+        let logger = Arc::new(CapturingLogger::new());
+        // Nothing listens on port 1: the connection is refused at once
+        let sender = SmtpSender::new(
+            SmtpConfig::new("127.0.0.1", "Shop <shop@example.com>", TlsMode::None).with_port(1),
+        )
+        .unwrap()
+        .with_logger(logger.clone());
+
+        let result = sender.notify(&ann(), &confirmed());
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+        let lines = logger.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(
+            lines[0]
+                .message
+                .starts_with("[SMTP] Sending to Ann <ann@example.com> through 127.0.0.1:1 failed"),
+            "{}",
+            lines[0].message
+        );
+    }
+
+    #[test]
+    fn a_malformed_from_address_is_refused_with_the_lettre_error() {
+        use std::error::Error as _;
+
+        let Err(e) = SmtpSender::new(SmtpConfig::new("localhost", "shop", TlsMode::None)) else {
+            panic!("accepted a from-address without a domain");
+        };
+
+        assert!(matches!(e, OrderError::NotificationFailed(_)));
+        let source = e.source().expect("the lettre error is attached");
+        assert!(
+            source
+                .downcast_ref::<lettre::address::AddressError>()
+                .is_some(),
+            "{source}"
+        );
+    }
+
+    #[test]
+    fn configuration_comes_from_smtp_variables() {
+        let config = config_from(&[
+            ("SMTP_HOST", "mail.example.com"),
+            ("SMTP_FROM", "shop@example.com"),
+            ("SMTP_TLS", "tls"),
+            ("SMTP_USERNAME", "shop"),
+            ("SMTP_PASSWORD", "hunter2"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            config,
+            SmtpConfig::new("mail.example.com", "shop@example.com", TlsMode::Implicit)
+                .with_credentials("shop", "hunter2")
+        );
+        assert_eq!(config.port, 465);
+        assert!(!format!("{config:?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn bad_configuration_is_refused() {
+        let base = [("SMTP_HOST", "mail"), ("SMTP_FROM", "shop@example.com")];
+
+        assert!(config_from(&base[..1]).is_err());
+        assert!(config_from(&[base[0], base[1], ("SMTP_TLS", "ssl")]).is_err());
+        assert!(config_from(&[base[0], base[1], ("SMTP_PORT", "smtp")]).is_err());
+        assert!(config_from(&[base[0], base[1], ("SMTP_USERNAME", "shop")]).is_err());
+        assert_eq!(
+            config_from(&[base[0], base[1], ("SMTP_PORT", "2525")])
+                .unwrap()
+                .port,
+            2525
+        );
+    }
+
+    #[test]
+    fn mailhog_receives_the_confirmation() {
+        let Ok(host) = std::env::var("MAILHOG_HOST") else {
+            println!("MAILHOG_HOST isn't set: skipping");
+            return;
+        };
+        let sender = SmtpSender::new(
+            SmtpConfig::new(host, "shop@example.com", TlsMode::None).with_port(1025),
+        )
+        .unwrap();
+
+        sender.notify(&ann(), &confirmed()).unwrap();
+    }
+}
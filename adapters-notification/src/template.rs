@@ -0,0 +1,225 @@
+// =============================================================================
+// Message Templates - Wording Chosen per Deployment
+// =============================================================================
+//
+// Every sender words its confirmation in its own hard-coded English. Marketing
+// would rather choose: a template, given to the sender, replaces the default
+// wording of the order confirmation.
+//
+//     Thanks! Order #{order_id}: {item_count} items, {total}
+//     {items}
+//
+// Placeholders:
+// - {order_id}    the order's number: 42
+// - {total}       the order's total: $179.98
+// - {item_count}  how many line items: 2
+// - {items}       one bullet per item: "- Keyboard ($129.99)", one per line
+//
+// `{{` and `}}` are a literal `{` and `}`.
+//
+// A typo in a placeholder (`{totl}`) is caught by `MessageTemplate::parse`,
+// when the deployment starts, not by the first customer who orders.
+//
+// Only confirmations carry an order to fill a template with: cancellations,
+// refunds and shipping keep each sender's default wording.
+
+use domain::{NotificationEvent, Order, OrderError};
+use std::fmt::{self, Write as _};
+
+/// Why a template can't be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// `{name}` isn't one of the placeholders.
+    UnknownPlaceholder(String),
+    /// The `{` at this byte offset is never closed.
+    UnclosedPlaceholder { at: usize },
+    /// The `}` at this byte offset closes nothing (write `}}` for a literal one).
+    UnmatchedBrace { at: usize },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPlaceholder(name) => write!(f, "UnknownPlaceholder({{{name}}})"),
+            Self::UnclosedPlaceholder { at } => write!(f, "UnclosedPlaceholder(at={at})"),
+            Self::UnmatchedBrace { at } => write!(f, "UnmatchedBrace(at={at})"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// What a placeholder stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    OrderId,
+    Total,
+    ItemCount,
+    Items,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Field),
+}
+
+/// A parsed message template, filled in from an [`Order`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTemplate {
+    segments: Vec<Segment>,
+}
+
+impl MessageTemplate {
+    /// Parses `text`, checking every placeholder.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TemplateError::UnknownPlaceholder`] for a placeholder that
+    /// isn't listed in the module docs, and the other variants for braces
+    /// that don't pair up.
+    pub fn parse(text: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.char_indices().peekable();
+        while let Some((at, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, next)| next == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|&(_, next)| next == '}').is_some() => literal.push('}'),
+                '{' => {
+                    let name: String = chars
+                        .by_ref()
+                        .map(|(_, c)| c)
+                        .take_while(|&c| c != '}')
+                        .collect();
+                    if !text[at..].contains('}') {
+                        return Err(TemplateError::UnclosedPlaceholder { at });
+                    }
+                    let field = match name.as_str() {
+                        "order_id" => Field::OrderId,
+                        "total" => Field::Total,
+                        "item_count" => Field::ItemCount,
+                        "items" => Field::Items,
+                        _ => return Err(TemplateError::UnknownPlaceholder(name)),
+                    };
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(field));
+                }
+                '}' => return Err(TemplateError::UnmatchedBrace { at }),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Fills the template in from `order`.
+    ///
+    /// # Errors
+    ///
+    /// None today: `parse` already checked every placeholder, and each of
+    /// them has a value for any order. The `Result` is there for placeholders
+    /// an order may have no value for.
+    pub fn render(&self, order: &Order) -> Result<String, TemplateError> {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            let _ = match segment {
+                Segment::Literal(text) => write!(rendered, "{text}"),
+                Segment::Placeholder(Field::OrderId) => write!(rendered, "{}", order.id.0),
+                Segment::Placeholder(Field::Total) => write!(rendered, "{}", order.total),
+                Segment::Placeholder(Field::ItemCount) => write!(rendered, "{}", order.items.len()),
+                Segment::Placeholder(Field::Items) => {
+                    let bullets: Vec<String> = order
+                        .items
+                        .iter()
+                        .map(|item| format!("- {} ({})", item.name, item.price))
+                        .collect();
+                    write!(rendered, "{}", bullets.join("\n"))
+                }
+            };
+        }
+        Ok(rendered)
+    }
+}
+
+/// How a sender words `event`: `template` filled in from a confirmation's
+/// order, or the sender's `default` wording for everything else.
+pub(crate) fn wording(
+    template: Option<&MessageTemplate>,
+    event: &NotificationEvent,
+    default: impl FnOnce(&NotificationEvent) -> String,
+) -> Result<String, OrderError> {
+    match (template, event) {
+        (Some(template), NotificationEvent::OrderConfirmed(order)) => {
+            template.render(order).map_err(|e| {
+                println!("  [Template] Can't word the confirmation: {e}");
+                OrderError::NotificationFailed(None)
+            })
+        }
+        _ => Ok(default(event)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{LineItem, Money, OrderId};
+
+    fn order() -> Order {
+        Order::new(
+            OrderId(42),
+            vec![
+                LineItem {
+                    name: "Book".to_string(),
+                    price: Money(4999),
+                    weight_grams: None,
+                },
+                LineItem {
+                    name: "Keyboard".to_string(),
+                    price: Money(12999),
+                    weight_grams: None,
+                },
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn every_placeholder_is_filled_in() {
+        let template =
+            MessageTemplate::parse("Order #{order_id}: {item_count} items, {total}\n{items}")
+                .unwrap();
+
+        assert_eq!(
+            template.render(&order()).unwrap(),
+            "Order #42: 2 items, $179.98\n- Book ($49.99)\n- Keyboard ($129.99)"
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        let template = MessageTemplate::parse("{{order_id}} is {{{order_id}}}").unwrap();
+
+        assert_eq!(template.render(&order()).unwrap(), "{order_id} is {42}");
+    }
+
+    #[test]
+    fn unknown_placeholders_fail_at_parse_time() {
+        assert_eq!(
+            MessageTemplate::parse("Total: {totl}"),
+            Err(TemplateError::UnknownPlaceholder("totl".to_string()))
+        );
+        assert_eq!(
+            MessageTemplate::parse("Total: {total"),
+            Err(TemplateError::UnclosedPlaceholder { at: 7 })
+        );
+        assert_eq!(
+            MessageTemplate::parse("Total} {total}"),
+            Err(TemplateError::UnmatchedBrace { at: 5 })
+        );
+    }
+}
@@ -0,0 +1,402 @@
+// =============================================================================
+// Webhook Sender - An HTTP POST per Order Event
+// =============================================================================
+//
+// Some customers don't want an email: they want their own system told, with
+// an HTTP POST to their endpoint. This adapter posts each event there as JSON,
+// behind the `http` feature, so the default build pulls in no HTTP client:
+//
+//     cargo test -p adapters-notification --features http
+//
+// The tests post to a throwaway server on 127.0.0.1: no network needed.
+//
+// THE PAYLOAD:
+// ------------
+// One JSON object per event, with an `event` field saying which:
+//
+//     {"event":"order.confirmed","order_id":1,
+//      "order":{"id":1,"items":[{"name":"Book","price_cents":4999}],
+//               "total_cents":4999,"status":"Paid","transaction_id":"ch_..."}}
+//     {"event":"order.cancelled","order_id":1}
+//     {"event":"refund.issued","order_id":1,"amount_cents":250}
+//     {"event":"order.shipped","order_id":1}
+//
+// The domain stays serde-free: the payload has its own types here, filled from
+// the order, the way OrderRow is in the repository adapters. Renaming a field
+// of Order doesn't silently change what our customers receive.
+//
+// THE SIGNATURE:
+// --------------
+// Anybody can POST to the customer's endpoint. To tell ours apart, each body is
+// signed with a secret we share with the customer, GitHub-style:
+//
+//     X-Signature-256: sha256=<hex of HMAC-SHA256(secret, body)>
+//
+// The receiver computes the same over the raw body, and compares.
+//
+// ERROR TRANSLATION:
+// ------------------
+// No connection, no answer in time, or an answer other than 2xx
+// -> OrderError::NotificationFailed, after logging the original.
+
+use domain::{HealthCheck, HealthStatus, NotificationEvent, Order, OrderError, Recipient, Sender};
+use hmac::{Hmac, Mac};
+use reqwest::blocking::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt::{self, Write as _};
+use std::time::Duration;
+
+/// The header carrying the body's signature.
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// The JSON body posted for one event.
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    event: &'static str,
+    order_id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    order: Option<OrderPayload<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount_cents: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct OrderPayload<'a> {
+    id: u32,
+    items: Vec<ItemPayload<'a>>,
+    total_cents: u32,
+    status: String,
+    transaction_id: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct ItemPayload<'a> {
+    name: &'a str,
+    price_cents: u32,
+}
+
+impl<'a> Payload<'a> {
+    fn of(event: &'a NotificationEvent) -> Self {
+        let (name, order, amount_cents) = match event {
+            NotificationEvent::OrderConfirmed(order) => {
+                ("order.confirmed", Some(OrderPayload::of(order)), None)
+            }
+            NotificationEvent::OrderCancelled { .. } => ("order.cancelled", None, None),
+            NotificationEvent::RefundIssued { amount, .. } => {
+                ("refund.issued", None, Some(amount.0))
+            }
+            NotificationEvent::OrderShipped { .. } => ("order.shipped", None, None),
+        };
+        Self {
+            event: name,
+            order_id: event.order_id().0,
+            order,
+            amount_cents,
+        }
+    }
+}
+
+impl<'a> OrderPayload<'a> {
+    fn of(order: &'a Order) -> Self {
+        Self {
+            id: order.id.0,
+            items: order
+                .items
+                .iter()
+                .map(|item| ItemPayload {
+                    name: &item.name,
+                    price_cents: item.price.0,
+                })
+                .collect(),
+            total_cents: order.total.0,
+            status: order.status.to_string(),
+            transaction_id: order.transaction_id.as_deref(),
+        }
+    }
+}
+
+/// The JSON body posted for `event`.
+fn body(event: &NotificationEvent) -> String {
+    serde_json::to_string(&Payload::of(event)).expect("the payload should serialize")
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` under `secret`.
+fn signature(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any size");
+    mac.update(body);
+    let mut signature = "sha256=".to_string();
+    for byte in mac.finalize().into_bytes() {
+        let _ = write!(signature, "{byte:02x}");
+    }
+    signature
+}
+
+/// Notification sender POSTing each event as signed JSON to one URL.
+pub struct WebhookSender {
+    http: Client,
+    url: String,
+    secret: Vec<u8>,
+    bearer_token: Option<String>,
+}
+
+// Manual impl: the secret and the token must never end up in a log.
+impl fmt::Debug for WebhookSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookSender")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebhookSender {
+    /// Creates a sender posting to `url`, signing with `secret`, and giving up
+    /// after 10 seconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS backend can't be initialized.
+    #[must_use]
+    pub fn new(url: impl Into<String>, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            http: Self::client(Duration::from_secs(10)),
+            url: url.into(),
+            secret: secret.into(),
+            bearer_token: None,
+        }
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every POST.
+    #[must_use]
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Gives up on a POST after `timeout` instead of 10 seconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS backend can't be initialized.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.http = Self::client(timeout);
+        self
+    }
+
+    /// The URL events are posted to.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn client(timeout: Duration) -> Client {
+        Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("the TLS backend should initialize")
+    }
+}
+
+impl Sender for WebhookSender {
+    /// POSTs the event to the URL, whoever the recipient is: the endpoint
+    /// belongs to the shop's customer, not to the person who ordered.
+    fn notify(&self, _recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let body = body(event);
+        let mut request = self
+            .http
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, signature(&self.secret, body.as_bytes()));
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.body(body).send().map_err(|e| {
+            println!("  [Webhook] POST {} failed: {e}", self.url);
+            OrderError::NotificationFailed(None)
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            println!("  [Webhook] POST {} -> {status}", self.url);
+            return Err(OrderError::NotificationFailed(None));
+        }
+        Ok(())
+    }
+}
+
+impl HealthCheck for WebhookSender {
+    /// Only the URL is checked: the endpoint is somebody else's, and a test
+    /// POST would be an event to them.
+    fn health(&self) -> HealthStatus {
+        if self.url.starts_with("https://") || self.url.starts_with("http://") {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Unhealthy(format!("{} isn't an HTTP URL", self.url))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, LineItem, Money, OrderId};
+    use serde_json::{Value, json};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+
+    fn ann() -> Recipient {
+        Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap()
+    }
+
+    fn confirmed() -> NotificationEvent {
+        let mut order = Order::new(
+            OrderId(1),
+            vec![LineItem {
+                name: "Book".to_string(),
+                price: Money(4999),
+                weight_grams: None,
+            }],
+        )
+        .unwrap();
+        order.mark_paid(std::time::UNIX_EPOCH).unwrap();
+        order.transaction_id = Some("ch_1".to_string());
+        NotificationEvent::OrderConfirmed(order)
+    }
+
+    /// One request, as the test server received it.
+    struct Received {
+        head: String,
+        body: String,
+    }
+
+    /// Serves one request on 127.0.0.1 with `status`, and hands it over.
+    fn serve_once(status: &'static str) -> (String, Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks/orders", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+            let _ = tx.send(Received {
+                head,
+                body: String::from_utf8(body).unwrap(),
+            });
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn a_confirmation_carries_the_order() {
+        let payload: Value = serde_json::from_str(&body(&confirmed())).unwrap();
+
+        assert_eq!(
+            payload,
+            json!({
+                "event": "order.confirmed",
+                "order_id": 1,
+                "order": {
+                    "id": 1,
+                    "items": [{"name": "Book", "price_cents": 4999}],
+                    "total_cents": 4999,
+                    "status": "Paid",
+                    "transaction_id": "ch_1",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn other_events_carry_the_order_id() {
+        let id = OrderId(4);
+
+        assert_eq!(
+            body(&NotificationEvent::RefundIssued {
+                id,
+                amount: Money(250)
+            }),
+            r#"{"event":"refund.issued","order_id":4,"amount_cents":250}"#
+        );
+        assert_eq!(
+            body(&NotificationEvent::OrderShipped { id }),
+            r#"{"event":"order.shipped","order_id":4}"#
+        );
+    }
+
+    #[test]
+    fn signature_is_hmac_sha256_in_hex() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            signature(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn the_post_is_signed_and_authorized() {
+        let (url, received) = serve_once("204 No Content");
+        let sender = WebhookSender::new(url, "s3cret").with_bearer_token("tok_123");
+
+        sender.notify(&ann(), &confirmed()).unwrap();
+
+        let received = received.recv().unwrap();
+        let head = received.head.to_ascii_lowercase();
+        assert!(head.starts_with("post /hooks/orders http/1.1"));
+        assert!(head.contains("authorization: bearer tok_123\r\n"));
+        assert!(head.contains("content-type: application/json\r\n"));
+        let expected = signature(b"s3cret", received.body.as_bytes());
+        assert!(head.contains(&format!("x-signature-256: {expected}\r\n")));
+        assert_eq!(received.body, body(&confirmed()));
+        assert!(!format!("{sender:?}").contains("s3cret"));
+    }
+
+    #[test]
+    fn a_non_2xx_answer_fails() {
+        let (url, _received) = serve_once("500 Internal Server Error");
+        let sender = WebhookSender::new(url, "s3cret");
+
+        let result = sender.notify(&ann(), &confirmed());
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+    }
+
+    #[test]
+    fn no_server_fails() {
+        // Bound, then dropped: nobody listens there any more
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let sender = WebhookSender::new(format!("http://127.0.0.1:{port}/"), "s3cret")
+            .with_timeout(Duration::from_secs(2));
+
+        let result = sender.notify(&ann(), &confirmed());
+
+        assert!(matches!(result, Err(OrderError::NotificationFailed(_))));
+    }
+}
@@ -0,0 +1,332 @@
+// =============================================================================
+// Auditing Payment Gateway - Every Charge Attempt, on the Record
+// =============================================================================
+//
+// Finance wants to know about every attempt to take money: the ones that
+// worked, and the ones that didn't. Whatever the provider, and whatever the
+// use case that asked. A decorator sees all of them:
+//
+//     OrderService -> AuditingPaymentGateway -> RetryingPaymentGateway -> Stripe
+//
+// One line per `charge` or `authorize`, appended to any `std::io::Write`: a
+// file opened in append mode in production, a `Vec<u8>` in tests. Each line
+// is a JSON object, with its fields always in this order:
+//
+//     {"timestamp_ms":1700000000000,"correlation_id":"req-42","gateway":"stripe",
+//      "operation":"charge","amount_cents":1000,"outcome":"succeeded",
+//      "transaction_id":"ch_..."}
+//
+// A failure has `"outcome":"failed"` and a `"reason"` ("Declined"...) instead
+// of the transaction ID. A call made on behalf of no request has a null
+// correlation ID.
+//
+// Placed outside the retrying gateway, it records one line per checkout;
+// inside, one per try. Finance usually wants the tries.
+//
+// The record is written after the payment, so a write error can't undo the
+// charge: it's logged, and the caller still gets the provider's answer.
+
+use domain::{
+    AuthorizationId, ChargeMetadata, Clock, HealthCheck, HealthStatus, IdempotencyKey, Money,
+    OrderError, PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::fmt::{self, Write as _};
+use std::io::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::UNIX_EPOCH;
+
+/// Payment gateway decorator appending one JSON line per charge attempt.
+pub struct AuditingPaymentGateway<P, W> {
+    inner: P,
+    gateway: String,
+    writer: Mutex<W>,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+// Manual impl: the clock is a trait object, and the writer needn't be Debug.
+impl<P: fmt::Debug, W> fmt::Debug for AuditingPaymentGateway<P, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditingPaymentGateway")
+            .field("inner", &self.inner)
+            .field("gateway", &self.gateway)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: PaymentGateway, W: Write> AuditingPaymentGateway<P, W> {
+    /// Wraps `inner`, recorded as `gateway` ("stripe"...), writing to
+    /// `writer`.
+    #[must_use]
+    pub fn new(
+        inner: P,
+        gateway: impl Into<String>,
+        writer: W,
+        clock: Arc<dyn Clock + Send + Sync>,
+    ) -> Self {
+        Self {
+            inner,
+            gateway: gateway.into(),
+            writer: Mutex::new(writer),
+            clock,
+        }
+    }
+
+    /// The wrapped gateway.
+    #[must_use]
+    pub const fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Gives the writer back, with every line written so far.
+    #[must_use]
+    pub fn into_writer(self) -> W {
+        // A poisoned lock only means another thread panicked; the lines are still fine
+        self.writer
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Writes the record of one attempt.
+    fn record(
+        &self,
+        ctx: &RequestContext,
+        operation: &str,
+        amount: Money,
+        outcome: Result<&str, &OrderError>,
+    ) {
+        let millis = self
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis());
+        let correlation_id = if ctx.correlation_id.is_empty() {
+            "null".to_string()
+        } else {
+            json_string(&ctx.correlation_id)
+        };
+        let mut line = format!(
+            r#"{{"timestamp_ms":{millis},"correlation_id":{correlation_id},"gateway":{},"operation":"{operation}","amount_cents":{}"#,
+            json_string(&self.gateway),
+            amount.0
+        );
+        let _ = match outcome {
+            Ok(id) => write!(
+                line,
+                r#","outcome":"succeeded","transaction_id":{}}}"#,
+                json_string(id)
+            ),
+            Err(OrderError::PaymentFailed(reason)) => {
+                write!(line, r#","outcome":"failed","reason":"{reason}"}}"#)
+            }
+            Err(e) => write!(
+                line,
+                r#","outcome":"failed","reason":{}}}"#,
+                json_string(&e.to_string())
+            ),
+        };
+        line.push('\n');
+
+        // A poisoned lock only means another thread panicked; the writer is still fine
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = writer
+            .write_all(line.as_bytes())
+            .and_then(|()| writer.flush())
+        {
+            println!("  [Audit] Can't write the payment record ({e}): {line}");
+        }
+    }
+}
+
+/// Quotes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl<P: PaymentGateway, W: Write> PaymentGateway for AuditingPaymentGateway<P, W> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let result = self.inner.charge_with_ctx(ctx, amount, idempotency_key);
+        let outcome = result
+            .as_ref()
+            .map(|receipt| receipt.transaction_id.as_str());
+        self.record(ctx, "charge", amount, outcome);
+        result
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let result = self
+            .inner
+            .charge_with_metadata(ctx, amount, idempotency_key, metadata);
+        let outcome = result
+            .as_ref()
+            .map(|receipt| receipt.transaction_id.as_str());
+        self.record(ctx, "charge", amount, outcome);
+        result
+    }
+
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        let result = self.inner.authorize_with_ctx(ctx, amount, idempotency_key);
+        let outcome = result.as_ref().map(|auth| auth.0.as_str());
+        self.record(ctx, "authorize", amount, outcome);
+        result
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.inner.capture_with_ctx(ctx, auth)
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.inner.void_with_ctx(ctx, auth)
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.inner.refund_with_ctx(ctx, transaction_id, amount)
+    }
+}
+
+impl<P: HealthCheck, W> HealthCheck for AuditingPaymentGateway<P, W> {
+    // Only as healthy as the gateway underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FailingPaymentGateway, MockPaymentGateway};
+    use adapters_repository::FixedClock;
+    use domain::PaymentFailureReason;
+    use serde_json::{Value, json};
+    use std::time::Duration;
+
+    fn clock() -> Arc<FixedClock> {
+        Arc::new(FixedClock::new(
+            UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+        ))
+    }
+
+    fn records(written: &[u8]) -> Vec<Value> {
+        String::from_utf8(written.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn a_successful_charge_is_recorded() {
+        let gateway =
+            AuditingPaymentGateway::new(MockPaymentGateway::new(), "mock", Vec::new(), clock());
+
+        gateway
+            .charge_with_ctx(&RequestContext::new("req-42"), Money(1000), None)
+            .unwrap();
+
+        assert_eq!(
+            records(&gateway.into_writer()),
+            vec![json!({
+                "timestamp_ms": 1_700_000_000_123_u64,
+                "correlation_id": "req-42",
+                "gateway": "mock",
+                "operation": "charge",
+                "amount_cents": 1000,
+                "outcome": "succeeded",
+                "transaction_id": "mock_txn_1",
+            })]
+        );
+    }
+
+    #[test]
+    fn a_failed_attempt_is_recorded_too() {
+        let gateway = AuditingPaymentGateway::new(
+            FailingPaymentGateway::new(PaymentFailureReason::Declined),
+            "backup \"eu\"",
+            Vec::new(),
+            clock(),
+        );
+
+        assert!(gateway.charge(Money(500), None).is_err());
+        assert!(gateway.authorize(Money(700), None).is_err());
+
+        let records = records(&gateway.into_writer());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["outcome"], "failed");
+        assert_eq!(records[0]["reason"], "Declined");
+        assert_eq!(records[0]["correlation_id"], Value::Null);
+        assert_eq!(records[0]["gateway"], "backup \"eu\"");
+        assert!(records[0].get("transaction_id").is_none());
+        assert_eq!(records[1]["operation"], "authorize");
+        assert_eq!(records[1]["amount_cents"], 700);
+    }
+}
@@ -0,0 +1,382 @@
+// =============================================================================
+// Circuit Breaker Payment Gateway - Stop Calling a Provider That's Down
+// =============================================================================
+//
+// When Stripe is down, every checkout still calls it, waits for the timeout,
+// and fails. The customers wait for nothing, and the provider, trying to come
+// back up, gets hammered by our retries. A circuit breaker cuts the calls off
+// for a while instead:
+//
+//            N transient failures in a row
+//     CLOSED ─────────────────────────────► OPEN ◄──────────┐
+//        ▲                                   │              │ probe fails
+//        │                                   │ cool-down    │
+//        │         probe succeeds            ▼ elapsed      │
+//        └──────────────────────────────── HALF-OPEN ───────┘
+//
+// - Closed: calls go through. Transient failures (Unavailable, Timeout) are
+//   counted; anything else, success or decline, proves the provider answers
+//   and resets the count.
+// - Open: calls fail at once with PaymentFailed(Unavailable), the provider
+//   is left alone.
+// - Half-open: once the cool-down is over, ONE call goes through, the probe.
+//   Its outcome decides: closed again, or open for another cool-down. Other
+//   calls arriving meanwhile fail fast.
+//
+// The state only changes on a call: an open breaker whose cool-down is over
+// still reports Open until the next call becomes the probe.
+//
+// Wrap it OUTSIDE the retrying gateway: retries are for a bad second, the
+// breaker for a bad ten minutes, and it should see each checkout once.
+//
+//     OrderService -> CircuitBreakerPaymentGateway -> RetryingPaymentGateway -> Stripe
+//
+// Time comes from the `Clock` port, so tests skip the cool-down with a
+// FixedClock.
+
+use domain::{
+    AuthorizationId, ChargeMetadata, Clock, HealthCheck, HealthStatus, IdempotencyKey, Money,
+    OrderError, PaymentFailureReason, PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// When the breaker opens, and for how long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerPolicy {
+    /// Transient failures in a row that open the breaker. Zero counts as one.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before letting a probe through.
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitBreakerPolicy {
+    /// Opens after 5 failures in a row, for 30 seconds.
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Where the breaker stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through.
+    Closed,
+    /// Calls fail fast.
+    Open,
+    /// One probe call is going through; the others fail fast.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum Breaker {
+    Closed { failures: u32 },
+    Open { since: SystemTime },
+    HalfOpen,
+}
+
+/// Payment gateway decorator failing fast while the provider is down.
+pub struct CircuitBreakerPaymentGateway<P> {
+    inner: P,
+    policy: CircuitBreakerPolicy,
+    clock: Arc<dyn Clock + Send + Sync>,
+    breaker: Mutex<Breaker>,
+}
+
+// Manual impl: the clock is a trait object with no Debug of its own.
+impl<P: fmt::Debug> fmt::Debug for CircuitBreakerPaymentGateway<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreakerPaymentGateway")
+            .field("inner", &self.inner)
+            .field("policy", &self.policy)
+            .field("breaker", &self.breaker)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: PaymentGateway> CircuitBreakerPaymentGateway<P> {
+    /// Wraps `inner`, starting closed.
+    #[must_use]
+    pub fn new(
+        inner: P,
+        policy: CircuitBreakerPolicy,
+        clock: Arc<dyn Clock + Send + Sync>,
+    ) -> Self {
+        Self {
+            inner,
+            policy,
+            clock,
+            breaker: Mutex::new(Breaker::Closed { failures: 0 }),
+        }
+    }
+
+    /// Where the breaker stands, for dashboards and health checks.
+    #[must_use]
+    pub fn state(&self) -> CircuitState {
+        match *self.breaker() {
+            Breaker::Closed { .. } => CircuitState::Closed,
+            Breaker::Open { .. } => CircuitState::Open,
+            Breaker::HalfOpen => CircuitState::HalfOpen,
+        }
+    }
+
+    /// The wrapped gateway.
+    #[must_use]
+    pub const fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn breaker(&self) -> MutexGuard<'_, Breaker> {
+        // A poisoned lock only means another thread panicked; the state is still fine
+        self.breaker.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Makes the call if the breaker lets it through, and learns from its
+    /// outcome.
+    fn guarded<T>(&self, call: impl FnOnce(&P) -> Result<T, OrderError>) -> Result<T, OrderError> {
+        {
+            let mut breaker = self.breaker();
+            match *breaker {
+                Breaker::Closed { .. } => {}
+                Breaker::Open { since } => {
+                    let cooled_down = self
+                        .clock
+                        .now()
+                        .duration_since(since)
+                        .is_ok_and(|open_for| open_for >= self.policy.cool_down);
+                    if !cooled_down {
+                        return Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable));
+                    }
+                    // This call is the probe
+                    *breaker = Breaker::HalfOpen;
+                }
+                Breaker::HalfOpen => {
+                    return Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable));
+                }
+            }
+        }
+
+        // The lock is released: other calls don't wait for the provider
+        let result = call(&self.inner);
+
+        let mut breaker = self.breaker();
+        let transient = result.as_ref().is_err_and(OrderError::is_transient);
+        *breaker = match (&*breaker, transient) {
+            (_, false) => Breaker::Closed { failures: 0 },
+            (Breaker::Closed { failures }, true)
+                if failures + 1 < self.policy.failure_threshold =>
+            {
+                Breaker::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, true) => Breaker::Open {
+                since: self.clock.now(),
+            },
+        };
+        result
+    }
+}
+
+impl<P: PaymentGateway> PaymentGateway for CircuitBreakerPaymentGateway<P> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.guarded(|inner| inner.charge_with_ctx(ctx, amount, idempotency_key))
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.guarded(|inner| inner.charge_with_metadata(ctx, amount, idempotency_key, metadata))
+    }
+
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.guarded(|inner| inner.authorize_with_ctx(ctx, amount, idempotency_key))
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.guarded(|inner| inner.capture_with_ctx(ctx, auth))
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.guarded(|inner| inner.void_with_ctx(ctx, auth))
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.guarded(|inner| inner.refund_with_ctx(ctx, transaction_id, amount))
+    }
+}
+
+impl<P: PaymentGateway + HealthCheck> HealthCheck for CircuitBreakerPaymentGateway<P> {
+    /// Open is unhealthy (every call fails fast), half-open degraded (a probe
+    /// is finding out); closed, the inner gateway decides.
+    fn health(&self) -> HealthStatus {
+        match self.state() {
+            CircuitState::Open => HealthStatus::Unhealthy("circuit open".to_string()),
+            CircuitState::HalfOpen => HealthStatus::Degraded("circuit half-open".to_string()),
+            CircuitState::Closed => self.inner.health(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ScriptedPaymentGateway, ScriptedResponse};
+    use adapters_repository::FixedClock;
+    use std::time::UNIX_EPOCH;
+
+    const COOL_DOWN: Duration = Duration::from_secs(30);
+
+    fn breaker(
+        script: Vec<ScriptedResponse>,
+    ) -> (
+        CircuitBreakerPaymentGateway<ScriptedPaymentGateway>,
+        Arc<FixedClock>,
+    ) {
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH));
+        let policy = CircuitBreakerPolicy {
+            failure_threshold: 2,
+            cool_down: COOL_DOWN,
+        };
+        let gateway = CircuitBreakerPaymentGateway::new(
+            ScriptedPaymentGateway::new(script),
+            policy,
+            Arc::clone(&clock) as _,
+        );
+        (gateway, clock)
+    }
+
+    fn is_unavailable<T>(result: Result<T, OrderError>) -> bool {
+        matches!(
+            result,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable))
+        )
+    }
+
+    #[test]
+    fn the_breaker_goes_through_its_whole_cycle() {
+        let unavailable = ScriptedResponse::Fail(PaymentFailureReason::Unavailable);
+        let (gateway, clock) = breaker(vec![
+            unavailable,
+            unavailable,
+            ScriptedResponse::Fail(PaymentFailureReason::Timeout),
+            ScriptedResponse::Succeed,
+            ScriptedResponse::Succeed,
+        ]);
+
+        // Two transient failures in a row: open
+        assert!(gateway.charge(Money(100), None).is_err());
+        assert_eq!(gateway.state(), CircuitState::Closed);
+        assert!(gateway.charge(Money(100), None).is_err());
+        assert_eq!(gateway.state(), CircuitState::Open);
+
+        // Open: fails fast, the provider isn't called
+        assert!(is_unavailable(gateway.charge(Money(100), None)));
+        assert_eq!(gateway.inner().calls().len(), 2);
+
+        // Cool-down over: the probe times out, open again
+        clock.advance(COOL_DOWN);
+        assert!(gateway.charge(Money(100), None).is_err());
+        assert_eq!(gateway.state(), CircuitState::Open);
+        assert!(is_unavailable(gateway.charge(Money(100), None)));
+
+        // Next cool-down: the probe succeeds, closed
+        clock.advance(COOL_DOWN);
+        gateway.charge(Money(100), None).unwrap();
+        assert_eq!(gateway.state(), CircuitState::Closed);
+        gateway.charge(Money(100), None).unwrap();
+        assert_eq!(gateway.inner().remaining(), 0);
+    }
+
+    #[test]
+    fn declines_prove_the_provider_is_up() {
+        let unavailable = ScriptedResponse::Fail(PaymentFailureReason::Unavailable);
+        let (gateway, _) = breaker(vec![
+            unavailable,
+            ScriptedResponse::Fail(PaymentFailureReason::Declined),
+            unavailable,
+        ]);
+
+        for _ in 0..3 {
+            assert!(gateway.charge(Money(100), None).is_err());
+        }
+
+        // Never two transient failures IN A ROW
+        assert_eq!(gateway.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn health_follows_the_breaker() {
+        let unavailable = ScriptedResponse::Fail(PaymentFailureReason::Unavailable);
+        let (gateway, _clock) = breaker(vec![unavailable, unavailable]);
+        // Two failures scripted: the gateway underneath says degraded
+        assert!(matches!(gateway.health(), HealthStatus::Degraded(_)));
+
+        let _ = gateway.charge(Money(100), None);
+        let _ = gateway.charge(Money(100), None);
+
+        assert_eq!(
+            gateway.health(),
+            HealthStatus::Unhealthy("circuit open".to_string())
+        );
+    }
+}
@@ -0,0 +1,38 @@
+// =============================================================================
+// Configuration Errors - Caught at Startup, Not at the First Charge
+// =============================================================================
+//
+// A gateway built from an empty key fails every charge; one built from a key
+// of the wrong mode charges real cards from a test, or test cards in
+// production. Constructors that take configuration check it, and refuse with
+// a `ConfigError` the deployment sees when it starts.
+//
+// adapters-notification has the same error for its senders. Adapter crates
+// don't depend on each other, so each has its own.
+
+use std::fmt;
+
+/// Why a gateway can't be built from its configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A required setting, or the environment variable holding it, is
+    /// missing or empty.
+    Missing(&'static str),
+    /// A setting has a value that can't be right. A secret shows only its
+    /// prefix here.
+    Invalid {
+        setting: &'static str,
+        value: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(setting) => write!(f, "{setting} is missing"),
+            Self::Invalid { setting, value } => write!(f, "{setting}={value:?} is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
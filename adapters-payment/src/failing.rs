@@ -0,0 +1,128 @@
+// =============================================================================
+// Failing Payment Gateway - The Unhappy Path
+// =============================================================================
+//
+// MockPaymentGateway's twin: every call fails, with the reason of our choice.
+//
+// - Declined: the customer's problem. Checkout must fail cleanly, store
+//   nothing charged, and say why.
+// - Unavailable / Timeout: the provider's problem. Retries kick in (see
+//   retry.rs), then give up.
+// - InvalidRequest: our problem, usually a bug.
+//
+// It counts its calls, so a test can check that a decline was tried once
+// and a timeout as many times as the retry policy allows.
+
+use domain::{
+    AuthorizationId, HealthCheck, HealthStatus, IdempotencyKey, Money, OrderError,
+    PaymentFailureReason, PaymentGateway, PaymentReceipt,
+};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Payment gateway failing every call with the same reason.
+#[derive(Debug)]
+pub struct FailingPaymentGateway {
+    reason: PaymentFailureReason,
+    calls: AtomicU32,
+}
+
+// Manual impl: a card declined is the failure tests want most often
+impl Default for FailingPaymentGateway {
+    fn default() -> Self {
+        Self::new(PaymentFailureReason::Declined)
+    }
+}
+
+impl FailingPaymentGateway {
+    /// Creates a gateway failing with `reason`.
+    #[must_use]
+    pub const fn new(reason: PaymentFailureReason) -> Self {
+        Self {
+            reason,
+            calls: AtomicU32::new(0),
+        }
+    }
+
+    /// Why every call fails.
+    #[must_use]
+    pub const fn reason(&self) -> PaymentFailureReason {
+        self.reason
+    }
+
+    /// Number of calls so far, all failed.
+    #[must_use]
+    pub fn calls(&self) -> u32 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    fn fail<T>(&self) -> Result<T, OrderError> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        Err(OrderError::PaymentFailed(self.reason))
+    }
+}
+
+impl PaymentGateway for FailingPaymentGateway {
+    fn charge(
+        &self,
+        _amount: Money,
+        _idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.fail()
+    }
+
+    fn authorize(
+        &self,
+        _amount: Money,
+        _idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.fail()
+    }
+
+    fn capture(&self, _auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.fail()
+    }
+
+    fn void(&self, _auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.fail()
+    }
+
+    fn refund(&self, _transaction_id: &str, _amount: Money) -> Result<(), OrderError> {
+        self.fail()
+    }
+}
+
+impl HealthCheck for FailingPaymentGateway {
+    // Fails every call: nothing goes through, by design
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Unhealthy(format!("every call fails ({})", self.reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_call_fails_with_the_chosen_reason() {
+        let gateway = FailingPaymentGateway::new(PaymentFailureReason::Timeout);
+
+        assert!(matches!(
+            gateway.charge(Money(1000), None),
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Timeout))
+        ));
+        assert!(gateway.authorize(Money(1000), None).is_err());
+        assert!(gateway.refund("txn_1", Money(1000)).is_err());
+
+        assert_eq!(gateway.calls(), 3);
+    }
+
+    #[test]
+    fn default_declines() {
+        let gateway = FailingPaymentGateway::default();
+
+        assert!(matches!(
+            gateway.capture(&AuthorizationId("auth_1".to_string())),
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+    }
+}
@@ -0,0 +1,336 @@
+// =============================================================================
+// Fallback Payment Gateway - A Backup Provider
+// =============================================================================
+//
+// Stripe is down; Adyen isn't. With an account at both, checkout can carry on:
+//
+//     OrderService -> FallbackPaymentGateway -> primary   (Stripe)
+//                                            -> secondary (Adyen), if needed
+//
+// The whole difficulty is to never charge twice. So the secondary is only
+// tried when the primary certainly did NOT take the money:
+//
+// - Unavailable: the provider refused the request (HTTP 503). Nothing
+//   happened: fall back.
+// - Timeout: the request may or may not have gone through. Charging again
+//   elsewhere could charge twice: NO fallback, the error goes back as it is.
+//   (Retrying the SAME provider with the same idempotency key is safe: that's
+//   RetryingPaymentGateway's job.)
+// - Declined: the customer's bank said no. Another provider would ask the
+//   same bank: no fallback either.
+//
+// Whoever answered, the receipt's `provider` says who took the money.
+//
+// Captures, voids and refunds have no choice: they go to the provider that
+// holds the payment. So the gateway remembers which one issued each
+// authorization and transaction ID. An ID it never issued (made before a
+// restart, say) goes to the primary.
+
+use domain::{
+    AuthorizationId, ChargeMetadata, HealthCheck, HealthStatus, IdempotencyKey, Money, OrderError,
+    PaymentFailureReason, PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Provider {
+    Primary,
+    Secondary,
+}
+
+/// Payment gateway trying a backup provider while the primary is unavailable.
+#[derive(Debug)]
+pub struct FallbackPaymentGateway<P1, P2> {
+    primary: P1,
+    secondary: P2,
+    // Which provider issued each authorization and transaction ID
+    issued: Mutex<HashMap<String, Provider>>,
+}
+
+impl<P1: PaymentGateway, P2: PaymentGateway> FallbackPaymentGateway<P1, P2> {
+    /// Sends payments to `primary`, and to `secondary` while it's unavailable.
+    #[must_use]
+    pub fn new(primary: P1, secondary: P2) -> Self {
+        Self {
+            primary,
+            secondary,
+            issued: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The provider tried first.
+    #[must_use]
+    pub const fn primary(&self) -> &P1 {
+        &self.primary
+    }
+
+    /// The backup provider.
+    #[must_use]
+    pub const fn secondary(&self) -> &P2 {
+        &self.secondary
+    }
+
+    fn issued(&self) -> MutexGuard<'_, HashMap<String, Provider>> {
+        // A poisoned lock only means another thread panicked; the IDs are still fine
+        self.issued.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Tries the primary, then the secondary if the primary was unavailable.
+    fn with_fallback<T>(
+        &self,
+        primary: impl FnOnce(&P1) -> Result<T, OrderError>,
+        secondary: impl FnOnce(&P2) -> Result<T, OrderError>,
+    ) -> Result<(T, Provider), OrderError> {
+        match primary(&self.primary) {
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable)) => {
+                println!("  [Fallback] Primary unavailable, trying the secondary");
+                secondary(&self.secondary).map(|answer| (answer, Provider::Secondary))
+            }
+            result => result.map(|answer| (answer, Provider::Primary)),
+        }
+    }
+
+    /// Remembers who issued `id`, for the calls that settle it.
+    fn remember(&self, id: &str, provider: Provider) {
+        self.issued().insert(id.to_string(), provider);
+    }
+
+    fn issuer(&self, id: &str) -> Provider {
+        self.issued().get(id).copied().unwrap_or(Provider::Primary)
+    }
+}
+
+impl<P1: PaymentGateway, P2: PaymentGateway> PaymentGateway for FallbackPaymentGateway<P1, P2> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let (receipt, provider) = self.with_fallback(
+            |primary| primary.charge_with_ctx(ctx, amount, idempotency_key),
+            |secondary| secondary.charge_with_ctx(ctx, amount, idempotency_key),
+        )?;
+        self.remember(&receipt.transaction_id, provider);
+        Ok(receipt)
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let (receipt, provider) = self.with_fallback(
+            |primary| primary.charge_with_metadata(ctx, amount, idempotency_key, metadata),
+            |secondary| secondary.charge_with_metadata(ctx, amount, idempotency_key, metadata),
+        )?;
+        self.remember(&receipt.transaction_id, provider);
+        Ok(receipt)
+    }
+
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        let (auth, provider) = self.with_fallback(
+            |primary| primary.authorize_with_ctx(ctx, amount, idempotency_key),
+            |secondary| secondary.authorize_with_ctx(ctx, amount, idempotency_key),
+        )?;
+        self.remember(&auth.0, provider);
+        Ok(auth)
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        match self.issuer(&auth.0) {
+            Provider::Primary => self.primary.capture_with_ctx(ctx, auth),
+            Provider::Secondary => self.secondary.capture_with_ctx(ctx, auth),
+        }
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        match self.issuer(&auth.0) {
+            Provider::Primary => self.primary.void_with_ctx(ctx, auth),
+            Provider::Secondary => self.secondary.void_with_ctx(ctx, auth),
+        }
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        match self.issuer(transaction_id) {
+            Provider::Primary => self.primary.refund_with_ctx(ctx, transaction_id, amount),
+            Provider::Secondary => self.secondary.refund_with_ctx(ctx, transaction_id, amount),
+        }
+    }
+}
+
+impl<P1: HealthCheck, P2: HealthCheck> HealthCheck for FallbackPaymentGateway<P1, P2> {
+    /// Payments go through while either provider is usable; running on the
+    /// secondary alone is degraded.
+    fn health(&self) -> HealthStatus {
+        let primary = self.primary.health();
+        if primary.is_usable() {
+            return primary;
+        }
+        match self.secondary.health() {
+            HealthStatus::Healthy => HealthStatus::Degraded(format!("primary: {primary}")),
+            HealthStatus::Degraded(reason) => {
+                HealthStatus::Degraded(format!("primary: {primary}; secondary: {reason}"))
+            }
+            HealthStatus::Unhealthy(reason) => {
+                HealthStatus::Unhealthy(format!("primary: {primary}; secondary: {reason}"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FailingPaymentGateway, MockPaymentGateway, ScriptedPaymentGateway, ScriptedResponse,
+    };
+
+    fn fallback(
+        primary: ScriptedResponse,
+    ) -> FallbackPaymentGateway<ScriptedPaymentGateway, MockPaymentGateway> {
+        FallbackPaymentGateway::new(
+            ScriptedPaymentGateway::new(vec![primary]),
+            MockPaymentGateway::new(),
+        )
+    }
+
+    #[test]
+    fn primary_success_stays_on_the_primary() {
+        let gateway = fallback(ScriptedResponse::Succeed);
+
+        let receipt = gateway.charge(Money(1000), None).unwrap();
+
+        assert_eq!(receipt.provider, "scripted");
+        assert!(gateway.secondary().charges().is_empty());
+    }
+
+    #[test]
+    fn primary_outage_falls_back_to_the_secondary() {
+        let gateway = fallback(ScriptedResponse::Fail(PaymentFailureReason::Unavailable));
+
+        let receipt = gateway.charge(Money(1000), None).unwrap();
+        gateway
+            .refund(&receipt.transaction_id, Money(1000))
+            .unwrap();
+
+        assert_eq!(receipt.provider, "mock");
+        assert_eq!(gateway.secondary().charges(), vec![Money(1000)]);
+        // The refund went where the money is
+        assert_eq!(gateway.secondary().refunds().len(), 1);
+    }
+
+    #[test]
+    fn captures_go_to_the_provider_that_authorized() {
+        let gateway = fallback(ScriptedResponse::Fail(PaymentFailureReason::Unavailable));
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        gateway.capture(&auth).unwrap();
+
+        assert_eq!(gateway.secondary().capture_count(), 1);
+        assert_eq!(gateway.primary().calls().len(), 1);
+    }
+
+    #[test]
+    fn declines_and_timeouts_never_fall_back() {
+        for reason in [
+            PaymentFailureReason::Declined,
+            PaymentFailureReason::Timeout,
+        ] {
+            let gateway = fallback(ScriptedResponse::Fail(reason));
+
+            let result = gateway.charge(Money(1000), None);
+
+            assert!(matches!(result, Err(OrderError::PaymentFailed(r)) if r == reason));
+            assert!(gateway.secondary().charges().is_empty());
+        }
+    }
+
+    #[test]
+    fn both_failing_returns_the_secondary_error() {
+        let gateway = FallbackPaymentGateway::new(
+            ScriptedPaymentGateway::new(vec![ScriptedResponse::Fail(
+                PaymentFailureReason::Unavailable,
+            )]),
+            FailingPaymentGateway::new(PaymentFailureReason::Timeout),
+        );
+
+        let result = gateway.charge(Money(1000), None);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Timeout))
+        ));
+        assert_eq!(gateway.secondary().calls(), 1);
+    }
+
+    #[test]
+    fn health_is_degraded_on_the_secondary_alone() {
+        let healthy =
+            FallbackPaymentGateway::new(MockPaymentGateway::new(), MockPaymentGateway::new());
+        let degraded = FallbackPaymentGateway::new(
+            FailingPaymentGateway::default(),
+            MockPaymentGateway::new(),
+        );
+        let down = FallbackPaymentGateway::new(
+            FailingPaymentGateway::default(),
+            FailingPaymentGateway::new(PaymentFailureReason::Unavailable),
+        );
+
+        assert_eq!(healthy.health(), HealthStatus::Healthy);
+        assert_eq!(
+            degraded.health(),
+            HealthStatus::Degraded("primary: unhealthy: every call fails (Declined)".to_string())
+        );
+        assert!(matches!(down.health(), HealthStatus::Unhealthy(_)));
+    }
+}
@@ -0,0 +1,310 @@
+// =============================================================================
+// Flaky Payment Gateway - A Provider Having a Bad Minute
+// =============================================================================
+//
+// Between "always works" (MockPaymentGateway) and "never works"
+// (FailingPaymentGateway) lies what providers actually do: work, mostly.
+// This decorator wraps any gateway and fails some of its calls:
+//
+// - the first N calls, then none: the provider comes back. The shape of a
+//   test for retries ("fails twice, the third attempt goes through").
+// - each call with probability p: chaos testing, for a demo or a soak test.
+//
+// Failures are Unavailable by default: transient, so RetryingPaymentGateway
+// retries them. `with_reason` picks another one.
+//
+// The dice are injected: a closure returning a number in [0, 1). A seeded
+// generator (or a fixed list of numbers) makes a "random" test fail the same
+// way on every run:
+//
+//     let mut rng = StdRng::seed_from_u64(42);
+//     FlakyPaymentGateway::failing_randomly(inner, 0.3, move || rng.random())
+
+use crate::MockPaymentGateway;
+use domain::{
+    AuthorizationId, ChargeMetadata, HealthCheck, HealthStatus, IdempotencyKey, Money, OrderError,
+    PaymentFailureReason, PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+type Dice = Box<dyn FnMut() -> f64 + Send>;
+
+/// Payment gateway decorator failing some calls, for retry and chaos testing.
+pub struct FlakyPaymentGateway<P = MockPaymentGateway> {
+    inner: P,
+    reason: PaymentFailureReason,
+    state: Mutex<FlakyState>,
+}
+
+struct FlakyState {
+    schedule: Schedule,
+    counters: FlakyCounters,
+}
+
+enum Schedule {
+    FirstCalls(u32),
+    Randomly { probability: f64, dice: Dice },
+}
+
+/// How many calls reached the gateway, and how many of them it failed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlakyCounters {
+    pub calls: u32,
+    pub failures: u32,
+}
+
+impl<P> FlakyPaymentGateway<P> {
+    fn state(&self) -> MutexGuard<'_, FlakyState> {
+        // A poisoned lock only means another thread panicked; the schedule is still fine
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Manual impl: the dice are a closure with no Debug of its own.
+impl<P: fmt::Debug> fmt::Debug for FlakyPaymentGateway<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        let mut debug = f.debug_struct("FlakyPaymentGateway");
+        debug
+            .field("inner", &self.inner)
+            .field("reason", &self.reason);
+        match &state.schedule {
+            Schedule::FirstCalls(left) => debug.field("failing_next", left),
+            Schedule::Randomly { probability, .. } => debug.field("probability", probability),
+        };
+        debug.field("counters", &state.counters).finish()
+    }
+}
+
+impl<P: PaymentGateway> FlakyPaymentGateway<P> {
+    /// Wraps `inner`, failing its first `count` calls.
+    #[must_use]
+    pub fn failing_first(inner: P, count: u32) -> Self {
+        Self::with_schedule(inner, Schedule::FirstCalls(count))
+    }
+
+    /// Wraps `inner`, failing each call with `probability`: when `dice`
+    /// returns less than it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` isn't between 0 and 1.
+    #[must_use]
+    pub fn failing_randomly(
+        inner: P,
+        probability: f64,
+        dice: impl FnMut() -> f64 + Send + 'static,
+    ) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "a probability is between 0 and 1"
+        );
+        Self::with_schedule(
+            inner,
+            Schedule::Randomly {
+                probability,
+                dice: Box::new(dice),
+            },
+        )
+    }
+
+    fn with_schedule(inner: P, schedule: Schedule) -> Self {
+        Self {
+            inner,
+            reason: PaymentFailureReason::Unavailable,
+            state: Mutex::new(FlakyState {
+                schedule,
+                counters: FlakyCounters::default(),
+            }),
+        }
+    }
+
+    /// Fails with `reason` instead of [`PaymentFailureReason::Unavailable`].
+    #[must_use]
+    pub const fn with_reason(mut self, reason: PaymentFailureReason) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    /// What happened so far.
+    #[must_use]
+    pub fn counters(&self) -> FlakyCounters {
+        self.state().counters
+    }
+
+    /// The wrapped gateway.
+    #[must_use]
+    pub const fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Counts a call, and fails it if the schedule says so.
+    fn attempt(&self) -> Result<(), OrderError> {
+        let mut state = self.state();
+        state.counters.calls += 1;
+        let fail = match &mut state.schedule {
+            Schedule::FirstCalls(0) => false,
+            Schedule::FirstCalls(left) => {
+                *left -= 1;
+                true
+            }
+            Schedule::Randomly { probability, dice } => dice() < *probability,
+        };
+        if fail {
+            state.counters.failures += 1;
+            return Err(OrderError::PaymentFailed(self.reason));
+        }
+        Ok(())
+    }
+}
+
+impl<P: PaymentGateway> PaymentGateway for FlakyPaymentGateway<P> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.attempt()?;
+        self.inner.charge_with_ctx(ctx, amount, idempotency_key)
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.attempt()?;
+        self.inner
+            .charge_with_metadata(ctx, amount, idempotency_key, metadata)
+    }
+
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.attempt()?;
+        self.inner.authorize_with_ctx(ctx, amount, idempotency_key)
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.attempt()?;
+        self.inner.capture_with_ctx(ctx, auth)
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.attempt()?;
+        self.inner.void_with_ctx(ctx, auth)
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.attempt()?;
+        self.inner.refund_with_ctx(ctx, transaction_id, amount)
+    }
+}
+
+impl<P: HealthCheck> HealthCheck for FlakyPaymentGateway<P> {
+    /// Degraded while calls still fail on schedule, on top of whatever the
+    /// inner gateway says.
+    fn health(&self) -> HealthStatus {
+        let own = match self.state().schedule {
+            Schedule::FirstCalls(0) => HealthStatus::Healthy,
+            Schedule::FirstCalls(left) => {
+                HealthStatus::Degraded(format!("calls left to fail: {left}"))
+            }
+            Schedule::Randomly { probability, .. } => {
+                HealthStatus::Degraded(format!("{:.0}% of calls fail", probability * 100.0))
+            }
+        };
+        own.max(self.inner.health())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_first_calls_fail_then_the_inner_gateway_answers() {
+        let gateway = FlakyPaymentGateway::failing_first(MockPaymentGateway::new(), 2);
+
+        assert!(matches!(
+            gateway.charge(Money(1000), None),
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable))
+        ));
+        assert!(gateway.charge(Money(1000), None).is_err());
+        gateway.charge(Money(1000), None).unwrap();
+
+        assert_eq!(
+            gateway.counters(),
+            FlakyCounters {
+                calls: 3,
+                failures: 2
+            }
+        );
+        assert_eq!(gateway.inner().charges(), vec![Money(1000)]);
+    }
+
+    #[test]
+    fn random_failures_follow_the_dice() {
+        let mut rolls = [0.9, 0.1, 0.5, 0.29].into_iter().cycle();
+        let gateway =
+            FlakyPaymentGateway::failing_randomly(MockPaymentGateway::new(), 0.3, move || {
+                rolls.next().unwrap_or(1.0)
+            })
+            .with_reason(PaymentFailureReason::Timeout);
+
+        let outcomes: Vec<bool> = (0..4)
+            .map(|_| gateway.authorize(Money(500), None).is_ok())
+            .collect();
+
+        assert_eq!(outcomes, vec![true, false, true, false]);
+        assert_eq!(gateway.counters().failures, 2);
+    }
+}
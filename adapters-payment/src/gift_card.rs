@@ -0,0 +1,166 @@
+// =============================================================================
+// In-Memory Gift Card Store - Prepaid Balances
+// =============================================================================
+//
+// The `GiftCardGateway` port in its simplest form: a HashMap from card to
+// balance. Cards are issued up front with `issue`, then spent through the port.
+//
+// A real gift card provider would be another HTTP API (like Stripe) or a
+// table in our own database:
+//
+//     UPDATE gift_cards SET balance = balance - $2
+//     WHERE id = $1 AND balance >= $2
+//
+// The `balance >= $2` condition is what keeps two concurrent debits from
+// spending the same dollars twice. Here the Mutex does that job.
+//
+// The log lines go to stdout, or to a `Logger` when one is plugged in.
+
+use domain::{
+    GiftCardGateway, GiftCardId, Logger, Money, OrderError, PaymentFailureReason, RequestContext,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// In-memory gift card balances for testing scenarios.
+#[derive(Default)]
+pub struct InMemoryGiftCardStore {
+    balances: Mutex<HashMap<GiftCardId, Money>>,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
+
+// Manual impl: the logger is a trait object with no Debug of its own.
+impl fmt::Debug for InMemoryGiftCardStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemoryGiftCardStore")
+            .field("balances", &self.balances)
+            .field("logger", &self.logger.is_some())
+            .finish()
+    }
+}
+
+impl InMemoryGiftCardStore {
+    /// Creates a store with no cards.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Issues `card` with `balance` on it, replacing any previous balance.
+    pub fn issue(&self, card: GiftCardId, balance: Money) {
+        self.log(&format!("[GiftCard] Issuing {card} with {balance}"));
+        self.balances().insert(card, balance);
+    }
+
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    fn balances(&self) -> MutexGuard<'_, HashMap<GiftCardId, Money>> {
+        // A poisoned lock only means another thread panicked; the balances are still fine
+        self.balances.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl GiftCardGateway for InMemoryGiftCardStore {
+    fn balance(&self, card: GiftCardId) -> Result<Money, OrderError> {
+        self.balances()
+            .get(&card)
+            .copied()
+            .ok_or(OrderError::GiftCardNotFound(card))
+    }
+
+    fn debit(&self, card: GiftCardId, amount: Money) -> Result<(), OrderError> {
+        self.log(&format!("[GiftCard] Debiting {amount} from {card}"));
+        let mut balances = self.balances();
+        let balance = balances
+            .get_mut(&card)
+            .ok_or(OrderError::GiftCardNotFound(card))?;
+        balance.0 = balance
+            .0
+            .checked_sub(amount.0)
+            .ok_or(OrderError::PaymentFailed(PaymentFailureReason::Declined))?;
+        Ok(())
+    }
+
+    fn credit(&self, card: GiftCardId, amount: Money) -> Result<(), OrderError> {
+        self.log(&format!("[GiftCard] Crediting {amount} to {card}"));
+        let mut balances = self.balances();
+        let balance = balances
+            .get_mut(&card)
+            .ok_or(OrderError::GiftCardNotFound(card))?;
+        balance.0 = balance.0.saturating_add(amount.0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_repository::CapturingLogger;
+
+    #[test]
+    fn debit_never_goes_below_zero() {
+        let store = InMemoryGiftCardStore::new();
+        store.issue(GiftCardId(1), Money(500));
+
+        store.debit(GiftCardId(1), Money(300)).unwrap();
+        let overdraft = store.debit(GiftCardId(1), Money(300));
+
+        assert!(matches!(
+            overdraft,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+        assert_eq!(store.balance(GiftCardId(1)).unwrap(), Money(200));
+    }
+
+    #[test]
+    fn credit_undoes_a_debit() {
+        let store = InMemoryGiftCardStore::new();
+        store.issue(GiftCardId(1), Money(500));
+
+        store.debit(GiftCardId(1), Money(500)).unwrap();
+        store.credit(GiftCardId(1), Money(500)).unwrap();
+
+        assert_eq!(store.balance(GiftCardId(1)).unwrap(), Money(500));
+    }
+
+    #[test]
+    fn log_lines_go_to_the_logger() {
+        let logger = Arc::new(CapturingLogger::new());
+        let store = InMemoryGiftCardStore::new().with_logger(logger.clone());
+
+        store.issue(GiftCardId(1), Money(500));
+        store.debit(GiftCardId(1), Money(200)).unwrap();
+
+        let messages: Vec<_> = logger.lines().into_iter().map(|l| l.message).collect();
+        assert_eq!(
+            messages,
+            [
+                format!("[GiftCard] Issuing {} with {}", GiftCardId(1), Money(500)),
+                format!("[GiftCard] Debiting {} from {}", Money(200), GiftCardId(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_card_is_reported() {
+        let store = InMemoryGiftCardStore::new();
+
+        assert!(matches!(
+            store.balance(GiftCardId(9)),
+            Err(OrderError::GiftCardNotFound(GiftCardId(9)))
+        ));
+    }
+}
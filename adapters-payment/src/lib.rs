@@ -3,9 +3,25 @@
 // =============================================================================
 //
 // This crate provides concrete implementations of the `PaymentGateway` port.
-// We have two adapters:
+// We have five adapters:
 // - MockPaymentGateway: Always succeeds, perfect for testing
+// - FailingPaymentGateway: Always fails, with the reason of your choice
+// - ScriptedPaymentGateway: Answers each call from a script written by the test
 // - StripePaymentGateway: Simulates calling Stripe's API
+// - StripeApiPaymentGateway: Really calls Stripe's API (`stripe` feature)
+//
+// Plus decorators that wrap either of them:
+// - RetryingPaymentGateway: Retries transient failures with backoff
+// - CircuitBreakerPaymentGateway: Fails fast while the provider is down
+// - FallbackPaymentGateway: Fails over to a backup provider when the primary is down
+// - FlakyPaymentGateway: Fails some calls, for retry and chaos testing
+// - LimitedPaymentGateway: Refuses charges over a spending or velocity limit
+// - AuditingPaymentGateway: Writes one JSON line per charge attempt, for finance
+// - SlowPaymentGateway: Adds latency to every call, for timeout testing
+// - RecordingPaymentGateway: Remembers every charge, for test assertions
+//
+// And the other way customers pay:
+// - InMemoryGiftCardStore: the `GiftCardGateway` port
 //
 // REAL-WORLD CONSIDERATIONS:
 // --------------------------
@@ -25,8 +41,36 @@
 //
 // Our simulated version shows the PATTERN without the complexity.
 
+mod auditing;
+mod circuit_breaker;
+mod config;
+mod failing;
+mod fallback;
+mod flaky;
+mod gift_card;
+mod limited;
 mod mock;
+mod recording;
+mod retry;
+mod scripted;
+mod slow;
 mod stripe;
+#[cfg(feature = "stripe")]
+mod stripe_api;
 
+pub use auditing::AuditingPaymentGateway;
+pub use circuit_breaker::{CircuitBreakerPaymentGateway, CircuitBreakerPolicy, CircuitState};
+pub use config::ConfigError;
+pub use failing::FailingPaymentGateway;
+pub use fallback::FallbackPaymentGateway;
+pub use flaky::{FlakyCounters, FlakyPaymentGateway};
+pub use gift_card::InMemoryGiftCardStore;
+pub use limited::LimitedPaymentGateway;
 pub use mock::MockPaymentGateway;
+pub use recording::{ChargeRecord, RecordingPaymentGateway};
+pub use retry::{Backoff, RetryPolicy, RetryingPaymentGateway};
+pub use scripted::{ScriptedCall, ScriptedPaymentGateway, ScriptedResponse};
+pub use slow::SlowPaymentGateway;
 pub use stripe::StripePaymentGateway;
+#[cfg(feature = "stripe")]
+pub use stripe_api::{Mode, StripeApiPaymentGateway};
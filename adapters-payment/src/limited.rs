@@ -0,0 +1,286 @@
+// =============================================================================
+// Limited Payment Gateway - Fraud Rules Before the Provider
+// =============================================================================
+//
+// "No single charge over $1,000" and "no more than 5 charges a minute" are
+// rules about PAYMENTS, not about orders: they'd apply just the same to a
+// subscription renewal. So they don't go in OrderService, they go in a
+// decorator any gateway can wear:
+//
+//     OrderService -> LimitedPaymentGateway -> RetryingPaymentGateway -> Stripe
+//
+// Two limits, each optional:
+// - a maximum per charge: at the limit is fine, one cent over is refused
+// - a velocity limit: at most N charges within any window of the given length
+//
+// "Charges" are the calls that take or hold money: `charge` and `authorize`.
+// Captures, voids and refunds go straight through: they settle a payment the
+// limits already let in.
+//
+// Every charge that passes the limits counts towards the velocity, whatever
+// the provider answers: a stolen card is tested with a burst of attempts,
+// mostly declined, and that burst is exactly what the rule is for.
+//
+// A refused charge never reaches the provider, and fails with
+// PaymentFailed(LimitExceeded): not transient, so not retried.
+//
+// Time comes from the `Clock` port, so tests slide the window with a
+// FixedClock instead of waiting a minute.
+
+use domain::{
+    AuthorizationId, ChargeMetadata, Clock, HealthCheck, HealthStatus, IdempotencyKey, Money,
+    OrderError, PaymentFailureReason, PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// Payment gateway decorator refusing charges over a spending or velocity limit.
+pub struct LimitedPaymentGateway<P> {
+    inner: P,
+    clock: Arc<dyn Clock + Send + Sync>,
+    max_per_charge: Option<Money>,
+    velocity: Option<(usize, Duration)>,
+    // When the charges within the window were made, oldest first
+    recent: Mutex<VecDeque<SystemTime>>,
+}
+
+// Manual impl: the clock is a trait object with no Debug of its own.
+impl<P: fmt::Debug> fmt::Debug for LimitedPaymentGateway<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LimitedPaymentGateway")
+            .field("inner", &self.inner)
+            .field("max_per_charge", &self.max_per_charge)
+            .field("velocity", &self.velocity)
+            .field("recent", &self.recent)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: PaymentGateway> LimitedPaymentGateway<P> {
+    /// Wraps `inner`, with no limit until one is set.
+    #[must_use]
+    pub fn new(inner: P, clock: Arc<dyn Clock + Send + Sync>) -> Self {
+        Self {
+            inner,
+            clock,
+            max_per_charge: None,
+            velocity: None,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Refuses any single charge over `max`.
+    #[must_use]
+    pub const fn with_max_per_charge(mut self, max: Money) -> Self {
+        self.max_per_charge = Some(max);
+        self
+    }
+
+    /// Refuses a charge if `count` were already made within the last `window`.
+    #[must_use]
+    pub const fn with_max_charges(mut self, count: usize, window: Duration) -> Self {
+        self.velocity = Some((count, window));
+        self
+    }
+
+    /// The wrapped gateway.
+    #[must_use]
+    pub const fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn recent(&self) -> MutexGuard<'_, VecDeque<SystemTime>> {
+        // A poisoned lock only means another thread panicked; the timestamps are still fine
+        self.recent.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Checks `amount` against both limits, and counts it if it passes.
+    fn allow(&self, amount: Money) -> Result<(), OrderError> {
+        let refused = Err(OrderError::PaymentFailed(
+            PaymentFailureReason::LimitExceeded,
+        ));
+        if self.max_per_charge.is_some_and(|max| amount.0 > max.0) {
+            return refused;
+        }
+
+        let now = self.clock.now();
+        let mut recent = self.recent();
+        if let Some((count, window)) = self.velocity {
+            // Forget the charges that slid out of the window
+            while recent
+                .front()
+                .is_some_and(|made| now.duration_since(*made).is_ok_and(|age| age >= window))
+            {
+                recent.pop_front();
+            }
+            if recent.len() >= count {
+                return refused;
+            }
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+impl<P: PaymentGateway> PaymentGateway for LimitedPaymentGateway<P> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.allow(amount)?;
+        self.inner.charge_with_ctx(ctx, amount, idempotency_key)
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.allow(amount)?;
+        self.inner
+            .charge_with_metadata(ctx, amount, idempotency_key, metadata)
+    }
+
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.allow(amount)?;
+        self.inner.authorize_with_ctx(ctx, amount, idempotency_key)
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.inner.capture_with_ctx(ctx, auth)
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.inner.void_with_ctx(ctx, auth)
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.inner.refund_with_ctx(ctx, transaction_id, amount)
+    }
+}
+
+impl<P: HealthCheck> HealthCheck for LimitedPaymentGateway<P> {
+    // Only as healthy as the gateway underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockPaymentGateway;
+    use adapters_repository::FixedClock;
+    use std::time::UNIX_EPOCH;
+
+    fn limited() -> (LimitedPaymentGateway<MockPaymentGateway>, Arc<FixedClock>) {
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH));
+        let gateway =
+            LimitedPaymentGateway::new(MockPaymentGateway::new(), Arc::clone(&clock) as _);
+        (gateway, clock)
+    }
+
+    fn is_limited<T>(result: Result<T, OrderError>) -> bool {
+        matches!(
+            result,
+            Err(OrderError::PaymentFailed(
+                PaymentFailureReason::LimitExceeded
+            ))
+        )
+    }
+
+    #[test]
+    fn a_charge_at_the_limit_passes_and_one_cent_over_does_not() {
+        let (gateway, _) = limited();
+        let gateway = gateway.with_max_per_charge(Money(100_000));
+
+        gateway.charge(Money(100_000), None).unwrap();
+        assert!(is_limited(gateway.authorize(Money(100_001), None)));
+
+        // The refused one never reached the provider
+        assert_eq!(gateway.inner().charges(), vec![Money(100_000)]);
+        assert!(gateway.inner().outstanding_authorizations().is_empty());
+    }
+
+    #[test]
+    fn the_velocity_window_rolls_over() {
+        let (gateway, clock) = limited();
+        let gateway = gateway.with_max_charges(5, Duration::from_secs(60));
+
+        for _ in 0..5 {
+            gateway.charge(Money(1000), None).unwrap();
+            clock.advance(Duration::from_secs(10));
+        }
+        // 50s after the first charge: still 5 in the last minute
+        assert!(is_limited(gateway.charge(Money(1000), None)));
+
+        // 60s after the first charge: it slid out of the window
+        clock.advance(Duration::from_secs(10));
+        gateway.charge(Money(1000), None).unwrap();
+        assert!(is_limited(gateway.charge(Money(1000), None)));
+    }
+
+    #[test]
+    fn settling_calls_are_not_limited() {
+        let (gateway, _) = limited();
+        let gateway = gateway.with_max_charges(1, Duration::from_secs(60));
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        gateway.capture(&auth).unwrap();
+        gateway.refund("mock_txn_1", Money(1000)).unwrap();
+
+        assert_eq!(gateway.inner().capture_count(), 1);
+    }
+}
@@ -5,41 +5,391 @@
 // This adapter always succeeds. It's the "happy path" mock.
 // Perfect for testing the normal flow of our application.
 //
-// In a more sophisticated test setup, we might also have:
-// - FailingPaymentGateway (always fails)
-// - FlakeyPaymentGateway (fails randomly: for chaos testing)
-// - SlowPaymentGateway (adds delays: for timeout testing)
+// Its siblings cover the other scenarios:
+// - FailingPaymentGateway (always fails, see failing.rs)
+// - FlakyPaymentGateway (fails some calls: for retry and chaos testing, see flaky.rs)
+// - SlowPaymentGateway (adds delays: for timeout testing, see slow.rs)
 //
 // Each helps test different scenarios without real payment APIs.
+//
+// Log lines go to stdout, or to a `Logger` when one is plugged in. Through
+// the `*_with_ctx` methods, each line carries the request's correlation ID.
+// Receipts are stamped with the system time, or a `Clock`'s when one is
+// plugged in (the only choice in a browser, where there's no system time).
 
-use domain::{Money, OrderError, PaymentGateway};
+use domain::{
+    AuthorizationId, ChargeMetadata, Clock, HealthCheck, HealthStatus, IdempotencyKey, Logger,
+    Money, OrderError, PaymentFailureReason, PaymentGateway, PaymentReceipt, PaymentStatus,
+    RequestContext,
+};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
 
 /// Mock payment gateway that always succeeds.
 ///
 /// No real money moves. No API calls. Just a log line.
 /// But from OrderService's perspective, the contract is fulfilled!
-#[derive(Debug, Default, Clone, Copy)]
-pub struct MockPaymentGateway;
+///
+/// Transaction IDs are sequential (`mock_txn_1`, `mock_txn_2`, ...) so tests
+/// can predict them. Authorizations are tracked until they are captured or
+/// voided, so tests can check that no hold is left behind.
+#[derive(Default)]
+pub struct MockPaymentGateway {
+    // The port methods take &self, so the bookkeeping needs interior mutability
+    state: Mutex<MockState>,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+    clock: Option<Arc<dyn Clock + Send + Sync>>,
+}
+
+// Manual impl: the logger and the clock are trait objects with no Debug of their own.
+impl fmt::Debug for MockPaymentGateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockPaymentGateway")
+            .field("state", &self.state)
+            .field("logger", &self.logger.is_some())
+            .field("clock", &self.clock.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    charges: Vec<Money>,
+    // One per charge, empty for the charges made without any
+    charge_metadata: Vec<ChargeMetadata>,
+    authorizations: u32,
+    outstanding: Vec<AuthorizationId>,
+    captures: u32,
+    voids: u32,
+    refunds: Vec<(String, Money)>,
+}
+
+impl MockPaymentGateway {
+    /// Creates a new mock gateway whose first transaction is `mock_txn_1`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Stamps receipts with `clock`'s time instead of the system's.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock + Send + Sync>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Authorizations that were neither captured nor voided yet.
+    #[must_use]
+    pub fn outstanding_authorizations(&self) -> Vec<AuthorizationId> {
+        self.state().outstanding.clone()
+    }
+
+    /// Number of successful captures.
+    #[must_use]
+    pub fn capture_count(&self) -> u32 {
+        self.state().captures
+    }
+
+    /// Number of successful voids.
+    #[must_use]
+    pub fn void_count(&self) -> u32 {
+        self.state().voids
+    }
+
+    /// The amount of every direct charge so far, oldest first.
+    #[must_use]
+    pub fn charges(&self) -> Vec<Money> {
+        self.state().charges.clone()
+    }
+
+    /// The metadata of every direct charge so far, oldest first: empty for
+    /// a charge made without any.
+    #[must_use]
+    pub fn charge_metadata(&self) -> Vec<ChargeMetadata> {
+        self.state().charge_metadata.clone()
+    }
+
+    /// Every refund so far, as (transaction ID, amount).
+    #[must_use]
+    pub fn refunds(&self) -> Vec<(String, Money)> {
+        self.state().refunds.clone()
+    }
+
+    fn log(&self, ctx: &RequestContext, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(ctx, message),
+            None => println!("  {message}"),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        // A poisoned lock only means another test thread panicked; the counters are still fine
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Removes `auth` from the outstanding list, failing if it isn't there.
+    fn settle(state: &mut MockState, auth: &AuthorizationId) -> Result<(), OrderError> {
+        let index =
+            state
+                .outstanding
+                .iter()
+                .position(|a| a == auth)
+                .ok_or(OrderError::PaymentFailed(
+                    PaymentFailureReason::InvalidRequest,
+                ))?;
+        state.outstanding.remove(index);
+        Ok(())
+    }
+}
 
 impl PaymentGateway for MockPaymentGateway {
-    /// "Charges" the amount by printing to stdout.
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    /// "Charges" the amount by logging it.
     ///
-    /// Returns Ok(()) always: the happy path.
-    fn charge(&self, amount: Money) -> Result<(), OrderError> {
-        println!("  [Mock] Charging {amount}");
+    /// Returns a receipt always: the happy path.
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_metadata(ctx, amount, idempotency_key, &ChargeMetadata::new())
+    }
+
+    /// Charges, and keeps the metadata for the test to look at.
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        _idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let mut state = self.state();
+        state.charges.push(amount);
+        state.charge_metadata.push(metadata.clone());
+        if metadata.is_empty() {
+            self.log(ctx, &format!("[Mock] Charging {amount}"));
+        } else {
+            let keys: Vec<&str> = metadata.entries().iter().map(|(k, _)| k.as_str()).collect();
+            self.log(
+                ctx,
+                &format!("[Mock] Charging {amount} with metadata {}", keys.join(", ")),
+            );
+        }
+        Ok(PaymentReceipt {
+            transaction_id: format!("mock_txn_{}", state.charges.len()),
+            provider: "mock".to_string(),
+            amount,
+            timestamp: self
+                .clock
+                .as_ref()
+                .map_or_else(SystemTime::now, |clock| clock.now()),
+            status: PaymentStatus::Settled,
+        })
+    }
+
+    /// "Holds" the amount and remembers the authorization.
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        _idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        let mut state = self.state();
+        state.authorizations += 1;
+        let auth = AuthorizationId(format!("mock_auth_{}", state.authorizations));
+        self.log(ctx, &format!("[Mock] Authorizing {amount} -> {auth}"));
+        state.outstanding.push(auth.clone());
+        Ok(auth)
+    }
+
+    /// "Takes" the held funds. Fails for unknown or already settled authorizations.
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        let mut state = self.state();
+        Self::settle(&mut state, auth)?;
+        state.captures += 1;
+        self.log(ctx, &format!("[Mock] Capturing {auth}"));
+        Ok(())
+    }
+
+    /// "Releases" the held funds. Fails for unknown or already settled authorizations.
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        let mut state = self.state();
+        Self::settle(&mut state, auth)?;
+        state.voids += 1;
+        self.log(ctx, &format!("[Mock] Voiding {auth}"));
+        Ok(())
+    }
+
+    /// "Refunds" the amount and remembers it.
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.log(
+            ctx,
+            &format!("[Mock] Refunding {amount} on {transaction_id}"),
+        );
+        self.state()
+            .refunds
+            .push((transaction_id.to_string(), amount));
         Ok(())
     }
 }
 
+impl HealthCheck for MockPaymentGateway {
+    // Approves everything, from our own process: always there
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapters_repository::{FixedClock, WriterLogger};
+    use std::time::{Duration, UNIX_EPOCH};
 
     #[test]
     fn mock_charge_succeeds() {
-        let gateway = MockPaymentGateway;
-        let result = gateway.charge(Money(1000));
+        let gateway = MockPaymentGateway::new();
+        let receipt = gateway.charge(Money(1000), None).unwrap();
+
+        assert_eq!(receipt.amount, Money(1000));
+    }
+
+    #[test]
+    fn mock_transaction_ids_are_sequential() {
+        let gateway = MockPaymentGateway::new();
+
+        let first = gateway.charge(Money(1000), None).unwrap();
+        let second = gateway.charge(Money(2000), None).unwrap();
+
+        assert_eq!(first.transaction_id, "mock_txn_1");
+        assert_eq!(second.transaction_id, "mock_txn_2");
+    }
+
+    #[test]
+    fn mock_authorize_then_capture() {
+        let gateway = MockPaymentGateway::new();
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        assert_eq!(gateway.outstanding_authorizations(), vec![auth.clone()]);
+
+        gateway.capture(&auth).unwrap();
+
+        assert!(gateway.outstanding_authorizations().is_empty());
+        assert_eq!(gateway.capture_count(), 1);
+        assert_eq!(gateway.void_count(), 0);
+    }
+
+    #[test]
+    fn mock_authorize_then_void() {
+        let gateway = MockPaymentGateway::new();
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        gateway.void(&auth).unwrap();
+
+        assert!(gateway.outstanding_authorizations().is_empty());
+        assert_eq!(gateway.capture_count(), 0);
+        assert_eq!(gateway.void_count(), 1);
+    }
+
+    #[test]
+    fn mock_capture_after_void_fails() {
+        let gateway = MockPaymentGateway::new();
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        gateway.void(&auth).unwrap();
+
+        assert!(matches!(
+            gateway.capture(&auth),
+            Err(OrderError::PaymentFailed(
+                PaymentFailureReason::InvalidRequest
+            ))
+        ));
+    }
+
+    #[test]
+    fn mock_records_refunds() {
+        let gateway = MockPaymentGateway::new();
+
+        gateway.refund("mock_txn_1", Money(250)).unwrap();
+
+        assert_eq!(
+            gateway.refunds(),
+            vec![("mock_txn_1".to_string(), Money(250))]
+        );
+    }
+
+    #[test]
+    fn mock_writes_its_log_lines_to_the_logger() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let gateway = MockPaymentGateway::new().with_logger(logger.clone());
+
+        gateway
+            .charge_with_ctx(&RequestContext::new("req-1"), Money(1000), None)
+            .unwrap();
+        gateway.refund("mock_txn_1", Money(250)).unwrap();
+
+        assert_eq!(
+            logger.text(),
+            "  [req-1] [Mock] Charging $10.00\n  [Mock] Refunding $2.50 on mock_txn_1\n"
+        );
+    }
+
+    #[test]
+    fn receipts_carry_the_clocks_time() {
+        let at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let gateway = MockPaymentGateway::new().with_clock(Arc::new(FixedClock::new(at)));
+
+        let receipt = gateway.charge(Money(1000), None).unwrap();
 
-        assert!(result.is_ok());
+        assert_eq!(receipt.timestamp, at);
     }
 }
@@ -0,0 +1,258 @@
+// =============================================================================
+// Recording Payment Gateway - What Was Charged, and When
+// =============================================================================
+//
+// A test stubbing the gateway with "always Ok" can check that checkout
+// succeeded. It can't check that the customer was charged the right amount,
+// or charged only once. This adapter remembers every charge so the test can.
+//
+// A "charge" is money actually taken from the customer:
+// - a direct `charge`
+// - a `capture`, for the amount that was authorized
+//
+// An authorization alone is a hold, not a charge: voided, it never shows up.
+// Failed calls don't show up either: no money moved.
+//
+// Two ways to use it:
+//
+//     RecordingPaymentGateway::new()          // on its own: every call succeeds
+//     RecordingPaymentGateway::wrapping(inner) // a decorator: inner decides
+//
+// Records land in an `Arc<Mutex<Vec<ChargeRecord>>>`. `charges()` copies
+// them out; `with_records` shares the Vec with the test, for when the
+// gateway itself was moved into the service.
+
+use crate::MockPaymentGateway;
+use domain::{
+    AuthorizationId, ChargeMetadata, HealthCheck, HealthStatus, IdempotencyKey, Money, OrderError,
+    PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+/// One charge, as seen by [`RecordingPaymentGateway`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChargeRecord {
+    /// 1 for the first charge, 2 for the second...
+    pub sequence: u64,
+    pub amount: Money,
+    pub timestamp: SystemTime,
+}
+
+/// Payment gateway recording every charge, for test assertions.
+#[derive(Debug)]
+pub struct RecordingPaymentGateway<P = MockPaymentGateway> {
+    inner: P,
+    records: Arc<Mutex<Vec<ChargeRecord>>>,
+    // Authorized amounts, until captured or voided
+    holds: Mutex<HashMap<AuthorizationId, Money>>,
+}
+
+impl RecordingPaymentGateway {
+    /// Creates a gateway where every call succeeds.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::wrapping(MockPaymentGateway::new())
+    }
+}
+
+// Manual impl: only the standalone gateway has a default, not every wrapper
+impl Default for RecordingPaymentGateway {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: PaymentGateway> RecordingPaymentGateway<P> {
+    /// Wraps `inner`, recording the charges it lets through.
+    #[must_use]
+    pub fn wrapping(inner: P) -> Self {
+        Self {
+            inner,
+            records: Arc::new(Mutex::new(Vec::new())),
+            holds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records into `records` instead of a Vec of its own.
+    #[must_use]
+    pub fn with_records(mut self, records: Arc<Mutex<Vec<ChargeRecord>>>) -> Self {
+        self.records = records;
+        self
+    }
+
+    /// Every charge so far, oldest first.
+    #[must_use]
+    pub fn charges(&self) -> Vec<ChargeRecord> {
+        self.records().clone()
+    }
+
+    /// The wrapped gateway.
+    #[must_use]
+    pub const fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn records(&self) -> MutexGuard<'_, Vec<ChargeRecord>> {
+        // A poisoned lock only means another thread panicked; the records are still fine
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn holds(&self) -> MutexGuard<'_, HashMap<AuthorizationId, Money>> {
+        // A poisoned lock only means another thread panicked; the holds are still fine
+        self.holds.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn record(&self, amount: Money, timestamp: SystemTime) {
+        let mut records = self.records();
+        let sequence = records.len() as u64 + 1;
+        records.push(ChargeRecord {
+            sequence,
+            amount,
+            timestamp,
+        });
+    }
+}
+
+impl<P: PaymentGateway> PaymentGateway for RecordingPaymentGateway<P> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let receipt = self.inner.charge_with_ctx(ctx, amount, idempotency_key)?;
+        self.record(receipt.amount, receipt.timestamp);
+        Ok(receipt)
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let receipt = self
+            .inner
+            .charge_with_metadata(ctx, amount, idempotency_key, metadata)?;
+        self.record(receipt.amount, receipt.timestamp);
+        Ok(receipt)
+    }
+
+    /// Remembers the amount held, to record it when it's captured.
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        let auth = self
+            .inner
+            .authorize_with_ctx(ctx, amount, idempotency_key)?;
+        self.holds().insert(auth.clone(), amount);
+        Ok(auth)
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.inner.capture_with_ctx(ctx, auth)?;
+        // An authorization made before we were wrapped around the gateway:
+        // the capture went through, but we can't say for how much
+        if let Some(amount) = self.holds().remove(auth) {
+            self.record(amount, SystemTime::now());
+        }
+        Ok(())
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.inner.void_with_ctx(ctx, auth)?;
+        self.holds().remove(auth);
+        Ok(())
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.inner.refund_with_ctx(ctx, transaction_id, amount)
+    }
+}
+
+impl<P: HealthCheck> HealthCheck for RecordingPaymentGateway<P> {
+    // Only as healthy as the gateway underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charges_and_captures_are_recorded_in_sequence() {
+        let gateway = RecordingPaymentGateway::new();
+
+        gateway.charge(Money(1000), None).unwrap();
+        let auth = gateway.authorize(Money(2500), None).unwrap();
+        gateway.capture(&auth).unwrap();
+
+        let charges = gateway.charges();
+        let seen: Vec<(u64, Money)> = charges.iter().map(|c| (c.sequence, c.amount)).collect();
+        assert_eq!(seen, vec![(1, Money(1000)), (2, Money(2500))]);
+        assert!(charges[0].timestamp <= charges[1].timestamp);
+    }
+
+    #[test]
+    fn voided_authorizations_and_failed_calls_are_not_charges() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let gateway = RecordingPaymentGateway::new().with_records(Arc::clone(&records));
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        gateway.void(&auth).unwrap();
+        // Already voided: the mock refuses
+        assert!(gateway.capture(&auth).is_err());
+
+        assert!(gateway.charges().is_empty());
+        assert!(records.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,326 @@
+// =============================================================================
+// Retrying Payment Gateway - A Decorator
+// =============================================================================
+//
+// Payment providers have bad minutes: a 503 here, a timeout there.
+// Failing the whole checkout for that is a poor experience.
+//
+// This adapter WRAPS another PaymentGateway and retries the calls that
+// failed for a transient reason (see `OrderError::is_transient`).
+// It implements the same port, so OrderService can't tell the difference:
+//
+//     OrderService -> RetryingPaymentGateway -> StripePaymentGateway
+//
+// That's the Decorator pattern, and it's why ports are so handy:
+// cross-cutting behavior is added without touching the core.
+//
+// Two rules keep retries safe:
+// - A permanent failure (card declined, bad request) is NEVER retried.
+//   Retrying a decline only annoys the bank.
+// - Every attempt reuses the same idempotency key / authorization ID, so
+//   the provider can't charge twice if an "error" actually went through.
+//
+// Refunds are deliberately NOT retried here: refunds carry no idempotency
+// key in our port, so a retried refund could pay the customer back twice.
+
+use domain::{
+    AuthorizationId, ChargeMetadata, HealthCheck, HealthStatus, IdempotencyKey, Money, OrderError,
+    PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::time::Duration;
+
+/// How long to wait between two attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    /// Always wait the same amount of time.
+    Fixed(Duration),
+    /// Double the wait after every failure, up to `max`.
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl Backoff {
+    /// The wait after the given failed attempt (1 for the first attempt).
+    #[must_use]
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        match *self {
+            Self::Fixed(delay) => delay,
+            Self::Exponential { initial, max } => {
+                let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+                initial.saturating_mul(factor).min(max)
+            }
+        }
+    }
+}
+
+/// How many times to try a payment call, and how long to wait in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. Zero counts as one.
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, waiting 100ms then 200ms.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(100),
+                max: Duration::from_secs(2),
+            },
+        }
+    }
+}
+
+/// Payment gateway decorator that retries transient failures.
+///
+/// `S` is the sleeper called between attempts. Production code uses
+/// [`std::thread::sleep`]; tests inject a closure that only records the delay.
+#[derive(Debug)]
+pub struct RetryingPaymentGateway<P, S = fn(Duration)> {
+    inner: P,
+    policy: RetryPolicy,
+    sleeper: S,
+}
+
+impl<P: PaymentGateway> RetryingPaymentGateway<P> {
+    /// Wraps `inner`, sleeping the current thread between attempts.
+    #[must_use]
+    pub fn new(inner: P, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            sleeper: std::thread::sleep,
+        }
+    }
+}
+
+impl<P: PaymentGateway, S: Fn(Duration)> RetryingPaymentGateway<P, S> {
+    /// Wraps `inner` with a custom sleeper.
+    #[must_use]
+    pub fn with_sleeper(inner: P, policy: RetryPolicy, sleeper: S) -> Self {
+        Self {
+            inner,
+            policy,
+            sleeper,
+        }
+    }
+
+    /// The wrapped gateway.
+    #[must_use]
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Runs `call` until it succeeds, fails permanently, or runs out of attempts.
+    ///
+    /// The error of the last attempt is the one returned.
+    fn retry<T>(&self, call: impl Fn(&P) -> Result<T, OrderError>) -> Result<T, OrderError> {
+        let max_attempts = self.policy.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match call(&self.inner) {
+                Err(e) if e.is_transient() && attempt < max_attempts => {
+                    let delay = self.policy.backoff.delay_for(attempt);
+                    println!("  [Retry] Attempt {attempt} failed ({e}), retrying in {delay:?}");
+                    (self.sleeper)(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<P: PaymentGateway, S: Fn(Duration)> PaymentGateway for RetryingPaymentGateway<P, S> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.retry(|inner| inner.charge(amount, idempotency_key))
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.retry(|inner| inner.authorize(amount, idempotency_key))
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.retry(|inner| inner.capture(auth))
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.retry(|inner| inner.void(auth))
+    }
+
+    /// Not retried: see the module comment.
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.inner.refund(transaction_id, amount)
+    }
+
+    // The *_with_ctx calls retry the same way, passing the context along
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.retry(|inner| inner.charge_with_ctx(ctx, amount, idempotency_key))
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.retry(|inner| inner.charge_with_metadata(ctx, amount, idempotency_key, metadata))
+    }
+
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.retry(|inner| inner.authorize_with_ctx(ctx, amount, idempotency_key))
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.retry(|inner| inner.capture_with_ctx(ctx, auth))
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.retry(|inner| inner.void_with_ctx(ctx, auth))
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.inner.refund_with_ctx(ctx, transaction_id, amount)
+    }
+}
+
+impl<P: HealthCheck, S> HealthCheck for RetryingPaymentGateway<P, S> {
+    // Only as healthy as the gateway underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FlakyPaymentGateway, MockPaymentGateway};
+    use domain::PaymentFailureReason;
+    use std::cell::RefCell;
+
+    /// Fails `failures` times with `reason`, then lets the mock answer.
+    fn flaky(failures: u32, reason: PaymentFailureReason) -> FlakyPaymentGateway {
+        FlakyPaymentGateway::failing_first(MockPaymentGateway::new(), failures).with_reason(reason)
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(100),
+                max: Duration::from_millis(250),
+            },
+        }
+    }
+
+    #[test]
+    fn transient_failures_are_retried_until_success() {
+        let delays = RefCell::new(Vec::new());
+        let gateway = RetryingPaymentGateway::with_sleeper(
+            flaky(2, PaymentFailureReason::Unavailable),
+            policy(3),
+            |d| delays.borrow_mut().push(d),
+        );
+
+        let receipt = gateway.charge(Money(1000), None).unwrap();
+
+        assert_eq!(receipt.amount, Money(1000));
+        assert_eq!(gateway.inner().counters().calls, 3);
+        assert_eq!(
+            *delays.borrow(),
+            vec![Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[test]
+    fn declines_are_never_retried() {
+        let delays = RefCell::new(Vec::new());
+        let gateway = RetryingPaymentGateway::with_sleeper(
+            flaky(1, PaymentFailureReason::Declined),
+            policy(3),
+            |d| delays.borrow_mut().push(d),
+        );
+
+        let result = gateway.charge(Money(1000), None);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+        assert_eq!(gateway.inner().counters().calls, 1);
+        assert!(delays.borrow().is_empty());
+    }
+
+    #[test]
+    fn exhausted_retries_surface_the_last_error() {
+        let gateway = RetryingPaymentGateway::with_sleeper(
+            flaky(5, PaymentFailureReason::Timeout),
+            policy(3),
+            |_| {},
+        );
+
+        let result = gateway.authorize(Money(1000), None);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Timeout))
+        ));
+        assert_eq!(gateway.inner().counters().calls, 3);
+    }
+
+    #[test]
+    fn refunds_are_not_retried() {
+        let gateway = RetryingPaymentGateway::with_sleeper(
+            flaky(1, PaymentFailureReason::Unavailable),
+            policy(3),
+            |_| {},
+        );
+
+        assert!(gateway.refund("flaky_txn", Money(100)).is_err());
+        assert_eq!(gateway.inner().counters().calls, 1);
+    }
+
+    #[test]
+    fn exponential_backoff_is_capped() {
+        let backoff = policy(5).backoff;
+
+        assert_eq!(backoff.delay_for(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay_for(2), Duration::from_millis(200));
+        assert_eq!(backoff.delay_for(3), Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,279 @@
+// =============================================================================
+// Scripted Payment Gateway - One Answer per Call, Written in Advance
+// =============================================================================
+//
+// Mock, Failing and Flaky gateways each follow one rule. A multi-step flow
+// needs a different answer at each step:
+//
+//     authorize: ok, capture: declined        -> is the hold released?
+//     authorize: unavailable, authorize: ok   -> did the retry reuse the key?
+//
+// ScriptedPaymentGateway takes those answers as a list, and hands them out in
+// order, one per `charge`, `authorize` or `capture`. Voids and refunds always
+// succeed: they are how a flow cleans up, and the test wants to see them
+// happen rather than script them.
+//
+// Running out of script panics, naming the call: a flow making more payment
+// calls than the test expected is a bug worth a loud failure. The other way
+// round, `remaining()` tells whether every answer was used.
+//
+// Every call is recorded, with its arguments, so a test can check what the
+// flow sent as well as how it reacted.
+
+use domain::{
+    AuthorizationId, HealthCheck, HealthStatus, IdempotencyKey, Money, OrderError,
+    PaymentFailureReason, PaymentGateway, PaymentReceipt, PaymentStatus,
+};
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// What the scripted gateway answers to one call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptedResponse {
+    Succeed,
+    Fail(PaymentFailureReason),
+    /// Panics, like a bug in a provider's SDK would.
+    Panic,
+    /// Blocks the thread for the duration, then succeeds: a slow provider.
+    DelayThenSucceed(Duration),
+}
+
+/// A call received by the scripted gateway, with its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptedCall {
+    Charge {
+        amount: Money,
+        idempotency_key: Option<IdempotencyKey>,
+    },
+    Authorize {
+        amount: Money,
+        idempotency_key: Option<IdempotencyKey>,
+    },
+    Capture(AuthorizationId),
+    Void(AuthorizationId),
+    Refund {
+        transaction_id: String,
+        amount: Money,
+    },
+}
+
+/// Payment gateway answering each call from a script, for multi-step tests.
+#[derive(Debug)]
+pub struct ScriptedPaymentGateway {
+    state: Mutex<ScriptState>,
+}
+
+#[derive(Debug)]
+struct ScriptState {
+    script: VecDeque<ScriptedResponse>,
+    calls: Vec<ScriptedCall>,
+}
+
+impl ScriptedPaymentGateway {
+    /// Creates a gateway answering with `script`, first entry first.
+    #[must_use]
+    pub fn new(script: Vec<ScriptedResponse>) -> Self {
+        Self {
+            state: Mutex::new(ScriptState {
+                script: script.into(),
+                calls: Vec::new(),
+            }),
+        }
+    }
+
+    /// Every call received so far, oldest first.
+    #[must_use]
+    pub fn calls(&self) -> Vec<ScriptedCall> {
+        self.state().calls.clone()
+    }
+
+    /// Number of answers not used yet. Zero once the flow followed the script.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.state().script.len()
+    }
+
+    fn state(&self) -> MutexGuard<'_, ScriptState> {
+        // A poisoned lock only means a scripted Panic went off; the script is still fine
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records `call`, then plays the next answer of the script.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the script is exhausted, or if the answer is
+    /// [`ScriptedResponse::Panic`].
+    fn answer(&self, call: ScriptedCall) -> Result<usize, OrderError> {
+        let (response, number) = {
+            let mut state = self.state();
+            let response = state.script.pop_front();
+            let Some(response) = response else {
+                panic!(
+                    "ScriptedPaymentGateway: script exhausted, no answer for call #{} ({call:?})",
+                    state.calls.len() + 1
+                );
+            };
+            state.calls.push(call);
+            (response, state.calls.len())
+        };
+        // The lock is released: a scripted panic or delay doesn't hold it
+        match response {
+            ScriptedResponse::Succeed => Ok(number),
+            ScriptedResponse::Fail(reason) => Err(OrderError::PaymentFailed(reason)),
+            ScriptedResponse::Panic => {
+                panic!("ScriptedPaymentGateway: scripted panic at call #{number}")
+            }
+            ScriptedResponse::DelayThenSucceed(delay) => {
+                std::thread::sleep(delay);
+                Ok(number)
+            }
+        }
+    }
+}
+
+impl PaymentGateway for ScriptedPaymentGateway {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let number = self.answer(ScriptedCall::Charge {
+            amount,
+            idempotency_key: idempotency_key.cloned(),
+        })?;
+        Ok(PaymentReceipt {
+            transaction_id: format!("scripted_txn_{number}"),
+            provider: "scripted".to_string(),
+            amount,
+            timestamp: SystemTime::now(),
+            status: PaymentStatus::Settled,
+        })
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        let number = self.answer(ScriptedCall::Authorize {
+            amount,
+            idempotency_key: idempotency_key.cloned(),
+        })?;
+        Ok(AuthorizationId(format!("scripted_auth_{number}")))
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.answer(ScriptedCall::Capture(auth.clone()))?;
+        Ok(())
+    }
+
+    /// Always succeeds, without using the script.
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.state().calls.push(ScriptedCall::Void(auth.clone()));
+        Ok(())
+    }
+
+    /// Always succeeds, without using the script.
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.state().calls.push(ScriptedCall::Refund {
+            transaction_id: transaction_id.to_string(),
+            amount,
+        });
+        Ok(())
+    }
+}
+
+impl HealthCheck for ScriptedPaymentGateway {
+    /// Degraded while failures are left in the script: the next calls may
+    /// hit them.
+    fn health(&self) -> HealthStatus {
+        let failures = self
+            .state()
+            .script
+            .iter()
+            .filter(|response| {
+                matches!(
+                    response,
+                    ScriptedResponse::Fail(_) | ScriptedResponse::Panic
+                )
+            })
+            .count();
+        if failures == 0 {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Degraded(format!("failures left in the script: {failures}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn answers_follow_the_script_and_calls_are_recorded() {
+        let gateway = ScriptedPaymentGateway::new(vec![
+            ScriptedResponse::Succeed,
+            ScriptedResponse::Fail(PaymentFailureReason::Declined),
+        ]);
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        let captured = gateway.capture(&auth);
+        gateway.void(&auth).unwrap();
+
+        assert!(matches!(
+            captured,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+        assert_eq!(gateway.remaining(), 0);
+        assert_eq!(
+            gateway.calls(),
+            vec![
+                ScriptedCall::Authorize {
+                    amount: Money(1000),
+                    idempotency_key: None
+                },
+                ScriptedCall::Capture(auth.clone()),
+                ScriptedCall::Void(auth),
+            ]
+        );
+    }
+
+    #[test]
+    fn delay_then_succeed_waits() {
+        let gateway = ScriptedPaymentGateway::new(vec![ScriptedResponse::DelayThenSucceed(
+            Duration::from_millis(5),
+        )]);
+        let start = std::time::Instant::now();
+
+        let receipt = gateway.charge(Money(700), None).unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(5));
+        assert_eq!(receipt.transaction_id, "scripted_txn_1");
+    }
+
+    #[test]
+    fn running_out_of_script_panics() {
+        let gateway = ScriptedPaymentGateway::new(vec![ScriptedResponse::Succeed]);
+        gateway.charge(Money(100), None).unwrap();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| gateway.charge(Money(100), None)));
+
+        assert!(result.is_err());
+        // The call that found no answer isn't recorded
+        assert_eq!(gateway.calls().len(), 1);
+    }
+
+    #[test]
+    fn scripted_panic_panics() {
+        let gateway = ScriptedPaymentGateway::new(vec![ScriptedResponse::Panic]);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| gateway.authorize(Money(100), None)));
+
+        assert!(result.is_err());
+        assert_eq!(gateway.remaining(), 0);
+    }
+}
@@ -0,0 +1,226 @@
+// =============================================================================
+// Slow Payment Gateway - A Provider Taking Its Time
+// =============================================================================
+//
+// A card payment is a round trip to the provider, who makes its own round
+// trip to the bank. Under load, that's seconds. The mock answers at once, so
+// nothing in our tests notices how much of a checkout is spent waiting.
+//
+// SlowPaymentGateway wraps any gateway and waits before every call:
+//
+//     OrderService -> RetryingPaymentGateway -> SlowPaymentGateway -> Stripe
+//
+// Like RetryingPaymentGateway, it waits through a sleeper: `std::thread::sleep`
+// by default, a closure that only records the delay in unit tests.
+//
+// Each call can also be timed, sleep included, into a shared Vec. Give it the
+// same Vec as a SlowOrderRepository and it reports where a whole use case
+// spent its time, storage and payment interleaved:
+//
+//     [("authorize", 50ms), ("insert", 20ms), ("capture", 50ms), ("update", 20ms)]
+
+use domain::{
+    AuthorizationId, ChargeMetadata, HealthCheck, HealthStatus, IdempotencyKey, Money, OrderError,
+    PaymentGateway, PaymentReceipt, RequestContext,
+};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Per-call timings: the name of the call, and how long it took.
+type Timings = Arc<Mutex<Vec<(&'static str, Duration)>>>;
+
+/// Payment gateway decorator adding latency to every call.
+///
+/// `S` is the sleeper. Production-like demos use [`std::thread::sleep`];
+/// tests inject a closure that only records the delay.
+#[derive(Debug)]
+pub struct SlowPaymentGateway<P, S = fn(Duration)> {
+    inner: P,
+    delay: Duration,
+    sleeper: S,
+    timings: Option<Timings>,
+}
+
+impl<P: PaymentGateway> SlowPaymentGateway<P> {
+    /// Wraps `inner`, sleeping the current thread `delay` before every call.
+    #[must_use]
+    pub fn new(inner: P, delay: Duration) -> Self {
+        Self::with_sleeper(inner, delay, std::thread::sleep)
+    }
+}
+
+impl<P: PaymentGateway, S: Fn(Duration)> SlowPaymentGateway<P, S> {
+    /// Wraps `inner` with a custom sleeper.
+    #[must_use]
+    pub fn with_sleeper(inner: P, delay: Duration, sleeper: S) -> Self {
+        Self {
+            inner,
+            delay,
+            sleeper,
+            timings: None,
+        }
+    }
+
+    /// Records how long each call took, sleep included, into `timings`.
+    #[must_use]
+    pub fn with_timings(mut self, timings: Arc<Mutex<Vec<(&'static str, Duration)>>>) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// The wrapped gateway.
+    #[must_use]
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Waits, then makes the call, timing both.
+    fn slowly<T>(&self, call: &'static str, run: impl FnOnce(&P) -> T) -> T {
+        let start = Instant::now();
+        (self.sleeper)(self.delay);
+        let result = run(&self.inner);
+        if let Some(timings) = &self.timings {
+            timings
+                .lock()
+                // A poisoned lock only means another thread panicked; the timings are still fine
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((call, start.elapsed()));
+        }
+        result
+    }
+}
+
+impl<P: PaymentGateway, S: Fn(Duration)> PaymentGateway for SlowPaymentGateway<P, S> {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize_with_ctx(&RequestContext::default(), amount, idempotency_key)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.capture_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.void_with_ctx(&RequestContext::default(), auth)
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.refund_with_ctx(&RequestContext::default(), transaction_id, amount)
+    }
+
+    fn charge_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.slowly("charge", |inner| {
+            inner.charge_with_ctx(ctx, amount, idempotency_key)
+        })
+    }
+
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.slowly("charge", |inner| {
+            inner.charge_with_metadata(ctx, amount, idempotency_key, metadata)
+        })
+    }
+
+    fn authorize_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.slowly("authorize", |inner| {
+            inner.authorize_with_ctx(ctx, amount, idempotency_key)
+        })
+    }
+
+    fn capture_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.slowly("capture", |inner| inner.capture_with_ctx(ctx, auth))
+    }
+
+    fn void_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.slowly("void", |inner| inner.void_with_ctx(ctx, auth))
+    }
+
+    fn refund_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.slowly("refund", |inner| {
+            inner.refund_with_ctx(ctx, transaction_id, amount)
+        })
+    }
+}
+
+impl<P: HealthCheck, S> HealthCheck for SlowPaymentGateway<P, S> {
+    // Only as healthy as the gateway underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockPaymentGateway;
+    use std::cell::RefCell;
+
+    #[test]
+    fn every_call_waits_before_going_through() {
+        let delays = RefCell::new(Vec::new());
+        let gateway = SlowPaymentGateway::with_sleeper(
+            MockPaymentGateway::new(),
+            Duration::from_secs(2),
+            |d| delays.borrow_mut().push(d),
+        );
+
+        let auth = gateway.authorize(Money(1000), None).unwrap();
+        gateway.capture(&auth).unwrap();
+
+        assert_eq!(gateway.inner().capture_count(), 1);
+        assert_eq!(*delays.borrow(), vec![Duration::from_secs(2); 2]);
+    }
+
+    #[test]
+    fn timings_include_the_delay() {
+        let timings = Arc::new(Mutex::new(Vec::new()));
+        let gateway = SlowPaymentGateway::new(MockPaymentGateway::new(), Duration::from_millis(5))
+            .with_timings(Arc::clone(&timings));
+
+        gateway.charge(Money(1000), None).unwrap();
+
+        let timings = timings.lock().unwrap();
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].0, "charge");
+        assert!(timings[0].1 >= Duration::from_millis(5));
+    }
+}
@@ -4,12 +4,57 @@
 //
 // This adapter simulates calling Stripe's payment API.
 // In production, we'd add stripe-rust to Cargo.toml and make real API calls.
+// That's StripeApiPaymentGateway (stripe_api.rs, `stripe` feature); this one
+// stays, for the demos and the tests that have no Stripe account to talk to.
 //
 // The adapter's job is to TRANSLATE between:
 // - Domain concepts (Money, OrderError)
 // - External API concepts (stripe::Amount, stripe::Error)
+//
+// PROVIDER RULES:
+// ---------------
+// Stripe refuses charges under 50 cents (for USD). That's Stripe's rule, not
+// the shop's: another provider has another minimum. So the check lives here,
+// and the simulation refuses what the real API would, instead of
+// "succeeding" on Money(0) until production says otherwise.
+//
+// Metadata has limits too: at most 50 keys, values of at most 500
+// characters. Over them, Stripe answers 400 `invalid_request_error`, which
+// becomes `InvalidRequest` like any request Stripe won't take.
+//
+// Money has no currency yet: everything is USD. Once it does, currencies
+// Stripe doesn't support get refused here too, and the minimum depends on
+// the currency.
+//
+// ASYNC SETTLEMENT:
+// -----------------
+// Some payments only settle later: the charge answers "pending", and a
+// webhook says hours later whether the money arrived. Built with
+// `with_async_settlement`, the simulation answers every charge that way, so
+// the application's webhook path (`handle_payment_confirmation`) gets
+// exercised without a real bank.
+//
+// The simulated requests go to stdout, or to a `Logger` when one is plugged
+// in.
+
+use domain::{
+    AuthorizationId, ChargeMetadata, HealthCheck, HealthStatus, IdempotencyKey, Logger, Money,
+    OrderError, PaymentFailureReason, PaymentGateway, PaymentReceipt, PaymentStatus,
+    RequestContext, money,
+};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The smallest amount Stripe charges in USD.
+const MINIMUM_CHARGE: Money = money!(0, 50);
 
-use domain::{Money, OrderError, PaymentGateway};
+/// The most metadata keys Stripe keeps on a charge.
+const MAX_METADATA_KEYS: usize = 50;
+
+/// The longest metadata value Stripe keeps, in characters.
+const MAX_METADATA_VALUE_CHARS: usize = 500;
 
 /// Simulated Stripe payment gateway.
 ///
@@ -19,8 +64,121 @@ use domain::{Money, OrderError, PaymentGateway};
 /// 3. Handle 3D Secure if needed
 /// 4. Deal with webhooks for async confirmation
 /// 5. Translate Stripe errors to domain errors
-#[derive(Debug, Default, Clone, Copy)]
-pub struct StripePaymentGateway;
+#[derive(Default, Clone)]
+pub struct StripePaymentGateway {
+    async_settlement: bool,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
+
+// Manual impl: the logger is a trait object with no Debug of its own.
+impl fmt::Debug for StripePaymentGateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StripePaymentGateway")
+            .field("async_settlement", &self.async_settlement)
+            .field("logger", &self.logger.is_some())
+            .finish()
+    }
+}
+
+impl StripePaymentGateway {
+    /// Creates a gateway whose charges settle at once.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            async_settlement: false,
+            logger: None,
+        }
+    }
+
+    /// Leaves every charge [`PaymentStatus::Pending`], for a webhook to settle.
+    #[must_use]
+    pub const fn with_async_settlement(mut self) -> Self {
+        self.async_settlement = true;
+        self
+    }
+
+    /// Sends the simulated requests to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    /// `POST /charges`, with `metadata[key]=value` for each key.
+    fn create_charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.check_amount(amount)?;
+        self.check_metadata(metadata)?;
+        let transaction_id = next_stripe_id("ch_");
+        self.log(&format!(
+            "[Stripe API] POST /charges amount={amount}{}{} -> {transaction_id}",
+            metadata_params(metadata),
+            idempotency_header(idempotency_key)
+        ));
+        let status = if self.async_settlement {
+            PaymentStatus::Pending
+        } else {
+            PaymentStatus::Settled
+        };
+        Ok(PaymentReceipt {
+            transaction_id,
+            provider: "stripe".to_string(),
+            amount,
+            timestamp: SystemTime::now(),
+            status,
+        })
+    }
+
+    /// Refuses what Stripe would: amounts under the minimum, zero included.
+    fn check_amount(&self, amount: Money) -> Result<(), OrderError> {
+        if amount.0 < MINIMUM_CHARGE.0 {
+            self.log(&format!(
+                "[Stripe API] 400 amount_too_small: {amount} is under {MINIMUM_CHARGE}"
+            ));
+            return Err(OrderError::PaymentFailed(
+                PaymentFailureReason::AmountTooSmall,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Refuses what Stripe would: too many keys, or a value too long.
+    fn check_metadata(&self, metadata: &ChargeMetadata) -> Result<(), OrderError> {
+        let refused = |message: String| {
+            self.log(&format!(
+                "[Stripe API] 400 invalid_request_error: {message}"
+            ));
+            Err(OrderError::PaymentFailed(
+                PaymentFailureReason::InvalidRequest,
+            ))
+        };
+        if metadata.len() > MAX_METADATA_KEYS {
+            return refused(format!(
+                "{} metadata keys, at most {MAX_METADATA_KEYS} allowed",
+                metadata.len()
+            ));
+        }
+        for (key, value) in metadata.entries() {
+            if value.chars().count() > MAX_METADATA_VALUE_CHARS {
+                return refused(format!(
+                    "metadata[{key}] is over {MAX_METADATA_VALUE_CHARS} characters"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
 
 // In a real implementation:
 //
@@ -41,7 +199,11 @@ impl PaymentGateway for StripePaymentGateway {
     ///
     /// Real implementation:
     /// ```ignore
-    /// async fn charge(&self, amount: Money) -> Result<(), OrderError> {
+    /// async fn charge(
+    ///     &self,
+    ///     amount: Money,
+    ///     idempotency_key: Option<&IdempotencyKey>,
+    /// ) -> Result<PaymentReceipt, OrderError> {
     ///     let charge = CreateCharge {
     ///         amount: amount.0 as i64,  // Stripe uses cents too!
     ///         currency: "usd",
@@ -49,33 +211,311 @@ impl PaymentGateway for StripePaymentGateway {
     ///         ..Default::default()
     ///     };
     ///
-    ///     self.client
+    ///     // Stripe deduplicates retried requests natively via this header
+    ///     let client = match idempotency_key {
+    ///         Some(key) => self.client.with_strategy(RequestStrategy::Idempotent(key.0.clone())),
+    ///         None => self.client.clone(),
+    ///     };
+    ///
+    ///     let charge = client
     ///         .charges()
     ///         .create(charge)
     ///         .await
-    ///         .map_err(|_| OrderError::PaymentFailed)?;
+    ///         .map_err(translate_stripe_error)?;
     ///
-    ///     Ok(())
+    ///     Ok(PaymentReceipt {
+    ///         transaction_id: charge.id.to_string(),  // "ch_3MmlLrLkdIwHu7ix0snN0B15"
+    ///         provider: "stripe".to_string(),
+    ///         amount,
+    ///         timestamp: SystemTime::now(),
+    ///         status: PaymentStatus::Settled,  // or Pending, see charge.status
+    ///     })
     /// }
     /// ```
     ///
-    /// Note: Stripe errors become `OrderError::PaymentFailed`.
+    /// Note: Stripe errors become `OrderError::PaymentFailed(reason)`:
+    /// `card_declined` -> Declined, HTTP 5xx -> Unavailable, and so on.
     /// The application layer never sees stripe::Error!
-    fn charge(&self, amount: Money) -> Result<(), OrderError> {
-        println!("  [Stripe API] POST /charges amount={amount}");
+    ///
+    /// Amounts under 50 cents fail with `AmountTooSmall`, as at Stripe.
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.create_charge(amount, idempotency_key, &ChargeMetadata::new())
+    }
+
+    /// Charges with the metadata on the charge, where Stripe's dashboard
+    /// and its exports show it.
+    ///
+    /// Real implementation: `metadata: Some(metadata.entries().iter().cloned().collect())`
+    /// in the same `CreateCharge`.
+    ///
+    /// More than 50 keys, or a value over 500 characters, fail with
+    /// `InvalidRequest`, as at Stripe.
+    fn charge_with_metadata(
+        &self,
+        _ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.create_charge(amount, idempotency_key, metadata)
+    }
+
+    /// Places a hold via a manually captured PaymentIntent.
+    ///
+    /// Real implementation:
+    /// ```ignore
+    /// let intent = PaymentIntent::create(&self.client, CreatePaymentIntent {
+    ///     amount: amount.0 as i64,
+    ///     currency: Currency::USD,
+    ///     capture_method: Some(PaymentIntentCaptureMethod::Manual),
+    ///     ..Default::default()
+    /// })
+    /// .await
+    /// .map_err(translate_stripe_error)?;
+    ///
+    /// Ok(AuthorizationId(intent.id.to_string()))  // "pi_..."
+    /// ```
+    ///
+    /// Same minimum as a charge: a hold is a charge not captured yet.
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.check_amount(amount)?;
+        let auth = AuthorizationId(next_stripe_id("pi_"));
+        self.log(&format!(
+            "[Stripe API] POST /payment_intents amount={amount} capture_method=manual{} -> {auth}",
+            idempotency_header(idempotency_key)
+        ));
+        Ok(auth)
+    }
+
+    /// Captures a PaymentIntent. Stripe itself tracks which intents are still open.
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[Stripe API] POST /payment_intents/{auth}/capture"
+        ));
+        Ok(())
+    }
+
+    /// Cancels a PaymentIntent, releasing the hold on the card.
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.log(&format!("[Stripe API] POST /payment_intents/{auth}/cancel"));
+        Ok(())
+    }
+
+    /// Refunds a charge or a captured PaymentIntent, fully or partially.
+    ///
+    /// Real implementation:
+    /// ```ignore
+    /// Refund::create(&self.client, CreateRefund {
+    ///     payment_intent: Some(transaction_id.parse()?),
+    ///     amount: Some(amount.0 as i64),
+    ///     ..Default::default()
+    /// })
+    /// .await
+    /// .map_err(translate_stripe_error)?;
+    /// ```
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[Stripe API] POST /refunds payment_intent={transaction_id} amount={amount}"
+        ));
         Ok(())
     }
 }
 
+/// Formats the metadata as the form fields of the simulated request log.
+fn metadata_params(metadata: &ChargeMetadata) -> String {
+    metadata
+        .entries()
+        .iter()
+        .map(|(key, value)| format!(" metadata[{key}]={value}"))
+        .collect()
+}
+
+/// Formats the `Idempotency-Key` header for the simulated request log.
+fn idempotency_header(key: Option<&IdempotencyKey>) -> String {
+    key.map(|k| format!(" Idempotency-Key={k}"))
+        .unwrap_or_default()
+}
+
+// Shared by every StripePaymentGateway so two gateways never hand out the same ID.
+static CHARGE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Generates a Stripe-looking ID: `prefix` followed by 24 base62 characters.
+///
+/// Real Stripe IDs are opaque random strings. We mix the clock with a counter
+/// and run a small xorshift over it. Not cryptographic, just realistic.
+fn next_stripe_id(prefix: &str) -> String {
+    const ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let count = CHARGE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut state = (nanos ^ count.wrapping_mul(0x9E37_79B9_7F4A_7C15)) | 1;
+
+    let mut id = String::from(prefix);
+    for _ in 0..24 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        id.push(ALPHABET[(state % 62) as usize] as char);
+    }
+    id
+}
+
+impl HealthCheck for StripePaymentGateway {
+    // Simulated: there is no Stripe to reach. The real one is StripeApiPaymentGateway
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapters_repository::CapturingLogger;
 
     #[test]
     fn stripe_charge_succeeds() {
-        let gateway = StripePaymentGateway;
-        let result = gateway.charge(Money(5000));
+        let gateway = StripePaymentGateway::new();
+        let receipt = gateway.charge(Money(5000), None).unwrap();
+
+        assert_eq!(receipt.amount, Money(5000));
+        assert_eq!(receipt.status, PaymentStatus::Settled);
+    }
+
+    #[test]
+    fn stripe_with_async_settlement_leaves_charges_pending() {
+        let gateway = StripePaymentGateway::new().with_async_settlement();
+        let receipt = gateway.charge(Money(5000), None).unwrap();
+
+        assert_eq!(receipt.status, PaymentStatus::Pending);
+    }
+
+    #[test]
+    fn stripe_transaction_ids_look_like_charges() {
+        let gateway = StripePaymentGateway::new();
+
+        let first = gateway.charge(Money(5000), None).unwrap().transaction_id;
+        let second = gateway.charge(Money(5000), None).unwrap().transaction_id;
+
+        assert!(first.starts_with("ch_"));
+        assert_eq!(first.len(), 27);
+        assert!(first[3..].chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn stripe_authorize_capture_and_void() {
+        let gateway = StripePaymentGateway::new();
+
+        let captured = gateway.authorize(Money(5000), None).unwrap();
+        let voided = gateway.authorize(Money(5000), None).unwrap();
+
+        assert!(captured.0.starts_with("pi_"));
+        assert!(gateway.capture(&captured).is_ok());
+        assert!(gateway.void(&voided).is_ok());
+        assert!(gateway.refund(&captured.0, Money(1000)).is_ok());
+    }
+
+    #[test]
+    fn stripe_refuses_amounts_under_the_minimum() {
+        let gateway = StripePaymentGateway::new();
+        let too_small = |result: Result<_, OrderError>| {
+            matches!(
+                result,
+                Err(OrderError::PaymentFailed(
+                    PaymentFailureReason::AmountTooSmall
+                ))
+            )
+        };
+
+        assert!(too_small(gateway.charge(Money(0), None).map(|_| ())));
+        assert!(too_small(gateway.charge(Money(49), None).map(|_| ())));
+        assert!(too_small(gateway.authorize(Money(49), None).map(|_| ())));
+        // Exactly the minimum is fine
+        assert!(gateway.charge(Money(50), None).is_ok());
+        assert!(gateway.authorize(Money(50), None).is_ok());
+    }
+
+    #[test]
+    fn stripe_requests_go_to_the_logger() {
+        let logger = Arc::new(CapturingLogger::new());
+        let gateway = StripePaymentGateway::new().with_logger(logger.clone());
+
+        let _ = gateway.charge(Money(49), None);
+
+        let messages: Vec<_> = logger.lines().into_iter().map(|l| l.message).collect();
+        assert_eq!(
+            messages,
+            [format!(
+                "[Stripe API] 400 amount_too_small: {} is under {MINIMUM_CHARGE}",
+                Money(49)
+            )]
+        );
+    }
+
+    #[test]
+    fn stripe_puts_the_metadata_on_the_charge() {
+        let metadata = ChargeMetadata::new()
+            .with_entry("order_id", "42")
+            .with_entry("correlation_id", "req-7");
+
+        let receipt = StripePaymentGateway::new()
+            .charge_with_metadata(&RequestContext::default(), Money(5000), None, &metadata)
+            .unwrap();
+
+        assert!(receipt.transaction_id.starts_with("ch_"));
+        assert_eq!(
+            metadata_params(&metadata),
+            " metadata[order_id]=42 metadata[correlation_id]=req-7"
+        );
+        assert_eq!(metadata_params(&ChargeMetadata::new()), "");
+    }
+
+    #[test]
+    fn stripe_refuses_metadata_over_its_limits() {
+        let gateway = StripePaymentGateway::new();
+        let charge = |metadata: &ChargeMetadata| {
+            gateway.charge_with_metadata(&RequestContext::default(), Money(5000), None, metadata)
+        };
+        let keys = |count: usize| {
+            (0..count).fold(ChargeMetadata::new(), |metadata, n| {
+                metadata.with_entry(format!("key_{n}"), "value")
+            })
+        };
+        let invalid = |result: Result<PaymentReceipt, OrderError>| {
+            matches!(
+                result,
+                Err(OrderError::PaymentFailed(
+                    PaymentFailureReason::InvalidRequest
+                ))
+            )
+        };
+
+        assert!(charge(&keys(50)).is_ok());
+        assert!(invalid(charge(&keys(51))));
+        // Characters, not bytes: 500 of "é" are 1,000 bytes, and fine
+        let long = |length: usize| ChargeMetadata::new().with_entry("note", "é".repeat(length));
+        assert!(charge(&long(500)).is_ok());
+        assert!(invalid(charge(&long(501))));
+    }
+
+    #[test]
+    fn stripe_forwards_idempotency_key() {
+        let key = IdempotencyKey("checkout-42".to_string());
 
-        assert!(result.is_ok());
+        assert_eq!(
+            idempotency_header(Some(&key)),
+            " Idempotency-Key=checkout-42"
+        );
+        assert_eq!(idempotency_header(None), "");
     }
 }
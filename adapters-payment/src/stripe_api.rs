@@ -0,0 +1,614 @@
+// =============================================================================
+// Stripe API Payment Gateway - The Real Stripe Adapter
+// =============================================================================
+//
+// StripePaymentGateway shows, in its doc comments, what calling Stripe would
+// look like. This is that code, for real: HTTPS requests to the Charges API.
+// It sits behind the `stripe` feature, so the default build pulls in no HTTP
+// client:
+//
+//     STRIPE_TEST_KEY=sk_test_... cargo test -p adapters-payment --features stripe
+//
+// Without STRIPE_TEST_KEY, the tests that call Stripe return early: CI
+// without a key still passes.
+//
+// TEST OR LIVE:
+// -------------
+// Stripe tells test and live keys apart by their prefix (`sk_test_...`,
+// `sk_live_...`). The gateway is created for a `Mode`, and refuses a key of
+// the other one: a live key pasted in a test's environment is a `ConfigError`
+// at startup instead of a charge on a real card.
+//
+// THE CALLS:
+// ----------
+// Everything goes through the Charges API:
+// - charge:    POST /charges
+// - authorize: POST /charges with capture=false, a hold on the card
+// - capture:   POST /charges/{id}/capture
+// - void:      POST /refunds on the uncaptured charge, which releases the hold
+// - refund:    POST /refunds, fully or partially
+//
+// Our ports carry no card details yet, so every charge uses the same
+// `source`: `tok_visa` by default, one of Stripe's test cards. Stripe's own
+// test tokens also make a card fail on purpose (`tok_chargeDeclined`).
+//
+// A charge Stripe can't settle at once (a bank debit, a card under review)
+// comes back `pending`: so does its receipt, and a webhook tells the rest.
+//
+// Idempotency keys go in the `Idempotency-Key` header: Stripe answers a
+// retried request with the first answer instead of charging again.
+//
+// Charge metadata goes in `metadata[key]` form fields. Stripe checks its
+// limits (50 keys, 500-character values) itself, and refuses with an
+// `invalid_request_error`.
+//
+// ERROR TRANSLATION:
+// ------------------
+// Stripe's errors -> OrderError::PaymentFailed(reason), after logging the
+// original:
+// - card errors (HTTP 402, `card_declined`, `expired_card`...) -> Declined
+// - `processing_error`, `lock_timeout`, HTTP 429, HTTP 5xx     -> Unavailable
+// - `amount_too_small`                                         -> AmountTooSmall
+// - anything else (unknown charge, already captured, bad key)  -> InvalidRequest
+// - no answer in time                                          -> Timeout
+//
+// A request that never reached Stripe (no connection) is Unavailable. One
+// that may have reached it is Timeout: its outcome is unknown, so only a
+// retry with the same idempotency key is safe.
+//
+// The log lines go to stdout, or to a `Logger` when one is plugged in.
+//
+// SYNC PORT, BLOCKING CLIENT:
+// ---------------------------
+// Our ports are not async, so the adapter uses reqwest's blocking client. Like
+// SqlxOrderRepository, don't call it from inside an async runtime.
+
+use crate::ConfigError;
+use domain::{
+    AuthorizationId, ChargeMetadata, HealthCheck, HealthStatus, IdempotencyKey, Logger, Money,
+    OrderError, PaymentFailureReason, PaymentGateway, PaymentReceipt, PaymentStatus,
+    RequestContext,
+};
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const API_BASE: &str = "https://api.stripe.com/v1";
+
+/// Whether the gateway moves real money.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Stripe's test mode: `sk_test_` or `rk_test_` keys, test cards only.
+    Test,
+    /// Real cards, real money: `sk_live_` or `rk_live_` keys.
+    Live,
+}
+
+impl Mode {
+    const fn key_prefixes(self) -> [&'static str; 2] {
+        match self {
+            Self::Test => ["sk_test_", "rk_test_"],
+            Self::Live => ["sk_live_", "rk_live_"],
+        }
+    }
+
+    /// The setting a key of this mode is, for a [`ConfigError`].
+    const fn key_setting(self) -> &'static str {
+        match self {
+            Self::Test => "Stripe secret key (test mode)",
+            Self::Live => "Stripe secret key (live mode)",
+        }
+    }
+}
+
+/// What the gateway needs to talk to Stripe: a key and a connection pool,
+/// and where to log.
+struct StripeClient {
+    http: Client,
+    secret_key: String,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
+
+// Manual impl: the secret key must never end up in a log.
+impl fmt::Debug for StripeClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StripeClient")
+            .field("logger", &self.logger.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StripeClient {
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    /// POSTs `form` to `path`, and returns Stripe's JSON answer.
+    fn post(
+        &self,
+        path: &str,
+        form: &[(&str, String)],
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<Value, OrderError> {
+        let mut request = self
+            .http
+            .post(format!("{API_BASE}{path}"))
+            .basic_auth(&self.secret_key, None::<&str>)
+            .form(form);
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", &key.0);
+        }
+
+        let response = request.send().map_err(|e| {
+            self.log(&format!("[Stripe API] POST {path} failed: {e}"));
+            OrderError::PaymentFailed(transport_failure(&e))
+        })?;
+        let status = response.status().as_u16();
+        let body = response.text().map_err(|e| {
+            self.log(&format!(
+                "[Stripe API] POST {path} -> {status}, unreadable body: {e}"
+            ));
+            OrderError::PaymentFailed(PaymentFailureReason::Timeout)
+        })?;
+
+        if !(200..300).contains(&status) {
+            self.log(&format!("[Stripe API] POST {path} -> {status} {body}"));
+            return Err(OrderError::PaymentFailed(translate_error(status, &body)));
+        }
+        // Stripe did it, but we can't tell what it did: like a timeout, only a
+        // retry with the same key gets the answer back
+        serde_json::from_str(&body).map_err(|e| {
+            self.log(&format!(
+                "[Stripe API] POST {path} -> {status}, unexpected body: {e}"
+            ));
+            OrderError::PaymentFailed(PaymentFailureReason::Timeout)
+        })
+    }
+}
+
+/// Payment gateway calling Stripe's API.
+#[derive(Debug)]
+pub struct StripeApiPaymentGateway {
+    client: StripeClient,
+    mode: Mode,
+    source: String,
+}
+
+impl StripeApiPaymentGateway {
+    /// Creates a gateway charging with `secret_key`, which must be a key of
+    /// `mode`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Missing`] if `secret_key` is empty, and
+    /// [`ConfigError::Invalid`] if it isn't a `mode` key (a live key in test
+    /// mode, or the other way round). The error shows the key's prefix only.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS backend can't be initialized.
+    pub fn new(secret_key: &str, mode: Mode) -> Result<Self, ConfigError> {
+        if secret_key.trim().is_empty() {
+            return Err(ConfigError::Missing(mode.key_setting()));
+        }
+        if !mode
+            .key_prefixes()
+            .iter()
+            .any(|prefix| secret_key.starts_with(prefix))
+        {
+            return Err(ConfigError::Invalid {
+                setting: mode.key_setting(),
+                value: key_prefix(secret_key),
+            });
+        }
+        let http = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("the TLS backend should initialize");
+        Ok(Self {
+            client: StripeClient {
+                http,
+                secret_key: secret_key.to_string(),
+                logger: None,
+            },
+            mode,
+            source: "tok_visa".to_string(),
+        })
+    }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.client.logger = Some(logger);
+        self
+    }
+
+    /// Charges `source` (a token or card ID) instead of `tok_visa`.
+    #[must_use]
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = source.to_string();
+        self
+    }
+
+    /// Whether the gateway moves real money.
+    #[must_use]
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Creates a charge, captured at once or held (`capture = false`), and
+    /// returns Stripe's charge object.
+    fn create_charge(
+        &self,
+        amount: Money,
+        capture: bool,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<Value, OrderError> {
+        let metadata_fields: Vec<String> = metadata
+            .entries()
+            .iter()
+            .map(|(key, _)| format!("metadata[{key}]"))
+            .collect();
+        let mut form = vec![
+            ("amount", amount.0.to_string()),
+            ("currency", "usd".to_string()),
+            ("source", self.source.clone()),
+            ("capture", capture.to_string()),
+        ];
+        form.extend(
+            metadata_fields
+                .iter()
+                .zip(metadata.entries())
+                .map(|(field, (_, value))| (field.as_str(), value.clone())),
+        );
+        self.client.post("/charges", &form, idempotency_key)
+    }
+
+    /// Charges `amount`, and turns Stripe's charge object into a receipt.
+    fn charge_with(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        let charge = self.create_charge(amount, true, idempotency_key, metadata)?;
+        let transaction_id = object_id(&self.client, &charge)?;
+        let status = charge_status(&charge);
+        self.client.log(&format!(
+            "[Stripe API] Charged {amount} -> {transaction_id} ({status})"
+        ));
+        Ok(PaymentReceipt {
+            transaction_id,
+            provider: "stripe".to_string(),
+            amount,
+            timestamp: SystemTime::now(),
+            status,
+        })
+    }
+}
+
+impl PaymentGateway for StripeApiPaymentGateway {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with(amount, idempotency_key, &ChargeMetadata::new())
+    }
+
+    fn charge_with_metadata(
+        &self,
+        _ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with(amount, idempotency_key, metadata)
+    }
+
+    /// Places a hold: an uncaptured charge, whose ID is the authorization's.
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        let charge = self.create_charge(amount, false, idempotency_key, &ChargeMetadata::new())?;
+        let auth = AuthorizationId(object_id(&self.client, &charge)?);
+        self.client
+            .log(&format!("[Stripe API] Authorized {amount} -> {auth}"));
+        Ok(auth)
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.client
+            .post(&format!("/charges/{auth}/capture"), &[], None)?;
+        Ok(())
+    }
+
+    /// Refunds the uncaptured charge: Stripe releases the hold.
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.client
+            .post("/refunds", &[("charge", auth.0.clone())], None)?;
+        Ok(())
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.client.post(
+            "/refunds",
+            &[
+                ("charge", transaction_id.to_string()),
+                ("amount", amount.0.to_string()),
+            ],
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+/// The `id` of the object Stripe returned.
+fn object_id(client: &StripeClient, object: &Value) -> Result<String, OrderError> {
+    object["id"].as_str().map(str::to_string).ok_or_else(|| {
+        client.log(&format!("[Stripe API] Answer without an id: {object}"));
+        OrderError::PaymentFailed(PaymentFailureReason::Timeout)
+    })
+}
+
+/// What a key shows of itself in an error: its `sk_test_`-like prefix.
+fn key_prefix(secret_key: &str) -> String {
+    match secret_key.splitn(3, '_').collect::<Vec<_>>()[..] {
+        [kind, mode, _] => format!("{kind}_{mode}_..."),
+        _ => "...".to_string(),
+    }
+}
+
+/// Where a charge stands: `succeeded`, `pending` (its webhook comes later)
+/// or `failed`.
+fn charge_status(charge: &Value) -> PaymentStatus {
+    match charge["status"].as_str() {
+        Some("succeeded") => PaymentStatus::Settled,
+        Some("failed") => PaymentStatus::Failed,
+        // A status we don't know yet: wait for the webhook rather than guess
+        _ => PaymentStatus::Pending,
+    }
+}
+
+/// Translates a request that got no answer.
+fn transport_failure(error: &reqwest::Error) -> PaymentFailureReason {
+    if error.is_connect() {
+        // Never reached Stripe: nothing happened
+        PaymentFailureReason::Unavailable
+    } else {
+        PaymentFailureReason::Timeout
+    }
+}
+
+/// Translates Stripe's error answer: an HTTP status and a body like
+/// `{"error": {"type": "card_error", "code": "card_declined", ...}}`.
+fn translate_error(status: u16, body: &str) -> PaymentFailureReason {
+    let body: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+    let error = &body["error"];
+    match (status, error["type"].as_str(), error["code"].as_str()) {
+        // Stripe's advice for these: try again
+        (_, _, Some("processing_error" | "lock_timeout")) => PaymentFailureReason::Unavailable,
+        (_, _, Some("amount_too_small")) => PaymentFailureReason::AmountTooSmall,
+        (429 | 500.., _, _) => PaymentFailureReason::Unavailable,
+        (402, _, _) | (_, Some("card_error"), _) => PaymentFailureReason::Declined,
+        _ => PaymentFailureReason::InvalidRequest,
+    }
+}
+
+impl HealthCheck for StripeApiPaymentGateway {
+    /// Reads the account's balance: the cheapest call proving that Stripe
+    /// answers and takes our key.
+    fn health(&self) -> HealthStatus {
+        let response = self
+            .client
+            .http
+            .get(format!("{API_BASE}/balance"))
+            .basic_auth(&self.client.secret_key, None::<&str>)
+            .send();
+        match response {
+            Err(e) => HealthStatus::Unhealthy(format!("Stripe unreachable: {e}")),
+            Ok(response) => match response.status().as_u16() {
+                200..300 => HealthStatus::Healthy,
+                status @ (429 | 500..) => {
+                    HealthStatus::Degraded(format!("Stripe answered {status}"))
+                }
+                status => HealthStatus::Unhealthy(format!("Stripe refused the key ({status})")),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_repository::CapturingLogger;
+
+    fn card_error(code: &str) -> String {
+        format!(
+            r#"{{"error": {{"type": "card_error", "code": "{code}", "decline_code": "generic_decline", "message": "Your card was declined."}}}}"#
+        )
+    }
+
+    #[test]
+    fn card_errors_are_declines() {
+        for code in ["card_declined", "expired_card", "incorrect_cvc"] {
+            assert_eq!(
+                translate_error(402, &card_error(code)),
+                PaymentFailureReason::Declined
+            );
+        }
+    }
+
+    #[test]
+    fn transient_errors_are_unavailable() {
+        assert_eq!(
+            translate_error(402, &card_error("processing_error")),
+            PaymentFailureReason::Unavailable
+        );
+        assert_eq!(
+            translate_error(
+                429,
+                r#"{"error": {"type": "invalid_request_error", "code": "rate_limit"}}"#
+            ),
+            PaymentFailureReason::Unavailable
+        );
+        // A proxy in front of Stripe answers with HTML, not JSON
+        assert_eq!(
+            translate_error(502, "<html>Bad Gateway</html>"),
+            PaymentFailureReason::Unavailable
+        );
+    }
+
+    #[test]
+    fn request_errors_are_invalid_requests() {
+        for (status, code) in [
+            (400, "charge_already_captured"),
+            (404, "resource_missing"),
+            (409, "idempotency_key_in_use"),
+        ] {
+            let body =
+                format!(r#"{{"error": {{"type": "invalid_request_error", "code": "{code}"}}}}"#);
+            assert_eq!(
+                translate_error(status, &body),
+                PaymentFailureReason::InvalidRequest
+            );
+        }
+        assert_eq!(
+            translate_error(401, r#"{"error": {"type": "invalid_request_error"}}"#),
+            PaymentFailureReason::InvalidRequest
+        );
+        assert_eq!(
+            translate_error(
+                400,
+                r#"{"error": {"type": "invalid_request_error", "code": "amount_too_small"}}"#
+            ),
+            PaymentFailureReason::AmountTooSmall
+        );
+    }
+
+    #[test]
+    fn charge_statuses_become_payment_statuses() {
+        let status = |text: &str| charge_status(&serde_json::json!({ "status": text }));
+
+        assert_eq!(status("succeeded"), PaymentStatus::Settled);
+        assert_eq!(status("pending"), PaymentStatus::Pending);
+        assert_eq!(status("failed"), PaymentStatus::Failed);
+        assert_eq!(status("something_new"), PaymentStatus::Pending);
+    }
+
+    #[test]
+    fn a_key_of_the_other_mode_is_refused() {
+        assert_eq!(
+            StripeApiPaymentGateway::new("sk_live_123", Mode::Test).unwrap_err(),
+            ConfigError::Invalid {
+                setting: "Stripe secret key (test mode)",
+                value: "sk_live_...".to_string(),
+            }
+        );
+        assert_eq!(
+            StripeApiPaymentGateway::new("sk_test_123", Mode::Live).unwrap_err(),
+            ConfigError::Invalid {
+                setting: "Stripe secret key (live mode)",
+                value: "sk_test_...".to_string(),
+            }
+        );
+        assert_eq!(
+            StripeApiPaymentGateway::new(" ", Mode::Test).unwrap_err(),
+            ConfigError::Missing("Stripe secret key (test mode)")
+        );
+
+        let gateway = StripeApiPaymentGateway::new("rk_test_123", Mode::Test).unwrap();
+        assert_eq!(gateway.mode(), Mode::Test);
+        assert!(!format!("{gateway:?}").contains("rk_test_123"));
+    }
+
+    #[test]
+    fn log_lines_go_to_the_logger() {
+        let logger = Arc::new(CapturingLogger::new());
+        let gateway = StripeApiPaymentGateway::new("sk_test_123", Mode::Test)
+            .unwrap()
+            .with_logger(logger.clone());
+
+        let result = object_id(&gateway.client, &serde_json::json!({}));
+
+        assert!(result.is_err());
+        let messages: Vec<_> = logger.lines().into_iter().map(|l| l.message).collect();
+        assert_eq!(messages, ["[Stripe API] Answer without an id: {}"]);
+    }
+
+    /// A test mode gateway on `STRIPE_TEST_KEY`.
+    ///
+    /// `None` without `STRIPE_TEST_KEY`: the test then passes without running.
+    fn gateway() -> Option<StripeApiPaymentGateway> {
+        let Ok(key) = std::env::var("STRIPE_TEST_KEY") else {
+            println!("STRIPE_TEST_KEY isn't set: skipping");
+            return None;
+        };
+        Some(StripeApiPaymentGateway::new(&key, Mode::Test).unwrap())
+    }
+
+    #[test]
+    fn a_test_card_is_charged_and_refunded() {
+        let Some(gateway) = gateway() else { return };
+
+        let receipt = gateway.charge(Money(1000), None).unwrap();
+        gateway.refund(&receipt.transaction_id, Money(400)).unwrap();
+
+        assert!(receipt.transaction_id.starts_with("ch_"));
+        assert_eq!(receipt.provider, "stripe");
+    }
+
+    #[test]
+    fn a_hold_is_captured_or_voided() {
+        let Some(gateway) = gateway() else { return };
+
+        let captured = gateway.authorize(Money(1000), None).unwrap();
+        let voided = gateway.authorize(Money(1000), None).unwrap();
+        gateway.capture(&captured).unwrap();
+        gateway.void(&voided).unwrap();
+
+        // Captured once is enough
+        assert!(matches!(
+            gateway.capture(&captured),
+            Err(OrderError::PaymentFailed(
+                PaymentFailureReason::InvalidRequest
+            ))
+        ));
+    }
+
+    #[test]
+    fn a_declining_test_card_is_declined() {
+        let Some(gateway) = gateway() else { return };
+        let gateway = gateway.with_source("tok_chargeDeclined");
+
+        let result = gateway.charge(Money(1000), None);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+    }
+
+    #[test]
+    fn a_retried_charge_with_the_same_key_charges_once() {
+        let Some(gateway) = gateway() else { return };
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let key = IdempotencyKey(format!("adapters-payment-test-{nanos}"));
+
+        let first = gateway.charge(Money(1000), Some(&key)).unwrap();
+        let retried = gateway.charge(Money(1000), Some(&key)).unwrap();
+
+        assert_eq!(first.transaction_id, retried.transaction_id);
+    }
+}
@@ -0,0 +1,163 @@
+// =============================================================================
+// Repository Benchmarks - What Swapping the Adapter Costs
+// =============================================================================
+//
+// The application only sees the OrderRepository port, so the adapter can be
+// swapped without changing a line of it. The bill for the swap is paid at
+// run time, by the adapter. These benchmarks measure it:
+//
+// - save:     inserting new orders, in orders per second
+// - find:     finding an order that is there, in a store of 1k / 10k / 100k
+// - find_all: listing a whole store of 1k / 10k / 100k orders
+//
+// for each adapter, and:
+//
+// - large_order: reading the total of one 1k-item order, with `find` (a
+//   clone of the order) and with `find_with` (a borrow), in memory
+//
+// - InMemoryOrderRepository
+// - CachedOrderRepository over it (`find` served from the LRU)
+// - PostgresOrderRepository (simulated: the rows and the mapping, no network)
+// - SqliteOrderRepository, in memory, with `--features sqlite`
+//
+// Every adapter gets a NullLogger: printing one line costs more than a
+// HashMap lookup, and would be all we'd measure. Stores are filled with the
+// same `seed_orders`, so they all hold the same data.
+//
+//     cargo bench -p adapters-repository
+//     cargo bench -p adapters-repository --features sqlite
+
+use adapters_repository::{
+    CachedOrderRepository, InMemoryOrderRepository, NullLogger, PostgresOrderRepository, seed,
+    seed_orders,
+};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use domain::{LineItem, Money, Order, OrderId, OrderRepository, TenantId};
+use std::hint::black_box;
+use std::sync::Arc;
+
+const SIZES: [u32; 3] = [1_000, 10_000, 100_000];
+/// The seed of the made-up orders: every run measures the same ones.
+const SEED: u64 = 42;
+
+/// Every adapter benchmarked, by name, empty.
+fn repositories() -> Vec<(&'static str, Box<dyn OrderRepository>)> {
+    let logger = Arc::new(NullLogger);
+    let mut all: Vec<(&'static str, Box<dyn OrderRepository>)> = vec![
+        (
+            "in_memory",
+            Box::new(InMemoryOrderRepository::new().with_logger(logger.clone())),
+        ),
+        (
+            "cached",
+            Box::new(CachedOrderRepository::new(
+                InMemoryOrderRepository::new().with_logger(logger.clone()),
+                1_000,
+            )),
+        ),
+        (
+            "postgres_simulated",
+            Box::new(PostgresOrderRepository::new().with_logger(logger.clone())),
+        ),
+    ];
+    all.extend(sqlite(logger));
+    all
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite(logger: Arc<NullLogger>) -> Option<(&'static str, Box<dyn OrderRepository>)> {
+    let repository = adapters_repository::SqliteOrderRepository::open_in_memory()
+        .expect("an in-memory SQLite database opens")
+        .with_logger(logger);
+    Some(("sqlite", Box::new(repository)))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite(_logger: Arc<NullLogger>) -> Option<(&'static str, Box<dyn OrderRepository>)> {
+    None
+}
+
+fn save(c: &mut Criterion) {
+    let mut group = c.benchmark_group("save");
+    group.throughput(Throughput::Elements(1));
+    for (name, repository) in repositories() {
+        // New IDs every iteration: the store grows as the benchmark runs
+        let mut orders = seed_orders(u32::MAX, SEED);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let order = orders.next().expect("seed orders never run out");
+                repository.insert(black_box(&order)).expect("IDs are fresh");
+            });
+        });
+    }
+    group.finish();
+}
+
+fn find(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find");
+    for size in SIZES {
+        for (name, repository) in repositories() {
+            seed(repository.as_ref(), size, SEED).expect("seeding succeeds");
+            let mut id = 0;
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, &size| {
+                b.iter(|| {
+                    // Step through the store, so a cache doesn't hit every time
+                    id = id % size + 1;
+                    let found = repository.find(TenantId::default(), OrderId(id));
+                    black_box(found.expect("find succeeds").expect("the order is there"))
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+fn find_all(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_all");
+    group.sample_size(10);
+    for size in SIZES {
+        group.throughput(Throughput::Elements(u64::from(size)));
+        for (name, repository) in repositories() {
+            seed(repository.as_ref(), size, SEED).expect("seeding succeeds");
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, _| {
+                b.iter(|| black_box(repository.find_all(TenantId::default())));
+            });
+        }
+    }
+    group.finish();
+}
+
+fn large_order(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_order");
+    let repository = InMemoryOrderRepository::new().with_logger(Arc::new(NullLogger));
+    let items = (0..1_000)
+        .map(|n| LineItem {
+            name: format!("Item number {n}"),
+            price: Money(100),
+            weight_grams: None,
+        })
+        .collect();
+    let order = Order::new(OrderId(1), items).expect("the order has items");
+    repository.insert(&order).expect("the store is empty");
+    let (tenant, id) = (TenantId::default(), OrderId(1));
+
+    group.bench_function("find", |b| {
+        b.iter(|| {
+            let found = repository.find(tenant, id).expect("find succeeds");
+            black_box(found.map(|order| order.total))
+        });
+    });
+    group.bench_function("find_with", |b| {
+        b.iter(|| {
+            let mut total = None;
+            repository
+                .find_with(tenant, id, &mut |order| total = order.map(|o| o.total))
+                .expect("find_with succeeds");
+            black_box(total)
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, save, find, find_all, large_order);
+criterion_main!(benches);
@@ -0,0 +1,165 @@
+// =============================================================================
+// Audit Logs - Append-Only Trails
+// =============================================================================
+//
+// Two adapters for the `AuditLog` port:
+// - InMemoryAuditLog: a Vec, so tests can read the trail back
+// - FileAuditLog: one line per entry, appended to a file
+//
+// The file is opened in append mode: we can add lines, never rewrite old ones.
+// In production, the trail would go to an insert-only table or a
+// write-once bucket, so nobody can quietly edit history.
+
+use domain::{AuditEntry, AuditLog, OrderError};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::UNIX_EPOCH;
+
+/// In-memory audit log for testing scenarios.
+#[derive(Debug, Default)]
+pub struct InMemoryAuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl InMemoryAuditLog {
+    /// Creates a new empty audit log.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every entry recorded so far, oldest first.
+    #[must_use]
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl AuditLog for InMemoryAuditLog {
+    fn record(&self, entry: AuditEntry) -> Result<(), OrderError> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(entry);
+        Ok(())
+    }
+}
+
+/// Audit log appending one line per entry to a file.
+///
+/// Line format: `<unix seconds> <action> <order id or -> <outcome>`.
+#[derive(Debug)]
+pub struct FileAuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl FileAuditLog {
+    /// Opens (or creates) the file at `path` for appending.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file can't be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// The file this log appends to.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AuditLog for FileAuditLog {
+    /// Appends one line. I/O errors become `StorageFailed`.
+    fn record(&self, entry: AuditEntry) -> Result<(), OrderError> {
+        let seconds = entry
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let order = entry
+            .order_id
+            .map_or_else(|| "-".to_string(), |id| id.0.to_string());
+        let line = format!("{seconds} {} {order} {}\n", entry.action, entry.outcome);
+
+        self.file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write_all(line.as_bytes())
+            .map_err(|_| OrderError::StorageFailed(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{AuditAction, AuditOutcome, OrderId};
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    fn entry(outcome: AuditOutcome) -> AuditEntry {
+        AuditEntry {
+            action: AuditAction::OrderPlaced,
+            order_id: Some(OrderId(7)),
+            outcome,
+            timestamp: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        }
+    }
+
+    #[test]
+    fn in_memory_log_keeps_entries_in_order() {
+        let log = InMemoryAuditLog::new();
+
+        log.record(entry(AuditOutcome::Succeeded)).unwrap();
+        log.record(entry(AuditOutcome::Failed("boom".to_string())))
+            .unwrap();
+
+        let outcomes: Vec<AuditOutcome> = log.entries().into_iter().map(|e| e.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                AuditOutcome::Succeeded,
+                AuditOutcome::Failed("boom".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn file_log_appends_lines() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("audit_{}_{nanos}.log", std::process::id()));
+
+        {
+            let log = FileAuditLog::open(&path).unwrap();
+            log.record(entry(AuditOutcome::Succeeded)).unwrap();
+        }
+        // Reopening appends instead of truncating
+        let log = FileAuditLog::open(&path).unwrap();
+        log.record(entry(AuditOutcome::Failed(
+            "PaymentFailed(Declined)".to_string(),
+        )))
+        .unwrap();
+
+        let content = fs::read_to_string(log.path()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            content,
+            "1700000000 OrderPlaced 7 Succeeded\n\
+             1700000000 OrderPlaced 7 Failed(PaymentFailed(Declined))\n"
+        );
+    }
+}
@@ -0,0 +1,392 @@
+// =============================================================================
+// Cached Repository - Remembering the Last Orders Read
+// =============================================================================
+//
+// `find` by ID is the hottest call on the real database, and the answer only
+// changes when we write. So: a decorator that keeps the most recently used
+// orders in memory, and serves `find` from there when it can.
+//
+// LRU (least recently used): the cache holds at most `capacity` orders. When
+// it's full, the order nobody asked for in the longest time makes room.
+// A HashMap holds the orders, a VecDeque remembers the order of use (front:
+// oldest). Moving a key to the back is O(capacity), fine for a few hundred
+// entries; a big cache would want a linked hash map instead.
+//
+// Writes go through this decorator, so it keeps itself up to date: a saved
+// order replaces its cached copy, a deleted one leaves the cache. A write
+// that fails drops the entry: we no longer know what the database holds.
+//
+// The limit of any cache: writes that DON'T go through it. Another process
+// updating the same database leaves this cache serving the old order. In
+// production, give entries a time-to-live, or invalidate on change events.
+
+use domain::{
+    HealthCheck, HealthStatus, Order, OrderError, OrderId, OrderRepository, RequestContext,
+    TenantId,
+};
+use std::collections::{HashMap, VecDeque};
+use std::ops::ControlFlow;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+type Key = (TenantId, OrderId);
+
+/// Repository decorator serving `find` from an in-memory LRU cache.
+#[derive(Debug)]
+pub struct CachedOrderRepository<R> {
+    inner: R,
+    cache: Mutex<Lru>,
+}
+
+#[derive(Debug)]
+struct Lru {
+    capacity: usize,
+    orders: HashMap<Key, Order>,
+    // Front: least recently used
+    recency: VecDeque<Key>,
+    stats: CacheStats,
+}
+
+/// How often `find` was answered from the cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: Key) {
+        if let Some(position) = self.recency.iter().position(|k| *k == key) {
+            self.recency.remove(position);
+        }
+        self.recency.push_back(key);
+    }
+
+    fn get(&mut self, key: Key) -> Option<Order> {
+        self.get_ref(key).cloned()
+    }
+
+    fn get_ref(&mut self, key: Key) -> Option<&Order> {
+        if self.orders.contains_key(&key) {
+            self.touch(key);
+        }
+        self.orders.get(&key)
+    }
+
+    fn put(&mut self, order: &Order) {
+        let key = (order.tenant, order.id);
+        self.orders.insert(key, order.clone());
+        self.touch(key);
+        while self.orders.len() > self.capacity {
+            let Some(oldest) = self.recency.pop_front() else {
+                break;
+            };
+            self.orders.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, key: Key) {
+        if self.orders.remove(&key).is_some() {
+            self.recency.retain(|k| *k != key);
+        }
+    }
+}
+
+impl<R> CachedOrderRepository<R> {
+    /// Wraps `inner`, caching up to `capacity` orders.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    #[must_use]
+    pub fn new(inner: R, capacity: usize) -> Self {
+        assert!(capacity > 0, "a cache needs room for at least one order");
+        Self {
+            inner,
+            cache: Mutex::new(Lru {
+                capacity,
+                orders: HashMap::new(),
+                recency: VecDeque::new(),
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    /// Hits and misses so far.
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        self.cache().stats
+    }
+
+    /// Number of orders cached right now.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cache().orders.len()
+    }
+
+    /// True when nothing is cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The wrapped repository.
+    #[must_use]
+    pub const fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    fn cache(&self) -> MutexGuard<'_, Lru> {
+        // A poisoned lock only means another thread panicked; the entries are still fine
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keeps the cache in step with a write: the new order if it went
+    /// through, nothing if we can't tell what's stored anymore.
+    fn after_write(&self, order: &Order, result: Result<(), OrderError>) -> Result<(), OrderError> {
+        let mut cache = self.cache();
+        match &result {
+            Ok(()) => cache.put(order),
+            Err(_) => cache.remove((order.tenant, order.id)),
+        }
+        result
+    }
+}
+
+impl<R: OrderRepository> OrderRepository for CachedOrderRepository<R> {
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.insert_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.update_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.after_write(order, self.inner.update_versioned(order, expected))
+    }
+
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        // Gone from the cache whatever happens: at worst, the next find asks again
+        self.cache().remove((tenant, id));
+        self.inner.delete(tenant, id)
+    }
+
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        let result = self.inner.save_all(orders);
+        let mut cache = self.cache();
+        for order in orders {
+            match &result {
+                Ok(()) => cache.put(order),
+                // Some of the batch may be stored (see the port's contract)
+                Err(_) => cache.remove((order.tenant, order.id)),
+            }
+        }
+        result
+    }
+
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        // Hidden orders aren't found, so they aren't cached either
+        self.cache().remove((tenant, id));
+        self.inner.soft_delete(tenant, id)
+    }
+
+    /// Nothing to update: the order wasn't cached while it was hidden.
+    fn restore(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.inner.restore(tenant, id)
+    }
+
+    /// Not cached: soft-deleted orders must not end up answering `find`.
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.inner.find_including_deleted(tenant, id)
+    }
+
+    fn find_with(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+        read: &mut dyn FnMut(Option<&Order>),
+    ) -> Result<(), OrderError> {
+        {
+            let mut cache = self.cache();
+            if let Some(order) = cache.get_ref((tenant, id)) {
+                read(Some(order));
+                cache.stats.hits += 1;
+                return Ok(());
+            }
+            cache.stats.misses += 1;
+        }
+
+        // A miss is a `find`: the order is cloned once, into the cache
+        let found = self.inner.find(tenant, id)?;
+        if let Some(order) = &found {
+            self.cache().put(order);
+        }
+        read(found.as_ref());
+        Ok(())
+    }
+
+    fn for_each(
+        &self,
+        tenant: TenantId,
+        visit: &mut dyn FnMut(&Order) -> ControlFlow<()>,
+    ) -> Result<(), OrderError> {
+        self.inner.for_each(tenant, visit)
+    }
+
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        self.inner.find_many(tenant, ids)
+    }
+
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        self.inner.find_by_item_name(tenant, query)
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.find_with_ctx(&RequestContext::default(), tenant, id)
+    }
+
+    /// Not cached: a list is only as fresh as its stalest entry.
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.inner.find_all(tenant)
+    }
+
+    fn insert_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        let result = self.inner.insert_with_ctx(ctx, order);
+        // A duplicate didn't touch what's stored, and says nothing about it
+        if matches!(result, Err(OrderError::DuplicateOrder(_))) {
+            return result;
+        }
+        self.after_write(order, result)
+    }
+
+    fn update_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.after_write(order, self.inner.update_with_ctx(ctx, order))
+    }
+
+    fn find_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        {
+            let mut cache = self.cache();
+            if let Some(order) = cache.get((tenant, id)) {
+                cache.stats.hits += 1;
+                return Ok(Some(order));
+            }
+            cache.stats.misses += 1;
+        }
+
+        // The lock is released: a slow database doesn't block other hits
+        let found = self.inner.find_with_ctx(ctx, tenant, id)?;
+        if let Some(order) = &found {
+            self.cache().put(order);
+        }
+        Ok(found)
+    }
+}
+
+impl<R: HealthCheck> HealthCheck for CachedOrderRepository<R> {
+    // Only as healthy as the repository underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryOrderRepository;
+    use domain::{LineItem, Money};
+
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
+            vec![LineItem {
+                name: "Test".to_string(),
+                price: Money(100),
+                weight_grams: None,
+            }],
+        )
+        .unwrap()
+    }
+
+    fn find(repo: &impl OrderRepository, id: u32) -> Option<Order> {
+        repo.find(TenantId::default(), OrderId(id)).unwrap()
+    }
+
+    #[test]
+    fn find_after_save_is_a_hit() {
+        let repo = CachedOrderRepository::new(InMemoryOrderRepository::new(), 10);
+        repo.insert(&test_order(1)).unwrap();
+        let mut changed = test_order(1);
+        changed.total = Money(42);
+        repo.update(&changed).unwrap();
+
+        assert_eq!(find(&repo, 1).unwrap().total, Money(42));
+        assert_eq!(repo.stats(), CacheStats { hits: 1, misses: 0 });
+    }
+
+    #[test]
+    fn miss_populates_the_cache() {
+        let inner = InMemoryOrderRepository::new();
+        inner.insert(&test_order(1)).unwrap();
+        let repo = CachedOrderRepository::new(inner, 10);
+
+        assert!(find(&repo, 1).is_some());
+        assert!(find(&repo, 1).is_some());
+
+        assert_eq!(repo.stats(), CacheStats { hits: 1, misses: 1 });
+    }
+
+    #[test]
+    fn find_with_is_served_from_the_cache_too() {
+        let inner = InMemoryOrderRepository::new();
+        inner.insert(&test_order(1)).unwrap();
+        let repo = CachedOrderRepository::new(inner, 10);
+        let mut totals = Vec::new();
+
+        for id in [1, 1, 2] {
+            repo.find_with(TenantId::default(), OrderId(id), &mut |order| {
+                totals.push(order.map(|o| o.total));
+            })
+            .unwrap();
+        }
+
+        assert_eq!(totals, [Some(Money(100)), Some(Money(100)), None]);
+        assert_eq!(repo.stats(), CacheStats { hits: 1, misses: 2 });
+        assert_eq!(repo.len(), 1);
+    }
+
+    #[test]
+    fn delete_invalidates_the_entry() {
+        let repo = CachedOrderRepository::new(InMemoryOrderRepository::new(), 10);
+        repo.insert(&test_order(1)).unwrap();
+
+        assert!(repo.delete(TenantId::default(), OrderId(1)).unwrap());
+
+        assert!(find(&repo, 1).is_none());
+        assert!(repo.is_empty());
+        assert_eq!(repo.stats().misses, 1);
+    }
+
+    #[test]
+    fn least_recently_used_order_is_evicted() {
+        let repo = CachedOrderRepository::new(InMemoryOrderRepository::new(), 2);
+        repo.insert(&test_order(1)).unwrap();
+        repo.insert(&test_order(2)).unwrap();
+        // Using #1 makes #2 the least recently used
+        find(&repo, 1);
+
+        repo.insert(&test_order(3)).unwrap();
+
+        assert_eq!(repo.len(), 2);
+        find(&repo, 2);
+        assert_eq!(repo.stats(), CacheStats { hits: 1, misses: 1 });
+    }
+}
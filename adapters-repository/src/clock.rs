@@ -0,0 +1,78 @@
+// =============================================================================
+// Clocks - Where "Now" Comes From
+// =============================================================================
+//
+// Two adapters for the `Clock` port:
+// - SystemClock: the real time, from the operating system
+// - FixedClock: a time that only moves when told to
+//
+// "Reject the same order within 10 seconds" is easy to write and awkward to
+// test: nobody wants a test that sleeps for 10 seconds. With a FixedClock the
+// test just says "10 seconds later" and carries on:
+//
+//     let clock = FixedClock::new(UNIX_EPOCH);
+//     clock.advance(Duration::from_secs(10));
+
+use domain::Clock;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// Clock reading the operating system's time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock standing still until it is moved, for tests.
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<SystemTime>,
+}
+
+impl FixedClock {
+    /// Creates a clock showing `now`.
+    #[must_use]
+    pub const fn new(now: SystemTime) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Moves the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += by;
+    }
+
+    /// Moves the clock to `now`, forward or backward.
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) = now;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn fixed_clock_only_moves_when_told() {
+        let clock = FixedClock::new(UNIX_EPOCH);
+        assert_eq!(clock.now(), UNIX_EPOCH);
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(5));
+
+        clock.set(UNIX_EPOCH);
+        assert_eq!(clock.now(), UNIX_EPOCH);
+    }
+}
@@ -0,0 +1,650 @@
+// =============================================================================
+// CSV Export and Import - Orders in a Spreadsheet
+// =============================================================================
+//
+// Finance wants the orders in a spreadsheet; analysts want a spreadsheet
+// back in a store. One file format for both ways:
+//
+//     order_id,status,item_name,quantity,price_cents
+//     1,Paid,Rust Programming Book,1,4999
+//     1,Paid,"Cable, 2m",2,999
+//     2,Pending,"The ""Rust"" Mug",1,1499
+//
+// - one line per item, in the order's item order; identical items next to
+//   each other share a line, with their quantity
+// - the order's ID and status repeat on each of its lines
+// - prices in cents, as everywhere else
+// - a field with a comma, a quote or a line break is quoted, its quotes
+//   doubled (RFC 4180)
+//
+// WHAT ISN'T IN IT:
+// -----------------
+// Only what's in the columns goes through. The tenant is the one exported
+// from and imported into; payments, refunds, history, customers, shipping
+// and item weights stay behind. An imported order is a new order
+// (`Order::new`, so "at least one item" and "the total fits" still hold)
+// with the exported status. Item names go through `LineItem::new_lossy`: sanitized like any
+// client's, and cut down rather than refused when they're too long. A name
+// with nothing left makes the row malformed.
+//
+// MALFORMED ROWS:
+// ---------------
+// Hand-edited spreadsheets come back broken. `OnMalformedRow` says what to
+// do about it: abort the whole import, or skip and report. Skipping a row
+// skips its whole order too (imported without one of its items, it would
+// have the wrong total). Either way, nothing is inserted before the whole
+// file has been read.
+
+use domain::{LineItem, Money, Order, OrderError, OrderId, OrderRepository, OrderStatus, TenantId};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::ops::ControlFlow;
+
+/// The first line of every export, and what an import expects first.
+pub const CSV_HEADER: &str = "order_id,status,item_name,quantity,price_cents";
+
+/// The most identical items one line holds. Longer runs take several lines.
+pub const MAX_QUANTITY_PER_LINE: u32 = 65_535;
+
+/// What [`import_csv`] does with a row it can't read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnMalformedRow {
+    /// Stop, and import nothing.
+    #[default]
+    Abort,
+    /// Leave the row's order out, and report the row.
+    Skip,
+}
+
+/// A row [`import_csv`] couldn't read, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedRow {
+    /// Where the row starts in the file, counting from 1 (the header).
+    pub line: usize,
+    /// What's wrong with it.
+    pub problem: String,
+}
+
+impl fmt::Display for MalformedRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.problem)
+    }
+}
+
+/// What [`import_csv`] did.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ImportReport {
+    /// How many orders went into the repository.
+    pub imported: usize,
+    /// The rows skipped with [`OnMalformedRow::Skip`], in file order.
+    pub malformed: Vec<MalformedRow>,
+    /// The orders left out because of them.
+    pub skipped_orders: Vec<OrderId>,
+}
+
+/// Why an export or an import failed.
+#[derive(Debug)]
+pub enum CsvError {
+    /// Reading or writing the CSV failed.
+    Io(io::Error),
+    /// The repository failed, or refused an order (an ID already taken).
+    Repository(OrderError),
+    /// A row can't be read, with [`OnMalformedRow::Abort`], or the header
+    /// isn't [`CSV_HEADER`].
+    Malformed(MalformedRow),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "CSV I/O failed: {e}"),
+            Self::Repository(e) => write!(f, "{e}"),
+            Self::Malformed(row) => write!(f, "malformed CSV, {row}"),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<io::Error> for CsvError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<OrderError> for CsvError {
+    fn from(e: OrderError) -> Self {
+        Self::Repository(e)
+    }
+}
+
+/// Writes `tenant`'s orders to `writer` as CSV, in ID order, and returns
+/// how many there were.
+///
+/// The orders are streamed with [`OrderRepository::for_each`], never all
+/// held at once.
+///
+/// # Errors
+///
+/// Returns [`CsvError::Io`] if writing fails, [`CsvError::Repository`] if
+/// reading the orders does.
+pub fn export_csv<R: OrderRepository + ?Sized, W: Write>(
+    repository: &R,
+    tenant: TenantId,
+    writer: W,
+) -> Result<usize, CsvError> {
+    let mut writer = io::BufWriter::new(writer);
+    writeln!(writer, "{CSV_HEADER}")?;
+    let mut exported = 0;
+    let mut failure = None;
+    repository.for_each(tenant, &mut |order| match write_order(&mut writer, order) {
+        Ok(()) => {
+            exported += 1;
+            ControlFlow::Continue(())
+        }
+        Err(e) => {
+            failure = Some(e);
+            ControlFlow::Break(())
+        }
+    })?;
+    if let Some(e) = failure {
+        return Err(e.into());
+    }
+    writer.flush()?;
+    Ok(exported)
+}
+
+/// Reads orders from CSV written by [`export_csv`], and inserts them into
+/// `repository` for `tenant`.
+///
+/// Rows may come in any order: an order's items are its rows in file order.
+/// Every row of an order must have the same status.
+///
+/// # Errors
+///
+/// Returns [`CsvError::Malformed`] if the header isn't [`CSV_HEADER`], or
+/// for the first malformed row with [`OnMalformedRow::Abort`]; nothing is
+/// inserted then. Returns [`CsvError::Io`] if reading fails, and
+/// [`CsvError::Repository`] if an insert does (the orders before it stay
+/// inserted).
+pub fn import_csv<R: OrderRepository + ?Sized, In: Read>(
+    repository: &R,
+    tenant: TenantId,
+    reader: In,
+    on_malformed: OnMalformedRow,
+) -> Result<ImportReport, CsvError> {
+    let mut records = Records::new(reader);
+    match records.next_record()? {
+        Some((_, header)) if header == CSV_HEADER => {}
+        _ => {
+            return Err(CsvError::Malformed(MalformedRow {
+                line: 1,
+                problem: format!("the header isn't {CSV_HEADER:?}"),
+            }));
+        }
+    }
+
+    let mut report = ImportReport::default();
+    let mut drafts: Vec<Draft> = Vec::new();
+    let mut index: HashMap<OrderId, usize> = HashMap::new();
+    let malformed = |report: &mut ImportReport, line, problem: String, id: Option<OrderId>| {
+        let row = MalformedRow { line, problem };
+        match on_malformed {
+            OnMalformedRow::Abort => Err(CsvError::Malformed(row)),
+            OnMalformedRow::Skip => {
+                report.malformed.push(row);
+                if let Some(id) = id.filter(|id| !report.skipped_orders.contains(id)) {
+                    report.skipped_orders.push(id);
+                }
+                Ok(())
+            }
+        }
+    };
+
+    while let Some((line, record)) = records.next_record()? {
+        let row = match parse_row(&record) {
+            Ok(row) => row,
+            Err((problem, id)) => {
+                malformed(&mut report, line, problem, id)?;
+                continue;
+            }
+        };
+        let draft = match index.get(&row.id) {
+            Some(&at) => &mut drafts[at],
+            None => {
+                index.insert(row.id, drafts.len());
+                drafts.push(Draft {
+                    id: row.id,
+                    status: row.status,
+                    line,
+                    items: Vec::new(),
+                });
+                drafts.last_mut().expect("just pushed")
+            }
+        };
+        if draft.status != row.status {
+            let problem = format!(
+                "{} is {} here, {} on line {}",
+                row.id, row.status, draft.status, draft.line
+            );
+            malformed(&mut report, line, problem, Some(row.id))?;
+            continue;
+        }
+        let quantity = usize::try_from(row.quantity).expect("a u32 fits in usize");
+        draft.items.extend(std::iter::repeat_n(row.item, quantity));
+    }
+
+    let mut orders = Vec::with_capacity(drafts.len());
+    for draft in drafts {
+        if report.skipped_orders.contains(&draft.id) {
+            continue;
+        }
+        match Order::new(draft.id, draft.items) {
+            Ok(mut order) => {
+                order.status = draft.status;
+                order.tenant = tenant;
+                orders.push(order);
+            }
+            Err(e) => malformed(&mut report, draft.line, e.to_string(), Some(draft.id))?,
+        }
+    }
+    report.skipped_orders.sort();
+
+    for order in &orders {
+        repository.insert(order)?;
+        report.imported += 1;
+    }
+    Ok(report)
+}
+
+/// An order being read back, row by row.
+struct Draft {
+    id: OrderId,
+    status: OrderStatus,
+    /// Its first line, for the messages.
+    line: usize,
+    items: Vec<LineItem>,
+}
+
+/// One row, read.
+struct Row {
+    id: OrderId,
+    status: OrderStatus,
+    item: LineItem,
+    quantity: u32,
+}
+
+fn write_order(writer: &mut impl Write, order: &Order) -> io::Result<()> {
+    let mut items = order.items.iter().peekable();
+    while let Some(item) = items.next() {
+        let mut quantity = 1;
+        while quantity < MAX_QUANTITY_PER_LINE
+            && items
+                .next_if(|next| next.name == item.name && next.price == item.price)
+                .is_some()
+        {
+            quantity += 1;
+        }
+        writeln!(
+            writer,
+            "{},{},{},{quantity},{}",
+            order.id.0,
+            order.status,
+            quote(&item.name),
+            item.price.0
+        )?;
+    }
+    Ok(())
+}
+
+/// The field as CSV: quoted, quotes doubled, if it needs to be.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Reads one row, or says what's wrong with it (and whose row it was, if
+/// the ID at least could be read).
+fn parse_row(record: &str) -> Result<Row, (String, Option<OrderId>)> {
+    let fields = split(record).map_err(|problem| (problem, None))?;
+    let [id, status, name, quantity, price] = <[String; 5]>::try_from(fields)
+        .map_err(|fields| (format!("expected 5 fields, found {}", fields.len()), None))?;
+    let id = id
+        .parse::<u32>()
+        .map(OrderId)
+        .map_err(|_| (format!("order_id {id:?} isn't an order ID"), None))?;
+    let bad = |problem: String| (problem, Some(id));
+    let status = parse_status(&status).ok_or_else(|| bad(format!("unknown status {status:?}")))?;
+    let quantity = quantity
+        .parse::<u32>()
+        .ok()
+        .filter(|q| (1..=MAX_QUANTITY_PER_LINE).contains(q))
+        .ok_or_else(|| {
+            bad(format!(
+                "quantity {quantity:?} isn't between 1 and {MAX_QUANTITY_PER_LINE}"
+            ))
+        })?;
+    let price = price
+        .parse::<u32>()
+        .map(Money)
+        .map_err(|_| bad(format!("price_cents {price:?} isn't an amount in cents")))?;
+    // Lossy: one overlong name shouldn't lose the whole file
+    let item = LineItem::new_lossy(&name, price)
+        .map_err(|_| bad(format!("item name {name:?} is blank")))?;
+    Ok(Row {
+        id,
+        status,
+        item,
+        quantity,
+    })
+}
+
+fn parse_status(status: &str) -> Option<OrderStatus> {
+    match status {
+        "Pending" => Some(OrderStatus::Pending),
+        "Paid" => Some(OrderStatus::Paid),
+        "Shipped" => Some(OrderStatus::Shipped),
+        "Cancelled" => Some(OrderStatus::Cancelled),
+        "Refunded" => Some(OrderStatus::Refunded),
+        _ => None,
+    }
+}
+
+/// Splits a record into its fields, unquoting them.
+fn split(record: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = record.chars().peekable();
+    // In quotes; and whether the current field was quoted at all
+    let (mut quoted, mut was_quoted) = (false, false);
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() && !was_quoted => (quoted, was_quoted) = (true, true),
+            '"' => return Err("a quote in the middle of an unquoted field".to_string()),
+            ',' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                was_quoted = false;
+            }
+            _ if was_quoted && !quoted => {
+                return Err("text after the closing quote of a field".to_string());
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("a quoted field never ends".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// The records of a CSV file, with the line each starts on. A quoted field
+/// may hold line breaks, so a record may span several lines.
+struct Records<In> {
+    lines: BufReader<In>,
+    line: usize,
+}
+
+impl<In: Read> Records<In> {
+    fn new(reader: In) -> Self {
+        Self {
+            lines: BufReader::new(reader),
+            line: 0,
+        }
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<(usize, String)>> {
+        let mut record = String::new();
+        let start = self.line + 1;
+        loop {
+            if self.lines.read_line(&mut record)? == 0 {
+                // The end of the file, maybe in the middle of a quoted field
+                // (which `split` reports)
+                return Ok((!record.is_empty()).then_some((start, record)));
+            }
+            self.line += 1;
+            // Quotes come in pairs (doubled ones too): an odd count means
+            // the line break is inside a quoted field
+            if record.matches('"').count().is_multiple_of(2) {
+                let end = record.trim_end_matches(['\n', '\r']).len();
+                record.truncate(end);
+                if record.is_empty() {
+                    // A blank line, at the end of a hand-edited file
+                    return self.next_record();
+                }
+                return Ok(Some((start, record)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryOrderRepository;
+    use domain::MAX_ITEM_NAME_CHARS;
+
+    fn order(id: u32, status: OrderStatus, items: &[(&str, u32)]) -> Order {
+        let items = items
+            .iter()
+            .map(|&(name, price)| LineItem {
+                name: name.to_string(),
+                price: Money(price),
+                weight_grams: None,
+            })
+            .collect();
+        let mut order = Order::new(OrderId(id), items).unwrap();
+        order.status = status;
+        order
+    }
+
+    fn export(repo: &InMemoryOrderRepository) -> String {
+        let mut csv = Vec::new();
+        export_csv(repo, TenantId::default(), &mut csv).unwrap();
+        String::from_utf8(csv).unwrap()
+    }
+
+    fn import(
+        csv: &str,
+        on_malformed: OnMalformedRow,
+    ) -> (InMemoryOrderRepository, Result<ImportReport, CsvError>) {
+        let repo = InMemoryOrderRepository::new();
+        let report = import_csv(&repo, TenantId::default(), csv.as_bytes(), on_malformed);
+        (repo, report)
+    }
+
+    #[test]
+    fn export_writes_one_line_per_run_of_identical_items() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&order(
+            2,
+            OrderStatus::Pending,
+            &[("The \"Rust\" Mug", 1499)],
+        ))
+        .unwrap();
+        repo.insert(&order(
+            1,
+            OrderStatus::Paid,
+            &[("Book", 4999), ("Cable, 2m", 999), ("Cable, 2m", 999)],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            export(&repo),
+            "order_id,status,item_name,quantity,price_cents\n\
+             1,Paid,Book,1,4999\n\
+             1,Paid,\"Cable, 2m\",2,999\n\
+             2,Pending,\"The \"\"Rust\"\" Mug\",1,1499\n"
+        );
+    }
+
+    #[test]
+    fn export_then_import_gives_the_same_store() {
+        let original = InMemoryOrderRepository::new();
+        for order in [
+            order(
+                1,
+                OrderStatus::Paid,
+                &[("Book", 4999), ("Pen", 199), ("Pen", 199), ("Book", 4999)],
+            ),
+            // Quoted on the way out; a line break wouldn't survive LineItem::new
+            order(2, OrderStatus::Cancelled, &[("Cable, 2m \"quoted\"", 0)]),
+            order(3, OrderStatus::Shipped, &[("Desk", 49999)]),
+        ] {
+            original.insert(&order).unwrap();
+        }
+        let csv = export(&original);
+
+        let (copy, report) = import(&csv, OnMalformedRow::Abort);
+
+        assert_eq!(report.unwrap().imported, 3);
+        assert_eq!(
+            copy.find_all(TenantId::default()).unwrap(),
+            original.find_all(TenantId::default()).unwrap()
+        );
+        assert_eq!(export(&copy), csv);
+    }
+
+    #[test]
+    fn an_empty_store_exports_only_the_header() {
+        let repo = InMemoryOrderRepository::new();
+
+        let csv = export(&repo);
+
+        assert_eq!(csv, format!("{CSV_HEADER}\n"));
+        assert_eq!(
+            import(&csv, OnMalformedRow::Abort).1.unwrap(),
+            ImportReport::default()
+        );
+    }
+
+    #[test]
+    fn abort_stops_at_the_first_malformed_row_and_imports_nothing() {
+        let csv = "order_id,status,item_name,quantity,price_cents\n\
+                   1,Paid,Book,1,4999\n\
+                   2,Lost,Pen,1,199\n";
+
+        let (repo, report) = import(csv, OnMalformedRow::Abort);
+
+        let Err(CsvError::Malformed(row)) = report else {
+            panic!("expected a malformed row, got {report:?}");
+        };
+        assert_eq!(row.line, 3);
+        assert_eq!(row.problem, "unknown status \"Lost\"");
+        assert!(repo.is_empty());
+    }
+
+    #[test]
+    fn skip_leaves_out_the_orders_of_malformed_rows_and_reports_them() {
+        let csv = "order_id,status,item_name,quantity,price_cents\n\
+                   1,Paid,Book,1,4999\n\
+                   2,Paid,Pen,1,199\n\
+                   2,Paid,Pen,0,199\n\
+                   x,Paid,Mug,1,1499\n\
+                   3,Paid,\"Mug\" cup,1,1499\n\
+                   1,Pending,Ink,1,99\n\
+                   4,Shipped,Desk,1,49999\n";
+
+        let (repo, report) = import(csv, OnMalformedRow::Skip);
+
+        let report = report.unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.skipped_orders, [OrderId(1), OrderId(2)]);
+        let lines: Vec<usize> = report.malformed.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [4, 5, 6, 7]);
+        assert_eq!(
+            report.malformed[3].problem,
+            "OrderId(1) is Pending here, Paid on line 2"
+        );
+        let ids: Vec<OrderId> = repo
+            .find_all(TenantId::default())
+            .unwrap()
+            .iter()
+            .map(|o| o.id)
+            .collect();
+        assert_eq!(ids, [OrderId(4)]);
+    }
+
+    #[test]
+    fn orders_are_still_validated_by_order_new() {
+        let csv = "order_id,status,item_name,quantity,price_cents\n\
+                   1,Paid,Gold,2,4294967295\n";
+
+        let (repo, report) = import(csv, OnMalformedRow::Abort);
+
+        assert!(matches!(
+            report,
+            Err(CsvError::Malformed(MalformedRow { line: 2, .. }))
+        ));
+        assert!(repo.is_empty());
+    }
+
+    #[test]
+    fn item_names_are_sanitized_and_cut_down() {
+        let long = "x".repeat(MAX_ITEM_NAME_CHARS + 50);
+        let csv = format!(
+            "order_id,status,item_name,quantity,price_cents\n\
+             1,Paid,\"  Blue\r\nMug \",1,1499\n\
+             2,Paid,{long},1,999\n\
+             3,Paid,\" \t \",1,99\n"
+        );
+
+        let (repo, report) = import(&csv, OnMalformedRow::Skip);
+
+        let report = report.unwrap();
+        assert_eq!(report.imported, 2);
+        // The quoted line break makes the first row two lines long
+        assert_eq!(report.malformed[0].line, 5);
+        let name = |id| {
+            repo.find(TenantId::default(), OrderId(id))
+                .unwrap()
+                .unwrap()
+                .items[0]
+                .name
+                .clone()
+        };
+        assert_eq!(name(1), "Blue Mug");
+        assert_eq!(name(2).chars().count(), MAX_ITEM_NAME_CHARS);
+    }
+
+    #[test]
+    fn a_wrong_header_is_always_fatal() {
+        let (_, report) = import("id,name\n1,Book\n", OnMalformedRow::Skip);
+        assert!(matches!(
+            report,
+            Err(CsvError::Malformed(MalformedRow { line: 1, .. }))
+        ));
+
+        let (_, report) = import("", OnMalformedRow::Skip);
+        assert!(matches!(report, Err(CsvError::Malformed(_))));
+    }
+
+    #[test]
+    fn importing_over_existing_orders_fails() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&order(1, OrderStatus::Paid, &[("Book", 4999)]))
+            .unwrap();
+        let csv = export(&repo);
+
+        let report = import_csv(
+            &repo,
+            TenantId::default(),
+            csv.as_bytes(),
+            OnMalformedRow::Skip,
+        );
+
+        assert!(matches!(
+            report,
+            Err(CsvError::Repository(OrderError::DuplicateOrder(_)))
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+// =============================================================================
+// In-Memory Customer Repository - Same Pattern, Different Entity
+// =============================================================================
+//
+// Customers get their own port (CustomerRepository) and therefore their own
+// adapter. Nothing new here: the same InMemoryStore as InMemoryOrderRepository,
+// keyed by CustomerId instead of (tenant, OrderId).
+
+use crate::InMemoryStore;
+use domain::{Customer, CustomerId, CustomerRepository, OrderError};
+use std::sync::{PoisonError, RwLock};
+
+/// In-memory customer repository for testing scenarios.
+#[derive(Debug, Default)]
+pub struct InMemoryCustomerRepository {
+    customers: RwLock<InMemoryStore<CustomerId, Customer>>,
+}
+
+impl InMemoryCustomerRepository {
+    /// Creates a new empty in-memory customer repository.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CustomerRepository for InMemoryCustomerRepository {
+    fn save(&self, customer: &Customer) -> Result<(), OrderError> {
+        println!("  [InMemory] Saving customer #{}", customer.id());
+        self.customers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(customer.id(), customer.clone());
+        Ok(())
+    }
+
+    fn find(&self, id: CustomerId) -> Result<Option<Customer>, OrderError> {
+        println!("  [InMemory] Finding customer #{id}");
+        let customers = self
+            .customers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(customers.get(id).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_find_customer() {
+        let repo = InMemoryCustomerRepository::new();
+        let customer = Customer::new(CustomerId(1), "Alice", "alice@example.com").unwrap();
+
+        repo.save(&customer).unwrap();
+
+        assert_eq!(repo.find(CustomerId(1)).unwrap(), Some(customer));
+    }
+
+    #[test]
+    fn find_unknown_customer_returns_none() {
+        let repo = InMemoryCustomerRepository::new();
+
+        assert_eq!(repo.find(CustomerId(9)).unwrap(), None);
+    }
+}
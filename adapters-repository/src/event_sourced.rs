@@ -0,0 +1,562 @@
+// =============================================================================
+// Event-Sourced Repository - Storing What Happened, Not Just Where We Are
+// =============================================================================
+//
+// The other repositories keep a snapshot: the latest state of each order.
+// Whatever it was before is gone. This one keeps the list of changes instead,
+// one `OrderEvent` per change, and rebuilds the order on every `find` by
+// replaying them through `Order::apply`.
+//
+// The application doesn't know. It still calls `insert` and `update` with
+// whole orders, so the repository works out the events itself: it compares
+// the order it's given with the one its events describe, and appends the
+// difference (an item added, a payment, a cancellation...).
+//
+// Not every difference is an event. Rewriting an item's price or lowering a
+// total by hand has no event, because the domain has no such operation. Such
+// an update is refused (StorageFailed) rather than recorded as something it
+// wasn't, with the reason attached as its source. Callers that know what
+// happened can say it directly with `append`.
+//
+// The log lines go to stdout, or to a `Logger` when one is plugged in.
+//
+// In production, the events would go to an append-only table
+// (`INSERT INTO order_events (tenant_id, order_id, seq, payload)`) or a
+// dedicated store like EventStoreDB, with snapshots every N events so a long
+// history doesn't make every read slow.
+
+use domain::{
+    HealthCheck, HealthStatus, Logger, Money, Order, OrderError, OrderEvent, OrderId,
+    OrderRepository, OrderStatus, RequestContext, TenantId,
+};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::UNIX_EPOCH;
+
+/// In-memory repository storing each order as its list of events.
+#[derive(Default)]
+pub struct EventSourcedOrderRepository {
+    streams: RwLock<HashMap<(TenantId, OrderId), Stream>>,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
+
+// Manual impl: the logger is a trait object with no Debug of its own.
+impl fmt::Debug for EventSourcedOrderRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSourcedOrderRepository")
+            .field("streams", &self.streams)
+            .field("logger", &self.logger.is_some())
+            .finish()
+    }
+}
+
+/// The events of one order, and the version of the last order saved.
+#[derive(Debug)]
+struct Stream {
+    events: Vec<OrderEvent>,
+    version: u32,
+}
+
+impl Stream {
+    fn replay(&self) -> Result<Order, OrderError> {
+        let mut order = Order::from_events(&self.events).map_err(|e| {
+            OrderError::storage_failed(format!("the stored events don't replay: {e}"))
+        })?;
+        order.version = self.version;
+        Ok(order)
+    }
+
+    /// Appends the events turning the current order into `order`.
+    fn record(&mut self, order: &Order) -> Result<(), OrderError> {
+        let current = self.replay()?;
+        let events = changes(&current, order)?;
+        self.events.extend(events);
+        self.version = order.version;
+        Ok(())
+    }
+}
+
+impl EventSourcedOrderRepository {
+    /// Creates a new empty repository.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    /// Every event of one of `tenant`'s orders, oldest first.
+    ///
+    /// Empty if there is no such order.
+    #[must_use]
+    pub fn history(&self, tenant: TenantId, id: OrderId) -> Vec<OrderEvent> {
+        self.read()
+            .get(&(tenant, id))
+            .map(|stream| stream.events.clone())
+            .unwrap_or_default()
+    }
+
+    /// Appends events to an existing order, and returns the order they lead to.
+    ///
+    /// For callers that know what happened: nothing to work out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] if no such order is stored, or
+    /// the error of the first event that doesn't apply. Then nothing is
+    /// appended.
+    pub fn append(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+        events: &[OrderEvent],
+    ) -> Result<Order, OrderError> {
+        self.log(&format!(
+            "[EventSourced] Appending {} event(s) to order #{id}",
+            events.len()
+        ));
+        let mut streams = self.write();
+        let stream = streams
+            .get_mut(&(tenant, id))
+            .ok_or(OrderError::OrderNotFound(id))?;
+
+        // Check the whole batch first: all or nothing
+        let mut order = stream.replay()?;
+        for event in events {
+            order.apply(event)?;
+        }
+        stream.events.extend_from_slice(events);
+        Ok(order)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<(TenantId, OrderId), Stream>> {
+        // A poisoned lock only means another thread panicked; the events are still there
+        self.streams.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<(TenantId, OrderId), Stream>> {
+        self.streams.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The events turning `from` into `to`.
+///
+/// Works out the likely sequence (items first, then the payment, then the
+/// status), then checks it by replaying: if the result isn't exactly `to`,
+/// the difference isn't something events can say.
+///
+/// The status events take their time and reason from the entries `to` added
+/// to the history.
+fn changes(from: &Order, to: &Order) -> Result<Vec<OrderEvent>, OrderError> {
+    let mut events = Vec::new();
+    let added = to
+        .history
+        .strip_prefix(from.history.as_slice())
+        .unwrap_or_default();
+    let entry = |status| added.iter().find(|entry| entry.to == status);
+    // A status that changed without an entry won't replay to `to`, so any
+    // time does; so does a partial refund's, which changes no status
+    let at = |status| entry(status).map_or(UNIX_EPOCH, |entry| entry.at);
+
+    if let Some(added) = to.items.strip_prefix(from.items.as_slice()) {
+        events.extend(added.iter().cloned().map(OrderEvent::ItemAdded));
+    }
+    if let Some(added) = to.extra_charges.strip_prefix(from.extra_charges.as_slice()) {
+        events.extend(added.iter().map(|charge| OrderEvent::ExtraCharged {
+            transaction_id: charge.transaction_id.clone(),
+            amount: charge.amount,
+        }));
+    }
+    if to.shipping != from.shipping {
+        events.push(OrderEvent::ShippingSet(to.shipping.clone()));
+    }
+    if to.transaction_id != from.transaction_id
+        && let Some(transaction_id) = &to.transaction_id
+    {
+        events.push(OrderEvent::PaymentRecorded {
+            transaction_id: transaction_id.clone(),
+        });
+    }
+    if from.status == OrderStatus::Pending && to.status != OrderStatus::Pending {
+        // Shipped and refunded orders were paid on the way
+        if matches!(
+            to.status,
+            OrderStatus::Paid | OrderStatus::Shipped | OrderStatus::Refunded
+        ) {
+            events.push(OrderEvent::Paid {
+                at: at(OrderStatus::Paid),
+            });
+        }
+    }
+    match to.status {
+        OrderStatus::Cancelled if from.status != OrderStatus::Cancelled => {
+            events.push(OrderEvent::Cancelled {
+                at: at(OrderStatus::Cancelled),
+                reason: entry(OrderStatus::Cancelled).and_then(|entry| entry.reason.clone()),
+            });
+        }
+        _ if to.refunded.0 > from.refunded.0 => events.push(OrderEvent::Refunded {
+            amount: Money(to.refunded.0 - from.refunded.0),
+            at: at(OrderStatus::Refunded),
+        }),
+        _ => {}
+    }
+    if to.status == OrderStatus::Shipped && from.status != OrderStatus::Shipped {
+        events.push(OrderEvent::Shipped {
+            at: at(OrderStatus::Shipped),
+        });
+    }
+    if to.loyalty_credited != from.loyalty_credited {
+        events.push(OrderEvent::LoyaltyRecorded {
+            credited: to.loyalty_credited,
+        });
+    }
+
+    let mut replayed = from.clone();
+    for event in &events {
+        replayed
+            .apply(event)
+            .map_err(|e| unexpressible(to.id, &e))?;
+    }
+    replayed.version = to.version;
+    if replayed != *to {
+        return Err(unexpressible(to.id, &"the orders still differ"));
+    }
+    Ok(events)
+}
+
+fn unexpressible(id: OrderId, why: &dyn fmt::Display) -> OrderError {
+    OrderError::storage_failed(format!(
+        "the change to order #{id} isn't expressible as events: {why}"
+    ))
+}
+
+impl OrderRepository for EventSourcedOrderRepository {
+    /// Starts a stream: `Placed`, plus whatever happened before the first save
+    /// (an order may be stored already paid).
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[EventSourced] Starting the stream of order #{}",
+            order.id
+        ));
+        let mut stream = Stream {
+            events: vec![OrderEvent::Placed {
+                id: order.id,
+                tenant: order.tenant,
+                items: order.items.clone(),
+                customer_id: order.customer_id,
+                gift_card: order.gift_card,
+                at: order
+                    .history
+                    .first()
+                    .filter(|entry| entry.from.is_none())
+                    .map(|entry| entry.at),
+            }],
+            version: order.version,
+        };
+        stream.record(order)?;
+
+        match self.write().entry((order.tenant, order.id)) {
+            Entry::Occupied(_) => Err(OrderError::DuplicateOrder(order.id)),
+            Entry::Vacant(slot) => {
+                slot.insert(stream);
+                Ok(())
+            }
+        }
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[EventSourced] Appending changes to order #{}",
+            order.id
+        ));
+        self.write()
+            .get_mut(&(order.tenant, order.id))
+            .ok_or(OrderError::OrderNotFound(order.id))?
+            .record(order)
+    }
+
+    /// The version check and the append happen under the same write lock.
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[EventSourced] Appending changes to order #{} at version {expected}",
+            order.id
+        ));
+        let mut streams = self.write();
+        let stream = streams
+            .get_mut(&(order.tenant, order.id))
+            .ok_or(OrderError::OrderNotFound(order.id))?;
+        if stream.version != expected {
+            return Err(OrderError::Conflict {
+                id: order.id,
+                expected,
+                found: stream.version,
+            });
+        }
+        stream.record(order)
+    }
+
+    /// Forgets the order, history included.
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.log(&format!(
+            "[EventSourced] Deleting the stream of order #{id}"
+        ));
+        Ok(self.write().remove(&(tenant, id)).is_some())
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.find_with_ctx(&RequestContext::default(), tenant, id)
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.log(&format!("[EventSourced] Replaying all orders of {tenant}"));
+        let mut orders = self
+            .read()
+            .iter()
+            .filter(|((stream_tenant, _), _)| *stream_tenant == tenant)
+            .map(|(_, stream)| stream.replay())
+            .collect::<Result<Vec<_>, _>>()?;
+        orders.sort_by_key(|order| order.id);
+        Ok(orders)
+    }
+
+    fn find_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.log(&format!("[EventSourced] Replaying order #{id} of {tenant}"));
+        self.read()
+            .get(&(tenant, id))
+            .map(Stream::replay)
+            .transpose()
+    }
+}
+
+impl HealthCheck for EventSourcedOrderRepository {
+    // Its event log lives in memory: always there
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WriterLogger;
+    use domain::{Address, ExtraCharge, LineItem, Shipping};
+    use std::time::Duration;
+
+    fn item(name: &str, price: u32) -> LineItem {
+        LineItem {
+            name: name.to_string(),
+            price: Money(price),
+            weight_grams: None,
+        }
+    }
+
+    fn find(repo: &EventSourcedOrderRepository, id: u32) -> Order {
+        repo.find(TenantId::default(), OrderId(id))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn place_update_cancel_replays_to_the_same_order() {
+        let repo = EventSourcedOrderRepository::new();
+        let mut order = Order::new(OrderId(1), vec![item("Book", 1000), item("Pen", 200)]).unwrap();
+        let placed = UNIX_EPOCH + Duration::from_secs(30);
+        order.record_placed(placed).unwrap();
+        repo.insert(&order).unwrap();
+
+        order.add_item(item("Pad", 300)).unwrap();
+        order.transaction_id = Some("txn_1".to_string());
+        let paid = UNIX_EPOCH + Duration::from_secs(60);
+        order.mark_paid(paid).unwrap();
+        order.version = 1;
+        repo.update_versioned(&order, 0).unwrap();
+        assert_eq!(find(&repo, 1), order);
+
+        let cancelled = paid + Duration::from_secs(60);
+        order.cancel(cancelled, Some("out of stock")).unwrap();
+        repo.update(&order).unwrap();
+        assert_eq!(find(&repo, 1), order);
+
+        assert_eq!(
+            repo.history(TenantId::default(), OrderId(1)),
+            vec![
+                OrderEvent::Placed {
+                    id: OrderId(1),
+                    tenant: TenantId::default(),
+                    items: vec![item("Book", 1000), item("Pen", 200)],
+                    customer_id: None,
+                    gift_card: None,
+                    at: Some(placed),
+                },
+                OrderEvent::ItemAdded(item("Pad", 300)),
+                OrderEvent::PaymentRecorded {
+                    transaction_id: "txn_1".to_string()
+                },
+                OrderEvent::Paid { at: paid },
+                OrderEvent::Cancelled {
+                    at: cancelled,
+                    reason: Some("out of stock".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn an_item_added_after_payment_keeps_its_charge() {
+        let repo = EventSourcedOrderRepository::new();
+        let mut order = Order::new(OrderId(1), vec![item("Book", 1000)]).unwrap();
+        order.transaction_id = Some("txn_1".to_string());
+        order.mark_paid(UNIX_EPOCH).unwrap();
+        repo.insert(&order).unwrap();
+
+        order.add_item(item("Pen", 200)).unwrap();
+        order.extra_charges.push(ExtraCharge {
+            transaction_id: "txn_2".to_string(),
+            amount: Money(200),
+        });
+        repo.update(&order).unwrap();
+
+        assert_eq!(find(&repo, 1), order);
+        assert_eq!(
+            repo.history(TenantId::default(), OrderId(1))[3..],
+            [
+                OrderEvent::ItemAdded(item("Pen", 200)),
+                OrderEvent::ExtraCharged {
+                    transaction_id: "txn_2".to_string(),
+                    amount: Money(200),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn shipping_is_repriced_after_the_item_it_ships() {
+        let repo = EventSourcedOrderRepository::new();
+        let mut order = Order::new(OrderId(1), vec![item("Book", 1000)]).unwrap();
+        let destination = Address::new("1 Main St", "Springfield", "12345", "US").unwrap();
+        let shipping = |cost| {
+            Some(Shipping {
+                destination: destination.clone(),
+                cost: Money(cost),
+            })
+        };
+        order.set_shipping(shipping(499)).unwrap();
+        repo.insert(&order).unwrap();
+
+        order.add_item(item("Pen", 200)).unwrap();
+        order.set_shipping(shipping(899)).unwrap();
+        repo.update(&order).unwrap();
+
+        assert_eq!(find(&repo, 1), order);
+        assert_eq!(find(&repo, 1).total, Money(2099));
+    }
+
+    #[test]
+    fn credited_points_are_recorded() {
+        let repo = EventSourcedOrderRepository::new();
+        let mut order = Order::new(OrderId(1), vec![item("Book", 1000)]).unwrap();
+        order.transaction_id = Some("txn_1".to_string());
+        order.mark_paid(UNIX_EPOCH).unwrap();
+        repo.insert(&order).unwrap();
+
+        order.loyalty_credited = 10;
+        repo.update(&order).unwrap();
+        order.record_refund(Money(400), UNIX_EPOCH).unwrap();
+        order.take_back_points();
+        repo.update(&order).unwrap();
+
+        assert_eq!(find(&repo, 1), order);
+        assert_eq!(find(&repo, 1).loyalty_credited, 6);
+    }
+
+    #[test]
+    fn change_without_an_event_is_refused() {
+        use std::error::Error as _;
+
+        let repo = EventSourcedOrderRepository::new();
+        let order = Order::new(OrderId(1), vec![item("Book", 1000)]).unwrap();
+        repo.insert(&order).unwrap();
+
+        let mut tampered = order.clone();
+        tampered.total = Money(1);
+
+        let e = repo.update(&tampered).unwrap_err();
+        assert!(matches!(e, OrderError::StorageFailed(_)));
+        let source = e.source().expect("the reason is attached").to_string();
+        assert!(
+            source.starts_with("the change to order #OrderId(1) isn't expressible as events"),
+            "{source}"
+        );
+        assert_eq!(find(&repo, 1), order);
+    }
+
+    #[test]
+    fn log_lines_go_to_the_logger() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let repo = EventSourcedOrderRepository::new().with_logger(logger.clone());
+
+        repo.insert(&Order::new(OrderId(1), vec![item("Book", 1000)]).unwrap())
+            .unwrap();
+        find(&repo, 1);
+
+        assert_eq!(
+            logger.text(),
+            "  [EventSourced] Starting the stream of order #OrderId(1)\n  \
+             [EventSourced] Replaying order #OrderId(1) of TenantId(0)\n"
+        );
+    }
+
+    #[test]
+    fn append_takes_events_directly() {
+        let repo = EventSourcedOrderRepository::new();
+        repo.insert(&Order::new(OrderId(1), vec![item("Book", 1000)]).unwrap())
+            .unwrap();
+
+        let order = repo
+            .append(
+                TenantId::default(),
+                OrderId(1),
+                &[
+                    OrderEvent::Paid { at: UNIX_EPOCH },
+                    OrderEvent::Shipped { at: UNIX_EPOCH },
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(order.status, OrderStatus::Shipped);
+        assert_eq!(find(&repo, 1), order);
+        // A refused batch appends nothing
+        assert!(
+            repo.append(
+                TenantId::default(),
+                OrderId(1),
+                &[OrderEvent::Cancelled {
+                    at: UNIX_EPOCH,
+                    reason: None,
+                }]
+            )
+            .is_err()
+        );
+        assert_eq!(repo.history(TenantId::default(), OrderId(1)).len(), 3);
+    }
+}
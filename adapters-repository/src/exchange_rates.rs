@@ -0,0 +1,68 @@
+// =============================================================================
+// Static Exchange Rates - Rates Seeded from a Map
+// =============================================================================
+//
+// The `ExchangeRates` port says what a currency is worth in another. Here
+// the rates are a HashMap filled once at startup, like the price list: good
+// for tests and demos, where rates don't move.
+//
+// Only the pairs given are quoted. USD to EUR says nothing about EUR to USD:
+// 1 / 0.91 has no exact four-decimal answer, and a real feed quotes both
+// directions anyway. A currency against itself is always 1.
+//
+// In production, the map would be refreshed from a rate feed once a day.
+
+use domain::{Currency, ExchangeRates, OrderError, Rate};
+use std::collections::HashMap;
+
+/// Exchange rates that never change, for testing scenarios.
+#[derive(Debug, Default)]
+pub struct StaticExchangeRates {
+    rates: HashMap<(Currency, Currency), Rate>,
+}
+
+impl StaticExchangeRates {
+    /// Creates rates quoting exactly the `(from, to)` pairs in `rates`.
+    #[must_use]
+    pub const fn new(rates: HashMap<(Currency, Currency), Rate>) -> Self {
+        Self { rates }
+    }
+}
+
+impl ExchangeRates for StaticExchangeRates {
+    fn rate(&self, from: Currency, to: Currency) -> Result<Rate, OrderError> {
+        if from == to {
+            return Ok(Rate::ONE);
+        }
+        self.rates
+            .get(&(from, to))
+            .copied()
+            .ok_or(OrderError::NoExchangeRate { from, to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_pairs_given_are_quoted() {
+        let rates = StaticExchangeRates::new(HashMap::from([(
+            (Currency::Usd, Currency::Eur),
+            Rate(9100),
+        )]));
+
+        assert_eq!(
+            rates.rate(Currency::Usd, Currency::Eur).unwrap(),
+            Rate(9100)
+        );
+        assert_eq!(rates.rate(Currency::Gbp, Currency::Gbp).unwrap(), Rate::ONE);
+        assert!(matches!(
+            rates.rate(Currency::Eur, Currency::Usd),
+            Err(OrderError::NoExchangeRate {
+                from: Currency::Eur,
+                to: Currency::Usd
+            })
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+// =============================================================================
+// Expiring Repository - Orders With a Time-to-Live
+// =============================================================================
+//
+// Draft orders are shopping carts: started, then often abandoned. Nobody
+// wants to delete them by hand, so this repository forgets them on its own.
+// Each order is stored with the time it was last saved; once it's older than
+// the TTL, it's gone as far as `find`, `find_all` and `count` can tell.
+//
+// "Last saved", not "first inserted": a cart the customer keeps touching
+// stays alive. Every insert and update stamps the order again.
+//
+// Expired orders still take memory until `purge_expired` removes them. A
+// background job would call it every few minutes; Redis does both for us
+// (`SET key value EX 1800`), which is what a production cart store would use.
+//
+// Time comes from the `Clock` port: SystemClock in production, FixedClock in
+// tests, which skip half an hour with one `advance` instead of sleeping.
+
+use crate::InMemoryStore;
+use domain::{
+    Clock, HealthCheck, HealthStatus, Order, OrderError, OrderId, OrderRepository, TenantId,
+};
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, SystemTime};
+
+/// In-memory order repository forgetting orders a while after their last save.
+pub struct ExpiringInMemoryOrderRepository {
+    orders: RwLock<InMemoryStore<(TenantId, OrderId), Entry>>,
+    ttl: Duration,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    order: Order,
+    saved_at: SystemTime,
+}
+
+// Manual impl: the clock is a trait object with no Debug of its own.
+impl fmt::Debug for ExpiringInMemoryOrderRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpiringInMemoryOrderRepository")
+            .field("orders", &self.orders)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExpiringInMemoryOrderRepository {
+    /// Creates an empty repository, forgetting orders `ttl` after their last
+    /// save, as told by `clock`.
+    #[must_use]
+    pub fn new(ttl: Duration, clock: Arc<dyn Clock + Send + Sync>) -> Self {
+        Self {
+            orders: RwLock::new(InMemoryStore::new()),
+            ttl,
+            clock,
+        }
+    }
+
+    /// How long an order lives after its last save.
+    #[must_use]
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Number of orders of `tenant` that haven't expired.
+    #[must_use]
+    pub fn count(&self, tenant: TenantId) -> usize {
+        let now = self.clock.now();
+        self.read()
+            .values()
+            .filter(|entry| entry.order.tenant == tenant && self.alive(entry, now))
+            .count()
+    }
+
+    /// Removes the expired orders, of every tenant, and returns how many.
+    pub fn purge_expired(&self) -> usize {
+        let now = self.clock.now();
+        let mut orders = self.write();
+        let expired: Vec<_> = orders
+            .values()
+            .filter(|entry| !self.alive(entry, now))
+            .map(|entry| (entry.order.tenant, entry.order.id))
+            .collect();
+        for key in &expired {
+            orders.remove(*key);
+        }
+        println!("  [Expiring] Purged {} expired orders", expired.len());
+        expired.len()
+    }
+
+    /// True until `ttl` has passed since the order was saved.
+    ///
+    /// A clock set back before `saved_at` keeps the order alive.
+    fn alive(&self, entry: &Entry, now: SystemTime) -> bool {
+        now.duration_since(entry.saved_at)
+            .map_or(true, |age| age < self.ttl)
+    }
+
+    /// The order stored under `key`, unless it has expired.
+    fn live(&self, key: (TenantId, OrderId)) -> Option<Order> {
+        let now = self.clock.now();
+        self.read()
+            .get(key)
+            .filter(|entry| self.alive(entry, now))
+            .map(|entry| entry.order.clone())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, InMemoryStore<(TenantId, OrderId), Entry>> {
+        // A poisoned lock only means another thread panicked; the orders are still fine
+        self.orders.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, InMemoryStore<(TenantId, OrderId), Entry>> {
+        // A poisoned lock only means another thread panicked; the orders are still fine
+        self.orders.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl OrderRepository for ExpiringInMemoryOrderRepository {
+    /// Inserts a new order. An expired one no longer holds its ID.
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        let now = self.clock.now();
+        let mut orders = self.write();
+        let key = (order.tenant, order.id);
+        if orders.get(key).is_some_and(|entry| self.alive(entry, now)) {
+            return Err(OrderError::DuplicateOrder(order.id));
+        }
+        orders.insert(
+            key,
+            Entry {
+                order: order.clone(),
+                saved_at: now,
+            },
+        );
+        Ok(())
+    }
+
+    /// Replaces an order, and starts its TTL over.
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        let now = self.clock.now();
+        let mut orders = self.write();
+        let entry = orders
+            .get_mut((order.tenant, order.id))
+            .filter(|entry| self.alive(entry, now))
+            .ok_or(OrderError::OrderNotFound(order.id))?;
+        *entry = Entry {
+            order: order.clone(),
+            saved_at: now,
+        };
+        Ok(())
+    }
+
+    /// Removes an order. An expired one is already gone: `false`.
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        let now = self.clock.now();
+        let removed = self.write().remove((tenant, id));
+        Ok(removed.is_some_and(|entry| self.alive(&entry, now)))
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        Ok(self.live((tenant, id)))
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        let now = self.clock.now();
+        let mut orders: Vec<Order> = self
+            .read()
+            .values()
+            .filter(|entry| entry.order.tenant == tenant && self.alive(entry, now))
+            .map(|entry| entry.order.clone())
+            .collect();
+        orders.sort_by_key(|order| order.id);
+        Ok(orders)
+    }
+}
+
+impl HealthCheck for ExpiringInMemoryOrderRepository {
+    // In memory, like InMemoryOrderRepository: always there
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedClock;
+    use domain::{LineItem, Money};
+    use std::time::UNIX_EPOCH;
+
+    const TTL: Duration = Duration::from_secs(30 * 60);
+
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
+            vec![LineItem {
+                name: "Test".to_string(),
+                price: Money(100),
+                weight_grams: None,
+            }],
+        )
+        .unwrap()
+    }
+
+    fn repo() -> (ExpiringInMemoryOrderRepository, Arc<FixedClock>) {
+        let clock = Arc::new(FixedClock::new(UNIX_EPOCH));
+        let repo = ExpiringInMemoryOrderRepository::new(TTL, Arc::clone(&clock) as _);
+        (repo, clock)
+    }
+
+    fn find(repo: &ExpiringInMemoryOrderRepository, id: u32) -> Option<Order> {
+        repo.find(TenantId::default(), OrderId(id)).unwrap()
+    }
+
+    #[test]
+    fn an_order_vanishes_once_its_ttl_has_passed() {
+        let (repo, clock) = repo();
+        repo.insert(&test_order(1)).unwrap();
+
+        clock.advance(TTL - Duration::from_secs(1));
+        assert!(find(&repo, 1).is_some());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(find(&repo, 1).is_none());
+        assert!(repo.find_all(TenantId::default()).unwrap().is_empty());
+        assert_eq!(repo.count(TenantId::default()), 0);
+        // Gone means the ID is free again
+        repo.insert(&test_order(1)).unwrap();
+    }
+
+    #[test]
+    fn saving_again_resets_the_clock() {
+        let (repo, clock) = repo();
+        repo.insert(&test_order(1)).unwrap();
+
+        clock.advance(Duration::from_secs(20 * 60));
+        repo.update(&test_order(1)).unwrap();
+        clock.advance(Duration::from_secs(20 * 60));
+
+        // 40 minutes after the insert, 20 after the update
+        assert!(find(&repo, 1).is_some());
+    }
+
+    #[test]
+    fn purge_expired_removes_and_counts_the_expired_orders() {
+        let (repo, clock) = repo();
+        repo.insert(&test_order(1)).unwrap();
+        repo.insert(&test_order(2)).unwrap();
+        clock.advance(TTL);
+        repo.insert(&test_order(3)).unwrap();
+
+        assert_eq!(repo.purge_expired(), 2);
+        assert_eq!(repo.purge_expired(), 0);
+        assert_eq!(repo.count(TenantId::default()), 1);
+        assert!(matches!(
+            repo.update(&test_order(1)),
+            Err(OrderError::OrderNotFound(OrderId(1)))
+        ));
+    }
+}
@@ -0,0 +1,381 @@
+// =============================================================================
+// Faulty Repository - Storage That Fails on Cue
+// =============================================================================
+//
+// The interesting paths of a use case are the ones where something breaks:
+// the order was charged, then the database went away. Real databases don't
+// fail when a test asks them to, so this decorator does it for them.
+//
+// FaultyOrderRepository wraps any repository and forwards every call, except
+// the ones its script says must fail. Those return StorageFailed without
+// reaching the inner repository, exactly like a dropped connection would.
+//
+// The script can say:
+// - fail the next N saves (inserts and updates)
+// - fail every Kth find
+// - fail saving any order matching a predicate (e.g. "once it's Paid")
+//
+// and the counters tell the test how many calls were made and how many
+// failed, so it can check that a retry really retried.
+
+use domain::{
+    HealthCheck, HealthStatus, Order, OrderError, OrderId, OrderRepository, RequestContext,
+    TenantId,
+};
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+type OrderPredicate = Box<dyn Fn(&Order) -> bool + Send + Sync>;
+
+/// Repository decorator failing on demand, for chaos testing.
+pub struct FaultyOrderRepository<R> {
+    inner: R,
+    script: Mutex<Script>,
+}
+
+#[derive(Default)]
+struct Script {
+    failing_saves: u32,
+    every_nth_find: Option<u32>,
+    failing_orders: Option<OrderPredicate>,
+    counters: FaultCounters,
+}
+
+/// How many calls reached the decorator, and how many of them it failed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounters {
+    /// Inserts and updates attempted.
+    pub saves: u32,
+    /// Finds attempted (`find`, `find_all` and `find_including_deleted`).
+    pub finds: u32,
+    /// Calls failed on purpose.
+    pub failures: u32,
+}
+
+// Manual impl: the predicate is a closure with no Debug of its own.
+impl<R: fmt::Debug> fmt::Debug for FaultyOrderRepository<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let script = self.script();
+        f.debug_struct("FaultyOrderRepository")
+            .field("inner", &self.inner)
+            .field("failing_saves", &script.failing_saves)
+            .field("every_nth_find", &script.every_nth_find)
+            .field("failing_orders", &script.failing_orders.is_some())
+            .field("counters", &script.counters)
+            .finish()
+    }
+}
+
+impl<R> FaultyOrderRepository<R> {
+    /// Wraps `inner`. Until told otherwise, every call goes through.
+    #[must_use]
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            script: Mutex::new(Script::default()),
+        }
+    }
+
+    /// Fails the next `count` saves. Replaces any count left over.
+    pub fn fail_next_saves(&self, count: u32) {
+        self.script().failing_saves = count;
+    }
+
+    /// Fails every `k`th find: with 3, the 3rd, 6th, 9th... `None` stops it.
+    ///
+    /// Counted from the first find ever made, not from this call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is `Some(0)`.
+    pub fn fail_every_nth_find(&self, k: Option<u32>) {
+        assert_ne!(k, Some(0), "every 0th find makes no sense");
+        self.script().every_nth_find = k;
+    }
+
+    /// Fails saving any order for which `predicate` returns true.
+    pub fn fail_saving_when(&self, predicate: impl Fn(&Order) -> bool + Send + Sync + 'static) {
+        self.script().failing_orders = Some(Box::new(predicate));
+    }
+
+    /// What happened so far.
+    #[must_use]
+    pub fn counters(&self) -> FaultCounters {
+        self.script().counters
+    }
+
+    /// The wrapped repository.
+    #[must_use]
+    pub const fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    fn script(&self) -> MutexGuard<'_, Script> {
+        // A poisoned lock only means another thread panicked; the script is still fine
+        self.script.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Counts a save, and fails it if the script says so.
+    fn save_allowed(&self, order: &Order) -> Result<(), OrderError> {
+        let mut script = self.script();
+        script.counters.saves += 1;
+        let fail = if script.failing_saves > 0 {
+            script.failing_saves -= 1;
+            true
+        } else {
+            script
+                .failing_orders
+                .as_ref()
+                .is_some_and(|predicate| predicate(order))
+        };
+        Self::outcome(&mut script, fail, "save")
+    }
+
+    /// Counts a find, and fails it if the script says so.
+    fn find_allowed(&self) -> Result<(), OrderError> {
+        let mut script = self.script();
+        script.counters.finds += 1;
+        let finds = script.counters.finds;
+        let fail = script
+            .every_nth_find
+            .is_some_and(|k| finds.is_multiple_of(k));
+        Self::outcome(&mut script, fail, "find")
+    }
+
+    fn outcome(script: &mut Script, fail: bool, what: &str) -> Result<(), OrderError> {
+        if fail {
+            script.counters.failures += 1;
+            println!("  [Faulty] Failing this {what} on purpose");
+            return Err(OrderError::StorageFailed(None));
+        }
+        Ok(())
+    }
+}
+
+impl<R: OrderRepository> OrderRepository for FaultyOrderRepository<R> {
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.insert_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.update_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.save_allowed(order)?;
+        self.inner.update_versioned(order, expected)
+    }
+
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.inner.delete(tenant, id)
+    }
+
+    /// Each order counts as a save. One failing fails the whole batch,
+    /// before any of it reaches the inner repository.
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        orders
+            .iter()
+            .try_for_each(|order| self.save_allowed(order))?;
+        self.inner.save_all(orders)
+    }
+
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.inner.soft_delete(tenant, id)
+    }
+
+    fn restore(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.inner.restore(tenant, id)
+    }
+
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.find_allowed()?;
+        self.inner.find_including_deleted(tenant, id)
+    }
+
+    fn find_with(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+        read: &mut dyn FnMut(Option<&Order>),
+    ) -> Result<(), OrderError> {
+        self.find_allowed()?;
+        self.inner.find_with(tenant, id, read)
+    }
+
+    fn for_each(
+        &self,
+        tenant: TenantId,
+        visit: &mut dyn FnMut(&Order) -> ControlFlow<()>,
+    ) -> Result<(), OrderError> {
+        self.find_allowed()?;
+        self.inner.for_each(tenant, visit)
+    }
+
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        self.find_allowed()?;
+        self.inner.find_many(tenant, ids)
+    }
+
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        self.find_allowed()?;
+        self.inner.find_by_item_name(tenant, query)
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.find_with_ctx(&RequestContext::default(), tenant, id)
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.find_allowed()?;
+        self.inner.find_all(tenant)
+    }
+
+    fn insert_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.save_allowed(order)?;
+        self.inner.insert_with_ctx(ctx, order)
+    }
+
+    fn update_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.save_allowed(order)?;
+        self.inner.update_with_ctx(ctx, order)
+    }
+
+    fn find_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.find_allowed()?;
+        self.inner.find_with_ctx(ctx, tenant, id)
+    }
+}
+
+impl<R: HealthCheck> HealthCheck for FaultyOrderRepository<R> {
+    /// Degraded while the script fails some calls, unhealthy when it fails
+    /// every find, on top of whatever the inner repository says.
+    fn health(&self) -> HealthStatus {
+        let script = self.script();
+        if script.every_nth_find == Some(1) {
+            return HealthStatus::Unhealthy("every find fails".to_string());
+        }
+        let mut faults = Vec::new();
+        if script.failing_saves > 0 {
+            faults.push(format!("saves left to fail: {}", script.failing_saves));
+        }
+        if script.failing_orders.is_some() {
+            faults.push("some saves fail".to_string());
+        }
+        if let Some(k) = script.every_nth_find {
+            faults.push(format!("one find in {k} fails"));
+        }
+        drop(script);
+
+        let own = if faults.is_empty() {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Degraded(faults.join(", "))
+        };
+        own.max(self.inner.health())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryOrderRepository;
+    use domain::{LineItem, Money};
+
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
+            vec![LineItem {
+                name: "Test".to_string(),
+                price: Money(100),
+                weight_grams: None,
+            }],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn next_saves_fail_then_go_through() {
+        let repo = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        repo.fail_next_saves(2);
+
+        assert!(matches!(
+            repo.insert(&test_order(1)),
+            Err(OrderError::StorageFailed(_))
+        ));
+        assert!(matches!(
+            repo.insert(&test_order(1)),
+            Err(OrderError::StorageFailed(_))
+        ));
+        repo.insert(&test_order(1)).unwrap();
+
+        assert_eq!(
+            repo.counters(),
+            FaultCounters {
+                saves: 3,
+                finds: 0,
+                failures: 2
+            }
+        );
+        assert_eq!(repo.inner().find_all(TenantId::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn every_nth_find_fails() {
+        let repo = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        repo.fail_every_nth_find(Some(2));
+
+        let failed: Vec<bool> = (0..4)
+            .map(|_| repo.find(TenantId::default(), OrderId(1)).is_err())
+            .collect();
+
+        assert_eq!(failed, vec![false, true, false, true]);
+        assert_eq!(repo.counters().failures, 2);
+    }
+
+    #[test]
+    fn saves_matching_the_predicate_fail() {
+        let repo = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        repo.fail_saving_when(|order| order.id == OrderId(2));
+
+        repo.insert(&test_order(1)).unwrap();
+
+        assert!(matches!(
+            repo.insert(&test_order(2)),
+            Err(OrderError::StorageFailed(_))
+        ));
+        assert!(
+            repo.find(TenantId::default(), OrderId(2))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn health_follows_the_script() {
+        let repo = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        assert_eq!(repo.health(), HealthStatus::Healthy);
+
+        repo.fail_next_saves(2);
+        repo.fail_every_nth_find(Some(3));
+        assert_eq!(
+            repo.health(),
+            HealthStatus::Degraded("saves left to fail: 2, one find in 3 fails".to_string())
+        );
+
+        repo.fail_every_nth_find(Some(1));
+        assert_eq!(
+            repo.health(),
+            HealthStatus::Unhealthy("every find fails".to_string())
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// =============================================================================
+// In-Memory Idempotency Store - Remembering Which Request Made Which Order
+// =============================================================================
+//
+// When a client retries a timed-out checkout, it sends the same idempotency
+// key again. This store maps each key to the order it produced, so the
+// application can hand back the first order instead of creating a second one.
+//
+// In production this would typically be Redis (SET key value NX EX 86400)
+// or a database table with a unique index on the key.
+
+use domain::{IdempotencyKey, IdempotencyStore, OrderError, OrderId};
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+/// In-memory idempotency store for testing scenarios.
+///
+/// Keys live as long as the process. A real store would expire them after
+/// a day or so.
+#[derive(Debug, Default)]
+pub struct InMemoryIdempotencyStore {
+    keys: RwLock<HashMap<IdempotencyKey, OrderId>>,
+}
+
+impl InMemoryIdempotencyStore {
+    /// Creates a new empty idempotency store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    fn find(&self, key: &IdempotencyKey) -> Result<Option<OrderId>, OrderError> {
+        println!("  [InMemory] Looking up idempotency key '{key}'");
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        Ok(keys.get(key).copied())
+    }
+
+    fn record(&self, key: IdempotencyKey, id: OrderId) -> Result<(), OrderError> {
+        println!("  [InMemory] Recording idempotency key '{key}' -> {id}");
+        self.keys
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_and_find_key() {
+        let store = InMemoryIdempotencyStore::new();
+        let key = IdempotencyKey("checkout-1".to_string());
+
+        store.record(key.clone(), OrderId(7)).unwrap();
+
+        assert_eq!(store.find(&key).unwrap(), Some(OrderId(7)));
+    }
+
+    #[test]
+    fn find_unknown_key_returns_none() {
+        let store = InMemoryIdempotencyStore::new();
+        let key = IdempotencyKey("never-seen".to_string());
+
+        assert_eq!(store.find(&key).unwrap(), None);
+    }
+}
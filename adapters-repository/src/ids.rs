@@ -0,0 +1,94 @@
+// =============================================================================
+// Sequential ID Generator - Counting From One
+// =============================================================================
+//
+// The `IdGenerator` port hands out fresh IDs. This adapter simply counts:
+// 1, 2, 3... one counter each for orders, customers and subscriptions.
+//
+// The counters are atomics, so the generator can be shared between threads
+// through `&self` and never hands out the same ID twice.
+//
+// In production, IDs would come from the database instead:
+//
+//     SELECT nextval('orders_id_seq')
+//
+// or from a UUID/ULID generator when IDs must be created without a round trip.
+
+use domain::{CustomerId, IdGenerator, OrderId, SubscriptionId};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// ID generator counting up from 1, safe to share between threads.
+#[derive(Debug)]
+pub struct SequentialIdGenerator {
+    next_order: AtomicU32,
+    next_customer: AtomicU32,
+    next_subscription: AtomicU32,
+}
+
+impl SequentialIdGenerator {
+    /// Creates a generator whose first order, customer and subscription
+    /// IDs are 1.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            next_order: AtomicU32::new(1),
+            next_customer: AtomicU32::new(1),
+            next_subscription: AtomicU32::new(1),
+        }
+    }
+
+    /// Creates a generator whose first order ID is `first_order`, for orders
+    /// kept from an earlier run. The other IDs still start at 1.
+    #[must_use]
+    pub const fn starting_at(first_order: OrderId) -> Self {
+        Self {
+            next_order: AtomicU32::new(first_order.0),
+            next_customer: AtomicU32::new(1),
+            next_subscription: AtomicU32::new(1),
+        }
+    }
+}
+
+impl Default for SequentialIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_order_id(&self) -> OrderId {
+        // Relaxed is enough: we only need each value handed out once
+        OrderId(self.next_order.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn next_customer_id(&self) -> CustomerId {
+        CustomerId(self.next_customer.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn next_subscription_id(&self) -> SubscriptionId {
+        SubscriptionId(self.next_subscription.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_count_up_independently() {
+        let ids = SequentialIdGenerator::new();
+
+        assert_eq!(ids.next_order_id(), OrderId(1));
+        assert_eq!(ids.next_order_id(), OrderId(2));
+        assert_eq!(ids.next_customer_id(), CustomerId(1));
+        assert_eq!(ids.next_subscription_id(), SubscriptionId(1));
+    }
+
+    #[test]
+    fn order_ids_can_start_further_up() {
+        let ids = SequentialIdGenerator::starting_at(OrderId(8));
+
+        assert_eq!(ids.next_order_id(), OrderId(8));
+        assert_eq!(ids.next_customer_id(), CustomerId(1));
+    }
+}
@@ -12,19 +12,68 @@
 // 2. CI/CD pipelines don't need database containers
 // 3. Local development works without infrastructure
 // 4. Demos work anywhere
+//
+// The HashMap sits behind an RwLock: the port takes `&self` so one repository
+// can serve many threads. Many readers at once, one writer at a time.
+//
+// The HashMap is an `InMemoryStore` (see store.rs), keyed by (tenant, id)
+// like the table's primary key: an order of another tenant can't be found,
+// even with the right ID.
+//
+// Soft-deleted orders move to a second store, the tombstones. `find` and
+// `find_all` only look at the live one; `restore` moves an order back.
+// Both sit behind the same lock, so an order is always in exactly one place.
+//
+// A few inherent methods (len, contains, snapshot...) let tests look inside.
+// They are NOT part of the OrderRepository port: a real database has no
+// cheap "how many rows in total" or "put everything back as it was", and the
+// application must not come to rely on them.
+//
+// Log lines go to stdout, or to a `Logger` when one is plugged in. Through
+// the `*_with_ctx` methods, each line carries the request's correlation ID.
 
-use domain::{Order, OrderError, OrderId, OrderRepository};
-use std::collections::HashMap;
+use crate::InMemoryStore;
+use domain::{
+    HealthCheck, HealthStatus, Logger, Order, OrderError, OrderId, OrderRepository, RequestContext,
+    TenantId,
+};
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 /// In-memory order repository for testing scenarios.
 ///
 /// Uses a HashMap as its "database". Data is lost when the process exits.
 /// Perfect for tests, development, and demos.
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct InMemoryOrderRepository {
-    orders: HashMap<OrderId, Order>,
+    orders: RwLock<Store>,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+    // `save` overwrites a different order instead of refusing it
+    lenient_saves: bool,
+}
+
+type Orders = InMemoryStore<(TenantId, OrderId), Order>;
+
+#[derive(Debug, Clone, Default)]
+struct Store {
+    live: Orders,
+    // Soft-deleted orders, out of sight until restored
+    tombstones: Orders,
 }
 
+impl Store {
+    /// True if the ID is taken, by a live order or a soft-deleted one.
+    fn taken(&self, key: (TenantId, OrderId)) -> bool {
+        self.live.contains(key) || self.tombstones.contains(key)
+    }
+}
+
+/// Everything an [`InMemoryOrderRepository`] held at one point, to put back later.
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySnapshot(Store);
+
 impl InMemoryOrderRepository {
     /// Creates a new empty in-memory repository.
     ///
@@ -34,50 +83,442 @@ impl InMemoryOrderRepository {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sends the log lines to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Lets `save` overwrite an order with a different one, as it used to,
+    /// instead of refusing it with [`OrderError::DuplicateOrder`].
+    #[must_use]
+    pub const fn with_lenient_saves(mut self) -> Self {
+        self.lenient_saves = true;
+        self
+    }
+
+    /// Number of orders stored, all tenants together, soft-deleted ones left out.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.read().live.len()
+    }
+
+    /// True when no order is stored (or only soft-deleted ones), for any tenant.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every order of every tenant, soft-deleted ones included.
+    pub fn clear(&self) {
+        *self.write() = Store::default();
+    }
+
+    /// True if an order with this ID is stored and not soft-deleted, for any tenant.
+    #[must_use]
+    pub fn contains(&self, id: OrderId) -> bool {
+        self.read().live.values().any(|order| order.id == id)
+    }
+
+    /// Every order stored, all tenants together, sorted by ID (then tenant).
+    /// Soft-deleted orders are left out.
+    #[must_use]
+    pub fn all_orders(&self) -> Vec<Order> {
+        let mut orders = self.read().live.all();
+        orders.sort_by_key(|order| (order.id, order.tenant));
+        orders
+    }
+
+    /// Captures everything stored right now, soft-deleted orders included.
+    #[must_use]
+    pub fn snapshot(&self) -> InMemorySnapshot {
+        InMemorySnapshot(self.read().clone())
+    }
+
+    /// Puts back what `snapshot` captured, dropping whatever came since.
+    ///
+    /// Not to be confused with [`OrderRepository::restore`], which brings
+    /// back one soft-deleted order.
+    pub fn restore_snapshot(&self, snapshot: InMemorySnapshot) {
+        *self.write() = snapshot.0;
+    }
+
+    fn log(&self, ctx: &RequestContext, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(ctx, message),
+            None => println!("  {message}"),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Store> {
+        // A poisoned lock only means another thread panicked; the map is still usable
+        self.orders.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Store> {
+        self.orders.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Manual impl: the logger is a trait object with no Debug of its own.
+impl fmt::Debug for InMemoryOrderRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemoryOrderRepository")
+            .field("orders", &self.orders)
+            .field("logger", &self.logger.is_some())
+            .field("lenient_saves", &self.lenient_saves)
+            .finish()
+    }
 }
 
 impl OrderRepository for InMemoryOrderRepository {
-    /// Saves an order to the HashMap.
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.insert_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.update_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.find_with_ctx(&RequestContext::default(), tenant, id)
+    }
+
+    /// Lists every order of a tenant.
+    ///
+    /// In PostgreSQL: `SELECT * FROM orders WHERE tenant_id = $1 ORDER BY id`
+    /// Here: `HashMap.values()`, filtered, and sorted because HashMap has no order
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        let ctx = RequestContext::default();
+        self.log(&ctx, &format!("[InMemory] Listing all orders of {tenant}"));
+        let mut orders: Vec<Order> = self
+            .read()
+            .live
+            .values()
+            .filter(|order| order.tenant == tenant)
+            .cloned()
+            .collect();
+        orders.sort_by_key(|order| order.id);
+        Ok(orders)
+    }
+
+    /// Like `find`, with the order lent where it sits, under the read lock.
+    fn find_with(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+        read: &mut dyn FnMut(Option<&Order>),
+    ) -> Result<(), OrderError> {
+        let ctx = RequestContext::default();
+        self.log(&ctx, &format!("[InMemory] Finding order #{id} of {tenant}"));
+        read(self.read().live.get((tenant, id)));
+        Ok(())
+    }
+
+    /// Like `find_all`, but sorting references instead of clones: the
+    /// orders are visited where they sit, under the read lock.
+    fn for_each(
+        &self,
+        tenant: TenantId,
+        visit: &mut dyn FnMut(&Order) -> ControlFlow<()>,
+    ) -> Result<(), OrderError> {
+        let ctx = RequestContext::default();
+        self.log(&ctx, &format!("[InMemory] Visiting the orders of {tenant}"));
+        let store = self.read();
+        let mut orders: Vec<&Order> = store
+            .live
+            .values()
+            .filter(|order| order.tenant == tenant)
+            .collect();
+        orders.sort_by_key(|order| order.id);
+        for order in orders {
+            if visit(order).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// One pass over the map, keeping the orders asked for, then put in
+    /// the order they were asked for.
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        let ctx = RequestContext::default();
+        self.log(
+            &ctx,
+            &format!("[InMemory] Finding {} orders of {tenant}", ids.len()),
+        );
+        let mut orders: Vec<(usize, Order)> = self
+            .read()
+            .live
+            .values()
+            .filter(|order| order.tenant == tenant)
+            .filter_map(|order| {
+                let first = ids.iter().position(|&id| id == order.id)?;
+                Some((first, order.clone()))
+            })
+            .collect();
+        orders.sort_by_key(|&(first, _)| first);
+        Ok(orders.into_iter().map(|(_, order)| order).collect())
+    }
+
+    /// Like `find_all`, but only cloning the orders that match.
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        let ctx = RequestContext::default();
+        self.log(
+            &ctx,
+            &format!("[InMemory] Searching the orders of {tenant} for {query:?}"),
+        );
+        let mut orders: Vec<Order> = self
+            .read()
+            .live
+            .values()
+            .filter(|order| order.tenant == tenant && order.has_item_named_like(query))
+            .cloned()
+            .collect();
+        orders.sort_by_key(|order| order.id);
+        Ok(orders)
+    }
+
+    /// Inserts a new order into the HashMap.
     ///
-    /// In PostgreSQL: `INSERT INTO orders (...) VALUES (...)`
-    /// Here: `HashMap.insert()`
+    /// In PostgreSQL: `INSERT INTO orders (...) VALUES (...)` with a primary key
+    /// Here: `HashMap.insert()`, refusing to overwrite an existing key
+    /// (a soft-deleted order still holds its ID)
     ///
     /// The application layer doesn't know the difference!
-    fn save(&mut self, order: &Order) -> Result<(), OrderError> {
-        println!("  [InMemory] Saving order #{}", order.id);
-        self.orders.insert(order.id, order.clone());
+    fn insert_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.log(ctx, &format!("[InMemory] Inserting order #{}", order.id));
+        let mut store = self.write();
+        let key = (order.tenant, order.id);
+        if store.taken(key) {
+            return Err(OrderError::DuplicateOrder(order.id));
+        }
+        store.live.insert(key, order.clone());
+        Ok(())
+    }
+
+    /// Inserts or overwrites an order, under one write lock.
+    ///
+    /// Saving the same order again changes nothing; overwriting a different
+    /// one is refused, unless the repository is lenient.
+    fn save(&self, order: &Order) -> Result<(), OrderError> {
+        self.log(
+            &RequestContext::default(),
+            &format!("[InMemory] Saving order #{}", order.id),
+        );
+        let mut guard = self.write();
+        let store = &mut *guard;
+        let key = (order.tenant, order.id);
+        match store.live.get_mut(key) {
+            Some(stored) if self.lenient_saves || stored.same_contents(order) => {
+                *stored = order.clone();
+            }
+            Some(_) => return Err(OrderError::DuplicateOrder(order.id)),
+            // Soft-deleted orders keep their ID, as for `insert`
+            None if store.tombstones.contains(key) => {
+                return Err(OrderError::DuplicateOrder(order.id));
+            }
+            None => {
+                store.live.insert(key, order.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts a batch of orders under one write lock.
+    ///
+    /// In PostgreSQL: one multi-row `INSERT` in a transaction
+    /// Here: every ID is checked before the first insert, so a duplicate
+    /// leaves the HashMap exactly as it was, and no reader sees half a batch
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        self.log(
+            &RequestContext::default(),
+            &format!("[InMemory] Inserting {} orders", orders.len()),
+        );
+        let mut store = self.write();
+        let mut batch = HashSet::new();
+        for order in orders {
+            let key = (order.tenant, order.id);
+            if store.taken(key) || !batch.insert(key) {
+                return Err(OrderError::DuplicateOrder(order.id));
+            }
+        }
+        for order in orders {
+            store.live.insert((order.tenant, order.id), order.clone());
+        }
         Ok(())
     }
 
-    /// Finds an order by ID.
+    /// Replaces an existing order in the HashMap.
     ///
-    /// In PostgreSQL: `SELECT * FROM orders WHERE id = $1`
+    /// In PostgreSQL: `UPDATE orders SET ... WHERE tenant_id = $1 AND id = $2`
+    /// Here: `HashMap.get_mut()`
+    fn update_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.log(ctx, &format!("[InMemory] Updating order #{}", order.id));
+        let mut store = self.write();
+        let stored = store
+            .live
+            .get_mut((order.tenant, order.id))
+            .ok_or(OrderError::OrderNotFound(order.id))?;
+        *stored = order.clone();
+        Ok(())
+    }
+
+    /// Replaces an order, if it's still at the version the caller loaded.
+    ///
+    /// In PostgreSQL: `UPDATE orders SET ... WHERE tenant_id = $1 AND id = $2 AND version = $3`
+    /// Here: the check and the write happen under the same write lock
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.log(
+            &RequestContext::default(),
+            &format!(
+                "[InMemory] Updating order #{} at version {expected}",
+                order.id
+            ),
+        );
+        let mut store = self.write();
+        let stored = store
+            .live
+            .get_mut((order.tenant, order.id))
+            .ok_or(OrderError::OrderNotFound(order.id))?;
+        if stored.version != expected {
+            return Err(OrderError::Conflict {
+                id: order.id,
+                expected,
+                found: stored.version,
+            });
+        }
+        *stored = order.clone();
+        Ok(())
+    }
+
+    /// Removes an order from the HashMap, soft-deleted or not.
+    ///
+    /// In PostgreSQL: `DELETE FROM orders WHERE tenant_id = $1 AND id = $2`
+    /// Here: `HashMap.remove()`
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.log(
+            &RequestContext::default(),
+            &format!("[InMemory] Deleting order #{id} of {tenant}"),
+        );
+        let mut store = self.write();
+        let live = store.live.remove((tenant, id));
+        let tombstone = store.tombstones.remove((tenant, id));
+        Ok(live.or(tombstone).is_some())
+    }
+
+    /// Moves an order to the tombstones.
+    ///
+    /// In PostgreSQL: `UPDATE orders SET deleted_at = now() WHERE ... AND deleted_at IS NULL`
+    /// Here: from one HashMap to the other
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.log(
+            &RequestContext::default(),
+            &format!("[InMemory] Soft-deleting order #{id} of {tenant}"),
+        );
+        let mut store = self.write();
+        let Some(order) = store.live.remove((tenant, id)) else {
+            return Ok(false);
+        };
+        store.tombstones.insert((tenant, id), order);
+        Ok(true)
+    }
+
+    /// Moves an order back from the tombstones.
+    ///
+    /// In PostgreSQL: `UPDATE orders SET deleted_at = NULL WHERE ... AND deleted_at IS NOT NULL`
+    fn restore(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.log(
+            &RequestContext::default(),
+            &format!("[InMemory] Restoring order #{id} of {tenant}"),
+        );
+        let mut store = self.write();
+        let Some(order) = store.tombstones.remove((tenant, id)) else {
+            return Ok(false);
+        };
+        store.live.insert((tenant, id), order);
+        Ok(true)
+    }
+
+    /// Finds an order among the live ones, then among the tombstones.
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.log(
+            &RequestContext::default(),
+            &format!("[InMemory] Finding order #{id} of {tenant}, deleted or not"),
+        );
+        let store = self.read();
+        Ok(store
+            .live
+            .get((tenant, id))
+            .or_else(|| store.tombstones.get((tenant, id)))
+            .cloned())
+    }
+
+    /// Finds an order of a tenant by ID.
+    ///
+    /// In PostgreSQL: `SELECT * FROM orders WHERE tenant_id = $1 AND id = $2`
     /// Here: `HashMap.get()`
-    fn find(&self, id: OrderId) -> Result<Option<Order>, OrderError> {
-        println!("  [InMemory] Finding order #{id}");
-        Ok(self.orders.get(&id).cloned())
+    fn find_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.log(ctx, &format!("[InMemory] Finding order #{id} of {tenant}"));
+        Ok(self.read().live.get((tenant, id)).cloned())
+    }
+}
+
+impl HealthCheck for InMemoryOrderRepository {
+    // A HashMap in our own process: nothing to reach, nothing to go down
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::WriterLogger;
     use domain::{LineItem, Money};
 
-    #[test]
-    fn save_and_find_order() {
-        let mut repo = InMemoryOrderRepository::new();
-        let order = Order::new(
-            OrderId(1),
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
             vec![LineItem {
                 name: "Test".to_string(),
                 price: Money(100),
+                weight_grams: None,
             }],
         )
-        .unwrap();
+        .unwrap()
+    }
+
+    fn named(id: u32, names: &[&str]) -> Order {
+        let items = names
+            .iter()
+            .map(|name| LineItem {
+                name: (*name).to_string(),
+                price: Money(100),
+                weight_grams: None,
+            })
+            .collect();
+        Order::new(OrderId(id), items).unwrap()
+    }
+
+    #[test]
+    fn save_and_find_order() {
+        let repo = InMemoryOrderRepository::new();
+        let order = test_order(1);
 
-        repo.save(&order).unwrap();
-        let found = repo.find(OrderId(1)).unwrap();
+        repo.insert(&order).unwrap();
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap();
 
         assert!(found.is_some());
         assert_eq!(found.unwrap().id, OrderId(1));
@@ -86,8 +527,427 @@ mod tests {
     #[test]
     fn find_nonexistent_returns_none() {
         let repo = InMemoryOrderRepository::new();
-        let found = repo.find(OrderId(999)).unwrap();
+        let found = repo.find(TenantId::default(), OrderId(999)).unwrap();
 
         assert!(found.is_none());
     }
+
+    #[test]
+    fn insert_existing_id_fails() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+
+        let result = repo.insert(&test_order(1));
+
+        assert!(matches!(
+            result,
+            Err(OrderError::DuplicateOrder(OrderId(1)))
+        ));
+    }
+
+    #[test]
+    fn find_all_is_sorted_by_id() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(2)).unwrap();
+        repo.insert(&test_order(1)).unwrap();
+
+        let ids: Vec<OrderId> = repo
+            .find_all(TenantId::default())
+            .unwrap()
+            .iter()
+            .map(|o| o.id)
+            .collect();
+
+        assert_eq!(ids, vec![OrderId(1), OrderId(2)]);
+    }
+
+    #[test]
+    fn update_missing_id_fails() {
+        let repo = InMemoryOrderRepository::new();
+
+        let result = repo.update(&test_order(1));
+
+        assert!(matches!(result, Err(OrderError::OrderNotFound(OrderId(1)))));
+    }
+
+    #[test]
+    fn update_replaces_stored_order() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        let mut changed = test_order(1);
+        changed.total = Money(42);
+
+        repo.update(&changed).unwrap();
+
+        assert_eq!(
+            repo.find(TenantId::default(), OrderId(1))
+                .unwrap()
+                .unwrap()
+                .total,
+            Money(42)
+        );
+    }
+
+    #[test]
+    fn update_versioned_rejects_a_stale_version() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        let mut first = test_order(1);
+        first.version = 1;
+        repo.update_versioned(&first, 0).unwrap();
+
+        // A second writer that also loaded version 0 comes too late
+        let result = repo.update_versioned(&first, 0);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::Conflict {
+                id: OrderId(1),
+                expected: 0,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn saving_a_stale_copy_is_a_conflict() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        let load = || repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+        let mut first = load();
+        let mut second = load();
+
+        first.total = Money(42);
+        first.version += 1;
+        repo.update_versioned(&first, first.version - 1).unwrap();
+        second.version += 1;
+        let result = repo.update_versioned(&second, second.version - 1);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::Conflict {
+                expected: 0,
+                found: 1,
+                ..
+            })
+        ));
+        assert_eq!(load(), first);
+    }
+
+    #[test]
+    fn inspection_helpers_see_every_tenant() {
+        let repo = InMemoryOrderRepository::new();
+        assert!(repo.is_empty());
+        let mut order = test_order(2);
+        order.tenant = TenantId(1);
+        repo.insert(&order).unwrap();
+        repo.insert(&test_order(1)).unwrap();
+
+        assert_eq!(repo.len(), 2);
+        assert!(repo.contains(OrderId(2)));
+        assert!(!repo.contains(OrderId(3)));
+        let ids: Vec<OrderId> = repo.all_orders().iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![OrderId(1), OrderId(2)]);
+
+        repo.clear();
+        assert!(repo.is_empty());
+    }
+
+    #[test]
+    fn restore_snapshot_undoes_everything_since_the_snapshot() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        let snapshot = repo.snapshot();
+
+        repo.insert(&test_order(2)).unwrap();
+        repo.delete(TenantId::default(), OrderId(1)).unwrap();
+        repo.restore_snapshot(snapshot);
+
+        let ids: Vec<OrderId> = repo.all_orders().iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![OrderId(1)]);
+    }
+
+    #[test]
+    fn soft_delete_hides_the_order_and_restore_brings_it_back() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        let tenant = TenantId::default();
+
+        assert!(repo.soft_delete(tenant, OrderId(1)).unwrap());
+        assert!(repo.find(tenant, OrderId(1)).unwrap().is_none());
+        assert!(repo.find_all(tenant).unwrap().is_empty());
+        assert!(
+            repo.find_including_deleted(tenant, OrderId(1))
+                .unwrap()
+                .is_some()
+        );
+        // The ID is still taken
+        assert!(matches!(
+            repo.insert(&test_order(1)),
+            Err(OrderError::DuplicateOrder(OrderId(1)))
+        ));
+
+        assert!(repo.restore(tenant, OrderId(1)).unwrap());
+        assert!(repo.find(tenant, OrderId(1)).unwrap().is_some());
+    }
+
+    #[test]
+    fn find_by_item_name_ignores_case_and_deleted_orders() {
+        let repo = InMemoryOrderRepository::new();
+        let tenant = TenantId::default();
+        repo.insert(&named(2, &["Coffee Mug"])).unwrap();
+        repo.insert(&named(1, &["Pen", "MUGS (set of 4)"])).unwrap();
+        repo.insert(&named(3, &["Mug"])).unwrap();
+        repo.soft_delete(tenant, OrderId(3)).unwrap();
+        let ids = |query| -> Vec<OrderId> {
+            repo.find_by_item_name(tenant, query)
+                .unwrap()
+                .iter()
+                .map(|o| o.id)
+                .collect()
+        };
+
+        assert_eq!(ids("mug"), [OrderId(1), OrderId(2)]);
+        assert_eq!(ids("COFFEE"), [OrderId(2)]);
+        assert!(ids("kettle").is_empty());
+        assert!(
+            repo.find_by_item_name(TenantId(1), "mug")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn save_refuses_to_overwrite_a_different_order() {
+        let repo = InMemoryOrderRepository::new();
+        let tenant = TenantId::default();
+        let mut other = test_order(1);
+        other.items[0].name = "Something else".to_string();
+
+        repo.save(&test_order(1)).unwrap();
+        repo.save(&test_order(1)).unwrap();
+        let conflicting = repo.save(&other);
+
+        assert!(matches!(
+            conflicting,
+            Err(OrderError::DuplicateOrder(OrderId(1)))
+        ));
+        assert_eq!(repo.find(tenant, OrderId(1)).unwrap(), Some(test_order(1)));
+        repo.soft_delete(tenant, OrderId(1)).unwrap();
+        assert!(matches!(
+            repo.save(&test_order(1)),
+            Err(OrderError::DuplicateOrder(_))
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn lenient_saves_overwrite() {
+        let repo = InMemoryOrderRepository::new().with_lenient_saves();
+        let mut other = test_order(1);
+        other.items[0].name = "Something else".to_string();
+
+        repo.save(&test_order(1)).unwrap();
+        repo.save(&other).unwrap();
+
+        assert_eq!(
+            repo.find(TenantId::default(), OrderId(1)).unwrap(),
+            Some(other)
+        );
+    }
+
+    #[test]
+    fn find_with_lends_the_stored_order() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        let mut lent = None;
+        let mut missing = false;
+
+        repo.find_with(TenantId::default(), OrderId(1), &mut |order| {
+            lent = order.map(std::ptr::from_ref);
+        })
+        .unwrap();
+        repo.find_with(TenantId::default(), OrderId(2), &mut |order| {
+            missing = order.is_none();
+        })
+        .unwrap();
+
+        let store = repo.read();
+        let stored = store.live.get((TenantId::default(), OrderId(1))).unwrap();
+        assert_eq!(lent, Some(std::ptr::from_ref(stored)));
+        assert!(missing);
+    }
+
+    #[test]
+    fn for_each_visits_in_id_order_until_told_to_stop() {
+        let repo = InMemoryOrderRepository::new();
+        for id in [3, 1, 4, 2] {
+            repo.insert(&test_order(id)).unwrap();
+        }
+        let mut seen = Vec::new();
+
+        repo.for_each(TenantId::default(), &mut |order| {
+            seen.push(order.id);
+            if order.id == OrderId(3) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(seen, [OrderId(1), OrderId(2), OrderId(3)]);
+        assert_eq!(
+            repo.count_where(TenantId::default(), &|order| order.id.0 % 2 == 0)
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn for_each_visits_the_stored_orders_not_clones() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        repo.insert(&test_order(2)).unwrap();
+        let mut visited = Vec::new();
+
+        repo.for_each(TenantId::default(), &mut |order| {
+            visited.push(std::ptr::from_ref(order));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        // A clone would live at another address than the order in the map
+        let store = repo.read();
+        let mut stored: Vec<*const Order> = store.live.values().map(std::ptr::from_ref).collect();
+        visited.sort_unstable();
+        stored.sort_unstable();
+        assert_eq!(visited, stored);
+    }
+
+    #[test]
+    fn find_many_keeps_the_input_order_and_skips_what_is_missing() {
+        let repo = InMemoryOrderRepository::new();
+        let tenant = TenantId::default();
+        for id in 1..=4 {
+            repo.insert(&test_order(id)).unwrap();
+        }
+        repo.soft_delete(tenant, OrderId(4)).unwrap();
+        let ids = |wanted: &[OrderId]| -> Vec<OrderId> {
+            repo.find_many(tenant, wanted)
+                .unwrap()
+                .iter()
+                .map(|o| o.id)
+                .collect()
+        };
+
+        assert_eq!(
+            ids(&[OrderId(3), OrderId(9), OrderId(1), OrderId(4)]),
+            [OrderId(3), OrderId(1)]
+        );
+        assert_eq!(
+            ids(&[OrderId(2), OrderId(1), OrderId(2)]),
+            [OrderId(2), OrderId(1)]
+        );
+        assert!(ids(&[]).is_empty());
+        assert!(
+            repo.find_many(TenantId(1), &[OrderId(1)])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn soft_deleting_an_unknown_order_finds_nothing() {
+        let repo = InMemoryOrderRepository::new();
+        let tenant = TenantId::default();
+
+        assert!(!repo.soft_delete(tenant, OrderId(9)).unwrap());
+        assert!(!repo.restore(tenant, OrderId(9)).unwrap());
+    }
+
+    #[test]
+    fn len_leaves_soft_deleted_orders_out() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        repo.insert(&test_order(2)).unwrap();
+
+        repo.soft_delete(TenantId::default(), OrderId(1)).unwrap();
+
+        assert_eq!(repo.len(), 1);
+        assert!(!repo.contains(OrderId(1)));
+        // A hard delete reaches tombstones too
+        assert!(repo.delete(TenantId::default(), OrderId(1)).unwrap());
+        assert!(
+            repo.find_including_deleted(TenantId::default(), OrderId(1))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn save_all_of_nothing_stores_nothing() {
+        let repo = InMemoryOrderRepository::new();
+
+        repo.save_all(&[]).unwrap();
+
+        assert!(repo.is_empty());
+    }
+
+    #[test]
+    fn save_all_stores_a_large_batch() {
+        let repo = InMemoryOrderRepository::new();
+        let orders: Vec<Order> = (1..=1000).map(test_order).collect();
+
+        repo.save_all(&orders).unwrap();
+
+        assert_eq!(repo.all_orders(), orders);
+    }
+
+    #[test]
+    fn save_all_with_a_duplicate_stores_nothing() {
+        let repo = InMemoryOrderRepository::new();
+        repo.insert(&test_order(3)).unwrap();
+
+        // Within the batch, then against what's stored
+        let twice = repo.save_all(&[test_order(1), test_order(2), test_order(1)]);
+        let taken = repo.save_all(&[test_order(1), test_order(3)]);
+
+        assert!(matches!(twice, Err(OrderError::DuplicateOrder(OrderId(1)))));
+        assert!(matches!(taken, Err(OrderError::DuplicateOrder(OrderId(3)))));
+        assert_eq!(repo.len(), 1);
+    }
+
+    #[test]
+    fn tenants_are_partitioned() {
+        let repo = InMemoryOrderRepository::new();
+        let mut order = test_order(1);
+        order.tenant = TenantId(1);
+        repo.insert(&order).unwrap();
+
+        // Same ID, other tenant: no clash
+        order.tenant = TenantId(2);
+        repo.insert(&order).unwrap();
+
+        assert!(repo.find(TenantId(3), OrderId(1)).unwrap().is_none());
+        assert!(repo.find_all(TenantId(3)).unwrap().is_empty());
+        assert_eq!(repo.find_all(TenantId(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn log_lines_go_to_the_logger() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let repo = InMemoryOrderRepository::new().with_logger(logger.clone());
+
+        repo.insert_with_ctx(&RequestContext::new("req-1"), &test_order(1))
+            .unwrap();
+        repo.find(TenantId::default(), OrderId(1)).unwrap();
+
+        assert_eq!(
+            logger.text(),
+            "  [req-1] [InMemory] Inserting order #OrderId(1)\n  \
+             [InMemory] Finding order #OrderId(1) of TenantId(0)\n"
+        );
+    }
 }
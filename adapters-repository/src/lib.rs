@@ -3,9 +3,37 @@
 // =============================================================================
 //
 // This crate provides concrete implementations of the `OrderRepository` port.
-// We have two adapters:
+// We have five adapters:
 // - InMemoryOrderRepository: HashMap-based, perfect for testing
 // - PostgresOrderRepository: Simulates a real database
+// - SqliteOrderRepository: A real embedded database (`sqlite` feature)
+// - SqlxOrderRepository: A real PostgreSQL server, through sqlx (`postgres` feature)
+// - EventSourcedOrderRepository: Stores each order's events, replays them on read
+//
+// It also hosts in-memory adapters for the other storage ports:
+// - InMemoryCustomerRepository: the `CustomerRepository` port
+// - InMemoryIdempotencyStore: the `IdempotencyStore` port
+// - InMemoryOutbox: the `NotificationOutbox` port
+// - InMemoryAuditLog and FileAuditLog: the `AuditLog` port
+// - InMemoryPriceList: the `PriceList` port (the catalog)
+// - StaticExchangeRates: the `ExchangeRates` port (currency conversion)
+// - InMemoryLoyaltyLedger: the `LoyaltyLedger` port (customers' points)
+// - InMemorySubscriptionRepository: the `SubscriptionRepository` port
+//
+// And a few helpers:
+// - InMemoryStore: the HashMap behind every in-memory repository
+// - ExpiringInMemoryOrderRepository: forgets orders after a TTL (draft orders)
+// - SequentialIdGenerator: the `IdGenerator` port (IDs usually come from the database)
+// - SystemClock and FixedClock: the `Clock` port
+// - StdoutLogger, WriterLogger, CapturingLogger, NullLogger and TracingLogger
+//   (`tracing` feature): the `Logger` port
+// - SharedOrderRepository: lets several owners use one repository
+// - FaultyOrderRepository: makes any repository fail on cue, for tests
+// - CachedOrderRepository: serves `find` from an LRU cache, over any repository
+// - SlowOrderRepository: adds latency to any repository, for timeout testing
+// - ReplicatedOrderRepository: writes to a primary, reads from replicas
+// - generate_items, seed_items, seed_orders and seed: made-up items and
+//   orders, for benchmarks, demos and the app's `seed` command
 //
 // THE REPOSITORY PATTERN:
 // -----------------------
@@ -26,8 +54,67 @@
 //
 // Our simulated version shows the pattern without the complexity.
 
+mod audit;
+mod cached;
+mod clock;
+mod csv;
+mod customers;
+mod event_sourced;
+mod exchange_rates;
+mod expiring;
+mod faulty;
+mod idempotency;
+mod ids;
 mod in_memory;
+mod logging;
+mod loyalty;
+mod outbox;
 mod postgres;
+mod price_list;
+mod replicated;
+mod seed;
+mod shared;
+mod slow;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "postgres")]
+mod sqlx_postgres;
+mod store;
+mod subscriptions;
 
-pub use in_memory::InMemoryOrderRepository;
-pub use postgres::PostgresOrderRepository;
+pub use audit::{FileAuditLog, InMemoryAuditLog};
+pub use cached::{CacheStats, CachedOrderRepository};
+pub use clock::{FixedClock, SystemClock};
+pub use csv::{
+    CSV_HEADER, CsvError, ImportReport, MAX_QUANTITY_PER_LINE, MalformedRow, OnMalformedRow,
+    export_csv, import_csv,
+};
+pub use customers::InMemoryCustomerRepository;
+pub use event_sourced::EventSourcedOrderRepository;
+pub use exchange_rates::StaticExchangeRates;
+pub use expiring::ExpiringInMemoryOrderRepository;
+pub use faulty::{FaultCounters, FaultyOrderRepository};
+pub use idempotency::InMemoryIdempotencyStore;
+pub use ids::SequentialIdGenerator;
+pub use in_memory::{InMemoryOrderRepository, InMemorySnapshot};
+#[cfg(feature = "tracing")]
+pub use logging::TracingLogger;
+pub use logging::{CapturingLogger, LogLine, NullLogger, StdoutLogger, WriterLogger};
+pub use loyalty::InMemoryLoyaltyLedger;
+pub use outbox::InMemoryOutbox;
+pub use postgres::{
+    HistoryRow, LineItemRow, OrderRow, PostgresOrderRepository, SimulatedQuery, StatementKind,
+};
+pub use price_list::InMemoryPriceList;
+pub use replicated::{ReadPreference, ReplicatedOrderRepository};
+pub use seed::{
+    MAX_PRICE, MIN_PRICE, generate_items, seed, seed_items, seed_orders, seed_recipient,
+};
+pub use shared::{SharedInMemoryOrderRepository, SharedOrderRepository};
+pub use slow::SlowOrderRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteOrderRepository;
+#[cfg(feature = "postgres")]
+pub use sqlx_postgres::SqlxOrderRepository;
+pub use store::InMemoryStore;
+pub use subscriptions::InMemorySubscriptionRepository;
@@ -0,0 +1,211 @@
+// =============================================================================
+// Loggers - Where Adapters Write Their Diagnostics
+// =============================================================================
+//
+// Five adapters for the `Logger` port:
+// - StdoutLogger: prints every line, prefixed with its correlation ID
+// - WriterLogger: the same lines, written to any `io::Write` (a file, a socket,
+//   a `Vec<u8>` a test reads back), or none of them when told to be quiet
+// - CapturingLogger: keeps every line, so tests can read them back
+// - NullLogger: drops every line, for benchmarks: printing a line costs more
+//   than the in-memory operation it describes
+// - TracingLogger (`tracing` feature): makes every line a `tracing` event
+//
+// Adapters that accept a logger take it as an `Arc`: the same logger is
+// usually shared by the repository, the payment gateway and the sender.
+//
+//     let logger = Arc::new(StdoutLogger);
+//     let repo = InMemoryOrderRepository::new().with_logger(logger.clone());
+//
+// In production, this is where `tracing` plugs in: with the application's
+// `tracing` feature on, each port call runs in a span naming the adapter and
+// the order, and TracingLogger's events land inside it.
+
+use domain::{Logger, RequestContext};
+use std::io::Write;
+use std::sync::{Mutex, PoisonError};
+
+/// Logger printing to stdout as `[correlation id] message`.
+///
+/// Lines logged outside of any request (empty ID) are printed as is.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdoutLogger;
+
+impl Logger for StdoutLogger {
+    fn log(&self, ctx: &RequestContext, message: &str) {
+        print!("{}", line(ctx, message));
+    }
+}
+
+/// `message` as StdoutLogger prints it, newline included.
+fn line(ctx: &RequestContext, message: &str) -> String {
+    if ctx.correlation_id.is_empty() {
+        format!("  {message}\n")
+    } else {
+        format!("  [{}] {message}\n", ctx.correlation_id)
+    }
+}
+
+/// Logger writing the lines StdoutLogger prints to `W` instead.
+///
+/// A line that can't be written is lost: logging never fails the operation
+/// it describes.
+#[derive(Debug, Default)]
+pub struct WriterLogger<W> {
+    writer: Mutex<W>,
+    quiet: bool,
+}
+
+impl<W: Write> WriterLogger<W> {
+    /// Creates a logger writing to `writer`.
+    #[must_use]
+    pub const fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            quiet: false,
+        }
+    }
+
+    /// Writes nothing at all when `quiet`, for a `--quiet` run.
+    ///
+    /// The adapters' lines have no levels of their own: a logger either
+    /// writes every one of them or none.
+    #[must_use]
+    pub const fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+}
+
+impl WriterLogger<Vec<u8>> {
+    /// Everything written so far, for a logger writing into memory.
+    #[must_use]
+    pub fn text(&self) -> String {
+        // A poisoned lock only means another thread panicked; the bytes are still fine
+        let bytes = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl<W: Write> Logger for WriterLogger<W> {
+    fn log(&self, ctx: &RequestContext, message: &str) {
+        if self.quiet {
+            return;
+        }
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writer.write_all(line(ctx, message).as_bytes());
+    }
+}
+
+/// Logger dropping every line.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullLogger;
+
+impl Logger for NullLogger {
+    fn log(&self, _ctx: &RequestContext, _message: &str) {}
+}
+
+/// Logger emitting every line as a `tracing` event, at INFO, with the
+/// correlation ID as a field.
+///
+/// The event goes to whatever subscriber the app installed, inside whatever
+/// span is current: with the application's `tracing` feature, the span of
+/// the port call that logged it.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TracingLogger;
+
+#[cfg(feature = "tracing")]
+impl Logger for TracingLogger {
+    fn log(&self, ctx: &RequestContext, message: &str) {
+        tracing::info!(correlation_id = %ctx.correlation_id, "{}", message.trim());
+    }
+}
+
+/// One line kept by a [`CapturingLogger`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub correlation_id: String,
+    pub message: String,
+}
+
+/// Logger keeping every line in memory, for tests.
+#[derive(Debug, Default)]
+pub struct CapturingLogger {
+    lines: Mutex<Vec<LogLine>>,
+}
+
+impl CapturingLogger {
+    /// Creates a logger with no lines yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every line logged so far, oldest first.
+    #[must_use]
+    pub fn lines(&self) -> Vec<LogLine> {
+        self.lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl Logger for CapturingLogger {
+    fn log(&self, ctx: &RequestContext, message: &str) {
+        self.lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(LogLine {
+                correlation_id: ctx.correlation_id.clone(),
+                message: message.to_string(),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capturing_logger_keeps_lines_with_their_id() {
+        let logger = CapturingLogger::new();
+
+        logger.log(&RequestContext::new("req-1"), "first");
+        logger.log(&RequestContext::new("req-2"), "second");
+
+        assert_eq!(
+            logger.lines(),
+            vec![
+                LogLine {
+                    correlation_id: "req-1".to_string(),
+                    message: "first".to_string(),
+                },
+                LogLine {
+                    correlation_id: "req-2".to_string(),
+                    message: "second".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn writer_logger_writes_what_stdout_logger_prints() {
+        let logger = WriterLogger::new(Vec::new());
+
+        logger.log(&RequestContext::new("req-1"), "first");
+        logger.log(&RequestContext::default(), "second");
+
+        assert_eq!(logger.text(), "  [req-1] first\n  second\n");
+    }
+
+    #[test]
+    fn a_quiet_writer_logger_writes_nothing() {
+        let logger = WriterLogger::new(Vec::new()).with_quiet(true);
+
+        logger.log(&RequestContext::new("req-1"), "first");
+
+        assert_eq!(logger.text(), "");
+    }
+}
@@ -0,0 +1,82 @@
+// =============================================================================
+// In-Memory Loyalty Ledger - Points per Customer
+// =============================================================================
+//
+// The `LoyaltyLedger` port in its simplest form: a HashMap from customer to
+// balance. A customer who never earned anything simply isn't in it, and has
+// a balance of 0.
+//
+// In a database, a debit is one statement that can't go negative:
+//
+//     UPDATE loyalty SET points = GREATEST(points - $2, 0) WHERE customer_id = $1
+
+use domain::{CustomerId, LoyaltyLedger, OrderError};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// In-memory loyalty balances for testing scenarios.
+#[derive(Debug, Default)]
+pub struct InMemoryLoyaltyLedger {
+    balances: Mutex<HashMap<CustomerId, u32>>,
+}
+
+impl InMemoryLoyaltyLedger {
+    /// Creates a ledger where nobody has any points.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn balances(&self) -> MutexGuard<'_, HashMap<CustomerId, u32>> {
+        // A poisoned lock only means another thread panicked; the balances are still fine
+        self.balances.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl LoyaltyLedger for InMemoryLoyaltyLedger {
+    fn credit(&self, customer: CustomerId, points: u32) -> Result<(), OrderError> {
+        println!("  [Loyalty] Crediting {points} point(s) to {customer}");
+        let mut balances = self.balances();
+        let balance = balances.entry(customer).or_default();
+        *balance = balance.saturating_add(points);
+        Ok(())
+    }
+
+    fn debit(&self, customer: CustomerId, points: u32) -> Result<(), OrderError> {
+        println!("  [Loyalty] Debiting {points} point(s) from {customer}");
+        if let Some(balance) = self.balances().get_mut(&customer) {
+            *balance = balance.saturating_sub(points);
+        }
+        Ok(())
+    }
+
+    fn balance(&self, customer: CustomerId) -> Result<u32, OrderError> {
+        Ok(self.balances().get(&customer).copied().unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credits_add_up_and_debits_stop_at_zero() {
+        let ledger = InMemoryLoyaltyLedger::new();
+
+        ledger.credit(CustomerId(1), 50).unwrap();
+        ledger.credit(CustomerId(1), 25).unwrap();
+        assert_eq!(ledger.balance(CustomerId(1)).unwrap(), 75);
+
+        ledger.debit(CustomerId(1), 100).unwrap();
+        assert_eq!(ledger.balance(CustomerId(1)).unwrap(), 0);
+    }
+
+    #[test]
+    fn unknown_customer_has_no_points() {
+        let ledger = InMemoryLoyaltyLedger::new();
+
+        ledger.debit(CustomerId(9), 10).unwrap();
+
+        assert_eq!(ledger.balance(CustomerId(9)).unwrap(), 0);
+    }
+}
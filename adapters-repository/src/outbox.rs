@@ -0,0 +1,107 @@
+// =============================================================================
+// In-Memory Outbox - Notifications Waiting Their Turn
+// =============================================================================
+//
+// The `NotificationOutbox` port stores notifications next to the orders, so
+// a crash between "order saved" and "email sent" can't lose the email.
+//
+// Here the "table" is a Vec, in insertion order. Entries stay until they're
+// marked as sent, so a send that failed is retried by the next flush.
+//
+// In PostgreSQL this would be:
+//
+//     INSERT INTO outbox (recipient, event) VALUES ($1, $2)      -- enqueue
+//     SELECT * FROM outbox ORDER BY id LIMIT $1                  -- dequeue_batch
+//     DELETE FROM outbox WHERE id = $1                           -- mark_sent
+//
+// with the INSERT in the same transaction as the order itself.
+
+use domain::{NotificationEvent, NotificationOutbox, OrderError, OutboxEntry, Recipient};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// In-memory notification outbox for testing scenarios.
+#[derive(Debug, Default)]
+pub struct InMemoryOutbox {
+    state: Mutex<OutboxState>,
+}
+
+#[derive(Debug, Default)]
+struct OutboxState {
+    next_id: u64,
+    pending: Vec<OutboxEntry>,
+}
+
+impl InMemoryOutbox {
+    /// Creates a new empty outbox.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of entries not sent yet.
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.state().pending.len()
+    }
+
+    fn state(&self) -> MutexGuard<'_, OutboxState> {
+        // A poisoned lock only means another thread panicked; the entries are still fine
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl NotificationOutbox for InMemoryOutbox {
+    fn enqueue(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        let mut state = self.state();
+        state.next_id += 1;
+        let id = state.next_id;
+        println!("  [InMemory] Enqueuing notification #{id} for {recipient}");
+        state.pending.push(OutboxEntry {
+            id,
+            recipient: recipient.clone(),
+            event: event.clone(),
+        });
+        Ok(())
+    }
+
+    fn dequeue_batch(&self, max: usize) -> Result<Vec<OutboxEntry>, OrderError> {
+        Ok(self.state().pending.iter().take(max).cloned().collect())
+    }
+
+    fn mark_sent(&self, id: u64) -> Result<(), OrderError> {
+        println!("  [InMemory] Notification #{id} sent");
+        self.state().pending.retain(|entry| entry.id != id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, OrderId};
+
+    fn recipient() -> Recipient {
+        Recipient::new("Test", Contact::Email("test@example.com".to_string())).unwrap()
+    }
+
+    #[test]
+    fn entries_stay_until_marked_sent() {
+        let outbox = InMemoryOutbox::new();
+        for id in 1..=3 {
+            let event = NotificationEvent::OrderShipped { id: OrderId(id) };
+            outbox.enqueue(&recipient(), &event).unwrap();
+        }
+
+        let batch = outbox.dequeue_batch(2).unwrap();
+        assert_eq!(batch.len(), 2);
+        outbox.mark_sent(batch[0].id).unwrap();
+
+        let ids: Vec<u64> = outbox
+            .dequeue_batch(10)
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![2, 3]);
+    }
+}
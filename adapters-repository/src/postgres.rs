@@ -4,16 +4,423 @@
 //
 // This adapter simulates a PostgreSQL database.
 // In production, we'd add sqlx or diesel to Cargo.toml and execute real queries.
+// That's SqlxOrderRepository (sqlx_postgres.rs, `postgres` feature); this one
+// stays, for the demos and the tests that have no server to talk to.
 //
 // ERROR TRANSLATION:
 // ------------------
 // A key responsibility of adapters is translating external errors to domain errors.
-// sqlx::Error -> OrderError::StorageFailed
+// sqlx::Error -> OrderError::StorageFailed, the sqlx::Error as its source
 //
 // The application layer never sees database-specific errors!
+//
+// ROW MAPPING:
+// ------------
+// An Order doesn't fit in one row: its items live in their own table.
+//
+//     orders        (tenant_id, id, total_cents, transaction_id, status,
+//                    refunded_cents, customer_id, gift_card_id, gift_card_cents,
+//                    version, merged_from, extra_charge_ids,
+//                    extra_charge_cents, loyalty_credited, shipping_cents,
+//                    ship_street, ship_city, ship_postal_code, ship_country,
+//                    deleted_at)
+//     line_items    (tenant_id, order_id, position, name, price_cents,
+//                    weight_grams)
+//     order_history (tenant_id, order_id, position, from_status, to_status,
+//                    changed_at_ns, reason)
+//
+// `OrderRow`, `LineItemRow` and `HistoryRow` are those rows, with the types
+// sqlx would hand us (BIGINT is i64, and Postgres has no unsigned integers).
+// Going from rows back to an Order is where a broken table shows up: a
+// negative amount, an unknown status, an order without items. None of those
+// can be a valid Order, so they're StorageFailed, never a made-up order.
+//
+// `changed_at_ns` is nanoseconds since the epoch, not a TIMESTAMPTZ: those
+// keep microseconds, and an order must come back exactly as it went in.
+//
+// The simulated tables below store rows, not Orders, so every insert and
+// every find goes through the same mapping a real database would.
+//
+// SOFT DELETE:
+// ------------
+// `soft_delete` doesn't remove the row, it stamps `deleted_at`. Every normal
+// query adds `AND deleted_at IS NULL`; `find_including_deleted` doesn't, and
+// `restore` clears the stamp. Finance keeps its history, customers don't see
+// the order. (A partial index `WHERE deleted_at IS NULL` keeps the normal
+// queries as fast as before.)
+//
+// MULTI-TENANCY:
+// --------------
+// Every row carries its tenant, and the primary key is (tenant_id, id).
+// Every query filters on tenant_id: forgetting it once is a data leak, so
+// the port doesn't even offer a query without it.
+//
+// The simulated queries go to stdout, or to a `Logger` when one is plugged in.
+//
+// QUERY LOG:
+// ----------
+// Each statement is also kept, as a `SimulatedQuery`: what kind of statement,
+// on which table, for which order. A test asserts the queries a use case
+// ran with `query_log()`, without reading stdout:
+//
+//     assert_eq!(repo.query_log().iter().filter(|q| q.kind == StatementKind::Insert).count(), 1);
+//
+// `clear_query_log()` starts over, between the setup and what is measured.
+
+use crate::StdoutLogger;
+use domain::{
+    Address, CustomerId, ExtraCharge, GiftCardId, GiftCardPayment, HealthCheck, HealthStatus,
+    HistoryEntry, LineItem, Logger, Money, Order, OrderError, OrderId, OrderRepository,
+    OrderStatus, RequestContext, Shipping, TenantId,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// One row of the `orders` table.
+///
+/// In production: `#[derive(sqlx::FromRow)]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderRow {
+    pub tenant_id: i64,
+    pub id: i64,
+    pub total_cents: i64,
+    pub transaction_id: Option<String>,
+    pub status: String,
+    pub refunded_cents: i64,
+    pub customer_id: Option<i64>,
+    pub gift_card_id: Option<i64>,
+    pub gift_card_cents: Option<i64>,
+    pub version: i64,
+    /// The IDs of the orders merged into this one: a `BIGINT[]`.
+    pub merged_from: Vec<i64>,
+    /// The transaction IDs of the extra charges, oldest first: a `TEXT[]`.
+    pub extra_charge_ids: Vec<String>,
+    /// What each of them charged, in the same order: a `BIGINT[]`.
+    pub extra_charge_cents: Vec<i64>,
+    pub loyalty_credited: i64,
+    /// What shipping costs, and where to: all NULL for an order that
+    /// doesn't ship.
+    pub shipping_cents: Option<i64>,
+    pub ship_street: Option<String>,
+    pub ship_city: Option<String>,
+    pub ship_postal_code: Option<String>,
+    pub ship_country: Option<String>,
+    /// When the order was soft-deleted. `None` for a live order.
+    pub deleted_at: Option<SystemTime>,
+}
+
+/// One row of the `line_items` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineItemRow {
+    pub tenant_id: i64,
+    pub order_id: i64,
+    /// Where the item sits in the order, from 0.
+    pub position: i64,
+    pub name: String,
+    pub price_cents: i64,
+    /// NULL when the item has no weight.
+    pub weight_grams: Option<i64>,
+}
+
+/// One row of the `order_history` table: one status change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryRow {
+    pub tenant_id: i64,
+    pub order_id: i64,
+    /// Where the change sits in the history, from 0.
+    pub position: i64,
+    /// NULL for the order being placed.
+    pub from_status: Option<String>,
+    pub to_status: String,
+    /// Nanoseconds since the epoch (negative before it).
+    pub changed_at_ns: i64,
+    pub reason: Option<String>,
+}
+
+impl OrderRow {
+    /// Splits an order into the rows that store it.
+    #[must_use]
+    pub fn from_order(order: &Order) -> (Self, Vec<LineItemRow>, Vec<HistoryRow>) {
+        let tenant_id = i64::from(order.tenant.0);
+        let id = i64::from(order.id.0);
+        let shipping = order.shipping.as_ref();
+        let destination = shipping.map(|shipping| &shipping.destination);
+        let row = Self {
+            tenant_id,
+            id,
+            total_cents: i64::from(order.total.0),
+            transaction_id: order.transaction_id.clone(),
+            status: order.status.to_string(),
+            refunded_cents: i64::from(order.refunded.0),
+            customer_id: order.customer_id.map(|customer| i64::from(customer.0)),
+            gift_card_id: order.gift_card.map(|gift| i64::from(gift.card.0)),
+            gift_card_cents: order.gift_card.map(|gift| i64::from(gift.amount.0)),
+            version: i64::from(order.version),
+            merged_from: order.merged_from.iter().map(|id| i64::from(id.0)).collect(),
+            extra_charge_ids: order
+                .extra_charges
+                .iter()
+                .map(|charge| charge.transaction_id.clone())
+                .collect(),
+            extra_charge_cents: order
+                .extra_charges
+                .iter()
+                .map(|charge| i64::from(charge.amount.0))
+                .collect(),
+            loyalty_credited: i64::from(order.loyalty_credited),
+            shipping_cents: shipping.map(|shipping| i64::from(shipping.cost.0)),
+            ship_street: destination.map(|to| to.street().to_string()),
+            ship_city: destination.map(|to| to.city().to_string()),
+            ship_postal_code: destination.map(|to| to.postal_code().to_string()),
+            ship_country: destination.map(|to| to.country().to_string()),
+            deleted_at: None,
+        };
+        let items = (0..)
+            .zip(&order.items)
+            .map(|(position, item)| LineItemRow {
+                tenant_id,
+                order_id: id,
+                position,
+                name: item.name.clone(),
+                price_cents: i64::from(item.price.0),
+                weight_grams: item.weight_grams.map(i64::from),
+            })
+            .collect();
+        let history = (0..)
+            .zip(&order.history)
+            .map(|(position, entry)| HistoryRow {
+                tenant_id,
+                order_id: id,
+                position,
+                from_status: entry.from.map(|status| status.to_string()),
+                to_status: entry.to.to_string(),
+                changed_at_ns: nanos_since_epoch(entry.at),
+                reason: entry.reason.clone(),
+            })
+            .collect();
+        (row, items, history)
+    }
+
+    /// Puts an order back together from its rows.
+    ///
+    /// The item and history rows may come in any order; they're sorted by
+    /// position. `deleted_at` isn't part of the order: whether to return a
+    /// soft-deleted one is the query's business.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the rows can't be a valid
+    /// order: no item rows, item or history rows of another order, a value
+    /// out of range, an unknown status, half a shipping destination, or a
+    /// total that isn't the sum of the items and the shipping.
+    pub fn into_order(
+        self,
+        mut items: Vec<LineItemRow>,
+        mut history: Vec<HistoryRow>,
+    ) -> Result<Order, OrderError> {
+        if items.is_empty() {
+            return Err(inconsistent(&format!(
+                "order #{} has no line items",
+                self.id
+            )));
+        }
+        if items
+            .iter()
+            .any(|item| item.tenant_id != self.tenant_id || item.order_id != self.id)
+        {
+            return Err(inconsistent(&format!(
+                "order #{} got another order's items",
+                self.id
+            )));
+        }
+        if history
+            .iter()
+            .any(|change| change.tenant_id != self.tenant_id || change.order_id != self.id)
+        {
+            return Err(inconsistent(&format!(
+                "order #{} got another order's history",
+                self.id
+            )));
+        }
+        items.sort_by_key(|item| item.position);
+        history.sort_by_key(|change| change.position);
+
+        let items = items
+            .into_iter()
+            .map(|item| {
+                Ok(LineItem {
+                    name: item.name,
+                    price: Money(column(item.price_cents)?),
+                    weight_grams: item.weight_grams.map(column).transpose()?,
+                })
+            })
+            .collect::<Result<Vec<_>, OrderError>>()?;
+        let shipping = match (
+            self.shipping_cents,
+            self.ship_street,
+            self.ship_city,
+            self.ship_postal_code,
+            self.ship_country,
+        ) {
+            (Some(cents), Some(street), Some(city), Some(postal_code), Some(country)) => {
+                Some(Shipping {
+                    destination: Address::new(street, city, postal_code, &country).map_err(
+                        |_| inconsistent(&format!("order #{} ships nowhere valid", self.id)),
+                    )?,
+                    cost: Money(column(cents)?),
+                })
+            }
+            (None, None, None, None, None) => None,
+            _ => {
+                return Err(inconsistent(&format!(
+                    "order #{} has half a shipping destination",
+                    self.id
+                )));
+            }
+        };
+        let total = Money(column(self.total_cents)?);
+        let shipping_cost = shipping.as_ref().map_or(Money(0), |shipping| shipping.cost);
+        if Order::total_of(&items)
+            .ok()
+            .and_then(|items| items.checked_add(shipping_cost))
+            != Some(total)
+        {
+            return Err(inconsistent(&format!(
+                "order #{} total doesn't match its items and shipping",
+                self.id
+            )));
+        }
+
+        let gift_card = match (self.gift_card_id, self.gift_card_cents) {
+            (Some(card), Some(cents)) => Some(GiftCardPayment {
+                card: GiftCardId(column(card)?),
+                amount: Money(column(cents)?),
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(inconsistent(&format!(
+                    "order #{} has half a gift card",
+                    self.id
+                )));
+            }
+        };
+        if self.extra_charge_ids.len() != self.extra_charge_cents.len() {
+            return Err(inconsistent(&format!(
+                "order #{} has half an extra charge",
+                self.id
+            )));
+        }
+
+        Ok(Order {
+            id: OrderId(column(self.id)?),
+            items,
+            total,
+            transaction_id: self.transaction_id,
+            status: parse_status(&self.status)?,
+            refunded: Money(column(self.refunded_cents)?),
+            customer_id: self.customer_id.map(column).transpose()?.map(CustomerId),
+            tenant: TenantId(column(self.tenant_id)?),
+            gift_card,
+            version: column(self.version)?,
+            history: history
+                .into_iter()
+                .map(|change| {
+                    Ok(HistoryEntry {
+                        from: change
+                            .from_status
+                            .as_deref()
+                            .map(parse_status)
+                            .transpose()?,
+                        to: parse_status(&change.to_status)?,
+                        at: time_from_nanos(change.changed_at_ns),
+                        reason: change.reason,
+                    })
+                })
+                .collect::<Result<_, OrderError>>()?,
+            merged_from: self
+                .merged_from
+                .into_iter()
+                .map(|id| column(id).map(OrderId))
+                .collect::<Result<_, OrderError>>()?,
+            extra_charges: self
+                .extra_charge_ids
+                .into_iter()
+                .zip(self.extra_charge_cents)
+                .map(|(transaction_id, cents)| {
+                    Ok(ExtraCharge {
+                        transaction_id,
+                        amount: Money(column(cents)?),
+                    })
+                })
+                .collect::<Result<_, OrderError>>()?,
+            loyalty_credited: column(self.loyalty_credited)?,
+            shipping,
+        })
+    }
+}
+
+/// A time as nanoseconds since the epoch: an `i64` goes from 1678 to 2262.
+pub(crate) fn nanos_since_epoch(at: SystemTime) -> i64 {
+    match at.duration_since(UNIX_EPOCH) {
+        Ok(after) => i64::try_from(after.as_nanos()).unwrap_or(i64::MAX),
+        Err(before) => i64::try_from(before.duration().as_nanos()).map_or(i64::MIN, |n| -n),
+    }
+}
+
+/// The time [`nanos_since_epoch`] gave `nanos` for.
+pub(crate) fn time_from_nanos(nanos: i64) -> SystemTime {
+    let distance = Duration::from_nanos(nanos.unsigned_abs());
+    if nanos < 0 {
+        UNIX_EPOCH - distance
+    } else {
+        UNIX_EPOCH + distance
+    }
+}
+
+/// Reads a BIGINT column back into one of our u32-based values.
+fn column(value: i64) -> Result<u32, OrderError> {
+    u32::try_from(value).map_err(|_| inconsistent(&format!("{value} is out of range")))
+}
+
+fn parse_status(status: &str) -> Result<OrderStatus, OrderError> {
+    match status {
+        "Pending" => Ok(OrderStatus::Pending),
+        "Paid" => Ok(OrderStatus::Paid),
+        "Shipped" => Ok(OrderStatus::Shipped),
+        "Cancelled" => Ok(OrderStatus::Cancelled),
+        "Refunded" => Ok(OrderStatus::Refunded),
+        other => Err(inconsistent(&format!("unknown status {other:?}"))),
+    }
+}
+
+/// What kind of statement a [`SimulatedQuery`] was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    Select,
+    /// An `INSERT`, upserts (`ON CONFLICT DO UPDATE`) included.
+    Insert,
+    Update,
+    Delete,
+}
 
-use domain::{Order, OrderError, OrderId, OrderRepository};
-use std::collections::HashMap;
+/// One statement the simulated database ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedQuery {
+    pub kind: StatementKind,
+    /// The table it ran on: the main one, for a statement reaching others.
+    pub table: &'static str,
+    /// The order ID bound to it, when it names exactly one order: `None`
+    /// for a list of IDs or a whole tenant.
+    pub order_id: Option<OrderId>,
+}
+
+/// Logs what's wrong with the stored data, then hands the application the domain error.
+fn inconsistent(what: &str) -> OrderError {
+    println!("  [Postgres] Inconsistent data: {what}");
+    OrderError::StorageFailed(None)
+}
 
 /// Simulated PostgreSQL order repository.
 ///
@@ -23,11 +430,86 @@ use std::collections::HashMap;
 ///     pool: sqlx::PgPool,
 /// }
 /// ```
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct PostgresOrderRepository {
     // In reality: pool: sqlx::PgPool
-    // For demo: just a HashMap
-    simulated_db: HashMap<OrderId, Order>,
+    // For demo: two "tables", locked because the port takes &self
+    // (a PgPool is shared the same way: &self, concurrency handled inside)
+    simulated_db: Mutex<Tables>,
+    // Every statement run, oldest first
+    query_log: Mutex<Vec<SimulatedQuery>>,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+    // `save` overwrites a different order instead of refusing it
+    lenient_saves: bool,
+}
+
+// Manual impl: the logger is a trait object with no Debug of its own.
+impl fmt::Debug for PostgresOrderRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostgresOrderRepository")
+            .field("simulated_db", &self.simulated_db)
+            .field("query_log", &self.query_log)
+            .field("logger", &self.logger.is_some())
+            .field("lenient_saves", &self.lenient_saves)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tables {
+    // Keyed like the table's primary key: (tenant_id, id)
+    orders: HashMap<(i64, i64), OrderRow>,
+    line_items: Vec<LineItemRow>,
+    history: Vec<HistoryRow>,
+}
+
+impl Tables {
+    /// `SELECT * FROM orders WHERE tenant_id = $1 AND id = $2 AND deleted_at IS NULL`
+    fn live(&self, key: (i64, i64)) -> Option<&OrderRow> {
+        self.orders.get(&key).filter(|row| row.deleted_at.is_none())
+    }
+
+    /// `SELECT * FROM line_items WHERE tenant_id = $1 AND order_id = $2`
+    fn items_of(&self, row: &OrderRow) -> Vec<LineItemRow> {
+        self.line_items
+            .iter()
+            .filter(|item| item.tenant_id == row.tenant_id && item.order_id == row.id)
+            .cloned()
+            .collect()
+    }
+
+    /// `SELECT * FROM order_history WHERE tenant_id = $1 AND order_id = $2`
+    fn history_of(&self, row: &OrderRow) -> Vec<HistoryRow> {
+        self.history
+            .iter()
+            .filter(|change| change.tenant_id == row.tenant_id && change.order_id == row.id)
+            .cloned()
+            .collect()
+    }
+
+    /// An order row, with its items and history, as an order.
+    fn order(&self, row: &OrderRow) -> Result<Order, OrderError> {
+        row.clone()
+            .into_order(self.items_of(row), self.history_of(row))
+    }
+
+    /// Replaces an order's rows, which must already exist.
+    fn replace(&mut self, order: &Order) {
+        let (row, items, history) = OrderRow::from_order(order);
+        let key = (row.tenant_id, row.id);
+        self.line_items
+            .retain(|item| (item.tenant_id, item.order_id) != key);
+        self.line_items.extend(items);
+        self.history
+            .retain(|change| (change.tenant_id, change.order_id) != key);
+        self.history.extend(history);
+        self.orders.insert(key, row);
+    }
+}
+
+/// The primary key of an order's row.
+fn key(tenant: TenantId, id: OrderId) -> (i64, i64) {
+    (i64::from(tenant.0), i64::from(id.0))
 }
 
 impl PostgresOrderRepository {
@@ -44,78 +526,986 @@ impl PostgresOrderRepository {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sends the simulated queries to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Lets `save` overwrite an order with a different one, as it used to,
+    /// instead of refusing it with [`OrderError::DuplicateOrder`].
+    #[must_use]
+    pub const fn with_lenient_saves(mut self) -> Self {
+        self.lenient_saves = true;
+        self
+    }
+
+    /// Every statement run so far, oldest first.
+    #[must_use]
+    pub fn query_log(&self) -> Vec<SimulatedQuery> {
+        self.queries().clone()
+    }
+
+    /// Forgets the statements run so far.
+    pub fn clear_query_log(&self) {
+        self.queries().clear();
+    }
+
+    /// Keeps a statement in the query log, and logs its SQL.
+    fn run(&self, kind: StatementKind, table: &'static str, order_id: Option<OrderId>, sql: &str) {
+        self.queries().push(SimulatedQuery {
+            kind,
+            table,
+            order_id,
+        });
+        let ctx = RequestContext::default();
+        match &self.logger {
+            Some(logger) => logger.log(&ctx, sql),
+            None => StdoutLogger.log(&ctx, sql),
+        }
+    }
+
+    fn queries(&self) -> MutexGuard<'_, Vec<SimulatedQuery>> {
+        // A poisoned lock only means another thread panicked; the log is still fine
+        self.query_log
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn db(&self) -> MutexGuard<'_, Tables> {
+        // A poisoned lock only means another thread panicked; the rows are still there
+        self.simulated_db
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 impl OrderRepository for PostgresOrderRepository {
-    /// Saves an order to PostgreSQL.
+    /// Inserts an order into PostgreSQL.
     ///
-    /// Real implementation:
+    /// Real implementation: one transaction, so nobody sees an order
+    /// without its items:
     /// ```ignore
-    /// async fn save(&mut self, order: &Order) -> Result<(), OrderError> {
+    /// async fn insert(&self, order: &Order) -> Result<(), OrderError> {
+    ///     let (row, items, history) = OrderRow::from_order(order);
+    ///     let mut tx = self.pool.begin().await.map_err(OrderError::storage_failed)?;
     ///     sqlx::query(
-    ///         "INSERT INTO orders (id, total) VALUES ($1, $2)"
+    ///         "INSERT INTO orders (tenant_id, id, total_cents, transaction_id, status, \
+    ///          refunded_cents, customer_id, gift_card_id, gift_card_cents, version) \
+    ///          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
     ///     )
-    ///     .bind(order.id.0)
-    ///     .bind(order.total.0)
-    ///     .execute(&self.pool)
+    ///     .bind(row.tenant_id)
+    ///     .bind(row.id)
+    ///     // ... one bind per column
+    ///     .execute(&mut *tx)
     ///     .await
-    ///     .map_err(|e| {
-    ///         // Log the actual error for debugging
-    ///         tracing::error!("Database error: {e}");
-    ///         // Return domain error to application
-    ///         OrderError::StorageFailed
+    ///     .map_err(|e| match e {
+    ///         // Primary key violation: the ID is already taken
+    ///         sqlx::Error::Database(db) if db.is_unique_violation() => {
+    ///             OrderError::DuplicateOrder(order.id)
+    ///         }
+    ///         // Return domain error to application, the real one attached
+    ///         e => OrderError::storage_failed(e),
     ///     })?;
-    ///     Ok(())
+    ///     for item in items {
+    ///         sqlx::query(
+    ///             "INSERT INTO line_items \
+    ///              (tenant_id, order_id, position, name, price_cents, weight_grams) \
+    ///              VALUES ($1, $2, $3, $4, $5, $6)"
+    ///         )
+    ///         // ... one bind per column
+    ///         .execute(&mut *tx)
+    ///         .await
+    ///         .map_err(OrderError::storage_failed)?;
+    ///     }
+    ///     // ... the same for the history rows, into order_history
+    ///     tx.commit().await.map_err(OrderError::storage_failed)
     /// }
     /// ```
-    fn save(&mut self, order: &Order) -> Result<(), OrderError> {
-        println!("  [Postgres] INSERT INTO orders VALUES ({}, ...)", order.id);
-        self.simulated_db.insert(order.id, order.clone());
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.run(
+            StatementKind::Insert,
+            "orders",
+            Some(order.id),
+            &format!(
+                "[Postgres] INSERT INTO orders VALUES ({}, {}, ...) + {} line_items",
+                order.tenant.0,
+                order.id,
+                order.items.len()
+            ),
+        );
+        let mut db = self.db();
+        if db.orders.contains_key(&key(order.tenant, order.id)) {
+            // Simulates: ERROR: duplicate key value violates unique constraint
+            return Err(OrderError::DuplicateOrder(order.id));
+        }
+        db.replace(order);
+        Ok(())
+    }
+
+    /// Inserts or overwrites an order in PostgreSQL.
+    ///
+    /// Real implementation: in a transaction, the stored order is read
+    /// `FOR UPDATE` (so nobody changes it in between) and compared, then
+    /// written like `insert` or `update`. Lenient, it's one upsert:
+    /// ```ignore
+    /// "INSERT INTO orders (...) VALUES (...)
+    ///  ON CONFLICT (tenant_id, id) DO UPDATE SET total_cents = EXCLUDED.total_cents, ..."
+    /// ```
+    fn save(&self, order: &Order) -> Result<(), OrderError> {
+        self.run(
+            StatementKind::Select,
+            "orders",
+            Some(order.id),
+            &format!(
+                "[Postgres] SELECT * FROM orders WHERE tenant_id = {} AND id = {} FOR UPDATE",
+                order.tenant.0, order.id
+            ),
+        );
+        let mut db = self.db();
+        if let Some(row) = db.orders.get(&key(order.tenant, order.id)) {
+            // Soft-deleted rows still hold the key, as for `insert`
+            let refused = row.deleted_at.is_some()
+                || !(self.lenient_saves || db.order(row)?.same_contents(order));
+            if refused {
+                return Err(OrderError::DuplicateOrder(order.id));
+            }
+        }
+        self.run(
+            StatementKind::Insert,
+            "orders",
+            Some(order.id),
+            &format!(
+                "[Postgres] INSERT INTO orders VALUES ({}, {}, ...) ON CONFLICT (tenant_id, id) \
+                 DO UPDATE SET ...",
+                order.tenant.0, order.id
+            ),
+        );
+        db.replace(order);
         Ok(())
     }
 
+    /// Inserts a batch of orders in PostgreSQL, in one round trip.
+    ///
+    /// Real implementation: one transaction, one multi-row `INSERT` per table
+    /// (sqlx's `QueryBuilder::push_values` builds the `VALUES (...), (...)`):
+    /// ```ignore
+    /// let mut tx = self.pool.begin().await.map_err(OrderError::storage_failed)?;
+    /// QueryBuilder::new("INSERT INTO orders (tenant_id, id, total_cents, ...) ")
+    ///     .push_values(&rows, |mut b, row| {
+    ///         b.push_bind(row.tenant_id).push_bind(row.id); // ... one bind per column
+    ///     })
+    ///     .build()
+    ///     .execute(&mut *tx)
+    ///     .await
+    ///     .map_err(/* unique violation -> DuplicateOrder, as in `insert` */)?;
+    /// // ... the same for line_items
+    /// tx.commit().await.map_err(OrderError::storage_failed)
+    /// ```
+    /// A duplicate key aborts the transaction: no order of the batch is stored.
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        if orders.is_empty() {
+            // No VALUES at all isn't valid SQL: nothing to send
+            return Ok(());
+        }
+        let values: Vec<String> = orders
+            .iter()
+            .map(|order| format!("({}, {}, ...)", order.tenant.0, order.id))
+            .collect();
+        self.run(
+            StatementKind::Insert,
+            "orders",
+            None,
+            &format!(
+                "[Postgres] INSERT INTO orders VALUES {} + {} line_items",
+                values.join(", "),
+                orders.iter().map(|order| order.items.len()).sum::<usize>()
+            ),
+        );
+        let mut db = self.db();
+        let mut batch = HashSet::new();
+        for order in orders {
+            let key = key(order.tenant, order.id);
+            if db.orders.contains_key(&key) || !batch.insert(key) {
+                // Simulates: ERROR: duplicate key value, then ROLLBACK
+                return Err(OrderError::DuplicateOrder(order.id));
+            }
+        }
+        for order in orders {
+            db.replace(order);
+        }
+        Ok(())
+    }
+
+    /// Updates an order in PostgreSQL.
+    ///
+    /// Real implementation, in a transaction like `insert`:
+    /// ```ignore
+    /// async fn update(&self, order: &Order) -> Result<(), OrderError> {
+    ///     let (row, items, history) = OrderRow::from_order(order);
+    ///     let mut tx = self.pool.begin().await.map_err(OrderError::storage_failed)?;
+    ///     let result = sqlx::query(
+    ///         "UPDATE orders SET total_cents = $3, status = $4, ... \
+    ///          WHERE tenant_id = $1 AND id = $2 AND deleted_at IS NULL"
+    ///     )
+    ///     // ... one bind per column
+    ///     .execute(&mut *tx)
+    ///     .await
+    ///     .map_err(OrderError::storage_failed)?;
+    ///
+    ///     // Zero rows touched means there was nothing to update
+    ///     if result.rows_affected() == 0 {
+    ///         return Err(OrderError::OrderNotFound(order.id));
+    ///     }
+    ///
+    ///     // Items and history may have changed: replace them all
+    ///     sqlx::query("DELETE FROM line_items WHERE tenant_id = $1 AND order_id = $2")
+    ///     // ... the same DELETE on order_history, then the same INSERTs as in `insert`
+    ///     tx.commit().await.map_err(OrderError::storage_failed)
+    /// }
+    /// ```
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.run(
+            StatementKind::Update,
+            "orders",
+            Some(order.id),
+            &format!(
+                "[Postgres] UPDATE orders SET ... WHERE tenant_id = {} AND id = {}",
+                order.tenant.0, order.id
+            ),
+        );
+        let mut db = self.db();
+        if db.live(key(order.tenant, order.id)).is_none() {
+            return Err(OrderError::OrderNotFound(order.id));
+        }
+        db.replace(order);
+        Ok(())
+    }
+
+    /// Updates an order in PostgreSQL, if it's still at the version we loaded.
+    ///
+    /// Real implementation: the same query as `update`, with the version in
+    /// the WHERE clause, so the check and the write are one statement:
+    /// ```ignore
+    /// "UPDATE orders SET total_cents = $3, version = $4, ...
+    ///  WHERE tenant_id = $1 AND id = $2 AND version = $5"
+    /// ```
+    /// Zero rows touched means either no such order or a newer version;
+    /// a follow-up `SELECT version` tells which, and which version won.
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.run(
+            StatementKind::Update,
+            "orders",
+            Some(order.id),
+            &format!(
+            "[Postgres] UPDATE orders SET ..., version = {} WHERE tenant_id = {} AND id = {} AND version = {expected}",
+            order.version, order.tenant.0, order.id
+            ),
+        );
+        let mut db = self.db();
+        let row = db
+            .live(key(order.tenant, order.id))
+            .ok_or(OrderError::OrderNotFound(order.id))?;
+        if row.version != i64::from(expected) {
+            return Err(OrderError::Conflict {
+                id: order.id,
+                expected,
+                found: column(row.version)?,
+            });
+        }
+        db.replace(order);
+        Ok(())
+    }
+
+    /// Deletes an order from PostgreSQL for good, soft-deleted or not.
+    ///
+    /// Real implementation: `"DELETE FROM orders WHERE tenant_id = $1 AND id = $2"`,
+    /// with `ON DELETE CASCADE` on `line_items` and `order_history` taking
+    /// the items and the history along, and `rows_affected() > 0` as the answer.
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.run(
+            StatementKind::Delete,
+            "orders",
+            Some(id),
+            &format!(
+                "[Postgres] DELETE FROM orders WHERE tenant_id = {} AND id = {id}",
+                tenant.0
+            ),
+        );
+        let mut db = self.db();
+        let key = key(tenant, id);
+        db.line_items
+            .retain(|item| (item.tenant_id, item.order_id) != key);
+        db.history
+            .retain(|change| (change.tenant_id, change.order_id) != key);
+        Ok(db.orders.remove(&key).is_some())
+    }
+
+    /// Stamps an order as deleted, keeping its rows.
+    ///
+    /// Real implementation: `"UPDATE orders SET deleted_at = now()
+    /// WHERE tenant_id = $1 AND id = $2 AND deleted_at IS NULL"`, and
+    /// `rows_affected() > 0` as the answer.
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.run(
+            StatementKind::Update,
+            "orders",
+            Some(id),
+            &format!(
+            "[Postgres] UPDATE orders SET deleted_at = now() WHERE tenant_id = {} AND id = {id} AND deleted_at IS NULL",
+            tenant.0
+            ),
+        );
+        let mut db = self.db();
+        match db.orders.get_mut(&key(tenant, id)) {
+            Some(row) if row.deleted_at.is_none() => {
+                row.deleted_at = Some(SystemTime::now());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Clears an order's deleted stamp.
+    ///
+    /// Real implementation: `"UPDATE orders SET deleted_at = NULL
+    /// WHERE tenant_id = $1 AND id = $2 AND deleted_at IS NOT NULL"`.
+    fn restore(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.run(
+            StatementKind::Update,
+            "orders",
+            Some(id),
+            &format!(
+            "[Postgres] UPDATE orders SET deleted_at = NULL WHERE tenant_id = {} AND id = {id} AND deleted_at IS NOT NULL",
+            tenant.0
+            ),
+        );
+        let mut db = self.db();
+        Ok(db
+            .orders
+            .get_mut(&key(tenant, id))
+            .and_then(|row| row.deleted_at.take())
+            .is_some())
+    }
+
     /// Retrieves an order from PostgreSQL.
     ///
     /// Real implementation:
     /// ```ignore
-    /// async fn find(&self, id: OrderId) -> Result<Option<Order>, OrderError> {
+    /// async fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
     ///     let row = sqlx::query_as::<_, OrderRow>(
-    ///         "SELECT * FROM orders WHERE id = $1"
+    ///         "SELECT * FROM orders WHERE tenant_id = $1 AND id = $2 AND deleted_at IS NULL"
     ///     )
-    ///     .bind(id.0)
+    ///     .bind(i64::from(tenant.0))
+    ///     .bind(i64::from(id.0))
     ///     .fetch_optional(&self.pool)
     ///     .await
-    ///     .map_err(|_| OrderError::StorageFailed)?;
+    ///     .map_err(OrderError::storage_failed)?;
+    ///     let Some(row) = row else { return Ok(None) };
     ///
-    ///     Ok(row.map(Into::into))
+    ///     let items = sqlx::query_as::<_, LineItemRow>(
+    ///         "SELECT * FROM line_items WHERE tenant_id = $1 AND order_id = $2"
+    ///     )
+    ///     // ... same binds
+    ///     .fetch_all(&self.pool)
+    ///     .await
+    ///     .map_err(OrderError::storage_failed)?;
+    ///     let history = sqlx::query_as::<_, HistoryRow>(
+    ///         "SELECT * FROM order_history WHERE tenant_id = $1 AND order_id = $2"
+    ///     )
+    ///     // ... same binds, same fetch
+    ///
+    ///     row.into_order(items, history).map(Some)
     /// }
     /// ```
-    fn find(&self, id: OrderId) -> Result<Option<Order>, OrderError> {
-        println!("  [Postgres] SELECT * FROM orders WHERE id = {id}");
-        Ok(self.simulated_db.get(&id).cloned())
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.run(
+            StatementKind::Select,
+            "orders",
+            Some(id),
+            &format!(
+            "[Postgres] SELECT * FROM orders WHERE tenant_id = {} AND id = {id} AND deleted_at IS NULL",
+            tenant.0
+            ),
+        );
+        let db = self.db();
+        db.live(key(tenant, id))
+            .map(|row| db.order(row))
+            .transpose()
+    }
+
+    /// The same query as `find`, without the `deleted_at` filter.
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.run(
+            StatementKind::Select,
+            "orders",
+            Some(id),
+            &format!(
+                "[Postgres] SELECT * FROM orders WHERE tenant_id = {} AND id = {id}",
+                tenant.0
+            ),
+        );
+        let db = self.db();
+        db.orders
+            .get(&key(tenant, id))
+            .map(|row| db.order(row))
+            .transpose()
+    }
+
+    /// Retrieves every order from PostgreSQL.
+    ///
+    /// Real implementation: the same `query_as` with
+    /// `"SELECT * FROM orders WHERE tenant_id = $1 AND deleted_at IS NULL ORDER BY id"`
+    /// and `fetch_all`,
+    /// then the items of all those orders in one more query.
+    /// A real app would paginate instead of loading the whole table.
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.run(
+            StatementKind::Select,
+            "orders",
+            None,
+            &format!(
+            "[Postgres] SELECT * FROM orders WHERE tenant_id = {} AND deleted_at IS NULL ORDER BY id",
+            tenant.0
+            ),
+        );
+        let db = self.db();
+        let mut orders = db
+            .orders
+            .values()
+            .filter(|row| row.tenant_id == i64::from(tenant.0) && row.deleted_at.is_none())
+            .map(|row| db.order(row))
+            .collect::<Result<Vec<_>, _>>()?;
+        orders.sort_by_key(|order| order.id);
+        Ok(orders)
+    }
+
+    /// One query for all the IDs. Postgres returns the rows in no
+    /// particular order, so they're put back in the order asked for (the
+    /// real query could `ORDER BY array_position($2, id)` instead).
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        let list = ids
+            .iter()
+            .map(|id| id.0.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.run(
+            StatementKind::Select,
+            "orders",
+            None,
+            &format!(
+                "[Postgres] SELECT * FROM orders WHERE tenant_id = {} AND id = ANY(ARRAY[{list}]) \
+                 AND deleted_at IS NULL",
+                tenant.0
+            ),
+        );
+        let db = self.db();
+        let mut orders: Vec<(usize, Order)> = db
+            .orders
+            .values()
+            .filter(|row| row.tenant_id == i64::from(tenant.0) && row.deleted_at.is_none())
+            .filter_map(|row| {
+                let first = ids.iter().position(|id| i64::from(id.0) == row.id)?;
+                Some(db.order(row).map(|order| (first, order)))
+            })
+            .collect::<Result<_, _>>()?;
+        orders.sort_by_key(|&(first, _)| first);
+        Ok(orders.into_iter().map(|(_, order)| order).collect())
+    }
+
+    /// The search runs on `line_items`. The real query binds the text with
+    /// its `%`, `_` and `\` escaped, so it matches as plain text, like here.
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        self.run(
+            StatementKind::Select,
+            "orders",
+            None,
+            &format!(
+                "[Postgres] SELECT * FROM orders WHERE tenant_id = {} AND deleted_at IS NULL \
+                 AND id IN (SELECT order_id FROM line_items WHERE tenant_id = {} \
+                 AND name ILIKE '%{query}%') ORDER BY id",
+                tenant.0, tenant.0
+            ),
+        );
+        let db = self.db();
+        let query = query.to_lowercase();
+        let matching: HashSet<i64> = db
+            .line_items
+            .iter()
+            .filter(|item| {
+                item.tenant_id == i64::from(tenant.0) && item.name.to_lowercase().contains(&query)
+            })
+            .map(|item| item.order_id)
+            .collect();
+        let mut orders = matching
+            .into_iter()
+            .filter_map(|id| db.live((i64::from(tenant.0), id)))
+            .map(|row| db.order(row))
+            .collect::<Result<Vec<_>, _>>()?;
+        orders.sort_by_key(|order| order.id);
+        Ok(orders)
+    }
+}
+
+impl HealthCheck for PostgresOrderRepository {
+    // Simulated: there is no server to ping. The real one is SqlxOrderRepository
+    fn health(&self) -> HealthStatus {
+        HealthStatus::Healthy
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::WriterLogger;
     use domain::{LineItem, Money};
 
-    #[test]
-    fn postgres_save_and_find() {
-        let mut repo = PostgresOrderRepository::new();
-        let order = Order::new(
-            OrderId(1),
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
             vec![LineItem {
                 name: "Test".to_string(),
                 price: Money(100),
+                weight_grams: None,
             }],
         )
-        .unwrap();
+        .unwrap()
+    }
 
-        repo.save(&order).unwrap();
-        let found = repo.find(OrderId(1)).unwrap();
+    #[test]
+    fn postgres_save_and_find() {
+        let repo = PostgresOrderRepository::new();
+        let order = test_order(1);
+
+        repo.insert(&order).unwrap();
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap();
 
         assert!(found.is_some());
     }
+
+    #[test]
+    fn postgres_insert_duplicate_fails() {
+        let repo = PostgresOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+
+        let result = repo.insert(&test_order(1));
+
+        assert!(matches!(
+            result,
+            Err(OrderError::DuplicateOrder(OrderId(1)))
+        ));
+    }
+
+    #[test]
+    fn postgres_update_missing_fails() {
+        let repo = PostgresOrderRepository::new();
+
+        let result = repo.update(&test_order(1));
+
+        assert!(matches!(result, Err(OrderError::OrderNotFound(OrderId(1)))));
+    }
+
+    #[test]
+    fn postgres_rows_are_scoped_by_tenant() {
+        let repo = PostgresOrderRepository::new();
+        let mut order = test_order(1);
+        order.tenant = TenantId(1);
+        repo.insert(&order).unwrap();
+
+        assert!(repo.find(TenantId(2), OrderId(1)).unwrap().is_none());
+        assert!(repo.find_all(TenantId(2)).unwrap().is_empty());
+        assert_eq!(repo.find_all(TenantId(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn three_item_order_round_trips_through_rows() {
+        let items = vec![
+            LineItem {
+                name: "Keyboard".to_string(),
+                price: Money(7999),
+                weight_grams: None,
+            },
+            LineItem {
+                name: "Mouse".to_string(),
+                price: Money(2999),
+                weight_grams: None,
+            },
+            LineItem {
+                name: "Pad".to_string(),
+                price: Money(999),
+                weight_grams: None,
+            },
+        ];
+        let order = Order::new(OrderId(1), items.clone()).unwrap();
+
+        let (row, mut item_rows, history) = OrderRow::from_order(&order);
+        assert_eq!(item_rows.len(), 3);
+        // The database returns rows in whatever order it likes
+        item_rows.reverse();
+        let back = row.into_order(item_rows, history).unwrap();
+
+        assert_eq!(back.items, items);
+        assert_eq!(back.total, Money(11997));
+        assert_eq!(back.status, order.status);
+    }
+
+    #[test]
+    fn order_row_without_items_is_storage_failure() {
+        let (row, _, _) = OrderRow::from_order(&test_order(1));
+
+        assert!(matches!(
+            row.into_order(vec![], vec![]),
+            Err(OrderError::StorageFailed(_))
+        ));
+    }
+
+    #[test]
+    fn inconsistent_rows_are_storage_failures() {
+        let (row, items, _) = OrderRow::from_order(&test_order(1));
+
+        let mut negative = items.clone();
+        negative[0].price_cents = -1;
+        assert!(matches!(
+            row.clone().into_order(negative, vec![]),
+            Err(OrderError::StorageFailed(_))
+        ));
+
+        let mut wrong_total = row.clone();
+        wrong_total.total_cents += 1;
+        assert!(matches!(
+            wrong_total.into_order(items.clone(), vec![]),
+            Err(OrderError::StorageFailed(_))
+        ));
+
+        let mut unknown_status = row.clone();
+        unknown_status.status = "Lost".to_string();
+        assert!(matches!(
+            unknown_status.into_order(items.clone(), vec![]),
+            Err(OrderError::StorageFailed(_))
+        ));
+
+        let mut half_a_charge = row.clone();
+        half_a_charge.extra_charge_ids = vec!["txn_extra".to_string()];
+        assert!(matches!(
+            half_a_charge.into_order(items.clone(), vec![]),
+            Err(OrderError::StorageFailed(_))
+        ));
+
+        let mut half_a_destination = row;
+        half_a_destination.shipping_cents = Some(0);
+        half_a_destination.ship_city = Some("Springfield".to_string());
+        assert!(matches!(
+            half_a_destination.into_order(items, vec![]),
+            Err(OrderError::StorageFailed(_))
+        ));
+    }
+
+    #[test]
+    fn shipping_round_trips_apart_from_the_items() {
+        let mut order = test_order(1);
+        order
+            .set_shipping(Some(Shipping {
+                destination: Address::new("1 Main St", "Springfield", "12345", "us").unwrap(),
+                cost: Money(499),
+            }))
+            .unwrap();
+        let (row, items, history) = OrderRow::from_order(&order);
+
+        assert_eq!(items.len(), order.items.len());
+        assert_eq!(row.shipping_cents, Some(499));
+        assert_eq!(row.ship_country.as_deref(), Some("US"));
+        assert_eq!(row.into_order(items, history).unwrap(), order);
+    }
+
+    #[test]
+    fn extra_charges_round_trip_in_order() {
+        let mut order = test_order(1);
+        order.transaction_id = Some("txn_first".to_string());
+        order.mark_paid(UNIX_EPOCH).unwrap();
+        for (transaction_id, cents) in [("txn_pen", 199), ("txn_pad", 999)] {
+            order.extra_charges.push(ExtraCharge {
+                transaction_id: transaction_id.to_string(),
+                amount: Money(cents),
+            });
+        }
+        order.loyalty_credited = 21;
+        let (row, items, history) = OrderRow::from_order(&order);
+
+        assert_eq!(row.extra_charge_ids, ["txn_pen", "txn_pad"]);
+        assert_eq!(row.extra_charge_cents, [199, 999]);
+        assert_eq!(row.loyalty_credited, 21);
+        assert_eq!(row.into_order(items, history).unwrap(), order);
+    }
+
+    #[test]
+    fn postgres_keeps_the_history_to_the_nanosecond() {
+        let repo = PostgresOrderRepository::new();
+        let mut order = test_order(1);
+        let paid = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        order.record_placed(paid - Duration::from_nanos(1)).unwrap();
+        order.mark_paid(paid).unwrap();
+        order
+            .cancel(paid + Duration::from_secs(5), Some("duplicate"))
+            .unwrap();
+        repo.insert(&order).unwrap();
+
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+
+        assert_eq!(found, order);
+        assert_eq!(found.history.len(), 3);
+    }
+
+    #[test]
+    fn history_rows_round_trip_in_any_order() {
+        let mut order = test_order(1);
+        order
+            .mark_paid(UNIX_EPOCH - Duration::from_secs(1))
+            .unwrap();
+        order.mark_shipped(UNIX_EPOCH).unwrap();
+        let (row, items, mut history) = OrderRow::from_order(&order);
+        history.reverse();
+
+        assert_eq!(
+            row.clone()
+                .into_order(items.clone(), history.clone())
+                .unwrap(),
+            order
+        );
+
+        history[0].order_id = 2;
+        assert!(matches!(
+            row.into_order(items, history),
+            Err(OrderError::StorageFailed(_))
+        ));
+    }
+
+    #[test]
+    fn postgres_find_returns_the_stored_items() {
+        let repo = PostgresOrderRepository::new();
+        let mut order = test_order(1);
+        order
+            .add_item(LineItem {
+                name: "Extra".to_string(),
+                price: Money(50),
+                weight_grams: None,
+            })
+            .unwrap();
+        repo.insert(&order).unwrap();
+
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+
+        assert_eq!(found.items, order.items);
+        assert_eq!(found.total, Money(150));
+    }
+
+    #[test]
+    fn postgres_rejects_saving_a_stale_copy() {
+        let repo = PostgresOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        let load = || repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+        let mut first = load();
+        let mut second = load();
+
+        first.mark_paid(UNIX_EPOCH).unwrap();
+        first.version += 1;
+        repo.update_versioned(&first, first.version - 1).unwrap();
+        second.cancel(UNIX_EPOCH, None).unwrap();
+        second.version += 1;
+        let result = repo.update_versioned(&second, second.version - 1);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::Conflict {
+                expected: 0,
+                found: 1,
+                ..
+            })
+        ));
+        assert_eq!(load().status, OrderStatus::Paid);
+    }
+
+    #[test]
+    fn postgres_soft_delete_hides_the_order_until_restored() {
+        let repo = PostgresOrderRepository::new();
+        repo.insert(&test_order(1)).unwrap();
+        repo.insert(&test_order(2)).unwrap();
+        let tenant = TenantId::default();
+
+        assert!(repo.soft_delete(tenant, OrderId(1)).unwrap());
+        // Twice is nothing new
+        assert!(!repo.soft_delete(tenant, OrderId(1)).unwrap());
+        assert!(repo.find(tenant, OrderId(1)).unwrap().is_none());
+        assert_eq!(repo.find_all(tenant).unwrap().len(), 1);
+        assert!(matches!(
+            repo.update(&test_order(1)),
+            Err(OrderError::OrderNotFound(OrderId(1)))
+        ));
+        assert!(
+            repo.find_including_deleted(tenant, OrderId(1))
+                .unwrap()
+                .is_some()
+        );
+
+        assert!(repo.restore(tenant, OrderId(1)).unwrap());
+        assert_eq!(repo.find_all(tenant).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn postgres_save_all_inserts_the_whole_batch() {
+        let repo = PostgresOrderRepository::new();
+        let orders: Vec<Order> = (1..=200).map(test_order).collect();
+
+        repo.save_all(&[]).unwrap();
+        repo.save_all(&orders).unwrap();
+
+        assert_eq!(repo.find_all(TenantId::default()).unwrap(), orders);
+    }
+
+    #[test]
+    fn postgres_save_all_with_a_duplicate_rolls_back() {
+        let repo = PostgresOrderRepository::new();
+        repo.insert(&test_order(2)).unwrap();
+
+        let result = repo.save_all(&[test_order(1), test_order(2), test_order(3)]);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::DuplicateOrder(OrderId(2)))
+        ));
+        assert_eq!(repo.find_all(TenantId::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn postgres_soft_deleting_an_unknown_order_finds_nothing() {
+        let repo = PostgresOrderRepository::new();
+
+        assert!(!repo.soft_delete(TenantId::default(), OrderId(9)).unwrap());
+        assert!(!repo.restore(TenantId::default(), OrderId(9)).unwrap());
+    }
+
+    #[test]
+    fn postgres_find_by_item_name_searches_the_line_items() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let repo = PostgresOrderRepository::new().with_logger(logger.clone());
+        for (id, name) in [(3, "Blue Mug"), (1, "mug rack"), (2, "Kettle")] {
+            let mut order = test_order(id);
+            order.items[0].name = name.to_string();
+            repo.insert(&order).unwrap();
+        }
+        let ids = |query| -> Vec<OrderId> {
+            repo.find_by_item_name(TenantId::default(), query)
+                .unwrap()
+                .iter()
+                .map(|o| o.id)
+                .collect()
+        };
+
+        assert_eq!(ids("MUG"), [OrderId(1), OrderId(3)]);
+        assert!(ids("tea").is_empty());
+        assert!(logger.text().ends_with(
+            "  [Postgres] SELECT * FROM orders WHERE tenant_id = 0 AND deleted_at IS NULL \
+             AND id IN (SELECT order_id FROM line_items WHERE tenant_id = 0 \
+             AND name ILIKE '%tea%') ORDER BY id\n"
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn postgres_save_refuses_to_overwrite_a_different_order() {
+        let repo = PostgresOrderRepository::new();
+        let mut other = test_order(1);
+        other.status = OrderStatus::Shipped;
+
+        repo.save(&test_order(1)).unwrap();
+        let mut saved_again = test_order(1);
+        saved_again.version = 1;
+        repo.save(&saved_again).unwrap();
+        let conflicting = repo.save(&other);
+
+        assert!(matches!(
+            conflicting,
+            Err(OrderError::DuplicateOrder(OrderId(1)))
+        ));
+        let stored = repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+        assert_eq!(stored.status, OrderStatus::Pending);
+        assert_eq!(stored.version, 1);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn postgres_lenient_saves_overwrite() {
+        let repo = PostgresOrderRepository::new().with_lenient_saves();
+        let mut other = test_order(1);
+        other.status = OrderStatus::Shipped;
+
+        repo.save(&test_order(1)).unwrap();
+        repo.save(&other).unwrap();
+
+        assert_eq!(
+            repo.find(TenantId::default(), OrderId(1)).unwrap(),
+            Some(other)
+        );
+    }
+
+    #[test]
+    fn postgres_find_many_is_one_query() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let repo = PostgresOrderRepository::new().with_logger(logger.clone());
+        for id in 1..=3 {
+            repo.insert(&test_order(id)).unwrap();
+        }
+        let wanted = [OrderId(3), OrderId(7), OrderId(1), OrderId(3)];
+
+        let found = repo.find_many(TenantId::default(), &wanted).unwrap();
+
+        let ids: Vec<OrderId> = found.iter().map(|o| o.id).collect();
+        assert_eq!(ids, [OrderId(3), OrderId(1)]);
+        assert_eq!(found[0], test_order(3));
+        assert!(logger.text().ends_with(
+            "  [Postgres] SELECT * FROM orders WHERE tenant_id = 0 \
+             AND id = ANY(ARRAY[3, 7, 1, 3]) AND deleted_at IS NULL\n"
+        ));
+        assert!(repo.find_many(TenantId::default(), &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn postgres_keeps_a_select_for_a_missing_order() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let repo = PostgresOrderRepository::new().with_logger(logger);
+        repo.insert(&test_order(1)).unwrap();
+        repo.clear_query_log();
+
+        let found = repo.find(TenantId::default(), OrderId(9)).unwrap();
+
+        assert!(found.is_none());
+        assert_eq!(
+            repo.query_log(),
+            [SimulatedQuery {
+                kind: StatementKind::Select,
+                table: "orders",
+                order_id: Some(OrderId(9)),
+            }]
+        );
+    }
+
+    #[test]
+    fn postgres_queries_go_to_the_logger() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let repo = PostgresOrderRepository::new().with_logger(logger.clone());
+
+        repo.insert(&test_order(1)).unwrap();
+        repo.find(TenantId::default(), OrderId(1)).unwrap();
+
+        assert_eq!(
+            logger.text(),
+            "  [Postgres] INSERT INTO orders VALUES (0, OrderId(1), ...) + 1 line_items\n  \
+             [Postgres] SELECT * FROM orders WHERE tenant_id = 0 AND id = OrderId(1) AND deleted_at IS NULL\n"
+        );
+    }
 }
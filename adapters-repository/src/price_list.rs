@@ -0,0 +1,55 @@
+// =============================================================================
+// In-Memory Price List - The Catalog, Seeded from a Map
+// =============================================================================
+//
+// The `PriceList` port says what a product costs. Here the catalog is a
+// HashMap filled once at startup; nothing changes a price afterwards.
+//
+// In PostgreSQL this would be:
+//
+//     SELECT price_cents FROM products WHERE sku = $1
+//
+// with no row meaning `UnknownProduct`.
+
+use domain::{Money, OrderError, PriceList, ProductId};
+use std::collections::HashMap;
+
+/// In-memory price list for testing scenarios.
+#[derive(Debug, Default)]
+pub struct InMemoryPriceList {
+    prices: HashMap<ProductId, Money>,
+}
+
+impl InMemoryPriceList {
+    /// Creates a price list knowing exactly the products in `prices`.
+    #[must_use]
+    pub const fn new(prices: HashMap<ProductId, Money>) -> Self {
+        Self { prices }
+    }
+}
+
+impl PriceList for InMemoryPriceList {
+    fn price_of(&self, product: &ProductId) -> Result<Money, OrderError> {
+        self.prices
+            .get(product)
+            .copied()
+            .ok_or_else(|| OrderError::UnknownProduct(product.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_products_have_a_price_others_fail() {
+        let keyboard = ProductId("KB-101".to_string());
+        let prices = InMemoryPriceList::new(HashMap::from([(keyboard.clone(), Money(4999))]));
+
+        assert_eq!(prices.price_of(&keyboard).unwrap(), Money(4999));
+        assert!(matches!(
+            prices.price_of(&ProductId("NOPE".to_string())),
+            Err(OrderError::UnknownProduct(ProductId(sku))) if sku == "NOPE"
+        ));
+    }
+}
@@ -0,0 +1,364 @@
+// =============================================================================
+// Replicated Repository - Writes to the Primary, Reads from the Replicas
+// =============================================================================
+//
+// A busy PostgreSQL setup has one primary, taking every write, and read
+// replicas streaming its changes. Most traffic is reads ("where's my order?"),
+// so sending those to the replicas leaves the primary free for the writes.
+//
+// ReplicatedOrderRepository is that routing, in one place:
+//
+//     OrderService -> ReplicatedOrderRepository -> primary    (insert, update, delete...)
+//                                               -> replica 1  (find, find_all...)
+//                                               -> replica 2
+//
+// Reads take turns between replicas (round robin). Where they go is the
+// ReadPreference:
+// - Primary: everything on the primary, replicas idle (e.g. during a failover)
+// - Replica: reads on a replica, and a replica error is the caller's error
+// - ReplicaWithFallback: a replica failing with StorageFailed gets the read
+//   retried on the primary. Other errors are answers, not outages: they go
+//   back to the caller as they are.
+//
+// THE CATCH: STALENESS
+// --------------------
+// Replicas lag behind the primary, by milliseconds on a good day. An order
+// just inserted may not be on the replica yet: "place the order, then show
+// it" can answer "not found". Reads that must see the caller's own writes
+// (read-your-writes) belong on the primary; a use case can hold a second
+// repository configured with ReadPreference::Primary for those.
+
+use domain::{
+    HealthCheck, HealthStatus, Order, OrderError, OrderId, OrderRepository, RequestContext,
+    TenantId,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Where reads go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadPreference {
+    /// Every read on the primary.
+    Primary,
+    /// Every read on a replica.
+    #[default]
+    Replica,
+    /// Reads on a replica, retried on the primary if the replica fails with
+    /// [`OrderError::StorageFailed`].
+    ReplicaWithFallback,
+}
+
+/// Repository routing writes to a primary and reads to replicas.
+///
+/// Replicas lag behind the primary: a read right after a write may not see
+/// it. See [`ReadPreference::Primary`] for reads that must.
+#[derive(Debug)]
+pub struct ReplicatedOrderRepository<W, R> {
+    primary: W,
+    replicas: Vec<R>,
+    preference: ReadPreference,
+    // The replica that serves the next read, modulo the number of replicas
+    next: AtomicUsize,
+}
+
+impl<W: OrderRepository, R: OrderRepository> ReplicatedOrderRepository<W, R> {
+    /// Writes to `primary`, reads from `replicas` in turn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `replicas` is empty.
+    #[must_use]
+    pub fn new(primary: W, replicas: Vec<R>) -> Self {
+        assert!(!replicas.is_empty(), "reads need at least one replica");
+        Self {
+            primary,
+            replicas,
+            preference: ReadPreference::default(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sends reads where `preference` says.
+    #[must_use]
+    pub const fn with_read_preference(mut self, preference: ReadPreference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    /// Where reads go.
+    #[must_use]
+    pub const fn read_preference(&self) -> ReadPreference {
+        self.preference
+    }
+
+    /// The repository taking the writes.
+    #[must_use]
+    pub const fn primary(&self) -> &W {
+        &self.primary
+    }
+
+    /// The repositories taking the reads.
+    #[must_use]
+    pub fn replicas(&self) -> &[R] {
+        &self.replicas
+    }
+
+    /// Runs a read where the preference says.
+    fn read<T>(
+        &self,
+        call: impl Fn(&dyn OrderRepository) -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        if self.preference == ReadPreference::Primary {
+            return call(&self.primary);
+        }
+        let turn = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        match call(&self.replicas[turn]) {
+            Err(OrderError::StorageFailed(_))
+                if self.preference == ReadPreference::ReplicaWithFallback =>
+            {
+                call(&self.primary)
+            }
+            result => result,
+        }
+    }
+}
+
+impl<W: OrderRepository, R: OrderRepository> OrderRepository for ReplicatedOrderRepository<W, R> {
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.primary.insert(order)
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.primary.update(order)
+    }
+
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.primary.update_versioned(order, expected)
+    }
+
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        self.primary.save_all(orders)
+    }
+
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.primary.delete(tenant, id)
+    }
+
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.primary.soft_delete(tenant, id)
+    }
+
+    fn restore(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.primary.restore(tenant, id)
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.read(|repo| repo.find(tenant, id))
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.read(|repo| repo.find_all(tenant))
+    }
+
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.read(|repo| repo.find_including_deleted(tenant, id))
+    }
+
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        self.read(|repo| repo.find_many(tenant, ids))
+    }
+
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        self.read(|repo| repo.find_by_item_name(tenant, query))
+    }
+
+    fn insert_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.primary.insert_with_ctx(ctx, order)
+    }
+
+    fn update_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.primary.update_with_ctx(ctx, order)
+    }
+
+    fn find_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.read(|repo| repo.find_with_ctx(ctx, tenant, id))
+    }
+}
+
+impl<W: HealthCheck, R: HealthCheck> HealthCheck for ReplicatedOrderRepository<W, R> {
+    /// The primary's health, made worse by replicas that are down: degraded
+    /// while some still answer (or the primary can stand in for them),
+    /// unhealthy when reads have nowhere left to go.
+    fn health(&self) -> HealthStatus {
+        let primary = match self.primary.health() {
+            HealthStatus::Healthy => HealthStatus::Healthy,
+            HealthStatus::Degraded(reason) => HealthStatus::Degraded(format!("primary: {reason}")),
+            HealthStatus::Unhealthy(reason) => {
+                return HealthStatus::Unhealthy(format!("primary: {reason}"));
+            }
+        };
+        if self.preference == ReadPreference::Primary {
+            return primary;
+        }
+
+        let down = self
+            .replicas
+            .iter()
+            .filter(|replica| !replica.health().is_usable())
+            .count();
+        let replicas = if down == 0 {
+            HealthStatus::Healthy
+        } else if down < self.replicas.len() {
+            HealthStatus::Degraded(format!("{down} of {} replicas down", self.replicas.len()))
+        } else if self.preference == ReadPreference::ReplicaWithFallback {
+            HealthStatus::Degraded("every replica down, reads go to the primary".to_string())
+        } else {
+            HealthStatus::Unhealthy("every replica down".to_string())
+        };
+        primary.max(replicas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FaultCounters, FaultyOrderRepository, SharedInMemoryOrderRepository};
+    use domain::{LineItem, Money};
+
+    type Counted = FaultyOrderRepository<SharedInMemoryOrderRepository>;
+
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
+            vec![LineItem {
+                name: "Test".to_string(),
+                price: Money(100),
+                weight_grams: None,
+            }],
+        )
+        .unwrap()
+    }
+
+    /// A primary and two replicas, all counting their calls, over one store:
+    /// replication without the lag.
+    fn replicated(preference: ReadPreference) -> ReplicatedOrderRepository<Counted, Counted> {
+        let store = SharedInMemoryOrderRepository::default();
+        let replicas = vec![
+            FaultyOrderRepository::new(store.clone()),
+            FaultyOrderRepository::new(store.clone()),
+        ];
+        ReplicatedOrderRepository::new(FaultyOrderRepository::new(store), replicas)
+            .with_read_preference(preference)
+    }
+
+    fn finds(repo: &Counted) -> u32 {
+        repo.counters().finds
+    }
+
+    #[test]
+    fn writes_go_to_the_primary_and_reads_take_turns_on_the_replicas() {
+        let repo = replicated(ReadPreference::Replica);
+
+        repo.insert(&test_order(1)).unwrap();
+        assert!(
+            repo.find(TenantId::default(), OrderId(1))
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(repo.find_all(TenantId::default()).unwrap().len(), 1);
+        repo.find(TenantId::default(), OrderId(1)).unwrap();
+
+        assert_eq!(repo.primary().counters().saves, 1);
+        assert_eq!(finds(repo.primary()), 0);
+        assert_eq!(finds(&repo.replicas()[0]), 2);
+        assert_eq!(finds(&repo.replicas()[1]), 1);
+        assert!(repo.replicas().iter().all(|r| r.counters().saves == 0));
+    }
+
+    #[test]
+    fn primary_preference_leaves_the_replicas_idle() {
+        let repo = replicated(ReadPreference::Primary);
+
+        repo.find(TenantId::default(), OrderId(1)).unwrap();
+
+        assert_eq!(finds(repo.primary()), 1);
+        assert!(repo.replicas().iter().all(|r| finds(r) == 0));
+    }
+
+    #[test]
+    fn a_failing_replica_falls_back_to_the_primary() {
+        let repo = replicated(ReadPreference::ReplicaWithFallback);
+        repo.insert(&test_order(1)).unwrap();
+        repo.replicas()[0].fail_every_nth_find(Some(1));
+
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap();
+
+        assert!(found.is_some());
+        assert_eq!(
+            repo.replicas()[0].counters(),
+            FaultCounters {
+                saves: 0,
+                finds: 1,
+                failures: 1
+            }
+        );
+        assert_eq!(finds(repo.primary()), 1);
+    }
+
+    #[test]
+    fn without_fallback_a_failing_replica_is_an_error() {
+        let repo = replicated(ReadPreference::Replica);
+        repo.replicas()[0].fail_every_nth_find(Some(1));
+
+        let result = repo.find_all(TenantId::default());
+
+        assert!(matches!(result, Err(OrderError::StorageFailed(_))));
+        assert_eq!(finds(repo.primary()), 0);
+    }
+
+    #[test]
+    fn health_depends_on_how_many_replicas_are_down() {
+        let repo = replicated(ReadPreference::Replica);
+        assert_eq!(repo.health(), HealthStatus::Healthy);
+
+        repo.replicas()[0].fail_every_nth_find(Some(1));
+        assert_eq!(
+            repo.health(),
+            HealthStatus::Degraded("1 of 2 replicas down".to_string())
+        );
+
+        repo.replicas()[1].fail_every_nth_find(Some(1));
+        assert_eq!(
+            repo.health(),
+            HealthStatus::Unhealthy("every replica down".to_string())
+        );
+    }
+
+    #[test]
+    fn with_fallback_or_on_the_primary_dead_replicas_only_degrade() {
+        let fallback = replicated(ReadPreference::ReplicaWithFallback);
+        let primary_only = replicated(ReadPreference::Primary);
+        for repo in [&fallback, &primary_only] {
+            for replica in repo.replicas() {
+                replica.fail_every_nth_find(Some(1));
+            }
+        }
+
+        assert!(matches!(fallback.health(), HealthStatus::Degraded(_)));
+        assert_eq!(primary_only.health(), HealthStatus::Healthy);
+
+        primary_only.primary().fail_every_nth_find(Some(1));
+        assert_eq!(
+            primary_only.health(),
+            HealthStatus::Unhealthy("primary: every find fails".to_string())
+        );
+    }
+}
@@ -0,0 +1,206 @@
+// =============================================================================
+// Seed Data - Made-Up Orders, by the Thousand
+// =============================================================================
+//
+// A benchmark of `find` against an empty repository measures nothing, and
+// every demo and manual test needs a few line items. Writing them by hand
+// gets old fast:
+//
+//     let items = generate_items(3, 42);          // always the same three
+//     let ferris = seed_recipient();              // who they're for
+//     seed(&repo, 10_000, 42)?;                   // a store to benchmark
+//
+// The items look like a shop's: an adjective and a noun from fixed word
+// lists ("Ergonomic Keyboard"), priced between $0.50 and $500.00. Seeded
+// orders have one to three of them, and two out of three are paid.
+//
+// This is the one generator of the workspace: the repository benchmarks,
+// the application benchmark, the app's `seed` command and its tests all
+// draw from it. It lives here, not in test-support, because the app ships
+// with that command.
+//
+// DETERMINISM:
+// ------------
+// The same seed gives the same items, on every run and every machine, so a
+// benchmark measures the same orders twice and a failing test fails again.
+// Hence a small generator of our own (SplitMix64) rather than `rand`: its
+// output can't change under us with a dependency update.
+
+use domain::{
+    Contact, LineItem, Money, Order, OrderError, OrderId, OrderRepository, Recipient, money,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+const ADJECTIVES: [&str; 8] = [
+    "Ergonomic",
+    "Wireless",
+    "Vintage",
+    "Compact",
+    "Deluxe",
+    "Rustproof",
+    "Handmade",
+    "Portable",
+];
+
+const NOUNS: [&str; 8] = [
+    "Keyboard",
+    "Mug",
+    "Notebook",
+    "Desk Lamp",
+    "Backpack",
+    "Headphones",
+    "Crab Plush",
+    "USB-C Cable",
+];
+
+/// The cheapest generated price: $0.50.
+pub const MIN_PRICE: Money = money!(0, 50);
+/// The dearest generated price: $500.00.
+pub const MAX_PRICE: Money = money!(500);
+
+/// SplitMix64: tiny, fast, and good enough for made-up shop data.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`. The modulo bias is negligible for our bounds.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    /// The next made-up item.
+    fn item(&mut self) -> LineItem {
+        let adjective = ADJECTIVES[self.below(ADJECTIVES.len() as u64) as usize];
+        let noun = NOUNS[self.below(NOUNS.len() as u64) as usize];
+        let spread = u64::from(MAX_PRICE.0 - MIN_PRICE.0) + 1;
+        // Below spread, itself at most 50 000: always fits
+        let price = MIN_PRICE.0 + u32::try_from(self.below(spread)).unwrap_or_default();
+        LineItem {
+            name: format!("{adjective} {noun}"),
+            price: Money(price),
+            weight_grams: None,
+        }
+    }
+}
+
+/// `count` items, made up but always the same for the same `rng_seed`.
+#[must_use]
+pub fn generate_items(count: usize, rng_seed: u64) -> Vec<LineItem> {
+    let mut rng = Rng(rng_seed);
+    (0..count).map(|_| rng.item()).collect()
+}
+
+/// The items of orders 1 to `count`: one to three each, always the same for
+/// the same `rng_seed`.
+///
+/// Order N has the same items whatever `count` is: seeding 10 orders then
+/// 100 gives the first 10 twice.
+pub fn seed_items(count: u32, rng_seed: u64) -> impl Iterator<Item = Vec<LineItem>> {
+    let mut rng = Rng(rng_seed);
+    (1..=count).map(move |id| (0..=id % 3).map(|_| rng.item()).collect())
+}
+
+/// Who made-up orders are for.
+///
+/// # Panics
+///
+/// Never: the recipient is valid.
+#[must_use]
+pub fn seed_recipient() -> Recipient {
+    Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string()))
+        .expect("the seed recipient is valid")
+}
+
+/// Orders 1 to `count`, with the items of [`seed_items`], two out of three
+/// paid.
+pub fn seed_orders(count: u32, rng_seed: u64) -> impl Iterator<Item = Order> {
+    seed_items(count, rng_seed).zip(1..).map(|(items, id)| {
+        let mut order = Order::new(OrderId(id), items).expect("seed orders have items");
+        // Two orders out of three are paid
+        if id % 3 != 0 {
+            order.transaction_id = Some(format!("seed_txn_{id}"));
+            // At a made-up time too: order N was paid N minutes into 2024
+            let paid_at = UNIX_EPOCH + Duration::from_secs(1_704_067_200 + 60 * u64::from(id));
+            order.mark_paid(paid_at).expect("a new order can be paid");
+        }
+        order
+    })
+}
+
+/// Inserts orders 1 to `count` of [`seed_orders`] into `repository`.
+///
+/// # Errors
+///
+/// Returns the repository's error if an insert fails (an ID already taken).
+pub fn seed<R: OrderRepository + ?Sized>(
+    repository: &R,
+    count: u32,
+    rng_seed: u64,
+) -> Result<(), OrderError> {
+    seed_orders(count, rng_seed).try_for_each(|order| repository.insert(&order))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryOrderRepository;
+    use domain::{OrderStatus, TenantId};
+
+    #[test]
+    fn the_same_seed_gives_the_same_items() {
+        let first = generate_items(50, 7);
+
+        assert_eq!(first, generate_items(50, 7));
+        assert_ne!(first, generate_items(50, 8));
+        assert_eq!(generate_items(10, 7), first[..10]);
+    }
+
+    #[test]
+    fn items_look_like_a_shops() {
+        let items = generate_items(1_000, 1);
+
+        for item in &items {
+            let (adjective, noun) = item.name.split_once(' ').unwrap();
+            assert!(ADJECTIVES.contains(&adjective), "{}", item.name);
+            assert!(NOUNS.contains(&noun), "{}", item.name);
+            assert!(
+                (MIN_PRICE.0..=MAX_PRICE.0).contains(&item.price.0),
+                "{}",
+                item.price
+            );
+        }
+        // Not the same item a thousand times over
+        assert!(items.iter().any(|item| item.price != items[0].price));
+        assert!(items.iter().any(|item| item.name != items[0].name));
+    }
+
+    #[test]
+    fn seed_items_draw_from_the_same_generator() {
+        let orders: Vec<Vec<LineItem>> = seed_items(4, 7).collect();
+
+        let sizes: Vec<usize> = orders.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [2, 3, 1, 2]);
+        assert_eq!(orders.concat(), generate_items(8, 7));
+        assert_eq!(seed_items(2, 7).collect::<Vec<_>>(), orders[..2]);
+    }
+
+    #[test]
+    fn seeding_is_deterministic() {
+        let repo = InMemoryOrderRepository::new();
+
+        seed(&repo, 6, 42).unwrap();
+
+        let orders = repo.find_all(TenantId::default()).unwrap();
+        assert_eq!(orders, seed_orders(6, 42).collect::<Vec<_>>());
+        assert_eq!(orders[0].items.len(), 2);
+        assert_eq!(orders[2].status, OrderStatus::Pending);
+        assert_eq!(orders[3].status, OrderStatus::Paid);
+    }
+}
@@ -0,0 +1,224 @@
+// =============================================================================
+// Shared Repository - One Store, Many Owners
+// =============================================================================
+//
+// The port takes `&self`, so OrderService (commands) and OrderQueries (reads)
+// can already borrow the same repository at the same time.
+//
+// Borrowing isn't always enough, though. A web framework like axum wants
+// handlers that OWN their state ('static), and a background job may outlive
+// the function that created the repository. The usual Rust answer is shared
+// ownership:
+//
+//     Arc<R>
+//
+// This wrapper does exactly that, and implements OrderRepository itself.
+// Cloning it is cheap and every clone talks to the SAME store:
+//
+//     let repo = SharedOrderRepository::new(InMemoryOrderRepository::new());
+//     let service = OrderService::new(&repo, &payment, &sender);
+//     let queries = OrderQueries::new(&repo);
+//     let for_the_background_job = repo.clone();
+//
+// Neither the domain nor the application know about Arc:
+// sharing is a wiring decision, so it lives with the adapters.
+// A real database adapter often doesn't need this at all: a connection
+// pool (sqlx::PgPool) is already cheap to clone and safe to share.
+
+use crate::InMemoryOrderRepository;
+use domain::{
+    HealthCheck, HealthStatus, Order, OrderError, OrderId, OrderRepository, RequestContext,
+    TenantId,
+};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+/// The usual pairing: one in-memory store, many owners, many threads.
+///
+/// `InMemoryOrderRepository` already locks internally, so the handle is
+/// `Send + Sync` and a clone can move into a `'static` thread.
+pub type SharedInMemoryOrderRepository = SharedOrderRepository<InMemoryOrderRepository>;
+
+/// Cloneable handle to a repository shared between several owners.
+///
+/// Every clone sees the writes of the others.
+#[derive(Debug, Default)]
+pub struct SharedOrderRepository<R> {
+    inner: Arc<R>,
+}
+
+impl<R> SharedOrderRepository<R> {
+    /// Wraps `repository` so it can be shared.
+    #[must_use]
+    pub fn new(repository: R) -> Self {
+        Self {
+            inner: Arc::new(repository),
+        }
+    }
+}
+
+// Manual impl: #[derive(Clone)] would require R: Clone, but only the Arc is cloned
+impl<R> Clone for SharedOrderRepository<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<R: OrderRepository> OrderRepository for SharedOrderRepository<R> {
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.inner.insert(order)
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.inner.update(order)
+    }
+
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.inner.update_versioned(order, expected)
+    }
+
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.inner.delete(tenant, id)
+    }
+
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        self.inner.save_all(orders)
+    }
+
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.inner.soft_delete(tenant, id)
+    }
+
+    fn restore(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.inner.restore(tenant, id)
+    }
+
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.inner.find_including_deleted(tenant, id)
+    }
+
+    fn find_with(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+        read: &mut dyn FnMut(Option<&Order>),
+    ) -> Result<(), OrderError> {
+        self.inner.find_with(tenant, id, read)
+    }
+
+    fn for_each(
+        &self,
+        tenant: TenantId,
+        visit: &mut dyn FnMut(&Order) -> ControlFlow<()>,
+    ) -> Result<(), OrderError> {
+        self.inner.for_each(tenant, visit)
+    }
+
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        self.inner.find_many(tenant, ids)
+    }
+
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        self.inner.find_by_item_name(tenant, query)
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.inner.find(tenant, id)
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.inner.find_all(tenant)
+    }
+
+    fn insert_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.inner.insert_with_ctx(ctx, order)
+    }
+
+    fn update_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.inner.update_with_ctx(ctx, order)
+    }
+
+    fn find_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.inner.find_with_ctx(ctx, tenant, id)
+    }
+}
+
+impl<R: HealthCheck> HealthCheck for SharedOrderRepository<R> {
+    // Only as healthy as the repository underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{LineItem, Money};
+    use std::thread;
+
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
+            vec![LineItem {
+                name: "Test".to_string(),
+                price: Money(100),
+                weight_grams: None,
+            }],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn clones_share_the_same_store() {
+        let repo = SharedOrderRepository::new(InMemoryOrderRepository::new());
+        let writer = repo.clone();
+
+        writer.insert(&test_order(1)).unwrap();
+
+        assert!(
+            repo.find(TenantId::default(), OrderId(1))
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(repo.find_all(TenantId::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn saves_from_many_threads_are_all_findable() {
+        let repo = SharedInMemoryOrderRepository::new(InMemoryOrderRepository::new());
+
+        let handles: Vec<_> = (0..8)
+            .map(|thread_index| {
+                let repo = repo.clone();
+                thread::spawn(move || {
+                    for n in 0..50 {
+                        repo.insert(&test_order(thread_index * 50 + n + 1)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        for id in 1..=400 {
+            assert!(
+                repo.find(TenantId::default(), OrderId(id))
+                    .unwrap()
+                    .is_some()
+            );
+        }
+        assert_eq!(repo.find_all(TenantId::default()).unwrap().len(), 400);
+    }
+}
@@ -0,0 +1,255 @@
+// =============================================================================
+// Slow Repository - A Database Having a Bad Day
+// =============================================================================
+//
+// The in-memory repository answers in nanoseconds. A real database, across a
+// real network, takes milliseconds, and sometimes seconds. Code that is fine
+// with the first can be wrong with the second: a timeout that fires too early,
+// a lock held across a slow call, a request that takes ten round trips.
+//
+// SlowOrderRepository wraps any repository and waits before every call:
+//
+//     OrderService -> SlowOrderRepository -> InMemoryOrderRepository
+//
+// The wait goes through a sleeper, like RetryingPaymentGateway's:
+// `std::thread::sleep` by default, a closure that only records the delay in
+// unit tests, so they don't actually wait.
+//
+// Each call can also be timed, sleep included, into a Vec shared with the
+// test (or with SlowPaymentGateway, for one report covering both):
+//
+//     let timings = Arc::new(Mutex::new(Vec::new()));
+//     let repo = SlowOrderRepository::new(inner, Duration::from_millis(50))
+//         .with_timings(Arc::clone(&timings));
+//
+// Each entry is the name of the call and how long it took.
+
+use domain::{
+    HealthCheck, HealthStatus, Order, OrderError, OrderId, OrderRepository, RequestContext,
+    TenantId,
+};
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Per-call timings: the name of the call, and how long it took.
+type Timings = Arc<Mutex<Vec<(&'static str, Duration)>>>;
+
+/// Repository decorator adding latency to every call.
+///
+/// `S` is the sleeper. Production-like demos use [`std::thread::sleep`];
+/// tests inject a closure that only records the delay.
+#[derive(Debug)]
+pub struct SlowOrderRepository<R, S = fn(Duration)> {
+    inner: R,
+    delay: Duration,
+    sleeper: S,
+    timings: Option<Timings>,
+}
+
+impl<R: OrderRepository> SlowOrderRepository<R> {
+    /// Wraps `inner`, sleeping the current thread `delay` before every call.
+    #[must_use]
+    pub fn new(inner: R, delay: Duration) -> Self {
+        Self::with_sleeper(inner, delay, std::thread::sleep)
+    }
+}
+
+impl<R: OrderRepository, S: Fn(Duration)> SlowOrderRepository<R, S> {
+    /// Wraps `inner` with a custom sleeper.
+    #[must_use]
+    pub fn with_sleeper(inner: R, delay: Duration, sleeper: S) -> Self {
+        Self {
+            inner,
+            delay,
+            sleeper,
+            timings: None,
+        }
+    }
+
+    /// Records how long each call took, sleep included, into `timings`.
+    #[must_use]
+    pub fn with_timings(mut self, timings: Arc<Mutex<Vec<(&'static str, Duration)>>>) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// The wrapped repository.
+    #[must_use]
+    pub const fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Waits, then makes the call, timing both.
+    fn slowly<T>(&self, call: &'static str, run: impl FnOnce(&R) -> T) -> T {
+        let start = Instant::now();
+        (self.sleeper)(self.delay);
+        let result = run(&self.inner);
+        if let Some(timings) = &self.timings {
+            timings
+                .lock()
+                // A poisoned lock only means another thread panicked; the timings are still fine
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((call, start.elapsed()));
+        }
+        result
+    }
+}
+
+impl<R: OrderRepository, S: Fn(Duration)> OrderRepository for SlowOrderRepository<R, S> {
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.insert_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.update_with_ctx(&RequestContext::default(), order)
+    }
+
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.slowly("update_versioned", |inner| {
+            inner.update_versioned(order, expected)
+        })
+    }
+
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.slowly("delete", |inner| inner.delete(tenant, id))
+    }
+
+    /// One wait for the whole batch: that's what batching is for.
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        self.slowly("save_all", |inner| inner.save_all(orders))
+    }
+
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.slowly("soft_delete", |inner| inner.soft_delete(tenant, id))
+    }
+
+    fn restore(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.slowly("restore", |inner| inner.restore(tenant, id))
+    }
+
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.slowly("find_including_deleted", |inner| {
+            inner.find_including_deleted(tenant, id)
+        })
+    }
+
+    fn find_with(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+        read: &mut dyn FnMut(Option<&Order>),
+    ) -> Result<(), OrderError> {
+        self.slowly("find_with", |inner| inner.find_with(tenant, id, read))
+    }
+
+    fn for_each(
+        &self,
+        tenant: TenantId,
+        visit: &mut dyn FnMut(&Order) -> ControlFlow<()>,
+    ) -> Result<(), OrderError> {
+        self.slowly("for_each", |inner| inner.for_each(tenant, visit))
+    }
+
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        self.slowly("find_many", |inner| inner.find_many(tenant, ids))
+    }
+
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        self.slowly("find_by_item_name", |inner| {
+            inner.find_by_item_name(tenant, query)
+        })
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.find_with_ctx(&RequestContext::default(), tenant, id)
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.slowly("find_all", |inner| inner.find_all(tenant))
+    }
+
+    fn insert_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.slowly("insert", |inner| inner.insert_with_ctx(ctx, order))
+    }
+
+    fn update_with_ctx(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.slowly("update", |inner| inner.update_with_ctx(ctx, order))
+    }
+
+    fn find_with_ctx(
+        &self,
+        ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.slowly("find", |inner| inner.find_with_ctx(ctx, tenant, id))
+    }
+}
+
+impl<R: HealthCheck, S> HealthCheck for SlowOrderRepository<R, S> {
+    // Only as healthy as the repository underneath
+    fn health(&self) -> HealthStatus {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryOrderRepository;
+    use domain::{LineItem, Money};
+    use std::cell::RefCell;
+
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
+            vec![LineItem {
+                name: "Test".to_string(),
+                price: Money(100),
+                weight_grams: None,
+            }],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn every_call_waits_before_going_through() {
+        let delays = RefCell::new(Vec::new());
+        let repo = SlowOrderRepository::with_sleeper(
+            InMemoryOrderRepository::new(),
+            Duration::from_millis(250),
+            |d| delays.borrow_mut().push(d),
+        );
+
+        repo.insert(&test_order(1)).unwrap();
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap();
+
+        assert!(found.is_some());
+        assert_eq!(*delays.borrow(), vec![Duration::from_millis(250); 2]);
+    }
+
+    #[test]
+    fn timings_include_the_delay() {
+        let timings = Arc::new(Mutex::new(Vec::new()));
+        let repo =
+            SlowOrderRepository::new(InMemoryOrderRepository::new(), Duration::from_millis(5))
+                .with_timings(Arc::clone(&timings));
+
+        repo.insert(&test_order(1)).unwrap();
+        repo.find_all(TenantId::default()).unwrap();
+
+        let timings = timings.lock().unwrap();
+        let calls: Vec<&str> = timings.iter().map(|(call, _)| *call).collect();
+        assert_eq!(calls, vec!["insert", "find_all"]);
+        assert!(
+            timings
+                .iter()
+                .all(|(_, elapsed)| *elapsed >= Duration::from_millis(5))
+        );
+    }
+}
@@ -0,0 +1,898 @@
+// =============================================================================
+// SQLite Repository - A Real Database, Embedded
+// =============================================================================
+//
+// PostgresOrderRepository only pretends. This one doesn't: it talks to a real
+// SQLite database through rusqlite, either in a file or in memory (":memory:"),
+// which makes it handy for integration tests and small apps alike.
+//
+// It sits behind the `sqlite` feature, so the default build doesn't compile
+// SQLite at all:
+//
+//     cargo test -p adapters-repository --features sqlite
+//
+// SCHEMA:
+// -------
+// Created on first open, if missing. An order is one row in `orders` plus one
+// row per item in `line_items`, one per status change in `order_history` and
+// one per extra charge in `extra_charges`, keyed by the order (and their
+// position, so they come back in the order they happened):
+//
+//     orders        (tenant_id, id, total, transaction_id, status, refunded,
+//                    customer_id, gift_card_id, gift_card_amount, version,
+//                    merged_from, loyalty_credited, shipping, ship_street,
+//                    ship_city, ship_postal_code, ship_country)
+//     line_items    (tenant_id, order_id, position, name, price, weight_grams)
+//     order_history (tenant_id, order_id, position, from_status, to_status,
+//                    changed_at_ns, reason)
+//     extra_charges (tenant_id, order_id, position, transaction_id, amount)
+//
+// Writing an order touches all four tables, so each write runs in a
+// transaction: nobody ever sees an order with half its items. Times are
+// nanoseconds since the epoch, so they come back exactly as they went in.
+// SQLite has no arrays: `merged_from` is the merged order IDs, comma-separated
+// ("" for none).
+//
+// ERROR TRANSLATION:
+// ------------------
+// rusqlite::Error -> OrderError::StorageFailed, with the original attached:
+// `Error::source` on the OrderError gives back the rusqlite::Error.
+// The one error with a business meaning gets its own variant: a primary key
+// violation on insert is OrderError::DuplicateOrder.
+//
+// The statements run are logged to stdout, or to a `Logger` when one is
+// plugged in: a CLI printing JSON on stdout sends them elsewhere.
+
+use crate::postgres::{nanos_since_epoch, time_from_nanos};
+use domain::{
+    Address, CustomerId, ExtraCharge, GiftCardId, GiftCardPayment, HealthCheck, HealthStatus,
+    HistoryEntry, LineItem, Logger, Money, Order, OrderError, OrderId, OrderRepository,
+    OrderStatus, RequestContext, Shipping, TenantId,
+};
+use rusqlite::{Connection, ErrorCode, OptionalExtension, Row, Transaction, params};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+
+    CREATE TABLE IF NOT EXISTS orders (
+        tenant_id        INTEGER NOT NULL,
+        id               INTEGER NOT NULL,
+        total            INTEGER NOT NULL,
+        transaction_id   TEXT,
+        status           TEXT    NOT NULL,
+        refunded         INTEGER NOT NULL,
+        customer_id      INTEGER,
+        gift_card_id     INTEGER,
+        gift_card_amount INTEGER,
+        version          INTEGER NOT NULL,
+        merged_from      TEXT    NOT NULL DEFAULT '',
+        loyalty_credited INTEGER NOT NULL DEFAULT 0,
+        shipping         INTEGER,
+        ship_street      TEXT,
+        ship_city        TEXT,
+        ship_postal_code TEXT,
+        ship_country     TEXT,
+        PRIMARY KEY (tenant_id, id)
+    );
+
+    CREATE TABLE IF NOT EXISTS line_items (
+        tenant_id    INTEGER NOT NULL,
+        order_id     INTEGER NOT NULL,
+        position     INTEGER NOT NULL,
+        name         TEXT    NOT NULL,
+        price        INTEGER NOT NULL,
+        weight_grams INTEGER,
+        PRIMARY KEY (tenant_id, order_id, position),
+        FOREIGN KEY (tenant_id, order_id) REFERENCES orders (tenant_id, id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS order_history (
+        tenant_id     INTEGER NOT NULL,
+        order_id      INTEGER NOT NULL,
+        position      INTEGER NOT NULL,
+        from_status   TEXT,
+        to_status     TEXT    NOT NULL,
+        changed_at_ns INTEGER NOT NULL,
+        reason        TEXT,
+        PRIMARY KEY (tenant_id, order_id, position),
+        FOREIGN KEY (tenant_id, order_id) REFERENCES orders (tenant_id, id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS extra_charges (
+        tenant_id      INTEGER NOT NULL,
+        order_id       INTEGER NOT NULL,
+        position       INTEGER NOT NULL,
+        transaction_id TEXT    NOT NULL,
+        amount         INTEGER NOT NULL,
+        PRIMARY KEY (tenant_id, order_id, position),
+        FOREIGN KEY (tenant_id, order_id) REFERENCES orders (tenant_id, id) ON DELETE CASCADE
+    );
+";
+
+const SELECT_ORDER: &str = "SELECT tenant_id, id, total, transaction_id, status, refunded, \
+                            customer_id, gift_card_id, gift_card_amount, version, merged_from, \
+                            loyalty_credited, shipping, ship_street, ship_city, \
+                            ship_postal_code, ship_country FROM orders";
+
+/// Order repository backed by SQLite.
+///
+/// A `rusqlite::Connection` can move between threads but not be shared by
+/// them, so it sits behind a Mutex: one query at a time.
+pub struct SqliteOrderRepository {
+    conn: Mutex<Connection>,
+    logger: Option<Arc<dyn Logger + Send + Sync>>,
+}
+
+// Manual impl: the logger is a trait object with no Debug of its own.
+impl fmt::Debug for SqliteOrderRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteOrderRepository")
+            .field("conn", &self.conn)
+            .field("logger", &self.logger.is_some())
+            .finish()
+    }
+}
+
+impl SqliteOrderRepository {
+    /// Opens (or creates) the database file at `path`, creating the tables
+    /// if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the file can't be opened or
+    /// the schema can't be created.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, OrderError> {
+        Self::with_connection(Connection::open(path), None)
+    }
+
+    /// Like [`open`](Self::open), with the log lines going to `logger` from
+    /// the start.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the file can't be opened or
+    /// the schema can't be created.
+    pub fn open_with_logger(
+        path: impl AsRef<Path>,
+        logger: Arc<dyn Logger + Send + Sync>,
+    ) -> Result<Self, OrderError> {
+        Self::with_connection(Connection::open(path), Some(logger))
+    }
+
+    /// Opens a fresh database living in memory only, gone with the repository.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the schema can't be created.
+    pub fn open_in_memory() -> Result<Self, OrderError> {
+        Self::with_connection(Connection::open_in_memory(), None)
+    }
+
+    fn with_connection(
+        conn: rusqlite::Result<Connection>,
+        logger: Option<Arc<dyn Logger + Send + Sync>>,
+    ) -> Result<Self, OrderError> {
+        let conn = conn.map_err(OrderError::storage_failed)?;
+        conn.execute_batch(SCHEMA)
+            .map_err(OrderError::storage_failed)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            logger,
+        })
+    }
+
+    /// Sends the statements run to `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: Arc<dyn Logger + Send + Sync>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    fn log(&self, message: &str) {
+        match &self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        // A poisoned lock only means another thread panicked; the connection is still fine
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Runs `write` in a transaction, committed only if it succeeds.
+    fn in_transaction(
+        &self,
+        write: impl FnOnce(&Transaction<'_>) -> Result<(), OrderError>,
+    ) -> Result<(), OrderError> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(OrderError::storage_failed)?;
+        write(&tx)?;
+        tx.commit().map_err(OrderError::storage_failed)
+    }
+
+    /// The UPDATE shared by `update` and `update_versioned`.
+    ///
+    /// Returns how many rows matched: zero means no such order (or, with
+    /// `expected`, not at that version).
+    fn update_row(
+        &self,
+        tx: &Transaction<'_>,
+        order: &Order,
+        expected: Option<u32>,
+    ) -> Result<usize, OrderError> {
+        let gift = order.gift_card;
+        let shipping = order.shipping.as_ref();
+        let destination = shipping.map(|shipping| &shipping.destination);
+        let updated = tx
+            .execute(
+                "UPDATE orders SET total = ?3, transaction_id = ?4, status = ?5, refunded = ?6, \
+                 customer_id = ?7, gift_card_id = ?8, gift_card_amount = ?9, version = ?10, \
+                 merged_from = ?12, loyalty_credited = ?13, shipping = ?14, ship_street = ?15, \
+                 ship_city = ?16, ship_postal_code = ?17, ship_country = ?18 \
+                 WHERE tenant_id = ?1 AND id = ?2 AND (?11 IS NULL OR version = ?11)",
+                params![
+                    order.tenant.0,
+                    order.id.0,
+                    order.total.0,
+                    order.transaction_id,
+                    order.status.to_string(),
+                    order.refunded.0,
+                    order.customer_id.map(|id| id.0),
+                    gift.map(|gift| gift.card.0),
+                    gift.map(|gift| gift.amount.0),
+                    order.version,
+                    expected,
+                    merged_ids(order),
+                    order.loyalty_credited,
+                    shipping.map(|shipping| shipping.cost.0),
+                    destination.map(Address::street),
+                    destination.map(Address::city),
+                    destination.map(Address::postal_code),
+                    destination.map(Address::country),
+                ],
+            )
+            .map_err(OrderError::storage_failed)?;
+        if updated > 0 {
+            for table in ["line_items", "order_history", "extra_charges"] {
+                tx.execute(
+                    &format!("DELETE FROM {table} WHERE tenant_id = ?1 AND order_id = ?2"),
+                    params![order.tenant.0, order.id.0],
+                )
+                .map_err(OrderError::storage_failed)?;
+            }
+            self.insert_items(tx, order)?;
+            self.insert_history(tx, order)?;
+            self.insert_extra_charges(tx, order)?;
+        }
+        Ok(updated)
+    }
+
+    /// Loads the items, the history and the extra charges of an order row,
+    /// turning it into an [`Order`].
+    fn complete(&self, conn: &Connection, row: OrderRow) -> Result<Order, OrderError> {
+        let mut statement = conn
+            .prepare(
+                "SELECT name, price, weight_grams FROM line_items \
+                 WHERE tenant_id = ?1 AND order_id = ?2 ORDER BY position",
+            )
+            .map_err(OrderError::storage_failed)?;
+        let items = statement
+            .query_map(params![row.tenant.0, row.id.0], |item| {
+                Ok(LineItem {
+                    name: item.get(0)?,
+                    price: Money(item.get(1)?),
+                    weight_grams: item.get(2)?,
+                })
+            })
+            .map_err(OrderError::storage_failed)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(OrderError::storage_failed)?;
+        let history = conn
+            .prepare(
+                "SELECT from_status, to_status, changed_at_ns, reason FROM order_history \
+                 WHERE tenant_id = ?1 AND order_id = ?2 ORDER BY position",
+            )
+            .map_err(OrderError::storage_failed)?
+            .query_map(params![row.tenant.0, row.id.0], |change| {
+                Ok(ChangeRow {
+                    from: change.get(0)?,
+                    to: change.get(1)?,
+                    at_ns: change.get(2)?,
+                    reason: change.get(3)?,
+                })
+            })
+            .map_err(OrderError::storage_failed)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(OrderError::storage_failed)?;
+        let extra_charges = conn
+            .prepare(
+                "SELECT transaction_id, amount FROM extra_charges \
+                 WHERE tenant_id = ?1 AND order_id = ?2 ORDER BY position",
+            )
+            .map_err(OrderError::storage_failed)?
+            .query_map(params![row.tenant.0, row.id.0], |charge| {
+                Ok(ExtraCharge {
+                    transaction_id: charge.get(0)?,
+                    amount: Money(charge.get(1)?),
+                })
+            })
+            .map_err(OrderError::storage_failed)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(OrderError::storage_failed)?;
+        row.into_order(items, history, extra_charges)
+            .map_err(OrderError::storage_failed)
+    }
+
+    /// Inserts the line items of `order`, in order.
+    fn insert_items(&self, tx: &Transaction<'_>, order: &Order) -> Result<(), OrderError> {
+        let mut statement = tx
+            .prepare(
+                "INSERT INTO line_items (tenant_id, order_id, position, name, price, \
+                 weight_grams) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(OrderError::storage_failed)?;
+        for (position, item) in (0u32..).zip(&order.items) {
+            statement
+                .execute(params![
+                    order.tenant.0,
+                    order.id.0,
+                    position,
+                    item.name,
+                    item.price.0,
+                    item.weight_grams
+                ])
+                .map_err(OrderError::storage_failed)?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the status changes of `order`, oldest first.
+    fn insert_history(&self, tx: &Transaction<'_>, order: &Order) -> Result<(), OrderError> {
+        let mut statement = tx
+            .prepare(
+                "INSERT INTO order_history \
+                 (tenant_id, order_id, position, from_status, to_status, changed_at_ns, reason) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .map_err(OrderError::storage_failed)?;
+        for (position, change) in (0u32..).zip(&order.history) {
+            statement
+                .execute(params![
+                    order.tenant.0,
+                    order.id.0,
+                    position,
+                    change.from.map(|status| status.to_string()),
+                    change.to.to_string(),
+                    nanos_since_epoch(change.at),
+                    change.reason,
+                ])
+                .map_err(OrderError::storage_failed)?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the extra charges of `order`, oldest first.
+    fn insert_extra_charges(&self, tx: &Transaction<'_>, order: &Order) -> Result<(), OrderError> {
+        let mut statement = tx
+            .prepare(
+                "INSERT INTO extra_charges (tenant_id, order_id, position, transaction_id, \
+                 amount) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .map_err(OrderError::storage_failed)?;
+        for (position, charge) in (0u32..).zip(&order.extra_charges) {
+            statement
+                .execute(params![
+                    order.tenant.0,
+                    order.id.0,
+                    position,
+                    charge.transaction_id,
+                    charge.amount.0
+                ])
+                .map_err(OrderError::storage_failed)?;
+        }
+        Ok(())
+    }
+}
+
+/// The columns of one `order_history` row.
+struct ChangeRow {
+    from: Option<String>,
+    to: String,
+    at_ns: i64,
+    reason: Option<String>,
+}
+
+impl ChangeRow {
+    fn into_entry(self) -> Result<HistoryEntry, String> {
+        Ok(HistoryEntry {
+            from: self.from.as_deref().map(parse_status).transpose()?,
+            to: parse_status(&self.to)?,
+            at: time_from_nanos(self.at_ns),
+            reason: self.reason,
+        })
+    }
+}
+
+/// The columns of one `orders` row, before its items are loaded.
+struct OrderRow {
+    tenant: TenantId,
+    id: OrderId,
+    total: Money,
+    transaction_id: Option<String>,
+    status: String,
+    refunded: Money,
+    customer_id: Option<CustomerId>,
+    gift_card: Option<GiftCardPayment>,
+    version: u32,
+    merged_from: String,
+    loyalty_credited: u32,
+    shipping: Option<u32>,
+    /// Street, city, postal code and country, when the order ships.
+    ship_to: [Option<String>; 4],
+}
+
+impl OrderRow {
+    /// Reads a row selected with [`SELECT_ORDER`].
+    fn read(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let gift_card_id: Option<u32> = row.get(7)?;
+        let gift_card_amount: Option<u32> = row.get(8)?;
+        Ok(Self {
+            tenant: TenantId(row.get(0)?),
+            id: OrderId(row.get(1)?),
+            total: Money(row.get(2)?),
+            transaction_id: row.get(3)?,
+            status: row.get(4)?,
+            refunded: Money(row.get(5)?),
+            customer_id: row.get::<_, Option<u32>>(6)?.map(CustomerId),
+            gift_card: gift_card_id
+                .zip(gift_card_amount)
+                .map(|(card, amount)| GiftCardPayment {
+                    card: GiftCardId(card),
+                    amount: Money(amount),
+                }),
+            version: row.get(9)?,
+            merged_from: row.get(10)?,
+            loyalty_credited: row.get(11)?,
+            shipping: row.get(12)?,
+            ship_to: [row.get(13)?, row.get(14)?, row.get(15)?, row.get(16)?],
+        })
+    }
+
+    /// The order, or what's wrong with the rows.
+    fn into_order(
+        self,
+        items: Vec<LineItem>,
+        history: Vec<ChangeRow>,
+        extra_charges: Vec<ExtraCharge>,
+    ) -> Result<Order, String> {
+        // An order without items, or with a status we never write, means the
+        // table was changed behind our back: refuse to make up an order
+        if items.is_empty() {
+            return Err(format!("Order #{} has no line items", self.id));
+        }
+        let shipping = match (self.shipping, self.ship_to) {
+            (Some(cost), [Some(street), Some(city), Some(postal_code), Some(country)]) => {
+                Some(Shipping {
+                    destination: Address::new(street, city, postal_code, &country)
+                        .map_err(|_| format!("Order #{} ships nowhere valid", self.id))?,
+                    cost: Money(cost),
+                })
+            }
+            (None, [None, None, None, None]) => None,
+            _ => {
+                return Err(format!(
+                    "Order #{} has half a shipping destination",
+                    self.id
+                ));
+            }
+        };
+        Ok(Order {
+            id: self.id,
+            items,
+            total: self.total,
+            transaction_id: self.transaction_id,
+            status: parse_status(&self.status)?,
+            refunded: self.refunded,
+            customer_id: self.customer_id,
+            tenant: self.tenant,
+            gift_card: self.gift_card,
+            version: self.version,
+            history: history
+                .into_iter()
+                .map(ChangeRow::into_entry)
+                .collect::<Result<_, _>>()?,
+            merged_from: self
+                .merged_from
+                .split(',')
+                .filter(|id| !id.is_empty())
+                .map(|id| {
+                    id.parse()
+                        .map(OrderId)
+                        .map_err(|_| format!("Order #{} was merged from {id:?}", self.id))
+                })
+                .collect::<Result<_, _>>()?,
+            extra_charges,
+            loyalty_credited: self.loyalty_credited,
+            shipping,
+        })
+    }
+}
+
+/// The `merged_from` column: "3,7", or "" for an order nothing was merged into.
+fn merged_ids(order: &Order) -> String {
+    order
+        .merged_from
+        .iter()
+        .map(|id| id.0.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_status(status: &str) -> Result<OrderStatus, String> {
+    match status {
+        "Pending" => Ok(OrderStatus::Pending),
+        "Paid" => Ok(OrderStatus::Paid),
+        "Shipped" => Ok(OrderStatus::Shipped),
+        "Cancelled" => Ok(OrderStatus::Cancelled),
+        "Refunded" => Ok(OrderStatus::Refunded),
+        other => Err(format!("Unknown order status {other:?}")),
+    }
+}
+
+impl OrderRepository for SqliteOrderRepository {
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[SQLite] INSERT INTO orders VALUES ({}, {}, ...)",
+            order.tenant.0, order.id
+        ));
+        self.in_transaction(|tx| {
+            let gift = order.gift_card;
+            let shipping = order.shipping.as_ref();
+            let destination = shipping.map(|shipping| &shipping.destination);
+            tx.execute(
+                "INSERT INTO orders (tenant_id, id, total, transaction_id, status, refunded, \
+                 customer_id, gift_card_id, gift_card_amount, version, merged_from, \
+                 loyalty_credited, shipping, ship_street, ship_city, ship_postal_code, \
+                 ship_country) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+                params![
+                    order.tenant.0,
+                    order.id.0,
+                    order.total.0,
+                    order.transaction_id,
+                    order.status.to_string(),
+                    order.refunded.0,
+                    order.customer_id.map(|id| id.0),
+                    gift.map(|gift| gift.card.0),
+                    gift.map(|gift| gift.amount.0),
+                    order.version,
+                    merged_ids(order),
+                    order.loyalty_credited,
+                    shipping.map(|shipping| shipping.cost.0),
+                    destination.map(Address::street),
+                    destination.map(Address::city),
+                    destination.map(Address::postal_code),
+                    destination.map(Address::country),
+                ],
+            )
+            .map_err(|e| match e.sqlite_error_code() {
+                // Primary key violation: the ID is already taken
+                Some(ErrorCode::ConstraintViolation) => OrderError::DuplicateOrder(order.id),
+                _ => OrderError::storage_failed(e),
+            })?;
+            self.insert_items(tx, order)?;
+            self.insert_history(tx, order)?;
+            self.insert_extra_charges(tx, order)
+        })
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[SQLite] UPDATE orders SET ... WHERE tenant_id = {} AND id = {}",
+            order.tenant.0, order.id
+        ));
+        self.in_transaction(|tx| match self.update_row(tx, order, None)? {
+            0 => Err(OrderError::OrderNotFound(order.id)),
+            _ => Ok(()),
+        })
+    }
+
+    /// Checks the version in the WHERE clause: the check and the write are
+    /// one statement, so no other writer can slip in between.
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        self.log(&format!(
+            "[SQLite] UPDATE orders SET ... WHERE tenant_id = {} AND id = {} AND version = {expected}",
+            order.tenant.0, order.id
+        ));
+        self.in_transaction(|tx| {
+            if self.update_row(tx, order, Some(expected))? > 0 {
+                return Ok(());
+            }
+            // Nothing matched: no such order, or a newer version of it?
+            let found = tx
+                .query_row(
+                    "SELECT version FROM orders WHERE tenant_id = ?1 AND id = ?2",
+                    params![order.tenant.0, order.id.0],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(OrderError::storage_failed)?;
+            Err(match found {
+                Some(found) => OrderError::Conflict {
+                    id: order.id,
+                    expected,
+                    found,
+                },
+                None => OrderError::OrderNotFound(order.id),
+            })
+        })
+    }
+
+    /// Deletes an order and, through `ON DELETE CASCADE`, its items and history.
+    fn delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.log(&format!(
+            "[SQLite] DELETE FROM orders WHERE tenant_id = {} AND id = {id}",
+            tenant.0
+        ));
+        let deleted = self
+            .conn()
+            .execute(
+                "DELETE FROM orders WHERE tenant_id = ?1 AND id = ?2",
+                params![tenant.0, id.0],
+            )
+            .map_err(OrderError::storage_failed)?;
+        Ok(deleted > 0)
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.log(&format!(
+            "[SQLite] SELECT * FROM orders WHERE tenant_id = {} AND id = {id}",
+            tenant.0
+        ));
+        let conn = self.conn();
+        let row = conn
+            .query_row(
+                &format!("{SELECT_ORDER} WHERE tenant_id = ?1 AND id = ?2"),
+                params![tenant.0, id.0],
+                OrderRow::read,
+            )
+            .optional()
+            .map_err(OrderError::storage_failed)?;
+        row.map(|row| self.complete(&conn, row)).transpose()
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.log(&format!(
+            "[SQLite] SELECT * FROM orders WHERE tenant_id = {} ORDER BY id",
+            tenant.0
+        ));
+        let conn = self.conn();
+        let rows = conn
+            .prepare(&format!("{SELECT_ORDER} WHERE tenant_id = ?1 ORDER BY id"))
+            .map_err(OrderError::storage_failed)?
+            .query_map(params![tenant.0], OrderRow::read)
+            .map_err(OrderError::storage_failed)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(OrderError::storage_failed)?;
+        rows.into_iter()
+            .map(|row| self.complete(&conn, row))
+            .collect()
+    }
+}
+
+impl HealthCheck for SqliteOrderRepository {
+    /// Runs `SELECT 1`, and reads the orders table: a file deleted or
+    /// corrupted under us fails one or the other.
+    fn health(&self) -> HealthStatus {
+        let conn = self.conn();
+        let checked = conn
+            .query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+            .and_then(|_| {
+                conn.query_row("SELECT COUNT(*) FROM orders", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+            });
+        match checked {
+            Ok(_) => HealthStatus::Healthy,
+            Err(e) => HealthStatus::Unhealthy(format!("SQLite: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WriterLogger;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn test_order(id: u32) -> Order {
+        Order::new(
+            OrderId(id),
+            vec![
+                LineItem {
+                    name: "Keyboard".to_string(),
+                    price: Money(7999),
+                    weight_grams: Some(850),
+                },
+                LineItem {
+                    name: "Mouse".to_string(),
+                    price: Money(2999),
+                    weight_grams: None,
+                },
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn save_find_find_all_delete() {
+        let repo = SqliteOrderRepository::open_in_memory().unwrap();
+        let mut order = test_order(1);
+        order
+            .mark_paid(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789))
+            .unwrap();
+        order.transaction_id = Some("txn_1".to_string());
+        order.customer_id = Some(CustomerId(7));
+        order.gift_card = Some(GiftCardPayment {
+            card: GiftCardId(3),
+            amount: Money(500),
+        });
+        order.record_refund(Money(100), UNIX_EPOCH).unwrap();
+        repo.insert(&order).unwrap();
+        repo.insert(&test_order(2)).unwrap();
+
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+        assert_eq!(found.items, order.items);
+        assert_eq!(found.total, Money(10998));
+        assert_eq!(found.transaction_id.as_deref(), Some("txn_1"));
+        assert_eq!(found.status, OrderStatus::Paid);
+        assert_eq!(found.refunded, Money(100));
+        assert_eq!(found.customer_id, Some(CustomerId(7)));
+        assert_eq!(found.gift_card, order.gift_card);
+        assert_eq!(found.history, order.history);
+
+        let ids: Vec<OrderId> = repo
+            .find_all(TenantId::default())
+            .unwrap()
+            .iter()
+            .map(|o| o.id)
+            .collect();
+        assert_eq!(ids, vec![OrderId(1), OrderId(2)]);
+
+        assert!(repo.delete(TenantId::default(), OrderId(1)).unwrap());
+        assert!(!repo.delete(TenantId::default(), OrderId(1)).unwrap());
+        assert!(
+            repo.find(TenantId::default(), OrderId(1))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn insert_existing_id_fails() {
+        let repo = SqliteOrderRepository::open_in_memory().unwrap();
+        repo.insert(&test_order(1)).unwrap();
+
+        let result = repo.insert(&test_order(1));
+
+        assert!(matches!(
+            result,
+            Err(OrderError::DuplicateOrder(OrderId(1)))
+        ));
+    }
+
+    #[test]
+    fn update_replaces_items_and_history_and_checks_versions() {
+        let repo = SqliteOrderRepository::open_in_memory().unwrap();
+        repo.insert(&test_order(1)).unwrap();
+        let mut changed = test_order(1);
+        changed.record_placed(UNIX_EPOCH).unwrap();
+        changed
+            .add_item(LineItem {
+                name: "Pad".to_string(),
+                price: Money(999),
+                weight_grams: None,
+            })
+            .unwrap();
+        changed.cancel(UNIX_EPOCH, Some("out of stock")).unwrap();
+        changed.merged_from = vec![OrderId(4), OrderId(12)];
+        changed.extra_charges = vec![ExtraCharge {
+            transaction_id: "txn_pad".to_string(),
+            amount: Money(999),
+        }];
+        changed.loyalty_credited = 9;
+        changed
+            .set_shipping(Some(Shipping {
+                destination: Address::new("1 Main St", "Springfield", "12345", "US").unwrap(),
+                cost: Money(499),
+            }))
+            .unwrap();
+        changed.version = 1;
+
+        repo.update_versioned(&changed, 0).unwrap();
+
+        let found = repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+        assert_eq!(found.items.len(), 3);
+        assert_eq!(found.version, 1);
+        assert_eq!(found.history, changed.history);
+        assert_eq!(found.history[0].from, None);
+        assert_eq!(found.merged_from, [OrderId(4), OrderId(12)]);
+        assert_eq!(found.extra_charges, changed.extra_charges);
+        assert_eq!(found.loyalty_credited, 9);
+        assert_eq!(found.shipping, changed.shipping);
+        assert_eq!(found.total, changed.total);
+        assert!(matches!(
+            repo.update_versioned(&changed, 0),
+            Err(OrderError::Conflict {
+                id: OrderId(1),
+                expected: 0,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            repo.update(&test_order(9)),
+            Err(OrderError::OrderNotFound(OrderId(9)))
+        ));
+    }
+
+    #[test]
+    fn file_database_survives_reopening() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("orders_{}_{nanos}.db", std::process::id()));
+
+        SqliteOrderRepository::open(&path)
+            .unwrap()
+            .insert(&test_order(1))
+            .unwrap();
+        let reopened = SqliteOrderRepository::open(&path).unwrap();
+        let found = reopened.find(TenantId::default(), OrderId(1)).unwrap();
+
+        drop(reopened);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(found.unwrap().items.len(), 2);
+    }
+
+    #[test]
+    fn statements_go_to_the_logger() {
+        let logger = Arc::new(WriterLogger::new(Vec::new()));
+        let repo = SqliteOrderRepository::open_in_memory()
+            .unwrap()
+            .with_logger(logger.clone());
+
+        repo.insert(&test_order(1)).unwrap();
+        repo.find(TenantId::default(), OrderId(1)).unwrap();
+
+        assert_eq!(
+            logger.text(),
+            "  [SQLite] INSERT INTO orders VALUES (0, OrderId(1), ...)\n  \
+             [SQLite] SELECT * FROM orders WHERE tenant_id = 0 AND id = OrderId(1)\n"
+        );
+    }
+
+    #[test]
+    fn a_file_that_cant_be_opened_carries_the_rusqlite_error() {
+        use std::error::Error as _;
+
+        // A directory isn't a database
+        let Err(e) = SqliteOrderRepository::open(std::env::temp_dir()) else {
+            panic!("opened a directory");
+        };
+
+        assert!(matches!(e, OrderError::StorageFailed(_)));
+        let source = e.source().expect("the rusqlite error is attached");
+        assert!(source.downcast_ref::<rusqlite::Error>().is_some());
+        assert!(
+            source
+                .to_string()
+                .starts_with("unable to open database file"),
+            "{source}"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+// =============================================================================
+// In-Memory Store - The HashMap Behind Every In-Memory Repository
+// =============================================================================
+//
+// Orders, customers, and soon refunds and gift cards: each in-memory
+// repository is the same HashMap with a different key and value. Rather than
+// copy it once per entity, they all hold an `InMemoryStore<Id, T>`.
+//
+// The store is a plain data structure: `&mut self` to write, no lock inside.
+// Locking stays with each repository, because only the repository knows what
+// must change together. InMemoryOrderRepository keeps two stores (live orders
+// and soft-deleted ones) behind ONE lock, so an order moving from one to the
+// other is never seen in both, or in neither.
+//
+// What stays in each repository is what makes it a repository for THAT
+// entity: the port it implements, its errors (DuplicateOrder...), its log
+// lines.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// A HashMap of entities by ID, shared by the in-memory repositories.
+#[derive(Clone)]
+pub struct InMemoryStore<Id, T> {
+    items: HashMap<Id, T>,
+}
+
+// Manual impl: #[derive(Default)] would require Id: Default and T: Default
+impl<Id, T> Default for InMemoryStore<Id, T> {
+    fn default() -> Self {
+        Self {
+            items: HashMap::new(),
+        }
+    }
+}
+
+// Manual impl: list the entities, the wrapper adds nothing worth printing
+impl<Id: fmt::Debug, T: fmt::Debug> fmt::Debug for InMemoryStore<Id, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(&self.items).finish()
+    }
+}
+
+impl<Id: Eq + Hash + Copy, T: Clone> InMemoryStore<Id, T> {
+    /// Creates an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `item` under `id`, and returns the item it replaced, if any.
+    pub fn insert(&mut self, id: Id, item: T) -> Option<T> {
+        self.items.insert(id, item)
+    }
+
+    /// The item stored under `id`.
+    #[must_use]
+    pub fn get(&self, id: Id) -> Option<&T> {
+        self.items.get(&id)
+    }
+
+    /// The item stored under `id`, to change in place.
+    pub fn get_mut(&mut self, id: Id) -> Option<&mut T> {
+        self.items.get_mut(&id)
+    }
+
+    /// True if an item is stored under `id`.
+    #[must_use]
+    pub fn contains(&self, id: Id) -> bool {
+        self.items.contains_key(&id)
+    }
+
+    /// Removes the item stored under `id`, and returns it.
+    pub fn remove(&mut self, id: Id) -> Option<T> {
+        self.items.remove(&id)
+    }
+
+    /// A copy of every item, in no particular order.
+    #[must_use]
+    pub fn all(&self) -> Vec<T> {
+        self.items.values().cloned().collect()
+    }
+
+    /// Every item, in no particular order, without copying them.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.items.values()
+    }
+
+    /// Number of items stored.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// True when nothing is stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Customer, CustomerId};
+
+    fn customer(id: u32, name: &str) -> Customer {
+        let email = format!("{}@example.com", name.to_lowercase());
+        Customer::new(CustomerId(id), name, email).unwrap()
+    }
+
+    #[test]
+    fn insert_replaces_and_returns_the_previous_item() {
+        let mut store = InMemoryStore::new();
+
+        assert!(store.insert(CustomerId(1), customer(1, "Alice")).is_none());
+        let replaced = store.insert(CustomerId(1), customer(1, "Alicia"));
+
+        assert_eq!(replaced, Some(customer(1, "Alice")));
+        assert_eq!(store.get(CustomerId(1)), Some(&customer(1, "Alicia")));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn remove_takes_the_item_out() {
+        let mut store = InMemoryStore::new();
+        store.insert(CustomerId(1), customer(1, "Alice"));
+        store.insert(CustomerId(2), customer(2, "Bob"));
+
+        assert_eq!(store.remove(CustomerId(1)), Some(customer(1, "Alice")));
+        assert_eq!(store.remove(CustomerId(1)), None);
+        assert!(!store.contains(CustomerId(1)));
+        assert_eq!(store.all(), vec![customer(2, "Bob")]);
+    }
+
+    #[test]
+    fn works_with_any_copyable_key() {
+        // Composite keys are how the order repository partitions by tenant
+        let mut store: InMemoryStore<(u32, char), &str> = InMemoryStore::new();
+        store.insert((1, 'a'), "first");
+        store.insert((2, 'a'), "second");
+
+        assert_eq!(store.get((2, 'a')), Some(&"second"));
+        assert_eq!(store.get((3, 'a')), None);
+        assert!(!store.is_empty());
+    }
+}
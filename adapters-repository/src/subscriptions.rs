@@ -0,0 +1,110 @@
+// =============================================================================
+// In-Memory Subscription Repository - Standing Orders
+// =============================================================================
+//
+// The `SubscriptionRepository` port over the same InMemoryStore as the
+// customers, keyed by SubscriptionId. `find_due` is a scan: fine for a
+// HashMap, and in SQL an index on `next_run` does the same job.
+
+use crate::InMemoryStore;
+use domain::{OrderError, Subscription, SubscriptionId, SubscriptionRepository};
+use std::sync::{PoisonError, RwLock};
+use std::time::SystemTime;
+
+/// In-memory subscription repository for testing scenarios.
+#[derive(Debug, Default)]
+pub struct InMemorySubscriptionRepository {
+    subscriptions: RwLock<InMemoryStore<SubscriptionId, Subscription>>,
+}
+
+impl InMemorySubscriptionRepository {
+    /// Creates a new empty in-memory subscription repository.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SubscriptionRepository for InMemorySubscriptionRepository {
+    fn save(&self, subscription: &Subscription) -> Result<(), OrderError> {
+        println!("  [InMemory] Saving subscription #{}", subscription.id.0);
+        self.subscriptions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(subscription.id, subscription.clone());
+        Ok(())
+    }
+
+    fn find(&self, id: SubscriptionId) -> Result<Option<Subscription>, OrderError> {
+        println!("  [InMemory] Finding subscription #{}", id.0);
+        let subscriptions = self
+            .subscriptions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(subscriptions.get(id).cloned())
+    }
+
+    fn find_due(&self, now: SystemTime) -> Result<Vec<Subscription>, OrderError> {
+        let subscriptions = self
+            .subscriptions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut due: Vec<Subscription> = subscriptions
+            .values()
+            .filter(|subscription| subscription.is_due(now))
+            .cloned()
+            .collect();
+        // HashMap order is random: oldest subscription first, every time
+        due.sort_by_key(|subscription| subscription.id.0);
+        Ok(due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{CustomerId, LineItem, Money};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn subscription(id: u32, first_run: SystemTime) -> Subscription {
+        let items = vec![LineItem {
+            name: "Coffee".to_string(),
+            price: Money(1200),
+            weight_grams: None,
+        }];
+        Subscription::new(SubscriptionId(id), CustomerId(1), items, 30, first_run).unwrap()
+    }
+
+    #[test]
+    fn save_and_find_subscription() {
+        let repo = InMemorySubscriptionRepository::new();
+        let saved = subscription(1, UNIX_EPOCH);
+
+        repo.save(&saved).unwrap();
+
+        assert_eq!(repo.find(SubscriptionId(1)).unwrap(), Some(saved));
+        assert_eq!(repo.find(SubscriptionId(9)).unwrap(), None);
+    }
+
+    #[test]
+    fn find_due_skips_future_and_cancelled_subscriptions() {
+        let repo = InMemorySubscriptionRepository::new();
+        let now = UNIX_EPOCH + Duration::from_secs(100);
+        let mut cancelled = subscription(3, UNIX_EPOCH);
+        cancelled.cancel();
+        repo.save(&subscription(2, now)).unwrap();
+        repo.save(&subscription(1, UNIX_EPOCH)).unwrap();
+        repo.save(&cancelled).unwrap();
+        repo.save(&subscription(4, now + Duration::from_secs(1)))
+            .unwrap();
+
+        let due: Vec<_> = repo
+            .find_due(now)
+            .unwrap()
+            .iter()
+            .map(|subscription| subscription.id)
+            .collect();
+
+        assert_eq!(due, [SubscriptionId(1), SubscriptionId(2)]);
+    }
+}
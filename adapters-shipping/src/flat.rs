@@ -0,0 +1,94 @@
+// =============================================================================
+// Flat Rate Shipping - One Price per Parcel
+// =============================================================================
+//
+// The simplest tariff there is: every order ships for the same price, one
+// item or fifty, a feather or a piano. Shops like it because customers can
+// see the shipping cost before they start filling their cart.
+//
+// The destination may still matter: `with_rate_for` sets another price for
+// one country (abroad costs more), and every other country pays the base
+// rate.
+
+use domain::{Address, LineItem, Money, OrderError, ShippingCalculator};
+use std::collections::HashMap;
+
+/// Shipping calculator charging one price per order, per country.
+#[derive(Debug, Clone)]
+pub struct FlatRateShipping {
+    rate: Money,
+    by_country: HashMap<String, Money>,
+}
+
+impl FlatRateShipping {
+    /// Creates a calculator charging `rate` for any order, anywhere.
+    #[must_use]
+    pub fn new(rate: Money) -> Self {
+        Self {
+            rate,
+            by_country: HashMap::new(),
+        }
+    }
+
+    /// Charges `rate` instead for orders shipping to `country`, an ISO 3166
+    /// code like [`Address::country`] (`"FR"`).
+    #[must_use]
+    pub fn with_rate_for(mut self, country: &str, rate: Money) -> Self {
+        self.by_country.insert(country.to_ascii_uppercase(), rate);
+        self
+    }
+}
+
+impl ShippingCalculator for FlatRateShipping {
+    fn cost(&self, _items: &[LineItem], destination: &Address) -> Result<Money, OrderError> {
+        Ok(self
+            .by_country
+            .get(destination.country())
+            .copied()
+            .unwrap_or(self.rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(country: &str) -> Address {
+        Address::new("1 Main St", "Springfield", "12345", country).unwrap()
+    }
+
+    fn item(weight_grams: Option<u32>) -> LineItem {
+        LineItem {
+            name: "Mug".to_string(),
+            price: Money(1200),
+            weight_grams,
+        }
+    }
+
+    #[test]
+    fn every_order_costs_the_same_whatever_it_weighs() {
+        let shipping = FlatRateShipping::new(Money(599));
+
+        let light = shipping.cost(&[item(Some(0))], &address("US")).unwrap();
+        let heavy = shipping
+            .cost(&[item(Some(30_000)), item(None)], &address("US"))
+            .unwrap();
+
+        assert_eq!(light, Money(599));
+        assert_eq!(heavy, Money(599));
+    }
+
+    #[test]
+    fn a_country_can_have_its_own_rate() {
+        let shipping = FlatRateShipping::new(Money(599)).with_rate_for("fr", Money(1499));
+
+        assert_eq!(
+            shipping.cost(&[item(None)], &address("FR")).unwrap(),
+            Money(1499)
+        );
+        assert_eq!(
+            shipping.cost(&[item(None)], &address("DE")).unwrap(),
+            Money(599)
+        );
+    }
+}
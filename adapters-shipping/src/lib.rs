@@ -0,0 +1,33 @@
+// =============================================================================
+// ADAPTERS-SHIPPING CRATE - What Shipping Costs
+// =============================================================================
+//
+// This crate provides concrete implementations of the `ShippingCalculator`
+// port. We have two adapters:
+// - FlatRateShipping: One price per parcel, whatever it weighs (per country
+//   if you like)
+// - TieredByWeightShipping: A price per weight bracket, like a postal tariff
+//
+// The application asks for a cost when the recipient has an address, and
+// adds it to the order as its own line ("Shipping") before charging. The
+// calculators only quote: they never see the order, and never change it.
+//
+// MISSING WEIGHTS:
+// ----------------
+// `LineItem::weight_grams` is optional: nobody weighed the old catalog. An
+// item without a weight counts as a default weight, which the shop picks
+// (`with_default_weight`). Counting it as nothing would ship a cast-iron pan
+// at the price of a postcard.
+//
+// REAL-WORLD CONSIDERATIONS:
+// --------------------------
+// In a production app, this crate would call a carrier's rate API (UPS,
+// DHL, EasyPost...) with the parcel's weight, its dimensions, and both
+// addresses, and translate "we don't ship there" into a domain error. The
+// tables here are what that API would answer, fixed in advance.
+
+mod flat;
+mod tiered;
+
+pub use flat::FlatRateShipping;
+pub use tiered::{DEFAULT_ITEM_WEIGHT_GRAMS, TieredByWeightShipping};
@@ -0,0 +1,155 @@
+// =============================================================================
+// Tiered by Weight Shipping - A Price per Weight Bracket
+// =============================================================================
+//
+// A postal tariff: up to 500 g costs this, up to 2 kg costs that, up to
+// 10 kg costs more, and past the last bracket the carrier won't take it.
+//
+//     let shipping = TieredByWeightShipping::new(vec![
+//         (500, Money(499)),     // up to 500 g:   $4.99
+//         (2_000, Money(899)),   // up to 2 kg:    $8.99
+//         (10_000, Money(1999)), // up to 10 kg:  $19.99
+//     ]);
+//
+// A bracket includes its upper bound: exactly 500 g is still $4.99. An
+// order weighing nothing at all (gift cards, downloads) has nothing to
+// ship, and ships free.
+//
+// The weight is the sum of the items' weights, with the default weight for
+// items that have none.
+
+use domain::{Address, LineItem, Money, OrderError, ShippingCalculator};
+
+/// What an item without a weight counts as, unless the shop picks another
+/// default: 500 g.
+pub const DEFAULT_ITEM_WEIGHT_GRAMS: u32 = 500;
+
+/// Shipping calculator charging by the order's total weight.
+#[derive(Debug, Clone)]
+pub struct TieredByWeightShipping {
+    /// `(up to grams, cost)`, lightest first.
+    tiers: Vec<(u32, Money)>,
+    default_weight_grams: u32,
+}
+
+impl TieredByWeightShipping {
+    /// Creates a calculator from `(up to grams, cost)` brackets, in any
+    /// order. Items without a weight count as [`DEFAULT_ITEM_WEIGHT_GRAMS`].
+    #[must_use]
+    pub fn new(mut tiers: Vec<(u32, Money)>) -> Self {
+        tiers.sort_by_key(|&(up_to, _)| up_to);
+        Self {
+            tiers,
+            default_weight_grams: DEFAULT_ITEM_WEIGHT_GRAMS,
+        }
+    }
+
+    /// Counts items without a weight as `grams` each.
+    #[must_use]
+    pub const fn with_default_weight(mut self, grams: u32) -> Self {
+        self.default_weight_grams = grams;
+        self
+    }
+
+    /// The weight of `items`, defaults included. Saturates rather than
+    /// overflow: 4,000 tonnes is past any bracket anyway.
+    fn weight_of(&self, items: &[LineItem]) -> u32 {
+        items.iter().fold(0, |total: u32, item| {
+            total.saturating_add(item.weight_grams.unwrap_or(self.default_weight_grams))
+        })
+    }
+}
+
+impl ShippingCalculator for TieredByWeightShipping {
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if the order is heavier than the
+    /// last bracket.
+    fn cost(&self, items: &[LineItem], _destination: &Address) -> Result<Money, OrderError> {
+        let weight = self.weight_of(items);
+        if weight == 0 {
+            return Ok(Money::ZERO);
+        }
+        self.tiers
+            .iter()
+            .find(|&&(up_to, _)| weight <= up_to)
+            .map(|&(_, cost)| cost)
+            .ok_or(OrderError::InvalidOrder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tariff() -> TieredByWeightShipping {
+        // Out of order on purpose: new() sorts them
+        TieredByWeightShipping::new(vec![
+            (2_000, Money(899)),
+            (500, Money(499)),
+            (10_000, Money(1999)),
+        ])
+    }
+
+    fn weighing(grams: &[Option<u32>]) -> Vec<LineItem> {
+        grams
+            .iter()
+            .map(|&weight_grams| LineItem {
+                name: "Parcel".to_string(),
+                price: Money(1000),
+                weight_grams,
+            })
+            .collect()
+    }
+
+    fn home() -> Address {
+        Address::new("1 Main St", "Springfield", "12345", "US").unwrap()
+    }
+
+    #[test]
+    fn brackets_include_their_upper_bound() {
+        let cost = |grams| tariff().cost(&weighing(&[Some(grams)]), &home()).unwrap();
+
+        assert_eq!(cost(1), Money(499));
+        assert_eq!(cost(500), Money(499));
+        assert_eq!(cost(501), Money(899));
+        assert_eq!(cost(2_000), Money(899));
+        assert_eq!(cost(2_001), Money(1999));
+        assert_eq!(cost(10_000), Money(1999));
+    }
+
+    #[test]
+    fn past_the_last_bracket_it_does_not_ship() {
+        assert!(matches!(
+            tariff().cost(&weighing(&[Some(6_000), Some(4_001)]), &home()),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert!(matches!(
+            tariff().cost(&weighing(&[Some(u32::MAX), Some(1)]), &home()),
+            Err(OrderError::InvalidOrder)
+        ));
+    }
+
+    #[test]
+    fn weightless_orders_ship_free() {
+        let cost = tariff()
+            .cost(&weighing(&[Some(0), Some(0)]), &home())
+            .unwrap();
+
+        assert_eq!(cost, Money::ZERO);
+    }
+
+    #[test]
+    fn missing_weights_count_as_the_default() {
+        let items = weighing(&[Some(100), None]);
+
+        // 100 g + 500 g
+        assert_eq!(tariff().cost(&items, &home()).unwrap(), Money(899));
+        // 100 g + 300 g
+        let lighter = tariff().with_default_weight(300);
+        assert_eq!(lighter.cost(&items, &home()).unwrap(), Money(499));
+        // Weightless by default: only the 100 g count
+        let none = tariff().with_default_weight(0);
+        assert_eq!(none.cost(&weighing(&[None]), &home()).unwrap(), Money::ZERO);
+    }
+}
@@ -0,0 +1,964 @@
+// =============================================================================
+// CLI - Driving the Service From a Terminal
+// =============================================================================
+//
+// main.rs without arguments runs the canned demo. With a subcommand, it's a
+// small command-line client of the same OrderService:
+//
+//     app place --item "Book:49.99" --item "Pen:1.99"
+//     app get 1
+//     app list --page 2
+//     app cancel 1
+//     app seed --count 100 --seed 7
+//     app doctor
+//
+// The CLI is just another DRIVING adapter, like an HTTP handler would be: it
+// turns arguments into DTOs, calls the use case, and turns the OrderDto that
+// comes back into text. It never touches Order's fields.
+//
+// Each run is a new process, so the orders live in a SQLite file (`--db`,
+// `orders.db` by default) and survive from one command to the next. Payment
+// is the mock gateway, the confirmation goes to the console.
+//
+// OUTPUT:
+// -------
+// The result goes to stdout, readable by a human, or as JSON with
+// `--output json` (`--json` for short) for scripts. Everything else (the
+// adapters' log lines through the Logger port, the confirmation) goes to
+// stderr, so `app --output json list | jq` sees nothing but JSON.
+//
+// `--quiet` silences stderr (the confirmation and the log lines), and
+// `--verbose` adds the confirmation's whole receipt to it. Stdout stays the
+// same either way. In a terminal, the order IDs on stderr are in bold.
+//
+// In JSON, every run prints exactly one document: the OrderDto, the page,
+// the seeded IDs, the health report... or, when it fails, the error with
+// its stable code (see `OrderError::code`):
+//
+//     {"error":{"code":"order_not_found","message":"OrderNotFound(42)"}}
+//
+// Arguments the CLI can't parse are `invalid_arguments`. Scripts should
+// match on the code: the message is for humans, and may change.
+//
+// EXIT CODES:
+// -----------
+// A script wants to know what kind of failure it got, without parsing text:
+//
+//     0  success
+//     2  the request was wrong: bad arguments, invalid order, unknown ID...
+//     3  the payment failed
+//     4  the infrastructure failed: storage, notification
+//
+// Retrying a 2 is pointless; retrying a 4 later may well work. `doctor`
+// exits with 4 too when an adapter is unhealthy: a degraded one still
+// serves, so it's a success.
+
+use crate::seed::seed_orders;
+use adapters_notification::{ConsoleSender, Verbosity};
+use adapters_payment::MockPaymentGateway;
+use adapters_repository::{SequentialIdGenerator, SqliteOrderRepository, WriterLogger};
+use application::{HealthReport, OrderDto, OrderQueries, OrderService, PlaceOrderRequest};
+use domain::{
+    Contact, HealthCheck, HealthStatus, LineItem, OrderError, OrderId, OrderRepository,
+    PaymentGateway, Recipient, Sender,
+};
+use serde::{Serialize, Serializer};
+use std::error::Error as _;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How many orders `list` shows per page.
+pub const PAGE_SIZE: usize = 10;
+
+/// The seed `seed` generates its orders from, without `--seed`.
+pub const DEFAULT_SEED: u64 = 42;
+
+/// What `--help`, and every usage error, prints.
+pub const USAGE: &str = "\
+Usage: app [--output json|text] [--quiet|--verbose] [--db PATH] <command>
+       app --scenario happy|payment-declined|storage-down|notification-down|everything-down
+
+Commands:
+  place --item NAME:PRICE [--item NAME:PRICE]... [--name NAME] [--email ADDRESS]
+  get <id>
+  list [--page N]
+  cancel <id> [--name NAME] [--email ADDRESS]
+  seed [--count N] [--seed S]
+                         place N made-up orders (default: 10), the same ones
+                         for the same S (default: 42)
+  doctor                 check the database, the payment gateway and the sender
+
+Options:
+  --output     json: print one JSON document, errors included (default: text)
+  --json       short for --output json
+  --quiet      nothing on stderr: no confirmation, no log lines
+  --verbose    the confirmation's whole receipt on stderr too
+  --db PATH    the SQLite database holding the orders (default: orders.db)
+  --name       who gets the notification (default: Ferris)
+  --email      where it goes (default: ferris@rustacean.net)";
+
+/// The code of a [`UsageError`] in JSON, next to `OrderError`'s.
+pub const USAGE_ERROR_CODE: &str = "invalid_arguments";
+
+/// One subcommand, with its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Places an order for these items.
+    Place(Vec<LineItem>),
+    Get(OrderId),
+    /// Lists the orders of one page, counted from 1.
+    List {
+        page: usize,
+    },
+    Cancel(OrderId),
+    /// Places this many made-up orders, generated from `rng_seed`.
+    Seed {
+        count: usize,
+        rng_seed: u64,
+    },
+    /// Checks the health of the adapters.
+    Doctor,
+}
+
+/// How the outcome is printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// For a human.
+    #[default]
+    Text,
+    /// One JSON document, for scripts.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = UsageError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(UsageError(format!(
+                "unknown output {other:?}: json or text"
+            ))),
+        }
+    }
+}
+
+/// Everything the command line asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub command: Command,
+    pub output: OutputFormat,
+    /// How much goes to stderr.
+    pub verbosity: Verbosity,
+    /// The SQLite file the orders are kept in.
+    pub db: PathBuf,
+    /// Who gets the confirmation (of `place`) or the cancellation notice.
+    pub name: String,
+    pub email: String,
+}
+
+/// Arguments the CLI can't make sense of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// What a command gives back, before it's printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Order(OrderDto),
+    Page {
+        page: usize,
+        orders: Vec<OrderDto>,
+    },
+    /// The IDs of the orders `seed` placed.
+    Seeded(Vec<OrderId>),
+    /// What `doctor` found.
+    Health(HealthReport),
+}
+
+/// Parses the arguments, program name excluded.
+///
+/// Options may come before or after the subcommand.
+///
+/// # Errors
+///
+/// Returns a [`UsageError`] saying what's wrong with the arguments.
+pub fn parse(args: &[String]) -> Result<Invocation, UsageError> {
+    let mut output = OutputFormat::Text;
+    let mut verbosity = Verbosity::Normal;
+    let mut db = PathBuf::from("orders.db");
+    let mut name = "Ferris".to_string();
+    let mut email = "ferris@rustacean.net".to_string();
+    let mut items = Vec::new();
+    let mut page = None;
+    let mut count = None;
+    let mut rng_seed = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| UsageError(format!("{arg} needs a value")))
+        };
+        match arg.as_str() {
+            "--json" => output = OutputFormat::Json,
+            "--output" => output = value()?.parse()?,
+            "--quiet" | "--verbose" => verbosity = verbosity_flag(arg).unwrap_or_default(),
+            "--db" => db = PathBuf::from(value()?),
+            "--name" => name = value()?,
+            "--email" => email = value()?,
+            "--item" => items.push(parse_item(&value()?)?),
+            "--page" => page = Some(parse_number(&value()?, "page")?),
+            "--count" => count = Some(parse_number(&value()?, "count")?),
+            "--seed" => rng_seed = Some(parse_number(&value()?, "seed")?),
+            option if option.starts_with("--") => {
+                return Err(UsageError(format!("unknown option {option}")));
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let command = match positional.as_slice() {
+        ["place"] if items.is_empty() => {
+            return Err(UsageError("place needs at least one --item".to_string()));
+        }
+        ["place"] => Command::Place(items),
+        ["get", id] => Command::Get(parse_number(id, "order ID")?),
+        ["list"] => Command::List {
+            page: page.unwrap_or(1),
+        },
+        ["cancel", id] => Command::Cancel(parse_number(id, "order ID")?),
+        ["seed"] => Command::Seed {
+            count: count.unwrap_or(10),
+            rng_seed: rng_seed.unwrap_or(DEFAULT_SEED),
+        },
+        ["doctor"] => Command::Doctor,
+        [] => return Err(UsageError("no command given".to_string())),
+        other => return Err(UsageError(format!("unknown command {:?}", other.join(" ")))),
+    };
+    if page == Some(0) {
+        return Err(UsageError("pages are counted from 1".to_string()));
+    }
+    Ok(Invocation {
+        command,
+        output,
+        verbosity,
+        db,
+        name,
+        email,
+    })
+}
+
+/// The verbosity of the demo, when `args` ask for it: no command, nothing
+/// but `--quiet` or `--verbose` (the last one wins), or nothing at all.
+#[must_use]
+pub fn demo_verbosity(args: &[String]) -> Option<Verbosity> {
+    args.iter()
+        .try_fold(Verbosity::Normal, |_, arg| verbosity_flag(arg))
+}
+
+/// The verbosity `arg` asks for, if it's `--quiet` or `--verbose`.
+fn verbosity_flag(arg: &str) -> Option<Verbosity> {
+    match arg {
+        "--quiet" => Some(Verbosity::Quiet),
+        "--verbose" => Some(Verbosity::Verbose),
+        _ => None,
+    }
+}
+
+/// The output format `args` ask for, even when they can't be parsed: a
+/// script asking for JSON gets its usage error in JSON too.
+#[must_use]
+pub fn requested_output(args: &[String]) -> OutputFormat {
+    let mut output = OutputFormat::Text;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => output = OutputFormat::Json,
+            "--output" => {
+                if let Some(Ok(format)) = args.next().map(|value| value.parse()) {
+                    output = format;
+                }
+            }
+            _ => {}
+        }
+    }
+    output
+}
+
+/// `"Book:49.99"` as a name and a price. The price comes after the last
+/// colon, so a name may hold colons of its own.
+fn parse_item(item: &str) -> Result<LineItem, UsageError> {
+    item.parse().map_err(|e| UsageError(format!("item {e}")))
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, what: &str) -> Result<T, UsageError> {
+    text.parse()
+        .map_err(|_| UsageError(format!("{text:?} isn't a valid {what}")))
+}
+
+/// Runs `command` against the service, or the queries for the read-only ones.
+///
+/// # Errors
+///
+/// Returns the use case's error: [`exit_code`] tells what kind it is.
+pub fn run<R, P, N>(
+    command: &Command,
+    recipient: &Recipient,
+    service: &OrderService<'_, R, P, N>,
+    queries: &OrderQueries<'_, R>,
+) -> Result<Output, OrderError>
+where
+    R: OrderRepository + HealthCheck,
+    P: PaymentGateway + HealthCheck,
+    N: Sender + HealthCheck,
+{
+    match command {
+        Command::Place(items) => {
+            let request = PlaceOrderRequest {
+                recipient_name: recipient.name().to_string(),
+                recipient_email: match recipient.contact() {
+                    Contact::Email(address) => address.clone(),
+                    _ => return Err(OrderError::InvalidRecipient),
+                },
+                items: items
+                    .iter()
+                    .map(|item| (item.name.clone(), i64::from(item.price.0), 1))
+                    .collect(),
+            };
+            service.place_order_dto(&request).map(Output::Order)
+        }
+        Command::Get(id) => service
+            .get_order_dto(id.0)?
+            .map(Output::Order)
+            .ok_or(OrderError::OrderNotFound(*id)),
+        Command::List { page } => {
+            let orders = queries.list_orders()?;
+            Ok(Output::Page {
+                page: *page,
+                orders: orders
+                    .iter()
+                    .skip((page - 1) * PAGE_SIZE)
+                    .take(PAGE_SIZE)
+                    .map(OrderDto::from)
+                    .collect(),
+            })
+        }
+        Command::Cancel(id) => service
+            .cancel_order(*id, recipient)
+            .map(|order| Output::Order(OrderDto::from(&order))),
+        Command::Seed { count, rng_seed } => {
+            seed_orders(service, recipient, *count, *rng_seed).map(Output::Seeded)
+        }
+        Command::Doctor => Ok(Output::Health(service.health_report())),
+    }
+}
+
+/// The exit code telling a script what kind of failure `error` is.
+///
+/// Every variant is listed: a new one has to be given a code.
+#[must_use]
+pub const fn exit_code(error: &OrderError) -> u8 {
+    match error {
+        OrderError::InvalidOrder
+        | OrderError::OrderNotFound(_)
+        | OrderError::InvalidRecipient
+        | OrderError::InvalidLineItem
+        | OrderError::InvalidStatus(_)
+        | OrderError::InvalidRefund
+        | OrderError::InvalidCustomer
+        | OrderError::CustomerNotFound(_)
+        | OrderError::GiftCardNotFound(_)
+        | OrderError::SubscriptionNotFound(_)
+        | OrderError::DuplicateSubmission { .. }
+        | OrderError::UnknownProduct(_)
+        | OrderError::NoExchangeRate { .. } => 2,
+        OrderError::PaymentFailed(_) => 3,
+        OrderError::StorageFailed(_)
+        | OrderError::NotificationFailed(_)
+        | OrderError::DuplicateOrder(_)
+        | OrderError::StorageFailedAfterCharge { .. }
+        | OrderError::NotificationFailedAfterCharge { .. }
+        | OrderError::Conflict { .. } => 4,
+    }
+}
+
+/// The kind of failure `error` is, as its [`exit_code`] tells it:
+/// `request`, `payment` or `infrastructure`.
+#[must_use]
+pub const fn category(error: &OrderError) -> &'static str {
+    match exit_code(error) {
+        3 => "payment",
+        4 => "infrastructure",
+        _ => "request",
+    }
+}
+
+/// `output` as text for a human, or as JSON.
+#[must_use]
+pub fn render(output: &Output, format: OutputFormat) -> String {
+    match (output, format) {
+        (Output::Order(order), OutputFormat::Text) => render_order(order),
+        (Output::Order(order), OutputFormat::Json) => json(&OrderJson::from(order)),
+        (Output::Page { page, orders }, OutputFormat::Text) if orders.is_empty() => {
+            format!("No orders on page {page}.\n")
+        }
+        (Output::Page { orders, .. }, OutputFormat::Text) => {
+            orders.iter().map(render_order).collect()
+        }
+        (Output::Page { page, orders }, OutputFormat::Json) => json(&PageJson {
+            page: *page,
+            orders: orders.iter().map(OrderJson::from).collect(),
+        }),
+        (Output::Seeded(ids), OutputFormat::Text) => {
+            let ids: Vec<String> = ids.iter().map(|id| format!("#{}", id.0)).collect();
+            format!("Seeded {} orders: {}\n", ids.len(), ids.join(", "))
+        }
+        (Output::Seeded(ids), OutputFormat::Json) => json(&SeededJson {
+            seeded: ids.iter().map(|id| id.0).collect(),
+        }),
+        (Output::Health(report), OutputFormat::Text) => {
+            let mut text = String::new();
+            for (name, status) in report.components() {
+                text.push_str(&format!("{name:<12}{status}\n"));
+            }
+            text.push_str(&format!("{:<12}{}\n", "overall", report.overall()));
+            text
+        }
+        (Output::Health(report), OutputFormat::Json) => {
+            let overall = report.overall();
+            json(&HealthJson {
+                overall: StatusJson::from(&overall),
+                components: Components(
+                    report
+                        .components()
+                        .iter()
+                        .map(|(name, status)| (*name, StatusJson::from(*status)))
+                        .collect(),
+                ),
+            })
+        }
+    }
+}
+
+/// `error` as text for a human, or as JSON.
+#[must_use]
+pub fn render_error(error: &OrderError, format: OutputFormat) -> String {
+    match format {
+        // The adapter's own error, when it attached one, says what went wrong
+        OutputFormat::Text => match error.source() {
+            Some(source) => format!("Error: {error} ({source})"),
+            None => format!("Error: {error}"),
+        },
+        OutputFormat::Json => json(&ErrorJson::new(error.code(), &error.to_string())),
+    }
+}
+
+/// `error`, and the usage, as text for a human; as JSON, the error alone.
+#[must_use]
+pub fn render_usage_error(error: &UsageError, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => format!("{error}\n\n{USAGE}"),
+        OutputFormat::Json => json(&ErrorJson::new(USAGE_ERROR_CODE, &error.0)),
+    }
+}
+
+fn render_order(order: &OrderDto) -> String {
+    let mut text = format!(
+        "Order #{} ({}), total {}\n",
+        order.id, order.status, order.total
+    );
+    for item in &order.items {
+        text.push_str(&format!("  {item}\n"));
+    }
+    for warning in &order.warnings {
+        text.push_str(&format!("  Warning: {warning}\n"));
+    }
+    text
+}
+
+// -----------------------------------------------------------------------------
+// JSON documents
+// -----------------------------------------------------------------------------
+//
+// One small struct per document, borrowing from what it shows, and serde
+// writing it: the escaping of a name with quotes or newlines in it is
+// serde_json's problem, not ours.
+
+/// `document` as one line of JSON.
+fn json(document: &impl Serialize) -> String {
+    // Plain structs of strings and numbers: there is nothing serde can refuse
+    serde_json::to_string(document).expect("a CLI document always serializes")
+}
+
+#[derive(Serialize)]
+struct OrderJson<'a> {
+    id: u32,
+    status: &'a str,
+    total: &'a str,
+    items: &'a [String],
+    warnings: &'a [String],
+}
+
+impl<'a> From<&'a OrderDto> for OrderJson<'a> {
+    fn from(order: &'a OrderDto) -> Self {
+        Self {
+            id: order.id,
+            status: &order.status,
+            total: &order.total,
+            items: &order.items,
+            warnings: &order.warnings,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PageJson<'a> {
+    page: usize,
+    orders: Vec<OrderJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct SeededJson {
+    seeded: Vec<u32>,
+}
+
+/// `{"overall": ..., "repository": ..., ...}`: overall first, then one
+/// field per component.
+#[derive(Serialize)]
+struct HealthJson<'a> {
+    overall: StatusJson<'a>,
+    #[serde(flatten)]
+    components: Components<'a>,
+}
+
+/// The components, as fields, in the report's order rather than sorted.
+struct Components<'a>(Vec<(&'a str, StatusJson<'a>)>);
+
+// Manual impl: a derived one would write a list of pairs, not fields.
+impl Serialize for Components<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(name, status)| (name, status)))
+    }
+}
+
+/// `{"status":"healthy"}`, or `{"status":"degraded","reason":"..."}`.
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum StatusJson<'a> {
+    Healthy,
+    Degraded { reason: &'a str },
+    Unhealthy { reason: &'a str },
+}
+
+impl<'a> From<&'a HealthStatus> for StatusJson<'a> {
+    fn from(status: &'a HealthStatus) -> Self {
+        match status {
+            HealthStatus::Healthy => Self::Healthy,
+            HealthStatus::Degraded(reason) => Self::Degraded { reason },
+            HealthStatus::Unhealthy(reason) => Self::Unhealthy { reason },
+        }
+    }
+}
+
+/// `{"error":{"code":"...","message":"..."}}`.
+#[derive(Serialize)]
+struct ErrorJson<'a> {
+    error: ErrorFields<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorFields<'a> {
+    code: &'a str,
+    message: &'a str,
+}
+
+impl<'a> ErrorJson<'a> {
+    const fn new(code: &'a str, message: &'a str) -> Self {
+        Self {
+            error: ErrorFields { code, message },
+        }
+    }
+}
+
+/// Wires the adapters, runs the invocation, prints the outcome and tells
+/// the process how to exit.
+pub fn execute(invocation: &Invocation) -> ExitCode {
+    match wire_and_run(invocation) {
+        Ok(output) => {
+            print!("{}", render(&output, invocation.output));
+            if invocation.output == OutputFormat::Json {
+                println!();
+            }
+            match output {
+                Output::Health(report) if !report.overall().is_usable() => ExitCode::from(4),
+                _ => ExitCode::SUCCESS,
+            }
+        }
+        Err(e) => {
+            match invocation.output {
+                OutputFormat::Text => eprintln!("{}", render_error(&e, OutputFormat::Text)),
+                OutputFormat::Json => println!("{}", render_error(&e, OutputFormat::Json)),
+            }
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn wire_and_run(invocation: &Invocation) -> Result<Output, OrderError> {
+    // Stdout is for the result: the adapters talk on stderr
+    let logger = Arc::new(
+        WriterLogger::new(io::stderr()).with_quiet(invocation.verbosity == Verbosity::Quiet),
+    );
+    let repo = SqliteOrderRepository::open_with_logger(&invocation.db, logger.clone())?;
+    let payment = MockPaymentGateway::new().with_logger(logger);
+    let sender = ConsoleSender::with_writer(io::stderr())
+        .with_verbosity(invocation.verbosity)
+        .with_tty_check(|| io::stderr().is_terminal());
+    let queries = OrderQueries::new(&repo);
+
+    // The IDs of earlier runs are taken: carry on from the last one
+    let next_id = queries
+        .list_orders()?
+        .last()
+        .map_or(1, |order| order.id.0 + 1);
+    let ids = SequentialIdGenerator::starting_at(OrderId(next_id));
+    let service = OrderService::new(&repo, &payment, &sender).with_id_generator(&ids);
+
+    let recipient = Recipient::new(
+        invocation.name.as_str(),
+        Contact::Email(invocation.email.clone()),
+    )?;
+    run(&invocation.command, &recipient, &service, &queries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_notification::RecordingSender;
+    use adapters_payment::FailingPaymentGateway;
+    use adapters_repository::{FaultyOrderRepository, InMemoryOrderRepository};
+    use serde_json::Value;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn item(name: &str, cents: u32) -> LineItem {
+        LineItem {
+            name: name.to_string(),
+            price: domain::Money(cents),
+            weight_grams: None,
+        }
+    }
+
+    fn ferris() -> Recipient {
+        Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string())).unwrap()
+    }
+
+    #[test]
+    fn subcommands_and_options_parse() {
+        let place = parse(&args("--json place --item Book:49.99 --item Pen:1.99")).unwrap();
+        let list = parse(&args("list --page 3 --db /tmp/x.db")).unwrap();
+
+        assert_eq!(place.output, OutputFormat::Json);
+        assert_eq!(
+            place.command,
+            Command::Place(vec![item("Book", 4999), item("Pen", 199),])
+        );
+        assert_eq!(list.command, Command::List { page: 3 });
+        assert_eq!(list.db, PathBuf::from("/tmp/x.db"));
+        assert_eq!(
+            parse(&args("get 7")).unwrap().command,
+            Command::Get(OrderId(7))
+        );
+        assert_eq!(
+            parse(&args("cancel 7")).unwrap().command,
+            Command::Cancel(OrderId(7))
+        );
+        assert_eq!(
+            parse(&args("seed --count 3 --seed 7")).unwrap().command,
+            Command::Seed {
+                count: 3,
+                rng_seed: 7
+            }
+        );
+        assert_eq!(
+            parse(&args("seed")).unwrap().command,
+            Command::Seed {
+                count: 10,
+                rng_seed: DEFAULT_SEED
+            }
+        );
+        assert_eq!(parse(&args("doctor")).unwrap().command, Command::Doctor);
+        assert_eq!(
+            parse(&args("list --output json")).unwrap().output,
+            OutputFormat::Json
+        );
+        assert_eq!(
+            parse(&args("--json list --output text")).unwrap().output,
+            OutputFormat::Text
+        );
+        assert_eq!(list.verbosity, Verbosity::Normal);
+        assert_eq!(
+            parse(&args("--quiet list")).unwrap().verbosity,
+            Verbosity::Quiet
+        );
+        assert_eq!(
+            parse(&args("list --quiet --verbose")).unwrap().verbosity,
+            Verbosity::Verbose
+        );
+    }
+
+    #[test]
+    fn only_verbosity_flags_run_the_demo() {
+        assert_eq!(demo_verbosity(&[]), Some(Verbosity::Normal));
+        assert_eq!(demo_verbosity(&args("--quiet")), Some(Verbosity::Quiet));
+        assert_eq!(
+            demo_verbosity(&args("--quiet --verbose")),
+            Some(Verbosity::Verbose)
+        );
+        assert_eq!(demo_verbosity(&args("--quiet list")), None);
+        assert_eq!(demo_verbosity(&args("--json")), None);
+    }
+
+    #[test]
+    fn bad_arguments_are_usage_errors() {
+        for line in [
+            "",
+            "place",
+            "place --item Book",
+            "place --item Book:free",
+            "get",
+            "get seven",
+            "list --page 0",
+            "list --page",
+            "list --color",
+            "seed --count many",
+            "seed --seed -1",
+            "list --output yaml",
+            "list --output",
+            "refund 1",
+        ] {
+            assert!(parse(&args(line)).is_err(), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn place_then_get_list_and_cancel() {
+        let repo = InMemoryOrderRepository::new();
+        let sender = RecordingSender::new();
+        let payment = MockPaymentGateway::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let queries = OrderQueries::new(&repo);
+        let dispatch = |command| run(&command, &ferris(), &service, &queries);
+
+        let placed =
+            dispatch(Command::Place(vec![item("Book", 4999), item("Book", 4999)])).unwrap();
+        let got = dispatch(Command::Get(OrderId(1))).unwrap();
+        let cancelled = dispatch(Command::Cancel(OrderId(1))).unwrap();
+        let page = dispatch(Command::List { page: 1 }).unwrap();
+        let empty = dispatch(Command::List { page: 2 }).unwrap();
+
+        assert_eq!(placed, got);
+        assert_eq!(
+            render(&got, OutputFormat::Text),
+            "Order #1 (Paid), total $99.98\n  2 x Book @ $49.99\n"
+        );
+        assert_eq!(
+            render(&cancelled, OutputFormat::Json),
+            "{\"id\":1,\"status\":\"Cancelled\",\"total\":\"$99.98\",\
+             \"items\":[\"2 x Book @ $49.99\"],\"warnings\":[]}"
+        );
+        assert!(matches!(page, Output::Page { ref orders, .. } if orders.len() == 1));
+        assert_eq!(render(&empty, OutputFormat::Text), "No orders on page 2.\n");
+        assert_eq!(sender.sent().len(), 2);
+    }
+
+    #[test]
+    fn failures_get_their_exit_code() {
+        let repo = InMemoryOrderRepository::new();
+        let sender = RecordingSender::new();
+        let declining = FailingPaymentGateway::default();
+        let service = OrderService::new(&repo, &declining, &sender);
+        let queries = OrderQueries::new(&repo);
+
+        let declined = run(
+            &Command::Place(vec![item("Book", 4999)]),
+            &ferris(),
+            &service,
+            &queries,
+        )
+        .unwrap_err();
+        let missing = run(&Command::Get(OrderId(9)), &ferris(), &service, &queries).unwrap_err();
+
+        assert_eq!(exit_code(&declined), 3);
+        assert_eq!(exit_code(&missing), 2);
+        assert_eq!(exit_code(&OrderError::StorageFailed(None)), 4);
+        assert_eq!(
+            render_error(&missing, OutputFormat::Json),
+            "{\"error\":{\"code\":\"order_not_found\",\
+             \"message\":\"OrderNotFound(OrderId(9))\"}}"
+        );
+        // A human gets the adapter's error too; a JSON client only the stable parts
+        let storage = OrderError::storage_failed("disk full");
+        assert_eq!(
+            render_error(&storage, OutputFormat::Text),
+            "Error: StorageFailed (disk full)"
+        );
+        assert_eq!(
+            render_error(&storage, OutputFormat::Json),
+            "{\"error\":{\"code\":\"storage_failed\",\"message\":\"StorageFailed\"}}"
+        );
+    }
+
+    /// `text` parsed as JSON: the test fails if it isn't.
+    fn document(text: &str) -> Value {
+        serde_json::from_str(text).unwrap_or_else(|e| panic!("{e}: {text}"))
+    }
+
+    /// The code of an error document, checking it has nothing else than
+    /// `{"error":{"code","message"}}`.
+    fn error_code(text: &str) -> String {
+        let document = document(text);
+        let fields: Vec<&String> = document.as_object().unwrap().keys().collect();
+        assert_eq!(fields, ["error"]);
+        let error = document["error"].as_object().unwrap();
+        let fields: Vec<&String> = error.keys().collect();
+        assert_eq!(fields, ["code", "message"]);
+        assert!(!error["message"].as_str().unwrap().is_empty());
+        error["code"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn every_json_success_is_one_document() {
+        let repo = InMemoryOrderRepository::new();
+        let sender = RecordingSender::new();
+        let payment = MockPaymentGateway::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let queries = OrderQueries::new(&repo);
+        let json = |command| {
+            let output = run(&command, &ferris(), &service, &queries).unwrap();
+            document(&render(&output, OutputFormat::Json))
+        };
+
+        let placed = json(Command::Place(vec![item("Book", 4999)]));
+        let seeded = json(Command::Seed {
+            count: 2,
+            rng_seed: 7,
+        });
+        let page = json(Command::List { page: 1 });
+        let health = json(Command::Doctor);
+
+        assert_eq!(placed["id"], 1);
+        assert_eq!(placed["status"], "Paid");
+        assert_eq!(placed["total"], "$49.99");
+        assert_eq!(placed["items"][0], "1 x Book @ $49.99");
+        assert!(placed["warnings"].as_array().unwrap().is_empty());
+        assert_eq!(seeded["seeded"], serde_json::json!([2, 3]));
+        assert_eq!(page["page"], 1);
+        assert_eq!(page["orders"].as_array().unwrap().len(), 3);
+        assert_eq!(page["orders"][0], placed);
+        for component in ["overall", "repository", "payment", "sender"] {
+            assert_eq!(health[component]["status"], "healthy", "{component}");
+        }
+    }
+
+    #[test]
+    fn every_json_failure_carries_its_stable_code() {
+        let repo = InMemoryOrderRepository::new();
+        let sender = RecordingSender::new();
+        let declining = FailingPaymentGateway::default();
+        let service = OrderService::new(&repo, &declining, &sender);
+        let queries = OrderQueries::new(&repo);
+        let book = || Command::Place(vec![item("Book", 4999)]);
+
+        // Exit code 2: the request was wrong
+        let missing = run(&Command::Get(OrderId(9)), &ferris(), &service, &queries).unwrap_err();
+        // 3: the payment failed
+        let declined = run(&book(), &ferris(), &service, &queries).unwrap_err();
+        // 4: the infrastructure failed
+        let faulty = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        faulty.fail_next_saves(1);
+        let payment = MockPaymentGateway::new();
+        let storing = OrderService::new(&faulty, &payment, &sender);
+        let unsaved = run(&book(), &ferris(), &storing, &OrderQueries::new(&faulty)).unwrap_err();
+        // And arguments that don't parse
+        let usage = parse(&args("refund 1")).unwrap_err();
+
+        for (error, exit) in [(&missing, 2), (&declined, 3), (&unsaved, 4)] {
+            assert_eq!(exit_code(error), exit);
+            assert_eq!(
+                error_code(&render_error(error, OutputFormat::Json)),
+                error.code()
+            );
+        }
+        assert_eq!(
+            error_code(&render_error(&missing, OutputFormat::Json)),
+            "order_not_found"
+        );
+        assert_eq!(
+            error_code(&render_error(&declined, OutputFormat::Json)),
+            "payment_failed"
+        );
+        assert_eq!(
+            error_code(&render_usage_error(&usage, OutputFormat::Json)),
+            USAGE_ERROR_CODE
+        );
+        assert!(render_usage_error(&usage, OutputFormat::Text).ends_with(USAGE));
+    }
+
+    #[test]
+    fn json_is_requested_even_in_arguments_that_dont_parse() {
+        assert_eq!(
+            requested_output(&args("--output json refund 1")),
+            OutputFormat::Json
+        );
+        assert_eq!(requested_output(&args("--json")), OutputFormat::Json);
+        assert_eq!(
+            requested_output(&args("--output yaml get")),
+            OutputFormat::Text
+        );
+    }
+
+    #[test]
+    fn doctor_reports_each_adapter() {
+        let repo = InMemoryOrderRepository::new();
+        let sender = RecordingSender::new();
+        let declining = FailingPaymentGateway::default();
+        let service = OrderService::new(&repo, &declining, &sender);
+        let queries = OrderQueries::new(&repo);
+
+        let health = run(&Command::Doctor, &ferris(), &service, &queries).unwrap();
+
+        assert_eq!(
+            render(&health, OutputFormat::Text),
+            "repository  healthy\n\
+             payment     unhealthy: every call fails (Declined)\n\
+             sender      healthy\n\
+             overall     unhealthy: payment: every call fails (Declined)\n"
+        );
+        assert_eq!(
+            render(&health, OutputFormat::Json),
+            "{\"overall\":{\"status\":\"unhealthy\",\
+             \"reason\":\"payment: every call fails (Declined)\"},\
+             \"repository\":{\"status\":\"healthy\"},\
+             \"payment\":{\"status\":\"unhealthy\",\"reason\":\"every call fails (Declined)\"},\
+             \"sender\":{\"status\":\"healthy\"}}"
+        );
+    }
+}
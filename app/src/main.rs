@@ -24,12 +24,38 @@
 // The benefit? MAXIMUM ISOLATION. Each crate compiles independently.
 // Change the domain? Only domain and its dependents recompile.
 // Change an adapter? Only that adapter crate recompiles.
+//
+// Run without arguments, it shows three configurations side by side
+// (`--quiet` hides what the adapters print, `--verbose` adds the receipts).
+// With a subcommand (`app place --item "Book:49.99"`, `app list`...), it's a CLI
+// working on the orders in a SQLite file: see cli.rs. With `--scenario`
+// (`app --scenario storage-down`), it places an order with failing adapters
+// plugged in, to show the OrderError that comes out: see scenario.rs.
+//
+// With the `tracing` feature, a subscriber prints the spans the application
+// opens around each use case and port call, and configuration #1 logs
+// through a TracingLogger, so its adapters' lines show up inside them.
+
+mod cli;
+mod scenario;
+mod seed;
+mod wiring;
 
-use adapters_notification::{ConsoleSender, SendGridSender};
-use adapters_payment::{MockPaymentGateway, StripePaymentGateway};
-use adapters_repository::{InMemoryOrderRepository, PostgresOrderRepository};
-use application::OrderService;
-use domain::{LineItem, Money};
+use adapters_notification::{
+    CompositeSender, ConsoleSender, FanoutPolicy, SendGridSender, Verbosity,
+};
+use adapters_payment::{
+    MockPaymentGateway, RetryPolicy, RetryingPaymentGateway, SlowPaymentGateway,
+    StripePaymentGateway,
+};
+use adapters_repository::{InMemoryOrderRepository, PostgresOrderRepository, SlowOrderRepository};
+use application::{OrderQueries, OrderService, PlacedOrder};
+use domain::{Contact, LineItem, Logger, Money, Recipient};
+use std::env;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use wiring::ResilientWiring;
 
 // =============================================================================
 // Main Function - Same as dip_06!
@@ -39,21 +65,92 @@ use domain::{LineItem, Money};
 // is identical to dip_06. We create adapters, inject them, use the service.
 // The architecture change is invisible to the business logic!
 
-fn main() {
+fn main() -> ExitCode {
+    #[cfg(feature = "tracing")]
+    init_tracing();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    match scenario::requested(&args) {
+        Some(Ok(scenario)) => return scenario::show(scenario),
+        Some(Err(e)) => {
+            eprintln!("{}", cli::render_usage_error(&e, cli::OutputFormat::Text));
+            return ExitCode::from(2);
+        }
+        None => {}
+    }
+    if let Some(verbosity) = cli::demo_verbosity(&args) {
+        demo(verbosity);
+        return ExitCode::SUCCESS;
+    }
+    match cli::parse(&args) {
+        Ok(invocation) => cli::execute(&invocation),
+        Err(e) => {
+            // Even unparsable, a request for JSON gets JSON, on stdout
+            match cli::requested_output(&args) {
+                cli::OutputFormat::Text => {
+                    eprintln!("{}", cli::render_usage_error(&e, cli::OutputFormat::Text));
+                }
+                cli::OutputFormat::Json => {
+                    println!("{}", cli::render_usage_error(&e, cli::OutputFormat::Json));
+                }
+            }
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints every event, and every span when it closes, with its fields and
+/// how long it took. To stderr, like the CLI's adapter lines: stdout is for
+/// `--output json`.
+#[cfg(feature = "tracing")]
+fn init_tracing() {
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    tracing_subscriber::fmt()
+        .with_span_events(FmtSpan::CLOSE)
+        .with_target(false)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// The logger of configuration #1: an event in the span of each call.
+///
+/// The subscriber decides what gets printed, not `--quiet`.
+#[cfg(feature = "tracing")]
+fn demo_logger(_verbosity: Verbosity) -> Arc<dyn Logger + Send + Sync> {
+    Arc::new(adapters_repository::TracingLogger)
+}
+
+/// The logger of configuration #1: a line per call, unless quiet.
+#[cfg(not(feature = "tracing"))]
+fn demo_logger(verbosity: Verbosity) -> Arc<dyn Logger + Send + Sync> {
+    Arc::new(
+        adapters_repository::WriterLogger::new(std::io::stdout())
+            .with_quiet(verbosity == Verbosity::Quiet),
+    )
+}
+
+fn demo(verbosity: Verbosity) {
     println!("=== Hexagonal Architecture Demo (Workspace) ===\n");
 
     // Test data: same as always
     let items = vec![
         LineItem {
             name: "Rust Programming Book".to_string(),
-            price: Money(4999), // $49.99
+            price: Money(4999), // $49.99,
+            weight_grams: None,
         },
         LineItem {
             name: "Mechanical Keyboard".to_string(),
-            price: Money(12999), // $129.99
+            price: Money(12999), // $129.99,
+            weight_grams: None,
         },
     ];
 
+    // Who gets the confirmation. Recipient::new validates the email address.
+    let recipient = Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string()))
+        .expect("demo recipient is valid");
+
     // -------------------------------------------------------------------------
     // Configuration #1: In-Memory Adapters (Testing/Development)
     // -------------------------------------------------------------------------
@@ -61,17 +158,27 @@ fn main() {
     // No external services needed: everything runs in memory.
     println!("--- Configuration #1: In-Memory Adapters (Testing) ---\n");
     {
-        let mut repo = InMemoryOrderRepository::new();
-        let payment = MockPaymentGateway;
-        let sender = ConsoleSender;
+        // One logger for both: every line says which request it belongs to
+        let logger = demo_logger(verbosity);
+        let repo = InMemoryOrderRepository::new().with_logger(logger.clone());
+        let payment = MockPaymentGateway::new().with_logger(logger);
+        let sender = ConsoleSender::new().with_verbosity(verbosity);
 
         // Dependency Injection: we choose the adapters, service doesn't care!
-        let mut service = OrderService::new(&mut repo, &payment, &sender);
+        // Commands and queries share the repository: every port takes &self.
+        let service = OrderService::new(&repo, &payment, &sender);
+        let queries = OrderQueries::new(&repo);
 
-        match service.place_order(items.clone()) {
-            Ok(order) => println!("\nOrder placed successfully: {}\n", order.id),
+        match service.place_order(&recipient, items.clone()) {
+            Ok(placed) => println!("\nOrder placed successfully: {}\n", placed.order.id),
             Err(e) => println!("\nError: {e}\n"),
         }
+
+        // The read side never touches payment or notification
+        match queries.list_orders() {
+            Ok(orders) => println!("Orders on file: {}\n", orders.len()),
+            Err(e) => println!("Error: {e}\n"),
+        }
     }
 
     // -------------------------------------------------------------------------
@@ -79,18 +186,53 @@ fn main() {
     // -------------------------------------------------------------------------
     // Same OrderService, completely different adapters.
     // In a real app, we'd choose based on environment variables or config.
+    // Each port gets a fallback: an unhealthy primary is swapped for it at
+    // startup, and the report says so (see wiring.rs).
     println!("--- Configuration #2: External Services (Production) ---\n");
     {
-        let mut repo = PostgresOrderRepository::new();
-        let payment = StripePaymentGateway;
-        let sender = SendGridSender;
+        let wiring = ResilientWiring::new()
+            .with_repository(PostgresOrderRepository::new, InMemoryOrderRepository::new)
+            // Stripe has bad minutes too: retry 503s and timeouts, never declines
+            .with_payment(
+                || RetryingPaymentGateway::new(StripePaymentGateway::new(), RetryPolicy::default()),
+                MockPaymentGateway::new,
+            )
+            // Email the customer, and echo to the console standing in for an ops channel.
+            // The order only counts as unnotified if neither got through.
+            // Without SENDGRID_API_KEY and SENDGRID_FROM, SendGrid is only simulated.
+            .with_sender(
+                move || {
+                    let sendgrid = SendGridSender::from_env().unwrap_or_else(|e| {
+                        println!("SendGrid not configured ({e}): simulating it\n");
+                        SendGridSender::simulated()
+                    });
+                    CompositeSender::new(
+                        vec![
+                            Box::new(sendgrid),
+                            Box::new(ConsoleSender::new().with_verbosity(verbosity)),
+                        ],
+                        FanoutPolicy::FailIfAllFail,
+                    )
+                },
+                move || ConsoleSender::new().with_verbosity(verbosity),
+            );
 
-        // Same OrderService, production adapters!
-        let mut service = OrderService::new(&mut repo, &payment, &sender);
+        // Same OrderService, production adapters (or their fallbacks)!
+        let (service, report) = wiring.build_service();
+        if report.substituted("repository") {
+            println!("Orders placed now only live as long as the process");
+        }
+        println!();
 
-        match service.place_order(items.clone()) {
-            Ok(order) => {
+        match service.place_order(&recipient, items.clone()) {
+            Ok(PlacedOrder { order, warnings }) => {
                 println!("\nOrder placed successfully: {}", order.id);
+                for warning in &warnings {
+                    println!("Warning: {warning}");
+                }
+                if let Some(transaction_id) = &order.transaction_id {
+                    println!("Payment reference: {transaction_id}");
+                }
 
                 // Demonstrate retrieval
                 println!();
@@ -105,6 +247,41 @@ fn main() {
             Err(e) => println!("\nError: {e}\n"),
         }
     }
+
+    // -------------------------------------------------------------------------
+    // Configuration #3: Slow Infrastructure (Latency Testing)
+    // -------------------------------------------------------------------------
+    // The in-memory adapters, wrapped in decorators that wait before every
+    // call: a rough stand-in for a database and a provider over the network.
+    // Both time their calls into the same Vec, so we see where the time went.
+    // Tests pass `with_sleeper` a closure instead, and don't actually wait.
+    println!("--- Configuration #3: Slow Infrastructure (Latency Testing) ---\n");
+    {
+        let timings = Arc::new(Mutex::new(Vec::new()));
+        let repo =
+            SlowOrderRepository::new(InMemoryOrderRepository::new(), Duration::from_millis(20))
+                .with_timings(Arc::clone(&timings));
+        let payment = SlowPaymentGateway::new(MockPaymentGateway::new(), Duration::from_millis(50))
+            .with_timings(Arc::clone(&timings));
+        let sender = ConsoleSender::new().with_verbosity(verbosity);
+
+        // Same OrderService again: it can't tell the adapters are slow
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        match service.place_order(&recipient, items) {
+            Ok(placed) => println!("\nOrder placed successfully: {}", placed.order.id),
+            Err(e) => println!("\nError: {e}"),
+        }
+
+        let timings = timings
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        for (call, elapsed) in timings.iter() {
+            println!("  {call:<18} {elapsed:?}");
+        }
+        let total: Duration = timings.iter().map(|(_, elapsed)| *elapsed).sum();
+        println!("Time spent waiting on infrastructure: {total:?}\n");
+    }
 }
 
 // =============================================================================
@@ -120,7 +297,7 @@ fn main() {
 //
 // 2. APPLICATION CRATE: Use case orchestration
 //    - Depends only on domain
-//    - Contains OrderService
+//    - Contains OrderService (commands) and OrderQueries (reads)
 //    - Coordinates domain + ports
 //
 // 3. ADAPTER CRATES: Infrastructure implementations
@@ -0,0 +1,266 @@
+// =============================================================================
+// What-If Scenarios - The Demo, When Things Break
+// =============================================================================
+//
+// The canned demo only shows orders going through. The more interesting half
+// of the story is what happens when they don't: an adapter fails in its own
+// terms (a card declined, a connection dropped, a mail server down), and the
+// application only ever sees an OrderError.
+//
+//     app --scenario storage-down
+//
+// runs the same two steps as the demo, place an order then get it back, with
+// the failing adapters of the scenario plugged in:
+//
+//     happy              nothing fails
+//     payment-declined   FailingPaymentGateway, the card is declined
+//     storage-down       FaultyOrderRepository, every save and find fails
+//     notification-down  FailingSender, the mail server is down
+//     everything-down    all three, the payment provider unreachable
+//
+// and prints the error that came out, with its stable code and its category
+// (the kind of failure the CLI's exit code tells: request, payment,
+// infrastructure).
+//
+// RUNTIME WIRING:
+// ---------------
+// Everywhere else, the adapters are chosen at compile time: OrderService is
+// generic over them. Here the scenario is only known once the arguments are
+// read, so `adapters` returns them boxed, as trait objects, and the service
+// is an OrderService over `dyn OrderRepository` & co. Same service, same use
+// cases: dynamic dispatch instead of monomorphization is the only difference.
+//
+// The service is strict about notifications (`FailOrder`): with the default
+// policy, a confirmation that can't be sent is only a warning, and
+// notification-down would look just like happy.
+
+use crate::cli::{UsageError, category, exit_code};
+use adapters_notification::{ConsoleSender, FailingSender};
+use adapters_payment::{FailingPaymentGateway, MockPaymentGateway};
+use adapters_repository::{FaultyOrderRepository, InMemoryOrderRepository};
+use application::{NotificationFailurePolicy, OrderDto, OrderService};
+use domain::{
+    Contact, LineItem, Money, OrderError, OrderRepository, PaymentFailureReason, PaymentGateway,
+    Recipient, Sender,
+};
+use std::fmt;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+/// Which adapters fail in a what-if run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    Happy,
+    PaymentDeclined,
+    StorageDown,
+    NotificationDown,
+    EverythingDown,
+}
+
+impl Scenario {
+    /// Every scenario, in the order `--help` lists them.
+    pub const ALL: [Self; 5] = [
+        Self::Happy,
+        Self::PaymentDeclined,
+        Self::StorageDown,
+        Self::NotificationDown,
+        Self::EverythingDown,
+    ];
+
+    /// The name `--scenario` takes.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Happy => "happy",
+            Self::PaymentDeclined => "payment-declined",
+            Self::StorageDown => "storage-down",
+            Self::NotificationDown => "notification-down",
+            Self::EverythingDown => "everything-down",
+        }
+    }
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Scenario {
+    type Err = UsageError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scenario| scenario.name() == text)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|s| s.name()).collect();
+                UsageError(format!("unknown scenario {text:?}: {}", names.join(", ")))
+            })
+    }
+}
+
+/// The scenario `args` ask for, when they are `--scenario NAME` and
+/// nothing else.
+#[must_use]
+pub fn requested(args: &[String]) -> Option<Result<Scenario, UsageError>> {
+    match args {
+        [flag] if flag == "--scenario" => {
+            Some(Err(UsageError("--scenario needs a value".to_string())))
+        }
+        [flag, name] if flag == "--scenario" => Some(name.parse()),
+        _ => None,
+    }
+}
+
+/// The three adapters of a scenario, as trait objects.
+pub struct ScenarioAdapters {
+    pub repository: Box<dyn OrderRepository + Send + Sync>,
+    pub payment: Box<dyn PaymentGateway + Send + Sync>,
+    pub sender: Box<dyn Sender + Send + Sync>,
+}
+
+// Manual impl: the adapters are trait objects with no Debug of their own.
+impl fmt::Debug for ScenarioAdapters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScenarioAdapters").finish_non_exhaustive()
+    }
+}
+
+/// Fresh adapters for `scenario`: the in-memory ones, with the failing
+/// ones in place of whatever the scenario breaks.
+#[must_use]
+pub fn adapters(scenario: Scenario) -> ScenarioAdapters {
+    let storage_down = matches!(scenario, Scenario::StorageDown | Scenario::EverythingDown);
+    let notification_down = matches!(
+        scenario,
+        Scenario::NotificationDown | Scenario::EverythingDown
+    );
+
+    let repository: Box<dyn OrderRepository + Send + Sync> = if storage_down {
+        let faulty = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        faulty.fail_next_saves(u32::MAX);
+        faulty.fail_every_nth_find(Some(1));
+        Box::new(faulty)
+    } else {
+        Box::new(InMemoryOrderRepository::new())
+    };
+    let payment: Box<dyn PaymentGateway + Send + Sync> = match scenario {
+        Scenario::PaymentDeclined => {
+            Box::new(FailingPaymentGateway::new(PaymentFailureReason::Declined))
+        }
+        Scenario::EverythingDown => Box::new(FailingPaymentGateway::new(
+            PaymentFailureReason::Unavailable,
+        )),
+        Scenario::Happy | Scenario::StorageDown | Scenario::NotificationDown => {
+            Box::new(MockPaymentGateway::new())
+        }
+    };
+    let sender: Box<dyn Sender + Send + Sync> = if notification_down {
+        Box::new(FailingSender::new())
+    } else {
+        Box::new(ConsoleSender::new())
+    };
+    ScenarioAdapters {
+        repository,
+        payment,
+        sender,
+    }
+}
+
+/// Places an order with the adapters of `scenario`, then gets it back.
+///
+/// # Errors
+///
+/// Returns the first [`OrderError`] on the way: the scenario's failure,
+/// as the application sees it.
+pub fn run(scenario: Scenario) -> Result<OrderDto, OrderError> {
+    let adapters = adapters(scenario);
+    let service = OrderService::new(&*adapters.repository, &*adapters.payment, &*adapters.sender)
+        .with_notification_failure_policy(NotificationFailurePolicy::FailOrder);
+
+    let recipient = Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string()))?;
+    let items = vec![LineItem {
+        name: "Rust Programming Book".to_string(),
+        price: Money(4999),
+        weight_grams: None,
+    }];
+    let id = service.place_order(&recipient, items)?.order.id;
+    service
+        .get_order_dto(id.0)?
+        .ok_or(OrderError::OrderNotFound(id))
+}
+
+/// Runs `scenario` and prints how it ended; exits like the CLI would.
+pub fn show(scenario: Scenario) -> ExitCode {
+    println!("=== What if: {scenario} ===\n");
+    match run(scenario) {
+        Ok(order) => {
+            println!(
+                "\nOrder #{} placed and read back: {}",
+                order.id, order.status
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("\nThe application got: {e:?}");
+            println!("  code:     {}", e.code());
+            println!("  category: {} (exit code {})", category(&e), exit_code(&e));
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::OrderStatus;
+
+    #[test]
+    fn each_scenario_surfaces_its_own_error() {
+        assert!(matches!(
+            run(Scenario::Happy),
+            Ok(order) if order.status == OrderStatus::Paid.to_string()
+        ));
+        assert!(matches!(
+            run(Scenario::PaymentDeclined),
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+        assert!(matches!(
+            run(Scenario::StorageDown),
+            Err(OrderError::StorageFailed(_))
+        ));
+        // Charged by then: the strict policy refunds it
+        assert!(matches!(
+            run(Scenario::NotificationDown),
+            Err(OrderError::NotificationFailedAfterCharge { refunded: true })
+        ));
+        // The payment is the first call: the outage shows there
+        assert!(matches!(
+            run(Scenario::EverythingDown),
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable))
+        ));
+    }
+
+    #[test]
+    fn scenarios_parse_by_name() {
+        for scenario in Scenario::ALL {
+            assert_eq!(scenario.name().parse::<Scenario>().unwrap(), scenario);
+        }
+        assert!("sunny".parse::<Scenario>().is_err());
+    }
+
+    #[test]
+    fn only_a_lone_scenario_flag_asks_for_one() {
+        let args =
+            |line: &str| -> Vec<String> { line.split_whitespace().map(str::to_string).collect() };
+
+        assert_eq!(
+            requested(&args("--scenario storage-down")),
+            Some(Ok(Scenario::StorageDown))
+        );
+        assert!(matches!(requested(&args("--scenario")), Some(Err(_))));
+        assert_eq!(requested(&args("list")), None);
+        assert_eq!(requested(&args("--scenario happy list")), None);
+    }
+}
@@ -0,0 +1,78 @@
+// =============================================================================
+// Seeding - Made-Up Orders, Placed for Real
+// =============================================================================
+//
+// `app seed --count 100 --seed 7` fills the SQLite file with orders to play
+// with. The items come from adapters-repository's `seed_items`, the
+// workspace's one generator: the same seed gives order N the same items, so
+// two files seeded alike hold the same orders.
+//
+// Unlike adapters-repository's `seed`, which inserts orders straight into a
+// repository, this goes through `OrderService::place_order`, payment and
+// notification included: every seeded order was placed the way a customer
+// would have.
+//
+// It lives here, in the app, rather than in test-support: test-support is
+// test doubles for dev-dependencies, and the app ships with this command.
+
+use adapters_repository::seed_items;
+use application::OrderService;
+use domain::{OrderError, OrderId, OrderRepository, PaymentGateway, Recipient, Sender};
+
+/// Places `n` orders for `recipient` through `service`, and returns their IDs.
+///
+/// Order `i` (from 1) has the items `seed_items` gives order `i` for
+/// `rng_seed`: the same seed always places the same orders.
+///
+/// # Errors
+///
+/// Returns the first error `place_order` gives; the orders placed before it
+/// stay placed.
+pub fn seed_orders<R, P, N>(
+    service: &OrderService<'_, R, P, N>,
+    recipient: &Recipient,
+    n: usize,
+    rng_seed: u64,
+) -> Result<Vec<OrderId>, OrderError>
+where
+    R: OrderRepository,
+    P: PaymentGateway,
+    N: Sender,
+{
+    let count = u32::try_from(n).unwrap_or(u32::MAX);
+    seed_items(count, rng_seed)
+        .map(|items| {
+            service
+                .place_order(recipient, items)
+                .map(|placed| placed.order.id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_notification::RecordingSender;
+    use adapters_payment::MockPaymentGateway;
+    use adapters_repository::{InMemoryOrderRepository, seed_recipient};
+    use application::OrderQueries;
+
+    #[test]
+    fn seed_orders_places_them_for_real() {
+        let repo = InMemoryOrderRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = RecordingSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let ids = seed_orders(&service, &seed_recipient(), 4, 7).unwrap();
+
+        assert_eq!(ids, [OrderId(1), OrderId(2), OrderId(3), OrderId(4)]);
+        let orders = OrderQueries::new(&repo).list_orders().unwrap();
+        let sizes: Vec<usize> = orders.iter().map(|order| order.items.len()).collect();
+        assert_eq!(sizes, [2, 3, 1, 2]);
+        let expected: Vec<_> = seed_items(4, 7).collect();
+        let placed: Vec<_> = orders.into_iter().map(|order| order.items).collect();
+        assert_eq!(placed, expected);
+        assert_eq!(sender.sent().len(), 4);
+    }
+}
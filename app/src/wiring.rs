@@ -0,0 +1,361 @@
+// =============================================================================
+// Resilient Wiring - Boot With the Fallback When the Primary Is Down
+// =============================================================================
+//
+// `app doctor` tells us an adapter is unhealthy. By then the service is
+// already wired to it, and every order that comes in will fail. For some
+// deployments, the better trade is to notice at startup and boot with a
+// lesser adapter instead: an in-memory store rather than an unreachable
+// database, the mock gateway rather than a provider that's down.
+//
+// `ResilientWiring` takes two factories per port, a primary and a fallback:
+//
+//     let wiring = ResilientWiring::new()
+//         .with_repository(PostgresOrderRepository::new, InMemoryOrderRepository::new)
+//         .with_payment(StripePaymentGateway::new, MockPaymentGateway::new)
+//         .with_sender(ConsoleSender::new, ConsoleSender::new);
+//     let (service, report) = wiring.build_service();
+//
+// `build_service` creates each primary and runs its HealthCheck. An
+// unhealthy one is dropped and the fallback takes its place; the
+// substitution goes in the startup report, which is printed and returned.
+// A degraded primary is kept: some calls working beats a whole store
+// starting empty.
+//
+// The fallback is created only when it's needed, and it isn't checked:
+// it's the last resort, there's nothing to fall back to after it.
+//
+// WHY A FACTORY:
+// --------------
+// Only the factory knows how to build an adapter, and a fallback that is
+// never needed is never built: no empty in-memory store next to a healthy
+// database, no file sender creating its file for nothing.
+//
+// OrderService itself doesn't change: it gets the chosen adapters as trait
+// objects, the way the what-if scenarios give it theirs (see scenario.rs).
+// The wiring owns them, the service borrows them, so the service lives as
+// long as the wiring.
+
+use application::OrderService;
+use domain::{HealthCheck, HealthStatus, OrderRepository, PaymentGateway, Sender};
+use std::cell::OnceCell;
+use std::fmt;
+
+/// An order repository that can say whether it's healthy.
+pub trait RepositoryAdapter: OrderRepository + HealthCheck {}
+impl<T: OrderRepository + HealthCheck> RepositoryAdapter for T {}
+
+/// A payment gateway that can say whether it's healthy.
+pub trait PaymentAdapter: PaymentGateway + HealthCheck {}
+impl<T: PaymentGateway + HealthCheck> PaymentAdapter for T {}
+
+/// A sender that can say whether it's healthy.
+pub trait SenderAdapter: Sender + HealthCheck {}
+impl<T: Sender + HealthCheck> SenderAdapter for T {}
+
+/// The service [`ResilientWiring::build_service`] builds.
+pub type ResilientService<'a> =
+    OrderService<'a, dyn RepositoryAdapter, dyn PaymentAdapter, dyn SenderAdapter>;
+
+type Factory<T> = Box<dyn Fn() -> Box<T>>;
+
+/// How to build one port's adapter, and what to build instead.
+struct Candidates<T: ?Sized> {
+    primary: Factory<T>,
+    fallback: Factory<T>,
+}
+
+impl<T: HealthCheck + ?Sized> Candidates<T> {
+    /// The primary if it's usable; otherwise the fallback, and why.
+    fn choose(&self, port: &'static str) -> (Box<T>, Option<Substitution>) {
+        let primary = (self.primary)();
+        let status = primary.health();
+        if status.is_usable() {
+            return (primary, None);
+        }
+        drop(primary);
+        let substitution = Substitution {
+            port,
+            primary: status,
+        };
+        ((self.fallback)(), Some(substitution))
+    }
+}
+
+/// A primary adapter that failed its health check at startup, and was
+/// replaced by its fallback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    /// "repository", "payment" or "sender", as in `app doctor`.
+    pub port: &'static str,
+    /// What the primary's health check said.
+    pub primary: HealthStatus,
+}
+
+/// Which ports booted with their fallback.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StartupReport {
+    pub substitutions: Vec<Substitution>,
+}
+
+impl StartupReport {
+    /// Whether every port got its primary adapter.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.substitutions.is_empty()
+    }
+
+    /// Whether `port` booted with its fallback.
+    #[must_use]
+    pub fn substituted(&self, port: &str) -> bool {
+        self.substitutions.iter().any(|s| s.port == port)
+    }
+}
+
+impl fmt::Display for StartupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return writeln!(f, "Startup: every primary adapter is healthy");
+        }
+        for substitution in &self.substitutions {
+            writeln!(
+                f,
+                "Startup: {} {}, using its fallback",
+                substitution.port, substitution.primary
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct Adapters {
+    repository: Box<dyn RepositoryAdapter>,
+    payment: Box<dyn PaymentAdapter>,
+    sender: Box<dyn SenderAdapter>,
+    report: StartupReport,
+}
+
+/// Builds an [`OrderService`] from the first healthy adapter of each port.
+#[derive(Default)]
+pub struct ResilientWiring {
+    repository: Option<Candidates<dyn RepositoryAdapter>>,
+    payment: Option<Candidates<dyn PaymentAdapter>>,
+    sender: Option<Candidates<dyn SenderAdapter>>,
+    adapters: OnceCell<Adapters>,
+}
+
+// Manual impl: the factories are closures with no Debug of their own.
+impl fmt::Debug for ResilientWiring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResilientWiring")
+            .field(
+                "report",
+                &self.adapters.get().map(|adapters| &adapters.report),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResilientWiring {
+    /// A wiring with no port set yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The repository: `primary()` if healthy, `fallback()` otherwise.
+    #[must_use]
+    pub fn with_repository<A, B>(
+        mut self,
+        primary: impl Fn() -> A + 'static,
+        fallback: impl Fn() -> B + 'static,
+    ) -> Self
+    where
+        A: RepositoryAdapter + 'static,
+        B: RepositoryAdapter + 'static,
+    {
+        self.repository = Some(Candidates {
+            primary: Box::new(move || Box::new(primary())),
+            fallback: Box::new(move || Box::new(fallback())),
+        });
+        self
+    }
+
+    /// The payment gateway: `primary()` if healthy, `fallback()` otherwise.
+    #[must_use]
+    pub fn with_payment<A, B>(
+        mut self,
+        primary: impl Fn() -> A + 'static,
+        fallback: impl Fn() -> B + 'static,
+    ) -> Self
+    where
+        A: PaymentAdapter + 'static,
+        B: PaymentAdapter + 'static,
+    {
+        self.payment = Some(Candidates {
+            primary: Box::new(move || Box::new(primary())),
+            fallback: Box::new(move || Box::new(fallback())),
+        });
+        self
+    }
+
+    /// The sender: `primary()` if healthy, `fallback()` otherwise.
+    #[must_use]
+    pub fn with_sender<A, B>(
+        mut self,
+        primary: impl Fn() -> A + 'static,
+        fallback: impl Fn() -> B + 'static,
+    ) -> Self
+    where
+        A: SenderAdapter + 'static,
+        B: SenderAdapter + 'static,
+    {
+        self.sender = Some(Candidates {
+            primary: Box::new(move || Box::new(primary())),
+            fallback: Box::new(move || Box::new(fallback())),
+        });
+        self
+    }
+
+    /// Checks each primary, substitutes the fallback for the unhealthy ones,
+    /// prints the startup report, and returns a service over the chosen
+    /// adapters along with the report.
+    ///
+    /// The adapters are chosen on the first call only: later calls return
+    /// another service over the same ones, and the same report, unprinted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a port was never given its factories.
+    pub fn build_service(&self) -> (ResilientService<'_>, StartupReport) {
+        let mut first = false;
+        let adapters = self.adapters.get_or_init(|| {
+            first = true;
+            self.choose()
+        });
+        if first {
+            print!("{}", adapters.report);
+        }
+        let service =
+            OrderService::new(&*adapters.repository, &*adapters.payment, &*adapters.sender);
+        (service, adapters.report.clone())
+    }
+
+    fn choose(&self) -> Adapters {
+        let missing = |port: &str| format!("ResilientWiring: no factories for the {port}");
+        let repository = self
+            .repository
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", missing("repository")));
+        let payment = self
+            .payment
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", missing("payment gateway")));
+        let sender = self
+            .sender
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", missing("sender")));
+
+        let mut report = StartupReport::default();
+        let (repository, substituted) = repository.choose("repository");
+        report.substitutions.extend(substituted);
+        let (payment, substituted) = payment.choose("payment");
+        report.substitutions.extend(substituted);
+        let (sender, substituted) = sender.choose("sender");
+        report.substitutions.extend(substituted);
+
+        Adapters {
+            repository,
+            payment,
+            sender,
+            report,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_notification::{ConsoleSender, FailingSender};
+    use adapters_payment::MockPaymentGateway;
+    use adapters_repository::{
+        FaultyOrderRepository, InMemoryOrderRepository, SharedInMemoryOrderRepository,
+    };
+    use domain::{Contact, LineItem, Money, Recipient, TenantId};
+
+    fn ferris() -> Recipient {
+        Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string())).unwrap()
+    }
+
+    fn book() -> Vec<LineItem> {
+        vec![LineItem::new("Rust Programming Book", Money(4999)).unwrap()]
+    }
+
+    fn down() -> FaultyOrderRepository<InMemoryOrderRepository> {
+        let faulty = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        faulty.fail_every_nth_find(Some(1));
+        faulty
+    }
+
+    #[test]
+    fn an_unhealthy_repository_boots_with_its_fallback() {
+        let fallback = SharedInMemoryOrderRepository::default();
+        let store = fallback.clone();
+        let wiring = ResilientWiring::new()
+            .with_repository(down, move || fallback.clone())
+            .with_payment(MockPaymentGateway::new, MockPaymentGateway::new)
+            .with_sender(ConsoleSender::new, ConsoleSender::new);
+
+        let (service, report) = wiring.build_service();
+        let id = service.place_order(&ferris(), book()).unwrap().order.id;
+
+        assert_eq!(
+            report.substitutions,
+            vec![Substitution {
+                port: "repository",
+                primary: HealthStatus::Unhealthy("every find fails".to_string()),
+            }]
+        );
+        assert_eq!(
+            report.to_string(),
+            "Startup: repository unhealthy: every find fails, using its fallback\n"
+        );
+        // The order landed in the fallback store, and reads back through it
+        assert!(store.find(TenantId::default(), id).unwrap().is_some());
+        assert!(service.get_order(id).unwrap().is_some());
+    }
+
+    #[test]
+    fn healthy_and_degraded_primaries_are_kept() {
+        let degraded = || {
+            let faulty = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+            faulty.fail_next_saves(1);
+            faulty
+        };
+        let wiring = ResilientWiring::new()
+            .with_repository(degraded, || -> InMemoryOrderRepository {
+                panic!("the fallback isn't needed")
+            })
+            .with_payment(MockPaymentGateway::new, MockPaymentGateway::new)
+            .with_sender(FailingSender::new, ConsoleSender::new);
+
+        let (service, report) = wiring.build_service();
+
+        assert!(!report.substituted("repository"));
+        assert!(report.substituted("sender"));
+        // The degraded repository is the one in use: its first save fails
+        assert!(service.place_order(&ferris(), book()).is_err());
+        // Same adapters on a second call
+        let (_, again) = wiring.build_service();
+        assert_eq!(again, report);
+    }
+
+    #[test]
+    #[should_panic(expected = "no factories for the payment gateway")]
+    fn every_port_needs_factories() {
+        let wiring = ResilientWiring::new()
+            .with_repository(InMemoryOrderRepository::new, InMemoryOrderRepository::new);
+
+        let _ = wiring.build_service();
+    }
+}
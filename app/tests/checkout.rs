@@ -0,0 +1,85 @@
+// =============================================================================
+// Checkout Tests - The Whole Workspace, Wired Together
+// =============================================================================
+//
+// The unit tests of each crate use each crate's own doubles. These tests wire
+// the real adapter crates to the application, the way main.rs does, and check
+// what comes out at the edges: what was charged, what the customer was told.
+
+use adapters_notification::RecordingSender;
+use adapters_payment::RecordingPaymentGateway;
+use adapters_repository::{InMemoryOrderRepository, seed_items, seed_recipient};
+use application::OrderService;
+use domain::{Contact, LineItem, Money, Order, OrderId, Recipient};
+
+fn ferris() -> Recipient {
+    Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string())).unwrap()
+}
+
+#[test]
+fn checkout_charges_and_confirms_with_the_total() {
+    let repo = InMemoryOrderRepository::new();
+    let payment = RecordingPaymentGateway::new();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, &payment, &sender);
+
+    let placed = service
+        .place_order(
+            &ferris(),
+            vec![
+                LineItem {
+                    name: "Rust Programming Book".to_string(),
+                    price: Money(4999),
+                    weight_grams: None,
+                },
+                LineItem {
+                    name: "Mechanical Keyboard".to_string(),
+                    price: Money(12999),
+                    weight_grams: None,
+                },
+            ],
+        )
+        .unwrap();
+    service
+        .refund_order(placed.order.id, Money(4999), &ferris())
+        .unwrap();
+
+    assert_eq!(payment.charges()[0].amount, Money(17998));
+    let sent = sender.sent();
+    assert_eq!(sent.len(), 2);
+    assert!(
+        sent.iter()
+            .all(|notification| notification.recipient == ferris()
+                && notification.order_id == OrderId(1))
+    );
+    assert!(sent[0].message.contains("OrderId(1)"));
+    assert!(sent[0].message.contains("$179.98"));
+    assert!(sent[1].message.contains("$49.99"));
+}
+
+#[test]
+fn every_made_up_order_is_charged_its_total() {
+    let repo = InMemoryOrderRepository::new();
+    let payment = RecordingPaymentGateway::new();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, &payment, &sender);
+    let orders: Vec<Vec<LineItem>> = seed_items(100, 7).collect();
+
+    for items in &orders {
+        service
+            .place_order(&seed_recipient(), items.clone())
+            .unwrap();
+    }
+
+    let charged: Vec<Money> = payment
+        .charges()
+        .iter()
+        .map(|charge| charge.amount)
+        .collect();
+    let totals: Vec<Money> = orders
+        .iter()
+        .map(|items| Order::total_of(items).unwrap())
+        .collect();
+    assert_eq!(charged, totals);
+    assert_eq!(sender.sent().len(), 100);
+}
@@ -0,0 +1,136 @@
+// =============================================================================
+// CLI Tests - The Binary, as a Script Would Run It
+// =============================================================================
+//
+// cli.rs tests the parsing and the dispatch in-process. These run the real
+// binary, several times over the same SQLite file, and only look at what a
+// script sees: stdout, and the exit code.
+
+use adapters_repository::seed_items;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A database path no other test uses.
+fn temp_db(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!("{name}_{}_{nanos}.db", std::process::id()))
+}
+
+fn app(db: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_app"))
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn orders_survive_from_one_command_to_the_next() {
+    let db = temp_db("cli_orders");
+
+    let placed = app(
+        &db,
+        &["place", "--item", "Book:49.99", "--item", "Pen:1.99"],
+    );
+    let second = app(&db, &["--json", "place", "--item", "Mug:9.99"]);
+    let got = app(&db, &["get", "1"]);
+    let cancelled = app(&db, &["--json", "cancel", "2"]);
+    let listed = app(&db, &["--json", "list", "--page", "1"]);
+
+    fs::remove_file(&db).unwrap();
+    assert!(placed.status.success());
+    assert_eq!(
+        stdout(&placed),
+        "Order #1 (Paid), total $51.98\n  1 x Book @ $49.99\n  1 x Pen @ $1.99\n"
+    );
+    assert_eq!(
+        stdout(&second),
+        "{\"id\":2,\"status\":\"Paid\",\"total\":\"$9.99\",\
+         \"items\":[\"1 x Mug @ $9.99\"],\"warnings\":[]}\n"
+    );
+    assert_eq!(stdout(&got), stdout(&placed));
+    assert!(stdout(&cancelled).contains("\"status\":\"Cancelled\""));
+    let listed = stdout(&listed);
+    assert!(listed.starts_with("{\"page\":1,\"orders\":[{\"id\":1,"));
+    assert!(listed.contains("{\"id\":2,\"status\":\"Cancelled\""));
+}
+
+#[test]
+fn exit_codes_tell_failures_apart() {
+    let db = temp_db("cli_exit_codes");
+
+    let bad_price = app(&db, &["place", "--item", "Book:free"]);
+    let bad_price_json = app(&db, &["--output", "json", "place", "--item", "Book:free"]);
+    let unknown = app(&db, &["--output", "json", "get", "42"]);
+    let unreadable = app(&std::env::temp_dir(), &["--output", "json", "list"]);
+
+    let _ = fs::remove_file(&db);
+    assert_eq!(bad_price.status.code(), Some(2));
+    assert!(stdout(&bad_price).is_empty());
+    assert_eq!(bad_price_json.status.code(), Some(2));
+    assert!(stdout(&bad_price_json).starts_with("{\"error\":{\"code\":\"invalid_arguments\","));
+    assert_eq!(unknown.status.code(), Some(2));
+    assert_eq!(
+        stdout(&unknown),
+        "{\"error\":{\"code\":\"order_not_found\",\"message\":\"OrderNotFound(OrderId(42))\"}}\n"
+    );
+    // A directory isn't a database: storage failure
+    assert_eq!(unreadable.status.code(), Some(4));
+    assert!(stdout(&unreadable).starts_with("{\"error\":{\"code\":\"storage_failed\","));
+}
+
+#[test]
+fn seed_places_the_same_orders_every_time() {
+    let first_db = temp_db("cli_seed_first");
+    let second_db = temp_db("cli_seed_second");
+
+    let seeded = app(&first_db, &["seed", "--count", "3", "--seed", "7"]);
+    let again = app(
+        &second_db,
+        &["--json", "seed", "--seed", "7", "--count", "3"],
+    );
+    let first = app(&first_db, &["--json", "list"]);
+    let second = app(&second_db, &["--json", "list"]);
+
+    fs::remove_file(&first_db).unwrap();
+    fs::remove_file(&second_db).unwrap();
+    assert!(seeded.status.success());
+    assert_eq!(stdout(&seeded), "Seeded 3 orders: #1, #2, #3\n");
+    assert_eq!(stdout(&again), "{\"seeded\":[1,2,3]}\n");
+    assert!(stdout(&first).contains("{\"id\":3,\"status\":\"Paid\""));
+    assert_eq!(stdout(&first), stdout(&second));
+    // The items are the generator's, in the same order
+    for item in seed_items(3, 7).flatten() {
+        assert!(stdout(&first).contains(&item.name), "{}", item.name);
+    }
+}
+
+#[test]
+fn doctor_finds_the_adapters_healthy() {
+    let db = temp_db("cli_doctor");
+
+    let doctor = app(&db, &["doctor"]);
+    let json = app(&db, &["--json", "doctor"]);
+
+    fs::remove_file(&db).unwrap();
+    assert!(doctor.status.success());
+    assert_eq!(
+        stdout(&doctor),
+        "repository  healthy\n\
+         payment     healthy\n\
+         sender      healthy\n\
+         overall     healthy\n"
+    );
+    assert!(json.status.success());
+    assert!(stdout(&json).starts_with("{\"overall\":{\"status\":\"healthy\"}"));
+}
@@ -0,0 +1,312 @@
+// =============================================================================
+// Combination Tests - Every Adapter Wiring, One Scenario
+// =============================================================================
+//
+// Each adapter crate tests its adapters alone. Whether they still plug into
+// OrderService the way main.rs plugs them only shows when app compiles, and
+// whether they BEHAVE the same once plugged in doesn't show at all.
+//
+// This suite wires every supported combination:
+//
+//     repository: in-memory, postgres (simulated)
+//     payment:    mock, stripe (simulated)
+//     sender:     console, recording
+//
+// and runs the same scenario on each: place two orders, get one, list them,
+// cancel one, then place a third that gets an ID already taken. What comes
+// out at the edges (the DTOs, the errors, what was charged, what the customer
+// was told) must be the same for all eight. Swapping an adapter must not
+// change what the application does: that's the whole point of the ports.
+//
+// Nothing here reads stdout. The adapters' log lines go to a logger nobody
+// reads, the console's to a buffer, and charges are counted by a
+// RecordingPaymentGateway wrapped around each gateway.
+
+use adapters_notification::{ConsoleSender, RecordingSender};
+use adapters_payment::{MockPaymentGateway, RecordingPaymentGateway, StripePaymentGateway};
+use adapters_repository::{InMemoryOrderRepository, PostgresOrderRepository, WriterLogger};
+use application::{OrderDto, OrderQueries, OrderService, PlaceOrderRequest};
+use domain::{
+    Contact, CustomerId, IdGenerator, Money, OrderId, OrderRepository, PaymentGateway, Recipient,
+    Sender, SubscriptionId,
+};
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy)]
+enum Repository {
+    InMemory,
+    Postgres,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Payment {
+    Mock,
+    Stripe,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Channel {
+    Console,
+    Recording,
+}
+
+/// One way of wiring the service.
+#[derive(Debug, Clone, Copy)]
+struct Combination {
+    repository: Repository,
+    payment: Payment,
+    channel: Channel,
+}
+
+impl fmt::Display for Combination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} + {:?} + {:?}",
+            self.repository, self.payment, self.channel
+        )
+    }
+}
+
+/// Every combination, in-memory + mock + recording first.
+fn combinations() -> Vec<Combination> {
+    let mut all = Vec::new();
+    for repository in [Repository::InMemory, Repository::Postgres] {
+        for payment in [Payment::Mock, Payment::Stripe] {
+            for channel in [Channel::Recording, Channel::Console] {
+                all.push(Combination {
+                    repository,
+                    payment,
+                    channel,
+                });
+            }
+        }
+    }
+    all
+}
+
+/// What the scenario shows at the edges, whatever the wiring.
+#[derive(Debug, PartialEq, Eq)]
+struct Outcome {
+    placed: Vec<OrderDto>,
+    got: Option<OrderDto>,
+    listed: Vec<OrderDto>,
+    cancelled: OrderDto,
+    /// The stable code of the error placing with a taken ID gave.
+    duplicate: &'static str,
+    /// The orders after that failed attempt.
+    after: Vec<OrderDto>,
+    charged: Vec<Money>,
+    /// One `"To <recipient>: <message>"` per notification.
+    notified: Vec<String>,
+}
+
+/// Order IDs from a list, in order: the scenario hands out one twice.
+struct ScriptedIds(Mutex<Vec<u32>>);
+
+impl ScriptedIds {
+    fn new(ids: &[u32]) -> Self {
+        Self(Mutex::new(ids.iter().rev().copied().collect()))
+    }
+}
+
+impl IdGenerator for ScriptedIds {
+    fn next_order_id(&self) -> OrderId {
+        OrderId(
+            self.0
+                .lock()
+                .unwrap()
+                .pop()
+                .expect("the scenario ran out of IDs"),
+        )
+    }
+
+    fn next_customer_id(&self) -> CustomerId {
+        CustomerId(1)
+    }
+
+    fn next_subscription_id(&self) -> SubscriptionId {
+        SubscriptionId(1)
+    }
+}
+
+/// A `Vec<u8>` the test keeps a handle on after giving it to ConsoleSender.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn ferris() -> Recipient {
+    Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string())).unwrap()
+}
+
+fn request(items: &[(&str, i64, u32)]) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+        recipient_name: "Ferris".to_string(),
+        recipient_email: "ferris@rustacean.net".to_string(),
+        items: items
+            .iter()
+            .map(|(name, price, quantity)| (name.to_string(), *price, *quantity))
+            .collect(),
+    }
+}
+
+/// The log lines of the adapters, somewhere nobody reads.
+fn quiet() -> Arc<WriterLogger<io::Sink>> {
+    Arc::new(WriterLogger::new(io::sink()))
+}
+
+/// The scenario itself. Notifications are left for the caller to fill in:
+/// only it knows where its sender put them.
+fn scenario<R, P, N>(repo: &R, payment: &RecordingPaymentGateway<P>, sender: &N) -> Outcome
+where
+    R: OrderRepository,
+    P: PaymentGateway,
+    N: Sender,
+{
+    // The third order gets ID 1 again
+    let ids = ScriptedIds::new(&[1, 2, 1]);
+    let service = OrderService::new(repo, payment, sender).with_id_generator(&ids);
+    let queries = OrderQueries::new(repo);
+    let dtos = |queries: &OrderQueries<'_, R>| -> Vec<OrderDto> {
+        let orders = queries.list_orders().unwrap();
+        orders.iter().map(OrderDto::from).collect()
+    };
+
+    let placed = vec![
+        service
+            .place_order_dto(&request(&[("Book", 4999, 1), ("Pen", 199, 2)]))
+            .unwrap(),
+        service
+            .place_order_dto(&request(&[("Keyboard", 12999, 1)]))
+            .unwrap(),
+    ];
+    let got = service.get_order_dto(1).unwrap();
+    let listed = dtos(&queries);
+    let cancelled = OrderDto::from(&service.cancel_order(OrderId(2), &ferris()).unwrap());
+    let duplicate = service
+        .place_order_dto(&request(&[("Mug", 999, 1)]))
+        .unwrap_err()
+        .code();
+
+    Outcome {
+        placed,
+        got,
+        listed,
+        cancelled,
+        duplicate,
+        after: dtos(&queries),
+        charged: payment
+            .charges()
+            .iter()
+            .map(|charge| charge.amount)
+            .collect(),
+        notified: Vec::new(),
+    }
+}
+
+fn run(combination: Combination) -> Outcome {
+    match combination.repository {
+        Repository::InMemory => with_payment(
+            combination,
+            &InMemoryOrderRepository::new().with_logger(quiet()),
+        ),
+        Repository::Postgres => with_payment(
+            combination,
+            &PostgresOrderRepository::new().with_logger(quiet()),
+        ),
+    }
+}
+
+fn with_payment<R: OrderRepository>(combination: Combination, repo: &R) -> Outcome {
+    match combination.payment {
+        Payment::Mock => with_channel(
+            combination,
+            repo,
+            &RecordingPaymentGateway::wrapping(MockPaymentGateway::new().with_logger(quiet())),
+        ),
+        Payment::Stripe => with_channel(
+            combination,
+            repo,
+            &RecordingPaymentGateway::wrapping(StripePaymentGateway::new()),
+        ),
+    }
+}
+
+fn with_channel<R: OrderRepository, P: PaymentGateway>(
+    combination: Combination,
+    repo: &R,
+    payment: &RecordingPaymentGateway<P>,
+) -> Outcome {
+    match combination.channel {
+        Channel::Recording => {
+            let sender = RecordingSender::new();
+            let mut outcome = scenario(repo, payment, &sender);
+            outcome.notified = sender
+                .sent()
+                .iter()
+                .map(|sent| format!("To {}: {}", sent.recipient, sent.message))
+                .collect();
+            outcome
+        }
+        Channel::Console => {
+            let buffer = Buffer::default();
+            let sender = ConsoleSender::with_writer(buffer.clone());
+            let mut outcome = scenario(repo, payment, &sender);
+            let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+            outcome.notified = text
+                .lines()
+                .map(|line| line.trim_start_matches("  [Console] ").to_string())
+                .collect();
+            outcome
+        }
+    }
+}
+
+#[test]
+fn the_reference_wiring_does_what_the_scenario_says() {
+    let outcome = run(combinations()[0]);
+
+    assert_eq!(outcome.placed.len(), 2);
+    assert_eq!(outcome.got.as_ref(), Some(&outcome.placed[0]));
+    assert_eq!(outcome.listed, outcome.placed);
+    assert_eq!(outcome.cancelled.status, "Cancelled");
+    assert_eq!(outcome.duplicate, "duplicate_order");
+    assert_eq!(outcome.after[1], outcome.cancelled);
+    assert_eq!(outcome.charged, [Money(5397), Money(12999)]);
+    assert_eq!(
+        outcome.notified,
+        [
+            "To Ferris <ferris@rustacean.net>: Order #OrderId(1) confirmed! Total: $53.97",
+            "To Ferris <ferris@rustacean.net>: Order #OrderId(2) confirmed! Total: $129.99",
+            "To Ferris <ferris@rustacean.net>: Order #OrderId(2) has been cancelled.",
+        ]
+    );
+}
+
+#[test]
+fn every_wiring_behaves_the_same() {
+    let all = combinations();
+    let reference = run(all[0]);
+
+    let broken: Vec<String> = all[1..]
+        .iter()
+        .filter_map(|&combination| {
+            let outcome = run(combination);
+            (outcome != reference)
+                .then(|| format!("{combination} differs from {}:\n{outcome:#?}", all[0]))
+        })
+        .collect();
+
+    assert!(broken.is_empty(), "{}", broken.join("\n\n"));
+}
@@ -0,0 +1,59 @@
+// =============================================================================
+// Place Order Benchmark - The Use Case, End to End
+// =============================================================================
+//
+// The repository benchmarks time one port call. This one times the whole
+// `place_order`: validation, ID, charge, insert, confirmation, through the
+// in-memory repository, the mock gateway and a console writing nowhere.
+//
+// It's the ceiling of what the application itself costs: with real adapters,
+// the network dwarfs it.
+//
+// As in the repository benchmarks, every adapter logs to a NullLogger, and
+// the console writes to `io::sink()`: printing would be all we'd measure.
+// The items come from adapters-repository's `generate_items`, always the
+// same two.
+//
+//     cargo bench -p application
+
+use adapters_notification::ConsoleSender;
+use adapters_payment::MockPaymentGateway;
+use adapters_repository::{
+    InMemoryOrderRepository, NullLogger, SequentialIdGenerator, generate_items, seed,
+    seed_recipient,
+};
+use application::OrderService;
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use domain::OrderId;
+use std::hint::black_box;
+use std::io;
+use std::sync::Arc;
+
+fn place_order(c: &mut Criterion) {
+    let logger = Arc::new(NullLogger);
+    let repo = InMemoryOrderRepository::new().with_logger(logger.clone());
+    let payment = MockPaymentGateway::new().with_logger(logger);
+    let sender = ConsoleSender::with_writer(io::sink());
+    // Orders 1 to 10k already there, as in the repository benchmarks
+    seed(&repo, 10_000, 42).expect("seeding succeeds");
+    let ids = SequentialIdGenerator::starting_at(OrderId(10_001));
+    let service = OrderService::new(&repo, &payment, &sender).with_id_generator(&ids);
+    let recipient = seed_recipient();
+    let items = generate_items(2, 42);
+
+    let mut group = c.benchmark_group("place_order");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("in_memory_mock_console", |b| {
+        b.iter(|| {
+            black_box(
+                service
+                    .place_order(&recipient, items.clone())
+                    .expect("the order is placed"),
+            )
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, place_order);
+criterion_main!(benches);
@@ -0,0 +1,252 @@
+// =============================================================================
+// DTOs - The Application Boundary
+// =============================================================================
+//
+// `place_order` takes `Vec<LineItem>` and returns `Order`. Convenient, but
+// every driving adapter (HTTP handler, CLI, message consumer) then builds
+// domain structs by hand and depends on their field layout. Rename a field
+// in Order and the REST layer breaks.
+//
+// Data Transfer Objects decouple the two. They are dumb, flat structs made of
+// plain types (strings, integers) that are easy to deserialize from JSON or
+// command-line arguments. The application converts them at the door:
+//
+//     PlaceOrderRequest ──► (validate) ──► Vec<LineItem>, Recipient
+//     Order ──► OrderDto
+//
+// Validation still belongs to the domain. The conversion only rejects what
+// can't even be represented (a negative price has no `Money`), and reports it
+// with the same domain errors the rest of the application uses.
+
+use domain::{Contact, LineItem, Money, Order, OrderError, OrderRequestItem, ProductId, Recipient};
+
+/// Largest quantity accepted for one line of a request.
+///
+/// Each unit becomes its own [`LineItem`], so an absurd quantity would
+/// allocate an absurd amount of memory before any business rule runs.
+pub const MAX_QUANTITY: u32 = 1_000;
+
+/// Input of the "place order" use case, in plain types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceOrderRequest {
+    /// Who gets the confirmation.
+    pub recipient_name: String,
+    /// Where the confirmation goes.
+    pub recipient_email: String,
+    /// One `(name, price in cents, quantity)` tuple per line.
+    ///
+    /// The price is signed because that's what clients actually send:
+    /// a negative value must be rejected, not wrapped around.
+    pub items: Vec<(String, i64, u32)>,
+}
+
+impl PlaceOrderRequest {
+    /// Converts the lines into domain line items, one per unit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if there are no items, a price is
+    /// negative or too large for [`Money`], or a quantity is zero or above
+    /// [`MAX_QUANTITY`]; [`OrderError::InvalidLineItem`] if a name is blank
+    /// or too long (see [`LineItem::new`]).
+    pub fn line_items(&self) -> Result<Vec<LineItem>, OrderError> {
+        if self.items.is_empty() {
+            return Err(OrderError::InvalidOrder);
+        }
+
+        let mut line_items = Vec::new();
+        for (name, price_cents, quantity) in &self.items {
+            let price = u32::try_from(*price_cents).map_err(|_| OrderError::InvalidOrder)?;
+            if !(1..=MAX_QUANTITY).contains(quantity) {
+                return Err(OrderError::InvalidOrder);
+            }
+            let item = LineItem::new(name, Money(price))?;
+            for _ in 0..*quantity {
+                line_items.push(item.clone());
+            }
+        }
+        Ok(line_items)
+    }
+
+    /// Converts the lines into catalog requests, dropping the prices.
+    ///
+    /// Each name is taken as a product ID; the catalog will say what it
+    /// costs. Quantities are checked later, when the items are priced.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if there are no items.
+    pub fn requested_items(&self) -> Result<Vec<OrderRequestItem>, OrderError> {
+        if self.items.is_empty() {
+            return Err(OrderError::InvalidOrder);
+        }
+        Ok(self
+            .items
+            .iter()
+            .map(|(name, _ignored_price, quantity)| OrderRequestItem {
+                product: ProductId(name.clone()),
+                quantity: *quantity,
+            })
+            .collect())
+    }
+
+    /// Builds the validated recipient of the confirmation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidRecipient`] if the name or email is invalid.
+    pub fn recipient(&self) -> Result<Recipient, OrderError> {
+        Recipient::new(
+            self.recipient_name.clone(),
+            Contact::Email(self.recipient_email.clone()),
+        )
+    }
+
+    /// Turns domain line items back into request lines.
+    ///
+    /// Consecutive identical items are folded into one line with a quantity,
+    /// so `line_items` gives back exactly the same items.
+    #[must_use]
+    pub fn lines_from(items: &[LineItem]) -> Vec<(String, i64, u32)> {
+        group(items)
+            .into_iter()
+            .map(|(item, quantity)| (item.name.clone(), i64::from(item.price.0), quantity))
+            .collect()
+    }
+}
+
+/// Output of the order use cases, in plain types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderDto {
+    pub id: u32,
+    /// One human-readable line per distinct item, e.g. `"2 x Keyboard @ $79.99"`.
+    pub items: Vec<String>,
+    /// The total, formatted, e.g. `"$179.98"`.
+    pub total: String,
+    /// The status, e.g. `"Paid"`.
+    pub status: String,
+    /// Non-fatal problems that happened while placing the order.
+    pub warnings: Vec<String>,
+}
+
+impl From<&Order> for OrderDto {
+    fn from(order: &Order) -> Self {
+        Self {
+            id: order.id.0,
+            items: group(&order.items)
+                .into_iter()
+                .map(|(item, quantity)| format!("{quantity} x {} @ {}", item.name, item.price))
+                .collect(),
+            total: order.total.to_string(),
+            status: order.status.to_string(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Folds runs of identical items into (item, count) pairs.
+fn group(items: &[LineItem]) -> Vec<(&LineItem, u32)> {
+    let mut groups: Vec<(&LineItem, u32)> = Vec::new();
+    for item in items {
+        match groups.last_mut() {
+            Some((last, count)) if *last == item => *count += 1,
+            _ => groups.push((item, 1)),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{OrderId, OrderStatus};
+
+    fn request(items: Vec<(String, i64, u32)>) -> PlaceOrderRequest {
+        PlaceOrderRequest {
+            recipient_name: "Test".to_string(),
+            recipient_email: "test@example.com".to_string(),
+            items,
+        }
+    }
+
+    #[test]
+    fn request_expands_quantities() {
+        let items = request(vec![("Pen".to_string(), 150, 2)])
+            .line_items()
+            .unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|i| i.price == Money(150)));
+    }
+
+    #[test]
+    fn invalid_requests_map_to_invalid_order() {
+        let bad = [
+            vec![],
+            vec![("Pen".to_string(), -1, 1)],
+            vec![("Pen".to_string(), i64::from(u32::MAX) + 1, 1)],
+            vec![("Pen".to_string(), 150, 0)],
+            vec![("Pen".to_string(), 150, MAX_QUANTITY + 1)],
+        ];
+
+        for items in bad {
+            assert!(matches!(
+                request(items).line_items(),
+                Err(OrderError::InvalidOrder)
+            ));
+        }
+    }
+
+    #[test]
+    fn item_names_are_sanitized_or_refused() {
+        let items = request(vec![("  Pen\n".to_string(), 150, 1)])
+            .line_items()
+            .unwrap();
+        assert_eq!(items[0].name, "Pen");
+
+        assert!(matches!(
+            request(vec![("\n\t".to_string(), 150, 1)]).line_items(),
+            Err(OrderError::InvalidLineItem)
+        ));
+    }
+
+    #[test]
+    fn invalid_email_maps_to_invalid_recipient() {
+        let mut request = request(vec![("Pen".to_string(), 150, 1)]);
+        request.recipient_email = "not-an-email".to_string();
+
+        assert!(matches!(
+            request.recipient(),
+            Err(OrderError::InvalidRecipient)
+        ));
+    }
+
+    #[test]
+    fn request_lines_round_trip() {
+        let lines = vec![
+            ("Pen".to_string(), 150, 2),
+            ("Ink".to_string(), 499, 1),
+            ("Pen".to_string(), 150, 1),
+        ];
+
+        let items = request(lines.clone()).line_items().unwrap();
+
+        assert_eq!(PlaceOrderRequest::lines_from(&items), lines);
+    }
+
+    #[test]
+    fn order_dto_from_order() {
+        let items = request(vec![("Pen".to_string(), 150, 2)])
+            .line_items()
+            .unwrap();
+        let mut order = Order::new(OrderId(7), items).unwrap();
+        order.status = OrderStatus::Paid;
+
+        let dto = OrderDto::from(&order);
+
+        assert_eq!(dto.id, 7);
+        assert_eq!(dto.items, vec!["2 x Pen @ $1.50".to_string()]);
+        assert_eq!(dto.total, "$3.00");
+        assert_eq!(dto.status, "Paid");
+    }
+}
@@ -28,7 +28,284 @@
 // No adapter crates! We don't know if we're using PostgreSQL or a HashMap.
 // We just know we have something that implements OrderRepository.
 
-use domain::{LineItem, Order, OrderError, OrderId, OrderRepository, PaymentGateway, Sender};
+use domain::{
+    Address, AuditAction, AuditEntry, AuditLog, AuditOutcome, AuthorizationId, ChargeMetadata,
+    Clock, Currency, Customer, CustomerId, CustomerRepository, ExchangeRates, ExtraCharge,
+    GiftCardGateway, GiftCardId, GiftCardPayment, HealthCheck, HealthStatus, HistoryEntry,
+    IdGenerator, IdempotencyKey, IdempotencyStore, LineItem, Logger, LoyaltyLedger, Money,
+    NotificationEvent, NotificationOutbox, Order, OrderError, OrderId, OrderRepository,
+    OrderRequestItem, OrderStatus, PaymentFailureReason, PaymentGateway, PaymentReceipt,
+    PaymentStatus, PriceList, Recipient, RequestContext, Sender, Shipping, ShippingCalculator,
+    Subscription, SubscriptionId, SubscriptionRepository, TenantId,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+use trace::Subject;
+
+mod dto;
+mod trace;
+
+pub use dto::{MAX_QUANTITY, OrderDto, PlaceOrderRequest};
+// Outcome of `OrderService::flush_notifications`; lives in the domain, since
+// adapters flush queues of their own too
+pub use domain::FlushReport;
+
+// =============================================================================
+// Use Case Results and Policies
+// =============================================================================
+//
+// Not every problem deserves to turn a successful checkout into an error.
+// If the customer paid and the order is stored, a flaky email provider
+// shouldn't make the frontend say "checkout failed". Such problems are
+// WARNINGS: reported next to the result instead of replacing it.
+
+/// A successfully placed order, plus anything that went wrong on the side.
+#[derive(Debug)]
+pub struct PlacedOrder {
+    pub order: Order,
+    pub warnings: Vec<OrderWarning>,
+}
+
+/// A non-fatal problem that happened while placing an order.
+#[derive(Debug)]
+pub enum OrderWarning {
+    /// The order is paid and stored, but the confirmation didn't go out.
+    NotificationFailed(OrderError),
+    /// The order is paid and stored, but its loyalty points weren't
+    /// credited. Marketing can credit them by hand; the customer still has
+    /// their order.
+    LoyaltyNotCredited(OrderError),
+}
+
+impl fmt::Display for OrderWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotificationFailed(e) => write!(f, "confirmation not sent ({e})"),
+            Self::LoyaltyNotCredited(e) => write!(f, "loyalty points not credited ({e})"),
+        }
+    }
+}
+
+/// What one [`OrderService::run_due_subscriptions`] did.
+///
+/// A failing subscription doesn't stop the batch: it ends up in `failed`,
+/// and the others still run.
+#[derive(Debug, Default)]
+pub struct SubscriptionRun {
+    /// The order placed for each subscription that ran.
+    pub placed: Vec<(SubscriptionId, Order)>,
+    /// The subscriptions whose order couldn't be placed, and why.
+    pub failed: Vec<(SubscriptionId, OrderError)>,
+}
+
+/// A priced preview of an order: what the customer would pay.
+///
+/// Tax is always zero for now; it's here so the frontend already has
+/// somewhere to show it once tax rules exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    /// What the items cost.
+    pub subtotal: Money,
+    /// What shipping them costs: zero when they don't ship.
+    pub shipping: Money,
+    pub tax: Money,
+    pub total: Money,
+    pub warnings: Vec<QuoteWarning>,
+}
+
+/// Something suspicious about quoted items that doesn't make them invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuoteWarning {
+    /// An item costs nothing. Allowed, but often a catalog mistake.
+    FreeItem(String),
+}
+
+impl fmt::Display for QuoteWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FreeItem(name) => write!(f, "'{name}' is free"),
+        }
+    }
+}
+
+/// Outcome of placing a batch of orders.
+///
+/// One failed entry doesn't stop the batch: it is recorded with its index
+/// in the input, and the remaining entries are still placed.
+#[derive(Debug, Default)]
+pub struct BatchResult {
+    /// The orders that went through, in input order.
+    pub placed: Vec<PlacedOrder>,
+    /// The entries that didn't, as (index in the batch, error).
+    pub failures: Vec<(usize, OrderError)>,
+}
+
+impl BatchResult {
+    /// True if every entry of the batch was placed.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The health of the three adapters behind an [`OrderService`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    pub repository: HealthStatus,
+    pub payment: HealthStatus,
+    pub sender: HealthStatus,
+}
+
+impl HealthReport {
+    /// Each adapter's status, named after its port.
+    #[must_use]
+    pub const fn components(&self) -> [(&'static str, &HealthStatus); 3] {
+        [
+            ("repository", &self.repository),
+            ("payment", &self.payment),
+            ("sender", &self.sender),
+        ]
+    }
+
+    /// The service's status: the worst of its adapters', with the reasons
+    /// of every adapter in that state.
+    ///
+    /// A degraded sender and a healthy rest make a degraded service; one
+    /// unhealthy adapter makes an unhealthy service, whatever the others say.
+    #[must_use]
+    pub fn overall(&self) -> HealthStatus {
+        let components = self.components();
+        let Some(worst) = components.iter().map(|(_, status)| *status).max() else {
+            return HealthStatus::Healthy;
+        };
+        let reasons = || -> String {
+            let reasons: Vec<String> = components
+                .iter()
+                .filter(|(_, status)| {
+                    std::mem::discriminant(*status) == std::mem::discriminant(worst)
+                })
+                .map(|(name, status)| match status {
+                    HealthStatus::Degraded(reason) | HealthStatus::Unhealthy(reason) => {
+                        format!("{name}: {reason}")
+                    }
+                    HealthStatus::Healthy => name.to_string(),
+                })
+                .collect();
+            reasons.join("; ")
+        };
+        match worst {
+            HealthStatus::Healthy => HealthStatus::Healthy,
+            HealthStatus::Degraded(_) => HealthStatus::Degraded(reasons()),
+            HealthStatus::Unhealthy(_) => HealthStatus::Unhealthy(reasons()),
+        }
+    }
+}
+
+/// What `place_order` (and `add_item_to_order`) does when the confirmation
+/// can't be sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotificationFailurePolicy {
+    /// Strict: refund the customer and fail the whole order.
+    FailOrder,
+    /// Keep the order and report an [`OrderWarning::NotificationFailed`].
+    #[default]
+    WarnOnly,
+}
+
+/// In which order `place_order` takes the money and stores the order.
+///
+/// A payment provider and a database can't share a transaction, so some
+/// failure will always leave them disagreeing. The policy chooses which
+/// disagreement we'd rather clean up.
+///
+/// | | Payment fails | Storage fails |
+/// |---|---|---|
+/// | `ChargeFirst` | nothing stored, or Cancelled if the capture fails | hold voided, or money refunded |
+/// | `SaveFirst` | order stored as Cancelled | nothing charged, or money refunded |
+/// | `AwaitSettlement` | order stored as Cancelled | nothing charged, or money refunded |
+///
+/// In both cases a failure AFTER the capture is compensated with a refund
+/// ([`OrderError::StorageFailedAfterCharge`]). If that refund fails too, or
+/// the process crashes in between, someone has to reconcile by hand:
+/// - `ChargeFirst`: a payment may exist with no order behind it, so you
+///   reconcile from the payment provider's side
+/// - `SaveFirst`: every payment has an order, but a Pending order may have
+///   been paid, so you reconcile from the database's side
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderingPolicy {
+    /// Authorize the payment first, then store the order, then capture.
+    /// A declined card leaves no trace in the database.
+    #[default]
+    ChargeFirst,
+    /// Store the order as Pending first, then authorize and capture, then
+    /// update it to Paid. Every attempt is on file, declined ones included.
+    SaveFirst,
+    /// Store the order as Pending first, then charge in one step. A charge
+    /// the provider leaves [`PaymentStatus::Pending`] leaves the order
+    /// Pending too, with no confirmation sent: the provider's webhook
+    /// finishes the job through [`OrderService::handle_payment_confirmation`].
+    AwaitSettlement,
+}
+
+// =============================================================================
+// Observers - Watching Use Cases Without Changing Them
+// =============================================================================
+//
+// Ops wants numbers: orders placed, payments failed, checkout latency.
+// We could call a metrics library from every use case... and then the
+// application crate would depend on Prometheus. Not great.
+//
+// Instead the application defines a small hook trait. A metrics adapter
+// implements it and forwards to Prometheus, StatsD, logs, whatever.
+// Every callback has an empty default, so an observer only overrides what
+// it cares about.
+//
+// Observers WATCH, they don't participate: they return nothing, and a
+// panicking observer is contained, so it can never change the outcome of a
+// use case.
+
+/// Hooks called by [`OrderService`] as use cases run.
+pub trait OrderObserver {
+    /// An order was placed successfully, `elapsed` after the call started.
+    fn on_order_placed(&self, _order: &Order, _elapsed: Duration) {}
+
+    /// The payment gateway returned an error.
+    fn on_payment_failed(&self, _error: &OrderError) {}
+
+    /// An order was looked up.
+    fn on_order_retrieved(&self, _id: OrderId, _found: bool) {}
+}
+
+/// Driving port: how the payment provider's verdict reaches the application.
+///
+/// The other ports are the application calling out. This one is the outside
+/// calling in: a webhook endpoint parses the provider's request, and calls
+/// `confirm`. [`OrderService`] implements it.
+pub trait PaymentConfirmation {
+    /// Records how the payment `transaction_id` ended, and returns its order.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`OrderService::handle_payment_confirmation`].
+    fn confirm(&self, transaction_id: &str, outcome: PaymentStatus) -> Result<Order, OrderError>;
+}
+
+/// Audit log that discards every entry.
+///
+/// The default of [`OrderService`], for apps (and tests) with no compliance
+/// requirements. A Null Object: the service always has a log to call.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullAuditLog;
+
+impl AuditLog for NullAuditLog {
+    fn record(&self, _entry: AuditEntry) -> Result<(), OrderError> {
+        Ok(())
+    }
+}
 
 // =============================================================================
 // Order Service - The Use Case Handler
@@ -51,30 +328,102 @@ use domain::{LineItem, Order, OrderError, OrderId, OrderRepository, PaymentGatew
 /// - `R`: Repository adapter (where orders are stored)
 /// - `P`: Payment adapter (how payments are processed)
 /// - `N`: Notification adapter (how customers are notified)
-#[derive(Debug)]
+///
+/// Optional collaborators (idempotency store, customer repository, ID
+/// generator) are not type parameters. They are plugged in with `with_*`
+/// methods and held as trait objects, so callers who don't need them never
+/// have to name them.
+///
+/// Every method takes `&self`: one service can be shared between threads
+/// (it is `Send + Sync` whenever its adapters are).
+///
+/// The adapters may be trait objects too (`dyn OrderRepository + Sync`...),
+/// for wiring chosen at runtime: see the app's `--scenario`.
 pub struct OrderService<'a, R, P, N>
 where
-    R: OrderRepository,
-    P: PaymentGateway,
-    N: Sender,
+    R: OrderRepository + ?Sized,
+    P: PaymentGateway + ?Sized,
+    N: Sender + ?Sized,
 {
     // These fields hold our adapters, but we only know them by their traits!
     // We don't know if `repository` is PostgreSQL or InMemory.
     // We don't care! That's abstraction at work.
-    repository: &'a mut R,
+    repository: &'a R,
     payment: &'a P,
     sender: &'a N,
 
-    // Application state - not business logic.
-    // In a real app, IDs would come from the database or UUID generator.
-    next_id: u32,
+    // Optional ports: None until the caller plugs one in.
+    // `+ Sync` keeps the whole service shareable between threads.
+    idempotency: Option<&'a (dyn IdempotencyStore + Sync)>,
+    customers: Option<&'a (dyn CustomerRepository + Sync)>,
+    ids: Option<&'a (dyn IdGenerator + Sync)>,
+    outbox: Option<&'a (dyn NotificationOutbox + Sync)>,
+    gift_cards: Option<&'a (dyn GiftCardGateway + Sync)>,
+    loyalty: Option<&'a (dyn LoyaltyLedger + Sync)>,
+    subscriptions: Option<&'a (dyn SubscriptionRepository + Sync)>,
+    audit_log: &'a (dyn AuditLog + Sync),
+    clock: Option<&'a (dyn Clock + Sync)>,
+    logger: Option<&'a (dyn Logger + Sync)>,
+    price_list: Option<&'a (dyn PriceList + Sync)>,
+    shipping: Option<&'a (dyn ShippingCalculator + Sync)>,
+    observers: Vec<&'a (dyn OrderObserver + Sync)>,
+
+    notification_failure_policy: NotificationFailurePolicy,
+    ordering_policy: OrderingPolicy,
+    duplicate_guard: Option<DuplicateGuard>,
+    tenant: TenantId,
+
+    // Who to confirm pending payments to, by transaction ID
+    awaiting_settlement: Mutex<HashMap<String, Recipient>>,
+    // Used when no ID generator is plugged in: counts from 1 for this service
+    default_ids: CountingIds,
+    // Numbers the correlation IDs handed to the ports
+    next_request: AtomicU64,
+}
+
+// Manual impl: the optional trait objects have no Debug of their own.
+impl<R, P, N> fmt::Debug for OrderService<'_, R, P, N>
+where
+    R: OrderRepository + fmt::Debug + ?Sized,
+    P: PaymentGateway + fmt::Debug + ?Sized,
+    N: Sender + fmt::Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderService")
+            .field("repository", &self.repository)
+            .field("payment", &self.payment)
+            .field("sender", &self.sender)
+            .field("idempotency", &self.idempotency.is_some())
+            .field("customers", &self.customers.is_some())
+            .field("ids", &self.ids.is_some())
+            .field("outbox", &self.outbox.is_some())
+            .field("gift_cards", &self.gift_cards.is_some())
+            .field("loyalty", &self.loyalty.is_some())
+            .field("subscriptions", &self.subscriptions.is_some())
+            .field("clock", &self.clock.is_some())
+            .field("logger", &self.logger.is_some())
+            .field("price_list", &self.price_list.is_some())
+            .field("shipping", &self.shipping.is_some())
+            .field("observers", &self.observers.len())
+            .field(
+                "notification_failure_policy",
+                &self.notification_failure_policy,
+            )
+            .field("ordering_policy", &self.ordering_policy)
+            .field("tenant", &self.tenant)
+            .field(
+                "duplicate_window",
+                &self.duplicate_guard.as_ref().map(|guard| guard.window),
+            )
+            .finish_non_exhaustive()
+    }
 }
 
 impl<'a, R, P, N> OrderService<'a, R, P, N>
 where
-    R: OrderRepository,
-    P: PaymentGateway,
-    N: Sender,
+    R: OrderRepository + ?Sized,
+    P: PaymentGateway + ?Sized,
+    N: Sender + ?Sized,
 {
     /// Creates a new order service with injected dependencies.
     ///
@@ -86,180 +435,4915 @@ where
     /// - Testing: pass mock adapters, no real database needed
     /// - Flexibility: swap PostgreSQL for MongoDB without changing this code
     /// - Clarity: dependencies are explicit in the function signature
-    pub fn new(repository: &'a mut R, payment: &'a P, sender: &'a N) -> Self {
+    pub fn new(repository: &'a R, payment: &'a P, sender: &'a N) -> Self {
         Self {
             repository,
             payment,
             sender,
-            next_id: 1,
+            idempotency: None,
+            customers: None,
+            ids: None,
+            outbox: None,
+            gift_cards: None,
+            loyalty: None,
+            subscriptions: None,
+            audit_log: &NullAuditLog,
+            clock: None,
+            logger: None,
+            price_list: None,
+            shipping: None,
+            observers: Vec::new(),
+            notification_failure_policy: NotificationFailurePolicy::default(),
+            ordering_policy: OrderingPolicy::default(),
+            duplicate_guard: None,
+            tenant: TenantId::default(),
+            awaiting_settlement: Mutex::new(HashMap::new()),
+            default_ids: CountingIds::default(),
+            next_request: AtomicU64::new(1),
         }
     }
 
-    /// Places a new order - the main use case.
-    ///
-    /// Look at what this method does:
-    /// 1. Generate an ID (application concern)
-    /// 2. Create the Order (delegates to domain)
-    /// 3. Charge payment (calls port -> adapter)
-    /// 4. Save order (calls port -> adapter)
-    /// 5. Send notification (calls port -> adapter)
-    ///
-    /// The ORDER of operations matters! That's orchestration.
-    /// We charge before saving because we don't want to save unpaid orders.
+    /// Plugs in the store used by [`OrderService::place_order_idempotent`].
+    #[must_use]
+    pub fn with_idempotency_store(mut self, store: &'a (dyn IdempotencyStore + Sync)) -> Self {
+        self.idempotency = Some(store);
+        self
+    }
+
+    /// Plugs in the repository used by the customer use cases.
+    #[must_use]
+    pub fn with_customer_repository(
+        mut self,
+        customers: &'a (dyn CustomerRepository + Sync),
+    ) -> Self {
+        self.customers = Some(customers);
+        self
+    }
+
+    /// Plugs in where new order and customer IDs come from.
     ///
-    /// # Errors
+    /// Without one, the service counts from 1 on its own. That's fine for a
+    /// single service, but two services (or two processes) sharing a
+    /// repository need a shared generator, or their IDs will collide.
+    #[must_use]
+    pub fn with_id_generator(mut self, ids: &'a (dyn IdGenerator + Sync)) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    /// Routes order confirmations through an outbox instead of sending them
+    /// right away.
     ///
-    /// Returns error if any step fails (validation, payment, storage, notification).
-    pub fn place_order(&mut self, items: Vec<LineItem>) -> Result<Order, OrderError> {
-        // Step 1: Generate ID (application layer responsibility)
-        let order_id = OrderId(self.next_id);
-        self.next_id += 1;
+    /// `place_order` then only enqueues the confirmation; it goes out on the
+    /// next [`OrderService::flush_notifications`].
+    #[must_use]
+    pub fn with_outbox(mut self, outbox: &'a (dyn NotificationOutbox + Sync)) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
 
-        // Step 2: Create order using domain logic
-        // Order::new() enforces business rules
-        let order = Order::new(order_id, items)?;
+    /// Plugs in the gift card provider used by [`OrderService::place_order_split`].
+    #[must_use]
+    pub fn with_gift_cards(mut self, gift_cards: &'a (dyn GiftCardGateway + Sync)) -> Self {
+        self.gift_cards = Some(gift_cards);
+        self
+    }
 
-        // Steps 3-5: Orchestrate external operations
-        // Each call goes through a port to an adapter.
-        // We don't know what adapter and we don't care!
-        self.payment.charge(order.total)?;
-        self.repository.save(&order)?;
-        self.sender.send(&order)?;
+    /// Plugs in the ledger crediting loyalty points for the orders of
+    /// registered customers ([`OrderService::place_order_for`]).
+    ///
+    /// Without one, nobody earns points.
+    #[must_use]
+    pub fn with_loyalty_ledger(mut self, loyalty: &'a (dyn LoyaltyLedger + Sync)) -> Self {
+        self.loyalty = Some(loyalty);
+        self
+    }
 
-        Ok(order)
+    /// Plugs in the repository used by the subscription use cases.
+    ///
+    /// Subscriptions belong to registered customers: they need a customer
+    /// repository too ([`OrderService::with_customer_repository`]).
+    #[must_use]
+    pub fn with_subscription_repository(
+        mut self,
+        subscriptions: &'a (dyn SubscriptionRepository + Sync),
+    ) -> Self {
+        self.subscriptions = Some(subscriptions);
+        self
     }
 
-    /// Retrieves an order by ID.
+    /// Plugs in where the audit trail goes.
     ///
-    /// A simple use case: just delegate to the repository.
+    /// Defaults to [`NullAuditLog`], which keeps nothing.
+    #[must_use]
+    pub fn with_audit_log(mut self, audit_log: &'a (dyn AuditLog + Sync)) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /// Scopes the service to one storefront.
     ///
-    /// # Errors
+    /// Orders placed through this service belong to `tenant`, and every
+    /// lookup only sees `tenant`'s orders. Build one service per tenant
+    /// (they're cheap: a handful of references). Defaults to
+    /// `TenantId::default()`.
     ///
-    /// Returns error if retrieval fails.
-    pub fn get_order(&self, id: OrderId) -> Result<Option<Order>, OrderError> {
-        self.repository.find(id)
+    /// Idempotency keys aren't scoped: give each tenant its own
+    /// [`IdempotencyStore`], or two storefronts could share a key.
+    #[must_use]
+    pub const fn with_tenant(mut self, tenant: TenantId) -> Self {
+        self.tenant = tenant;
+        self
     }
-}
 
-// =============================================================================
-// Tests
-// =============================================================================
-//
-// Application tests need mock adapters because we're testing orchestration.
-// We create simple test doubles that implement the port traits.
-// No real database, no real payment API - just verifying the flow works.
+    /// Plugs in where the current time comes from.
+    ///
+    /// Without one, the service reads the system clock.
+    #[must_use]
+    pub fn with_clock(mut self, clock: &'a (dyn Clock + Sync)) -> Self {
+        self.clock = Some(clock);
+        self
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use domain::Money;
-    use std::cell::RefCell;
-    use std::collections::HashMap;
+    /// Sends the service's own log lines (a best-effort step that failed) to
+    /// `logger` instead of stdout.
+    #[must_use]
+    pub fn with_logger(mut self, logger: &'a (dyn Logger + Sync)) -> Self {
+        self.logger = Some(logger);
+        self
+    }
 
-    // -------------------------------------------------------------------------
-    // Test Doubles (Mock Adapters)
-    // -------------------------------------------------------------------------
-    // These are minimal implementations just for testing.
-    // They prove that our application layer works with ANY implementation
-    // of the port traits.
+    /// Plugs in the catalog used by [`OrderService::place_order_from_catalog`].
+    ///
+    /// With one, [`OrderService::place_order_dto`] goes through the catalog
+    /// too, and the prices in the request are ignored.
+    #[must_use]
+    pub fn with_price_list(mut self, price_list: &'a (dyn PriceList + Sync)) -> Self {
+        self.price_list = Some(price_list);
+        self
+    }
 
-    struct MockRepository {
-        orders: RefCell<HashMap<OrderId, Order>>,
+    /// Plugs in what shipping costs.
+    ///
+    /// With one, an order for a recipient with an address gets its
+    /// [`Order::shipping`], charged with the items. It's priced again when
+    /// the items change: an item added, an order split or merged.
+    /// Recipients without an address (a download, a gift card) ship nothing.
+    #[must_use]
+    pub fn with_shipping_calculator(
+        mut self,
+        shipping: &'a (dyn ShippingCalculator + Sync),
+    ) -> Self {
+        self.shipping = Some(shipping);
+        self
     }
 
-    impl MockRepository {
-        fn new() -> Self {
-            Self {
-                orders: RefCell::new(HashMap::new()),
-            }
-        }
+    /// Rejects an order whose items match one placed less than `window` ago.
+    ///
+    /// The double-click guard: the second click gets
+    /// [`OrderError::DuplicateSubmission`] pointing at the first order instead
+    /// of charging the customer twice. Items are compared as a set of
+    /// (name, price) with their quantities, in any order.
+    ///
+    /// Off by default. The recent orders live in this service only, so two
+    /// services (or two processes) don't see each other's; and two clicks
+    /// racing each other can both get through. When that matters, use
+    /// [`OrderService::place_order_idempotent`] with a key from the client.
+    #[must_use]
+    pub fn with_duplicate_guard(mut self, window: Duration) -> Self {
+        self.duplicate_guard = Some(DuplicateGuard {
+            window,
+            recent: Mutex::new(Vec::new()),
+        });
+        self
     }
 
-    impl OrderRepository for MockRepository {
-        fn save(&mut self, order: &Order) -> Result<(), OrderError> {
-            self.orders.borrow_mut().insert(order.id, order.clone());
-            Ok(())
-        }
+    /// Registers an observer, called after the ones already registered.
+    pub fn add_observer(&mut self, observer: &'a (dyn OrderObserver + Sync)) {
+        self.observers.push(observer);
+    }
 
-        fn find(&self, id: OrderId) -> Result<Option<Order>, OrderError> {
-            Ok(self.orders.borrow().get(&id).cloned())
-        }
+    /// Chooses what happens when an order confirmation can't be sent.
+    ///
+    /// Defaults to [`NotificationFailurePolicy::WarnOnly`].
+    #[must_use]
+    pub const fn with_notification_failure_policy(
+        mut self,
+        policy: NotificationFailurePolicy,
+    ) -> Self {
+        self.notification_failure_policy = policy;
+        self
     }
 
-    struct MockPayment;
+    /// Chooses whether `place_order` takes the money or stores the order first.
+    ///
+    /// Defaults to [`OrderingPolicy::ChargeFirst`].
+    #[must_use]
+    pub const fn with_ordering_policy(mut self, policy: OrderingPolicy) -> Self {
+        self.ordering_policy = policy;
+        self
+    }
 
-    impl PaymentGateway for MockPayment {
-        fn charge(&self, _amount: Money) -> Result<(), OrderError> {
-            Ok(())
-        }
+    /// Registers a new customer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidCustomer`] if the name or email is
+    /// invalid, or [`OrderError::StorageFailed`] if saving fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no repository was plugged in with
+    /// [`OrderService::with_customer_repository`].
+    pub fn register_customer(&self, name: &str, email: &str) -> Result<Customer, OrderError> {
+        // Validate before consuming an ID
+        Customer::validate(name, email)?;
+        let customer = Customer::new(self.ids().next_customer_id(), name, email)?;
+
+        self.customers
+            .expect("register_customer requires a customer repository")
+            .save(&customer)?;
+
+        Ok(customer)
     }
 
-    struct MockSender;
+    /// Places an order for a registered customer.
+    ///
+    /// Same as [`OrderService::place_order`], except the recipient comes
+    /// from the customer record, and the order remembers who placed it.
+    ///
+    /// Once the order is paid and stored, the customer earns its
+    /// [`Order::loyalty_points`] (with a ledger plugged in, see
+    /// [`OrderService::with_loyalty_ledger`]). A ledger failure doesn't undo
+    /// the order: it becomes an [`OrderWarning::LoyaltyNotCredited`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::CustomerNotFound`] for an unknown customer,
+    /// otherwise the same errors as [`OrderService::place_order`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no repository was plugged in with
+    /// [`OrderService::with_customer_repository`].
+    pub fn place_order_for(
+        &self,
+        customer_id: CustomerId,
+        items: Vec<LineItem>,
+    ) -> Result<PlacedOrder, OrderError> {
+        let customer = self
+            .customers
+            .expect("place_order_for requires a customer repository")
+            .find(customer_id)?
+            .ok_or(OrderError::CustomerNotFound(customer_id))?;
 
-    impl Sender for MockSender {
-        fn send(&self, _order: &Order) -> Result<(), OrderError> {
-            Ok(())
+        let ctx = self.context(Some(customer_id));
+        let mut placed =
+            self.place_order_with_key(&ctx, &customer.recipient(), items, None, None)?;
+        if let Err(e) = self.credit_points(&ctx, &mut placed.order) {
+            placed.warnings.push(OrderWarning::LoyaltyNotCredited(e));
         }
+        Ok(placed)
     }
 
-    struct FailingPayment;
+    /// Subscribes a registered customer to `items`, every `interval_days`
+    /// days. The first order goes out one interval from now.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::CustomerNotFound`] for an unknown customer,
+    /// [`OrderError::InvalidOrder`] for items that couldn't make an order
+    /// or an interval of 0 days, [`OrderError::StorageFailed`] if no
+    /// subscription repository is plugged in, or the repository's error if
+    /// saving fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no customer repository was plugged in.
+    pub fn create_subscription(
+        &self,
+        customer_id: CustomerId,
+        items: Vec<LineItem>,
+        interval_days: u32,
+    ) -> Result<Subscription, OrderError> {
+        self.customers
+            .expect("create_subscription requires a customer repository")
+            .find(customer_id)?
+            .ok_or(OrderError::CustomerNotFound(customer_id))?;
+        // Validate before consuming an ID
+        Subscription::validate(&items, interval_days)?;
+
+        let now = self.now();
+        let mut subscription = Subscription::new(
+            self.ids().next_subscription_id(),
+            customer_id,
+            items,
+            interval_days,
+            now,
+        )?;
+        // Due now, and moved one interval further: the first order isn't today's
+        subscription.advance(now);
+        self.subscription_repository("create_subscription")?
+            .save(&subscription)?;
+        Ok(subscription)
+    }
 
-    impl PaymentGateway for FailingPayment {
-        fn charge(&self, _amount: Money) -> Result<(), OrderError> {
-            Err(OrderError::PaymentFailed)
-        }
+    /// Stops a subscription. It's kept, and never runs again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::SubscriptionNotFound`] for an unknown ID,
+    /// [`OrderError::StorageFailed`] if no subscription repository is
+    /// plugged in, or the repository's error if loading or saving fails.
+    pub fn cancel_subscription(&self, id: SubscriptionId) -> Result<Subscription, OrderError> {
+        let subscriptions = self.subscription_repository("cancel_subscription")?;
+        let mut subscription = subscriptions
+            .find(id)?
+            .ok_or(OrderError::SubscriptionNotFound(id))?;
+        subscription.cancel();
+        subscriptions.save(&subscription)?;
+        Ok(subscription)
     }
 
-    // -------------------------------------------------------------------------
-    // Actual Tests
-    // -------------------------------------------------------------------------
+    /// Places an order for every subscription due at `now`.
+    ///
+    /// The scheduler's use case: a cron job calls it every hour or so.
+    /// For each due subscription:
+    /// 1. Advance its next run past `now`, and save it
+    /// 2. Place the order, as [`OrderService::place_order_for`] would
+    ///
+    /// Saving first means a crash in between loses one order rather than
+    /// placing it twice. A failed order (a declined card) doesn't retry on
+    /// the next call either: it's reported, and the schedule goes on.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if no subscription repository
+    /// is plugged in, or the repository's error if the due subscriptions
+    /// can't be listed. Anything failing for one subscription ends up in
+    /// [`SubscriptionRun::failed`] instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a subscription is due and no customer repository was
+    /// plugged in.
+    pub fn run_due_subscriptions(&self, now: SystemTime) -> Result<SubscriptionRun, OrderError> {
+        let subscriptions = self.subscription_repository("run_due_subscriptions")?;
+        let mut run = SubscriptionRun::default();
 
-    #[test]
-    fn place_order_succeeds() {
-        let mut service = OrderService::new(MockRepository::new(), MockPayment, MockSender);
+        for mut subscription in subscriptions.find_due(now)? {
+            subscription.advance(now);
+            let placed = subscriptions.save(&subscription).and_then(|()| {
+                self.place_order_for(subscription.customer, subscription.items.clone())
+            });
+            match placed {
+                Ok(placed) => run.placed.push((subscription.id, placed.order)),
+                Err(e) => {
+                    self.log(&format!("[Subscription] {} failed: {e}", subscription.id));
+                    run.failed.push((subscription.id, e));
+                }
+            }
+        }
+        Ok(run)
+    }
 
-        let items = vec![LineItem {
-            name: "Test".to_string(),
-            price: Money(1000),
-        }];
+    /// Places a new order - the main use case.
+    ///
+    /// Look at what this method does:
+    /// 1. Validate the items, then generate an ID (application concern)
+    /// 2. Create the Order (delegates to domain), with a shipping line when
+    ///    the recipient has an address and a [`ShippingCalculator`] is
+    ///    plugged in
+    /// 3. Authorize payment: hold the funds (calls port -> adapter)
+    /// 4. Insert order (calls port -> adapter)
+    /// 5. Capture payment: take the held funds, mark the order Paid
+    ///    (calls port -> adapter)
+    /// 6. Notify the recipient (calls port -> adapter)
+    ///
+    /// That's the default [`OrderingPolicy::ChargeFirst`]. Under
+    /// [`OrderingPolicy::SaveFirst`], step 4 comes before step 3.
+    ///
+    /// The ORDER of operations matters! That's orchestration.
+    /// We only take the money once the order is safely stored. If the process
+    /// crashes between steps 3 and 5, the customer has a hold that expires on
+    /// its own, not a charge with no order behind it. If the insert fails, we
+    /// void the authorization right away; if the capture fails, we void it
+    /// and store the order as Cancelled.
+    ///
+    /// Once the payment is captured, the money is gone from the customer's
+    /// account. Any later failure is COMPENSATED: we refund the full amount
+    /// before returning the error, and the error says whether that refund
+    /// worked ([`OrderError::StorageFailedAfterCharge`],
+    /// [`OrderError::NotificationFailedAfterCharge`]).
+    ///
+    /// The one exception is the notification under the default
+    /// [`NotificationFailurePolicy::WarnOnly`]: the order is paid and stored,
+    /// so we keep it and report an [`OrderWarning`] in the [`PlacedOrder`].
+    /// We `insert` rather than overwrite, so an ID that is already taken
+    /// surfaces immediately as [`OrderError::DuplicateOrder`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if any step fails (validation, payment, storage, and
+    /// notification under [`NotificationFailurePolicy::FailOrder`]).
+    /// Returns [`OrderError::DuplicateOrder`] if the generated ID is already stored.
+    pub fn place_order(
+        &self,
+        recipient: &Recipient,
+        items: Vec<LineItem>,
+    ) -> Result<PlacedOrder, OrderError> {
+        self.place_order_with_key(&self.context(None), recipient, items, None, None)
+    }
 
-        let result = service.place_order(items);
+    /// Places an order for catalog products, at catalog prices.
+    ///
+    /// [`OrderService::place_order`] trusts the prices in its line items:
+    /// fine for a back-office tool, not for a storefront where anyone can
+    /// send a keyboard at one cent. Here the client only says what and how
+    /// many; the [`PriceList`] says how much. Each unit becomes its own
+    /// [`LineItem`], named after the product.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if a quantity is zero or above
+    /// [`MAX_QUANTITY`], [`OrderError::UnknownProduct`] for a product the
+    /// catalog doesn't have, otherwise the same errors as
+    /// [`OrderService::place_order`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no price list was plugged in with
+    /// [`OrderService::with_price_list`].
+    pub fn place_order_from_catalog(
+        &self,
+        recipient: &Recipient,
+        items: &[OrderRequestItem],
+    ) -> Result<PlacedOrder, OrderError> {
+        let items = self.price_items(items)?;
+        self.place_order(recipient, items)
+    }
 
-        assert!(result.is_ok());
-        let order = result.unwrap();
-        assert_eq!(order.id, OrderId(1));
-        assert_eq!(order.total, Money(1000));
+    /// Places an order paid partly, or fully, with a gift card.
+    ///
+    /// The gift card pays first, up to its balance, and the customer's card
+    /// pays the rest. A gift card covering the whole order means no card
+    /// payment at all; an empty one means the card pays everything.
+    ///
+    /// If anything fails once the gift card was debited (the card is
+    /// declined, storage fails...), the debit is reversed before the error
+    /// is returned.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`OrderService::place_order`], plus
+    /// [`OrderError::GiftCardNotFound`] for a card the provider doesn't know.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no gift card provider was plugged in with
+    /// [`OrderService::with_gift_cards`].
+    pub fn place_order_split(
+        &self,
+        recipient: &Recipient,
+        items: Vec<LineItem>,
+        gift_card: GiftCardId,
+    ) -> Result<PlacedOrder, OrderError> {
+        let ctx = self.context(None);
+        self.place_order_with_key(&ctx, recipient, items, None, Some(gift_card))
     }
 
-    #[test]
-    fn place_order_payment_fails() {
-        // Using FailingPayment instead of MockPayment
-        let mut service = OrderService::new(MockRepository::new(), FailingPayment, MockSender);
+    /// Places an order described by a [`PlaceOrderRequest`].
+    ///
+    /// Same use case as [`OrderService::place_order`], for driving adapters
+    /// that speak DTOs instead of domain types. Warnings end up as strings
+    /// in [`OrderDto::warnings`].
+    ///
+    /// With a price list plugged in, the request's prices are ignored: the
+    /// item names are looked up as [`ProductId`](domain::ProductId)s, as in
+    /// [`OrderService::place_order_from_catalog`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] or [`OrderError::InvalidRecipient`]
+    /// if the request doesn't convert, otherwise the errors of `place_order`
+    /// (or `place_order_from_catalog`).
+    pub fn place_order_dto(&self, request: &PlaceOrderRequest) -> Result<OrderDto, OrderError> {
+        let recipient = request.recipient()?;
+        let placed = if self.price_list.is_some() {
+            self.place_order_from_catalog(&recipient, &request.requested_items()?)?
+        } else {
+            self.place_order(&recipient, request.line_items()?)?
+        };
 
-        let items = vec![LineItem {
-            name: "Test".to_string(),
-            price: Money(1000),
-        }];
+        let mut dto = OrderDto::from(&placed.order);
+        dto.warnings = placed.warnings.iter().map(ToString::to_string).collect();
+        Ok(dto)
+    }
 
-        let result = service.place_order(items);
+    /// Prices items the way `place_order` would, without placing anything:
+    /// shipping to `destination` included, for a recipient with an address.
+    ///
+    /// A dry run: validation and pricing only. No ID is consumed, and neither
+    /// the payment gateway nor the repository is called: only the domain's
+    /// [`Order::total_of`] and the [`ShippingCalculator`], if one is plugged in.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if `place_order` would reject the
+    /// items, or the shipping calculator's error.
+    pub fn quote(
+        &self,
+        items: &[LineItem],
+        destination: Option<&Address>,
+    ) -> Result<Quote, OrderError> {
+        let subtotal = Order::total_of(items)?;
+        let shipping = self
+            .shipping_to(items, destination)?
+            .map_or(Money(0), |shipping| shipping.cost);
+        let tax = Money(0);
+        let warnings = items
+            .iter()
+            .filter(|item| item.price == Money(0))
+            .map(|item| QuoteWarning::FreeItem(item.name.clone()))
+            .collect();
 
-        assert!(matches!(result, Err(OrderError::PaymentFailed)));
+        Ok(Quote {
+            subtotal,
+            shipping,
+            tax,
+            total: subtotal
+                .checked_add(shipping)
+                .and_then(|total| total.checked_add(tax))
+                .ok_or(OrderError::InvalidOrder)?,
+            warnings,
+        })
     }
 
-    #[test]
-    fn get_order_returns_saved_order() {
-        let mut service = OrderService::new(MockRepository::new(), MockPayment, MockSender);
+    /// Places one order per entry of `batches`, all for the same recipient.
+    ///
+    /// Used by bulk imports. Each entry goes through the full `place_order`
+    /// flow, so each gets its own ID and only valid entries are charged.
+    /// A failing entry is recorded in [`BatchResult::failures`] and the batch
+    /// moves on to the next one.
+    pub fn place_orders(&self, recipient: &Recipient, batches: Vec<Vec<LineItem>>) -> BatchResult {
+        let mut result = BatchResult::default();
+        for (index, items) in batches.into_iter().enumerate() {
+            match self.place_order(recipient, items) {
+                Ok(placed) => result.placed.push(placed),
+                Err(e) => result.failures.push((index, e)),
+            }
+        }
+        result
+    }
 
-        let items = vec![LineItem {
-            name: "Test".to_string(),
-            price: Money(1000),
-        }];
+    /// Places an order at most once per idempotency key.
+    ///
+    /// A client that retries a timed-out checkout sends the same key again.
+    /// If we have already seen the key, we return the order it created
+    /// instead of charging the customer a second time.
+    ///
+    /// The key is also forwarded to the payment gateway. If we crash after
+    /// paying but before recording the key, the provider still recognizes
+    /// the retry and won't take the money twice.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`OrderService::place_order`], plus [`OrderError::OrderNotFound`]
+    /// if the key points at an order the repository no longer has.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no store was plugged in with
+    /// [`OrderService::with_idempotency_store`]. Silently ignoring the key
+    /// would bring back the very double charge it exists to prevent.
+    pub fn place_order_idempotent(
+        &self,
+        key: IdempotencyKey,
+        recipient: &Recipient,
+        items: Vec<LineItem>,
+    ) -> Result<PlacedOrder, OrderError> {
+        let ctx = self.context(None);
+        let seen = self
+            .idempotency
+            .expect("place_order_idempotent requires an idempotency store")
+            .find(&key)?;
 
-        let order = service.place_order(items).unwrap();
-        let retrieved = service.get_order(order.id).unwrap();
+        if let Some(id) = seen {
+            // A retry: hand back the original order, no new charge
+            let order = self.load(&ctx, id)?;
+            return Ok(PlacedOrder {
+                order,
+                warnings: Vec::new(),
+            });
+        }
 
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap().id, order.id);
+        let placed = self.place_order_with_key(&ctx, recipient, items, Some(&key), None)?;
+        self.idempotency
+            .expect("place_order_idempotent requires an idempotency store")
+            .record(key, placed.order.id)?;
+
+        Ok(placed)
+    }
+
+    /// Shared body of the place-order use cases.
+    ///
+    /// `ctx` goes with every port call, so the adapters' log lines for this
+    /// order all carry the same correlation ID.
+    fn place_order_with_key(
+        &self,
+        ctx: &RequestContext,
+        recipient: &Recipient,
+        items: Vec<LineItem>,
+        idempotency_key: Option<&IdempotencyKey>,
+        gift_card: Option<GiftCardId>,
+    ) -> Result<PlacedOrder, OrderError> {
+        let _span = trace::place_order();
+
+        // Step 1: Validate, then get a fresh ID (through the IdGenerator port).
+        // In that order: a rejected order must not leave a gap in the numbering.
+        if let Err(e) = Order::validate_items(&items) {
+            self.audit(AuditAction::OrderPlaced, None, Some(&e));
+            return Err(e);
+        }
+        let fingerprint = Fingerprint::of(&items);
+        if let Some(existing) = self.recent_duplicate(&fingerprint) {
+            let e = OrderError::DuplicateSubmission { existing };
+            self.audit(AuditAction::OrderPlaced, None, Some(&e));
+            return Err(e);
+        }
+        let order_id = self.ids().next_order_id();
+        trace::record(Subject::id(order_id));
+
+        let result =
+            self.place_new_order(ctx, order_id, recipient, items, idempotency_key, gift_card);
+        if result.is_ok() {
+            self.remember(fingerprint, order_id);
+        }
+        self.audit(
+            AuditAction::OrderPlaced,
+            Some(order_id),
+            result.as_ref().err(),
+        );
+        result
+    }
+
+    /// Steps 2-7 of placing an order, once its ID is chosen.
+    fn place_new_order(
+        &self,
+        ctx: &RequestContext,
+        order_id: OrderId,
+        recipient: &Recipient,
+        items: Vec<LineItem>,
+        idempotency_key: Option<&IdempotencyKey>,
+        gift_card: Option<GiftCardId>,
+    ) -> Result<PlacedOrder, OrderError> {
+        // From the clock port: `Instant::now` panics where there's no OS clock (wasm)
+        let started = self.now();
+
+        // Step 2: Create order using domain logic
+        // Order::new() enforces business rules
+        let shipping = self.shipping_to(&items, recipient.address())?;
+        let mut order = Order::new(order_id, items)?;
+        order.set_shipping(shipping)?;
+        order.record_placed(started)?;
+        order.customer_id = ctx.customer;
+        order.tenant = self.tenant;
+        trace::record(Subject::total(order.total));
+
+        // A gift card pays what it can, the customer's card pays the rest
+        if let Some(card) = gift_card {
+            self.debit_gift_card(&mut order, card)?;
+        }
+
+        match self.pay_and_confirm(ctx, &mut order, recipient, idempotency_key) {
+            Ok(warnings) => {
+                let elapsed = self.now().duration_since(started).unwrap_or_default();
+                self.observe(|observer| observer.on_order_placed(&order, elapsed));
+                Ok(PlacedOrder { order, warnings })
+            }
+            Err(e) => {
+                // No order: whatever the gift card paid goes back on it
+                self.restore_gift_card(&order);
+                Err(e)
+            }
+        }
+    }
+
+    /// Steps 3-6: payment, storage and confirmation. Returns the warnings.
+    fn pay_and_confirm(
+        &self,
+        ctx: &RequestContext,
+        order: &mut Order,
+        recipient: &Recipient,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<Vec<OrderWarning>, OrderError> {
+        // Each call goes through a port to an adapter.
+        // We don't know what adapter and we don't care!
+        if order.card_amount().0 == 0 {
+            // The gift card paid it all: no card payment to take
+            self.insert(ctx, order)?;
+            self.finish_paid(ctx, order)?;
+        } else {
+            match self.ordering_policy {
+                OrderingPolicy::ChargeFirst => {
+                    self.charge_then_save(ctx, order, idempotency_key)?
+                }
+                OrderingPolicy::SaveFirst => self.save_then_charge(ctx, order, idempotency_key)?,
+                OrderingPolicy::AwaitSettlement => {
+                    self.save_then_settle(ctx, order, idempotency_key)?;
+                }
+            }
+        }
+
+        if order.status == OrderStatus::Pending {
+            // Not paid yet: the confirmation goes out with the settlement
+            if let Some(transaction_id) = &order.transaction_id {
+                self.awaiting_settlement()
+                    .insert(transaction_id.clone(), recipient.clone());
+            }
+            return Ok(Vec::new());
+        }
+        self.send_confirmation(ctx, order, recipient)
+    }
+
+    /// Step 6: tells the recipient their order is paid. Returns the warnings.
+    ///
+    /// A failure is compensated under [`NotificationFailurePolicy::FailOrder`].
+    fn send_confirmation(
+        &self,
+        ctx: &RequestContext,
+        order: &mut Order,
+        recipient: &Recipient,
+    ) -> Result<Vec<OrderWarning>, OrderError> {
+        // With an outbox, the confirmation is stored right after the order (in a
+        // real database: same transaction) and sent by flush_notifications.
+        let confirmed = NotificationEvent::OrderConfirmed(order.clone());
+        let confirmation = match self.outbox {
+            Some(outbox) => outbox.enqueue(recipient, &confirmed),
+            None => self.notify(ctx, order, recipient, &confirmed),
+        };
+
+        let mut warnings = Vec::new();
+        if let Err(e) = confirmation {
+            match self.notification_failure_policy {
+                NotificationFailurePolicy::WarnOnly => {
+                    warnings.push(OrderWarning::NotificationFailed(e));
+                }
+                NotificationFailurePolicy::FailOrder => {
+                    let refunded = self.compensate(ctx, order);
+                    if refunded && order.record_refund(order.total, self.now()).is_ok() {
+                        // Best effort: the money is back either way, the stored
+                        // record should just say so too
+                        let _ = self.update(ctx, order);
+                    }
+                    return Err(OrderError::NotificationFailedAfterCharge { refunded });
+                }
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// Takes up to the order's total off the gift card.
+    fn debit_gift_card(&self, order: &mut Order, card: GiftCardId) -> Result<(), OrderError> {
+        let gift_cards = self
+            .gift_cards
+            .expect("place_order_split requires a gift card provider");
+        let amount = Money(gift_cards.balance(card)?.0.min(order.total.0));
+        if amount.0 > 0 {
+            gift_cards.debit(card, amount)?;
+            order.gift_card = Some(GiftCardPayment { card, amount });
+        }
+        Ok(())
+    }
+
+    /// Reverses the gift card debit of an order that didn't go through.
+    ///
+    /// Best effort, like the audit: the error being handled is what the
+    /// caller needs to see.
+    fn restore_gift_card(&self, order: &Order) {
+        let (Some(gift), Some(gift_cards)) = (order.gift_card, self.gift_cards) else {
+            return;
+        };
+        if let Err(e) = gift_cards.credit(gift.card, gift.amount) {
+            self.log(&format!(
+                "[GiftCard] Could not give {} back to {}: {e}",
+                gift.amount, gift.card
+            ));
+        }
+    }
+
+    /// [`OrderingPolicy::ChargeFirst`]: authorize, insert, capture, update.
+    fn charge_then_save(
+        &self,
+        ctx: &RequestContext,
+        order: &mut Order,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<(), OrderError> {
+        let authorization = self
+            .authorize(ctx, order, idempotency_key)
+            .inspect_err(|e| self.payment_failed(e))?;
+        order.transaction_id = Some(authorization.0.clone());
+
+        if let Err(e) = self.insert(ctx, order) {
+            // Release the hold. If the void fails too, the storage error is
+            // still the one the caller needs to see.
+            let _ = self.void(ctx, order, &authorization);
+            return Err(e);
+        }
+
+        if let Err(e) = self.capture(ctx, order, &authorization) {
+            self.payment_failed(&e);
+            // Release the hold and keep the attempt on file as Cancelled, as
+            // save_then_charge does. Best effort: the capture error is the
+            // one the caller needs to see.
+            let _ = self.void(ctx, order, &authorization);
+            if order.cancel(self.now(), Some(&e.to_string())).is_ok() {
+                let _ = self.update(ctx, order);
+            }
+            return Err(e);
+        }
+        self.finish_paid(ctx, order)
+    }
+
+    /// [`OrderingPolicy::SaveFirst`]: insert, authorize, capture, update.
+    fn save_then_charge(
+        &self,
+        ctx: &RequestContext,
+        order: &mut Order,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<(), OrderError> {
+        // Nothing charged yet: a storage failure here needs no cleanup
+        self.insert(ctx, order)?;
+
+        let charged = self
+            .authorize(ctx, order, idempotency_key)
+            .and_then(
+                |authorization| match self.capture(ctx, order, &authorization) {
+                    Ok(()) => Ok(authorization),
+                    Err(e) => {
+                        let _ = self.void(ctx, order, &authorization);
+                        Err(e)
+                    }
+                },
+            );
+        let authorization = match charged {
+            Ok(authorization) => authorization,
+            Err(e) => {
+                self.payment_failed(&e);
+                // Best effort: keep the attempt on file as Cancelled. If this
+                // update fails, a Pending order with no payment is left behind.
+                if order.cancel(self.now(), Some(&e.to_string())).is_ok() {
+                    let _ = self.update(ctx, order);
+                }
+                return Err(e);
+            }
+        };
+        order.transaction_id = Some(authorization.0);
+        self.finish_paid(ctx, order)
+    }
+
+    /// [`OrderingPolicy::AwaitSettlement`]: insert, charge, then update to
+    /// Paid if the charge settled at once, or just record the transaction.
+    fn save_then_settle(
+        &self,
+        ctx: &RequestContext,
+        order: &mut Order,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<(), OrderError> {
+        // Nothing charged yet: a storage failure here needs no cleanup
+        self.insert(ctx, order)?;
+
+        let receipt = self
+            .charge(ctx, order, order.card_amount(), idempotency_key)
+            .and_then(|receipt| match receipt.status {
+                PaymentStatus::Failed => {
+                    Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+                }
+                PaymentStatus::Pending | PaymentStatus::Settled => Ok(receipt),
+            });
+        let receipt = match receipt {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                self.payment_failed(&e);
+                // Best effort, as in save_then_charge
+                if order.cancel(self.now(), Some(&e.to_string())).is_ok() {
+                    let _ = self.update(ctx, order);
+                }
+                return Err(e);
+            }
+        };
+        order.transaction_id = Some(receipt.transaction_id);
+
+        if receipt.status == PaymentStatus::Settled {
+            return self.finish_paid(ctx, order);
+        }
+        // The webhook will look the order up by this transaction ID
+        if self.update(ctx, order).is_err() {
+            let refunded = self.compensate(ctx, order);
+            return Err(OrderError::StorageFailedAfterCharge { refunded });
+        }
+        Ok(())
+    }
+
+    /// Marks a captured order as paid and stores it, refunding if that fails.
+    fn finish_paid(&self, ctx: &RequestContext, order: &mut Order) -> Result<(), OrderError> {
+        order.mark_paid(self.now())?;
+
+        // From here on the customer has paid: failures must give the money back
+        if self.update(ctx, order).is_err() {
+            let refunded = self.compensate(ctx, order);
+            return Err(OrderError::StorageFailedAfterCharge { refunded });
+        }
+        Ok(())
+    }
+
+    /// Finishes an order whose payment was pending, once the provider says
+    /// how it ended (see [`OrderingPolicy::AwaitSettlement`]).
+    ///
+    /// 1. Find the Pending order paid with `transaction_id`
+    /// 2. Settled: mark it Paid, store it, send the confirmation that
+    ///    `place_order` held back, and credit the loyalty points
+    ///    `place_order_for` couldn't credit yet
+    /// 3. Failed: cancel it, give back what a gift card paid, store it, and
+    ///    tell the recipient
+    ///
+    /// A Pending outcome changes nothing. Providers send each webhook at
+    /// least once, so an outcome the order already reflects is no error: the
+    /// order comes back unchanged, and nobody is notified twice. A payment
+    /// that settles after its order was cancelled is refunded.
+    ///
+    /// The recipient is the one given to `place_order`, kept in memory. After
+    /// a restart, a registered customer is looked up in the customer
+    /// repository; a guest can't be, and the missing notification is
+    /// reported as a warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::PaymentFailed`] with
+    /// [`PaymentFailureReason::InvalidRequest`] if no order was paid with
+    /// `transaction_id`, [`OrderError::InvalidStatus`] if the order already
+    /// moved on the other way (a failed payment on a Paid order),
+    /// [`OrderError::StorageFailedAfterCharge`] or
+    /// [`OrderError::NotificationFailedAfterCharge`] like
+    /// [`OrderService::place_order`], or the error of any failing port.
+    pub fn handle_payment_confirmation(
+        &self,
+        transaction_id: &str,
+        outcome: PaymentStatus,
+    ) -> Result<PlacedOrder, OrderError> {
+        let ctx = self.context(None);
+        let result = self.try_confirm_payment(&ctx, transaction_id, outcome);
+        let order_id = result.as_ref().ok().map(|placed| placed.order.id);
+        self.audit(
+            AuditAction::PaymentConfirmed,
+            order_id,
+            result.as_ref().err(),
+        );
+        result
+    }
+
+    fn try_confirm_payment(
+        &self,
+        ctx: &RequestContext,
+        transaction_id: &str,
+        outcome: PaymentStatus,
+    ) -> Result<PlacedOrder, OrderError> {
+        // A real database would index transaction_id; a scan does for us
+        let mut order = trace::repository::<R, _>("find_all", Subject::default(), || {
+            self.repository.find_all(self.tenant)
+        })?
+        .into_iter()
+        .find(|order| order.transaction_id.as_deref() == Some(transaction_id))
+        .ok_or(OrderError::PaymentFailed(
+            PaymentFailureReason::InvalidRequest,
+        ))?;
+        let unchanged = |order| PlacedOrder {
+            order,
+            warnings: Vec::new(),
+        };
+
+        match (outcome, order.status) {
+            (PaymentStatus::Pending, _)
+            | (PaymentStatus::Settled, OrderStatus::Paid | OrderStatus::Shipped)
+            | (PaymentStatus::Settled, OrderStatus::Refunded)
+            | (PaymentStatus::Failed, OrderStatus::Cancelled) => Ok(unchanged(order)),
+            (PaymentStatus::Settled, OrderStatus::Pending) => {
+                self.finish_paid(ctx, &mut order)?;
+                let mut warnings = match self.settlement_recipient(transaction_id, &order)? {
+                    Some(recipient) => self.send_confirmation(ctx, &mut order, &recipient)?,
+                    None => vec![OrderWarning::NotificationFailed(
+                        OrderError::InvalidRecipient,
+                    )],
+                };
+                // Paid at last: the points place_order_for couldn't credit yet
+                if let Err(e) = self.credit_points(ctx, &mut order) {
+                    warnings.push(OrderWarning::LoyaltyNotCredited(e));
+                }
+                Ok(PlacedOrder { order, warnings })
+            }
+            (PaymentStatus::Settled, OrderStatus::Cancelled) => {
+                // Cancelled while the payment was pending: it went through anyway
+                self.refund(ctx, &order, transaction_id, order.card_amount())
+                    .inspect_err(|e| self.payment_failed(e))?;
+                self.awaiting_settlement().remove(transaction_id);
+                Ok(unchanged(order))
+            }
+            (PaymentStatus::Failed, OrderStatus::Pending) => {
+                let declined = OrderError::PaymentFailed(PaymentFailureReason::Declined);
+                self.payment_failed(&declined);
+                order.cancel(self.now(), Some(&declined.to_string()))?;
+                self.update(ctx, &order)?;
+                self.restore_gift_card(&order);
+
+                let mut warnings = Vec::new();
+                let cancelled = NotificationEvent::OrderCancelled { id: order.id };
+                let notified = self
+                    .settlement_recipient(transaction_id, &order)?
+                    .ok_or(OrderError::InvalidRecipient)
+                    .and_then(|recipient| self.notify(ctx, &order, &recipient, &cancelled));
+                if let Err(e) = notified {
+                    warnings.push(OrderWarning::NotificationFailed(e));
+                }
+                Ok(PlacedOrder { order, warnings })
+            }
+            (PaymentStatus::Failed, status) => Err(OrderError::InvalidStatus(status)),
+        }
+    }
+
+    /// Who to tell about the settlement of `transaction_id`, if anyone knows.
+    fn settlement_recipient(
+        &self,
+        transaction_id: &str,
+        order: &Order,
+    ) -> Result<Option<Recipient>, OrderError> {
+        if let Some(recipient) = self.awaiting_settlement().remove(transaction_id) {
+            return Ok(Some(recipient));
+        }
+        let (Some(customer_id), Some(customers)) = (order.customer_id, self.customers) else {
+            return Ok(None);
+        };
+        Ok(customers
+            .find(customer_id)?
+            .map(|customer| customer.recipient()))
+    }
+
+    fn awaiting_settlement(&self) -> MutexGuard<'_, HashMap<String, Recipient>> {
+        // A poisoned lock only means another thread panicked; the map is still fine
+        self.awaiting_settlement
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sends up to `max` pending notifications from the outbox.
+    ///
+    /// Each entry is marked as sent only once the sender accepted it.
+    /// A failed entry stays in the outbox and is retried by the next flush:
+    /// nothing is lost, and nothing already sent is sent again.
+    ///
+    /// # Errors
+    ///
+    /// Returns the outbox's error if it can't be read or updated. Sender
+    /// failures are not errors: they are counted in [`FlushReport::failed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no outbox was plugged in with [`OrderService::with_outbox`].
+    pub fn flush_notifications(&self, max: usize) -> Result<FlushReport, OrderError> {
+        let outbox = self.outbox.expect("flush_notifications requires an outbox");
+
+        let mut report = FlushReport::default();
+        for entry in outbox.dequeue_batch(max)? {
+            let notified = trace::sender::<N, _>("notify", Subject::default(), || {
+                self.sender.notify(&entry.recipient, &entry.event)
+            });
+            match notified {
+                Ok(()) => {
+                    outbox.mark_sent(entry.id)?;
+                    report.sent += 1;
+                }
+                Err(_) => report.failed += 1,
+            }
+        }
+        Ok(report)
+    }
+
+    /// Refunds what the customer's card paid for an order. Returns whether
+    /// the refund worked.
+    ///
+    /// Only called on a failure path: the error being handled is what the
+    /// caller sees, so a failed refund is reported, not propagated. A gift
+    /// card part is given back separately, by `restore_gift_card`.
+    fn compensate(&self, ctx: &RequestContext, order: &Order) -> bool {
+        let amount = order.card_amount();
+        if amount.0 == 0 {
+            return true;
+        }
+        paid_with(order)
+            .and_then(|transaction_id| self.refund(ctx, order, transaction_id, amount))
+            .is_ok()
+    }
+
+    /// Sends a refund just recorded on `order` back where the money came from.
+    ///
+    /// The customer's card is paid back first, the gift card gets the rest.
+    /// The card part goes back through the first payment, then through the
+    /// extra charges of the items added later ([`Order::card_payments`]).
+    /// Earlier refunds followed the same rule, so `order.refunded` tells how
+    /// much of each is already back.
+    ///
+    /// A gift card part needs a gift card provider. An order paid that way
+    /// may be loaded by a service wired without one (after a restart, or in
+    /// another app): its refund is then refused, not a panic.
+    fn give_back(
+        &self,
+        ctx: &RequestContext,
+        order: &Order,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        let refunded_before = order.refunded.0.saturating_sub(amount.0);
+        let card_left = order.card_amount().0.saturating_sub(refunded_before);
+        let to_card = amount.0.min(card_left);
+        let to_gift_card = amount.0 - to_card;
+
+        if to_card > 0 {
+            let mut already_back = refunded_before;
+            let mut left = to_card;
+            for (transaction_id, paid) in order.card_payments() {
+                let part = left.min(paid.0.saturating_sub(already_back));
+                already_back = already_back.saturating_sub(paid.0);
+                if part > 0 {
+                    self.refund(ctx, order, transaction_id, Money(part))?;
+                    left -= part;
+                }
+            }
+            if left > 0 {
+                // No transaction to send it through: the stored data is broken
+                return Err(OrderError::PaymentFailed(
+                    PaymentFailureReason::InvalidRequest,
+                ));
+            }
+        }
+        if to_gift_card > 0 {
+            let gift = order.gift_card.ok_or(OrderError::PaymentFailed(
+                PaymentFailureReason::InvalidRequest,
+            ))?;
+            let gift_cards = self.gift_cards.ok_or_else(|| {
+                self.log(&format!(
+                    "[Service] Can't credit {} back: no gift card provider",
+                    gift.card
+                ));
+                OrderError::PaymentFailed(PaymentFailureReason::InvalidRequest)
+            })?;
+            gift_cards.credit(gift.card, Money(to_gift_card))?;
+        }
+        Ok(())
+    }
+
+    /// Cancels an order, refunding whatever the customer paid.
+    ///
+    /// 1. Load the order
+    /// 2. Cancel it (the domain says how much must go back)
+    /// 3. Refund that amount (calls port -> adapter)
+    /// 4. Update the stored order
+    /// 5. Tell the recipient (an `OrderCancelled` event)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] for an unknown ID,
+    /// [`OrderError::InvalidStatus`] if the order is already cancelled or
+    /// refunded, [`OrderError::PaymentFailed`] with
+    /// [`PaymentFailureReason::InvalidRequest`] if part of it goes back to a
+    /// gift card and no gift card provider is plugged in, or the error of
+    /// any failing port.
+    pub fn cancel_order(&self, id: OrderId, recipient: &Recipient) -> Result<Order, OrderError> {
+        let result = self.try_cancel_order(id, recipient);
+        self.audit(AuditAction::OrderCancelled, Some(id), result.as_ref().err());
+        result
+    }
+
+    fn try_cancel_order(&self, id: OrderId, recipient: &Recipient) -> Result<Order, OrderError> {
+        let ctx = self.context(None);
+        let mut order = self.load(&ctx, id)?;
+
+        let to_refund = order.cancel(self.now(), None)?;
+        let points = order.take_back_points();
+        if to_refund.0 > 0 {
+            self.give_back(&ctx, &order, to_refund)
+                .inspect_err(|e| self.payment_failed(e))?;
+        }
+        self.update(&ctx, &order)?;
+        self.debit_points(&order, points);
+
+        self.notify(
+            &ctx,
+            &order,
+            recipient,
+            &NotificationEvent::OrderCancelled { id },
+        )?;
+
+        Ok(order)
+    }
+
+    /// Gives back part or all of an order's payment.
+    ///
+    /// The domain checks the amount first, so we never ask the payment
+    /// provider for a refund the business rules would reject.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] for an unknown ID,
+    /// [`OrderError::InvalidStatus`] unless the order is Paid,
+    /// [`OrderError::InvalidRefund`] for a zero or excessive amount,
+    /// [`OrderError::PaymentFailed`] for a gift card part with no gift card
+    /// provider (as [`OrderService::cancel_order`]), or the error of any
+    /// failing port.
+    pub fn refund_order(
+        &self,
+        id: OrderId,
+        amount: Money,
+        recipient: &Recipient,
+    ) -> Result<Order, OrderError> {
+        let result = self.try_refund_order(id, amount, recipient);
+        self.audit(AuditAction::OrderRefunded, Some(id), result.as_ref().err());
+        result
+    }
+
+    fn try_refund_order(
+        &self,
+        id: OrderId,
+        amount: Money,
+        recipient: &Recipient,
+    ) -> Result<Order, OrderError> {
+        let ctx = self.context(None);
+        let mut order = self.load(&ctx, id)?;
+
+        order.record_refund(amount, self.now())?;
+        let points = order.take_back_points();
+        self.give_back(&ctx, &order, amount)
+            .inspect_err(|e| self.payment_failed(e))?;
+        self.update(&ctx, &order)?;
+        self.debit_points(&order, points);
+
+        let refunded = NotificationEvent::RefundIssued { id, amount };
+        self.notify(&ctx, &order, recipient, &refunded)?;
+
+        Ok(order)
+    }
+
+    /// Adds an item to an order that hasn't shipped yet.
+    ///
+    /// 1. Load the order
+    /// 2. Add the item (the domain says whether the status allows it), and
+    ///    price the shipping of the heavier parcel
+    /// 3. Charge what the total went up by, if the order was already paid,
+    ///    and keep the charge on the order ([`Order::extra_charges`]): a
+    ///    later refund goes back through it
+    /// 4. Update the stored order, checking nobody changed it meanwhile
+    /// 5. Send the recipient an updated confirmation
+    /// 6. Credit the loyalty points the item earns, if it was paid for (a
+    ///    ledger failure is an [`OrderWarning::LoyaltyNotCredited`])
+    ///
+    /// A failed confirmation follows the notification failure policy, as
+    /// in `place_order`: a warning next to the updated order under
+    /// [`NotificationFailurePolicy::WarnOnly`]; under
+    /// [`NotificationFailurePolicy::FailOrder`], the difference is refunded
+    /// and the stored order put back as it was.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] for an unknown ID,
+    /// [`OrderError::InvalidStatus`] unless the order is Pending or Paid,
+    /// [`OrderError::InvalidOrder`] while its charge waits for the
+    /// settlement webhook, [`OrderError::StorageFailedAfterCharge`] if the
+    /// update failed after the extra charge (including a
+    /// [`OrderError::Conflict`]), [`OrderError::NotificationFailedAfterCharge`]
+    /// if the confirmation failed after it under `FailOrder`, or the error of
+    /// any failing port.
+    pub fn add_item_to_order(
+        &self,
+        id: OrderId,
+        item: LineItem,
+        recipient: &Recipient,
+    ) -> Result<PlacedOrder, OrderError> {
+        let result = self.try_add_item_to_order(id, item, recipient);
+        self.audit(AuditAction::OrderUpdated, Some(id), result.as_ref().err());
+        result
+    }
+
+    fn try_add_item_to_order(
+        &self,
+        id: OrderId,
+        item: LineItem,
+        recipient: &Recipient,
+    ) -> Result<PlacedOrder, OrderError> {
+        let ctx = self.context(None);
+        let before = self.load(&ctx, id)?;
+        let mut order = before.clone();
+
+        order.add_item(item)?;
+        // A heavier parcel may cost more to ship: that's charged with the item
+        self.reprice_shipping(&mut order)?;
+        let difference = order.total.saturating_sub(before.total);
+        order.version += 1;
+
+        let charged = order.status == OrderStatus::Paid;
+        if charged {
+            let receipt = self
+                .charge(&ctx, &order, difference, None)
+                .inspect_err(|e| self.payment_failed(e))?;
+            order.extra_charges.push(ExtraCharge {
+                transaction_id: receipt.transaction_id,
+                amount: difference,
+            });
+            if let Err(e) = self.update_versioned(&order, before.version) {
+                self.log(&format!(
+                    "[Service] Update failed after the extra charge: {e}"
+                ));
+                let refunded = self.refund_extra_charge(&ctx, &order);
+                return Err(OrderError::StorageFailedAfterCharge { refunded });
+            }
+        } else {
+            // Nothing was charged yet: the new total is what gets paid
+            self.update_versioned(&order, before.version)?;
+        }
+
+        let mut warnings = Vec::new();
+        let confirmed = NotificationEvent::OrderConfirmed(order.clone());
+        if let Err(e) = self.notify(&ctx, &order, recipient, &confirmed) {
+            if self.notification_failure_policy == NotificationFailurePolicy::FailOrder {
+                self.log(&format!(
+                    "[Service] Confirmation failed, taking the item back out: {e}"
+                ));
+                let refunded = !charged || self.refund_extra_charge(&ctx, &order);
+                if refunded {
+                    // Best effort, like place_order's: the money is back
+                    // either way, the stored order should just say so too
+                    let restored = Order {
+                        version: order.version + 1,
+                        ..before
+                    };
+                    let _ = self.update_versioned(&restored, order.version);
+                }
+                return Err(if charged {
+                    OrderError::NotificationFailedAfterCharge { refunded }
+                } else {
+                    e
+                });
+            }
+            warnings.push(OrderWarning::NotificationFailed(e));
+        }
+        // The item earns points too, once it's paid for
+        if let Err(e) = self.credit_points(&ctx, &mut order) {
+            warnings.push(OrderWarning::LoyaltyNotCredited(e));
+        }
+        Ok(PlacedOrder { order, warnings })
+    }
+
+    /// Refunds the last extra charge of `order`, on a failure path: whether
+    /// it worked is reported, not propagated.
+    fn refund_extra_charge(&self, ctx: &RequestContext, order: &Order) -> bool {
+        order.extra_charges.last().is_some_and(|charge| {
+            self.refund(ctx, order, &charge.transaction_id, charge.amount)
+                .is_ok()
+        })
+    }
+
+    /// Use case: Merge order `b` into order `a`, for one shipment.
+    ///
+    /// 1. Load both orders
+    /// 2. Merge them (domain rules: same customer, both Pending and unpaid),
+    ///    and price the shipping of the one parcel left
+    /// 3. Save `a` with `b`'s items
+    /// 4. Tombstone `b`: it stays in the books, listed in `a`'s
+    ///    [`Order::merged_from`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] if either order doesn't exist,
+    /// the error of [`Order::merge`] if they can't be merged (e.g.
+    /// [`OrderError::InvalidOrder`] for two customers' orders), or the
+    /// error of a failing repository (including a [`OrderError::Conflict`]).
+    pub fn merge_orders(&self, a: OrderId, b: OrderId) -> Result<Order, OrderError> {
+        let result = self.try_merge_orders(a, b);
+        self.audit(AuditAction::OrderUpdated, Some(a), result.as_ref().err());
+        result
+    }
+
+    fn try_merge_orders(&self, a: OrderId, b: OrderId) -> Result<Order, OrderError> {
+        let ctx = self.context(None);
+        let first = self.load(&ctx, a)?;
+        let second = self.load(&ctx, b)?;
+        let expected = first.version;
+
+        let mut merged = first.merge(second)?;
+        // One parcel instead of two
+        self.reprice_shipping(&mut merged)?;
+        merged.version += 1;
+
+        self.update_versioned(&merged, expected)?;
+        // Tombstoned rather than deleted: `merged_from` keeps pointing at it
+        self.repository.soft_delete(self.tenant, b)?;
+        self.log(&format!("[Service] {b} merged into {a}"));
+
+        Ok(merged)
+    }
+
+    /// Use case: Split the items at `item_indices` off order `id`, into a
+    /// new order of the same customer.
+    ///
+    /// 1. Load the order
+    /// 2. Split it (domain rules: Pending and unpaid, both halves non-empty),
+    ///    and price the shipping of each parcel
+    /// 3. Give the split-off order a fresh ID, and save it
+    /// 4. Save the original with the items it kept
+    ///
+    /// Returns the original order, then the split-off one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] for an unknown ID, the error of
+    /// [`Order::split`] if the order can't be split that way, or the error of
+    /// a failing repository (including a [`OrderError::Conflict`]).
+    pub fn split_order(
+        &self,
+        id: OrderId,
+        item_indices: &[usize],
+    ) -> Result<(Order, Order), OrderError> {
+        let result = self.try_split_order(id, item_indices);
+        self.audit(AuditAction::OrderUpdated, Some(id), result.as_ref().err());
+        result
+    }
+
+    fn try_split_order(
+        &self,
+        id: OrderId,
+        item_indices: &[usize],
+    ) -> Result<(Order, Order), OrderError> {
+        let ctx = self.context(None);
+        let order = self.load(&ctx, id)?;
+        let expected = order.version;
+
+        let (mut kept, mut split_off) = order.split(item_indices)?;
+        // Two parcels instead of one
+        self.reprice_shipping(&mut kept)?;
+        self.reprice_shipping(&mut split_off)?;
+        kept.version += 1;
+        split_off.id = self.ids().next_order_id();
+        split_off.record_placed(self.now())?;
+
+        // The new order first: if saving the original then fails, the split
+        // items exist twice rather than not at all
+        self.insert(&ctx, &split_off)?;
+        self.update_versioned(&kept, expected)?;
+        self.log(&format!("[Service] {} split off {id}", split_off.id));
+
+        Ok((kept, split_off))
+    }
+
+    /// Appends to the audit trail: succeeded, or failed with `error`.
+    ///
+    /// Best effort: by the time we audit, money may already have moved, so a
+    /// broken audit log must not turn the outcome into a failure.
+    fn audit(&self, action: AuditAction, order_id: Option<OrderId>, error: Option<&OrderError>) {
+        if let Some(e) = error {
+            trace::failed(e);
+        }
+        let outcome = error.map_or(AuditOutcome::Succeeded, |e| {
+            AuditOutcome::Failed(e.to_string())
+        });
+        let entry = AuditEntry {
+            action,
+            order_id,
+            outcome,
+            timestamp: self.now(),
+        };
+        if let Err(e) = self.audit_log.record(entry) {
+            self.log(&format!("[Audit] Could not record {action}: {e}"));
+        }
+    }
+
+    /// Credits the customer who placed a paid order with the points it
+    /// earns and they weren't credited yet ([`Order::points_owed`]), and
+    /// stores them as credited on the order.
+    ///
+    /// An order still waiting for its payment to settle earns nothing yet.
+    fn credit_points(&self, ctx: &RequestContext, order: &mut Order) -> Result<(), OrderError> {
+        let (Some(loyalty), Some(customer)) = (self.loyalty, order.customer_id) else {
+            return Ok(());
+        };
+        let points = order.points_owed();
+        if points == 0 {
+            return Ok(());
+        }
+        loyalty.credit(customer, points)?;
+        order.loyalty_credited += points;
+        // The points are credited either way; unrecorded, a refund just
+        // can't take them back
+        if let Err(e) = self.update(ctx, order) {
+            self.log(&format!(
+                "[Loyalty] Credited {points} point(s) to {customer}, but could not record it: {e}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Takes back `points` the order was credited and no longer earns (see
+    /// [`Order::take_back_points`]).
+    ///
+    /// Best effort, like the audit: the money already went back, and that's
+    /// what the caller needs to hear about.
+    fn debit_points(&self, order: &Order, points: u32) {
+        let (Some(loyalty), Some(customer)) = (self.loyalty, order.customer_id) else {
+            return;
+        };
+        if points == 0 {
+            return;
+        }
+        if let Err(e) = loyalty.debit(customer, points) {
+            self.log(&format!(
+                "[Loyalty] Could not take {points} point(s) back from {customer}: {e}"
+            ));
+        }
+    }
+
+    /// The subscription repository, or a StorageFailed saying `use_case`
+    /// has nowhere to keep subscriptions.
+    fn subscription_repository(
+        &self,
+        use_case: &str,
+    ) -> Result<&'a (dyn SubscriptionRepository + Sync), OrderError> {
+        self.subscriptions.ok_or_else(|| {
+            OrderError::storage_failed(format!("{use_case} requires a subscription repository"))
+        })
+    }
+
+    /// Writes `message` to the plugged-in logger, or stdout.
+    ///
+    /// These lines belong to no single port call, so they carry no
+    /// correlation ID.
+    fn log(&self, message: &str) {
+        match self.logger {
+            Some(logger) => logger.log(&RequestContext::default(), message),
+            None => println!("  {message}"),
+        }
+    }
+
+    /// The current time, from the plugged-in clock or the system.
+    fn now(&self) -> SystemTime {
+        self.clock.map_or_else(SystemTime::now, Clock::now)
+    }
+
+    /// The order placed with these items within the duplicate window, if any.
+    ///
+    /// Forgets the orders that fell out of the window on the way.
+    fn recent_duplicate(&self, fingerprint: &Fingerprint) -> Option<OrderId> {
+        let guard = self.duplicate_guard.as_ref()?;
+        let now = self.now();
+        let mut recent = guard.recent();
+        recent.retain(|(_, _, placed_at)| {
+            // A clock that went backwards counts as "just now"
+            now.duration_since(*placed_at).unwrap_or_default() < guard.window
+        });
+        recent
+            .iter()
+            .find(|(seen, _, _)| seen == fingerprint)
+            .map(|(_, id, _)| *id)
+    }
+
+    /// Remembers a placed order for the duplicate guard, if there is one.
+    fn remember(&self, fingerprint: Fingerprint, id: OrderId) {
+        if let Some(guard) = &self.duplicate_guard {
+            let now = self.now();
+            guard.recent().push((fingerprint, id, now));
+        }
+    }
+
+    /// The plugged-in ID generator, or the service's own counter.
+    fn ids(&self) -> &dyn IdGenerator {
+        match self.ids {
+            Some(ids) => ids,
+            None => &self.default_ids,
+        }
+    }
+
+    /// Calls every observer. A panicking observer is contained and skipped.
+    fn observe(&self, callback: impl Fn(&dyn OrderObserver)) {
+        for observer in &self.observers {
+            let observer: &dyn OrderObserver = *observer;
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| callback(observer)));
+        }
+    }
+
+    fn payment_failed(&self, error: &OrderError) {
+        self.observe(|observer| observer.on_payment_failed(error));
+    }
+
+    /// A fresh context for one use case invocation.
+    ///
+    /// A per-service counter is enough to tie log lines together here. In
+    /// production, the ID would come from the incoming request (an
+    /// `X-Request-Id` header), or be a UUID when there is none.
+    fn context(&self, customer: Option<CustomerId>) -> RequestContext {
+        let n = self.next_request.fetch_add(1, Ordering::Relaxed);
+        RequestContext {
+            correlation_id: format!("req-{n}"),
+            tenant: Some(self.tenant),
+            customer,
+        }
+    }
+
+    /// What shipping `items` to `destination` costs, if they ship: not
+    /// without a destination, nor without a [`ShippingCalculator`].
+    fn shipping_to(
+        &self,
+        items: &[LineItem],
+        destination: Option<&Address>,
+    ) -> Result<Option<Shipping>, OrderError> {
+        let (Some(calculator), Some(destination)) = (self.shipping, destination) else {
+            return Ok(None);
+        };
+        Ok(Some(Shipping {
+            cost: calculator.cost(items, destination)?,
+            destination: destination.clone(),
+        }))
+    }
+
+    /// Prices an order's shipping again, for the items it has now. An order
+    /// that doesn't ship stays that way, and so does one priced by a
+    /// calculator this service doesn't have.
+    fn reprice_shipping(&self, order: &mut Order) -> Result<(), OrderError> {
+        let Some(shipping) = &order.shipping else {
+            return Ok(());
+        };
+        let repriced = self.shipping_to(&order.items, Some(&shipping.destination))?;
+        if repriced.is_some() {
+            order.set_shipping(repriced)?;
+        }
+        Ok(())
+    }
+
+    /// Turns requested products into line items at catalog prices.
+    fn price_items(&self, items: &[OrderRequestItem]) -> Result<Vec<LineItem>, OrderError> {
+        let price_list = self
+            .price_list
+            .expect("place_order_from_catalog requires a price list");
+
+        let mut line_items = Vec::new();
+        for item in items {
+            if !(1..=MAX_QUANTITY).contains(&item.quantity) {
+                return Err(OrderError::InvalidOrder);
+            }
+            let price = price_list.price_of(&item.product)?;
+            for _ in 0..item.quantity {
+                line_items.push(LineItem {
+                    name: item.product.0.clone(),
+                    price,
+                    weight_grams: None,
+                });
+            }
+        }
+        Ok(line_items)
+    }
+
+    // -------------------------------------------------------------------------
+    // Port calls about one order. Each goes through `trace`, which puts it in
+    // a span when the `tracing` feature is on, and is a plain call otherwise.
+    // -------------------------------------------------------------------------
+
+    fn insert(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        trace::repository::<R, _>("insert", order.into(), || {
+            self.repository.insert_with_ctx(ctx, order)
+        })
+    }
+
+    fn update(&self, ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        trace::repository::<R, _>("update", order.into(), || {
+            self.repository.update_with_ctx(ctx, order)
+        })
+    }
+
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        trace::repository::<R, _>("update_versioned", order.into(), || {
+            self.repository.update_versioned(order, expected)
+        })
+    }
+
+    fn authorize(
+        &self,
+        ctx: &RequestContext,
+        order: &Order,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        trace::payment::<P, _>("authorize", order.into(), || {
+            self.payment
+                .authorize_with_ctx(ctx, order.card_amount(), idempotency_key)
+        })
+    }
+
+    fn capture(
+        &self,
+        ctx: &RequestContext,
+        order: &Order,
+        authorization: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        trace::payment::<P, _>("capture", order.into(), || {
+            self.payment.capture_with_ctx(ctx, authorization)
+        })
+    }
+
+    fn void(
+        &self,
+        ctx: &RequestContext,
+        order: &Order,
+        authorization: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        trace::payment::<P, _>("void", order.into(), || {
+            self.payment.void_with_ctx(ctx, authorization)
+        })
+    }
+
+    fn charge(
+        &self,
+        ctx: &RequestContext,
+        order: &Order,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        // What finance needs to match the provider's charge to our order,
+        // without a join table on our side
+        let mut metadata = ChargeMetadata::new().with_entry("order_id", order.id.0.to_string());
+        if !ctx.correlation_id.is_empty() {
+            metadata = metadata.with_entry("correlation_id", ctx.correlation_id.clone());
+        }
+        if let Some(customer) = order.customer_id {
+            metadata = metadata.with_entry("customer_id", customer.0.to_string());
+        }
+        trace::payment::<P, _>("charge", order.into(), || {
+            self.payment
+                .charge_with_metadata(ctx, amount, idempotency_key, &metadata)
+        })
+    }
+
+    fn refund(
+        &self,
+        ctx: &RequestContext,
+        order: &Order,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        trace::payment::<P, _>("refund", order.into(), || {
+            self.payment.refund_with_ctx(ctx, transaction_id, amount)
+        })
+    }
+
+    fn notify(
+        &self,
+        ctx: &RequestContext,
+        order: &Order,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<(), OrderError> {
+        trace::sender::<N, _>("notify", order.into(), || {
+            self.sender.notify_with_ctx(ctx, recipient, event)
+        })
+    }
+
+    /// Loads an order that must exist.
+    fn load(&self, ctx: &RequestContext, id: OrderId) -> Result<Order, OrderError> {
+        trace::repository::<R, _>("find", Subject::id(id), || {
+            self.repository.find_with_ctx(ctx, self.tenant, id)
+        })?
+        .ok_or(OrderError::OrderNotFound(id))
+    }
+
+    /// Retrieves an order by ID.
+    ///
+    /// A simple use case: just delegate to the repository.
+    /// Read-only callers should prefer [`OrderQueries`], which needs no
+    /// payment or notification adapter.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if retrieval fails.
+    pub fn get_order(&self, id: OrderId) -> Result<Option<Order>, OrderError> {
+        let _span = trace::get_order(Subject::id(id));
+        let order = trace::repository::<R, _>("find", Subject::id(id), || {
+            self.repository.find(self.tenant, id)
+        })
+        .inspect_err(trace::failed)?;
+        if let Some(order) = &order {
+            trace::record(Subject::total(order.total));
+        }
+        let found = order.is_some();
+        self.observe(|observer| observer.on_order_retrieved(id, found));
+        Ok(order)
+    }
+
+    /// Retrieves an order by ID, as an [`OrderDto`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if retrieval fails.
+    pub fn get_order_dto(&self, id: u32) -> Result<Option<OrderDto>, OrderError> {
+        Ok(self.get_order(OrderId(id))?.as_ref().map(OrderDto::from))
+    }
+
+    /// Every status change of an order, oldest first: when it was paid,
+    /// shipped or cancelled, and why when that's known.
+    ///
+    /// Support's use case: "when did my order change, and why?"
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] if there's no such order, or
+    /// the repository's error if retrieval fails.
+    pub fn get_order_history(&self, id: OrderId) -> Result<Vec<HistoryEntry>, OrderError> {
+        let ctx = self.context(None);
+        Ok(self.load(&ctx, id)?.history)
+    }
+}
+
+impl<R, P, N> OrderService<'_, R, P, N>
+where
+    R: OrderRepository + HealthCheck + ?Sized,
+    P: PaymentGateway + HealthCheck + ?Sized,
+    N: Sender + HealthCheck + ?Sized,
+{
+    /// Checks the repository, the payment gateway and the sender, in that
+    /// order: "can this service take orders?" before it's asked to.
+    ///
+    /// Only the three ports every use case needs. The optional ones
+    /// (outbox, gift cards...) are checked where they're wired, if at all.
+    #[must_use]
+    pub fn health_report(&self) -> HealthReport {
+        HealthReport {
+            repository: self.repository.health(),
+            payment: self.payment.health(),
+            sender: self.sender.health(),
+        }
+    }
+}
+
+impl<R, P, N> PaymentConfirmation for OrderService<'_, R, P, N>
+where
+    R: OrderRepository + ?Sized,
+    P: PaymentGateway + ?Sized,
+    N: Sender + ?Sized,
+{
+    fn confirm(&self, transaction_id: &str, outcome: PaymentStatus) -> Result<Order, OrderError> {
+        self.handle_payment_confirmation(transaction_id, outcome)
+            .map(|placed| placed.order)
+    }
+}
+
+/// Orders placed recently, for [`OrderService::with_duplicate_guard`].
+#[derive(Debug)]
+struct DuplicateGuard {
+    window: Duration,
+    recent: Mutex<Vec<(Fingerprint, OrderId, SystemTime)>>,
+}
+
+impl DuplicateGuard {
+    fn recent(&self) -> MutexGuard<'_, Vec<(Fingerprint, OrderId, SystemTime)>> {
+        // A poisoned lock only means another thread panicked; the list is still fine
+        self.recent.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// What two orders must share to count as duplicates: the same items, each
+/// the same number of times, in any order.
+#[derive(Debug, PartialEq, Eq)]
+struct Fingerprint(Vec<(String, u32)>);
+
+impl Fingerprint {
+    fn of(items: &[LineItem]) -> Self {
+        // Quantities are repeated items, so sorting keeps them in the comparison
+        let mut items: Vec<(String, u32)> = items
+            .iter()
+            .map(|item| (item.name.clone(), item.price.0))
+            .collect();
+        items.sort_unstable();
+        Self(items)
+    }
+}
+
+/// Fallback [`IdGenerator`]: one atomic counter per kind of ID, starting at 1.
+#[derive(Debug)]
+struct CountingIds {
+    next_order: AtomicU32,
+    next_customer: AtomicU32,
+    next_subscription: AtomicU32,
+}
+
+impl Default for CountingIds {
+    fn default() -> Self {
+        Self {
+            next_order: AtomicU32::new(1),
+            next_customer: AtomicU32::new(1),
+            next_subscription: AtomicU32::new(1),
+        }
+    }
+}
+
+impl IdGenerator for CountingIds {
+    fn next_order_id(&self) -> OrderId {
+        OrderId(self.next_order.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn next_customer_id(&self) -> CustomerId {
+        CustomerId(self.next_customer.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn next_subscription_id(&self) -> SubscriptionId {
+        SubscriptionId(self.next_subscription.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// The payment reference a refund must point at.
+///
+/// A paid order always has one. If it doesn't, the stored data is broken
+/// and there is nothing we could refund.
+fn paid_with(order: &Order) -> Result<&str, OrderError> {
+    order
+        .transaction_id
+        .as_deref()
+        .ok_or(OrderError::PaymentFailed(
+            PaymentFailureReason::InvalidRequest,
+        ))
+}
+
+// =============================================================================
+// Order Queries - The Read Side
+// =============================================================================
+//
+// OrderService handles COMMANDS: things that change state (place, cancel,
+// refund). They need the repository, plus payment and notification.
+//
+// QUERIES only look. A dashboard listing orders has no business constructing
+// a payment gateway it will never call. So reads get their own struct,
+// generic over the repository alone: a lightweight take on CQRS
+// (Command Query Responsibility Segregation).
+//
+// Both sides usually want the SAME repository at the same time. Since every
+// port method takes `&self`, both can simply borrow it. When they need to OWN
+// it instead (a web server's state, a background job), the app crate wraps it
+// in `SharedOrderRepository` from adapters-repository. Either way, the
+// application layer doesn't care how sharing works.
+
+/// Read-only use cases over the order repository.
+///
+/// Needs nothing but a repository: no payment, no sender. Exchange rates
+/// are optional, for [`OrderQueries::order_total_in`].
+pub struct OrderQueries<'a, R>
+where
+    R: OrderRepository + ?Sized,
+{
+    repository: &'a R,
+    tenant: TenantId,
+    rates: Option<&'a (dyn ExchangeRates + Sync)>,
+}
+
+// Manual impl: the exchange rates have no Debug of their own.
+impl<R> fmt::Debug for OrderQueries<'_, R>
+where
+    R: OrderRepository + fmt::Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderQueries")
+            .field("repository", &self.repository)
+            .field("tenant", &self.tenant)
+            .field("rates", &self.rates.is_some())
+            .finish()
+    }
+}
+
+impl<'a, R> OrderQueries<'a, R>
+where
+    R: OrderRepository + ?Sized,
+{
+    /// Creates the query side over a repository.
+    #[must_use]
+    pub const fn new(repository: &'a R) -> Self {
+        Self {
+            repository,
+            tenant: TenantId(0),
+            rates: None,
+        }
+    }
+
+    /// Scopes the queries to one storefront, like
+    /// [`OrderService::with_tenant`].
+    #[must_use]
+    pub const fn with_tenant(mut self, tenant: TenantId) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    /// Plugs in exchange rates, for totals in another currency.
+    #[must_use]
+    pub const fn with_exchange_rates(mut self, rates: &'a (dyn ExchangeRates + Sync)) -> Self {
+        self.rates = Some(rates);
+        self
+    }
+
+    /// Retrieves an order by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if retrieval fails.
+    pub fn get_order(&self, id: OrderId) -> Result<Option<Order>, OrderError> {
+        let _span = trace::get_order(Subject::id(id));
+        let order = trace::repository::<R, _>("find", Subject::id(id), || {
+            self.repository.find(self.tenant, id)
+        })
+        .inspect_err(trace::failed)?;
+        if let Some(order) = &order {
+            trace::record(Subject::total(order.total));
+        }
+        Ok(order)
+    }
+
+    /// Lists every order, sorted by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if retrieval fails.
+    pub fn list_orders(&self) -> Result<Vec<Order>, OrderError> {
+        trace::repository::<R, _>("find_all", Subject::default(), || {
+            self.repository.find_all(self.tenant)
+        })
+    }
+
+    /// Lists the orders with an item whose name contains `query`, ignoring
+    /// case, sorted by ID: support's "who ordered the blue mug?".
+    ///
+    /// # Errors
+    ///
+    /// Returns error if retrieval fails.
+    pub fn search_orders(&self, query: &str) -> Result<Vec<Order>, OrderError> {
+        trace::repository::<R, _>("find_by_item_name", Subject::default(), || {
+            self.repository.find_by_item_name(self.tenant, query)
+        })
+    }
+
+    /// Returns the total of an order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] if there is no such order,
+    /// or the repository's error if retrieval fails.
+    pub fn order_total(&self, id: OrderId) -> Result<Money, OrderError> {
+        // Only the total is needed: no copy of the order and its items
+        let mut total = None;
+        trace::repository::<R, _>("find_with", Subject::id(id), || {
+            self.repository
+                .find_with(self.tenant, id, &mut |order| total = order.map(|o| o.total))
+        })?;
+        total.ok_or(OrderError::OrderNotFound(id))
+    }
+
+    /// Returns the total of an order in `currency`, e.g. the merchant's
+    /// settlement currency for a report. Rounded as [`Money::convert`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] if there is no such order,
+    /// [`OrderError::NoExchangeRate`] if the rates don't quote `currency`,
+    /// or the repository's error if retrieval fails.
+    ///
+    /// # Panics
+    ///
+    /// If no exchange rates were plugged in (see
+    /// [`OrderQueries::with_exchange_rates`]).
+    pub fn order_total_in(&self, id: OrderId, currency: Currency) -> Result<Money, OrderError> {
+        let rates = self.rates.expect("order_total_in requires exchange rates");
+        self.order_total(id)?.convert(currency, rates)
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+//
+// Application tests need mock adapters because we're testing orchestration.
+// We create simple test doubles that implement the port traits.
+// No real database, no real payment API - just verifying the flow works.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapters_notification::{FailingSender, IntermittentSender};
+    use adapters_payment::{
+        FailingPaymentGateway, FlakyCounters, FlakyPaymentGateway, InMemoryGiftCardStore,
+        LimitedPaymentGateway, MockPaymentGateway, RecordingPaymentGateway, RetryPolicy,
+        RetryingPaymentGateway, ScriptedCall, ScriptedPaymentGateway, ScriptedResponse,
+        StripePaymentGateway,
+    };
+    use adapters_repository::{
+        CapturingLogger, FaultyOrderRepository, FixedClock, InMemoryAuditLog,
+        InMemoryCustomerRepository, InMemoryIdempotencyStore, InMemoryLoyaltyLedger,
+        InMemoryOrderRepository, InMemoryOutbox, InMemoryPriceList, InMemorySubscriptionRepository,
+        NullLogger, PostgresOrderRepository, SequentialIdGenerator, SharedOrderRepository,
+        StatementKind, StaticExchangeRates, SystemClock,
+    };
+    use adapters_shipping::TieredByWeightShipping;
+    use domain::{AuthorizationId, Contact, Logger, OrderStatus, PaymentReceipt, ProductId, Rate};
+    use std::cell::Cell;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+    use std::time::UNIX_EPOCH;
+    use test_support::{
+        FailingRepository, FixedRateForTests, MockPayment, MockRepository, MockSender, PaymentCall,
+    };
+
+    // -------------------------------------------------------------------------
+    // Test Doubles (Mock Adapters)
+    // -------------------------------------------------------------------------
+    // The doubles come from the test-support crate: minimal implementations
+    // of the port traits, counting their calls. They prove that our
+    // application layer works with ANY implementation of the port traits.
+
+    fn test_recipient() -> Recipient {
+        Recipient::new("Test", Contact::Email("test@example.com".to_string())).unwrap()
+    }
+
+    // -------------------------------------------------------------------------
+    // Actual Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn place_order_succeeds() {
+        let repo = MockRepository::new();
+        let payment = RecordingPaymentGateway::new();
+        let sender = adapters_notification::RecordingSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        let result = service.place_order(&test_recipient(), items);
+
+        assert!(result.is_ok());
+        let order = result.unwrap().order;
+        assert_eq!(order.id, OrderId(1));
+        assert_eq!(order.total, Money(1000));
+        assert_eq!(order.transaction_id.as_deref(), Some("mock_auth_1"));
+        assert_eq!(order.status, OrderStatus::Paid);
+        let charged: Vec<Money> = payment.charges().iter().map(|c| c.amount).collect();
+        assert_eq!(charged, vec![Money(1000)]);
+        let sent = sender.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].recipient, test_recipient());
+        assert_eq!(sent[0].order_id, OrderId(1));
+        assert!(sent[0].message.contains("OrderId(1)"));
+        assert!(sent[0].message.contains("$10.00"));
+    }
+
+    #[test]
+    fn place_order_payment_fails() {
+        // Using FailingPaymentGateway instead of MockPayment
+        let repo = MockRepository::new();
+        let payment = FailingPaymentGateway::default();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        let result = service.place_order(&test_recipient(), items);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+    }
+
+    #[test]
+    fn place_order_retries_a_flaky_provider_until_it_answers() {
+        let repo = MockRepository::new();
+        let payment = RetryingPaymentGateway::with_sleeper(
+            FlakyPaymentGateway::failing_first(MockPaymentGateway::new(), 2),
+            RetryPolicy::default(),
+            |_| {},
+        );
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        assert_eq!(placed.order.status, OrderStatus::Paid);
+        // Two failed authorizations, the third one, then the capture
+        assert_eq!(
+            payment.inner().counters(),
+            FlakyCounters {
+                calls: 4,
+                failures: 2
+            }
+        );
+        assert_eq!(payment.inner().inner().capture_count(), 1);
+    }
+
+    #[test]
+    fn retry_policy_retries_the_authorization_not_the_capture() {
+        let repo = MockRepository::new();
+        let payment = RetryingPaymentGateway::with_sleeper(
+            ScriptedPaymentGateway::new(vec![
+                ScriptedResponse::Fail(PaymentFailureReason::Timeout),
+                ScriptedResponse::Succeed,
+                ScriptedResponse::Succeed,
+            ]),
+            RetryPolicy::default(),
+            |_| {},
+        );
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        let authorize = ScriptedCall::Authorize {
+            amount: Money(1000),
+            idempotency_key: None,
+        };
+        let auth = AuthorizationId("scripted_auth_2".to_string());
+        assert_eq!(
+            payment.inner().calls(),
+            vec![
+                authorize.clone(),
+                authorize,
+                ScriptedCall::Capture(auth.clone())
+            ]
+        );
+        assert_eq!(payment.inner().remaining(), 0);
+        assert_eq!(placed.order.transaction_id, Some(auth.0));
+    }
+
+    #[test]
+    fn get_order_returns_saved_order() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        let order = service.place_order(&test_recipient(), items).unwrap().order;
+        let retrieved = service.get_order(order.id).unwrap();
+
+        assert!(retrieved.is_some());
+        let retrieved = retrieved.unwrap();
+        assert_eq!(retrieved.id, order.id);
+        assert_eq!(retrieved.transaction_id.as_deref(), Some("auth_test"));
+    }
+
+    #[test]
+    fn place_order_duplicate_id_fails() {
+        // An order already sits under the ID the service is about to generate
+        let repo = MockRepository::new();
+        let existing = Order::new(
+            OrderId(1),
+            vec![LineItem {
+                name: "Existing".to_string(),
+                price: Money(500),
+                weight_grams: None,
+            }],
+        )
+        .unwrap();
+        repo.insert(&existing).unwrap();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        let result = service.place_order(&test_recipient(), items);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::DuplicateOrder(OrderId(1)))
+        ));
+    }
+
+    #[test]
+    fn place_order_captures_after_save() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        service.place_order(&test_recipient(), items).unwrap();
+
+        assert_eq!(payment.capture_count(), 1);
+        assert_eq!(payment.void_count(), 0);
+        assert!(payment.outstanding_authorizations().is_empty());
+    }
+
+    #[test]
+    fn place_order_save_fails_voids_authorization() {
+        let repo = FailingRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        let result = service.place_order(&test_recipient(), items);
+
+        assert!(matches!(result, Err(OrderError::StorageFailed(_))));
+        assert_eq!(payment.void_count(), 1);
+        assert_eq!(payment.capture_count(), 0);
+        assert!(payment.outstanding_authorizations().is_empty());
+    }
+
+    #[test]
+    fn place_order_idempotent_same_key_charges_once() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let store = InMemoryIdempotencyStore::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_idempotency_store(&store);
+        let key = IdempotencyKey("checkout-1".to_string());
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        // The client times out and retries with the same key
+        let first = service
+            .place_order_idempotent(key.clone(), &test_recipient(), items.clone())
+            .unwrap();
+        let second = service
+            .place_order_idempotent(key, &test_recipient(), items)
+            .unwrap();
+
+        assert_eq!(first.order.id, second.order.id);
+        assert_eq!(payment.capture_count(), 1);
+        assert_eq!(repo.orders().len(), 1);
+    }
+
+    #[test]
+    fn place_order_idempotent_different_keys_place_two_orders() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let store = InMemoryIdempotencyStore::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_idempotency_store(&store);
+
+        let items = vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }];
+
+        let first = service
+            .place_order_idempotent(
+                IdempotencyKey("a".to_string()),
+                &test_recipient(),
+                items.clone(),
+            )
+            .unwrap();
+        let second = service
+            .place_order_idempotent(IdempotencyKey("b".to_string()), &test_recipient(), items)
+            .unwrap();
+
+        assert_ne!(first.order.id, second.order.id);
+        assert_eq!(payment.capture_count(), 2);
+    }
+
+    fn test_items() -> Vec<LineItem> {
+        vec![LineItem {
+            name: "Test".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        }]
+    }
+
+    #[test]
+    fn place_order_sends_order_confirmed() {
+        let repo = MockRepository::new();
+        let sender = MockSender::new();
+        let payment = MockPayment::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        let events = sender.events();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], NotificationEvent::OrderConfirmed(_)));
+    }
+
+    #[test]
+    fn cancel_order_refunds_and_sends_order_cancelled() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+
+        let cancelled = service.cancel_order(order.id, &test_recipient()).unwrap();
+
+        assert_eq!(cancelled.status, OrderStatus::Cancelled);
+        assert_eq!(
+            payment.refunds(),
+            vec![("mock_auth_1".to_string(), Money(1000))]
+        );
+        let events = sender.events();
+        assert!(matches!(
+            events.last(),
+            Some(NotificationEvent::OrderCancelled { id }) if *id == order.id
+        ));
+        drop(events);
+        assert_eq!(
+            service.get_order(order.id).unwrap().unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn coffee(price: u32) -> Vec<LineItem> {
+        vec![LineItem {
+            name: "Coffee".to_string(),
+            price: Money(price),
+            weight_grams: None,
+        }]
+    }
+
+    #[test]
+    fn subscriptions_run_when_due_and_not_before() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let subscriptions = InMemorySubscriptionRepository::new();
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_subscription_repository(&subscriptions)
+            .with_clock(&clock);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+        let monthly = service
+            .create_subscription(customer.id(), coffee(1200), 30)
+            .unwrap();
+        let weekly = service
+            .create_subscription(customer.id(), coffee(500), 7)
+            .unwrap();
+
+        let early = service.run_due_subscriptions(UNIX_EPOCH + DAY * 6).unwrap();
+        assert!(early.placed.is_empty() && early.failed.is_empty());
+        assert!(repo.orders().is_empty());
+
+        let run = service.run_due_subscriptions(UNIX_EPOCH + DAY * 7).unwrap();
+        let ran: Vec<_> = run.placed.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ran, [weekly.id]);
+        assert_eq!(run.placed[0].1.customer_id, Some(customer.id()));
+        assert_eq!(run.placed[0].1.total, Money(500));
+
+        // Both due a month in; the weekly one runs once, not four times
+        let run = service
+            .run_due_subscriptions(UNIX_EPOCH + DAY * 30)
+            .unwrap();
+        let ran: Vec<_> = run.placed.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ran, [monthly.id, weekly.id]);
+        assert_eq!(repo.orders().len(), 3);
+        assert_eq!(
+            subscriptions.find(monthly.id).unwrap().unwrap().next_run,
+            UNIX_EPOCH + DAY * 60
+        );
+    }
+
+    #[test]
+    fn a_failing_subscription_does_not_block_the_others() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let subscriptions = InMemorySubscriptionRepository::new();
+        let clock = FixedClock::new(UNIX_EPOCH);
+        // Refuses anything over $100
+        let payment = LimitedPaymentGateway::new(MockPaymentGateway::new(), Arc::new(SystemClock))
+            .with_max_per_charge(Money(10_000));
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_subscription_repository(&subscriptions)
+            .with_clock(&clock);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+        let cheap = service
+            .create_subscription(customer.id(), coffee(1200), 30)
+            .unwrap();
+        let pricey = service
+            .create_subscription(customer.id(), coffee(15_000), 30)
+            .unwrap();
+        let also_cheap = service
+            .create_subscription(customer.id(), coffee(900), 30)
+            .unwrap();
+
+        let run = service
+            .run_due_subscriptions(UNIX_EPOCH + DAY * 30)
+            .unwrap();
+
+        let ran: Vec<_> = run.placed.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ran, [cheap.id, also_cheap.id]);
+        assert!(matches!(
+            run.failed.as_slice(),
+            [(id, OrderError::PaymentFailed(PaymentFailureReason::LimitExceeded))] if *id == pricey.id
+        ));
+        // The failed one moved on too: it's not retried on every call
+        assert!(
+            service
+                .run_due_subscriptions(UNIX_EPOCH + DAY * 30)
+                .unwrap()
+                .failed
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn subscriptions_without_a_repository_are_refused() {
+        use std::error::Error as _;
+
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let Err(e) = service.cancel_subscription(SubscriptionId(1)) else {
+            panic!("cancelled a subscription with nowhere to keep it");
+        };
+
+        assert!(matches!(e, OrderError::StorageFailed(_)));
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "cancel_subscription requires a subscription repository"
+        );
+        assert!(matches!(
+            service.run_due_subscriptions(UNIX_EPOCH),
+            Err(OrderError::StorageFailed(_))
+        ));
+    }
+
+    #[test]
+    fn cancelled_subscriptions_never_run() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let subscriptions = InMemorySubscriptionRepository::new();
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_subscription_repository(&subscriptions)
+            .with_clock(&clock);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+        let subscription = service
+            .create_subscription(customer.id(), coffee(1200), 30)
+            .unwrap();
+
+        assert!(!service.cancel_subscription(subscription.id).unwrap().active);
+        let run = service
+            .run_due_subscriptions(UNIX_EPOCH + DAY * 365)
+            .unwrap();
+
+        assert!(run.placed.is_empty());
+        assert!(matches!(
+            service.cancel_subscription(SubscriptionId(99)),
+            Err(OrderError::SubscriptionNotFound(SubscriptionId(99)))
+        ));
+        assert!(matches!(
+            service.create_subscription(CustomerId(99), coffee(1200), 30),
+            Err(OrderError::CustomerNotFound(CustomerId(99)))
+        ));
+    }
+
+    /// A Pending order of `customer`, one item per price, stored as is.
+    fn pending_order(repo: &impl OrderRepository, id: u32, customer: u32, prices: &[u32]) {
+        let items = prices
+            .iter()
+            .map(|&price| coffee(price).remove(0))
+            .collect();
+        let mut order = Order::new(OrderId(id), items).unwrap();
+        order.customer_id = Some(CustomerId(customer));
+        repo.insert(&order).unwrap();
+    }
+
+    #[test]
+    fn merging_keeps_the_first_order_and_tombstones_the_second() {
+        let repo = InMemoryOrderRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        pending_order(&repo, 1, 7, &[1000]);
+        pending_order(&repo, 2, 7, &[500, 250]);
+
+        let merged = service.merge_orders(OrderId(1), OrderId(2)).unwrap();
+
+        assert_eq!(merged.items.len(), 3);
+        assert_eq!(merged.total, Money(1750));
+        assert_eq!(merged.merged_from, [OrderId(2)]);
+        assert_eq!(service.get_order(OrderId(1)).unwrap(), Some(merged));
+        assert_eq!(service.get_order(OrderId(2)).unwrap(), None);
+        assert!(repo.restore(TenantId::default(), OrderId(2)).unwrap());
+    }
+
+    #[test]
+    fn only_pending_orders_of_one_customer_merge() {
+        let repo = InMemoryOrderRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let paid = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+        pending_order(&repo, 11, 7, &[1000]);
+        pending_order(&repo, 12, 8, &[500]);
+
+        assert!(matches!(
+            service.merge_orders(OrderId(11), OrderId(12)),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert!(matches!(
+            service.merge_orders(OrderId(11), paid.id),
+            Err(OrderError::InvalidStatus(_))
+        ));
+        assert!(matches!(
+            service.merge_orders(OrderId(11), OrderId(99)),
+            Err(OrderError::OrderNotFound(OrderId(99)))
+        ));
+        // Nothing was touched
+        assert_eq!(service.get_order(OrderId(11)).unwrap().unwrap().version, 0);
+        assert!(service.get_order(OrderId(12)).unwrap().is_some());
+    }
+
+    #[test]
+    fn splitting_saves_both_halves_under_their_own_ids() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let ids = SequentialIdGenerator::starting_at(OrderId(10));
+        let service = OrderService::new(&repo, &payment, &sender).with_id_generator(&ids);
+        pending_order(&repo, 1, 7, &[1000, 500, 250]);
+
+        let (kept, split_off) = service.split_order(OrderId(1), &[0, 2]).unwrap();
+
+        assert_eq!(kept.total, Money(500));
+        assert_eq!(split_off.id, OrderId(10));
+        assert_eq!(split_off.total, Money(1250));
+        assert_eq!(split_off.customer_id, Some(CustomerId(7)));
+        assert_eq!(repo.order(OrderId(1)), Some(kept));
+        assert_eq!(repo.order(OrderId(10)), Some(split_off));
+    }
+
+    #[test]
+    fn a_split_must_leave_items_on_both_sides() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        pending_order(&repo, 1, 7, &[1000, 500]);
+
+        for indices in [&[][..], &[0, 1], &[0, 0], &[2]] {
+            assert!(matches!(
+                service.split_order(OrderId(1), indices),
+                Err(OrderError::InvalidOrder)
+            ));
+        }
+        assert_eq!(repo.orders().len(), 1);
+        assert!(repo.updated().is_empty());
+    }
+
+    fn shipped_recipient() -> Recipient {
+        test_recipient()
+            .with_address(Address::new("1 Main St", "Springfield", "12345", "US").unwrap())
+    }
+
+    fn weighing(grams: u32) -> Vec<LineItem> {
+        vec![LineItem {
+            name: "Kettle".to_string(),
+            price: Money(3000),
+            weight_grams: Some(grams),
+        }]
+    }
+
+    #[test]
+    fn shipping_is_charged_with_the_order_but_is_no_item() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let shipping = TieredByWeightShipping::new(vec![(500, Money(499)), (2_000, Money(899))]);
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_shipping_calculator(&shipping);
+
+        let order = service
+            .place_order(&shipped_recipient(), weighing(1_200))
+            .unwrap()
+            .order;
+
+        assert_eq!(order.items, weighing(1_200));
+        let shipped = order.shipping.as_ref().unwrap();
+        assert_eq!(Some(&shipped.destination), shipped_recipient().address());
+        assert_eq!(shipped.cost, Money(899));
+        assert_eq!(order.total, Money(3899));
+        assert!(matches!(
+            payment.calls()[..],
+            [
+                PaymentCall::Authorize {
+                    amount: Money(3899),
+                    ..
+                },
+                PaymentCall::Capture(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn nothing_ships_without_an_address_or_a_weight() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let shipping = TieredByWeightShipping::new(vec![(500, Money(499))]);
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_shipping_calculator(&shipping);
+
+        let unshipped = service
+            .place_order(&test_recipient(), weighing(200))
+            .unwrap()
+            .order;
+        let weightless = service
+            .place_order(&shipped_recipient(), weighing(0))
+            .unwrap()
+            .order;
+
+        assert_eq!(unshipped.shipping, None);
+        assert_eq!(unshipped.total, Money(3000));
+        // It ships, for free
+        assert_eq!(weightless.shipping_cost(), Money(0));
+        assert_eq!(weightless.total, Money(3000));
+    }
+
+    #[test]
+    fn an_added_item_pays_for_the_heavier_parcel() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let shipping = TieredByWeightShipping::new(vec![(500, Money(499)), (2_000, Money(899))]);
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_shipping_calculator(&shipping);
+        let order = service
+            .place_order(&shipped_recipient(), weighing(400))
+            .unwrap()
+            .order;
+        let mug = LineItem {
+            name: "Mug".to_string(),
+            price: Money(1000),
+            weight_grams: Some(300),
+        };
+
+        let updated = service
+            .add_item_to_order(order.id, mug, &shipped_recipient())
+            .unwrap()
+            .order;
+
+        // $10.00 for the mug, $4.00 more to ship 700 g than 400 g
+        assert_eq!(updated.shipping_cost(), Money(899));
+        assert_eq!(updated.total, Money(4899));
+        assert_eq!(payment.charges(), vec![Money(1400)]);
+        assert_eq!(updated.extra_charges[0].amount, Money(1400));
+    }
+
+    #[test]
+    fn split_and_merged_orders_ship_at_their_own_weight() {
+        let repo = InMemoryOrderRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let ids = SequentialIdGenerator::starting_at(OrderId(10));
+        let shipping = TieredByWeightShipping::new(vec![(500, Money(499)), (2_000, Money(899))]);
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_id_generator(&ids)
+            .with_shipping_calculator(&shipping);
+        let mut order = Order::new(OrderId(1), [weighing(400), weighing(400)].concat()).unwrap();
+        order.customer_id = Some(CustomerId(7));
+        order
+            .set_shipping(Some(Shipping {
+                destination: shipped_recipient().address().unwrap().clone(),
+                cost: Money(899),
+            }))
+            .unwrap();
+        repo.insert(&order).unwrap();
+
+        let (kept, split_off) = service.split_order(OrderId(1), &[1]).unwrap();
+
+        // Two light parcels
+        assert_eq!(kept.shipping_cost(), Money(499));
+        assert_eq!(split_off.shipping_cost(), Money(499));
+        assert_eq!(kept.total, Money(3499));
+        assert_eq!(
+            repo.find(TenantId::default(), OrderId(10)).unwrap(),
+            Some(split_off)
+        );
+
+        // One heavier parcel again, shipped once
+        let merged = service.merge_orders(OrderId(1), OrderId(10)).unwrap();
+        assert_eq!(merged.shipping_cost(), Money(899));
+        assert_eq!(merged.total, Money(6899));
+        assert_eq!(
+            repo.find(TenantId::default(), OrderId(1)).unwrap(),
+            Some(merged)
+        );
+    }
+
+    #[test]
+    fn quote_prices_the_shipping_place_order_charges() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let shipping = TieredByWeightShipping::new(vec![(500, Money(499)), (2_000, Money(899))]);
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_shipping_calculator(&shipping);
+        let recipient = shipped_recipient();
+
+        let quote = service
+            .quote(&weighing(1_200), recipient.address())
+            .unwrap();
+        let placed = service.place_order(&recipient, weighing(1_200)).unwrap();
+
+        assert_eq!(quote.subtotal, Money(3000));
+        assert_eq!(quote.shipping, Money(899));
+        assert_eq!(quote.total, placed.order.total);
+        // Nowhere to ship to: nothing to pay for shipping
+        let unshipped = service.quote(&weighing(1_200), None).unwrap();
+        assert_eq!(unshipped.shipping, Money(0));
+        assert_eq!(unshipped.total, Money(3000));
+    }
+
+    #[test]
+    fn an_order_too_heavy_to_ship_is_never_charged() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let shipping = TieredByWeightShipping::new(vec![(500, Money(499))]);
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_shipping_calculator(&shipping);
+
+        let result = service.place_order(&shipped_recipient(), weighing(501));
+
+        assert!(matches!(result, Err(OrderError::InvalidOrder)));
+        assert!(payment.calls().is_empty());
+        assert!(repo.orders().is_empty());
+    }
+
+    #[test]
+    fn order_history_lists_every_status_change_in_order() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let service = OrderService::new(&repo, &payment, &sender).with_clock(&clock);
+        let order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+        clock.advance(Duration::from_secs(60));
+        service.cancel_order(order.id, &test_recipient()).unwrap();
+
+        let history = service.get_order_history(order.id).unwrap();
+
+        let changes: Vec<_> = history
+            .iter()
+            .map(|entry| (entry.from, entry.to, entry.at))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (None, OrderStatus::Pending, UNIX_EPOCH),
+                (Some(OrderStatus::Pending), OrderStatus::Paid, UNIX_EPOCH),
+                (
+                    Some(OrderStatus::Paid),
+                    OrderStatus::Cancelled,
+                    UNIX_EPOCH + Duration::from_secs(60)
+                ),
+            ]
+        );
+        assert!(matches!(
+            service.get_order_history(OrderId(999)),
+            Err(OrderError::OrderNotFound(OrderId(999)))
+        ));
+    }
+
+    #[test]
+    fn refund_order_sends_refund_issued() {
+        let repo = MockRepository::new();
+        let sender = MockSender::new();
+        let payment = MockPayment::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+
+        let refunded = service
+            .refund_order(order.id, Money(300), &test_recipient())
+            .unwrap();
+
+        assert_eq!(refunded.refunded, Money(300));
+        assert_eq!(refunded.status, OrderStatus::Paid);
+        let events = sender.events();
+        assert!(matches!(
+            events.last(),
+            Some(NotificationEvent::RefundIssued { amount, .. }) if *amount == Money(300)
+        ));
+    }
+
+    #[test]
+    fn cancel_unknown_order_fails() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let result = service.cancel_order(OrderId(42), &test_recipient());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::OrderNotFound(OrderId(42)))
+        ));
+    }
+
+    #[test]
+    fn place_order_for_notifies_customer_email() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let sender = MockSender::new();
+        let payment = MockPayment::new();
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_customer_repository(&customers);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+
+        let order = service
+            .place_order_for(customer.id(), test_items())
+            .unwrap()
+            .order;
+
+        assert_eq!(order.customer_id, Some(customer.id()));
+        let recipients = sender.recipients();
+        assert_eq!(
+            recipients[0].contact(),
+            &Contact::Email("alice@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn place_order_for_unknown_customer_fails() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_customer_repository(&customers);
+
+        let result = service.place_order_for(CustomerId(99), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::CustomerNotFound(CustomerId(99)))
+        ));
+        // Nothing was charged for a customer we don't know
+        assert!(payment.outstanding_authorizations().is_empty());
+        assert_eq!(payment.capture_count(), 0);
+    }
+
+    // A loyalty ledger that can't be written to
+    struct BrokenLoyaltyLedger;
+
+    impl LoyaltyLedger for BrokenLoyaltyLedger {
+        fn credit(&self, _customer: CustomerId, _points: u32) -> Result<(), OrderError> {
+            Err(OrderError::StorageFailed(None))
+        }
+
+        fn debit(&self, _customer: CustomerId, _points: u32) -> Result<(), OrderError> {
+            Err(OrderError::StorageFailed(None))
+        }
+
+        fn balance(&self, _customer: CustomerId) -> Result<u32, OrderError> {
+            Err(OrderError::StorageFailed(None))
+        }
+    }
+
+    // A loyalty ledger whose next credit fails; it works again afterwards
+    struct FlakyLoyaltyLedger {
+        ledger: InMemoryLoyaltyLedger,
+        fail_next_credit: AtomicBool,
+    }
+
+    impl LoyaltyLedger for FlakyLoyaltyLedger {
+        fn credit(&self, customer: CustomerId, points: u32) -> Result<(), OrderError> {
+            if self.fail_next_credit.swap(false, Ordering::SeqCst) {
+                return Err(OrderError::StorageFailed(None));
+            }
+            self.ledger.credit(customer, points)
+        }
+
+        fn debit(&self, customer: CustomerId, points: u32) -> Result<(), OrderError> {
+            self.ledger.debit(customer, points)
+        }
+
+        fn balance(&self, customer: CustomerId) -> Result<u32, OrderError> {
+            self.ledger.balance(customer)
+        }
+    }
+
+    fn fifty_dollars() -> Vec<LineItem> {
+        vec![LineItem {
+            name: "Keyboard".to_string(),
+            price: Money(5000),
+            weight_grams: None,
+        }]
+    }
+
+    #[test]
+    fn paid_orders_earn_a_point_per_dollar() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let loyalty = InMemoryLoyaltyLedger::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_loyalty_ledger(&loyalty);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+
+        service
+            .place_order_for(customer.id(), fifty_dollars())
+            .unwrap();
+        // $10.00
+        service
+            .place_order_for(customer.id(), test_items())
+            .unwrap();
+        // Anonymous orders earn nothing, for nobody
+        service
+            .place_order(&test_recipient(), fifty_dollars())
+            .unwrap();
+
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 60);
+    }
+
+    #[test]
+    fn refunds_debit_the_points_they_cover() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let loyalty = InMemoryLoyaltyLedger::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_loyalty_ledger(&loyalty);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+        let recipient = customer.recipient();
+        let order = service
+            .place_order_for(customer.id(), fifty_dollars())
+            .unwrap()
+            .order;
+
+        // $39.50 kept: 39 points
+        service
+            .refund_order(order.id, Money(1050), &recipient)
+            .unwrap();
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 39);
+
+        // The rest goes back with the cancellation, and so do the points
+        service.cancel_order(order.id, &recipient).unwrap();
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 0);
+    }
+
+    #[test]
+    fn a_broken_loyalty_ledger_does_not_break_checkout() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let logger = CapturingLogger::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_loyalty_ledger(&BrokenLoyaltyLedger)
+            .with_logger(&logger);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+
+        let placed = service
+            .place_order_for(customer.id(), fifty_dollars())
+            .unwrap();
+
+        assert_eq!(placed.order.status, OrderStatus::Paid);
+        assert!(matches!(
+            placed.warnings.as_slice(),
+            [OrderWarning::LoyaltyNotCredited(OrderError::StorageFailed(
+                _
+            ))]
+        ));
+        assert_eq!(repo.orders().len(), 1);
+
+        // Nor does it break a refund; nothing was credited, nothing is taken back
+        let refunded = service
+            .refund_order(placed.order.id, Money(1000), &customer.recipient())
+            .unwrap();
+        assert_eq!(refunded.refunded, Money(1000));
+        assert_eq!(refunded.loyalty_credited, 0);
+        assert!(
+            !logger
+                .lines()
+                .iter()
+                .any(|line| line.message.contains("[Loyalty]"))
+        );
+    }
+
+    #[test]
+    fn a_refund_takes_back_only_the_points_credited() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let loyalty = FlakyLoyaltyLedger {
+            ledger: InMemoryLoyaltyLedger::new(),
+            fail_next_credit: AtomicBool::new(false),
+        };
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_loyalty_ledger(&loyalty);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+        // $10.00, credited
+        service
+            .place_order_for(customer.id(), test_items())
+            .unwrap();
+        loyalty.fail_next_credit.store(true, Ordering::SeqCst);
+        let uncredited = service
+            .place_order_for(customer.id(), fifty_dollars())
+            .unwrap();
+        assert!(matches!(
+            uncredited.warnings.as_slice(),
+            [OrderWarning::LoyaltyNotCredited(_)]
+        ));
+
+        service
+            .cancel_order(uncredited.order.id, &customer.recipient())
+            .unwrap();
+
+        // The other order's points stay: these 50 were never there to take
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 10);
+    }
+
+    #[test]
+    fn a_settled_payment_earns_points_a_refund_takes_back() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let loyalty = InMemoryLoyaltyLedger::new();
+        let payment = StripePaymentGateway::new().with_async_settlement();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_loyalty_ledger(&loyalty)
+            .with_ordering_policy(OrderingPolicy::AwaitSettlement);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+        let placed = service
+            .place_order_for(customer.id(), fifty_dollars())
+            .unwrap();
+        // Not paid yet: nothing earned
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 0);
+
+        let settled = service
+            .handle_payment_confirmation(
+                placed.order.transaction_id.as_deref().unwrap(),
+                PaymentStatus::Settled,
+            )
+            .unwrap();
+        assert!(settled.warnings.is_empty());
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 50);
+        assert_eq!(repo.order(placed.order.id).unwrap().loyalty_credited, 50);
+
+        service
+            .refund_order(placed.order.id, Money(1050), &customer.recipient())
+            .unwrap();
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 39);
+    }
+
+    #[test]
+    fn an_item_added_after_payment_earns_its_points() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let loyalty = InMemoryLoyaltyLedger::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_customer_repository(&customers)
+            .with_loyalty_ledger(&loyalty);
+        let customer = service
+            .register_customer("Alice", "alice@example.com")
+            .unwrap();
+        let recipient = customer.recipient();
+        let order = service
+            .place_order_for(customer.id(), fifty_dollars())
+            .unwrap()
+            .order;
+
+        // $52.50 paid in all: 52 points
+        service
+            .add_item_to_order(order.id, pen(), &recipient)
+            .unwrap();
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 52);
+
+        service.cancel_order(order.id, &recipient).unwrap();
+        assert_eq!(loyalty.balance(customer.id()).unwrap(), 0);
+    }
+
+    #[test]
+    fn register_customer_with_invalid_email_fails() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_customer_repository(&customers);
+
+        let result = service.register_customer("Bob", "bob-at-example");
+
+        assert!(matches!(result, Err(OrderError::InvalidCustomer)));
+    }
+
+    #[test]
+    fn storage_failure_after_capture_refunds_once() {
+        let repo = MockRepository::new().with_failing_updates();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::StorageFailedAfterCharge { refunded: true })
+        ));
+        assert_eq!(payment.counters().refunds, 1);
+    }
+
+    #[test]
+    fn storage_failure_after_capture_reports_failed_refund() {
+        let repo = MockRepository::new().with_failing_updates();
+        let payment = MockPayment::new().with_failing_refunds();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::StorageFailedAfterCharge { refunded: false })
+        ));
+        assert_eq!(payment.counters().refunds, 1);
+    }
+
+    #[test]
+    fn strict_notification_failure_refunds_and_records_it() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = FailingSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_notification_failure_policy(NotificationFailurePolicy::FailOrder);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::NotificationFailedAfterCharge { refunded: true })
+        ));
+        assert_eq!(payment.counters().refunds, 1);
+        let stored = service.get_order(OrderId(1)).unwrap().unwrap();
+        assert_eq!(stored.status, OrderStatus::Refunded);
+    }
+
+    #[test]
+    fn notification_failure_keeps_order_and_warns() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = FailingSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        assert!(matches!(
+            placed.warnings.as_slice(),
+            [OrderWarning::NotificationFailed(
+                OrderError::NotificationFailed(_)
+            )]
+        ));
+        assert_eq!(sender.attempts(), 1);
+        // Paid, stored, and not refunded
+        let stored = service.get_order(placed.order.id).unwrap().unwrap();
+        assert_eq!(stored.status, OrderStatus::Paid);
+        assert_eq!(payment.counters().refunds, 0);
+    }
+
+    #[test]
+    fn successful_order_has_no_warnings() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        assert!(placed.warnings.is_empty());
+    }
+
+    // -------------------------------------------------------------------------
+    // Query Side
+    // -------------------------------------------------------------------------
+    // No payment or sender doubles below: the read side doesn't need them.
+
+    fn stored_order(repo: &MockRepository, id: u32, price: u32) {
+        let items = vec![LineItem {
+            name: "Widget".to_string(),
+            price: Money(price),
+            weight_grams: None,
+        }];
+        repo.insert(&Order::new(OrderId(id), items).unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn queries_get_and_list_orders() {
+        let repo = MockRepository::new();
+        stored_order(&repo, 2, 500);
+        stored_order(&repo, 1, 300);
+
+        let queries = OrderQueries::new(&repo);
+
+        assert!(queries.get_order(OrderId(1)).unwrap().is_some());
+        assert!(queries.get_order(OrderId(3)).unwrap().is_none());
+        let ids: Vec<OrderId> = queries
+            .list_orders()
+            .unwrap()
+            .iter()
+            .map(|o| o.id)
+            .collect();
+        assert_eq!(ids, vec![OrderId(1), OrderId(2)]);
+    }
+
+    #[test]
+    fn search_orders_matches_item_names_ignoring_case() {
+        let repo = MockRepository::new();
+        for (id, names) in [
+            (3, ["Blue Mug", "Tea"]),
+            (1, ["MUG rack", "Pen"]),
+            (2, ["Tea", "Pen"]),
+        ] {
+            let items = names
+                .iter()
+                .map(|name| LineItem {
+                    name: (*name).to_string(),
+                    price: Money(500),
+                    weight_grams: None,
+                })
+                .collect();
+            repo.insert(&Order::new(OrderId(id), items).unwrap())
+                .unwrap();
+        }
+        let queries = OrderQueries::new(&repo);
+        let ids = |query| -> Vec<OrderId> {
+            queries
+                .search_orders(query)
+                .unwrap()
+                .iter()
+                .map(|order| order.id)
+                .collect()
+        };
+
+        assert_eq!(ids("mug"), [OrderId(1), OrderId(3)]);
+        assert_eq!(ids("BLUE"), [OrderId(3)]);
+        assert_eq!(ids("tea"), [OrderId(2), OrderId(3)]);
+        assert!(ids("kettle").is_empty());
+    }
+
+    #[test]
+    fn queries_order_total() {
+        let repo = MockRepository::new();
+        stored_order(&repo, 1, 300);
+
+        let queries = OrderQueries::new(&repo);
+
+        assert_eq!(queries.order_total(OrderId(1)).unwrap(), Money(300));
+        assert!(matches!(
+            queries.order_total(OrderId(9)),
+            Err(OrderError::OrderNotFound(OrderId(9)))
+        ));
+    }
+
+    #[test]
+    fn queries_order_total_in_another_currency() {
+        let repo = MockRepository::new();
+        stored_order(&repo, 1, 4999);
+        stored_order(&repo, 2, 1);
+        let rates = FixedRateForTests::new(Rate(9100));
+
+        let queries = OrderQueries::new(&repo).with_exchange_rates(&rates);
+
+        assert_eq!(
+            queries.order_total_in(OrderId(1), Currency::Eur).unwrap(),
+            Money(4549)
+        );
+        assert_eq!(
+            queries.order_total_in(OrderId(2), Currency::Eur).unwrap(),
+            Money(1)
+        );
+        assert_eq!(
+            queries.order_total_in(OrderId(1), Currency::Usd).unwrap(),
+            Money(4999)
+        );
+        // Dollars to dollars asks for no rate
+        assert_eq!(rates.asked().len(), 2);
+    }
+
+    #[test]
+    fn order_total_in_an_unquoted_currency_fails() {
+        let repo = MockRepository::new();
+        stored_order(&repo, 1, 300);
+        let rates = StaticExchangeRates::new(HashMap::from([(
+            (Currency::Usd, Currency::Eur),
+            Rate(9100),
+        )]));
+
+        let queries = OrderQueries::new(&repo).with_exchange_rates(&rates);
+
+        assert!(matches!(
+            queries.order_total_in(OrderId(1), Currency::Gbp),
+            Err(OrderError::NoExchangeRate {
+                from: Currency::Usd,
+                to: Currency::Gbp
+            })
+        ));
+        assert!(matches!(
+            queries.order_total_in(OrderId(9), Currency::Eur),
+            Err(OrderError::OrderNotFound(OrderId(9)))
+        ));
+    }
+
+    #[test]
+    fn queries_see_orders_placed_through_a_shared_repository() {
+        let repo = SharedOrderRepository::new(InMemoryOrderRepository::new());
+        let writes = repo.clone();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&writes, &payment, &sender);
+        let queries = OrderQueries::new(&repo);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        assert_eq!(queries.list_orders().unwrap().len(), 1);
+        assert_eq!(
+            queries.order_total(placed.order.id).unwrap(),
+            placed.order.total
+        );
+    }
+
+    #[test]
+    fn place_order_dto_round_trips_through_get_order_dto() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let request = PlaceOrderRequest {
+            recipient_name: "Test".to_string(),
+            recipient_email: "test@example.com".to_string(),
+            items: vec![("Pen".to_string(), 150, 2)],
+        };
+
+        let placed = service.place_order_dto(&request).unwrap();
+        let fetched = service.get_order_dto(placed.id).unwrap().unwrap();
+
+        assert_eq!(placed.total, "$3.00");
+        assert_eq!(placed.status, "Paid");
+        assert_eq!(fetched, placed);
+    }
+
+    #[test]
+    fn place_order_dto_rejects_negative_price_before_payment() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let request = PlaceOrderRequest {
+            recipient_name: "Test".to_string(),
+            recipient_email: "test@example.com".to_string(),
+            items: vec![("Pen".to_string(), -150, 1)],
+        };
+
+        let result = service.place_order_dto(&request);
+
+        assert!(matches!(result, Err(OrderError::InvalidOrder)));
+        assert!(service.get_order_dto(1).unwrap().is_none());
+        assert!(payment.outstanding_authorizations().is_empty());
+        assert_eq!(payment.capture_count(), 0);
+    }
+
+    // Delegates to MockPaymentGateway, but declines the Nth authorization
+    struct DecliningPayment {
+        inner: MockPaymentGateway,
+        decline_on: u32,
+        authorizations: Cell<u32>,
+    }
+
+    impl PaymentGateway for DecliningPayment {
+        fn charge(
+            &self,
+            amount: Money,
+            idempotency_key: Option<&IdempotencyKey>,
+        ) -> Result<PaymentReceipt, OrderError> {
+            self.inner.charge(amount, idempotency_key)
+        }
+
+        fn authorize(
+            &self,
+            amount: Money,
+            idempotency_key: Option<&IdempotencyKey>,
+        ) -> Result<AuthorizationId, OrderError> {
+            self.authorizations.set(self.authorizations.get() + 1);
+            if self.authorizations.get() == self.decline_on {
+                return Err(OrderError::PaymentFailed(PaymentFailureReason::Declined));
+            }
+            self.inner.authorize(amount, idempotency_key)
+        }
+
+        fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+            self.inner.capture(auth)
+        }
+
+        fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+            self.inner.void(auth)
+        }
+
+        fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+            self.inner.refund(transaction_id, amount)
+        }
+    }
+
+    #[test]
+    fn place_orders_continues_past_failures() {
+        let repo = MockRepository::new();
+        // Entry 1 is empty and never reaches payment, so entry 2 is the 2nd authorization
+        let payment = DecliningPayment {
+            inner: MockPaymentGateway::new(),
+            decline_on: 2,
+            authorizations: Cell::new(0),
+        };
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let result = service.place_orders(
+            &test_recipient(),
+            vec![test_items(), vec![], test_items(), test_items()],
+        );
+
+        assert!(!result.is_complete());
+        let failed: Vec<usize> = result.failures.iter().map(|(i, _)| *i).collect();
+        assert_eq!(failed, vec![1, 2]);
+        assert!(matches!(result.failures[0].1, OrderError::InvalidOrder));
+        assert!(matches!(
+            result.failures[1].1,
+            OrderError::PaymentFailed(PaymentFailureReason::Declined)
+        ));
+
+        let ids: Vec<OrderId> = result.placed.iter().map(|p| p.order.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(payment.inner.capture_count(), 2);
+        assert!(payment.inner.outstanding_authorizations().is_empty());
+        assert_eq!(repo.find_all(TenantId::default()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn quote_matches_place_order_without_side_effects() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let items = vec![
+            LineItem {
+                name: "Pen".to_string(),
+                price: Money(150),
+                weight_grams: None,
+            },
+            LineItem {
+                name: "Sticker".to_string(),
+                price: Money(0),
+                weight_grams: None,
+            },
+        ];
+
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let quote = service.quote(&items, None).unwrap();
+        let placed = service.place_order(&test_recipient(), items).unwrap();
+
+        assert_eq!(quote.total, placed.order.total);
+        assert_eq!(
+            quote.warnings,
+            vec![QuoteWarning::FreeItem("Sticker".to_string())]
+        );
+        // Only place_order touched the adapters: one hold, one insert, one update
+        assert_eq!(payment.capture_count(), 1);
+        assert_eq!(repo.counters().total(), 2);
+        assert_eq!(placed.order.id, OrderId(1));
+    }
+
+    #[test]
+    fn quote_rejects_what_place_order_rejects() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        assert!(matches!(
+            service.quote(&[], None),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert!(payment.outstanding_authorizations().is_empty());
+        assert_eq!(repo.counters().total(), 0);
+    }
+
+    #[test]
+    fn service_is_shareable_between_threads() {
+        let repo = InMemoryOrderRepository::new();
+        let payment = MockPaymentGateway::new();
+        let ids = SequentialIdGenerator::new();
+        let sender = MockSender::new();
+        let service = Arc::new(OrderService::new(&repo, &payment, &sender).with_id_generator(&ids));
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                let service = Arc::clone(&service);
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        service
+                            .place_order(&test_recipient(), test_items())
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let ids: Vec<u32> = repo.all_orders().iter().map(|o| o.id.0).collect();
+        assert_eq!(ids, (1..=40).collect::<Vec<_>>());
+        assert_eq!(payment.capture_count(), 40);
+    }
+
+    #[test]
+    fn plugged_in_id_generator_is_used() {
+        let repo = MockRepository::new();
+        let ids = SequentialIdGenerator::new();
+        ids.next_order_id();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_id_generator(&ids);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        assert_eq!(placed.order.id, OrderId(2));
+    }
+
+    // Counts every callback. Atomics, because observers must be Sync.
+    #[derive(Default)]
+    struct CountingObserver {
+        placed: AtomicU32,
+        payment_failures: AtomicU32,
+        retrieved: AtomicU32,
+        found: AtomicU32,
+    }
+
+    impl OrderObserver for CountingObserver {
+        fn on_order_placed(&self, _order: &Order, _elapsed: Duration) {
+            self.placed.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_payment_failed(&self, _error: &OrderError) {
+            self.payment_failures.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_order_retrieved(&self, _id: OrderId, found: bool) {
+            self.retrieved.fetch_add(1, Ordering::Relaxed);
+            if found {
+                self.found.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    struct PanickingObserver;
+
+    impl OrderObserver for PanickingObserver {
+        fn on_order_placed(&self, _order: &Order, _elapsed: Duration) {
+            panic!("observer bug");
+        }
+    }
+
+    #[test]
+    fn observers_see_successes_and_failures() {
+        let repo = MockRepository::new();
+        let observer = CountingObserver::default();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let mut service = OrderService::new(&repo, &payment, &sender);
+        service.add_observer(&observer);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+        service.get_order(placed.order.id).unwrap();
+        service.get_order(OrderId(99)).unwrap();
+
+        let repo = MockRepository::new();
+        let payment = FailingPaymentGateway::default();
+        let mut failing = OrderService::new(&repo, &payment, &sender);
+        failing.add_observer(&observer);
+        let _ = failing.place_order(&test_recipient(), test_items());
+
+        assert_eq!(observer.placed.load(Ordering::Relaxed), 1);
+        assert_eq!(observer.payment_failures.load(Ordering::Relaxed), 1);
+        assert_eq!(observer.retrieved.load(Ordering::Relaxed), 2);
+        assert_eq!(observer.found.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn panicking_observer_does_not_change_the_outcome() {
+        let repo = MockRepository::new();
+        let counting = CountingObserver::default();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let mut service = OrderService::new(&repo, &payment, &sender);
+        service.add_observer(&PanickingObserver);
+        service.add_observer(&counting);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(result.is_ok());
+        // Observers after the panicking one still run
+        assert_eq!(counting.placed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn rejected_order_does_not_consume_an_id() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let rejected = service.place_order(&test_recipient(), vec![]);
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        assert!(matches!(rejected, Err(OrderError::InvalidOrder)));
+        assert_eq!(placed.order.id, OrderId(1));
+    }
+
+    #[test]
+    fn rejected_customer_does_not_consume_an_id() {
+        let repo = MockRepository::new();
+        let customers = InMemoryCustomerRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service =
+            OrderService::new(&repo, &payment, &sender).with_customer_repository(&customers);
+
+        assert!(service.register_customer("Bob", "not-an-email").is_err());
+        let customer = service.register_customer("Bob", "bob@example.com").unwrap();
+
+        assert_eq!(customer.id(), CustomerId(1));
+    }
+
+    #[test]
+    fn outbox_keeps_failed_notifications_for_the_next_flush() {
+        let repo = MockRepository::new();
+        let outbox = InMemoryOutbox::new();
+        let sender = IntermittentSender::failing_first(1);
+        let payment = MockPayment::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_outbox(&outbox);
+
+        service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+        service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+        // Placing only enqueues
+        assert_eq!(sender.attempts(), 0);
+        assert_eq!(outbox.pending(), 2);
+
+        let first = service.flush_notifications(10).unwrap();
+        assert_eq!(first, FlushReport { sent: 1, failed: 1 });
+
+        let second = service.flush_notifications(10).unwrap();
+        assert_eq!(second, FlushReport { sent: 1, failed: 0 });
+
+        // Each confirmation delivered exactly once
+        assert_eq!(sender.attempts(), 3);
+        let mut delivered = sender.delivered();
+        delivered.sort();
+        assert_eq!(delivered, vec![OrderId(1), OrderId(2)]);
+        assert_eq!(outbox.pending(), 0);
+    }
+
+    #[test]
+    fn failed_payment_is_audited() {
+        let repo = MockRepository::new();
+        let payment = FailingPaymentGateway::default();
+        let audit = InMemoryAuditLog::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_audit_log(&audit);
+
+        let _ = service.place_order(&test_recipient(), test_items());
+
+        let entries = audit.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::OrderPlaced);
+        assert_eq!(entries[0].order_id, Some(OrderId(1)));
+        assert_eq!(
+            entries[0].outcome,
+            AuditOutcome::Failed("PaymentFailed(Declined)".to_string())
+        );
+    }
+
+    #[test]
+    fn every_use_case_is_audited() {
+        let repo = MockRepository::new();
+        let audit = InMemoryAuditLog::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_audit_log(&audit);
+
+        let _ = service.place_order(&test_recipient(), vec![]);
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+        let id = placed.order.id;
+        service
+            .refund_order(id, Money(100), &test_recipient())
+            .unwrap();
+        service.cancel_order(id, &test_recipient()).unwrap();
+
+        let trail: Vec<(AuditAction, Option<OrderId>, bool)> = audit
+            .entries()
+            .into_iter()
+            .map(|e| (e.action, e.order_id, e.outcome == AuditOutcome::Succeeded))
+            .collect();
+        assert_eq!(
+            trail,
+            vec![
+                (AuditAction::OrderPlaced, None, false),
+                (AuditAction::OrderPlaced, Some(id), true),
+                (AuditAction::OrderRefunded, Some(id), true),
+                (AuditAction::OrderCancelled, Some(id), true),
+            ]
+        );
+    }
+
+    // An audit trail that can't be written to
+    struct BrokenAuditLog;
+
+    impl AuditLog for BrokenAuditLog {
+        fn record(&self, _entry: AuditEntry) -> Result<(), OrderError> {
+            Err(OrderError::StorageFailed(None))
+        }
+    }
+
+    #[test]
+    fn a_broken_audit_log_is_reported_to_the_logger() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let logger = CapturingLogger::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_audit_log(&BrokenAuditLog)
+            .with_logger(&logger);
+
+        service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        let messages: Vec<String> = logger.lines().into_iter().map(|l| l.message).collect();
+        assert_eq!(
+            messages,
+            ["[Audit] Could not record OrderPlaced: StorageFailed"]
+        );
+    }
+
+    #[test]
+    fn charge_first_payment_failure_stores_nothing() {
+        let repo = MockRepository::new();
+        let payment = FailingPaymentGateway::default();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::ChargeFirst);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(result, Err(OrderError::PaymentFailed(_))));
+        assert!(repo.orders().is_empty());
+    }
+
+    // Delegates to MockPaymentGateway, but the provider is down at capture time
+    struct CaptureFailingPayment {
+        inner: MockPaymentGateway,
+    }
+
+    impl PaymentGateway for CaptureFailingPayment {
+        fn charge(
+            &self,
+            amount: Money,
+            idempotency_key: Option<&IdempotencyKey>,
+        ) -> Result<PaymentReceipt, OrderError> {
+            self.inner.charge(amount, idempotency_key)
+        }
+
+        fn authorize(
+            &self,
+            amount: Money,
+            idempotency_key: Option<&IdempotencyKey>,
+        ) -> Result<AuthorizationId, OrderError> {
+            self.inner.authorize(amount, idempotency_key)
+        }
+
+        fn capture(&self, _auth: &AuthorizationId) -> Result<(), OrderError> {
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable))
+        }
+
+        fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+            self.inner.void(auth)
+        }
+
+        fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+            self.inner.refund(transaction_id, amount)
+        }
+    }
+
+    #[test]
+    fn charge_first_capture_failure_voids_and_cancels_the_stored_order() {
+        let repo = MockRepository::new();
+        let payment = CaptureFailingPayment {
+            inner: MockPaymentGateway::new(),
+        };
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::ChargeFirst);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable))
+        ));
+        assert_eq!(payment.inner.void_count(), 1);
+        assert!(payment.inner.outstanding_authorizations().is_empty());
+        let stored = repo.order(OrderId(1)).unwrap();
+        assert_eq!(stored.status, OrderStatus::Cancelled);
+    }
+
+    #[test]
+    fn save_first_payment_failure_keeps_cancelled_order() {
+        let repo = MockRepository::new();
+        let payment = FailingPaymentGateway::default();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::SaveFirst);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(result, Err(OrderError::PaymentFailed(_))));
+        let stored = repo.order(OrderId(1)).unwrap();
+        assert_eq!(stored.status, OrderStatus::Cancelled);
+        assert_eq!(stored.transaction_id, None);
+        // Support can see why
+        let cancelled = stored.history.last().unwrap();
+        assert_eq!(cancelled.from, Some(OrderStatus::Pending));
+        assert!(cancelled.reason.as_deref().unwrap().contains("Declined"));
+    }
+
+    #[test]
+    fn charge_first_storage_failure_refunds_and_leaves_pending_order() {
+        let repo = MockRepository::new().with_failing_updates();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::ChargeFirst);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::StorageFailedAfterCharge { refunded: true })
+        ));
+        assert_eq!(payment.counters().refunds, 1);
+        // The insert happened after the authorization, so it knows the payment
+        let stored = repo.order(OrderId(1)).unwrap();
+        assert_eq!(stored.status, OrderStatus::Pending);
+        assert!(stored.transaction_id.is_some());
+    }
+
+    #[test]
+    fn save_first_storage_failure_refunds_and_leaves_pending_order() {
+        let repo = MockRepository::new().with_failing_updates();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::SaveFirst);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::StorageFailedAfterCharge { refunded: true })
+        ));
+        assert_eq!(payment.counters().refunds, 1);
+        // The insert happened before any payment
+        let stored = repo.order(OrderId(1)).unwrap();
+        assert_eq!(stored.status, OrderStatus::Pending);
+        assert_eq!(stored.transaction_id, None);
+    }
+
+    #[test]
+    fn save_first_insert_failure_charges_nothing() {
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let repo = FailingRepository::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::SaveFirst);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(result, Err(OrderError::StorageFailed(_))));
+        assert_eq!(payment.counters().refunds, 0);
+    }
+
+    #[test]
+    fn save_first_places_paid_order() {
+        let repo = MockRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::SaveFirst);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        let stored = repo.order(placed.order.id).unwrap();
+        assert_eq!(stored.status, OrderStatus::Paid);
+        assert!(stored.transaction_id.is_some());
+    }
+
+    #[test]
+    fn settlement_webhook_pays_the_order_and_only_then_confirms() {
+        let repo = MockRepository::new();
+        let payment = StripePaymentGateway::new().with_async_settlement();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::AwaitSettlement);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+        let transaction_id = placed.order.transaction_id.clone().unwrap();
+
+        // Charged, not settled: the order waits, and so does the email
+        assert_eq!(placed.order.status, OrderStatus::Pending);
+        assert!(sender.events().is_empty());
+
+        let paid = service
+            .confirm(&transaction_id, PaymentStatus::Settled)
+            .unwrap();
+        // The provider sends the same webhook again
+        service
+            .confirm(&transaction_id, PaymentStatus::Settled)
+            .unwrap();
+
+        assert_eq!(paid.status, OrderStatus::Paid);
+        assert_eq!(repo.order(paid.id).unwrap().status, OrderStatus::Paid);
+        assert!(matches!(
+            sender.events().as_slice(),
+            [NotificationEvent::OrderConfirmed(confirmed)] if *confirmed == paid
+        ));
+        assert_eq!(sender.recipients(), vec![test_recipient()]);
+    }
+
+    #[test]
+    fn placing_an_order_inserts_it_exactly_once() {
+        let repo = PostgresOrderRepository::new().with_logger(Arc::new(NullLogger));
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        let inserts: Vec<_> = repo
+            .query_log()
+            .into_iter()
+            .filter(|query| query.kind == StatementKind::Insert)
+            .collect();
+        assert_eq!(inserts.len(), 1);
+        assert_eq!(inserts[0].table, "orders");
+        assert_eq!(inserts[0].order_id, Some(placed.order.id));
+    }
+
+    #[test]
+    fn charges_carry_the_order_and_correlation_ids() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::AwaitSettlement);
+
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        let metadata = payment.charge_metadata();
+        assert_eq!(metadata.len(), 1);
+        let expected_id = placed.order.id.0.to_string();
+        assert_eq!(metadata[0].get("order_id"), Some(expected_id.as_str()));
+        assert!(
+            metadata[0]
+                .get("correlation_id")
+                .is_some_and(|id| id.starts_with("req-"))
+        );
+        // An anonymous order: no customer to reconcile against
+        assert_eq!(metadata[0].get("customer_id"), None);
+    }
+
+    #[test]
+    fn failed_settlement_webhook_cancels_the_order() {
+        let repo = MockRepository::new();
+        let payment = StripePaymentGateway::new().with_async_settlement();
+        let sender = MockSender::new();
+        let gift_cards = InMemoryGiftCardStore::new();
+        let card = GiftCardId(7);
+        gift_cards.issue(card, Money(400));
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_gift_cards(&gift_cards)
+            .with_ordering_policy(OrderingPolicy::AwaitSettlement);
+
+        let placed = service
+            .place_order_split(&test_recipient(), test_items(), card)
+            .unwrap();
+        let transaction_id = placed.order.transaction_id.clone().unwrap();
+
+        let result = service
+            .handle_payment_confirmation(&transaction_id, PaymentStatus::Failed)
+            .unwrap();
+
+        assert_eq!(result.order.status, OrderStatus::Cancelled);
+        assert!(result.warnings.is_empty());
+        assert_eq!(
+            repo.order(placed.order.id).unwrap().status,
+            OrderStatus::Cancelled
+        );
+        // Compensated: the gift card part is back on the card
+        assert_eq!(gift_cards.balance(card).unwrap(), Money(400));
+        assert!(matches!(
+            sender.events().as_slice(),
+            [NotificationEvent::OrderCancelled { id }] if *id == placed.order.id
+        ));
+        // A webhook for a payment we never made
+        assert!(matches!(
+            service.confirm("ch_unknown", PaymentStatus::Settled),
+            Err(OrderError::PaymentFailed(
+                PaymentFailureReason::InvalidRequest
+            ))
+        ));
+    }
+
+    fn pen_and_paper() -> Vec<LineItem> {
+        vec![
+            LineItem {
+                name: "Pen".to_string(),
+                price: Money(150),
+                weight_grams: None,
+            },
+            LineItem {
+                name: "Paper".to_string(),
+                price: Money(350),
+                weight_grams: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn duplicate_inside_window_is_rejected() {
+        let repo = MockRepository::new();
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_clock(&clock)
+            .with_duplicate_guard(Duration::from_secs(10));
+        let first = service
+            .place_order(&test_recipient(), pen_and_paper())
+            .unwrap();
+
+        clock.advance(Duration::from_secs(3));
+        // Same items, listed the other way round
+        let mut again = pen_and_paper();
+        again.reverse();
+        let result = service.place_order(&test_recipient(), again);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::DuplicateSubmission { existing }) if existing == first.order.id
+        ));
+        assert_eq!(repo.orders().len(), 1);
+    }
+
+    #[test]
+    fn duplicate_after_window_is_placed() {
+        let repo = MockRepository::new();
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_clock(&clock)
+            .with_duplicate_guard(Duration::from_secs(10));
+        service
+            .place_order(&test_recipient(), pen_and_paper())
+            .unwrap();
+
+        clock.advance(Duration::from_secs(10));
+        let second = service.place_order(&test_recipient(), pen_and_paper());
+
+        assert!(second.is_ok());
+        assert_eq!(repo.orders().len(), 2);
+    }
+
+    #[test]
+    fn same_total_with_different_items_is_not_a_duplicate() {
+        let repo = MockRepository::new();
+        let clock = FixedClock::new(UNIX_EPOCH);
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_clock(&clock)
+            .with_duplicate_guard(Duration::from_secs(10));
+        service
+            .place_order(&test_recipient(), pen_and_paper())
+            .unwrap();
+
+        // $5.00 as well, but one notebook instead of a pen and paper
+        let notebook = vec![LineItem {
+            name: "Notebook".to_string(),
+            price: Money(500),
+            weight_grams: None,
+        }];
+        let second = service.place_order(&test_recipient(), notebook);
+
+        assert!(second.is_ok());
+        assert_eq!(repo.orders().len(), 2);
+    }
+
+    #[test]
+    fn orders_of_one_tenant_are_invisible_to_another() {
+        let repo = InMemoryOrderRepository::new();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let shop_a = OrderService::new(&repo, &payment, &sender).with_tenant(TenantId(1));
+        let shop_b = OrderService::new(&repo, &payment, &sender).with_tenant(TenantId(2));
+
+        let placed = shop_a.place_order(&test_recipient(), test_items()).unwrap();
+        let id = placed.order.id;
+
+        assert_eq!(placed.order.tenant, TenantId(1));
+        assert!(shop_a.get_order(id).unwrap().is_some());
+        assert!(shop_b.get_order(id).unwrap().is_none());
+        assert!(matches!(
+            shop_b.cancel_order(id, &test_recipient()),
+            Err(OrderError::OrderNotFound(_))
+        ));
+
+        let queries_b = OrderQueries::new(&repo).with_tenant(TenantId(2));
+        assert!(queries_b.list_orders().unwrap().is_empty());
+        assert!(matches!(
+            queries_b.order_total(id),
+            Err(OrderError::OrderNotFound(_))
+        ));
+        let queries_a = OrderQueries::new(&repo).with_tenant(TenantId(1));
+        assert_eq!(queries_a.list_orders().unwrap().len(), 1);
+        assert_eq!(repo.len(), 1);
+    }
+
+    // Writes one log line per notification to a shared logger
+    struct LoggingSender {
+        logger: Arc<CapturingLogger>,
+    }
+
+    impl Sender for LoggingSender {
+        fn notify(
+            &self,
+            recipient: &Recipient,
+            event: &NotificationEvent,
+        ) -> Result<(), OrderError> {
+            self.notify_with_ctx(&RequestContext::default(), recipient, event)
+        }
+
+        fn notify_with_ctx(
+            &self,
+            ctx: &RequestContext,
+            recipient: &Recipient,
+            _event: &NotificationEvent,
+        ) -> Result<(), OrderError> {
+            self.logger
+                .log(ctx, &format!("[Sender] Notifying {recipient}"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn one_place_order_logs_under_one_correlation_id() {
+        let logger = Arc::new(CapturingLogger::new());
+        let repo = InMemoryOrderRepository::new().with_logger(logger.clone());
+        let payment = MockPaymentGateway::new().with_logger(logger.clone());
+        let sender = LoggingSender {
+            logger: logger.clone(),
+        };
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+        let first = logger.lines();
+        service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+
+        // Repository, payment and sender all logged, all under the same ID
+        for tag in ["[InMemory]", "[Mock]", "[Sender]"] {
+            assert!(first.iter().any(|line| line.message.starts_with(tag)));
+        }
+        let id = &first[0].correlation_id;
+        assert!(!id.is_empty());
+        assert!(first.iter().all(|line| &line.correlation_id == id));
+
+        // The next order gets an ID of its own
+        let second = &logger.lines()[first.len()..];
+        assert!(!second.is_empty());
+        assert!(second.iter().all(|line| &line.correlation_id != id));
+    }
+
+    fn gift_card_with(balance: u32) -> InMemoryGiftCardStore {
+        let gift_cards = InMemoryGiftCardStore::new();
+        gift_cards.issue(GiftCardId(1), Money(balance));
+        gift_cards
+    }
+
+    #[test]
+    fn gift_card_covering_the_order_skips_the_card() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let gift_cards = gift_card_with(5000);
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_gift_cards(&gift_cards);
+
+        let placed = service
+            .place_order_split(&test_recipient(), test_items(), GiftCardId(1))
+            .unwrap();
+
+        assert_eq!(placed.order.status, OrderStatus::Paid);
+        assert_eq!(placed.order.card_amount(), Money(0));
+        assert_eq!(placed.order.transaction_id, None);
+        assert_eq!(payment.capture_count(), 0);
+        assert!(payment.outstanding_authorizations().is_empty());
+        assert_eq!(gift_cards.balance(GiftCardId(1)).unwrap(), Money(4000));
+    }
+
+    #[test]
+    fn empty_gift_card_leaves_everything_to_the_card() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let gift_cards = gift_card_with(0);
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_gift_cards(&gift_cards);
+
+        let placed = service
+            .place_order_split(&test_recipient(), test_items(), GiftCardId(1))
+            .unwrap();
+
+        assert_eq!(placed.order.gift_card, None);
+        assert_eq!(placed.order.card_amount(), Money(1000));
+        assert_eq!(payment.capture_count(), 1);
+    }
+
+    #[test]
+    fn gift_card_pays_its_balance_and_the_card_the_rest() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let gift_cards = gift_card_with(400);
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_gift_cards(&gift_cards);
+
+        let placed = service
+            .place_order_split(&test_recipient(), test_items(), GiftCardId(1))
+            .unwrap();
+
+        assert_eq!(
+            placed.order.gift_card,
+            Some(GiftCardPayment {
+                card: GiftCardId(1),
+                amount: Money(400),
+            })
+        );
+        assert_eq!(placed.order.card_amount(), Money(600));
+        assert_eq!(payment.capture_count(), 1);
+        assert_eq!(gift_cards.balance(GiftCardId(1)).unwrap(), Money(0));
+
+        // Cancelling sends each part back where it came from
+        service
+            .cancel_order(placed.order.id, &test_recipient())
+            .unwrap();
+        assert_eq!(
+            payment.refunds(),
+            vec![("mock_auth_1".to_string(), Money(600))]
+        );
+        assert_eq!(gift_cards.balance(GiftCardId(1)).unwrap(), Money(400));
+    }
+
+    #[test]
+    fn a_gift_card_refund_without_a_provider_is_refused() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let gift_cards = gift_card_with(5000);
+        let sender = MockSender::new();
+        let placed = OrderService::new(&repo, &payment, &sender)
+            .with_gift_cards(&gift_cards)
+            .place_order_split(&test_recipient(), test_items(), GiftCardId(1))
+            .unwrap();
+        // Another wiring, with no gift card provider, loads the same order
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let result = service.cancel_order(placed.order.id, &test_recipient());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::PaymentFailed(
+                PaymentFailureReason::InvalidRequest
+            ))
+        ));
+        assert_eq!(gift_cards.balance(GiftCardId(1)).unwrap(), Money(4000));
+    }
+
+    #[test]
+    fn card_failure_after_gift_card_debit_restores_the_balance() {
+        let repo = MockRepository::new();
+        let gift_cards = gift_card_with(400);
+        let payment = FailingPaymentGateway::default();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_gift_cards(&gift_cards);
+
+        let result = service.place_order_split(&test_recipient(), test_items(), GiftCardId(1));
+
+        assert!(matches!(result, Err(OrderError::PaymentFailed(_))));
+        assert_eq!(gift_cards.balance(GiftCardId(1)).unwrap(), Money(400));
+        assert!(repo.orders().is_empty());
+    }
+
+    fn pen() -> LineItem {
+        LineItem {
+            name: "Pen".to_string(),
+            price: Money(250),
+            weight_grams: None,
+        }
+    }
+
+    #[test]
+    fn add_item_to_pending_order_updates_it_without_charging() {
+        let repo = MockRepository::new();
+        stored_order(&repo, 1, 500);
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let updated = service
+            .add_item_to_order(OrderId(1), pen(), &test_recipient())
+            .unwrap()
+            .order;
+
+        assert_eq!(updated.total, Money(750));
+        assert_eq!(updated.version, 1);
+        assert!(payment.charges().is_empty());
+        let stored = repo.find(TenantId::default(), OrderId(1)).unwrap().unwrap();
+        assert_eq!((stored.total, stored.version), (Money(750), 1));
+        assert!(matches!(
+            sender.events().last(),
+            Some(NotificationEvent::OrderConfirmed(order)) if order.items.len() == 2
+        ));
+    }
+
+    #[test]
+    fn add_item_to_paid_order_charges_the_item_price() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+
+        let updated = service
+            .add_item_to_order(order.id, pen(), &test_recipient())
+            .unwrap()
+            .order;
+
+        assert_eq!(payment.charges(), vec![Money(250)]);
+        assert_eq!(updated.total, Money(1250));
+        assert_eq!(updated.status, OrderStatus::Paid);
+    }
+
+    #[test]
+    fn add_item_keeps_the_extra_charge_when_only_warned() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        // The order's own confirmation goes out, the updated one doesn't
+        let sender = IntermittentSender::scripted(vec![true, false]);
+        let service = OrderService::new(&repo, &payment, &sender);
+        let order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+
+        let updated = service
+            .add_item_to_order(order.id, pen(), &test_recipient())
+            .unwrap();
+
+        assert!(matches!(
+            updated.warnings.as_slice(),
+            [OrderWarning::NotificationFailed(_)]
+        ));
+        // The item was charged and stored, its charge kept for a refund
+        assert_eq!(payment.charges(), vec![Money(250)]);
+        let stored = service.get_order(order.id).unwrap().unwrap();
+        assert_eq!(stored.total, Money(1250));
+        assert_eq!(stored.extra_charges.len(), 1);
+        assert_eq!(stored.extra_charges[0].amount, Money(250));
+        assert!(payment.refunds().is_empty());
+    }
+
+    #[test]
+    fn add_item_refunds_the_extra_charge_when_the_order_must_fail() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = IntermittentSender::scripted(vec![true, false]);
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_notification_failure_policy(NotificationFailurePolicy::FailOrder);
+        let order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+
+        let result = service.add_item_to_order(order.id, pen(), &test_recipient());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::NotificationFailedAfterCharge { refunded: true })
+        ));
+        let refunds = payment.refunds();
+        assert_eq!(refunds.len(), 1);
+        assert_ne!(Some(&refunds[0].0), order.transaction_id.as_ref());
+        assert_eq!(refunds[0].1, Money(250));
+        // Back as it was: no item, no charge to refund twice
+        let stored = service.get_order(order.id).unwrap().unwrap();
+        assert_eq!(stored.items, order.items);
+        assert_eq!(stored.total, Money(1000));
+        assert!(stored.extra_charges.is_empty());
+    }
+
+    #[test]
+    fn a_refund_goes_back_through_every_charge_of_the_order() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+        let first = order.transaction_id.clone().unwrap();
+        let updated = service
+            .add_item_to_order(order.id, pen(), &test_recipient())
+            .unwrap()
+            .order;
+        let extra = updated.extra_charges[0].transaction_id.clone();
+
+        // $9.00 of the first $10.00, then the last $1.00 and the pen
+        service
+            .refund_order(order.id, Money(900), &test_recipient())
+            .unwrap();
+        service.cancel_order(order.id, &test_recipient()).unwrap();
+
+        assert_eq!(
+            payment.refunds(),
+            vec![
+                (first.clone(), Money(900)),
+                (first, Money(100)),
+                (extra, Money(250)),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_item_waits_for_the_settlement_of_the_charge() {
+        let repo = MockRepository::new();
+        let payment = StripePaymentGateway::new().with_async_settlement();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender)
+            .with_ordering_policy(OrderingPolicy::AwaitSettlement);
+        let placed = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap();
+        let transaction_id = placed.order.transaction_id.clone().unwrap();
+
+        let refused = service.add_item_to_order(placed.order.id, pen(), &test_recipient());
+        let paid = service
+            .confirm(&transaction_id, PaymentStatus::Settled)
+            .unwrap();
+
+        assert!(matches!(refused, Err(OrderError::InvalidOrder)));
+        // Paid for what was charged, not for an item added meanwhile
+        assert_eq!(paid.total, placed.order.total);
+        assert_eq!(paid.items, placed.order.items);
+        // Settled, the item can be added, and is charged on its own
+        let updated = service
+            .add_item_to_order(paid.id, pen(), &test_recipient())
+            .unwrap()
+            .order;
+        assert_eq!(updated.total.0, placed.order.total.0 + pen().price.0);
+    }
+
+    #[test]
+    fn add_item_to_shipped_order_is_rejected() {
+        let repo = MockRepository::new();
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+        let mut order = service
+            .place_order(&test_recipient(), test_items())
+            .unwrap()
+            .order;
+        order.mark_shipped(UNIX_EPOCH).unwrap();
+        repo.update(&order).unwrap();
+
+        let result = service.add_item_to_order(order.id, pen(), &test_recipient());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::InvalidStatus(OrderStatus::Shipped))
+        ));
+        assert!(payment.charges().is_empty());
+        assert_eq!(
+            service.get_order(order.id).unwrap().unwrap().total,
+            Money(1000)
+        );
+    }
+
+    fn catalog() -> InMemoryPriceList {
+        InMemoryPriceList::new(HashMap::from([(
+            ProductId("KB-101".to_string()),
+            Money(4999),
+        )]))
+    }
+
+    #[test]
+    fn place_order_from_catalog_uses_catalog_prices() {
+        let repo = MockRepository::new();
+        let prices = catalog();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_price_list(&prices);
+        let items = [OrderRequestItem {
+            product: ProductId("KB-101".to_string()),
+            quantity: 2,
+        }];
+
+        let placed = service
+            .place_order_from_catalog(&test_recipient(), &items)
+            .unwrap();
+
+        assert_eq!(placed.order.total, Money(9998));
+        assert_eq!(placed.order.items.len(), 2);
+    }
+
+    #[test]
+    fn client_supplied_price_is_ignored_for_the_catalog_price() {
+        let repo = MockRepository::new();
+        let prices = catalog();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_price_list(&prices);
+        let request = PlaceOrderRequest {
+            recipient_name: "Test".to_string(),
+            recipient_email: "test@example.com".to_string(),
+            // A keyboard for one cent, please
+            items: vec![("KB-101".to_string(), 1, 1)],
+        };
+
+        let placed = service.place_order_dto(&request).unwrap();
+
+        assert_eq!(placed.total, "$49.99");
+    }
+
+    #[test]
+    fn unknown_product_is_rejected_before_anything_is_stored() {
+        let repo = MockRepository::new();
+        let prices = catalog();
+        let payment = MockPayment::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender).with_price_list(&prices);
+        let items = [OrderRequestItem {
+            product: ProductId("NOPE".to_string()),
+            quantity: 1,
+        }];
+
+        let result = service.place_order_from_catalog(&test_recipient(), &items);
+
+        assert!(matches!(result, Err(OrderError::UnknownProduct(_))));
+        assert!(repo.orders().is_empty());
+    }
+
+    #[test]
+    fn save_failing_after_the_charge_refunds_the_customer() {
+        let repo = FaultyOrderRepository::new(InMemoryOrderRepository::new());
+        // The insert goes through, marking the order Paid doesn't
+        repo.fail_saving_when(|order| order.status == OrderStatus::Paid);
+        let payment = MockPaymentGateway::new();
+        let sender = MockSender::new();
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let result = service.place_order(&test_recipient(), test_items());
+
+        assert!(matches!(
+            result,
+            Err(OrderError::StorageFailedAfterCharge { refunded: true })
+        ));
+        assert_eq!(payment.capture_count(), 1);
+        assert_eq!(
+            payment.refunds(),
+            vec![("mock_auth_1".to_string(), Money(1000))]
+        );
+        let counters = repo.counters();
+        assert_eq!((counters.saves, counters.failures), (2, 1));
+        // The order stays, unpaid: only its update failed
+        assert_eq!(repo.inner().all_orders()[0].status, OrderStatus::Pending);
+    }
+
+    fn report(
+        repository: HealthStatus,
+        payment: HealthStatus,
+        sender: HealthStatus,
+    ) -> HealthReport {
+        HealthReport {
+            repository,
+            payment,
+            sender,
+        }
+    }
+
+    fn degraded(reason: &str) -> HealthStatus {
+        HealthStatus::Degraded(reason.to_string())
+    }
+
+    fn unhealthy(reason: &str) -> HealthStatus {
+        HealthStatus::Unhealthy(reason.to_string())
+    }
+
+    #[test]
+    fn all_healthy_is_healthy() {
+        let all_healthy = report(
+            HealthStatus::Healthy,
+            HealthStatus::Healthy,
+            HealthStatus::Healthy,
+        );
+
+        assert_eq!(all_healthy.overall(), HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn one_degraded_adapter_degrades_the_service() {
+        let health = report(
+            HealthStatus::Healthy,
+            HealthStatus::Healthy,
+            degraded("failures left in the script: 2"),
+        );
+
+        assert_eq!(
+            health.overall(),
+            degraded("sender: failures left in the script: 2")
+        );
+    }
+
+    #[test]
+    fn unhealthy_wins_over_degraded() {
+        let health = report(
+            degraded("one find in 3 fails"),
+            unhealthy("circuit open"),
+            HealthStatus::Healthy,
+        );
+
+        // Only the reasons at the worst level: the degraded repository can wait
+        assert_eq!(health.overall(), unhealthy("payment: circuit open"));
+    }
+
+    #[test]
+    fn the_reasons_of_every_unhealthy_adapter_are_kept() {
+        let health = report(
+            unhealthy("database is locked"),
+            degraded("circuit half-open"),
+            unhealthy("every send fails"),
+        );
+
+        assert_eq!(
+            health.overall(),
+            unhealthy("repository: database is locked; sender: every send fails")
+        );
+    }
+
+    #[test]
+    fn health_report_asks_each_adapter() {
+        let repo = InMemoryOrderRepository::new();
+        let payment = FailingPaymentGateway::default();
+        let sender = IntermittentSender::failing_first(1);
+        let service = OrderService::new(&repo, &payment, &sender);
+
+        let health = service.health_report();
+
+        assert_eq!(
+            health,
+            report(
+                HealthStatus::Healthy,
+                unhealthy("every call fails (Declined)"),
+                degraded("failures left in the script: 1"),
+            )
+        );
+        assert!(!health.overall().is_usable());
     }
 }
 
@@ -0,0 +1,253 @@
+// =============================================================================
+// Tracing - Spans Around the Use Cases and Their Port Calls
+// =============================================================================
+//
+// With the `tracing` feature, every use case runs in a span, and so does each
+// call it makes through the three main ports:
+//
+//     place_order{order_id=1 total=$53.97}
+//     ├── payment{call="authorize" adapter="MockPaymentGateway" order_id=1 total=$53.97}
+//     ├── repository{call="insert" adapter="InMemoryOrderRepository" order_id=1 total=$53.97}
+//     ├── payment{call="capture" ...}
+//     ├── repository{call="update" ...}
+//     └── sender{call="notify" adapter="ConsoleSender" order_id=1}
+//
+// A failure is an event, at WARN, with the error's stable code: in the span
+// of the port that failed, and in the use case's span (see `failed`).
+//
+// WHY HERE AND NOT IN THE ADAPTERS?
+// ---------------------------------
+// The application is the one place every port call goes through. Spans
+// opened here cover every adapter, including the ones written next year,
+// and the adapter's name comes from its type. Adapters that log through the
+// `Logger` port can plug in adapters-repository's `TracingLogger`: their
+// lines then become events inside these spans.
+//
+// The domain knows nothing of this, and without the feature neither does the
+// application: every function below compiles to a plain call.
+
+use domain::{Money, Order, OrderId};
+
+/// What a span is about: an order, as far as it's known.
+// Without the feature, nothing reads it: the calls that build it stay the same
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Subject {
+    pub(crate) id: Option<OrderId>,
+    pub(crate) total: Option<Money>,
+}
+
+impl Subject {
+    /// An order known only by its ID.
+    pub(crate) const fn id(id: OrderId) -> Self {
+        Self {
+            id: Some(id),
+            total: None,
+        }
+    }
+
+    /// An order's total, when its ID is already recorded.
+    pub(crate) const fn total(total: Money) -> Self {
+        Self {
+            id: None,
+            total: Some(total),
+        }
+    }
+}
+
+impl From<&Order> for Subject {
+    fn from(order: &Order) -> Self {
+        Self {
+            id: Some(order.id),
+            total: Some(order.total),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) use enabled::*;
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) use disabled::*;
+
+#[cfg(feature = "tracing")]
+mod enabled {
+    use super::Subject;
+    use domain::OrderError;
+    use tracing::field::{self, Empty};
+    use tracing::span::EnteredSpan;
+    use tracing::{Span, info_span};
+
+    /// Enters the span of a `place_order` (any of its variants).
+    pub(crate) fn place_order() -> EnteredSpan {
+        info_span!("place_order", order_id = Empty, total = Empty).entered()
+    }
+
+    /// Enters the span of a `get_order`.
+    pub(crate) fn get_order(subject: Subject) -> EnteredSpan {
+        let span = info_span!("get_order", order_id = Empty, total = Empty);
+        fill(&span, subject);
+        span.entered()
+    }
+
+    /// Adds what's now known of the order to the current span.
+    pub(crate) fn record(subject: Subject) {
+        fill(&Span::current(), subject);
+    }
+
+    /// Reports a use case's failure, with the error's code.
+    pub(crate) fn failed(error: &OrderError) {
+        tracing::warn!(code = error.code(), error = %error, "use case failed");
+    }
+
+    /// Runs a call to the OrderRepository adapter `A` in a span.
+    pub(crate) fn repository<A: ?Sized, T>(
+        call: &'static str,
+        subject: Subject,
+        f: impl FnOnce() -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        let span = info_span!(
+            "repository",
+            call,
+            adapter = %adapter::<A>(),
+            order_id = Empty,
+            total = Empty,
+        );
+        run(&span, subject, f)
+    }
+
+    /// Runs a call to the PaymentGateway adapter `A` in a span.
+    pub(crate) fn payment<A: ?Sized, T>(
+        call: &'static str,
+        subject: Subject,
+        f: impl FnOnce() -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        let span = info_span!(
+            "payment",
+            call,
+            adapter = %adapter::<A>(),
+            order_id = Empty,
+            total = Empty,
+        );
+        run(&span, subject, f)
+    }
+
+    /// Runs a call to the Sender adapter `A` in a span.
+    pub(crate) fn sender<A: ?Sized, T>(
+        call: &'static str,
+        subject: Subject,
+        f: impl FnOnce() -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        let span = info_span!(
+            "sender",
+            call,
+            adapter = %adapter::<A>(),
+            order_id = Empty,
+            total = Empty,
+        );
+        run(&span, subject, f)
+    }
+
+    fn run<T>(
+        span: &Span,
+        subject: Subject,
+        f: impl FnOnce() -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        fill(span, subject);
+        let _entered = span.enter();
+        f().inspect_err(|e| {
+            tracing::warn!(code = e.code(), error = %e, "port call failed");
+        })
+    }
+
+    fn fill(span: &Span, subject: Subject) {
+        if let Some(id) = subject.id {
+            span.record("order_id", id.0);
+        }
+        if let Some(total) = subject.total {
+            span.record("total", field::display(total));
+        }
+    }
+
+    /// The adapter's type name, without module paths:
+    /// `RecordingPaymentGateway<MockPaymentGateway>`.
+    fn adapter<A: ?Sized>() -> String {
+        let full = std::any::type_name::<A>();
+        let mut short = String::with_capacity(full.len());
+        // Where the path segment being read started in `short`
+        let mut start = 0;
+        let mut chars = full.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == ':' && chars.peek() == Some(&':') {
+                chars.next();
+                short.truncate(start);
+            } else {
+                short.push(c);
+                if !(c.is_alphanumeric() || c == '_') {
+                    start = short.len();
+                }
+            }
+        }
+        short
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn adapter_names_drop_module_paths() {
+            assert_eq!(adapter::<Subject>(), "Subject");
+            assert_eq!(adapter::<Option<Vec<String>>>(), "Option<Vec<String>>");
+            assert_eq!(adapter::<dyn Fn(u8)>(), "dyn Fn(u8)");
+        }
+    }
+}
+
+// Same signatures as `enabled`, so callers can't tell: hence the adapter type
+// parameters nothing uses
+#[cfg(not(feature = "tracing"))]
+#[allow(clippy::extra_unused_type_parameters)]
+mod disabled {
+    use super::Subject;
+    use domain::OrderError;
+
+    /// Stands for a span guard. Nothing to exit.
+    pub(crate) struct Entered;
+
+    pub(crate) const fn place_order() -> Entered {
+        Entered
+    }
+
+    pub(crate) const fn get_order(_subject: Subject) -> Entered {
+        Entered
+    }
+
+    pub(crate) const fn record(_subject: Subject) {}
+
+    pub(crate) const fn failed(_error: &OrderError) {}
+
+    pub(crate) fn repository<A: ?Sized, T>(
+        _call: &'static str,
+        _subject: Subject,
+        f: impl FnOnce() -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        f()
+    }
+
+    pub(crate) fn payment<A: ?Sized, T>(
+        _call: &'static str,
+        _subject: Subject,
+        f: impl FnOnce() -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        f()
+    }
+
+    pub(crate) fn sender<A: ?Sized, T>(
+        _call: &'static str,
+        _subject: Subject,
+        f: impl FnOnce() -> Result<T, OrderError>,
+    ) -> Result<T, OrderError> {
+        f()
+    }
+}
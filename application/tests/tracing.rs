@@ -0,0 +1,211 @@
+// =============================================================================
+// Tracing Tests - The Spans a place_order Opens
+// =============================================================================
+//
+// Only built with the feature: `cargo test -p application --features tracing`
+//
+// A subscriber of our own keeps every span (its name, its parent, its fields)
+// and every event (the span it happened in, its error code). The assertions
+// are on that tree, not on formatted output: a change of format in
+// tracing-subscriber must not break them.
+
+#![cfg(feature = "tracing")]
+
+use adapters_notification::RecordingSender;
+use adapters_payment::{FailingPaymentGateway, MockPaymentGateway};
+use adapters_repository::InMemoryOrderRepository;
+use application::OrderService;
+use domain::{Contact, LineItem, Money, PaymentGateway, Recipient};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/// One span, as the capture saw it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SpanRecord {
+    name: &'static str,
+    parent: Option<&'static str>,
+    fields: BTreeMap<String, String>,
+}
+
+/// One event: the span it happened in, and its `code` field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EventRecord {
+    span: Option<&'static str>,
+    code: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Captured {
+    /// In the order they were opened; `ids` says which is which.
+    spans: Vec<SpanRecord>,
+    ids: Vec<Id>,
+    events: Vec<EventRecord>,
+}
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Captured>>);
+
+#[derive(Default)]
+struct Fields(BTreeMap<String, String>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S> Layer<S> for Capture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent())
+            .map(|parent| parent.name());
+        let mut captured = self.0.lock().unwrap();
+        captured.spans.push(SpanRecord {
+            name: attrs.metadata().name(),
+            parent,
+            fields: fields.0,
+        });
+        captured.ids.push(id.clone());
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        let mut captured = self.0.lock().unwrap();
+        let index = captured.ids.iter().rposition(|known| known == id).unwrap();
+        captured.spans[index].fields.extend(fields.0);
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.0.lock().unwrap().events.push(EventRecord {
+            span: ctx.event_span(event).map(|span| span.name()),
+            code: fields.0.remove("code"),
+        });
+    }
+}
+
+fn items() -> Vec<LineItem> {
+    vec![
+        LineItem {
+            name: "Book".to_string(),
+            price: Money(4999),
+            weight_grams: None,
+        },
+        LineItem {
+            name: "Pen".to_string(),
+            price: Money(199),
+            weight_grams: None,
+        },
+    ]
+}
+
+fn ferris() -> Recipient {
+    Recipient::new("Ferris", Contact::Email("ferris@rustacean.net".to_string())).unwrap()
+}
+
+/// Places one order through `payment`, and returns what was traced.
+fn place_order_traced(payment: &impl PaymentGateway) -> Captured {
+    let capture = Capture::default();
+    let repo = InMemoryOrderRepository::new();
+    let sender = RecordingSender::new();
+    let service = OrderService::new(&repo, payment, &sender);
+
+    let subscriber = Registry::default().with(capture.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        let _ = service.place_order(&ferris(), items());
+    });
+
+    std::mem::take(&mut *capture.0.lock().unwrap())
+}
+
+/// `(name, parent, call)` for each span, in the order they were opened.
+fn tree(captured: &Captured) -> Vec<(&str, Option<&str>, Option<&str>)> {
+    captured
+        .spans
+        .iter()
+        .map(|span| {
+            (
+                span.name,
+                span.parent,
+                span.fields.get("call").map(String::as_str),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn a_successful_place_order_spans_each_port_call() {
+    let captured = place_order_traced(&MockPaymentGateway::new());
+
+    assert_eq!(
+        tree(&captured),
+        [
+            ("place_order", None, None),
+            ("payment", Some("place_order"), Some("authorize")),
+            ("repository", Some("place_order"), Some("insert")),
+            ("payment", Some("place_order"), Some("capture")),
+            ("repository", Some("place_order"), Some("update")),
+            ("sender", Some("place_order"), Some("notify")),
+        ]
+    );
+    let place_order = &captured.spans[0].fields;
+    assert_eq!(place_order["order_id"], "1");
+    assert_eq!(place_order["total"], "$51.98");
+    let insert = &captured.spans[2].fields;
+    assert_eq!(insert["adapter"], "InMemoryOrderRepository");
+    assert_eq!(insert["order_id"], "1");
+    assert_eq!(insert["total"], "$51.98");
+    assert_eq!(captured.spans[5].fields["adapter"], "RecordingSender");
+    assert!(captured.events.iter().all(|event| event.code.is_none()));
+}
+
+#[test]
+fn a_failing_place_order_reports_the_code_where_it_failed() {
+    let captured = place_order_traced(&FailingPaymentGateway::default());
+
+    assert_eq!(
+        tree(&captured),
+        [
+            ("place_order", None, None),
+            ("payment", Some("place_order"), Some("authorize")),
+        ]
+    );
+    assert_eq!(captured.spans[1].fields["adapter"], "FailingPaymentGateway");
+    let failures: Vec<_> = captured
+        .events
+        .iter()
+        .filter(|event| event.code.is_some())
+        .collect();
+    assert_eq!(
+        failures,
+        [
+            &EventRecord {
+                span: Some("payment"),
+                code: Some("payment_failed".to_string()),
+            },
+            &EventRecord {
+                span: Some("place_order"),
+                code: Some("payment_failed".to_string()),
+            },
+        ]
+    );
+}
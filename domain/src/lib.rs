@@ -24,15 +24,33 @@
 //
 // WHAT BELONGS HERE:
 // ------------------
-// - Value Objects (OrderId, Money)
-// - Entities (Order, LineItem)
-// - Domain Errors (OrderError)
-// - Port Traits (OrderRepository, PaymentGateway, Sender)
+// - Value Objects (OrderId, TenantId, SubscriptionId, GiftCardId, GiftCardPayment, Money, Currency,
+//   Rate, Address, Shipping, ExtraCharge, PaymentReceipt,
+//   PaymentStatus, AuthorizationId, IdempotencyKey, Recipient, Contact, FlushReport)
+// - Parse Errors (ParseMoneyError, ParseOrderIdError, ParseLineItemError)
+// - Entities (Order, LineItem, OrderStatus, HistoryEntry, Customer, Subscription)
+// - Domain Events (NotificationEvent)
+// - Audit Records (AuditEntry, AuditAction, AuditOutcome)
+// - Request Context (RequestContext)
+// - Domain Errors (OrderError, PaymentFailureReason)
+// - Port Traits (OrderRepository, PaymentGateway, Sender, IdempotencyStore,
+//   CustomerRepository, IdGenerator, NotificationOutbox, AuditLog, Clock, Logger,
+//   GiftCardGateway, LoyaltyLedger, SubscriptionRepository, ExchangeRates,
+//   ShippingCalculator)
 //
 // The port traits live here because the domain DEFINES what it needs.
 // Adapters (in other crates) IMPLEMENT those needs.
+//
+// The one exception to "depends on nothing": the optional `proptest` feature,
+// which adds random Money and LineItem generators (strategies.rs) for other
+// crates' property tests. Builds without it never see proptest.
 
 use std::fmt;
+use std::ops::ControlFlow;
+use std::time::{Duration, SystemTime};
+
+#[cfg(any(test, feature = "proptest"))]
+pub mod strategies;
 
 // =============================================================================
 // Value Objects
@@ -43,20 +61,161 @@ use std::fmt;
 //
 // We use newtype wrappers (struct OrderId(u32)) instead of raw primitives.
 // This gives us type safety: we can't pass a CustomerId where OrderId is expected.
+//
+// Money's constructors and arithmetic are `const fn`, so a price can be a
+// constant the compiler checks: `money!(4, 99)` is $4.99, and
+// `money!(4, 100)` doesn't compile.
 
 /// A unique identifier for an order.
 ///
 /// Using a newtype wrapper instead of raw `u32` provides type safety.
 /// The compiler prevents mixing up different ID types.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct OrderId(pub u32);
 
+impl OrderId {
+    /// The ID after this one, or `None` after the last one.
+    ///
+    /// A `const fn`, like Money's helpers, so ID constants can be built from
+    /// one another.
+    #[must_use]
+    pub const fn next(self) -> Option<Self> {
+        match self.0.checked_add(1) {
+            Some(next) => Some(Self(next)),
+            None => None,
+        }
+    }
+}
+
 impl fmt::Display for OrderId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "OrderId({})", self.0)
     }
 }
 
+/// Why a string isn't an [`OrderId`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOrderIdError(pub String);
+
+impl fmt::Display for ParseOrderIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} isn't an order ID", self.0)
+    }
+}
+
+impl std::error::Error for ParseOrderIdError {}
+
+/// Parses an order number as typed by a person: `"42"`, spaces around
+/// allowed.
+///
+/// Only ASCII digits: no sign, and no other script's digits, which a `u32`
+/// parser might otherwise accept or choke on. Numbers too large for a `u32`
+/// are refused.
+impl std::str::FromStr for OrderId {
+    type Err = ParseOrderIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.trim();
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseOrderIdError(s.to_string()));
+        }
+        digits
+            .parse()
+            .map(OrderId)
+            .map_err(|_| ParseOrderIdError(s.to_string()))
+    }
+}
+
+/// A unique identifier for a customer.
+///
+/// A separate newtype from [`OrderId`]: `find(CustomerId(1))` on the order
+/// repository simply doesn't compile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomerId(pub u32);
+
+impl fmt::Display for CustomerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CustomerId({})", self.0)
+    }
+}
+
+/// A unique identifier for a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(pub u32);
+
+impl fmt::Display for SubscriptionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SubscriptionId({})", self.0)
+    }
+}
+
+/// A gift card, as known to the gift card provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GiftCardId(pub u32);
+
+impl fmt::Display for GiftCardId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GiftCardId({})", self.0)
+    }
+}
+
+/// A product of the catalog, by SKU (e.g. `"KB-101"`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProductId(pub String);
+
+impl fmt::Display for ProductId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProductId({})", self.0)
+    }
+}
+
+/// What a client may ask for: a product and how many of it.
+///
+/// No price on purpose. What a product costs is the catalog's business,
+/// not the client's: see [`PriceList`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderRequestItem {
+    pub product: ProductId,
+    pub quantity: u32,
+}
+
+/// The part of an order paid with a gift card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GiftCardPayment {
+    pub card: GiftCardId,
+    pub amount: Money,
+}
+
+/// A charge an order took on top of its first payment: the price of an
+/// item added once the order was paid ([`Order::add_item`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraCharge {
+    /// The provider's ID for this charge: its refund goes through it.
+    pub transaction_id: String,
+    pub amount: Money,
+}
+
+/// Where an order ships, and what that costs (see [`ShippingCalculator`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shipping {
+    pub destination: Address,
+    pub cost: Money,
+}
+
+/// A storefront sharing this service with others.
+///
+/// Every order belongs to exactly one tenant, and the repository only ever
+/// answers questions within one tenant. Single-storefront setups never have
+/// to think about it: they all live in `TenantId::default()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TenantId(pub u32);
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TenantId({})", self.0)
+    }
+}
+
 /// Monetary value in cents to avoid floating-point precision issues.
 ///
 /// $49.99 is stored as `Money(4999)`. This is a common pattern in financial
@@ -64,7 +223,65 @@ impl fmt::Display for OrderId {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Money(pub u32);
 
+/// A [`Money`] constant, checked at compile time: `money!(49, 99)` is
+/// $49.99, `money!(12)` is $12.00.
+///
+/// Cents outside 0-99, or an amount too large for [`Money`], don't compile:
+///
+/// ```compile_fail
+/// const PRICE: domain::Money = domain::money!(49, 100);
+/// ```
+#[macro_export]
+macro_rules! money {
+    ($dollars:expr) => {
+        $crate::money!($dollars, 0)
+    };
+    ($dollars:expr, $cents:expr) => {
+        const { $crate::Money::const_new($dollars, $cents) }
+    };
+}
+
 impl Money {
+    /// No money at all: $0.00.
+    pub const ZERO: Self = Self(0);
+
+    /// The currency every amount is in: the shop sells in US dollars.
+    ///
+    /// Amounts in other currencies only come out of [`Money::convert`], for
+    /// reporting; an order never holds one.
+    pub const CURRENCY: Currency = Currency::Usd;
+
+    /// The amount `dollars.cents`, or `None` if `cents` isn't 0-99 or the
+    /// amount doesn't fit in a `u32` of cents.
+    #[must_use]
+    pub const fn from_dollars_and_cents(dollars: u32, cents: u32) -> Option<Self> {
+        if cents >= 100 {
+            return None;
+        }
+        match dollars.checked_mul(100) {
+            Some(whole) => match whole.checked_add(cents) {
+                Some(total) => Some(Self(total)),
+                None => None,
+            },
+            None => None,
+        }
+    }
+
+    /// The amount `dollars.cents`, for constants: see [`money!`].
+    ///
+    /// # Panics
+    ///
+    /// If `cents` isn't 0-99 or the amount doesn't fit in a `u32` of cents.
+    /// In a constant, the panic is a compile error.
+    #[must_use]
+    pub const fn const_new(dollars: u32, cents: u32) -> Self {
+        assert!(cents < 100, "cents must be 0-99");
+        match Self::from_dollars_and_cents(dollars, cents) {
+            Some(amount) => amount,
+            None => panic!("amount too large for Money"),
+        }
+    }
+
     /// Returns the dollars portion (e.g., 49 for $49.99).
     #[must_use]
     pub const fn dollars(self) -> u32 {
@@ -76,6 +293,79 @@ impl Money {
     pub const fn cents(self) -> u32 {
         self.0 % 100
     }
+
+    /// `self + other`, or `None` on overflow.
+    #[must_use]
+    pub const fn checked_add(self, other: Self) -> Option<Self> {
+        match self.0.checked_add(other.0) {
+            Some(total) => Some(Self(total)),
+            None => None,
+        }
+    }
+
+    /// `self - other`, or `None` if `other` is the larger.
+    #[must_use]
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match self.0.checked_sub(other.0) {
+            Some(difference) => Some(Self(difference)),
+            None => None,
+        }
+    }
+
+    /// `self - other`, or $0.00 if `other` is the larger.
+    #[must_use]
+    pub const fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    /// `self` times `quantity`, or `None` on overflow.
+    #[must_use]
+    pub const fn checked_mul(self, quantity: u32) -> Option<Self> {
+        match self.0.checked_mul(quantity) {
+            Some(total) => Some(Self(total)),
+            None => None,
+        }
+    }
+
+    /// Splits the amount into `parts` amounts adding up to exactly the whole.
+    ///
+    /// The parts differ by one cent at most, the larger ones first: $10.00
+    /// in 3 is $3.34, $3.33 and $3.33. No cent gets lost, none gets made up.
+    /// Zero parts is an empty list.
+    #[must_use]
+    pub fn allocate(self, parts: u32) -> Vec<Self> {
+        if parts == 0 {
+            return Vec::new();
+        }
+        let (share, left_over) = (self.0 / parts, self.0 % parts);
+        (0..parts)
+            .map(|part| Self(share + u32::from(part < left_over)))
+            .collect()
+    }
+
+    /// This amount (in [`Money::CURRENCY`]) in `to`, at the rate `rates`
+    /// gives. The result is in `to`'s cents.
+    ///
+    /// Integer math all the way: cents times the rate's ten-thousandths,
+    /// rounded half up to the nearest cent. 1 cent at 0.91 is 0.91 cents, so
+    /// 1 cent; 1 cent at 0.45 is 0 cents. Converting to [`Money::CURRENCY`]
+    /// itself asks for no rate and changes nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `rates` (e.g. [`OrderError::NoExchangeRate`]),
+    /// or [`OrderError::InvalidOrder`] if the result doesn't fit in `Money`.
+    pub fn convert(&self, to: Currency, rates: &dyn ExchangeRates) -> Result<Self, OrderError> {
+        if to == Self::CURRENCY {
+            return Ok(*self);
+        }
+        let rate = rates.rate(Self::CURRENCY, to)?;
+        let scale = u64::from(Rate::SCALE);
+        let converted = (u64::from(self.0) * u64::from(rate.0) + scale / 2) / scale;
+        u32::try_from(converted)
+            .map(Self)
+            .map_err(|_| OrderError::InvalidOrder)
+    }
 }
 
 impl fmt::Display for Money {
@@ -84,189 +374,3107 @@ impl fmt::Display for Money {
     }
 }
 
-// =============================================================================
-// Entities
-// =============================================================================
-//
-// Entities have identity: two orders with the same items are still different
-// orders if they have different IDs. Unlike value objects, entities are mutable
-// over their lifecycle (though we keep Order simple here).
+/// Why a string isn't an amount of [`Money`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMoneyError(pub String);
 
-/// A single item in an order.
-#[derive(Debug, Clone)]
-pub struct LineItem {
-    pub name: String,
-    pub price: Money,
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} isn't an amount like 49.99", self.0)
+    }
 }
 
-/// An order containing line items.
+impl std::error::Error for ParseMoneyError {}
+
+/// Parses an amount the way [`Display`](fmt::Display) writes it, with or
+/// without the `$`: `"$49.99"`, `"49.99"`, `"49.9"` and `"49"` are all fine.
 ///
-/// Notice what's NOT here: database IDs, timestamps, "created_by" fields.
-/// Those are infrastructure concerns. The domain only cares about what
-/// an order IS from a business perspective.
-#[derive(Debug, Clone)]
-pub struct Order {
-    pub id: OrderId,
-    pub items: Vec<LineItem>,
-    pub total: Money,
-}
+/// More than two decimals would be a fraction of a cent, so they're refused,
+/// as are negative amounts and amounts too large for a `u32` of cents.
+impl std::str::FromStr for Money {
+    type Err = ParseMoneyError;
 
-impl Order {
-    /// Creates a new order from line items.
-    ///
-    /// This is where business rules live! "An order must have at least one item"
-    /// is a business rule, not a database constraint or API validation.
-    ///
-    /// # Errors
-    ///
-    /// Returns [`OrderError::InvalidOrder`] if items is empty.
-    pub fn new(id: OrderId, items: Vec<LineItem>) -> Result<Self, OrderError> {
-        // Business rule: an order must have items
-        if items.is_empty() {
-            return Err(OrderError::InvalidOrder);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseMoneyError(s.to_string());
+        let amount = s.trim();
+        let amount = amount.strip_prefix('$').unwrap_or(amount);
+        let (dollars, cents) = amount.split_once('.').unwrap_or((amount, "00"));
+        let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+        if !all_digits(dollars) || !all_digits(cents) || cents.len() > 2 {
+            return Err(error());
         }
 
-        // Calculate total: pure business logic
-        let total = Money(items.iter().map(|item| item.price.0).sum());
-
-        Ok(Self { id, items, total })
+        let dollars: u32 = dollars.parse().map_err(|_| error())?;
+        // "49.9" is 90 cents, not 9
+        let cents: u32 = format!("{cents:0<2}").parse().map_err(|_| error())?;
+        dollars
+            .checked_mul(100)
+            .and_then(|total| total.checked_add(cents))
+            .map(Money)
+            .ok_or_else(error)
     }
 }
 
-// =============================================================================
-// Domain Errors
-// =============================================================================
-//
-// These are BUSINESS errors, not technical errors. Notice:
-// - "InvalidOrder" = business rule violation
-// - "PaymentFailed" = business operation failed
-//
-// We DON'T have "DatabaseConnectionError" or "HttpTimeout".
-// Those are adapter errors that get TRANSLATED into domain errors.
-// The domain never sees sqlx::Error or reqwest::Error.
-//
-// More information available here:
-// https://www.40tude.fr/docs/06_programmation/rust/016_errors/errors_02.html
-
-/// Domain errors for order operations.
-#[derive(Debug)]
-pub enum OrderError {
-    /// Order violates business rules (e.g., no items).
-    InvalidOrder,
-    /// Payment processing failed.
-    PaymentFailed,
-    /// Storage operation failed.
-    StorageFailed,
-    /// Notification delivery failed.
-    NotificationFailed,
+/// A currency [`Money`] can be converted to, by its ISO 4217 code.
+///
+/// Only currencies with cents: a conversion result is a number of cents,
+/// whatever the currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Currency {
+    Usd,
+    Eur,
+    Gbp,
+    Chf,
 }
 
-impl fmt::Display for OrderError {
+impl fmt::Display for Currency {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::InvalidOrder => write!(f, "InvalidOrder"),
-            Self::PaymentFailed => write!(f, "PaymentFailed"),
-            Self::StorageFailed => write!(f, "StorageFailed"),
-            Self::NotificationFailed => write!(f, "NotificationFailed"),
+            Self::Usd => write!(f, "USD"),
+            Self::Eur => write!(f, "EUR"),
+            Self::Gbp => write!(f, "GBP"),
+            Self::Chf => write!(f, "CHF"),
         }
     }
 }
 
-impl std::error::Error for OrderError {}
+/// How much one unit of a currency is worth in another, in fixed point:
+/// ten-thousandths, so `Rate(9100)` is 0.91.
+///
+/// Four decimals are what rate feeds publish; a float would make the same
+/// conversion come out a cent apart on two machines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate(pub u32);
 
-// =============================================================================
-// Port Traits (Output Ports)
-// =============================================================================
-//
-// Here's where it gets interesting! Remember the Sender trait from dip_02?
-// That was our first "port". Ports define what the domain NEEDS from the
-// outside world, without specifying HOW those needs are fulfilled.
-//
-// These traits live in the domain crate because:
-// 1. The domain DEFINES its own contracts
-// 2. Adapters (in other crates) IMPLEMENT these contracts
-// 3. The dependency arrow points INWARD: adapters -> domain
-//
-// This is the Dependency Inversion Principle at the crate level!
+impl Rate {
+    /// What a [`Rate`] counts in: `Rate(SCALE)` is 1.
+    pub const SCALE: u32 = 10_000;
 
-/// Repository port for persisting orders.
-///
-/// The domain needs to store orders somewhere. It doesn't care if that
-/// "somewhere" is PostgreSQL, MongoDB, a file, or a HashMap.
-/// That's an adapter's decision.
-pub trait OrderRepository {
-    /// Saves an order to storage.
-    ///
-    /// # Errors
-    ///
-    /// Returns [`OrderError::StorageFailed`] if the operation fails.
-    fn save(&mut self, order: &Order) -> Result<(), OrderError>;
+    /// A currency against itself: 1.0000.
+    pub const ONE: Self = Self(Self::SCALE);
+}
 
-    /// Finds an order by ID.
-    ///
-    /// # Errors
-    ///
-    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
-    fn find(&self, id: OrderId) -> Result<Option<Order>, OrderError>;
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:04}", self.0 / Self::SCALE, self.0 % Self::SCALE)
+    }
 }
 
-/// Payment gateway port for processing payments.
+/// Reference to funds held on a customer's card but not yet taken.
 ///
-/// The domain needs to charge customers. It doesn't care if that's
-/// via Stripe, PayPal, or carrier pigeons carrying gold coins.
-pub trait PaymentGateway {
-    /// Charges the given amount.
-    ///
-    /// # Errors
-    ///
-    /// Returns [`OrderError::PaymentFailed`] if payment fails.
-    fn charge(&self, amount: Money) -> Result<(), OrderError>;
+/// Returned by [`PaymentGateway::authorize`], then handed back to
+/// [`PaymentGateway::capture`] or [`PaymentGateway::void`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthorizationId(pub String);
+
+impl fmt::Display for AuthorizationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
-/// Notification port for sending messages to customers.
+/// Client-chosen token identifying one logical request.
 ///
-/// Hey, this is our old friend from dip_02! Same concept:
-/// "I need to notify someone about an order."
-/// Could be email, SMS, push notification, carrier pigeon...
-pub trait Sender {
-    /// Sends a notification about an order.
-    ///
-    /// # Errors
-    ///
-    /// Returns [`OrderError::NotificationFailed`] if sending fails.
-    fn send(&self, order: &Order) -> Result<(), OrderError>;
-}
+/// A client that retries a timed-out checkout sends the same key again,
+/// so we can return the first result instead of charging twice.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey(pub String);
 
-// =============================================================================
-// Tests
-// =============================================================================
-//
-// Domain tests are PURE. No mocks needed. We're testing business logic
-// with plain Rust values. This is one of the biggest benefits of a clean
-// domain layer. Tests are simple and fast.
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Key/value pairs attached to a charge, shown next to it at the provider:
+/// which order it paid for, which request made it. Finance matches the
+/// provider's payouts to our orders with them.
+///
+/// The keys keep the order they were added in. Providers limit how many
+/// there are and how long they get; the adapters refuse what theirs would.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChargeMetadata(Vec<(String, String)>);
 
-    #[test]
-    fn order_new_with_items_succeeds() {
-        let items = vec![
-            LineItem {
-                name: "Book".to_string(),
-                price: Money(4999),
-            },
-            LineItem {
-                name: "Pen".to_string(),
-                price: Money(199),
-            },
-        ];
+impl ChargeMetadata {
+    /// Metadata with no keys.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        let order = Order::new(OrderId(1), items).unwrap();
+    /// Sets `key` to `value`, replacing what it was set to before.
+    #[must_use]
+    pub fn with_entry(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key, value)),
+        }
+        self
+    }
 
-        assert_eq!(order.id, OrderId(1));
-        assert_eq!(order.items.len(), 2);
-        assert_eq!(order.total, Money(5198)); // $51.98
+    /// The value of `key`, if it's set.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every key and its value, in the order they were added.
+    #[must_use]
+    pub fn entries(&self) -> &[(String, String)] {
+        &self.0
+    }
+
+    /// How many keys are set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// True when no key is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Proof that a payment went through.
+///
+/// The transaction ID is whatever the payment provider gave us
+/// (`ch_...` for Stripe). We keep it so we can find the payment again later
+/// for refunds, reconciliation, or support tickets.
+///
+/// The provider says who processed it ("stripe", "mock"...): with a backup
+/// provider, the transaction ID only makes sense to one of them.
+///
+/// Not every payment is over when the provider answers: a bank transfer, or
+/// a card the bank wants to look at, stays [`PaymentStatus::Pending`] until
+/// the provider's webhook says how it ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentReceipt {
+    pub transaction_id: String,
+    pub provider: String,
+    pub amount: Money,
+    pub timestamp: SystemTime,
+    pub status: PaymentStatus,
+}
+
+/// Where a payment stands at the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentStatus {
+    /// Accepted, but the money hasn't moved yet. A webhook will tell.
+    Pending,
+    /// The money moved.
+    Settled,
+    /// The provider gave up on it after accepting it.
+    Failed,
+}
+
+impl fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "Pending"),
+            Self::Settled => write!(f, "Settled"),
+            Self::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+/// How to reach a recipient.
+///
+/// Each channel needs a different kind of address: SendGrid needs an email,
+/// Twilio needs a phone number, a webhook needs a URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Contact {
+    /// An email address, e.g. `alice@example.com`.
+    Email(String),
+    /// A phone number: optional leading `+`, then 7 to 15 digits.
+    Phone(String),
+    /// An `http://` or `https://` URL to POST to.
+    Webhook(String),
+}
+
+impl Contact {
+    /// Checks the address format for this kind of contact.
+    ///
+    /// These are deliberately simple checks. The goal is to catch obvious
+    /// garbage at the door, not to implement RFC 5322.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Email(address) => is_valid_email(address),
+            Self::Phone(number) => is_valid_phone(number),
+            Self::Webhook(url) => is_valid_webhook_url(url),
+        }
+    }
+}
+
+impl fmt::Display for Contact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Email(address) => write!(f, "{address}"),
+            Self::Phone(number) => write!(f, "{number}"),
+            Self::Webhook(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+fn is_valid_email(address: &str) -> bool {
+    // one '@', something before it, a dotted domain after it, no spaces
+    let Some((local, domain)) = address.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !address.chars().any(char::is_whitespace)
+}
+
+fn is_valid_phone(number: &str) -> bool {
+    let digits = number.strip_prefix('+').unwrap_or(number);
+    (7..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_valid_webhook_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split('/').next().unwrap_or_default();
+    !host.is_empty() && !url.chars().any(char::is_whitespace)
+}
+
+/// Someone to notify about an order.
+///
+/// Fields are private so a `Recipient` can only exist once it has been
+/// validated. If we hold one, its contact is well-formed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    name: String,
+    contact: Contact,
+    address: Option<Address>,
+}
+
+impl Recipient {
+    /// Creates a recipient, validating the name and contact.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidRecipient`] if the name is blank or the
+    /// contact is malformed (e.g., an email without `@`).
+    pub fn new(name: impl Into<String>, contact: Contact) -> Result<Self, OrderError> {
+        let name = name.into();
+        if name.trim().is_empty() || !contact.is_valid() {
+            return Err(OrderError::InvalidRecipient);
+        }
+        Ok(Self {
+            name,
+            contact,
+            address: None,
+        })
+    }
+
+    /// Gives the recipient an address to ship to. Without one, nothing
+    /// ships: no shipping line is added to their orders.
+    #[must_use]
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Returns the recipient's display name.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns how to reach the recipient.
+    #[must_use]
+    pub const fn contact(&self) -> &Contact {
+        &self.contact
+    }
+
+    /// Returns where the recipient's orders ship to, if anywhere.
+    #[must_use]
+    pub const fn address(&self) -> Option<&Address> {
+        self.address.as_ref()
+    }
+}
+
+/// Where an order ships to.
+///
+/// Like [`Recipient`], fields are private: an `Address` has been validated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    street: String,
+    city: String,
+    postal_code: String,
+    country: String,
+}
+
+impl Address {
+    /// Creates an address, validating it. `country` is an ISO 3166 code
+    /// (`"US"`, `"FR"`), stored upper-case.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidRecipient`] if a part is blank or the
+    /// country isn't two letters.
+    pub fn new(
+        street: impl Into<String>,
+        city: impl Into<String>,
+        postal_code: impl Into<String>,
+        country: &str,
+    ) -> Result<Self, OrderError> {
+        let (street, city, postal_code) = (street.into(), city.into(), postal_code.into());
+        let country = country.trim().to_ascii_uppercase();
+        let blank = [&street, &city, &postal_code]
+            .iter()
+            .any(|part| part.trim().is_empty());
+        if blank || country.len() != 2 || !country.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(OrderError::InvalidRecipient);
+        }
+        Ok(Self {
+            street,
+            city,
+            postal_code,
+            country,
+        })
+    }
+
+    /// Returns the street line, e.g. `"1 Main St"`.
+    #[must_use]
+    pub fn street(&self) -> &str {
+        &self.street
+    }
+
+    /// Returns the city.
+    #[must_use]
+    pub fn city(&self) -> &str {
+        &self.city
+    }
+
+    /// Returns the postal code, as written (`"75001"`, `"SW1A 1AA"`).
+    #[must_use]
+    pub fn postal_code(&self) -> &str {
+        &self.postal_code
+    }
+
+    /// Returns the ISO 3166 country code, e.g. `"US"`.
+    #[must_use]
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {} {}, {}",
+            self.street, self.postal_code, self.city, self.country
+        )
+    }
+}
+
+impl fmt::Display for Recipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} <{}>", self.name, self.contact)
+    }
+}
+
+// =============================================================================
+// Entities
+// =============================================================================
+//
+// Entities have identity: two orders with the same items are still different
+// orders if they have different IDs. Unlike value objects, entities are mutable
+// over their lifecycle: an order is placed, paid, maybe cancelled or refunded.
+
+/// A single item in an order.
+///
+/// The weight is for shipping (see [`ShippingCalculator`]); `None` when
+/// nobody entered one, and a calculator falls back to a default.
+///
+/// Items from outside go through [`LineItem::new`] (or its `FromStr` and
+/// [`LineItem::new_lossy`]), which sanitizes the name. The fields stay
+/// public all the same: a repository rebuilds the items it stored, which
+/// were checked on the way in, and must give them back exactly as they
+/// were; tests and the property-test strategies build items field by field.
+/// Validation guards the driving adapters, not every constructor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineItem {
+    pub name: String,
+    pub price: Money,
+    pub weight_grams: Option<u32>,
+}
+
+/// The longest item name [`LineItem::new`] accepts, in characters.
+pub const MAX_ITEM_NAME_CHARS: usize = 200;
+
+impl LineItem {
+    /// Creates an item from a name a client sent, sanitized.
+    ///
+    /// Item names end up on receipts, in log lines and in emails. So
+    /// surrounding whitespace is trimmed, and each run of control characters
+    /// (a newline, a tab, an escape code) becomes a single space:
+    /// `" Blue\r\nMug "` is `"Blue Mug"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidLineItem`] if nothing is left of the
+    /// name, or if it's longer than [`MAX_ITEM_NAME_CHARS`] characters.
+    pub fn new(name: &str, price: Money) -> Result<Self, OrderError> {
+        Self::new_with_limit(name, price, MAX_ITEM_NAME_CHARS)
+    }
+
+    /// [`LineItem::new`], with names of up to `max_chars` characters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidLineItem`] if nothing is left of the
+    /// name, or if it's longer than `max_chars` characters.
+    pub fn new_with_limit(name: &str, price: Money, max_chars: usize) -> Result<Self, OrderError> {
+        let name = sanitize_item_name(name);
+        // Characters, not bytes: "é" counts for one
+        if name.is_empty() || name.chars().count() > max_chars {
+            return Err(OrderError::InvalidLineItem);
+        }
+        Ok(Self {
+            name,
+            price,
+            weight_grams: None,
+        })
+    }
+
+    /// [`LineItem::new`], cutting a name that's too long down to
+    /// [`MAX_ITEM_NAME_CHARS`] characters instead of refusing it.
+    ///
+    /// For imports: one long name shouldn't lose a whole file of orders.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidLineItem`] if nothing is left of the
+    /// name: there's no sensible name to make up.
+    pub fn new_lossy(name: &str, price: Money) -> Result<Self, OrderError> {
+        let name: String = sanitize_item_name(name)
+            .chars()
+            .take(MAX_ITEM_NAME_CHARS)
+            .collect();
+        Self::new(&name, price)
+    }
+}
+
+/// `name` trimmed, with each run of control characters (and the whitespace
+/// around it) replaced by one space.
+fn sanitize_item_name(name: &str) -> String {
+    name.split(char::is_control)
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Why a string isn't a [`LineItem`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseLineItemError {
+    /// There's no `:` between the name and the price.
+    MissingPrice(String),
+    /// What follows the last `:` isn't an amount of [`Money`].
+    InvalidPrice(String, ParseMoneyError),
+    /// The name is blank once sanitized, or too long (see [`LineItem::new`]).
+    InvalidName(String),
+}
+
+impl fmt::Display for ParseLineItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrice(item) => write!(f, "{item:?} isn't NAME:PRICE"),
+            Self::InvalidPrice(item, e) => write!(f, "{item:?}: {e}"),
+            Self::InvalidName(item) => write!(
+                f,
+                "{item:?}: the name is blank or longer than {MAX_ITEM_NAME_CHARS} characters"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseLineItemError {}
+
+/// Parses `NAME:PRICE`, e.g. `"Book:49.99"`, the price as [`Money`] parses it.
+///
+/// The price is after the LAST `:`, so names may contain one
+/// (`"Cable 2m:USB-C:9.99"`). The name goes through [`LineItem::new`]:
+/// sanitized, and no longer than [`MAX_ITEM_NAME_CHARS`].
+impl std::str::FromStr for LineItem {
+    type Err = ParseLineItemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, price) = s
+            .rsplit_once(':')
+            .ok_or_else(|| ParseLineItemError::MissingPrice(s.to_string()))?;
+        let price = price
+            .parse()
+            .map_err(|e| ParseLineItemError::InvalidPrice(s.to_string(), e))?;
+        Self::new(name, price).map_err(|_| ParseLineItemError::InvalidName(s.to_string()))
+    }
+}
+
+/// Where an order is in its lifecycle.
+///
+/// ```text
+/// Pending ──► Paid ──► Shipped
+///    │          │
+///    │          └────► Refunded (once fully refunded)
+///    │          │
+///    └──────────┴────► Cancelled
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    /// Stored, payment not captured yet.
+    Pending,
+    /// Payment captured.
+    Paid,
+    /// Handed to the carrier. The items can't change anymore.
+    Shipped,
+    /// Cancelled by the customer or the shop.
+    Cancelled,
+    /// The whole payment went back to the customer.
+    Refunded,
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "Pending"),
+            Self::Paid => write!(f, "Paid"),
+            Self::Shipped => write!(f, "Shipped"),
+            Self::Cancelled => write!(f, "Cancelled"),
+            Self::Refunded => write!(f, "Refunded"),
+        }
+    }
+}
+
+/// One status change of an order: from what, to what, when, and why.
+///
+/// Support's answer to "when did this order change state, and why".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// `None` for the first entry: the order being placed.
+    pub from: Option<OrderStatus>,
+    pub to: OrderStatus,
+    pub at: SystemTime,
+    /// Why, when it's known: the payment error for an order cancelled
+    /// because its payment failed, for instance.
+    pub reason: Option<String>,
+}
+
+/// An order containing line items.
+///
+/// Notice what's NOT here: database IDs, "created_by" fields. Those are
+/// infrastructure concerns. The domain only cares about what an order IS
+/// from a business perspective. The one timestamp, in `history`, is there
+/// because when an order changed status IS business: support answers
+/// customers with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    pub id: OrderId,
+    pub items: Vec<LineItem>,
+    pub total: Money,
+    /// Transaction ID from the [`PaymentReceipt`], once the order is paid.
+    pub transaction_id: Option<String>,
+    pub status: OrderStatus,
+    /// How much of the total went back to the customer so far.
+    pub refunded: Money,
+    /// Who placed the order, when it was placed for a registered customer.
+    pub customer_id: Option<CustomerId>,
+    /// The storefront the order was placed on.
+    pub tenant: TenantId,
+    /// What a gift card paid, when one was used. The card paid the rest.
+    pub gift_card: Option<GiftCardPayment>,
+    /// Bumped on every checked update, so two concurrent edits can't
+    /// silently overwrite each other. See [`OrderRepository::update_versioned`].
+    pub version: u32,
+    /// Every status change, oldest first: being placed
+    /// ([`Order::record_placed`]), then whatever the transitions
+    /// ([`Order::mark_paid`], [`Order::cancel`]...) appended.
+    pub history: Vec<HistoryEntry>,
+    /// The orders merged into this one ([`Order::merge`]), in the order
+    /// they were merged.
+    pub merged_from: Vec<OrderId>,
+    /// The charges for items added after the order was paid, oldest first.
+    /// Part of what the card paid: see [`Order::card_payments`].
+    pub extra_charges: Vec<ExtraCharge>,
+    /// The loyalty points the customer was actually credited for this
+    /// order, and not taken back yet. What a refund can take back: see
+    /// [`Order::points_owed`] and [`Order::take_back_points`].
+    pub loyalty_credited: u32,
+    /// Where the order ships and what that costs, for an order that ships.
+    /// The cost is part of the total, not an item: it earns no points. Set
+    /// it with [`Order::set_shipping`], which keeps the total right.
+    pub shipping: Option<Shipping>,
+}
+
+impl Order {
+    /// Creates a new order from line items.
+    ///
+    /// This is where business rules live! "An order must have at least one item"
+    /// is a business rule, not a database constraint or API validation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if items is empty, or if the
+    /// total doesn't fit in [`Money`].
+    pub fn new(id: OrderId, items: Vec<LineItem>) -> Result<Self, OrderError> {
+        let total = Self::total_of(&items)?;
+
+        Ok(Self {
+            id,
+            items,
+            total,
+            transaction_id: None,
+            status: OrderStatus::Pending,
+            refunded: Money(0),
+            customer_id: None,
+            tenant: TenantId::default(),
+            gift_card: None,
+            version: 0,
+            history: Vec::new(),
+            merged_from: Vec::new(),
+            extra_charges: Vec::new(),
+            loyalty_credited: 0,
+            shipping: None,
+        })
+    }
+
+    /// Validates line items and computes their total, without creating an order.
+    ///
+    /// [`Order::new`] applies exactly these rules, so a total computed here
+    /// is the total the order will have. Useful for quotes and previews.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if items is empty, or if the
+    /// total doesn't fit in [`Money`].
+    pub fn total_of(items: &[LineItem]) -> Result<Money, OrderError> {
+        // Business rule: an order must have items
+        if items.is_empty() {
+            return Err(OrderError::InvalidOrder);
+        }
+
+        // Calculate total: pure business logic. An overflow is bad input, not a crash.
+        items
+            .iter()
+            .try_fold(0u32, |total, item| total.checked_add(item.price.0))
+            .map(Money)
+            .ok_or(OrderError::InvalidOrder)
+    }
+
+    /// Checks that line items would make a valid order.
+    ///
+    /// Lets the application reject bad input BEFORE spending an order ID.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Order::total_of`].
+    pub fn validate_items(items: &[LineItem]) -> Result<(), OrderError> {
+        Self::total_of(items).map(|_| ())
+    }
+
+    /// Whether `other` is this order, as far as what's in it goes: every
+    /// field but the [`Order::version`], which only counts the writes.
+    ///
+    /// What tells an idempotent re-save from two different orders under
+    /// one ID (see [`OrderRepository::save`]).
+    #[must_use]
+    pub fn same_contents(&self, other: &Self) -> bool {
+        // Destructured, so a new field has to be sorted in or out here
+        let Self {
+            id,
+            items,
+            total,
+            transaction_id,
+            status,
+            refunded,
+            customer_id,
+            tenant,
+            gift_card,
+            version: _,
+            history,
+            merged_from,
+            extra_charges,
+            loyalty_credited,
+            shipping,
+        } = self;
+        *id == other.id
+            && *items == other.items
+            && *total == other.total
+            && *transaction_id == other.transaction_id
+            && *status == other.status
+            && *refunded == other.refunded
+            && *customer_id == other.customer_id
+            && *tenant == other.tenant
+            && *gift_card == other.gift_card
+            && *history == other.history
+            && *merged_from == other.merged_from
+            && *extra_charges == other.extra_charges
+            && *loyalty_credited == other.loyalty_credited
+            && *shipping == other.shipping
+    }
+
+    /// Whether an item's name contains `query`, ignoring case: `"mug"`
+    /// matches "Coffee Mug" and "MUGS". An empty query matches any order.
+    ///
+    /// The search rule of [`OrderRepository::find_by_item_name`], in one
+    /// place for every adapter.
+    #[must_use]
+    pub fn has_item_named_like(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.items
+            .iter()
+            .any(|item| item.name.to_lowercase().contains(&query))
+    }
+
+    /// What the customer's card pays: the total minus any gift card part.
+    #[must_use]
+    pub fn card_amount(&self) -> Money {
+        let gift = self.gift_card.map_or(0, |gift| gift.amount.0);
+        Money(self.total.0.saturating_sub(gift))
+    }
+
+    /// What each card transaction of the order paid: the first payment,
+    /// then the extra charges, oldest first. Together, the card amount.
+    ///
+    /// Refunds go back through them in that order, so how much of each one
+    /// is already back follows from [`Order::refunded`]. Without a first
+    /// transaction ID (a gift card paid it all), the list starts with the
+    /// extra charges.
+    #[must_use]
+    pub fn card_payments(&self) -> Vec<(&str, Money)> {
+        let extra = self
+            .extra_charges
+            .iter()
+            .fold(0, |sum, charge| sum + charge.amount.0);
+        let first = Money(self.card_amount().0.saturating_sub(extra));
+        self.transaction_id
+            .as_deref()
+            .map(|transaction_id| (transaction_id, first))
+            .into_iter()
+            .chain(
+                self.extra_charges
+                    .iter()
+                    .map(|charge| (charge.transaction_id.as_str(), charge.amount)),
+            )
+            .collect()
+    }
+
+    /// The loyalty points this order earns: one per whole dollar paid for
+    /// its items and not refunded. Shipping earns none.
+    ///
+    /// Rounding is always down, and on the order as a whole, not per item:
+    /// $9.99 earns 9 points, and so do nine $1.11 items ($9.99 again).
+    /// After a refund the order is worth fewer points; the difference is
+    /// what to take back from the customer. A refund of everything leaves 0,
+    /// and so does an order never paid (Pending, or cancelled unpaid).
+    #[must_use]
+    pub const fn loyalty_points(&self) -> u32 {
+        match self.status {
+            OrderStatus::Pending | OrderStatus::Cancelled => 0,
+            OrderStatus::Paid | OrderStatus::Shipped | OrderStatus::Refunded => {
+                let kept = self.total.0.saturating_sub(self.refunded.0);
+                // A refund gives back the shipping last
+                if kept < self.item_total().0 {
+                    kept / 100
+                } else {
+                    self.item_total().0 / 100
+                }
+            }
+        }
+    }
+
+    /// What shipping costs: nothing for an order that doesn't ship.
+    #[must_use]
+    pub const fn shipping_cost(&self) -> Money {
+        match &self.shipping {
+            Some(shipping) => shipping.cost,
+            None => Money(0),
+        }
+    }
+
+    /// Sets where the order ships and what that costs (`None`: it doesn't
+    /// ship), and moves the total by the difference in cost.
+    ///
+    /// The application prices shipping ([`ShippingCalculator`]); the order
+    /// only makes sure the total stays the items plus the shipping.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if the new total doesn't fit in
+    /// [`Money`], or would be less than what was already refunded.
+    pub fn set_shipping(&mut self, shipping: Option<Shipping>) -> Result<(), OrderError> {
+        let cost = shipping.as_ref().map_or(Money(0), |shipping| shipping.cost);
+        let total = self
+            .item_total()
+            .checked_add(cost)
+            .ok_or(OrderError::InvalidOrder)?;
+        if total.0 < self.refunded.0 {
+            return Err(OrderError::InvalidOrder);
+        }
+        self.total = total;
+        self.shipping = shipping;
+        Ok(())
+    }
+
+    /// What the items cost: the total without the shipping.
+    const fn item_total(&self) -> Money {
+        Money(self.total.0 - self.shipping_cost().0)
+    }
+
+    /// The points this order earns and the customer wasn't credited yet:
+    /// all of them once it's paid, the difference after an item was added.
+    #[must_use]
+    pub const fn points_owed(&self) -> u32 {
+        self.loyalty_points().saturating_sub(self.loyalty_credited)
+    }
+
+    /// Forgets the credited points this order no longer earns, after a
+    /// refund or a cancellation, and returns how many to take back.
+    ///
+    /// Only points actually credited come back: if crediting them failed,
+    /// there's nothing to take.
+    pub const fn take_back_points(&mut self) -> u32 {
+        let points = self.loyalty_credited.saturating_sub(self.loyalty_points());
+        self.loyalty_credited -= points;
+        points
+    }
+
+    /// The order as a plain-text receipt: one line per item, then the total.
+    ///
+    /// Adapters send it as is (an email body, a printed slip), so the wording
+    /// lives here, once.
+    #[must_use]
+    pub fn render_receipt(&self) -> String {
+        use fmt::Write as _;
+
+        let mut receipt = format!("Order #{}\n\n", self.id);
+        for item in &self.items {
+            let _ = writeln!(
+                receipt,
+                "  {:<30} {:>10}",
+                item.name,
+                item.price.to_string()
+            );
+        }
+        // Free shipping is no line: a $0.00 line is noise on the receipt
+        let shipping = self.shipping_cost();
+        if shipping != Money(0) {
+            let _ = writeln!(
+                receipt,
+                "  {:<30} {:>10}",
+                SHIPPING_LINE_NAME,
+                shipping.to_string()
+            );
+        }
+        let _ = writeln!(
+            receipt,
+            "\n  {:<30} {:>10}",
+            "Total",
+            self.total.to_string()
+        );
+        if let Some(gift) = self.gift_card {
+            let _ = writeln!(
+                receipt,
+                "  {:<30} {:>10}",
+                "Gift card",
+                gift.amount.to_string()
+            );
+            let _ = writeln!(
+                receipt,
+                "  {:<30} {:>10}",
+                "Card",
+                self.card_amount().to_string()
+            );
+        }
+        receipt
+    }
+
+    /// Records that the order was placed, `at` that time: the first entry
+    /// of its history, from no status to Pending.
+    ///
+    /// [`Order::new`] can't do it, having no clock: the application calls
+    /// this right after. An order built with `new` alone (as in most tests)
+    /// simply has no placement entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless the order is Pending
+    /// with nothing in its history yet.
+    pub fn record_placed(&mut self, at: SystemTime) -> Result<(), OrderError> {
+        if self.status != OrderStatus::Pending || !self.history.is_empty() {
+            return Err(OrderError::InvalidStatus(self.status));
+        }
+        self.history.push(HistoryEntry {
+            from: None,
+            to: OrderStatus::Pending,
+            at,
+            reason: None,
+        });
+        Ok(())
+    }
+
+    /// Records that the payment was captured, `at` that time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless the order is Pending.
+    pub fn mark_paid(&mut self, at: SystemTime) -> Result<(), OrderError> {
+        if self.status != OrderStatus::Pending {
+            return Err(OrderError::InvalidStatus(self.status));
+        }
+        self.change_status(OrderStatus::Paid, at, None);
+        Ok(())
+    }
+
+    /// Records that the order left the warehouse, `at` that time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless the order is Paid.
+    pub fn mark_shipped(&mut self, at: SystemTime) -> Result<(), OrderError> {
+        if self.status != OrderStatus::Paid {
+            return Err(OrderError::InvalidStatus(self.status));
+        }
+        self.change_status(OrderStatus::Shipped, at, None);
+        Ok(())
+    }
+
+    /// Moves to `to`, and writes it down in the history.
+    ///
+    /// The time is the caller's to give: the domain has no clock of its own,
+    /// the application asks its [`Clock`].
+    fn change_status(&mut self, to: OrderStatus, at: SystemTime, reason: Option<&str>) {
+        self.history.push(HistoryEntry {
+            from: Some(self.status),
+            to,
+            at,
+            reason: reason.map(str::to_string),
+        });
+        self.status = to;
+    }
+
+    /// Adds an item to an order that hasn't shipped yet.
+    ///
+    /// Returns how much the total went up: what the customer owes on top of
+    /// what they already paid.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless the order is Pending or
+    /// Paid, or [`OrderError::InvalidOrder`] if a charge is still waiting to
+    /// settle (the settlement would mark the order paid for an item nobody
+    /// paid for) or if the new total doesn't fit in [`Money`].
+    pub fn add_item(&mut self, item: LineItem) -> Result<Money, OrderError> {
+        self.check_amendable()?;
+        let difference = item.price;
+        self.total = self
+            .total
+            .0
+            .checked_add(difference.0)
+            .map(Money)
+            .ok_or(OrderError::InvalidOrder)?;
+        self.items.push(item);
+        Ok(difference)
+    }
+
+    /// Removes the item at `index` from an order that hasn't shipped yet.
+    ///
+    /// Returns how much the total went down: what the customer gets back if
+    /// they already paid.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless the order is Pending or
+    /// Paid, or [`OrderError::InvalidOrder`] if a charge is still waiting to
+    /// settle, if there's no item at `index`, if it's the last one (an order
+    /// always has items), or if the new total would be less than what was
+    /// already refunded.
+    pub fn remove_item(&mut self, index: usize) -> Result<Money, OrderError> {
+        self.check_amendable()?;
+        if index >= self.items.len() || self.items.len() == 1 {
+            return Err(OrderError::InvalidOrder);
+        }
+        let difference = self.items[index].price;
+        let total = self.total.0 - difference.0;
+        if total < self.refunded.0 {
+            return Err(OrderError::InvalidOrder);
+        }
+        self.items.remove(index);
+        self.total = Money(total);
+        Ok(difference)
+    }
+
+    /// Merges `other` into this order, for one shipment instead of two.
+    ///
+    /// This order's ID survives, `other`'s items come after its own, and
+    /// `other` (with whatever was merged into it) is added to
+    /// [`Order::merged_from`]. `other` itself is gone: the caller tombstones it.
+    ///
+    /// One shipment ships once: the merged order keeps this order's
+    /// [`Order::shipping`] (or `other`'s, if this one doesn't ship), at its
+    /// old cost. The heavier parcel is for the caller to reprice.
+    ///
+    /// Business rules: both orders are Pending, with no payment attached
+    /// yet (a payment is for one order's total), and belong to the same
+    /// registered customer, on the same storefront.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless both orders are Pending,
+    /// or [`OrderError::InvalidOrder`] if they're the same order, belong to
+    /// different (or no) customers, have a payment attached, or if the
+    /// merged total doesn't fit in [`Money`].
+    pub fn merge(mut self, other: Self) -> Result<Self, OrderError> {
+        self.check_unpaid()?;
+        other.check_unpaid()?;
+        if self.id == other.id
+            || self.customer_id.is_none()
+            || self.customer_id != other.customer_id
+            || self.tenant != other.tenant
+        {
+            return Err(OrderError::InvalidOrder);
+        }
+
+        self.total = self
+            .item_total()
+            .checked_add(other.item_total())
+            .ok_or(OrderError::InvalidOrder)?;
+        let shipping = self.shipping.take().or(other.shipping);
+        self.set_shipping(shipping)?;
+        self.items.extend(other.items);
+        self.merged_from.push(other.id);
+        self.merged_from.extend(other.merged_from);
+        Ok(self)
+    }
+
+    /// Splits the items at `item_indices` off into a second order, for a
+    /// separate shipment. Returns this order with the other items, then the
+    /// split-off order.
+    ///
+    /// The split-off order is new: same customer and storefront, no history
+    /// yet, and this order's ID until the caller gives it its own (the
+    /// domain doesn't hand out IDs, see [`IdGenerator`]).
+    ///
+    /// Two shipments now, to the same place: both halves keep this order's
+    /// [`Order::shipping`], at its old cost, for the caller to reprice.
+    ///
+    /// Business rules: the order is Pending with no payment attached, and
+    /// both halves keep at least one item.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless the order is Pending, or
+    /// [`OrderError::InvalidOrder`] if it has a payment attached, or if
+    /// `item_indices` is empty, lists every item, repeats an index or has
+    /// one past the last item.
+    pub fn split(mut self, item_indices: &[usize]) -> Result<(Self, Self), OrderError> {
+        self.check_unpaid()?;
+        let mut split_off = vec![false; self.items.len()];
+        for &index in item_indices {
+            match split_off.get_mut(index) {
+                Some(taken @ false) => *taken = true,
+                _ => return Err(OrderError::InvalidOrder),
+            }
+        }
+
+        let (taken, kept): (Vec<_>, Vec<_>) = self
+            .items
+            .into_iter()
+            .zip(split_off)
+            .partition(|(_, taken)| *taken);
+        let kept: Vec<LineItem> = kept.into_iter().map(|(item, _)| item).collect();
+        let taken: Vec<LineItem> = taken.into_iter().map(|(item, _)| item).collect();
+        // Order::new refuses an empty half
+        let mut other = Self::new(self.id, taken)?;
+        let shipping = self.shipping.take();
+        self.total = Self::total_of(&kept)?;
+        self.items = kept;
+        self.set_shipping(shipping.clone())?;
+        other.set_shipping(shipping)?;
+        other.customer_id = self.customer_id;
+        other.tenant = self.tenant;
+        Ok((self, other))
+    }
+
+    /// Pending or Paid, with no charge waiting to settle: the orders whose
+    /// items can change.
+    ///
+    /// A Pending order with a transaction ID was charged for its total as it
+    /// was then. The settlement marks it Paid for its total as it is now:
+    /// an item added in between would be paid for by nobody.
+    fn check_amendable(&self) -> Result<(), OrderError> {
+        if !matches!(self.status, OrderStatus::Pending | OrderStatus::Paid) {
+            return Err(OrderError::InvalidStatus(self.status));
+        }
+        if self.status == OrderStatus::Pending && self.transaction_id.is_some() {
+            return Err(OrderError::InvalidOrder);
+        }
+        Ok(())
+    }
+
+    /// Pending, with no payment attached: the orders merge and split accept.
+    fn check_unpaid(&self) -> Result<(), OrderError> {
+        if self.status != OrderStatus::Pending {
+            return Err(OrderError::InvalidStatus(self.status));
+        }
+        if self.transaction_id.is_some() || self.gift_card.is_some() {
+            return Err(OrderError::InvalidOrder);
+        }
+        Ok(())
+    }
+
+    /// Rebuilds an order from its events, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] unless the first event, and only
+    /// the first, is [`OrderEvent::Placed`]. Otherwise, the error of the
+    /// first event that doesn't apply (see [`Order::apply`]).
+    pub fn from_events(events: &[OrderEvent]) -> Result<Self, OrderError> {
+        let Some((
+            OrderEvent::Placed {
+                id,
+                tenant,
+                items,
+                customer_id,
+                gift_card,
+                at,
+            },
+            rest,
+        )) = events.split_first()
+        else {
+            return Err(OrderError::InvalidOrder);
+        };
+
+        let mut order = Self::new(*id, items.clone())?;
+        order.tenant = *tenant;
+        order.customer_id = *customer_id;
+        order.gift_card = *gift_card;
+        if let Some(at) = at {
+            order.record_placed(*at)?;
+        }
+        for event in rest {
+            order.apply(event)?;
+        }
+        Ok(order)
+    }
+
+    /// Applies one recorded change, through the same transition that made it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] for a second
+    /// [`OrderEvent::Placed`], otherwise the error of the transition
+    /// (e.g. [`OrderError::InvalidStatus`] for `Paid` on a cancelled order).
+    pub fn apply(&mut self, event: &OrderEvent) -> Result<(), OrderError> {
+        match event {
+            OrderEvent::Placed { .. } => Err(OrderError::InvalidOrder),
+            OrderEvent::ItemAdded(item) => self.add_item(item.clone()).map(|_| ()),
+            OrderEvent::ExtraCharged {
+                transaction_id,
+                amount,
+            } => {
+                self.extra_charges.push(ExtraCharge {
+                    transaction_id: transaction_id.clone(),
+                    amount: *amount,
+                });
+                Ok(())
+            }
+            OrderEvent::PaymentRecorded { transaction_id } => {
+                self.transaction_id = Some(transaction_id.clone());
+                Ok(())
+            }
+            OrderEvent::ShippingSet(shipping) => self.set_shipping(shipping.clone()),
+            OrderEvent::LoyaltyRecorded { credited } => {
+                self.loyalty_credited = *credited;
+                Ok(())
+            }
+            OrderEvent::Paid { at } => self.mark_paid(*at),
+            OrderEvent::Shipped { at } => self.mark_shipped(*at),
+            OrderEvent::Cancelled { at, reason } => self.cancel(*at, reason.as_deref()).map(|_| ()),
+            OrderEvent::Refunded { amount, at } => self.record_refund(*amount, *at),
+        }
+    }
+
+    /// Cancels the order, `at` that time, for `reason` when there's one.
+    ///
+    /// Returns how much money must go back to the customer: whatever was
+    /// paid and not refunded yet. Nothing for a Pending order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] if the order is already
+    /// Shipped, Cancelled or Refunded.
+    pub fn cancel(&mut self, at: SystemTime, reason: Option<&str>) -> Result<Money, OrderError> {
+        let to_refund = match self.status {
+            OrderStatus::Pending => Money(0),
+            OrderStatus::Paid => Money(self.total.0 - self.refunded.0),
+            OrderStatus::Shipped | OrderStatus::Cancelled | OrderStatus::Refunded => {
+                return Err(OrderError::InvalidStatus(self.status));
+            }
+        };
+        self.refunded = Money(self.refunded.0 + to_refund.0);
+        self.change_status(OrderStatus::Cancelled, at, reason);
+        Ok(to_refund)
+    }
+
+    /// Records a (possibly partial) refund, `at` that time.
+    ///
+    /// Business rule: we never give back more than was paid.
+    /// Once everything is refunded, the order becomes Refunded. Until then
+    /// the status doesn't change, so a partial refund leaves no history.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidStatus`] unless the order is Paid, or
+    /// [`OrderError::InvalidRefund`] if the amount is zero or exceeds what
+    /// is left to refund.
+    pub fn record_refund(&mut self, amount: Money, at: SystemTime) -> Result<(), OrderError> {
+        if self.status != OrderStatus::Paid {
+            return Err(OrderError::InvalidStatus(self.status));
+        }
+        let remaining = self.total.0 - self.refunded.0;
+        if amount.0 == 0 || amount.0 > remaining {
+            return Err(OrderError::InvalidRefund);
+        }
+        self.refunded = Money(self.refunded.0 + amount.0);
+        if self.refunded == self.total {
+            self.change_status(OrderStatus::Refunded, at, None);
+        }
+        Ok(())
+    }
+}
+
+/// A registered customer.
+///
+/// The email is validated on construction (same rules as
+/// [`Contact::Email`]), so every customer can be notified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Customer {
+    id: CustomerId,
+    name: String,
+    email: String,
+}
+
+impl Customer {
+    /// Creates a customer, validating the name and email.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidCustomer`] if the name is blank or the
+    /// email is malformed.
+    pub fn new(
+        id: CustomerId,
+        name: impl Into<String>,
+        email: impl Into<String>,
+    ) -> Result<Self, OrderError> {
+        let name = name.into();
+        let email = email.into();
+        Self::validate(&name, &email)?;
+        Ok(Self { id, name, email })
+    }
+
+    /// Checks a name and email without creating a customer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidCustomer`] if the name is blank or the
+    /// email is malformed.
+    pub fn validate(name: &str, email: &str) -> Result<(), OrderError> {
+        if name.trim().is_empty() || !is_valid_email(email) {
+            return Err(OrderError::InvalidCustomer);
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub const fn id(&self) -> CustomerId {
+        self.id
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[must_use]
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
+    /// Returns who to notify about this customer's orders: them, by email.
+    #[must_use]
+    pub fn recipient(&self) -> Recipient {
+        // Both fields were validated in Customer::new, so no Result here
+        Recipient {
+            name: self.name.clone(),
+            contact: Contact::Email(self.email.clone()),
+            address: None,
+        }
+    }
+}
+
+/// A standing order: the same items, for the same customer, every
+/// `interval_days` days.
+///
+/// The subscription only knows WHEN it's due. Placing the order is the
+/// application's job, through the same use case as any other order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub id: SubscriptionId,
+    pub customer: CustomerId,
+    pub items: Vec<LineItem>,
+    pub interval_days: u32,
+    /// When the next order is due.
+    pub next_run: SystemTime,
+    /// False once cancelled: a cancelled subscription is kept, never run.
+    pub active: bool,
+}
+
+impl Subscription {
+    /// Creates an active subscription, first due at `first_run`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if `items` couldn't make an
+    /// order (see [`Order::validate_items`]) or if `interval_days` is 0.
+    pub fn new(
+        id: SubscriptionId,
+        customer: CustomerId,
+        items: Vec<LineItem>,
+        interval_days: u32,
+        first_run: SystemTime,
+    ) -> Result<Self, OrderError> {
+        Self::validate(&items, interval_days)?;
+        Ok(Self {
+            id,
+            customer,
+            items,
+            interval_days,
+            next_run: first_run,
+            active: true,
+        })
+    }
+
+    /// Checks items and an interval without creating a subscription.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Subscription::new`].
+    pub fn validate(items: &[LineItem], interval_days: u32) -> Result<(), OrderError> {
+        Order::validate_items(items)?;
+        if interval_days == 0 {
+            return Err(OrderError::InvalidOrder);
+        }
+        Ok(())
+    }
+
+    /// The time between two orders.
+    #[must_use]
+    pub const fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_days as u64 * 24 * 60 * 60)
+    }
+
+    /// True if the subscription is active and its next run isn't after `now`.
+    #[must_use]
+    pub fn is_due(&self, now: SystemTime) -> bool {
+        self.active && self.next_run <= now
+    }
+
+    /// Moves the next run to the first one after `now`.
+    ///
+    /// Usually that's one interval later. A subscription that missed several
+    /// runs (the scheduler was down for a month) skips them: the customer
+    /// gets one order, not a backlog of them.
+    pub fn advance(&mut self, now: SystemTime) {
+        let interval = self.interval();
+        while self.next_run <= now {
+            self.next_run += interval;
+        }
+    }
+
+    /// Stops the subscription: it's never due again.
+    pub const fn cancel(&mut self) {
+        self.active = false;
+    }
+}
+
+// =============================================================================
+// Domain Events
+// =============================================================================
+//
+// Things that happened to an order and that someone should hear about.
+// The application decides WHEN an event happens; notification adapters
+// decide how to word it (email subject, SMS text, Slack message...).
+//
+// OrderEvent is the other kind: not news for a customer, but the record of
+// one change to an order. Replay an order's events in sequence and you get
+// the order back; keep them and you get its history too. That's event
+// sourcing, and `Order::apply` is where the replay happens.
+
+/// Something worth telling a customer about.
+// One event per notification sent, and short-lived: boxing the order would
+// save a few bytes on the small variants and cost every sender a `*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum NotificationEvent {
+    /// The order was placed and paid.
+    OrderConfirmed(Order),
+    /// The order was cancelled.
+    OrderCancelled { id: OrderId },
+    /// Some or all of the payment went back to the customer.
+    RefundIssued { id: OrderId, amount: Money },
+    /// The order left the warehouse.
+    OrderShipped { id: OrderId },
+}
+
+/// One change to an order, as recorded by an event-sourced store.
+///
+/// Each event maps to one of [`Order`]'s transitions, so replaying follows
+/// the same business rules as the original change did. The status changes
+/// carry their time (and reason), so the replayed order has the same
+/// history too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderEvent {
+    /// The order was created. Always the first event, and only the first.
+    Placed {
+        id: OrderId,
+        tenant: TenantId,
+        items: Vec<LineItem>,
+        customer_id: Option<CustomerId>,
+        gift_card: Option<GiftCardPayment>,
+        /// When, if the order's history says (see [`Order::record_placed`]).
+        at: Option<SystemTime>,
+    },
+    /// An item was added ([`Order::add_item`]).
+    ItemAdded(LineItem),
+    /// An added item was charged on its own ([`Order::extra_charges`]).
+    ExtraCharged {
+        transaction_id: String,
+        amount: Money,
+    },
+    /// Where the order ships, or what that costs, changed
+    /// ([`Order::set_shipping`]).
+    ShippingSet(Option<Shipping>),
+    /// The payment provider's transaction ID was recorded.
+    PaymentRecorded { transaction_id: String },
+    /// The customer was credited points for the order, or some were taken
+    /// back: `credited` is the new [`Order::loyalty_credited`].
+    LoyaltyRecorded { credited: u32 },
+    /// The payment was captured ([`Order::mark_paid`]).
+    Paid { at: SystemTime },
+    /// The order left the warehouse ([`Order::mark_shipped`]).
+    Shipped { at: SystemTime },
+    /// The order was cancelled ([`Order::cancel`]).
+    Cancelled {
+        at: SystemTime,
+        reason: Option<String>,
+    },
+    /// Part of the payment went back ([`Order::record_refund`]).
+    Refunded { amount: Money, at: SystemTime },
+}
+
+impl NotificationEvent {
+    /// Returns the ID of the order this event is about.
+    #[must_use]
+    pub const fn order_id(&self) -> OrderId {
+        match self {
+            Self::OrderConfirmed(order) => order.id,
+            Self::OrderCancelled { id }
+            | Self::RefundIssued { id, .. }
+            | Self::OrderShipped { id } => *id,
+        }
+    }
+}
+
+// =============================================================================
+// Audit Records
+// =============================================================================
+//
+// Compliance wants to know who did what, and how it went. Every use case
+// leaves an AuditEntry behind, failures included: "a refund was attempted
+// and declined" matters as much as "a refund went through".
+
+/// What was attempted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    OrderPlaced,
+    OrderCancelled,
+    OrderRefunded,
+    OrderUpdated,
+    /// The provider said how a pending payment ended.
+    PaymentConfirmed,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OrderPlaced => write!(f, "OrderPlaced"),
+            Self::OrderCancelled => write!(f, "OrderCancelled"),
+            Self::OrderRefunded => write!(f, "OrderRefunded"),
+            Self::OrderUpdated => write!(f, "OrderUpdated"),
+            Self::PaymentConfirmed => write!(f, "PaymentConfirmed"),
+        }
+    }
+}
+
+/// How it went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditOutcome {
+    Succeeded,
+    /// The use case failed; the error, as text.
+    Failed(String),
+}
+
+impl fmt::Display for AuditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Succeeded => write!(f, "Succeeded"),
+            Self::Failed(reason) => write!(f, "Failed({reason})"),
+        }
+    }
+}
+
+/// One line of the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    /// The order concerned. `None` if the attempt failed before it had one
+    /// (e.g. an order rejected by validation).
+    pub order_id: Option<OrderId>,
+    pub outcome: AuditOutcome,
+    pub timestamp: SystemTime,
+}
+
+// =============================================================================
+// Request Context
+// =============================================================================
+//
+// One checkout touches the repository, the payment provider and the sender.
+// Each writes its own log lines, and when the checkout fails in production
+// we need to find the lines that belong together.
+//
+// The answer is a correlation ID: the application picks one per use case and
+// hands it to every port call, and every adapter writes it into its logs.
+// Search the logs for "req-42" and you get the whole story of request 42.
+
+/// Who and what a port call is working for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestContext {
+    /// Shared by every log line of one use case invocation.
+    pub correlation_id: String,
+    pub tenant: Option<TenantId>,
+    pub customer: Option<CustomerId>,
+}
+
+impl RequestContext {
+    /// Creates a context with just a correlation ID.
+    #[must_use]
+    pub fn new(correlation_id: impl Into<String>) -> Self {
+        Self {
+            correlation_id: correlation_id.into(),
+            tenant: None,
+            customer: None,
+        }
+    }
+}
+
+// =============================================================================
+// Domain Errors
+// =============================================================================
+//
+// These are BUSINESS errors, not technical errors. Notice:
+// - "InvalidOrder" = business rule violation
+// - "PaymentFailed" = business operation failed
+//
+// We DON'T have "DatabaseConnectionError" or "HttpTimeout".
+// Those are adapter errors that get TRANSLATED into domain errors.
+// The domain never sees sqlx::Error or reqwest::Error.
+//
+// More information available here:
+// https://www.40tude.fr/docs/06_programmation/rust/016_errors/errors_02.html
+
+/// Why a payment failed, in business terms.
+///
+/// Adapters translate provider-specific codes (Stripe's `card_declined`,
+/// an HTTP 503...) into one of these. The distinction that matters most:
+/// is it worth trying again?
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentFailureReason {
+    /// The card or account was declined. Retrying won't help.
+    Declined,
+    /// The provider is temporarily unavailable (e.g., HTTP 503).
+    Unavailable,
+    /// The provider didn't answer in time.
+    Timeout,
+    /// The request itself was wrong (unknown authorization, already captured...).
+    InvalidRequest,
+    /// Our own fraud rules refused it (amount too high, too many charges
+    /// too quickly). Retrying right away only trips them again.
+    LimitExceeded,
+    /// Below the smallest amount the provider accepts (50 cents at Stripe).
+    AmountTooSmall,
+}
+
+impl PaymentFailureReason {
+    /// Returns true if the same request might succeed a moment later.
+    #[must_use]
+    pub const fn is_transient(self) -> bool {
+        matches!(self, Self::Unavailable | Self::Timeout)
+    }
+}
+
+impl fmt::Display for PaymentFailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Declined => write!(f, "Declined"),
+            Self::Unavailable => write!(f, "Unavailable"),
+            Self::Timeout => write!(f, "Timeout"),
+            Self::InvalidRequest => write!(f, "InvalidRequest"),
+            Self::LimitExceeded => write!(f, "LimitExceeded"),
+            Self::AmountTooSmall => write!(f, "AmountTooSmall"),
+        }
+    }
+}
+
+/// The backend's own error behind an [`OrderError`], e.g. a
+/// `rusqlite::Error`, for [`std::error::Error::source`].
+pub type ErrorSource = Box<dyn std::error::Error + Send + Sync>;
+
+/// Domain errors for order operations.
+#[derive(Debug)]
+pub enum OrderError {
+    /// Order violates business rules (e.g., no items).
+    InvalidOrder,
+    /// Payment processing failed.
+    PaymentFailed(PaymentFailureReason),
+    /// Storage operation failed. Carries the backend's error, when there
+    /// is one, as the [`source`](std::error::Error::source).
+    StorageFailed(Option<ErrorSource>),
+    /// Notification delivery failed. Carries the provider's error, when
+    /// there is one, as the [`source`](std::error::Error::source).
+    NotificationFailed(Option<ErrorSource>),
+    /// An order with this ID is already stored.
+    DuplicateOrder(OrderId),
+    /// No order with this ID is stored.
+    OrderNotFound(OrderId),
+    /// Recipient name is blank or their contact is malformed.
+    InvalidRecipient,
+    /// An item's name is blank once sanitized, or too long.
+    InvalidLineItem,
+    /// The operation isn't allowed while the order is in this status.
+    InvalidStatus(OrderStatus),
+    /// Refund amount is zero or more than what is left to refund.
+    InvalidRefund,
+    /// Customer name is blank or their email is malformed.
+    InvalidCustomer,
+    /// No customer with this ID is registered.
+    CustomerNotFound(CustomerId),
+    /// The gift card provider doesn't know this card.
+    GiftCardNotFound(GiftCardId),
+    /// No subscription with this ID is stored.
+    SubscriptionNotFound(SubscriptionId),
+    /// Storage failed after the customer was charged.
+    /// `refunded` tells whether the money went back to them.
+    StorageFailedAfterCharge { refunded: bool },
+    /// Notification failed after the customer was charged.
+    /// `refunded` tells whether the money went back to them.
+    NotificationFailedAfterCharge { refunded: bool },
+    /// The same items were ordered moments ago; `existing` is that order.
+    DuplicateSubmission { existing: OrderId },
+    /// Someone else updated this order since it was loaded: the caller
+    /// loaded version `expected`, storage holds version `found`.
+    Conflict {
+        id: OrderId,
+        expected: u32,
+        found: u32,
+    },
+    /// The catalog has no such product.
+    UnknownProduct(ProductId),
+    /// There is no exchange rate from one currency to the other.
+    NoExchangeRate { from: Currency, to: Currency },
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOrder => write!(f, "InvalidOrder"),
+            Self::PaymentFailed(reason) => write!(f, "PaymentFailed({reason})"),
+            Self::StorageFailed(_) => write!(f, "StorageFailed"),
+            Self::NotificationFailed(_) => write!(f, "NotificationFailed"),
+            Self::DuplicateOrder(id) => write!(f, "DuplicateOrder({id})"),
+            Self::OrderNotFound(id) => write!(f, "OrderNotFound({id})"),
+            Self::InvalidRecipient => write!(f, "InvalidRecipient"),
+            Self::InvalidLineItem => write!(f, "InvalidLineItem"),
+            Self::InvalidStatus(status) => write!(f, "InvalidStatus({status})"),
+            Self::InvalidRefund => write!(f, "InvalidRefund"),
+            Self::InvalidCustomer => write!(f, "InvalidCustomer"),
+            Self::CustomerNotFound(id) => write!(f, "CustomerNotFound({id})"),
+            Self::GiftCardNotFound(id) => write!(f, "GiftCardNotFound({id})"),
+            Self::SubscriptionNotFound(id) => write!(f, "SubscriptionNotFound({id})"),
+            Self::StorageFailedAfterCharge { refunded } => {
+                write!(f, "StorageFailedAfterCharge(refunded={refunded})")
+            }
+            Self::NotificationFailedAfterCharge { refunded } => {
+                write!(f, "NotificationFailedAfterCharge(refunded={refunded})")
+            }
+            Self::DuplicateSubmission { existing } => {
+                write!(f, "DuplicateSubmission(existing={existing})")
+            }
+            Self::Conflict {
+                id,
+                expected,
+                found,
+            } => write!(f, "Conflict({id}, expected={expected}, found={found})"),
+            Self::UnknownProduct(id) => write!(f, "UnknownProduct({id})"),
+            Self::NoExchangeRate { from, to } => write!(f, "NoExchangeRate({from} to {to})"),
+        }
+    }
+}
+
+impl OrderError {
+    /// [`OrderError::StorageFailed`], with the backend's error attached.
+    #[must_use]
+    pub fn storage_failed(source: impl Into<ErrorSource>) -> Self {
+        Self::StorageFailed(Some(source.into()))
+    }
+
+    /// [`OrderError::NotificationFailed`], with the provider's error attached.
+    #[must_use]
+    pub fn notification_failed(source: impl Into<ErrorSource>) -> Self {
+        Self::NotificationFailed(Some(source.into()))
+    }
+
+    /// Returns true for failures worth retrying (a payment provider hiccup),
+    /// false for business outcomes like a declined card.
+    #[must_use]
+    pub const fn is_transient(&self) -> bool {
+        match self {
+            Self::PaymentFailed(reason) => reason.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// A short, stable name for the kind of error, e.g. `"order_not_found"`.
+    ///
+    /// Driving adapters hand it to their clients (in a JSON error body, say):
+    /// unlike the Display text, it never carries data and never changes, so
+    /// clients can match on it.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidOrder => "invalid_order",
+            Self::PaymentFailed(_) => "payment_failed",
+            Self::StorageFailed(_) => "storage_failed",
+            Self::NotificationFailed(_) => "notification_failed",
+            Self::DuplicateOrder(_) => "duplicate_order",
+            Self::OrderNotFound(_) => "order_not_found",
+            Self::InvalidRecipient => "invalid_recipient",
+            Self::InvalidLineItem => "invalid_line_item",
+            Self::InvalidStatus(_) => "invalid_status",
+            Self::InvalidRefund => "invalid_refund",
+            Self::InvalidCustomer => "invalid_customer",
+            Self::CustomerNotFound(_) => "customer_not_found",
+            Self::GiftCardNotFound(_) => "gift_card_not_found",
+            Self::SubscriptionNotFound(_) => "subscription_not_found",
+            Self::StorageFailedAfterCharge { .. } => "storage_failed_after_charge",
+            Self::NotificationFailedAfterCharge { .. } => "notification_failed_after_charge",
+            Self::DuplicateSubmission { .. } => "duplicate_submission",
+            Self::Conflict { .. } => "conflict",
+            Self::UnknownProduct(_) => "unknown_product",
+            Self::NoExchangeRate { .. } => "no_exchange_rate",
+        }
+    }
+}
+
+impl std::error::Error for OrderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::StorageFailed(Some(source)) | Self::NotificationFailed(Some(source)) => {
+                Some(source.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
+
+// =============================================================================
+// Port Traits (Output Ports)
+// =============================================================================
+//
+// Here's where it gets interesting! Remember the Sender trait from dip_02?
+// That was our first "port". Ports define what the domain NEEDS from the
+// outside world, without specifying HOW those needs are fulfilled.
+//
+// These traits live in the domain crate because:
+// 1. The domain DEFINES its own contracts
+// 2. Adapters (in other crates) IMPLEMENT these contracts
+// 3. The dependency arrow points INWARD: adapters -> domain
+//
+// This is the Dependency Inversion Principle at the crate level!
+//
+// Every port method takes `&self`, even the ones that write. A web server
+// shares one OrderService between many request handlers (threads), and
+// `&mut self` would make that impossible. Adapters that keep state in memory
+// use interior mutability (RwLock, Mutex, atomics); real databases already
+// work that way: a connection pool is shared, not owned.
+//
+// The `*_with_ctx` methods are the same calls with a [`RequestContext`].
+// By default they drop the context and call the plain method, so adapters
+// that don't log anything keep working untouched. Adapters that do log
+// override them and tag their lines with the correlation ID.
+//
+// Such an adapter usually goes the other way round too: the real work is in
+// its `*_with_ctx` methods, and each plain method forwards to its
+// counterpart with `RequestContext::default()`. The plain calls are the same
+// calls, on behalf of no request in particular.
+
+/// Repository port for persisting orders.
+///
+/// The domain needs to store orders somewhere. It doesn't care if that
+/// "somewhere" is PostgreSQL, MongoDB, a file, or a HashMap.
+/// That's an adapter's decision.
+///
+/// Writes are split in two on purpose: `insert` for new orders and `update`
+/// for existing ones. A silent overwrite would hide bugs like two orders
+/// getting the same ID.
+///
+/// Storage is partitioned by [`TenantId`]: writes go to `order.tenant`, reads
+/// name the tenant they look in. There is no way to ask "order #7 of any
+/// tenant", so one storefront can't stumble on another's orders. Two tenants
+/// may even use the same order IDs.
+pub trait OrderRepository {
+    /// Stores a new order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::DuplicateOrder`] if an order with the same ID
+    /// is already stored, or [`OrderError::StorageFailed`] if the operation fails.
+    fn insert(&self, order: &Order) -> Result<(), OrderError>;
+
+    /// Replaces an existing order.
+    ///
+    /// The stored version isn't checked: the last write wins. Use
+    /// [`OrderRepository::update_versioned`] when two writers may race.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] if no order with this ID is stored,
+    /// or [`OrderError::StorageFailed`] if the operation fails.
+    fn update(&self, order: &Order) -> Result<(), OrderError>;
+
+    /// Replaces an existing order, unless someone else changed it first.
+    ///
+    /// Optimistic locking: the stored order must still be at version
+    /// `expected`, the one the caller loaded. `order` carries the new version.
+    ///
+    /// This default reads then writes, which leaves a gap between the two.
+    /// Adapters that can check and write at once (a lock, a conditional
+    /// `UPDATE`) should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::OrderNotFound`] if no order with this ID is stored,
+    /// [`OrderError::Conflict`] if the stored version isn't `expected`,
+    /// or [`OrderError::StorageFailed`] if the operation fails.
+    fn update_versioned(&self, order: &Order, expected: u32) -> Result<(), OrderError> {
+        let stored = self
+            .find(order.tenant, order.id)?
+            .ok_or(OrderError::OrderNotFound(order.id))?;
+        if stored.version != expected {
+            return Err(OrderError::Conflict {
+                id: order.id,
+                expected,
+                found: stored.version,
+            });
+        }
+        self.update(order)
+    }
+
+    /// Saves an order to storage, inserting or overwriting it.
+    ///
+    /// Kept for one release so existing callers keep compiling.
+    /// Use [`OrderRepository::insert`] or [`OrderRepository::update`] instead.
+    ///
+    /// Overwriting an order with a different one is almost always two
+    /// orders given the same ID. The in-memory and PostgreSQL adapters
+    /// refuse it (unless made lenient), and accept saving the same order
+    /// again ([`Order::same_contents`]). This default overwrites.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::DuplicateOrder`] if an adapter refuses to
+    /// overwrite a different order, or [`OrderError::StorageFailed`] if the
+    /// operation fails.
+    #[deprecated(note = "use `insert` or `update` instead")]
+    fn save(&self, order: &Order) -> Result<(), OrderError> {
+        if self.find(order.tenant, order.id)?.is_some() {
+            self.update(order)
+        } else {
+            self.insert(order)
+        }
+    }
+
+    /// Stores new orders in one go: one round trip instead of one per order.
+    ///
+    /// All or nothing, for adapters that can manage it (a transaction, a
+    /// single lock): if any order can't be inserted, none is. This default
+    /// can't: it inserts one order at a time and stops at the first failure,
+    /// leaving the orders before it stored. An empty slice stores nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::DuplicateOrder`] for the first order whose ID is
+    /// already stored (or appears twice in `orders`), or
+    /// [`OrderError::StorageFailed`] if the operation fails.
+    fn save_all(&self, orders: &[Order]) -> Result<(), OrderError> {
+        orders.iter().try_for_each(|order| self.insert(order))
+    }
+
+    /// Removes one of `tenant`'s orders. Returns whether there was one.
+    ///
+    /// The use cases never delete an order (a cancelled order stays, for the
+    /// books), but maintenance scripts, tests and caches need to. A
+    /// repository that can't delete keeps this default, which refuses.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails, or if
+    /// the repository doesn't support deleting.
+    fn delete(&self, _tenant: TenantId, _id: OrderId) -> Result<bool, OrderError> {
+        Err(OrderError::StorageFailed(None))
+    }
+
+    /// Hides one of `tenant`'s orders from `find` and `find_all`, but keeps
+    /// it, so [`OrderRepository::restore`] can bring it back. Returns whether
+    /// there was one to hide.
+    ///
+    /// Finance wants deleted orders out of sight, not out of the books. A
+    /// repository that can't keep tombstones keeps this default, which
+    /// deletes for good.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`OrderRepository::delete`].
+    fn soft_delete(&self, tenant: TenantId, id: OrderId) -> Result<bool, OrderError> {
+        self.delete(tenant, id)
+    }
+
+    /// Brings back an order hidden by [`OrderRepository::soft_delete`].
+    /// Returns whether there was one to bring back.
+    ///
+    /// The default never has one: without tombstones, deleted is gone.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn restore(&self, _tenant: TenantId, _id: OrderId) -> Result<bool, OrderError> {
+        Ok(false)
+    }
+
+    /// Finds one of `tenant`'s orders by ID. Soft-deleted orders are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError>;
+
+    /// Returns every order stored for `tenant`, sorted by ID, soft-deleted
+    /// orders left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError>;
+
+    /// Like [`OrderRepository::find`], soft-deleted orders included.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find_including_deleted(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.find(tenant, id)
+    }
+
+    /// Shows `tenant`'s order `id` to `read` (`None` if there's no such
+    /// order), without handing out a copy of it.
+    ///
+    /// [`OrderRepository::find`] returns an owned [`Order`], so it clones
+    /// one out of the store, every item name with it. When the caller only
+    /// wants a field or two (the total, the status), a borrow is enough.
+    /// Sharing the items instead (`Arc<[LineItem]>`) would make every read
+    /// cheap, but change a field that all the code building and editing
+    /// orders uses. Like [`OrderRepository::for_each`], `read` must not
+    /// call back into the repository.
+    ///
+    /// This default still goes through `find`; adapters holding their
+    /// orders in memory should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find_with(
+        &self,
+        tenant: TenantId,
+        id: OrderId,
+        read: &mut dyn FnMut(Option<&Order>),
+    ) -> Result<(), OrderError> {
+        read(self.find(tenant, id)?.as_ref());
+        Ok(())
+    }
+
+    /// Shows each of `tenant`'s orders to `visit`, in ID order, until it
+    /// returns [`ControlFlow::Break`].
+    ///
+    /// For going through every order (an export, a count) without holding
+    /// them all at once. This default still loads them all with
+    /// [`OrderRepository::find_all`]; adapters that can stream should
+    /// override it. `visit` must not call back into the repository: an
+    /// adapter may be holding a lock or a cursor while it runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn for_each(
+        &self,
+        tenant: TenantId,
+        visit: &mut dyn FnMut(&Order) -> ControlFlow<()>,
+    ) -> Result<(), OrderError> {
+        for order in &self.find_all(tenant)? {
+            if visit(order).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// How many of `tenant`'s orders match `predicate`, counted with
+    /// [`OrderRepository::for_each`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn count_where(
+        &self,
+        tenant: TenantId,
+        predicate: &dyn Fn(&Order) -> bool,
+    ) -> Result<usize, OrderError> {
+        let mut count = 0;
+        self.for_each(tenant, &mut |order| {
+            if predicate(order) {
+                count += 1;
+            }
+            ControlFlow::Continue(())
+        })?;
+        Ok(count)
+    }
+
+    /// Finds several of `tenant`'s orders by ID at once: one round trip
+    /// instead of one per ID.
+    ///
+    /// The contract, for every adapter:
+    /// - the orders come in the order of `ids`
+    /// - an ID with no order (or a soft-deleted one) is simply absent from
+    ///   the result: no error, no placeholder
+    /// - an ID given twice gives its order once, where it first appears
+    /// - no IDs, no orders
+    ///
+    /// This default calls [`OrderRepository::find`] once per ID. Adapters
+    /// that can fetch many rows at once should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find_many(&self, tenant: TenantId, ids: &[OrderId]) -> Result<Vec<Order>, OrderError> {
+        let mut orders: Vec<Order> = Vec::new();
+        for &id in ids {
+            if orders.iter().any(|order| order.id == id) {
+                continue;
+            }
+            if let Some(order) = self.find(tenant, id)? {
+                orders.push(order);
+            }
+        }
+        Ok(orders)
+    }
+
+    /// Returns `tenant`'s orders with at least one item whose name contains
+    /// `query`, ignoring case (see [`Order::has_item_named_like`]), sorted by
+    /// ID. Soft-deleted orders are left out.
+    ///
+    /// The query is plain text, not a pattern: `%` and `_` match themselves.
+    ///
+    /// This default loads every order and filters them: fine in memory, a
+    /// full read of the table in a database. Adapters that can search where
+    /// the orders are should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find_by_item_name(&self, tenant: TenantId, query: &str) -> Result<Vec<Order>, OrderError> {
+        let mut orders = self.find_all(tenant)?;
+        orders.retain(|order| order.has_item_named_like(query));
+        Ok(orders)
+    }
+
+    /// [`OrderRepository::insert`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`OrderRepository::insert`].
+    fn insert_with_ctx(&self, _ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.insert(order)
+    }
+
+    /// [`OrderRepository::update`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`OrderRepository::update`].
+    fn update_with_ctx(&self, _ctx: &RequestContext, order: &Order) -> Result<(), OrderError> {
+        self.update(order)
+    }
+
+    /// [`OrderRepository::find`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`OrderRepository::find`].
+    fn find_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        tenant: TenantId,
+        id: OrderId,
+    ) -> Result<Option<Order>, OrderError> {
+        self.find(tenant, id)
+    }
+}
+
+/// Payment gateway port for processing payments.
+///
+/// The domain needs to charge customers. It doesn't care if that's
+/// via Stripe, PayPal, or carrier pigeons carrying gold coins.
+///
+/// Two styles are supported:
+/// - one step: `charge`
+/// - two steps: `authorize` (hold the funds), then `capture` (take them)
+///   or `void` (release them)
+///
+/// When an [`IdempotencyKey`] is given, adapters should forward it to the
+/// provider so a retried request never moves money twice.
+pub trait PaymentGateway {
+    /// Charges the given amount and returns the provider's receipt.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::PaymentFailed`] with the reason if payment fails.
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError>;
+
+    /// Holds the given amount without taking it yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::PaymentFailed`] if the authorization is declined.
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError>;
+
+    /// Takes the funds held by a previous authorization.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::PaymentFailed`] if the authorization is unknown,
+    /// expired, or already settled.
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError>;
+
+    /// Releases the funds held by a previous authorization.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::PaymentFailed`] if the authorization is unknown
+    /// or already settled.
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError>;
+
+    /// Gives back some or all of a settled payment.
+    ///
+    /// `transaction_id` is the ID stored on the order (see [`Order::transaction_id`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::PaymentFailed`] if the refund is rejected.
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError>;
+
+    /// [`PaymentGateway::charge`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`PaymentGateway::charge`].
+    fn charge_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge(amount, idempotency_key)
+    }
+
+    /// [`PaymentGateway::charge_with_ctx`], with metadata for the provider
+    /// to keep with the charge.
+    ///
+    /// This default drops the metadata: a provider without any still
+    /// charges. Adapters for providers that keep it should override this,
+    /// and decorators forward it.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`PaymentGateway::charge`], and
+    /// [`PaymentFailureReason::InvalidRequest`] if the provider refuses the
+    /// metadata (too many keys, a value too long).
+    fn charge_with_metadata(
+        &self,
+        ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+        _metadata: &ChargeMetadata,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.charge_with_ctx(ctx, amount, idempotency_key)
+    }
+
+    /// [`PaymentGateway::authorize`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`PaymentGateway::authorize`].
+    fn authorize_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.authorize(amount, idempotency_key)
+    }
+
+    /// [`PaymentGateway::capture`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`PaymentGateway::capture`].
+    fn capture_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.capture(auth)
+    }
+
+    /// [`PaymentGateway::void`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`PaymentGateway::void`].
+    fn void_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        auth: &AuthorizationId,
+    ) -> Result<(), OrderError> {
+        self.void(auth)
+    }
+
+    /// [`PaymentGateway::refund`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`PaymentGateway::refund`].
+    fn refund_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        transaction_id: &str,
+        amount: Money,
+    ) -> Result<(), OrderError> {
+        self.refund(transaction_id, amount)
+    }
+}
+
+/// Gift card port: a prepaid balance the customer can spend.
+///
+/// Unlike a card payment there's no hold: a debit takes the money at once,
+/// and a credit is how a debit gets undone.
+pub trait GiftCardGateway {
+    /// What is left on the card.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::GiftCardNotFound`] for an unknown card.
+    fn balance(&self, card: GiftCardId) -> Result<Money, OrderError>;
+
+    /// Takes `amount` off the card.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::GiftCardNotFound`] for an unknown card, or
+    /// [`OrderError::PaymentFailed`] if the balance is too low.
+    fn debit(&self, card: GiftCardId, amount: Money) -> Result<(), OrderError>;
+
+    /// Puts `amount` back on the card.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::GiftCardNotFound`] for an unknown card.
+    fn credit(&self, card: GiftCardId, amount: Money) -> Result<(), OrderError>;
+}
+
+/// Notification port for sending messages to customers.
+///
+/// Hey, this is our old friend from dip_02! Same concept:
+/// "I need to notify someone about an order."
+/// Could be email, SMS, push notification, carrier pigeon...
+///
+/// The [`Recipient`] tells the adapter WHO to notify. An adapter that can't
+/// use the recipient's kind of contact (SendGrid given a phone number)
+/// fails with [`OrderError::NotificationFailed`].
+///
+/// The [`NotificationEvent`] tells it WHAT happened. Each adapter words
+/// each kind of event its own way.
+pub trait Sender {
+    /// Notifies a recipient about an event.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::NotificationFailed`] if sending fails.
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError>;
+
+    /// Sends an order confirmation to a recipient.
+    ///
+    /// Shorthand for notifying [`NotificationEvent::OrderConfirmed`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::NotificationFailed`] if sending fails.
+    fn send(&self, recipient: &Recipient, order: &Order) -> Result<(), OrderError> {
+        self.notify(recipient, &NotificationEvent::OrderConfirmed(order.clone()))
+    }
+
+    /// Sends an order confirmation to each recipient of `batch`, carrying
+    /// on past the ones that fail.
+    ///
+    /// Failures aren't errors: they're in the report, by their index in
+    /// `batch`, for the caller to retry or set aside. This default calls
+    /// [`Sender::send`] once per confirmation; providers with a batch
+    /// endpoint should override it.
+    fn send_all(&self, batch: &[(Recipient, Order)]) -> SendAllReport {
+        let mut report = SendAllReport::default();
+        for (index, (recipient, order)) in batch.iter().enumerate() {
+            match self.send(recipient, order) {
+                Ok(()) => report.succeeded += 1,
+                Err(e) => report.failures.push((index, e)),
+            }
+        }
+        report
+    }
+
+    /// [`Sender::notify`] on behalf of a request.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Sender::notify`].
+    fn notify_with_ctx(
+        &self,
+        _ctx: &RequestContext,
+        recipient: &Recipient,
+        event: &NotificationEvent,
+    ) -> Result<(), OrderError> {
+        self.notify(recipient, event)
+    }
+}
+
+/// Outcome of [`Sender::send_all`].
+#[derive(Debug, Default)]
+pub struct SendAllReport {
+    /// Confirmations the sender accepted.
+    pub succeeded: usize,
+    /// The confirmations that failed: their index in the batch, and why.
+    pub failures: Vec<(usize, OrderError)>,
+}
+
+impl SendAllReport {
+    /// How many confirmations failed.
+    #[must_use]
+    pub fn failed(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// The indices in the batch of the confirmations that failed, in order.
+    #[must_use]
+    pub fn failed_indices(&self) -> Vec<usize> {
+        self.failures.iter().map(|&(index, _)| index).collect()
+    }
+
+    /// True when every confirmation went out.
+    #[must_use]
+    pub fn all_sent(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Idempotency port remembering which order each request key produced.
+///
+/// A small key -> OrderId map. It could live in Redis with a TTL, in a
+/// database table, or in a HashMap. The domain doesn't care.
+pub trait IdempotencyStore {
+    /// Finds the order previously created for this key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find(&self, key: &IdempotencyKey) -> Result<Option<OrderId>, OrderError>;
+
+    /// Remembers that this key produced this order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn record(&self, key: IdempotencyKey, id: OrderId) -> Result<(), OrderError>;
+}
+
+/// Repository port for registered customers.
+///
+/// Same idea as [`OrderRepository`], for a different entity.
+pub trait CustomerRepository {
+    /// Saves a customer, replacing any customer with the same ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn save(&self, customer: &Customer) -> Result<(), OrderError>;
+
+    /// Finds a customer by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find(&self, id: CustomerId) -> Result<Option<Customer>, OrderError>;
+}
+
+/// Loyalty port: the points each customer has earned.
+///
+/// Points are counted, not money: a ledger only adds and takes away. It
+/// could be a `loyalty` table, or a marketing platform's API.
+pub trait LoyaltyLedger {
+    /// Adds `points` to the customer's balance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn credit(&self, customer: CustomerId, points: u32) -> Result<(), OrderError>;
+
+    /// Takes `points` off the customer's balance, down to zero at most:
+    /// points already spent can't be taken back.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn debit(&self, customer: CustomerId, points: u32) -> Result<(), OrderError>;
+
+    /// The customer's balance: 0 for a customer who never earned any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn balance(&self, customer: CustomerId) -> Result<u32, OrderError>;
+}
+
+/// Repository port for subscriptions.
+///
+/// Same idea as [`CustomerRepository`], plus the one query the scheduler
+/// needs: which subscriptions are due.
+pub trait SubscriptionRepository {
+    /// Saves a subscription, replacing any subscription with the same ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn save(&self, subscription: &Subscription) -> Result<(), OrderError>;
+
+    /// Finds a subscription by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find(&self, id: SubscriptionId) -> Result<Option<Subscription>, OrderError>;
+
+    /// Every subscription due at `now` (see [`Subscription::is_due`]).
+    ///
+    /// In SQL: `WHERE active AND next_run <= $1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn find_due(&self, now: SystemTime) -> Result<Vec<Subscription>, OrderError>;
+}
+
+/// A notification waiting in a [`NotificationOutbox`].
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    /// Assigned by the outbox; used to mark the entry as sent.
+    pub id: u64,
+    pub recipient: Recipient,
+    pub event: NotificationEvent,
+}
+
+/// Outcome of sending a batch of notifications that were set aside for later,
+/// from a [`NotificationOutbox`] or an in-memory queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Notifications sent and removed from the backlog.
+    pub sent: usize,
+    /// Notifications whose send failed; they stay for the next flush.
+    pub failed: usize,
+}
+
+/// Outbox port: notifications stored next to the data, sent later.
+///
+/// The "transactional outbox" pattern. Instead of calling the sender right
+/// after saving an order (and losing the email if we crash in between), we
+/// store the notification in the same unit of work as the order. A separate
+/// step reads the outbox, sends, and marks entries as sent. Worst case, a
+/// crash means a message is sent late, never that it's lost.
+///
+/// In PostgreSQL this is an `outbox` table written in the same transaction
+/// as the `orders` row.
+pub trait NotificationOutbox {
+    /// Stores a notification to send later.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn enqueue(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError>;
+
+    /// Returns up to `max` unsent entries, oldest first, WITHOUT removing them.
+    ///
+    /// An entry stays in the outbox until [`NotificationOutbox::mark_sent`],
+    /// so a failed send is simply picked up again by the next batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if retrieval fails.
+    fn dequeue_batch(&self, max: usize) -> Result<Vec<OutboxEntry>, OrderError>;
+
+    /// Removes a sent entry from the outbox.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the operation fails.
+    fn mark_sent(&self, id: u64) -> Result<(), OrderError>;
+}
+
+/// Audit port: an append-only trail of use case invocations.
+///
+/// Entries are only ever added, never changed or removed. In production this
+/// is an insert-only table, a write-once bucket, or a log shipper.
+pub trait AuditLog {
+    /// Appends an entry to the trail.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::StorageFailed`] if the entry can't be written.
+    fn record(&self, entry: AuditEntry) -> Result<(), OrderError>;
+}
+
+/// Port handing out new identifiers.
+///
+/// In production, IDs come from a database sequence, a UUID/ULID generator,
+/// or a Snowflake-style service. Each call must return an ID never returned
+/// before, even when called from several threads at once.
+pub trait IdGenerator {
+    /// Returns a fresh order ID.
+    fn next_order_id(&self) -> OrderId;
+
+    /// Returns a fresh customer ID.
+    fn next_customer_id(&self) -> CustomerId;
+
+    /// Returns a fresh subscription ID.
+    fn next_subscription_id(&self) -> SubscriptionId;
+}
+
+/// Port telling what products cost.
+///
+/// The authoritative price, as opposed to whatever a client sends. In
+/// production: the catalog service, or a `products` table.
+pub trait PriceList {
+    /// Returns the unit price of `product`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::UnknownProduct`] if the catalog doesn't have it,
+    /// or [`OrderError::StorageFailed`] if the catalog can't be read.
+    fn price_of(&self, product: &ProductId) -> Result<Money, OrderError>;
+}
+
+/// Port telling what one currency is worth in another.
+///
+/// For reporting in the merchant's settlement currency. In production: a
+/// rate feed (the ECB publishes one daily), cached for the day.
+pub trait ExchangeRates {
+    /// Returns what one unit of `from` is worth in `to`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::NoExchangeRate`] if the pair isn't quoted, or
+    /// [`OrderError::StorageFailed`] if the rates can't be read.
+    fn rate(&self, from: Currency, to: Currency) -> Result<Rate, OrderError>;
+}
+
+/// What a receipt calls the [`Order::shipping`] cost, on the line after
+/// the items.
+pub const SHIPPING_LINE_NAME: &str = "Shipping";
+
+/// Port telling what shipping an order costs.
+///
+/// In production: a carrier's rate API (UPS, DHL...), which quotes on the
+/// parcel's weight and where it goes.
+pub trait ShippingCalculator {
+    /// Returns the cost of shipping `items` to `destination`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrderError::InvalidOrder`] if the items can't be shipped
+    /// (too heavy, say), or [`OrderError::StorageFailed`] if the rates
+    /// can't be read.
+    fn cost(&self, items: &[LineItem], destination: &Address) -> Result<Money, OrderError>;
+}
+
+/// Port telling the current time.
+///
+/// Code that calls `SystemTime::now()` directly can only be tested by
+/// waiting. Behind a port, a test plugs in a clock it moves by hand.
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// Port writing diagnostic log lines.
+///
+/// In production: `tracing`, `log`, or a JSON line per event shipped to
+/// Loki or Datadog. Logging must never fail a use case, so there is no
+/// error to return.
+pub trait Logger {
+    /// Writes `message`, tagged with the request it belongs to.
+    fn log(&self, ctx: &RequestContext, message: &str);
+}
+
+/// How well an adapter can do its job right now.
+///
+/// Ordered from best to worst, so the status of several adapters is their
+/// [`Ord::max`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthStatus {
+    Healthy,
+    /// Working, but not as it should: a fallback is in use, some calls fail.
+    Degraded(String),
+    /// Not working: calls will fail.
+    Unhealthy(String),
+}
+
+impl HealthStatus {
+    /// Whether calls can be expected to work, if not all of them.
+    #[must_use]
+    pub const fn is_usable(&self) -> bool {
+        !matches!(self, Self::Unhealthy(_))
+    }
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Healthy => write!(f, "healthy"),
+            Self::Degraded(reason) => write!(f, "degraded: {reason}"),
+            Self::Unhealthy(reason) => write!(f, "unhealthy: {reason}"),
+        }
+    }
+}
+
+/// Capability of an adapter to say whether it can do its job, before any
+/// traffic relies on it.
+///
+/// Not part of the port traits: a test double has no health to report, and
+/// shouldn't have to pretend. Adapters implement it next to their port.
+/// Real ones check their backend (a `SELECT 1`, an authenticated GET);
+/// simulated ones are always healthy.
+pub trait HealthCheck {
+    /// Checks the adapter. May take a network round trip.
+    fn health(&self) -> HealthStatus;
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+//
+// Domain tests are PURE. No mocks needed. We're testing business logic
+// with plain Rust values. This is one of the biggest benefits of a clean
+// domain layer. Tests are simple and fast.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn order_new_with_items_succeeds() {
+        let items = vec![
+            LineItem {
+                name: "Book".to_string(),
+                price: Money(4999),
+                weight_grams: None,
+            },
+            LineItem {
+                name: "Pen".to_string(),
+                price: Money(199),
+                weight_grams: None,
+            },
+        ];
+
+        let order = Order::new(OrderId(1), items).unwrap();
+
+        assert_eq!(order.id, OrderId(1));
+        assert_eq!(order.items.len(), 2);
+        assert_eq!(order.total, Money(5198)); // $51.98
+        assert_eq!(order.transaction_id, None); // not paid yet
+        assert_eq!(order.status, OrderStatus::Pending);
+    }
+
+    fn paid_order(total: u32) -> Order {
+        let mut order = Order::new(
+            OrderId(1),
+            vec![LineItem {
+                name: "Book".to_string(),
+                price: Money(total),
+                weight_grams: None,
+            }],
+        )
+        .unwrap();
+        order.mark_paid(UNIX_EPOCH).unwrap();
+        order
+    }
+
+    #[test]
+    fn cancel_paid_order_returns_amount_to_refund() {
+        let mut order = paid_order(1000);
+
+        let to_refund = order.cancel(UNIX_EPOCH, None).unwrap();
+
+        assert_eq!(to_refund, Money(1000));
+        assert_eq!(order.status, OrderStatus::Cancelled);
+    }
+
+    #[test]
+    fn cancel_twice_fails() {
+        let mut order = paid_order(1000);
+        order.cancel(UNIX_EPOCH, None).unwrap();
+
+        let result = order.cancel(UNIX_EPOCH, None);
+
+        assert!(matches!(
+            result,
+            Err(OrderError::InvalidStatus(OrderStatus::Cancelled))
+        ));
+    }
+
+    #[test]
+    fn status_changes_are_written_down_in_the_history() {
+        let placed = UNIX_EPOCH + Duration::from_secs(60);
+        let paid = placed + Duration::from_secs(1);
+        let cancelled = paid + Duration::from_secs(60);
+        let book = LineItem {
+            name: "Book".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        };
+        let mut order = Order::new(OrderId(1), vec![book]).unwrap();
+
+        order.record_placed(placed).unwrap();
+        order.mark_paid(paid).unwrap();
+        order.cancel(cancelled, Some("changed my mind")).unwrap();
+        // A refused transition leaves no trace
+        order.cancel(cancelled, None).unwrap_err();
+        order.record_placed(cancelled).unwrap_err();
+
+        assert_eq!(
+            order.history,
+            [
+                HistoryEntry {
+                    from: None,
+                    to: OrderStatus::Pending,
+                    at: placed,
+                    reason: None,
+                },
+                HistoryEntry {
+                    from: Some(OrderStatus::Pending),
+                    to: OrderStatus::Paid,
+                    at: paid,
+                    reason: None,
+                },
+                HistoryEntry {
+                    from: Some(OrderStatus::Paid),
+                    to: OrderStatus::Cancelled,
+                    at: cancelled,
+                    reason: Some("changed my mind".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_refund_that_completes_it_changes_the_history() {
+        let mut order = paid_order(1000);
+        let done = UNIX_EPOCH + Duration::from_secs(7);
+
+        order.record_refund(Money(400), UNIX_EPOCH).unwrap();
+        assert_eq!(order.history.len(), 1);
+        order.record_refund(Money(600), done).unwrap();
+
+        let last = order.history.last().unwrap();
+        assert_eq!(
+            (last.from, last.to, last.at),
+            (Some(OrderStatus::Paid), OrderStatus::Refunded, done)
+        );
+    }
+
+    #[test]
+    fn subscription_needs_items_and_an_interval() {
+        let book = || {
+            vec![LineItem {
+                name: "Book".to_string(),
+                price: Money(1000),
+                weight_grams: None,
+            }]
+        };
+        let new = |items, days| {
+            Subscription::new(SubscriptionId(1), CustomerId(1), items, days, UNIX_EPOCH)
+        };
+
+        assert!(matches!(new(Vec::new(), 30), Err(OrderError::InvalidOrder)));
+        assert!(matches!(new(book(), 0), Err(OrderError::InvalidOrder)));
+        assert!(new(book(), 30).unwrap().active);
+    }
+
+    #[test]
+    fn subscription_is_due_until_advanced_past_now() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let items = vec![LineItem {
+            name: "Coffee".to_string(),
+            price: Money(1200),
+            weight_grams: None,
+        }];
+        let mut subscription =
+            Subscription::new(SubscriptionId(1), CustomerId(1), items, 7, UNIX_EPOCH + day)
+                .unwrap();
+
+        assert!(!subscription.is_due(UNIX_EPOCH));
+        assert!(subscription.is_due(UNIX_EPOCH + day));
+
+        // Three weeks late: one run, and the missed ones are skipped
+        let now = UNIX_EPOCH + day * 22;
+        subscription.advance(now);
+        assert_eq!(subscription.next_run, UNIX_EPOCH + day * 29);
+        assert!(!subscription.is_due(now));
+
+        subscription.cancel();
+        assert!(!subscription.is_due(UNIX_EPOCH + day * 100));
+    }
+
+    #[test]
+    fn loyalty_points_round_down_on_the_whole_order() {
+        let nine_items = vec![
+            LineItem {
+                name: "Pen".to_string(),
+                price: Money(111),
+                weight_grams: None,
+            };
+            9
+        ];
+        let mut order = Order::new(OrderId(1), nine_items).unwrap();
+        assert_eq!(order.loyalty_points(), 0);
+        order.mark_paid(UNIX_EPOCH).unwrap();
+
+        assert_eq!(order.total, Money(999));
+        assert_eq!(order.loyalty_points(), 9);
+        assert_eq!(paid_order(99).loyalty_points(), 0);
+    }
+
+    #[test]
+    fn refunds_take_loyalty_points_back() {
+        let mut order = paid_order(5000);
+
+        order.record_refund(Money(1050), UNIX_EPOCH).unwrap();
+        assert_eq!(order.loyalty_points(), 39);
+        order.cancel(UNIX_EPOCH, None).unwrap();
+        assert_eq!(order.loyalty_points(), 0);
+    }
+
+    #[test]
+    fn only_credited_points_are_taken_back() {
+        let mut order = paid_order(5000);
+        assert_eq!(order.points_owed(), 50);
+        // Crediting failed: a refund has nothing to take back
+        order.record_refund(Money(1050), UNIX_EPOCH).unwrap();
+        assert_eq!(order.take_back_points(), 0);
+
+        order.loyalty_credited = order.points_owed();
+        assert_eq!(order.loyalty_credited, 39);
+        order.cancel(UNIX_EPOCH, None).unwrap();
+        assert_eq!(order.take_back_points(), 39);
+        assert_eq!(order.loyalty_credited, 0);
+        assert_eq!(order.take_back_points(), 0);
+    }
+
+    #[test]
+    fn add_item_returns_the_price_difference() {
+        let mut order = paid_order(1000);
+        let pen = LineItem {
+            name: "Pen".to_string(),
+            price: Money(199),
+            weight_grams: None,
+        };
+
+        assert_eq!(order.add_item(pen.clone()).unwrap(), Money(199));
+        assert_eq!(order.total, Money(1199));
+
+        order.mark_shipped(UNIX_EPOCH).unwrap();
+        assert!(matches!(
+            order.add_item(pen),
+            Err(OrderError::InvalidStatus(OrderStatus::Shipped))
+        ));
+        assert_eq!(order.items.len(), 2);
+    }
+
+    #[test]
+    fn items_dont_change_while_a_charge_settles() {
+        let pen = LineItem {
+            name: "Pen".to_string(),
+            price: Money(199),
+            weight_grams: None,
+        };
+        let mut order = Order::new(OrderId(1), vec![pen.clone(), pen.clone()]).unwrap();
+        order.transaction_id = Some("txn_pending".to_string());
+
+        assert!(matches!(
+            order.add_item(pen.clone()),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert!(matches!(
+            order.remove_item(0),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert_eq!(order.total, Money(398));
+
+        // Once settled, the extra item is charged on its own
+        order.mark_paid(UNIX_EPOCH).unwrap();
+        assert_eq!(order.add_item(pen).unwrap(), Money(199));
+    }
+
+    #[test]
+    fn replaying_events_follows_the_business_rules() {
+        let book = LineItem {
+            name: "Book".to_string(),
+            price: Money(1000),
+            weight_grams: None,
+        };
+        let placed = OrderEvent::Placed {
+            id: OrderId(1),
+            tenant: TenantId(2),
+            items: vec![book.clone()],
+            customer_id: None,
+            gift_card: None,
+            at: Some(UNIX_EPOCH),
+        };
+
+        let order = Order::from_events(&[
+            placed.clone(),
+            OrderEvent::ItemAdded(book),
+            OrderEvent::Paid { at: UNIX_EPOCH },
+            OrderEvent::Refunded {
+                amount: Money(500),
+                at: UNIX_EPOCH,
+            },
+        ])
+        .unwrap();
+        assert_eq!(order.tenant, TenantId(2));
+        assert_eq!(order.total, Money(2000));
+        assert_eq!(order.refunded, Money(500));
+        assert_eq!(order.history.len(), 2);
+        assert_eq!(order.history[1].to, OrderStatus::Paid);
+
+        // Same rules as live: no refund before payment, one Placed only
+        assert!(matches!(
+            Order::from_events(&[
+                placed.clone(),
+                OrderEvent::Refunded {
+                    amount: Money(1),
+                    at: UNIX_EPOCH
+                }
+            ]),
+            Err(OrderError::InvalidStatus(OrderStatus::Pending))
+        ));
+        assert!(matches!(
+            Order::from_events(&[placed.clone(), placed]),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert!(matches!(
+            Order::from_events(&[OrderEvent::Paid { at: UNIX_EPOCH }]),
+            Err(OrderError::InvalidOrder)
+        ));
+    }
+
+    #[test]
+    fn partial_then_full_refund_marks_refunded() {
+        let mut order = paid_order(1000);
+
+        order.record_refund(Money(400), UNIX_EPOCH).unwrap();
+        assert_eq!(order.status, OrderStatus::Paid);
+
+        order.record_refund(Money(600), UNIX_EPOCH).unwrap();
+        assert_eq!(order.status, OrderStatus::Refunded);
+        assert_eq!(order.refunded, Money(1000));
+    }
+
+    #[test]
+    fn refund_more_than_paid_fails() {
+        let mut order = paid_order(1000);
+
+        assert!(matches!(
+            order.record_refund(Money(1001), UNIX_EPOCH),
+            Err(OrderError::InvalidRefund)
+        ));
+        assert!(matches!(
+            order.record_refund(Money(0), UNIX_EPOCH),
+            Err(OrderError::InvalidRefund)
+        ));
     }
 
     #[test]
@@ -276,6 +3484,52 @@ mod tests {
         assert!(matches!(result, Err(OrderError::InvalidOrder)));
     }
 
+    #[test]
+    fn order_new_overflowing_total_fails() {
+        let item = LineItem {
+            name: "Yacht".to_string(),
+            price: Money(u32::MAX),
+            weight_grams: None,
+        };
+
+        let result = Order::new(OrderId(1), vec![item.clone(), item]);
+
+        assert!(matches!(result, Err(OrderError::InvalidOrder)));
+    }
+
+    #[test]
+    fn order_receipt_lists_items_and_total() {
+        let mut order = Order::new(
+            OrderId(3),
+            vec![
+                LineItem {
+                    name: "Book".to_string(),
+                    price: Money(4999),
+                    weight_grams: None,
+                },
+                LineItem {
+                    name: "Pen".to_string(),
+                    price: Money(199),
+                    weight_grams: None,
+                },
+            ],
+        )
+        .unwrap();
+        order.gift_card = Some(GiftCardPayment {
+            card: GiftCardId(1),
+            amount: Money(1000),
+        });
+
+        let receipt = order.render_receipt();
+
+        let lines: Vec<&str> = receipt.lines().collect();
+        assert_eq!(lines[0], "Order #OrderId(3)");
+        assert!(lines[2].starts_with("  Book") && lines[2].ends_with("$49.99"));
+        assert!(lines[3].starts_with("  Pen") && lines[3].ends_with("$1.99"));
+        assert!(lines[5].starts_with("  Total") && lines[5].ends_with("$51.98"));
+        assert!(lines[7].starts_with("  Card") && lines[7].ends_with("$41.98"));
+    }
+
     #[test]
     fn money_display_formats_correctly() {
         assert_eq!(Money(4999).to_string(), "$49.99");
@@ -283,10 +3537,732 @@ mod tests {
         assert_eq!(Money(5).to_string(), "$0.05");
     }
 
+    // Built by the compiler: if any of these were wrong, the crate wouldn't build
+    const BOOK: Money = money!(49, 99);
+    const SHIPPING: Money = Money::const_new(4, 50);
+    const FREE: Money = money!(0);
+    const PARCEL: Option<Money> = BOOK.checked_add(SHIPPING);
+    const TWO_BOOKS: Option<Money> = BOOK.checked_mul(2);
+    const LARGEST: Option<Money> = Money::from_dollars_and_cents(42_949_672, 95);
+    const SECOND_ORDER: Option<OrderId> = OrderId(1).next();
+
+    #[test]
+    fn money_constants_are_built_at_compile_time() {
+        assert_eq!(BOOK, Money(4999));
+        assert_eq!(SHIPPING, Money(450));
+        assert_eq!(FREE, Money::ZERO);
+        assert_eq!(money!(12), Money(1200));
+        assert_eq!(PARCEL, Some(Money(5449)));
+        assert_eq!(TWO_BOOKS, Some(Money(9998)));
+        assert_eq!(LARGEST, Some(Money(u32::MAX)));
+        assert_eq!(SECOND_ORDER, Some(OrderId(2)));
+    }
+
+    #[test]
+    fn money_arithmetic_refuses_to_overflow() {
+        assert_eq!(Money::from_dollars_and_cents(1, 100), None);
+        assert_eq!(Money::from_dollars_and_cents(42_949_672, 96), None);
+        assert_eq!(Money(u32::MAX).checked_add(Money(1)), None);
+        assert_eq!(Money(u32::MAX).checked_mul(2), None);
+        assert_eq!(SHIPPING.checked_sub(BOOK), None);
+        assert_eq!(BOOK.checked_sub(SHIPPING), Some(Money(4549)));
+        assert_eq!(SHIPPING.saturating_sub(BOOK), Money::ZERO);
+        assert_eq!(OrderId(u32::MAX).next(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "cents must be 0-99")]
+    fn money_outside_a_constant_panics_on_invalid_cents() {
+        let cents = 100;
+        let _ = Money::const_new(1, cents);
+    }
+
+    #[test]
+    fn money_parses_what_display_writes() {
+        for (text, cents) in [
+            ("$49.99", 4999),
+            ("49.99", 4999),
+            ("1.5", 150),
+            ("12", 1200),
+            ("0.05", 5),
+        ] {
+            assert_eq!(text.parse::<Money>(), Ok(Money(cents)), "{text}");
+        }
+        for cents in [0, 5, 199, 4999] {
+            assert_eq!(Money(cents).to_string().parse::<Money>(), Ok(Money(cents)));
+        }
+    }
+
+    #[test]
+    fn money_refuses_what_isnt_an_amount() {
+        for text in [
+            "",
+            "$",
+            "abc",
+            "-1.00",
+            "1.999",
+            "1.",
+            ".5",
+            "1,50",
+            "99999999.00",
+            "42949672.96",
+            "\u{663}",
+            "\u{ff11}\u{ff12}.00",
+        ] {
+            assert_eq!(
+                text.parse::<Money>(),
+                Err(ParseMoneyError(text.to_string())),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn order_ids_parse_from_ascii_digits_only() {
+        assert_eq!("42".parse(), Ok(OrderId(42)));
+        assert_eq!(" 7 ".parse(), Ok(OrderId(7)));
+        assert_eq!("4294967295".parse(), Ok(OrderId(u32::MAX)));
+        for text in [
+            "",
+            "-1",
+            "+1",
+            "4294967296",
+            "\u{663}",
+            "\u{ff11}\u{ff12}",
+            "1.0",
+        ] {
+            assert_eq!(
+                text.parse::<OrderId>(),
+                Err(ParseOrderIdError(text.to_string())),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn line_items_parse_from_name_and_price() {
+        let item = |name: &str, cents| LineItem {
+            name: name.to_string(),
+            price: Money(cents),
+            weight_grams: None,
+        };
+
+        assert_eq!("Book:49.99".parse(), Ok(item("Book", 4999)));
+        assert_eq!(
+            "Cable 2m:USB-C:9.99".parse(),
+            Ok(item("Cable 2m:USB-C", 999))
+        );
+        assert_eq!(
+            "Book".parse::<LineItem>(),
+            Err(ParseLineItemError::MissingPrice("Book".to_string()))
+        );
+        assert_eq!(
+            "Book:$.99".parse::<LineItem>(),
+            Err(ParseLineItemError::InvalidPrice(
+                "Book:$.99".to_string(),
+                ParseMoneyError("$.99".to_string())
+            ))
+        );
+        // The name is checked like any other client's
+        assert_eq!(" Blue\tMug :4.99".parse(), Ok(item("Blue Mug", 499)));
+        assert_eq!(
+            " \n:4.99".parse::<LineItem>(),
+            Err(ParseLineItemError::InvalidName(" \n:4.99".to_string()))
+        );
+        let long = format!("{}:1.00", "x".repeat(MAX_ITEM_NAME_CHARS + 1));
+        assert_eq!(
+            long.parse::<LineItem>(),
+            Err(ParseLineItemError::InvalidName(long.clone()))
+        );
+    }
+
+    #[test]
+    fn item_names_are_trimmed_and_lose_their_control_characters() {
+        let name = |name: &str| LineItem::new(name, Money(100)).ok().map(|item| item.name);
+
+        assert_eq!(name("  Mug\t").as_deref(), Some("Mug"));
+        assert_eq!(name("Blue \r\n\x1b Mug").as_deref(), Some("Blue Mug"));
+        assert_eq!(name("Two\nlines\nhere").as_deref(), Some("Two lines here"));
+        // Spaces between words are the customer's: only control characters go
+        assert_eq!(name("Mug  XL").as_deref(), Some("Mug  XL"));
+        for blank in ["", "   ", "\n\t\r\x07\x1b"] {
+            assert!(matches!(
+                LineItem::new(blank, Money(100)),
+                Err(OrderError::InvalidLineItem)
+            ));
+            assert!(matches!(
+                LineItem::new_lossy(blank, Money(100)),
+                Err(OrderError::InvalidLineItem)
+            ));
+        }
+    }
+
+    #[test]
+    fn item_names_are_limited_in_characters_not_bytes() {
+        // 200 characters, 400 bytes
+        let longest = "é".repeat(MAX_ITEM_NAME_CHARS);
+        let too_long = format!("{longest}é");
+
+        assert_eq!(LineItem::new(&longest, Money(100)).unwrap().name, longest);
+        assert!(matches!(
+            LineItem::new(&too_long, Money(100)),
+            Err(OrderError::InvalidLineItem)
+        ));
+        assert_eq!(
+            LineItem::new_lossy(&too_long, Money(100)).unwrap().name,
+            longest
+        );
+        // Sanitized first: the trimmed spaces don't count
+        assert!(LineItem::new(&format!("  {longest}  "), Money(100)).is_ok());
+
+        assert!(LineItem::new_with_limit("Mug", Money(100), 3).is_ok());
+        assert!(matches!(
+            LineItem::new_with_limit("Mugs", Money(100), 3),
+            Err(OrderError::InvalidLineItem)
+        ));
+    }
+
+    #[test]
+    fn error_codes_ignore_the_data() {
+        assert_eq!(
+            OrderError::OrderNotFound(OrderId(1)).code(),
+            "order_not_found"
+        );
+        assert_eq!(
+            OrderError::OrderNotFound(OrderId(2)).code(),
+            OrderError::OrderNotFound(OrderId(1)).code()
+        );
+        assert_eq!(
+            OrderError::PaymentFailed(PaymentFailureReason::Declined).code(),
+            "payment_failed"
+        );
+    }
+
     #[test]
     fn order_id_display_formats_correctly() {
         assert_eq!(OrderId(42).to_string(), "OrderId(42)");
     }
+
+    #[test]
+    fn recipient_with_valid_email_succeeds() {
+        let recipient =
+            Recipient::new("Alice", Contact::Email("alice@example.com".to_string())).unwrap();
+
+        assert_eq!(recipient.name(), "Alice");
+        assert_eq!(recipient.to_string(), "Alice <alice@example.com>");
+    }
+
+    #[test]
+    fn recipient_with_invalid_email_fails() {
+        for address in [
+            "",
+            "alice",
+            "alice@",
+            "@example.com",
+            "alice@example",
+            "a b@c.com",
+        ] {
+            let result = Recipient::new("Alice", Contact::Email(address.to_string()));
+
+            assert!(
+                matches!(result, Err(OrderError::InvalidRecipient)),
+                "{address:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn recipient_phone_and_webhook_validation() {
+        assert!(Contact::Phone("+33612345678".to_string()).is_valid());
+        assert!(!Contact::Phone("06-12-34".to_string()).is_valid());
+        assert!(Contact::Webhook("https://hooks.example.com/orders".to_string()).is_valid());
+        assert!(!Contact::Webhook("ftp://example.com".to_string()).is_valid());
+        assert!(!Contact::Webhook("https://".to_string()).is_valid());
+    }
+
+    #[test]
+    fn recipient_with_blank_name_fails() {
+        let result = Recipient::new("  ", Contact::Email("alice@example.com".to_string()));
+
+        assert!(matches!(result, Err(OrderError::InvalidRecipient)));
+    }
+
+    #[test]
+    fn address_needs_every_part_and_a_two_letter_country() {
+        let address = Address::new("1 Main St", "Springfield", "12345", " us ").unwrap();
+        let recipient = Recipient::new("Alice", Contact::Email("alice@example.com".to_string()))
+            .unwrap()
+            .with_address(address.clone());
+
+        assert_eq!(address.country(), "US");
+        assert_eq!(address.to_string(), "1 Main St, 12345 Springfield, US");
+        assert_eq!(recipient.address(), Some(&address));
+        for (street, city, postal_code, country) in [
+            ("", "Springfield", "12345", "US"),
+            ("1 Main St", " ", "12345", "US"),
+            ("1 Main St", "Springfield", "", "US"),
+            ("1 Main St", "Springfield", "12345", "USA"),
+            ("1 Main St", "Springfield", "12345", "U1"),
+        ] {
+            assert!(matches!(
+                Address::new(street, city, postal_code, country),
+                Err(OrderError::InvalidRecipient)
+            ));
+        }
+    }
+
+    #[test]
+    fn customer_with_valid_email_succeeds() {
+        let customer = Customer::new(CustomerId(1), "Alice", "alice@example.com").unwrap();
+
+        assert_eq!(
+            customer.recipient().to_string(),
+            "Alice <alice@example.com>"
+        );
+    }
+
+    #[test]
+    fn customer_with_invalid_email_fails() {
+        let result = Customer::new(CustomerId(1), "Alice", "not-an-email");
+
+        assert!(matches!(result, Err(OrderError::InvalidCustomer)));
+    }
+
+    #[test]
+    fn only_unavailable_and_timeout_are_transient() {
+        assert!(OrderError::PaymentFailed(PaymentFailureReason::Unavailable).is_transient());
+        assert!(OrderError::PaymentFailed(PaymentFailureReason::Timeout).is_transient());
+        assert!(!OrderError::PaymentFailed(PaymentFailureReason::Declined).is_transient());
+        assert!(!OrderError::StorageFailed(None).is_transient());
+    }
+
+    #[test]
+    fn allocate_spreads_the_left_over_cents_first() {
+        assert_eq!(
+            Money(1000).allocate(3),
+            vec![Money(334), Money(333), Money(333)]
+        );
+        assert_eq!(
+            Money(2).allocate(4),
+            [Money(1), Money(1), Money(0), Money(0)]
+        );
+        assert!(Money(1000).allocate(0).is_empty());
+    }
+
+    /// Quotes only USD to EUR, at 0.91.
+    struct UsdToEur;
+
+    impl ExchangeRates for UsdToEur {
+        fn rate(&self, from: Currency, to: Currency) -> Result<Rate, OrderError> {
+            match (from, to) {
+                (Currency::Usd, Currency::Eur) => Ok(Rate(9100)),
+                _ => Err(OrderError::NoExchangeRate { from, to }),
+            }
+        }
+    }
+
+    #[test]
+    fn convert_rounds_half_up_to_the_cent() {
+        assert_eq!(
+            Money(4999).convert(Currency::Eur, &UsdToEur).unwrap(),
+            Money(4549)
+        );
+        // 0.91 cents
+        assert_eq!(
+            Money(1).convert(Currency::Eur, &UsdToEur).unwrap(),
+            Money(1)
+        );
+        // 4.55 cents, then 4.5409 cents
+        assert_eq!(
+            Money(5).convert(Currency::Eur, &UsdToEur).unwrap(),
+            Money(5)
+        );
+        assert_eq!(
+            Money(499).convert(Currency::Eur, &UsdToEur).unwrap(),
+            Money(454)
+        );
+        assert_eq!(
+            Money::ZERO.convert(Currency::Eur, &UsdToEur).unwrap(),
+            Money::ZERO
+        );
+    }
+
+    #[test]
+    fn convert_to_the_same_currency_needs_no_rate() {
+        assert_eq!(
+            Money(4999).convert(Money::CURRENCY, &UsdToEur).unwrap(),
+            Money(4999)
+        );
+        assert!(matches!(
+            Money(4999).convert(Currency::Gbp, &UsdToEur),
+            Err(OrderError::NoExchangeRate {
+                from: Currency::Usd,
+                to: Currency::Gbp
+            })
+        ));
+        assert_eq!(Rate(9100).to_string(), "0.9100");
+        assert_eq!(Rate::ONE.to_string(), "1.0000");
+    }
+
+    fn customer_order(id: u32, customer: u32, prices: &[u32]) -> Order {
+        let items = prices
+            .iter()
+            .map(|&price| LineItem {
+                name: format!("Item {price}"),
+                price: Money(price),
+                weight_grams: None,
+            })
+            .collect();
+        let mut order = Order::new(OrderId(id), items).unwrap();
+        order.customer_id = Some(CustomerId(customer));
+        order
+    }
+
+    #[test]
+    fn same_contents_ignores_only_the_version() {
+        let order = customer_order(1, 7, &[100]);
+        let mut rewritten = order.clone();
+        rewritten.version += 3;
+        let mut paid = order.clone();
+        paid.status = OrderStatus::Paid;
+
+        assert!(order.same_contents(&rewritten));
+        assert!(!order.same_contents(&paid));
+        assert!(!order.same_contents(&customer_order(1, 8, &[100])));
+    }
+
+    #[test]
+    fn merge_keeps_the_first_id_and_records_the_other() {
+        let mut second = customer_order(2, 7, &[300]);
+        second.merged_from.push(OrderId(3));
+
+        let merged = customer_order(1, 7, &[100, 200]).merge(second).unwrap();
+
+        assert_eq!(merged.id, OrderId(1));
+        assert_eq!(merged.total, Money(600));
+        let prices: Vec<_> = merged.items.iter().map(|item| item.price.0).collect();
+        assert_eq!(prices, [100, 200, 300]);
+        assert_eq!(merged.merged_from, [OrderId(2), OrderId(3)]);
+    }
+
+    #[test]
+    fn merge_refuses_orders_of_different_customers_or_statuses() {
+        let merge = |a: Order, b: Order| a.merge(b);
+
+        assert!(matches!(
+            merge(customer_order(1, 7, &[100]), customer_order(2, 8, &[100])),
+            Err(OrderError::InvalidOrder)
+        ));
+        let mut anonymous = customer_order(2, 7, &[100]);
+        anonymous.customer_id = None;
+        assert!(matches!(
+            merge(customer_order(1, 7, &[100]), anonymous),
+            Err(OrderError::InvalidOrder)
+        ));
+        let mut paid = customer_order(2, 7, &[100]);
+        paid.mark_paid(UNIX_EPOCH).unwrap();
+        assert!(matches!(
+            merge(customer_order(1, 7, &[100]), paid),
+            Err(OrderError::InvalidStatus(OrderStatus::Paid))
+        ));
+        assert!(matches!(
+            merge(
+                customer_order(1, 7, &[u32::MAX]),
+                customer_order(2, 7, &[1])
+            ),
+            Err(OrderError::InvalidOrder)
+        ));
+    }
+
+    #[test]
+    fn split_moves_the_chosen_items_to_a_new_order() {
+        let (kept, split_off) = customer_order(1, 7, &[100, 200, 300, 400])
+            .split(&[3, 1])
+            .unwrap();
+
+        let prices =
+            |order: &Order| -> Vec<u32> { order.items.iter().map(|i| i.price.0).collect() };
+        assert_eq!(prices(&kept), [100, 300]);
+        assert_eq!(kept.total, Money(400));
+        assert_eq!(prices(&split_off), [200, 400]);
+        assert_eq!(split_off.total, Money(600));
+        assert_eq!(split_off.customer_id, Some(CustomerId(7)));
+    }
+
+    fn shipping(cost: u32) -> Option<Shipping> {
+        Some(Shipping {
+            destination: Address::new("1 Main St", "Springfield", "12345", "US").unwrap(),
+            cost: Money(cost),
+        })
+    }
+
+    #[test]
+    fn shipping_is_part_of_the_total_and_earns_no_points() {
+        let mut order = customer_order(1, 7, &[5000]);
+        order.set_shipping(shipping(899)).unwrap();
+        assert_eq!(order.total, Money(5899));
+        order.set_shipping(shipping(499)).unwrap();
+        assert_eq!(order.total, Money(5499));
+        order.mark_paid(UNIX_EPOCH).unwrap();
+
+        assert_eq!(order.loyalty_points(), 50);
+        // The shipping goes back first, as far as points go
+        order.record_refund(Money(499), UNIX_EPOCH).unwrap();
+        assert_eq!(order.loyalty_points(), 50);
+        order.record_refund(Money(1000), UNIX_EPOCH).unwrap();
+        assert_eq!(order.loyalty_points(), 40);
+        order.set_shipping(shipping(0)).unwrap();
+        assert_eq!(order.total, Money(5000));
+
+        // Not below what was refunded already
+        let mut mostly_shipping = customer_order(2, 7, &[100]);
+        mostly_shipping.set_shipping(shipping(899)).unwrap();
+        mostly_shipping.mark_paid(UNIX_EPOCH).unwrap();
+        mostly_shipping
+            .record_refund(Money(500), UNIX_EPOCH)
+            .unwrap();
+        assert!(matches!(
+            mostly_shipping.set_shipping(None),
+            Err(OrderError::InvalidOrder)
+        ));
+    }
+
+    #[test]
+    fn split_and_merge_ship_once_per_order() {
+        let mut order = customer_order(1, 7, &[100, 200]);
+        order.set_shipping(shipping(50)).unwrap();
+
+        let (kept, mut split_off) = order.split(&[1]).unwrap();
+        split_off.id = OrderId(2);
+        // Both ship where the order did, at its old cost until repriced
+        assert_eq!((kept.total, split_off.total), (Money(150), Money(250)));
+        assert_eq!(kept.shipping, split_off.shipping);
+
+        let merged = kept.merge(split_off).unwrap();
+        assert_eq!(merged.total, Money(350));
+        assert_eq!(merged.shipping, shipping(50));
+        // An order that doesn't ship takes the other's shipping
+        let mut shipped = customer_order(2, 7, &[200]);
+        shipped.set_shipping(shipping(40)).unwrap();
+        let merged = customer_order(1, 7, &[100]).merge(shipped).unwrap();
+        assert_eq!(merged.total, Money(340));
+        assert_eq!(merged.shipping, shipping(40));
+    }
+
+    #[test]
+    fn split_leaves_items_on_both_sides() {
+        let split = |indices: &[usize]| customer_order(1, 7, &[100, 200]).split(indices);
+
+        assert!(matches!(split(&[0, 1]), Err(OrderError::InvalidOrder)));
+        assert!(matches!(split(&[]), Err(OrderError::InvalidOrder)));
+        assert!(matches!(split(&[2]), Err(OrderError::InvalidOrder)));
+        assert!(matches!(split(&[0, 0]), Err(OrderError::InvalidOrder)));
+        assert!(split(&[0]).is_ok());
+    }
+
+    #[test]
+    fn remove_item_keeps_at_least_one_item() {
+        let mut order = Order::new(
+            OrderId(1),
+            vec![
+                LineItem {
+                    name: "Book".to_string(),
+                    price: Money(4999),
+                    weight_grams: None,
+                },
+                LineItem {
+                    name: "Pen".to_string(),
+                    price: Money(199),
+                    weight_grams: None,
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(order.remove_item(0).unwrap(), Money(4999));
+        assert_eq!(order.total, Money(199));
+        assert!(matches!(
+            order.remove_item(0),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert!(matches!(
+            order.remove_item(5),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert_eq!(order.items.len(), 1);
+    }
+
+    #[test]
+    fn remove_item_never_drops_below_the_refunds() {
+        let mut order = Order::new(
+            OrderId(1),
+            vec![
+                LineItem {
+                    name: "Book".to_string(),
+                    price: Money(4999),
+                    weight_grams: None,
+                },
+                LineItem {
+                    name: "Pen".to_string(),
+                    price: Money(199),
+                    weight_grams: None,
+                },
+            ],
+        )
+        .unwrap();
+        order.mark_paid(UNIX_EPOCH).unwrap();
+        order.record_refund(Money(1000), UNIX_EPOCH).unwrap();
+
+        assert!(matches!(
+            order.remove_item(0),
+            Err(OrderError::InvalidOrder)
+        ));
+        assert_eq!(order.remove_item(1).unwrap(), Money(199));
+        assert_eq!(order.total, Money(4999));
+    }
+
+    #[test]
+    fn duplicate_order_display_includes_id() {
+        assert_eq!(
+            OrderError::DuplicateOrder(OrderId(7)).to_string(),
+            "DuplicateOrder(OrderId(7))"
+        );
+    }
+}
+
+// =============================================================================
+// Properties
+// =============================================================================
+//
+// The tests above check examples. These check the invariants themselves,
+// against random values from strategies.rs. On failure, proptest prints the
+// smallest input it could shrink the failure down to.
+
+#[cfg(test)]
+mod properties {
+    use super::*;
+    use crate::strategies::{line_item, line_items, money};
+    use proptest::prelude::*;
+
+    fn sum(items: &[LineItem]) -> u64 {
+        items.iter().map(|item| u64::from(item.price.0)).sum()
+    }
+
+    /// A change to an order's items. The index is reduced modulo the number
+    /// of items, so every removal targets an item that exists.
+    #[derive(Debug, Clone)]
+    enum Change {
+        Add(LineItem),
+        Remove(usize),
+    }
+
+    fn change() -> impl Strategy<Value = Change> {
+        prop_oneof![
+            line_item().prop_map(Change::Add),
+            any::<usize>().prop_map(Change::Remove),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn order_total_is_the_sum_of_its_items(items in line_items()) {
+            let order = Order::new(OrderId(1), items.clone()).unwrap();
+
+            prop_assert_eq!(u64::from(order.total.0), sum(&items));
+            prop_assert_eq!(order.items, items);
+        }
+
+        #[test]
+        fn an_order_without_items_is_refused(id in any::<u32>()) {
+            let refused = Order::new(OrderId(id), Vec::new());
+
+            prop_assert!(matches!(refused, Err(OrderError::InvalidOrder)));
+        }
+
+        #[test]
+        fn money_parses_back_from_its_display(amount in money()) {
+            let shown = amount.to_string();
+
+            prop_assert_eq!(shown.parse::<Money>(), Ok(amount));
+            prop_assert_eq!(shown.trim_start_matches('$').parse::<Money>(), Ok(amount));
+        }
+
+        // The fuzz targets (fuzz/) check the same two things for much longer:
+        // parsing any text is an Ok or an error, never a panic, and an Ok
+        // writes back as the same value
+        #[test]
+        fn parsing_any_text_never_panics(text in any::<String>()) {
+            if let Ok(amount) = text.parse::<Money>() {
+                prop_assert_eq!(amount.to_string().parse::<Money>(), Ok(amount));
+            }
+            if let Ok(id) = text.parse::<OrderId>() {
+                prop_assert_eq!(id.0.to_string().parse::<OrderId>(), Ok(id));
+            }
+            if let Ok(item) = text.parse::<LineItem>() {
+                let written = format!("{}:{}", item.name, item.price);
+                prop_assert_eq!(written.parse::<LineItem>(), Ok(item));
+            }
+        }
+
+        #[test]
+        fn parsing_amount_like_text_never_panics(text in r"\$?[0-9]{0,12}(\.[0-9]{0,3})?") {
+            if let Ok(amount) = text.parse::<Money>() {
+                prop_assert_eq!(amount.to_string().parse::<Money>(), Ok(amount));
+            }
+        }
+
+        #[test]
+        fn order_ids_parse_back_from_their_number(id in any::<u32>()) {
+            prop_assert_eq!(id.to_string().parse::<OrderId>(), Ok(OrderId(id)));
+        }
+
+        #[test]
+        fn line_items_parse_back_from_name_and_price(item in line_item()) {
+            let written = format!("{}:{}", item.name, item.price);
+
+            prop_assert_eq!(written.parse::<LineItem>(), Ok(item));
+        }
+
+        #[test]
+        fn allocated_parts_add_up_to_the_whole(amount in money(), parts in 1..=100u32) {
+            let allocated = amount.allocate(parts);
+
+            prop_assert_eq!(allocated.len(), parts as usize);
+            let total: u64 = allocated.iter().map(|part| u64::from(part.0)).sum();
+            prop_assert_eq!(total, u64::from(amount.0));
+            let largest = allocated.iter().map(|part| part.0).max().unwrap();
+            let smallest = allocated.iter().map(|part| part.0).min().unwrap();
+            prop_assert!(largest - smallest <= 1);
+        }
+
+        #[test]
+        fn adding_and_removing_items_keeps_the_total_right(
+            items in line_items(),
+            changes in prop::collection::vec(change(), 0..20),
+        ) {
+            let mut order = Order::new(OrderId(1), items).unwrap();
+
+            for change in changes {
+                let before = order.total.0;
+                match change {
+                    Change::Add(item) => {
+                        let added = order.add_item(item).unwrap();
+                        prop_assert_eq!(order.total.0, before + added.0);
+                    }
+                    Change::Remove(index) => {
+                        let index = index % order.items.len();
+                        match order.remove_item(index) {
+                            Ok(removed) => prop_assert_eq!(order.total.0, before - removed.0),
+                            // Only the last item can't go
+                            Err(_) => prop_assert_eq!(order.items.len(), 1),
+                        }
+                    }
+                }
+                prop_assert!(!order.items.is_empty());
+                prop_assert_eq!(u64::from(order.total.0), sum(&order.items));
+            }
+        }
+    }
 }
 
 // =============================================================================
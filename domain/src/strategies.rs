@@ -0,0 +1,82 @@
+// =============================================================================
+// Proptest Strategies - Random Domain Values, Shrinking to Small Ones
+// =============================================================================
+//
+// An example-based test checks the orders someone thought of. A property
+// test checks a rule ("the total is the sum of the items") against hundreds
+// of random orders, and when one breaks the rule, proptest shrinks it to
+// the smallest order that still does: one item, priced $0.01, named "a".
+//
+// These strategies are built from ranges and `vec`, never filters, so
+// shrinking always works: prices shrink towards $0.00, lists towards one
+// item, names towards "a".
+//
+// Other crates use them through the `proptest` feature, as a dev-dependency:
+//
+//     [dev-dependencies]
+//     domain = { path = "../domain", features = ["proptest"] }
+//
+//     proptest! {
+//         #[test]
+//         fn quotes_match_orders(items in domain::strategies::line_items()) { ... }
+//     }
+//
+// `any::<Money>()` and `any::<LineItem>()` work too (see the Arbitrary impls
+// at the bottom).
+
+use crate::{LineItem, Money};
+use proptest::prelude::*;
+
+/// The highest price [`price`] gives: $10,000.00.
+///
+/// Low enough that [`MAX_ITEMS`] items, and as many added later, never
+/// overflow an order's total.
+pub const MAX_PRICE: u32 = 1_000_000;
+
+/// The most items [`line_items`] gives.
+pub const MAX_ITEMS: usize = 20;
+
+/// Any amount of [`Money`], from $0.00 to the largest one.
+pub fn money() -> impl Strategy<Value = Money> {
+    any::<u32>().prop_map(Money)
+}
+
+/// A price a line item could have, from $0.00 to [`MAX_PRICE`].
+pub fn price() -> impl Strategy<Value = Money> {
+    (0..=MAX_PRICE).prop_map(Money)
+}
+
+/// A line item with a short name and a [`price`].
+///
+/// The name starts and ends with a letter: [`LineItem::new`] would trim
+/// anything else, and parsing the item back wouldn't give the same one.
+pub fn line_item() -> impl Strategy<Value = LineItem> {
+    ("[a-z]([a-z ]{0,14}[a-z])?", price()).prop_map(|(name, price)| LineItem {
+        name,
+        price,
+        weight_grams: None,
+    })
+}
+
+/// Between one and [`MAX_ITEMS`] line items: enough for an order.
+pub fn line_items() -> impl Strategy<Value = Vec<LineItem>> {
+    prop::collection::vec(line_item(), 1..=MAX_ITEMS)
+}
+
+impl Arbitrary for Money {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        money().boxed()
+    }
+}
+
+impl Arbitrary for LineItem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        line_item().boxed()
+    }
+}
@@ -0,0 +1,11 @@
+// Money constants are checked by the compiler: these must NOT build.
+//
+// Each file in tests/ui/ is compiled on its own, and its error is compared
+// with the .stderr file next to it. After a compiler upgrade changes the
+// wording, refresh them with `TRYBUILD=overwrite cargo test -p domain --test compile_fail`.
+
+#[test]
+fn invalid_money_constants_dont_compile() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,8 @@
+use domain::{Money, money};
+
+// 100 cents is a dollar: it's written money!(50, 0)
+const PRICE: Money = money!(49, 100);
+
+fn main() {
+    println!("{PRICE}");
+}
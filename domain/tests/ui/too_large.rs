@@ -0,0 +1,8 @@
+use domain::Money;
+
+// One cent more than u32::MAX cents
+const PRICE: Money = Money::const_new(42_949_672, 96);
+
+fn main() {
+    println!("{PRICE}");
+}
@@ -0,0 +1,23 @@
+// Any text parses to a line item or a ParseLineItemError: no panic, no
+// overflow. An item written back as NAME:PRICE parses to the same item.
+
+#![no_main]
+
+use domain::{LineItem, ParseLineItemError};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    match text.parse::<LineItem>() {
+        Ok(item) => {
+            let written = format!("{}:{}", item.name, item.price);
+            assert_eq!(written.parse(), Ok(item));
+        }
+        Err(ParseLineItemError::MissingPrice(refused)) => {
+            assert_eq!(refused, text);
+            assert!(!text.contains(':'));
+        }
+        Err(
+            ParseLineItemError::InvalidPrice(refused, _) | ParseLineItemError::InvalidName(refused),
+        ) => assert_eq!(refused, text),
+    }
+});
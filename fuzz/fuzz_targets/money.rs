@@ -0,0 +1,14 @@
+// Any text parses to an amount or a ParseMoneyError: no panic, no overflow.
+// An amount writes back as itself.
+
+#![no_main]
+
+use domain::{Money, ParseMoneyError};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    match text.parse::<Money>() {
+        Ok(amount) => assert_eq!(amount.to_string().parse(), Ok(amount)),
+        Err(ParseMoneyError(refused)) => assert_eq!(refused, text),
+    }
+});
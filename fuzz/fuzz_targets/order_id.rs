@@ -0,0 +1,14 @@
+// Any text parses to an order ID or a ParseOrderIdError: no panic, no
+// overflow. An ID's number parses back to the same ID.
+
+#![no_main]
+
+use domain::{OrderId, ParseOrderIdError};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    match text.parse::<OrderId>() {
+        Ok(id) => assert_eq!(id.0.to_string().parse(), Ok(id)),
+        Err(ParseOrderIdError(refused)) => assert_eq!(refused, text),
+    }
+});
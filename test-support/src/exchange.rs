@@ -0,0 +1,77 @@
+// =============================================================================
+// Exchange Rate Double - One Rate for Every Pair
+// =============================================================================
+//
+// FixedRateForTests quotes the same rate whatever the currencies, so a test
+// picks the rate its arithmetic needs, and keeps every pair it was asked
+// for:
+//
+//     let rates = FixedRateForTests::new(Rate(9100));
+//     assert_eq!(Money(1).convert(Currency::Eur, &rates)?, Money(1));
+//     assert_eq!(rates.asked(), [(Currency::Usd, Currency::Eur)]);
+
+use domain::{Currency, ExchangeRates, OrderError, Rate};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Exchange rates quoting one rate for every pair of currencies.
+#[derive(Debug)]
+pub struct FixedRateForTests {
+    rate: Rate,
+    asked: Mutex<Vec<(Currency, Currency)>>,
+}
+
+impl FixedRateForTests {
+    /// Creates rates quoting `rate` for every pair.
+    #[must_use]
+    pub const fn new(rate: Rate) -> Self {
+        Self {
+            rate,
+            asked: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Every `(from, to)` pair asked for, oldest first.
+    #[must_use]
+    pub fn asked(&self) -> Vec<(Currency, Currency)> {
+        self.guard().clone()
+    }
+
+    fn guard(&self) -> MutexGuard<'_, Vec<(Currency, Currency)>> {
+        // A poisoned lock only means another thread panicked; the pairs are still fine
+        self.asked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ExchangeRates for FixedRateForTests {
+    fn rate(&self, from: Currency, to: Currency) -> Result<Rate, OrderError> {
+        self.guard().push((from, to));
+        Ok(self.rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_pair_gets_the_rate_and_is_kept() {
+        let rates = FixedRateForTests::new(Rate(9100));
+
+        assert_eq!(
+            rates.rate(Currency::Usd, Currency::Eur).unwrap(),
+            Rate(9100)
+        );
+        assert_eq!(
+            rates.rate(Currency::Gbp, Currency::Chf).unwrap(),
+            Rate(9100)
+        );
+
+        assert_eq!(
+            rates.asked(),
+            [
+                (Currency::Usd, Currency::Eur),
+                (Currency::Gbp, Currency::Chf)
+            ]
+        );
+    }
+}
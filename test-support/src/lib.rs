@@ -0,0 +1,52 @@
+// =============================================================================
+// TEST-SUPPORT CRATE - Test Doubles
+// =============================================================================
+//
+// Everything a test needs besides the code under test, in one place instead
+// of a copy in every crate's `mod tests`:
+//
+// Test doubles, one per port:
+// - MockRepository: keeps orders in memory (`with_failing_updates` for a
+//   storage that breaks after the payment)
+// - FailingRepository: fails every call with StorageFailed
+// - MockPayment: approves everything (`with_failing_refunds` to decline
+//   refunds)
+// - FailingPayment: fails every call, with the reason given
+// - MockSender: delivers nothing, successfully
+// - FixedRateForTests: quotes one exchange rate for every pair
+// - FixedClock and SequentialIdGenerator: re-exported from
+//   adapters-repository, where they already live as adapters of their own
+//
+// Made-up items and orders come from adapters-repository (`generate_items`,
+// `seed_items`, `seed_recipient`), the one generator of the workspace.
+//
+// COUNTERS AND RECORDERS:
+// -----------------------
+// Every double counts its calls (`counters()`) and keeps the arguments each
+// call got (`inserted()`, `calls()`, `sent()`...), so a test can say both
+// "one refund" and "the refund of txn_test, for $10.00".
+//
+// DOUBLES OR ADAPTERS?
+// --------------------
+// adapters-payment's MockPaymentGateway and adapters-notification's
+// ConsoleSender are adapters: the demo runs on them. The doubles here only
+// exist to be asserted on, and never print anything. They implement the port
+// traits (their required methods; the defaults do the rest) and nothing
+// else, so they stay valid for any crate's `#[cfg(test)]` code.
+//
+// Their state sits behind a Mutex: they are Send and Sync, and a test may
+// share them between threads.
+//
+// Use it as a dev-dependency. The application can too: the doubles only
+// know the domain, so they work in its own unit tests.
+
+mod exchange;
+mod payment;
+mod repository;
+mod sender;
+
+pub use adapters_repository::{FixedClock, SequentialIdGenerator};
+pub use exchange::FixedRateForTests;
+pub use payment::{FailingPayment, MockPayment, PaymentCall, PaymentCounters};
+pub use repository::{FailingRepository, MockRepository, RepositoryCounters};
+pub use sender::MockSender;
@@ -0,0 +1,350 @@
+// =============================================================================
+// Payment Doubles - Approve Everything, or Nothing
+// =============================================================================
+//
+// MockPayment approves every call, and keeps each one with its arguments:
+//
+//     let payment = MockPayment::new();
+//     service.place_order(&recipient, items)?;
+//     assert_eq!(payment.counters().captures, 1);
+//     assert_eq!(payment.calls()[0], PaymentCall::Authorize { .. });
+//
+// Its IDs never change: every charge is "txn_test", every hold "auth_test".
+// A test can write them down without reading them back first.
+//
+// FailingPayment fails every call with one reason, Declined by default.
+
+use domain::{
+    AuthorizationId, IdempotencyKey, Money, OrderError, PaymentFailureReason, PaymentGateway,
+    PaymentReceipt, PaymentStatus,
+};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+/// A call received by a payment double, with its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentCall {
+    Charge {
+        amount: Money,
+        idempotency_key: Option<IdempotencyKey>,
+    },
+    Authorize {
+        amount: Money,
+        idempotency_key: Option<IdempotencyKey>,
+    },
+    Capture(AuthorizationId),
+    Void(AuthorizationId),
+    Refund {
+        transaction_id: String,
+        amount: Money,
+    },
+}
+
+/// How many times each method of a payment double was called, failed calls
+/// included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaymentCounters {
+    pub charges: u32,
+    pub authorizations: u32,
+    pub captures: u32,
+    pub voids: u32,
+    pub refunds: u32,
+}
+
+impl PaymentCounters {
+    fn of(calls: &[PaymentCall]) -> Self {
+        let mut counters = Self::default();
+        for call in calls {
+            let counter = match call {
+                PaymentCall::Charge { .. } => &mut counters.charges,
+                PaymentCall::Authorize { .. } => &mut counters.authorizations,
+                PaymentCall::Capture(_) => &mut counters.captures,
+                PaymentCall::Void(_) => &mut counters.voids,
+                PaymentCall::Refund { .. } => &mut counters.refunds,
+            };
+            *counter += 1;
+        }
+        counters
+    }
+}
+
+/// The calls of a double, behind a lock.
+#[derive(Debug, Default)]
+struct Calls(Mutex<Vec<PaymentCall>>);
+
+impl Calls {
+    fn record(&self, call: PaymentCall) {
+        self.guard().push(call);
+    }
+
+    fn all(&self) -> Vec<PaymentCall> {
+        self.guard().clone()
+    }
+
+    fn guard(&self) -> MutexGuard<'_, Vec<PaymentCall>> {
+        // A poisoned lock only means another thread panicked; the record is still fine
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Payment gateway approving every call, and recording it.
+#[derive(Debug, Default)]
+pub struct MockPayment {
+    calls: Calls,
+    fail_refunds: bool,
+}
+
+impl MockPayment {
+    /// Creates a gateway with no call yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declines every refund: money that can't be given back.
+    #[must_use]
+    pub const fn with_failing_refunds(mut self) -> Self {
+        self.fail_refunds = true;
+        self
+    }
+
+    /// Every call received so far, oldest first.
+    #[must_use]
+    pub fn calls(&self) -> Vec<PaymentCall> {
+        self.calls.all()
+    }
+
+    /// How many calls of each kind were received so far.
+    #[must_use]
+    pub fn counters(&self) -> PaymentCounters {
+        PaymentCounters::of(&self.calls.guard())
+    }
+
+    /// The `(transaction ID, amount)` of every refund asked for, declined
+    /// ones included.
+    #[must_use]
+    pub fn refunds(&self) -> Vec<(String, Money)> {
+        self.calls
+            .guard()
+            .iter()
+            .filter_map(|call| match call {
+                PaymentCall::Refund {
+                    transaction_id,
+                    amount,
+                } => Some((transaction_id.clone(), *amount)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl PaymentGateway for MockPayment {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.calls.record(PaymentCall::Charge {
+            amount,
+            idempotency_key: idempotency_key.cloned(),
+        });
+        Ok(PaymentReceipt {
+            transaction_id: "txn_test".to_string(),
+            provider: "test".to_string(),
+            amount,
+            timestamp: SystemTime::now(),
+            status: PaymentStatus::Settled,
+        })
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.calls.record(PaymentCall::Authorize {
+            amount,
+            idempotency_key: idempotency_key.cloned(),
+        });
+        Ok(AuthorizationId("auth_test".to_string()))
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.calls.record(PaymentCall::Capture(auth.clone()));
+        Ok(())
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.calls.record(PaymentCall::Void(auth.clone()));
+        Ok(())
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.calls.record(PaymentCall::Refund {
+            transaction_id: transaction_id.to_string(),
+            amount,
+        });
+        if self.fail_refunds {
+            return Err(OrderError::PaymentFailed(PaymentFailureReason::Declined));
+        }
+        Ok(())
+    }
+}
+
+/// Payment gateway failing every call with the same reason, and recording
+/// it.
+#[derive(Debug)]
+pub struct FailingPayment {
+    reason: PaymentFailureReason,
+    calls: Calls,
+}
+
+// Manual impl: a card declined is the failure tests want most often
+impl Default for FailingPayment {
+    fn default() -> Self {
+        Self::new(PaymentFailureReason::Declined)
+    }
+}
+
+impl FailingPayment {
+    /// Creates a gateway failing with `reason`.
+    #[must_use]
+    pub fn new(reason: PaymentFailureReason) -> Self {
+        Self {
+            reason,
+            calls: Calls::default(),
+        }
+    }
+
+    /// Every call received (and failed) so far, oldest first.
+    #[must_use]
+    pub fn calls(&self) -> Vec<PaymentCall> {
+        self.calls.all()
+    }
+
+    /// How many calls of each kind were received so far.
+    #[must_use]
+    pub fn counters(&self) -> PaymentCounters {
+        PaymentCounters::of(&self.calls.guard())
+    }
+
+    fn fail<T>(&self, call: PaymentCall) -> Result<T, OrderError> {
+        self.calls.record(call);
+        Err(OrderError::PaymentFailed(self.reason))
+    }
+}
+
+impl PaymentGateway for FailingPayment {
+    fn charge(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<PaymentReceipt, OrderError> {
+        self.fail(PaymentCall::Charge {
+            amount,
+            idempotency_key: idempotency_key.cloned(),
+        })
+    }
+
+    fn authorize(
+        &self,
+        amount: Money,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<AuthorizationId, OrderError> {
+        self.fail(PaymentCall::Authorize {
+            amount,
+            idempotency_key: idempotency_key.cloned(),
+        })
+    }
+
+    fn capture(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.fail(PaymentCall::Capture(auth.clone()))
+    }
+
+    fn void(&self, auth: &AuthorizationId) -> Result<(), OrderError> {
+        self.fail(PaymentCall::Void(auth.clone()))
+    }
+
+    fn refund(&self, transaction_id: &str, amount: Money) -> Result<(), OrderError> {
+        self.fail(PaymentCall::Refund {
+            transaction_id: transaction_id.to_string(),
+            amount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_call_is_recorded_with_its_arguments() {
+        let payment = MockPayment::new();
+        let key = IdempotencyKey("order-1".to_string());
+
+        let auth = payment.authorize(Money(1000), Some(&key)).unwrap();
+        payment.capture(&auth).unwrap();
+        let receipt = payment.charge(Money(250), None).unwrap();
+        payment.refund(&receipt.transaction_id, Money(250)).unwrap();
+
+        assert_eq!(
+            payment.calls(),
+            [
+                PaymentCall::Authorize {
+                    amount: Money(1000),
+                    idempotency_key: Some(key),
+                },
+                PaymentCall::Capture(AuthorizationId("auth_test".to_string())),
+                PaymentCall::Charge {
+                    amount: Money(250),
+                    idempotency_key: None,
+                },
+                PaymentCall::Refund {
+                    transaction_id: "txn_test".to_string(),
+                    amount: Money(250),
+                },
+            ]
+        );
+        assert_eq!(
+            payment.counters(),
+            PaymentCounters {
+                charges: 1,
+                authorizations: 1,
+                captures: 1,
+                voids: 0,
+                refunds: 1,
+            }
+        );
+        assert_eq!(payment.refunds(), [("txn_test".to_string(), Money(250))]);
+    }
+
+    #[test]
+    fn failing_refunds_are_still_recorded() {
+        let payment = MockPayment::new().with_failing_refunds();
+
+        let refunded = payment.refund("txn_test", Money(100));
+
+        assert!(matches!(
+            refunded,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Declined))
+        ));
+        assert_eq!(payment.counters().refunds, 1);
+    }
+
+    #[test]
+    fn the_failing_payment_fails_with_its_reason() {
+        let payment = FailingPayment::new(PaymentFailureReason::Unavailable);
+
+        let charged = payment.charge(Money(100), None);
+        let voided = payment.void(&AuthorizationId("auth_1".to_string()));
+
+        assert!(matches!(
+            charged,
+            Err(OrderError::PaymentFailed(PaymentFailureReason::Unavailable))
+        ));
+        assert!(voided.is_err());
+        assert_eq!(payment.counters().charges, 1);
+        assert_eq!(payment.counters().voids, 1);
+        assert_eq!(payment.calls().len(), 2);
+    }
+}
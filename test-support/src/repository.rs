@@ -0,0 +1,334 @@
+// =============================================================================
+// Repository Doubles - Orders in a Map, Every Call on Record
+// =============================================================================
+//
+// MockRepository is the simplest repository that still behaves like one:
+// duplicate IDs are refused, updating an unknown order fails, each tenant
+// sees its own orders. On top of that it counts its calls and keeps the
+// orders it was given, so a test can check what the use case wrote, not
+// only what ended up stored:
+//
+//     let repo = MockRepository::new();
+//     service.place_order(&recipient, items)?;
+//     assert_eq!(repo.counters().inserts, 1);
+//     assert_eq!(repo.updated()[0].status, OrderStatus::Paid);
+//
+// FailingRepository is storage that's down: every call fails.
+
+use domain::{Order, OrderError, OrderId, OrderRepository, TenantId};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// How many times each method of a repository double was called, failed
+/// calls included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepositoryCounters {
+    pub inserts: u32,
+    pub updates: u32,
+    pub finds: u32,
+    /// Calls to `find_all`.
+    pub lists: u32,
+}
+
+impl RepositoryCounters {
+    /// Every call, whatever the method.
+    #[must_use]
+    pub const fn total(&self) -> u32 {
+        self.inserts + self.updates + self.finds + self.lists
+    }
+}
+
+/// Repository keeping orders in memory, and a record of every call.
+#[derive(Debug, Default)]
+pub struct MockRepository {
+    state: Mutex<RepositoryState>,
+    fail_updates: bool,
+}
+
+#[derive(Debug, Default)]
+struct RepositoryState {
+    orders: BTreeMap<(TenantId, OrderId), Order>,
+    counters: RepositoryCounters,
+    inserted: Vec<Order>,
+    updated: Vec<Order>,
+}
+
+impl MockRepository {
+    /// Creates an empty repository.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails every update with [`OrderError::StorageFailed`], while inserts
+    /// still work: storage breaking after the payment went through.
+    #[must_use]
+    pub const fn with_failing_updates(mut self) -> Self {
+        self.fail_updates = true;
+        self
+    }
+
+    /// How many calls were made so far.
+    #[must_use]
+    pub fn counters(&self) -> RepositoryCounters {
+        self.state().counters
+    }
+
+    /// Every order passed to `insert`, oldest first, refused ones included.
+    #[must_use]
+    pub fn inserted(&self) -> Vec<Order> {
+        self.state().inserted.clone()
+    }
+
+    /// Every order passed to `update`, oldest first, refused ones included.
+    #[must_use]
+    pub fn updated(&self) -> Vec<Order> {
+        self.state().updated.clone()
+    }
+
+    /// The orders stored, of every tenant, by tenant then ID.
+    #[must_use]
+    pub fn orders(&self) -> Vec<Order> {
+        self.state().orders.values().cloned().collect()
+    }
+
+    /// The stored order `id` of the default tenant. Not counted as a call.
+    #[must_use]
+    pub fn order(&self, id: OrderId) -> Option<Order> {
+        self.state().orders.get(&(TenantId::default(), id)).cloned()
+    }
+
+    fn state(&self) -> MutexGuard<'_, RepositoryState> {
+        // A poisoned lock only means another thread panicked; the orders are still fine
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl OrderRepository for MockRepository {
+    fn insert(&self, order: &Order) -> Result<(), OrderError> {
+        let mut state = self.state();
+        state.counters.inserts += 1;
+        state.inserted.push(order.clone());
+        let key = (order.tenant, order.id);
+        if state.orders.contains_key(&key) {
+            return Err(OrderError::DuplicateOrder(order.id));
+        }
+        state.orders.insert(key, order.clone());
+        Ok(())
+    }
+
+    fn update(&self, order: &Order) -> Result<(), OrderError> {
+        let mut state = self.state();
+        state.counters.updates += 1;
+        state.updated.push(order.clone());
+        if self.fail_updates {
+            return Err(OrderError::StorageFailed(None));
+        }
+        let key = (order.tenant, order.id);
+        if !state.orders.contains_key(&key) {
+            return Err(OrderError::OrderNotFound(order.id));
+        }
+        state.orders.insert(key, order.clone());
+        Ok(())
+    }
+
+    fn find(&self, tenant: TenantId, id: OrderId) -> Result<Option<Order>, OrderError> {
+        let mut state = self.state();
+        state.counters.finds += 1;
+        Ok(state.orders.get(&(tenant, id)).cloned())
+    }
+
+    fn find_all(&self, tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        let mut state = self.state();
+        state.counters.lists += 1;
+        Ok(state
+            .orders
+            .range((tenant, OrderId(0))..=(tenant, OrderId(u32::MAX)))
+            .map(|(_, order)| order.clone())
+            .collect())
+    }
+}
+
+/// Repository failing every call with [`OrderError::StorageFailed`].
+#[derive(Debug, Default)]
+pub struct FailingRepository {
+    counters: Mutex<RepositoryCounters>,
+}
+
+impl FailingRepository {
+    /// Creates a repository with no call yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many calls were made (and failed) so far.
+    #[must_use]
+    pub fn counters(&self) -> RepositoryCounters {
+        *self.count()
+    }
+
+    fn count(&self) -> MutexGuard<'_, RepositoryCounters> {
+        // A poisoned lock only means another thread panicked; the counters are still fine
+        self.counters.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl OrderRepository for FailingRepository {
+    fn insert(&self, _order: &Order) -> Result<(), OrderError> {
+        self.count().inserts += 1;
+        Err(OrderError::StorageFailed(None))
+    }
+
+    fn update(&self, _order: &Order) -> Result<(), OrderError> {
+        self.count().updates += 1;
+        Err(OrderError::StorageFailed(None))
+    }
+
+    fn find(&self, _tenant: TenantId, _id: OrderId) -> Result<Option<Order>, OrderError> {
+        self.count().finds += 1;
+        Err(OrderError::StorageFailed(None))
+    }
+
+    fn find_all(&self, _tenant: TenantId) -> Result<Vec<Order>, OrderError> {
+        self.count().lists += 1;
+        Err(OrderError::StorageFailed(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{LineItem, Money};
+    use std::ops::ControlFlow;
+
+    fn order(tenant: u32, id: u32) -> Order {
+        let mut order = Order::new(
+            OrderId(id),
+            vec![LineItem {
+                name: "Book".to_string(),
+                price: Money(4999),
+                weight_grams: None,
+            }],
+        )
+        .unwrap();
+        order.tenant = TenantId(tenant);
+        order
+    }
+
+    #[test]
+    fn every_call_is_counted_and_recorded() {
+        let repo = MockRepository::new();
+        let mut paid = order(0, 1);
+
+        repo.insert(&paid).unwrap();
+        let duplicate = repo.insert(&paid);
+        paid.version += 1;
+        repo.update(&paid).unwrap();
+        let unknown = repo.update(&order(0, 2));
+        repo.find(TenantId::default(), OrderId(1)).unwrap();
+        repo.find_all(TenantId::default()).unwrap();
+
+        assert!(matches!(duplicate, Err(OrderError::DuplicateOrder(_))));
+        assert!(matches!(unknown, Err(OrderError::OrderNotFound(_))));
+        assert_eq!(
+            repo.counters(),
+            RepositoryCounters {
+                inserts: 2,
+                updates: 2,
+                finds: 1,
+                lists: 1,
+            }
+        );
+        assert_eq!(repo.counters().total(), 6);
+        assert_eq!(repo.inserted(), [order(0, 1), order(0, 1)]);
+        assert_eq!(repo.updated(), [paid.clone(), order(0, 2)]);
+        assert_eq!(repo.order(OrderId(1)), Some(paid));
+    }
+
+    #[test]
+    fn each_tenant_sees_its_own_orders() {
+        let repo = MockRepository::new();
+
+        repo.insert(&order(1, 7)).unwrap();
+        repo.insert(&order(2, 7)).unwrap();
+        repo.insert(&order(2, 3)).unwrap();
+
+        let ids = |tenant| -> Vec<OrderId> {
+            repo.find_all(TenantId(tenant))
+                .unwrap()
+                .iter()
+                .map(|order| order.id)
+                .collect()
+        };
+        assert_eq!(ids(1), [OrderId(7)]);
+        assert_eq!(ids(2), [OrderId(3), OrderId(7)]);
+        assert!(ids(0).is_empty());
+        assert_eq!(repo.find(TenantId(3), OrderId(7)).unwrap(), None);
+        assert_eq!(repo.orders().len(), 3);
+    }
+
+    #[test]
+    fn the_default_find_many_finds_one_id_at_a_time() {
+        let repo = MockRepository::new();
+        repo.insert(&order(0, 1)).unwrap();
+        repo.insert(&order(0, 2)).unwrap();
+
+        let found = repo
+            .find_many(
+                TenantId::default(),
+                &[OrderId(2), OrderId(9), OrderId(1), OrderId(2)],
+            )
+            .unwrap();
+
+        assert_eq!(found, [order(0, 2), order(0, 1)]);
+        // One find per distinct ID: the repeated 2 was already found
+        assert_eq!(repo.counters().finds, 3);
+    }
+
+    #[test]
+    fn the_default_for_each_stops_at_break() {
+        let repo = MockRepository::new();
+        for id in 1..=3 {
+            repo.insert(&order(0, id)).unwrap();
+        }
+        let mut seen = Vec::new();
+
+        repo.for_each(TenantId::default(), &mut |order| {
+            seen.push(order.id);
+            ControlFlow::Break(())
+        })
+        .unwrap();
+
+        assert_eq!(seen, [OrderId(1)]);
+        assert_eq!(
+            repo.count_where(TenantId::default(), &|order| order.id != OrderId(2))
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn failing_updates_still_take_inserts() {
+        let repo = MockRepository::new().with_failing_updates();
+
+        repo.insert(&order(0, 1)).unwrap();
+        let updated = repo.update(&order(0, 1));
+
+        assert!(matches!(updated, Err(OrderError::StorageFailed(_))));
+        assert_eq!(repo.updated().len(), 1);
+        assert!(repo.order(OrderId(1)).is_some());
+    }
+
+    #[test]
+    fn the_failing_repository_counts_what_it_failed() {
+        let repo = FailingRepository::new();
+
+        let inserted = repo.insert(&order(0, 1));
+        let found = repo.find(TenantId::default(), OrderId(1));
+
+        assert!(matches!(inserted, Err(OrderError::StorageFailed(_))));
+        assert!(matches!(found, Err(OrderError::StorageFailed(_))));
+        assert_eq!(repo.counters().total(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+// =============================================================================
+// Sender Double - Notifications Kept, Never Sent
+// =============================================================================
+//
+// MockSender "delivers" every notification by keeping it, with its
+// recipient, so a test can check which notification went out and to whom:
+//
+//     assert!(matches!(sender.events()[..], [NotificationEvent::OrderConfirmed(_)]));
+//     assert_eq!(sender.recipients(), [ferris]);
+
+use domain::{NotificationEvent, OrderError, Recipient, Sender};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Sender keeping every notification instead of sending it.
+#[derive(Debug, Default)]
+pub struct MockSender {
+    sent: Mutex<Vec<(Recipient, NotificationEvent)>>,
+}
+
+impl MockSender {
+    /// Creates a sender with nothing sent yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every notification, with its recipient, oldest first.
+    #[must_use]
+    pub fn sent(&self) -> Vec<(Recipient, NotificationEvent)> {
+        self.guard().clone()
+    }
+
+    /// The event of every notification, oldest first.
+    #[must_use]
+    pub fn events(&self) -> Vec<NotificationEvent> {
+        self.guard()
+            .iter()
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
+
+    /// Who every notification went to, oldest first.
+    #[must_use]
+    pub fn recipients(&self) -> Vec<Recipient> {
+        self.guard()
+            .iter()
+            .map(|(recipient, _)| recipient.clone())
+            .collect()
+    }
+
+    /// How many notifications were sent.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.guard().len()
+    }
+
+    fn guard(&self) -> MutexGuard<'_, Vec<(Recipient, NotificationEvent)>> {
+        // A poisoned lock only means another thread panicked; the record is still fine
+        self.sent.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Sender for MockSender {
+    fn notify(&self, recipient: &Recipient, event: &NotificationEvent) -> Result<(), OrderError> {
+        self.guard().push((recipient.clone(), event.clone()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::{Contact, OrderId};
+
+    #[test]
+    fn every_notification_is_kept_with_its_recipient() {
+        let sender = MockSender::new();
+        let ann = Recipient::new("Ann", Contact::Email("ann@example.com".to_string())).unwrap();
+        let bob = Recipient::new("Bob", Contact::Email("bob@example.com".to_string())).unwrap();
+        let shipped = NotificationEvent::OrderShipped { id: OrderId(1) };
+        let cancelled = NotificationEvent::OrderCancelled { id: OrderId(2) };
+
+        sender.notify(&ann, &shipped).unwrap();
+        sender.notify(&bob, &cancelled).unwrap();
+
+        assert_eq!(sender.count(), 2);
+        assert_eq!(sender.events(), [shipped.clone(), cancelled]);
+        assert_eq!(sender.recipients(), [ann.clone(), bob]);
+        assert_eq!(sender.sent()[0], (ann, shipped));
+    }
+}
@@ -0,0 +1,93 @@
+// =============================================================================
+// Browser Adapters - The Console and the Clock of the Page
+// =============================================================================
+//
+// Compiled to wasm32-unknown-unknown, the standard library has no operating
+// system under it:
+// - `println!` compiles, and its lines go nowhere
+// - `SystemTime::now()` and `Instant::now()` compile, and panic when called
+//
+// Both are things the browser DOES have, through JavaScript: `console.log`
+// and `Date.now()`. So the demo plugs in two small adapters for ports the
+// domain already defines:
+// - JsConsole: a Logger (and an `io::Write`, for the ConsoleSender) writing
+//   to `console.log`
+// - BrowserClock: a Clock reading `Date.now()`
+//
+// Off wasm (the native unit tests), they use stdout and the system clock.
+
+use domain::{Clock, Logger, RequestContext};
+use std::io;
+use std::time::SystemTime;
+#[cfg(target_arch = "wasm32")]
+use std::time::{Duration, UNIX_EPOCH};
+
+#[cfg(target_arch = "wasm32")]
+mod js {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = console)]
+        pub fn log(line: &str);
+
+        #[wasm_bindgen(js_namespace = Date)]
+        pub fn now() -> f64;
+    }
+}
+
+/// Logger writing to the JavaScript console, one `console.log` per line.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsConsole;
+
+impl JsConsole {
+    fn write_line(line: &str) {
+        #[cfg(target_arch = "wasm32")]
+        js::log(line);
+        #[cfg(not(target_arch = "wasm32"))]
+        println!("{line}");
+    }
+}
+
+impl Logger for JsConsole {
+    fn log(&self, ctx: &RequestContext, message: &str) {
+        if ctx.correlation_id.is_empty() {
+            Self::write_line(message);
+        } else {
+            Self::write_line(&format!("[{}] {message}", ctx.correlation_id));
+        }
+    }
+}
+
+// For the ConsoleSender, which writes its notifications to an `io::Write`
+impl io::Write for JsConsole {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for line in String::from_utf8_lossy(buf).lines() {
+            if !line.trim().is_empty() {
+                Self::write_line(line.trim());
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Clock reading the page's time, `Date.now()`, to the millisecond.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BrowserClock;
+
+impl Clock for BrowserClock {
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> SystemTime {
+        // Milliseconds since the epoch: a whole number, positive for any date this demo sees
+        UNIX_EPOCH + Duration::from_millis(js::now() as u64)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
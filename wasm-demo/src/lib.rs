@@ -0,0 +1,150 @@
+// =============================================================================
+// WASM-DEMO CRATE - The Hexagon in a Browser
+// =============================================================================
+//
+// The same domain and application as everywhere else, with the in-memory
+// adapters, compiled to wasm32-unknown-unknown and driven from JavaScript:
+//
+//     import init, { place_order, get_order } from "./pkg/wasm_demo.js";
+//
+//     await init();
+//     const placed = JSON.parse(place_order('[{"name": "Book", "price": 4999}]'));
+//     const order = JSON.parse(get_order(placed.id));
+//
+// Build it with `wasm-pack build wasm-demo --target web`.
+//
+// WHAT IT TOOK:
+// -------------
+// Nothing in the domain or the application knows about the browser, and
+// nothing had to. What did have to go was every direct use of the operating
+// system on the way, since wasm32-unknown-unknown doesn't have one:
+// - the adapters' and the service's log lines go through the Logger port
+//   (here, JsConsole: `console.log`) instead of `println!`
+// - the time comes from the Clock port (here, BrowserClock: `Date.now()`),
+//   for the service and for the mock gateway's receipts
+//
+// That's the hexagon paying off: a new platform is new adapters, not a
+// rewrite. See browser.rs.
+//
+// A DRIVING ADAPTER OVER ANOTHER:
+// -------------------------------
+// JavaScript is one more driving side, like the CLI and the REST API. The
+// exports build HTTP requests and hand them to adapters-http's Router, so
+// the JSON is the REST API's, errors and their stable codes included:
+//
+//     place_order(items_json)  ->  POST /orders       (the items, in cents)
+//     get_order(id)            ->  GET  /orders/{id}
+//
+// The orders live as long as the page: one static service over one static
+// in-memory repository.
+
+mod browser;
+
+pub use browser::{BrowserClock, JsConsole};
+
+use adapters_http::{HttpRequest, Router};
+use adapters_notification::ConsoleSender;
+use adapters_payment::MockPaymentGateway;
+use adapters_repository::InMemoryOrderRepository;
+use application::{OrderQueries, OrderService};
+use serde_json::{Value, json};
+use std::sync::{Arc, LazyLock};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Who the demo's notifications go to: the page has no customer.
+pub const DEMO_RECIPIENT_NAME: &str = "Browser Demo";
+/// The email of [`DEMO_RECIPIENT_NAME`].
+pub const DEMO_RECIPIENT_EMAIL: &str = "demo@example.com";
+
+type DemoService =
+    OrderService<'static, InMemoryOrderRepository, MockPaymentGateway, ConsoleSender>;
+
+static REPOSITORY: LazyLock<InMemoryOrderRepository> =
+    LazyLock::new(|| InMemoryOrderRepository::new().with_logger(Arc::new(JsConsole)));
+
+static PAYMENT: LazyLock<MockPaymentGateway> = LazyLock::new(|| {
+    MockPaymentGateway::new()
+        .with_logger(Arc::new(JsConsole))
+        .with_clock(Arc::new(BrowserClock))
+});
+
+static SENDER: LazyLock<ConsoleSender> = LazyLock::new(|| ConsoleSender::with_writer(JsConsole));
+
+static SERVICE: LazyLock<DemoService> = LazyLock::new(|| {
+    OrderService::new(&*REPOSITORY, &*PAYMENT, &*SENDER)
+        .with_clock(&BrowserClock)
+        .with_logger(&JsConsole)
+});
+
+static QUERIES: LazyLock<OrderQueries<'static, InMemoryOrderRepository>> =
+    LazyLock::new(|| OrderQueries::new(&*REPOSITORY));
+
+static ROUTER: LazyLock<
+    Router<'static, InMemoryOrderRepository, MockPaymentGateway, ConsoleSender>,
+> = LazyLock::new(|| Router::new(&SERVICE, &QUERIES));
+
+/// Places an order for the demo recipient, and returns it as JSON.
+///
+/// `items_json` is the `items` of the REST API's `POST /orders`, e.g.
+/// `[{"name": "Book", "price": 4999, "quantity": 2}]`. On failure, the JSON
+/// is `{"error": {"code": ..., "message": ...}}`.
+#[wasm_bindgen]
+#[must_use]
+pub fn place_order(items_json: &str) -> String {
+    let items: Value = match serde_json::from_str(items_json) {
+        Ok(items) => items,
+        Err(e) => {
+            return json!({ "error": { "code": "invalid_body", "message": e.to_string() } })
+                .to_string();
+        }
+    };
+    let body = json!({
+        "recipient_name": DEMO_RECIPIENT_NAME,
+        "recipient_email": DEMO_RECIPIENT_EMAIL,
+        "items": items,
+    });
+    ROUTER
+        .handle(&HttpRequest::new("POST", "/orders", body.to_string()))
+        .body
+}
+
+/// The order `id` as JSON, or `{"error": {"code": "order_not_found", ...}}`.
+#[wasm_bindgen]
+#[must_use]
+pub fn get_order(id: u32) -> String {
+    ROUTER
+        .handle(&HttpRequest::new("GET", &format!("/orders/{id}"), ""))
+        .body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Value {
+        serde_json::from_str(json).unwrap_or_else(|e| panic!("{e}: {json}"))
+    }
+
+    #[test]
+    fn an_order_placed_from_json_can_be_read_back() {
+        let placed = parse(&place_order(
+            r#"[{"name": "Book", "price": 4999}, {"name": "Pen", "price": 199, "quantity": 2}]"#,
+        ));
+        let id = u32::try_from(placed["id"].as_u64().unwrap()).unwrap();
+
+        let order = parse(&get_order(id));
+
+        assert_eq!(order, placed);
+        assert_eq!(order["total"], "$53.97");
+        assert_eq!(order["status"], "Paid");
+    }
+
+    #[test]
+    fn failures_are_json_errors_with_their_code() {
+        let code = |json: String| parse(&json)["error"]["code"].clone();
+
+        assert_eq!(code(place_order("[]")), "validation_failed");
+        assert_eq!(code(place_order("not json")), "invalid_body");
+        assert_eq!(code(get_order(u32::MAX)), "order_not_found");
+    }
+}
@@ -0,0 +1,26 @@
+// The demo as JavaScript sees it: compiled to wasm32-unknown-unknown, run
+// headless in Node by wasm-bindgen-test-runner. Placing an order in there
+// goes through the real clock and console bridges, so an `Instant::now()` or
+// `SystemTime::now()` left on the way panics here, not in someone's browser.
+//
+//     cargo test -p wasm-demo --target wasm32-unknown-unknown
+
+#![cfg(target_arch = "wasm32")]
+
+use serde_json::Value;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn places_and_retrieves_an_order() {
+    let placed: Value = serde_json::from_str(&wasm_demo::place_order(
+        r#"[{"name": "Book", "price": 4999}]"#,
+    ))
+    .unwrap();
+    let id = u32::try_from(placed["id"].as_u64().unwrap()).unwrap();
+
+    let order: Value = serde_json::from_str(&wasm_demo::get_order(id)).unwrap();
+
+    assert_eq!(order["id"], placed["id"]);
+    assert_eq!(order["total"], "$49.99");
+    assert_eq!(order["status"], "Paid");
+}